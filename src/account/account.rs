@@ -3,9 +3,9 @@ use crate::{
     commit::Payload,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Account {
     height: u64,
     state: State,
@@ -26,25 +26,51 @@ impl Account {
         }
     }
 
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn balance(&self) -> Option<u64> {
+        self.state.balance()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.is_closed()
+    }
+
+    // Unlike `apply`, this does not advance `self.height`
+    pub fn collect_fee(&mut self, amount: u64) {
+        self.state.collect_fee(amount);
+    }
+
     pub fn applicable(&self, height: u64) -> bool {
-        height <= self.height + 1
+        // Saturating: every height is applicable to an `Account` at height
+        // `u64::MAX` (and is ignored by `apply`, as it does not exceed it)
+        height <= self.height.saturating_add(1)
     }
 
     pub fn apply(
         &mut self,
         payload: &Payload,
-        dependency: Option<&Operation>,
+        dependencies: &[Operation],
         settings: &AccountSettings,
     ) -> bool {
         if payload.height() <= self.height {
             return payload.height() < self.height || self.state.is_correct();
         }
 
+        // Operations on a closed account are rejected without affecting its state
+        // (in particular, a closed account is never corrupted)
+        if self.state.is_closed() {
+            return false;
+        }
+
         let result = match &mut self.state {
-            State::Correct(state) => state.apply(payload.operation(), dependency, settings),
+            State::Correct(state) => state.apply(payload.operation(), dependencies, settings),
             State::Corrupted(_) => unreachable!(),
         };
 
+        // This cannot overflow, as `payload.height() > self.height`
         self.height += 1;
 
         match result {
@@ -4,6 +4,11 @@ pub(crate) struct AccountSettings {
     // for the sake of testing / benchmarking: use at own risk!
     pub initial_balance: u64,
     pub supports_capacity: usize,
+    // Maximum number of `Withdraw`s a `CollectAll` can deposit
+    pub collect_capacity: usize,
+    // Minimum `Fee` each `Withdraw` must pay to its broker (if zero,
+    // `Withdraw`s can omit their `Fee`)
+    pub minimum_fee: u64,
 }
 
 impl Default for AccountSettings {
@@ -11,6 +16,8 @@ impl Default for AccountSettings {
         AccountSettings {
             initial_balance: 0,
             supports_capacity: 8,
+            collect_capacity: 64,
+            minimum_fee: 0,
         }
     }
 }
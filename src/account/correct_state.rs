@@ -1,14 +1,14 @@
 use crate::{
     account::{
-        operations::{Abandon, Deposit, Support, Withdraw},
-        AccountSettings, CorruptedState, Id, Operation, OperationError,
+        operations::{Abandon, CollectAll, Deposit, Fulfillment, Refund, Support, Withdraw},
+        AccountSettings, CorruptedState, Entry, Id, Operation, OperationError,
     },
     crypto::Identify,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use std::collections::BTreeSet;
 
@@ -16,15 +16,18 @@ use talk::crypto::primitives::hash::{self, Hash};
 
 use zebra::map::Set;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct CorrectState {
     id: Id,
     balance: u64,
     deposits: Deposits,
     motions: BTreeSet<Hash>,
+    // Heights of the escrowed `Withdraw`s of `self` that were refunded
+    refunds: BTreeSet<u64>,
+    closed: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Deposits {
     slot: u64,
     root: Option<Hash>,
@@ -40,29 +43,64 @@ impl CorrectState {
                 root: None,
             },
             motions: BTreeSet::new(),
+            refunds: BTreeSet::new(),
+            closed: false,
         }
     }
 
+    pub fn balance(&self) -> u64 {
+        self.balance
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
     pub fn apply(
         &mut self,
         operation: &Operation,
-        dependency: Option<&Operation>,
+        dependencies: &[Operation],
         settings: &AccountSettings,
     ) -> Result<(), Top<OperationError>> {
+        if self.closed {
+            return OperationError::AccountClosed.fail().spot(here!());
+        }
+
         match operation {
-            Operation::Withdraw(withdraw) => self.apply_withdraw(withdraw),
-            Operation::Deposit(deposit) => self.apply_deposit(deposit, dependency.unwrap()),
+            Operation::Withdraw(withdraw) => self.apply_withdraw(withdraw, settings),
+            Operation::Deposit(deposit) => self.apply_deposit(deposit, dependencies),
             Operation::Support(support) => self.apply_support(support, settings),
             Operation::Abandon(abandon) => self.apply_abandon(abandon),
+            Operation::Close(_) => self.apply_close(),
+            Operation::CollectAll(collect_all) => {
+                self.apply_collect_all(collect_all, dependencies, settings)
+            }
+            Operation::Refund(refund) => self.apply_refund(refund, dependencies),
         }
     }
 
-    fn apply_withdraw(&mut self, withdraw: &Withdraw) -> Result<(), Top<OperationError>> {
-        if self.balance < withdraw.amount() {
-            return OperationError::Overdraft.fail().spot(here!());
+    fn apply_withdraw(
+        &mut self,
+        withdraw: &Withdraw,
+        settings: &AccountSettings,
+    ) -> Result<(), Top<OperationError>> {
+        let fee = withdraw.fee().map(|fee| fee.amount()).unwrap_or(0);
+
+        if fee < settings.minimum_fee {
+            return OperationError::InsufficientFee.fail().spot(here!());
         }
 
-        self.balance -= withdraw.amount();
+        // The fee is debited along with `withdraw.amount()`: either both are, or neither is
+        let total = withdraw
+            .total()
+            .ok_or(OperationError::AmountOverflow.into_top())
+            .spot(here!())?;
+
+        self.balance = self
+            .balance
+            .checked_sub(total)
+            .ok_or(OperationError::Overdraft.into_top())
+            .spot(here!())?;
 
         Ok(())
     }
@@ -70,46 +108,46 @@ impl CorrectState {
     fn apply_deposit(
         &mut self,
         deposit: &Deposit,
-        dependency: &Operation,
+        dependencies: &[Operation],
     ) -> Result<(), Top<OperationError>> {
-        let withdraw = match dependency {
-            Operation::Withdraw(withdraw) => withdraw,
+        let dependency = match dependencies {
+            [dependency] => dependency,
             _ => {
                 return OperationError::UnexpectedDependency.fail().spot(here!());
             }
         };
 
-        if withdraw.beneficiary() != self.id || withdraw.slot() != self.deposits.slot {
-            return OperationError::IllegitimateDeposit.fail().spot(here!());
-        }
+        let credit = self.credit(
+            deposit.withdraw(),
+            dependency,
+            deposit.fulfillment(),
+            deposit.collect(),
+        )?;
+        let deposits = self.exclude(deposit.exclusion(), &[deposit.withdraw()])?;
 
-        let deposits = match (self.deposits.root, deposit.exclusion()) {
-            (Some(root), Some(exclusion)) => {
-                let mut deposits = Set::root_stub(root);
+        // Overflows are checked before `self` is modified: a failed `Deposit`
+        // must not partially apply
 
-                deposits
-                    .import(exclusion.clone())
-                    .pot(OperationError::ExclusionInvalid, here!())?;
-
-                if deposits
-                    .contains(&deposit.withdraw())
-                    .pot(OperationError::ExclusionInvalid, here!())?
-                {
-                    return OperationError::DoubleDeposit.fail().spot(here!());
-                }
+        let balance = self
+            .balance
+            .checked_add(credit)
+            .ok_or(OperationError::BalanceOverflow.into_top())
+            .spot(here!())?;
 
-                Some(deposits)
-            }
-            (None, None) => None,
-            _ => {
-                return OperationError::ExclusionInvalid.fail().spot(here!());
-            }
+        let slot = if deposit.collect() {
+            self.deposits
+                .slot
+                .checked_add(1)
+                .ok_or(OperationError::SlotOverflow.into_top())
+                .spot(here!())?
+        } else {
+            self.deposits.slot
         };
 
-        self.balance += withdraw.amount();
+        self.balance = balance;
 
         if deposit.collect() {
-            self.deposits.slot += 1;
+            self.deposits.slot = slot;
             self.deposits.root = None;
         } else {
             let mut deposits = deposits.unwrap_or(Set::new());
@@ -120,6 +158,198 @@ impl CorrectState {
         Ok(())
     }
 
+    fn apply_collect_all(
+        &mut self,
+        collect_all: &CollectAll,
+        dependencies: &[Operation],
+        settings: &AccountSettings,
+    ) -> Result<(), Top<OperationError>> {
+        let withdraws = collect_all.withdraws();
+
+        // `withdraws` must be strictly increasing (hence free of duplicates)
+        if withdraws.is_empty() || withdraws.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return OperationError::MalformedCollection.fail().spot(here!());
+        }
+
+        if withdraws.len() > settings.collect_capacity {
+            return OperationError::CollectionOverflow.fail().spot(here!());
+        }
+
+        if dependencies.len() != withdraws.len() {
+            return OperationError::UnexpectedDependency.fail().spot(here!());
+        }
+
+        let mut credit: u64 = 0;
+
+        // Escrowed `Withdraw`s cannot be collected (no `Fulfillment` is provided)
+        for (withdraw, dependency) in withdraws.iter().zip(dependencies) {
+            credit = credit
+                .checked_add(self.credit(*withdraw, dependency, None, true)?)
+                .ok_or(OperationError::BalanceOverflow.into_top())
+                .spot(here!())?;
+        }
+
+        self.exclude(collect_all.exclusion(), withdraws)?;
+
+        // As with `Deposit`, `self` is modified only once all checks pass
+
+        let balance = self
+            .balance
+            .checked_add(credit)
+            .ok_or(OperationError::BalanceOverflow.into_top())
+            .spot(here!())?;
+
+        let slot = self
+            .deposits
+            .slot
+            .checked_add(1)
+            .ok_or(OperationError::SlotOverflow.into_top())
+            .spot(here!())?;
+
+        self.balance = balance;
+        self.deposits.slot = slot;
+        self.deposits.root = None;
+
+        Ok(())
+    }
+
+    // Returns the amount `self` is credited by depositing `dependency` (the
+    // `Withdraw` at `withdraw`) in its current slot (`collect` is set if the
+    // deposit collects the slot)
+    fn credit(
+        &self,
+        withdraw: Entry,
+        dependency: &Operation,
+        fulfillment: Option<&Fulfillment>,
+        collect: bool,
+    ) -> Result<u64, Top<OperationError>> {
+        let dependency = match dependency {
+            Operation::Withdraw(dependency) => dependency,
+            _ => {
+                return OperationError::UnexpectedDependency.fail().spot(here!());
+            }
+        };
+
+        // Only `dependency`'s beneficiary can deposit it (its fee, if any,
+        // is credited to its broker as `dependency` is applied)
+        if dependency.beneficiary() != self.id || dependency.slot() != self.deposits.slot {
+            return OperationError::IllegitimateDeposit.fail().spot(here!());
+        }
+
+        // An escrowed `dependency` is credited only if `fulfillment` fulfills its condition,
+        // and only by collecting its slot: an escrow left undeposited by the collection
+        // of its slot is never deposited, and can be refunded (see `apply_refund`)
+        if let Some(condition) = dependency.condition() {
+            if !collect {
+                return OperationError::EscrowUncollected.fail().spot(here!());
+            }
+
+            let fulfilled = fulfillment.map_or(false, |fulfillment| {
+                condition.fulfilled(fulfillment, withdraw, self.id)
+            });
+
+            if !fulfilled {
+                return OperationError::ConditionUnfulfilled.fail().spot(here!());
+            }
+        }
+
+        Ok(dependency.amount())
+    }
+
+    fn apply_refund(
+        &mut self,
+        refund: &Refund,
+        dependencies: &[Operation],
+    ) -> Result<(), Top<OperationError>> {
+        let (escrow, closure, collected) = match dependencies {
+            [Operation::Withdraw(escrow), closure, Operation::Withdraw(collected)] => {
+                (escrow, closure, collected)
+            }
+            _ => {
+                return OperationError::UnexpectedDependency.fail().spot(here!());
+            }
+        };
+
+        // Only escrowed `Withdraw`s can be refunded, to their issuer
+        if refund.withdraw().id != self.id || escrow.condition().is_none() {
+            return OperationError::IllegitimateRefund.fail().spot(here!());
+        }
+
+        // `closure` (by `escrow`'s beneficiary) must collect `collected`, a `Withdraw`
+        // other than `escrow` in `escrow`'s slot: `escrow`'s slot was then collected
+        // without depositing `escrow`
+        let collects = match closure {
+            Operation::Deposit(deposit) => {
+                deposit.collect() && deposit.withdraw() == refund.collected()
+            }
+            Operation::CollectAll(collect_all) => {
+                collect_all.withdraws().contains(&refund.collected())
+            }
+            _ => false,
+        };
+
+        if !collects
+            || refund.closure().id != escrow.beneficiary()
+            || refund.collected() == refund.withdraw()
+            || collected.beneficiary() != escrow.beneficiary()
+            || collected.slot() != escrow.slot()
+        {
+            return OperationError::IllegitimateRefund.fail().spot(here!());
+        }
+
+        if self.refunds.contains(&refund.withdraw().height) {
+            return OperationError::DoubleRefund.fail().spot(here!());
+        }
+
+        self.balance = self
+            .balance
+            .checked_add(escrow.amount())
+            .ok_or(OperationError::BalanceOverflow.into_top())
+            .spot(here!())?;
+
+        self.refunds.insert(refund.withdraw().height);
+
+        Ok(())
+    }
+
+    // Credits `self` with a `Fee` paid by a `Withdraw` brokered by `self`. Saturating:
+    // outside of testing (see `AccountSettings::initial_balance`), the total supply
+    // cannot overflow
+    pub fn collect_fee(&mut self, amount: u64) {
+        self.balance = self.balance.saturating_add(amount);
+    }
+
+    // Checks that `exclusion` proves that none of `withdraws` was deposited in the
+    // current slot, returning the (stub of the) current slot's deposits, if any
+    fn exclude(
+        &self,
+        exclusion: &Option<Set<Entry>>,
+        withdraws: &[Entry],
+    ) -> Result<Option<Set<Entry>>, Top<OperationError>> {
+        match (self.deposits.root, exclusion) {
+            (Some(root), Some(exclusion)) => {
+                let mut deposits = Set::root_stub(root);
+
+                deposits
+                    .import(exclusion.clone())
+                    .pot(OperationError::ExclusionInvalid, here!())?;
+
+                for withdraw in withdraws {
+                    if deposits
+                        .contains(withdraw)
+                        .pot(OperationError::ExclusionInvalid, here!())?
+                    {
+                        return OperationError::DoubleDeposit.fail().spot(here!());
+                    }
+                }
+
+                Ok(Some(deposits))
+            }
+            (None, None) => Ok(None),
+            _ => OperationError::ExclusionInvalid.fail().spot(here!()),
+        }
+    }
+
     fn apply_support(
         &mut self,
         support: &Support,
@@ -144,6 +374,16 @@ impl CorrectState {
         Ok(())
     }
 
+    fn apply_close(&mut self) -> Result<(), Top<OperationError>> {
+        // Closing an account with a residual balance would burn that balance
+        if self.balance != 0 {
+            return OperationError::ResidualBalance.fail().spot(here!());
+        }
+
+        self.closed = true;
+        Ok(())
+    }
+
     pub fn corrupted(&self) -> CorruptedState {
         CorruptedState::new(self.id)
     }
@@ -151,7 +391,15 @@ impl CorrectState {
 
 impl Identify for CorrectState {
     fn identifier(&self) -> Hash {
-        (&self.balance, &self.deposits, &self.motions).identifier()
+        let closed = hash::hash(&self.closed).unwrap();
+
+        (
+            &self.balance,
+            &self.deposits,
+            (&self.motions, &self.refunds),
+            closed,
+        )
+            .identifier()
     }
 }
 
@@ -160,3 +408,426 @@ impl Identify for Deposits {
         hash::hash(&self).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::account::operations::{Condition, Fee};
+
+    use std::slice;
+
+    fn settings(initial_balance: u64) -> AccountSettings {
+        AccountSettings {
+            initial_balance,
+            ..Default::default()
+        }
+    }
+
+    fn withdraw(beneficiary: Id, amount: u64, fee: Option<Fee>) -> Operation {
+        Operation::Withdraw(Withdraw::new(beneficiary, 0, amount, fee, None))
+    }
+
+    fn deposit(collect: bool) -> Operation {
+        Operation::Deposit(Deposit::new(
+            Entry { id: 1, height: 1 },
+            None,
+            collect,
+            None,
+        ))
+    }
+
+    #[test]
+    fn withdraw_boundaries() {
+        for (balance, amount, fee, expected) in [
+            (u64::MAX, u64::MAX, 0, Some(0)),
+            (u64::MAX, u64::MAX - 1, 1, Some(0)),
+            (u64::MAX, u64::MAX, 1, None),
+            (u64::MAX, 1, u64::MAX, None),
+            (0, 0, 0, Some(0)),
+            (0, 1, 0, None),
+            (1, 0, 1, Some(0)),
+            (1, 1, 1, None),
+        ] {
+            let settings = settings(balance);
+            let mut state = CorrectState::new(0, &settings);
+
+            let fee = if fee > 0 {
+                Some(Fee::new(2, fee))
+            } else {
+                None
+            };
+
+            let result = state.apply(&withdraw(3, amount, fee), &[], &settings);
+
+            match expected {
+                Some(expected) => {
+                    assert!(result.is_ok());
+                    assert_eq!(state.balance, expected);
+                }
+                None => {
+                    assert!(result.is_err());
+                    assert_eq!(state.balance, balance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deposit_boundaries() {
+        for (balance, amount, expected) in [
+            (0, u64::MAX, Some(u64::MAX)),
+            (u64::MAX - 1, 1, Some(u64::MAX)),
+            (u64::MAX, 1, None),
+            (1, u64::MAX, None),
+        ] {
+            let settings = settings(balance);
+            let mut state = CorrectState::new(0, &settings);
+
+            let result = state.apply(&deposit(true), &[withdraw(0, amount, None)], &settings);
+
+            match expected {
+                Some(expected) => {
+                    assert!(result.is_ok());
+                    assert_eq!(state.balance, expected);
+                    assert_eq!(state.deposits.slot, 1);
+                }
+                None => {
+                    assert!(result.is_err());
+                    assert_eq!(state.balance, balance);
+                    assert_eq!(state.deposits.slot, 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slot_overflow() {
+        let settings = settings(0);
+
+        let mut state = CorrectState::new(0, &settings);
+        state.deposits.slot = u64::MAX;
+
+        let dependency = Operation::Withdraw(Withdraw::new(0, u64::MAX, 1, None, None));
+
+        assert!(state
+            .apply(&deposit(true), slice::from_ref(&dependency), &settings)
+            .is_err());
+
+        assert_eq!(state.balance, 0);
+        assert_eq!(state.deposits.slot, u64::MAX);
+
+        // Non-collecting deposits do not advance the slot
+        assert!(state
+            .apply(&deposit(false), slice::from_ref(&dependency), &settings)
+            .is_ok());
+
+        assert_eq!(state.balance, 1);
+    }
+
+    #[test]
+    fn collect_all() {
+        let settings = AccountSettings {
+            collect_capacity: 2,
+            ..Default::default()
+        };
+
+        let mut state = CorrectState::new(0, &settings);
+
+        let first = Entry { id: 1, height: 1 };
+        let second = Entry { id: 2, height: 5 };
+        let third = Entry { id: 3, height: 1 };
+
+        let dependencies = [
+            withdraw(0, 3, None),
+            withdraw(0, 4, Some(Fee::new(0, 1))),
+            withdraw(0, 5, None),
+        ];
+
+        // Collections are all-or-nothing: a single illegitimate `Withdraw` fails the whole
+        assert!(state
+            .apply(
+                &Operation::collect_all([first, second], None),
+                &[dependencies[0].clone(), withdraw(4, 4, None)],
+                &settings
+            )
+            .is_err());
+
+        assert!(state
+            .apply(
+                &Operation::collect_all([first, second, third], None),
+                &dependencies,
+                &settings
+            )
+            .is_err());
+
+        assert!(state
+            .apply(
+                &Operation::collect_all([first, second], None),
+                &dependencies[..1],
+                &settings
+            )
+            .is_err());
+
+        assert_eq!(state.balance, 0);
+
+        // `deposit` deposits `first`
+        assert!(state
+            .apply(&deposit(false), &dependencies[..1], &settings)
+            .is_ok());
+
+        let mut deposits = Set::new();
+        deposits.insert(first).unwrap();
+
+        assert!(state
+            .apply(
+                &Operation::collect_all([first, second], Some(&deposits)),
+                &dependencies[..2],
+                &settings
+            )
+            .is_err());
+
+        assert_eq!(state.balance, 3);
+
+        assert!(state
+            .apply(
+                &Operation::collect_all([second], Some(&deposits)),
+                &dependencies[1..2],
+                &settings
+            )
+            .is_ok());
+
+        // `second`'s fee is credited to its broker as `second` is applied, not upon deposit
+        assert_eq!(state.balance, 7);
+        assert_eq!(state.deposits.slot, 1);
+        assert_eq!(state.deposits.root, None);
+    }
+
+    #[test]
+    fn transfer() {
+        let settings = settings(10);
+
+        let mut payer = CorrectState::new(0, &settings);
+        let mut payee = CorrectState::new(1, &settings);
+
+        let withdraw = withdraw(1, 4, None);
+
+        assert!(payer.apply(&withdraw, &[], &settings).is_ok());
+        assert!(payee
+            .apply(&deposit(false), slice::from_ref(&withdraw), &settings)
+            .is_ok());
+
+        assert_eq!(payer.balance, 6);
+        assert_eq!(payee.balance, 14);
+
+        // Only the beneficiary can deposit
+        assert!(payer
+            .apply(&deposit(false), slice::from_ref(&withdraw), &settings)
+            .is_err());
+
+        assert_eq!(payer.balance, 6);
+    }
+
+    #[test]
+    fn motions() {
+        let settings = AccountSettings {
+            supports_capacity: 2,
+            ..Default::default()
+        };
+
+        let mut state = CorrectState::new(0, &settings);
+
+        let motions = (0u64..3)
+            .map(|motion| hash::hash(&motion).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(state
+            .apply(&Operation::support(motions[0]), &[], &settings)
+            .is_ok());
+
+        assert!(state
+            .apply(&Operation::support(motions[0]), &[], &settings)
+            .is_err());
+
+        assert!(state
+            .apply(&Operation::support(motions[1]), &[], &settings)
+            .is_ok());
+
+        // `settings.supports_capacity` motions are already supported
+        assert!(state
+            .apply(&Operation::support(motions[2]), &[], &settings)
+            .is_err());
+
+        assert!(state
+            .apply(&Operation::abandon(motions[2]), &[], &settings)
+            .is_err());
+
+        assert!(state
+            .apply(&Operation::abandon(motions[0]), &[], &settings)
+            .is_ok());
+
+        assert!(state
+            .apply(&Operation::support(motions[2]), &[], &settings)
+            .is_ok());
+
+        assert_eq!(state.motions.len(), 2);
+    }
+
+    #[test]
+    fn close() {
+        let settings = settings(5);
+        let mut state = CorrectState::new(0, &settings);
+
+        // A residual balance prevents closure
+        assert!(state.apply(&Operation::close(), &[], &settings).is_err());
+        assert!(!state.is_closed());
+
+        assert!(state.apply(&withdraw(1, 5, None), &[], &settings).is_ok());
+        assert!(state.apply(&Operation::close(), &[], &settings).is_ok());
+        assert!(state.is_closed());
+
+        // No operation is applicable to a closed account
+        assert!(state
+            .apply(&deposit(false), &[withdraw(0, 1, None)], &settings)
+            .is_err());
+
+        assert!(state.apply(&Operation::close(), &[], &settings).is_err());
+        assert_eq!(state.balance, 0);
+    }
+
+    #[test]
+    fn escrow_unfulfilled() {
+        let settings = settings(10);
+
+        let mut payee = CorrectState::new(1, &settings);
+
+        let withdraw = Entry { id: 0, height: 1 };
+        let escrow = Operation::escrow(1, 0, 4, Condition::preimage(b"secret"));
+
+        let deposit = |fulfillment: Option<Fulfillment>, collect| {
+            Operation::Deposit(Deposit::new(withdraw, None, collect, fulfillment))
+        };
+
+        let preimage = |preimage: &[u8]| Some(Fulfillment::preimage(preimage.to_vec()));
+
+        // An escrow is deposited only with a valid `Fulfillment`, by collecting its slot
+        for deposit in [
+            deposit(None, true),
+            deposit(preimage(b"guess"), true),
+            deposit(preimage(b"secret"), false),
+        ] {
+            assert!(payee
+                .apply(&deposit, slice::from_ref(&escrow), &settings)
+                .is_err());
+        }
+
+        assert_eq!(payee.balance, 10);
+        assert_eq!(payee.deposits.slot, 0);
+
+        let fulfilled =
+            Operation::deposit_escrow(withdraw, None, Fulfillment::preimage(b"secret".to_vec()));
+
+        assert!(payee
+            .apply(&fulfilled, slice::from_ref(&escrow), &settings)
+            .is_ok());
+
+        assert_eq!(payee.balance, 14);
+        assert_eq!(payee.deposits.slot, 1);
+    }
+
+    #[test]
+    fn escrow_refund() {
+        let settings = settings(10);
+
+        let mut payer = CorrectState::new(0, &settings);
+        let mut payee = CorrectState::new(1, &settings);
+
+        // `payer` escrows 4 to `payee` (in `payee`'s slot 0), which collects
+        // slot 0 by depositing 3 from account 2 instead
+        let escrow_entry = Entry { id: 0, height: 1 };
+        let escrow = Operation::escrow(1, 0, 4, Condition::preimage(b"secret"));
+
+        let collected_entry = Entry { id: 2, height: 1 };
+        let collected = withdraw(1, 3, None);
+
+        let closure_entry = Entry { id: 1, height: 1 };
+        let closure = Operation::deposit(collected_entry, None, true);
+
+        assert!(payer.apply(&escrow, &[], &settings).is_ok());
+        assert!(payee
+            .apply(&closure, slice::from_ref(&collected), &settings)
+            .is_ok());
+
+        assert_eq!(payer.balance, 6);
+
+        let refund = Operation::refund(escrow_entry, closure_entry, collected_entry);
+        let dependencies = [escrow.clone(), closure.clone(), collected.clone()];
+
+        // Only the issuer of the escrow can refund it
+        assert!(payee.apply(&refund, &dependencies, &settings).is_err());
+
+        assert!(payer.apply(&refund, &dependencies, &settings).is_ok());
+        assert_eq!(payer.balance, 10);
+
+        // An escrow is refunded at most once
+        assert!(payer.apply(&refund, &dependencies, &settings).is_err());
+        assert_eq!(payer.balance, 10);
+    }
+
+    #[test]
+    fn illegitimate_refund() {
+        let settings = settings(10);
+
+        let mut payer = CorrectState::new(0, &settings);
+
+        let escrow_entry = Entry { id: 0, height: 1 };
+        let escrow = Operation::escrow(1, 0, 4, Condition::preimage(b"secret"));
+
+        let collected_entry = Entry { id: 2, height: 1 };
+        let collected = withdraw(1, 3, None);
+
+        let closure_entry = Entry { id: 1, height: 1 };
+
+        assert!(payer.apply(&escrow, &[], &settings).is_ok());
+
+        let refund = |closure: &Operation, collected_entry, collected: &Operation| {
+            let refund = Operation::refund(escrow_entry, closure_entry, collected_entry);
+            let dependencies = [escrow.clone(), closure.clone(), collected.clone()];
+
+            payer.clone().apply(&refund, &dependencies, &settings)
+        };
+
+        // A non-collecting `Deposit` leaves the escrow's slot open
+        let open = Operation::deposit(collected_entry, None, false);
+        assert!(refund(&open, collected_entry, &collected).is_err());
+
+        // A slot collected by depositing the escrow itself cannot be refunded
+        let claim = Operation::deposit_escrow(
+            escrow_entry,
+            None,
+            Fulfillment::preimage(b"secret".to_vec()),
+        );
+
+        assert!(refund(&claim, escrow_entry, &escrow).is_err());
+
+        // The collected `Withdraw` must belong to the escrow's slot
+        let closure = Operation::deposit(collected_entry, None, true);
+        let later = Operation::Withdraw(Withdraw::new(1, 1, 3, None, None));
+
+        assert!(refund(&closure, collected_entry, &later).is_err());
+        assert!(refund(&closure, collected_entry, &collected).is_ok());
+
+        // Non-escrowed `Withdraw`s are not refunded
+        let mut state = CorrectState::new(0, &settings);
+        let plain = withdraw(1, 4, None);
+
+        assert!(state.apply(&plain, &[], &settings).is_ok());
+        assert!(state
+            .apply(
+                &Operation::refund(escrow_entry, closure_entry, collected_entry),
+                &[plain, closure, collected],
+                &settings
+            )
+            .is_err());
+    }
+}
@@ -1,8 +1,8 @@
 use crate::account::Id;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct CorruptedState {
     id: Id,
 }
@@ -4,18 +4,42 @@ use doomstack::Doom;
 pub(crate) enum OperationError {
     #[doom(description("Overdraft"))]
     Overdraft,
+    #[doom(description("Fee below minimum"))]
+    InsufficientFee,
     #[doom(description("Unexpected dependency"))]
     UnexpectedDependency,
     #[doom(description("Illegitimate deposit"))]
     IllegitimateDeposit,
+    #[doom(description("Escrow condition unfulfilled"))]
+    ConditionUnfulfilled,
+    #[doom(description("Escrow deposited without collecting"))]
+    EscrowUncollected,
+    #[doom(description("Illegitimate refund"))]
+    IllegitimateRefund,
+    #[doom(description("Double refund"))]
+    DoubleRefund,
     #[doom(description("Exclusion invalid"))]
     ExclusionInvalid,
     #[doom(description("Double deposit"))]
     DoubleDeposit,
+    #[doom(description("Malformed collection"))]
+    MalformedCollection,
+    #[doom(description("Collection overflow"))]
+    CollectionOverflow,
     #[doom(description("Motions overflow"))]
     MotionsOverflow,
     #[doom(description("Double support"))]
     DoubleSupport,
     #[doom(description("Unexpected abandon"))]
     UnexpectedAbandon,
+    #[doom(description("Account closed"))]
+    AccountClosed,
+    #[doom(description("Closing account has a residual balance"))]
+    ResidualBalance,
+    #[doom(description("Withdrawn amount overflows"))]
+    AmountOverflow,
+    #[doom(description("Balance overflows"))]
+    BalanceOverflow,
+    #[doom(description("Deposit slot overflows"))]
+    SlotOverflow,
 }
@@ -0,0 +1,165 @@
+use crate::{
+    account::{Entry, Id, Operation},
+    crypto::{Header, Identify, Rogue},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::{
+    primitives::{
+        hash::{self, Hash},
+        sign::Signature,
+    },
+    KeyCard, KeyChain, Statement,
+};
+
+// A `KeyDelegation` is a statement by which the master key of account `id`
+// authorizes a secondary (hot) key to sign low-value operations on its behalf:
+// withdrawals of at most `limit` (fee included) and deposits, up to height `expiry`.
+// As delegates contribute to reduction signatures, the delegate's `Rogue` proof
+// is included. The master `IdAssignment` is unaffected by delegation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyDelegation {
+    id: Id,
+    delegate: KeyCard,
+    limit: u64,
+    expiry: u64,
+    rogue: Rogue,
+    signature: Signature,
+}
+
+#[derive(Serialize)]
+struct KeyDelegationStatement {
+    id: Id,
+    delegate: KeyCard,
+    limit: u64,
+    expiry: u64,
+}
+
+#[derive(Doom)]
+pub enum KeyDelegationError {
+    #[doom(description("Signature invalid"))]
+    SignatureInvalid,
+    #[doom(description("Rogue-safety proof invalid"))]
+    RogueInvalid,
+}
+
+impl KeyDelegation {
+    pub fn new(master: &KeyChain, id: Id, delegate: &KeyChain, limit: u64, expiry: u64) -> Self {
+        let statement = KeyDelegationStatement {
+            id,
+            delegate: delegate.keycard(),
+            limit,
+            expiry,
+        };
+
+        let signature = master.sign(&statement).unwrap();
+
+        KeyDelegation {
+            id,
+            delegate: statement.delegate,
+            limit,
+            expiry,
+            rogue: Rogue::new(delegate),
+            signature,
+        }
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    pub fn delegate(&self) -> &KeyCard {
+        &self.delegate
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    // The commitment of a `Prepare` signed by the delegate binds `self`: a `Payload`
+    // revealing `operation` must carry `self`, and abide by its limits, to be committed
+    pub fn commitment(&self, operation: Hash) -> Hash {
+        hash::hash(&(operation, self.identifier())).unwrap()
+    }
+
+    // Determines whether or not `self` authorizes its delegate to sign for `entry`
+    pub fn covers(&self, entry: Entry) -> bool {
+        entry.id == self.id && entry.height <= self.expiry
+    }
+
+    // Determines whether or not `operation` is within the limits of `self`
+    pub fn permits(&self, operation: &Operation) -> bool {
+        match operation {
+            Operation::Withdraw(withdraw) => {
+                let fee = withdraw.fee().map(|fee| fee.amount()).unwrap_or(0);
+
+                withdraw
+                    .amount()
+                    .checked_add(fee)
+                    .map_or(false, |total| total <= self.limit)
+            }
+            Operation::Deposit(_) | Operation::CollectAll(_) | Operation::Refund(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn validate(&self, master: &KeyCard) -> Result<(), Top<KeyDelegationError>> {
+        let statement = KeyDelegationStatement {
+            id: self.id,
+            delegate: self.delegate.clone(),
+            limit: self.limit,
+            expiry: self.expiry,
+        };
+
+        self.signature
+            .verify(master, &statement)
+            .pot(KeyDelegationError::SignatureInvalid, here!())?;
+
+        self.rogue
+            .validate(&self.delegate)
+            .pot(KeyDelegationError::RogueInvalid, here!())
+    }
+}
+
+impl Identify for KeyDelegation {
+    fn identifier(&self) -> Hash {
+        hash::hash(self).unwrap()
+    }
+}
+
+impl Statement for KeyDelegationStatement {
+    type Header = Header;
+    const HEADER: Header = Header::KeyDelegation;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let master = KeyChain::random();
+        let delegate = KeyChain::random();
+
+        let delegation = KeyDelegation::new(&master, 0, &delegate, 100, 10);
+
+        delegation.validate(&master.keycard()).unwrap();
+        assert!(delegation.validate(&delegate.keycard()).is_err());
+
+        assert!(delegation.covers(Entry { id: 0, height: 10 }));
+        assert!(!delegation.covers(Entry { id: 0, height: 11 }));
+        assert!(!delegation.covers(Entry { id: 1, height: 5 }));
+
+        assert!(delegation.permits(&Operation::withdraw(1, 0, 100)));
+        assert!(!delegation.permits(&Operation::withdraw(1, 0, 101)));
+        assert!(!delegation.permits(&Operation::withdraw_with_fee(1, 0, 90, 2, 20)));
+        assert!(!delegation.permits(&Operation::close()));
+    }
+}
@@ -6,6 +6,7 @@ mod corrupted_state;
 mod entry;
 mod errors;
 mod id;
+mod key_delegation;
 mod operation;
 mod state;
 mod state_summary;
@@ -19,9 +20,10 @@ pub(crate) use account_settings::AccountSettings;
 pub(crate) use account_summary::AccountSummary;
 pub(crate) use correct_state::CorrectState;
 pub(crate) use corrupted_state::CorruptedState;
-pub(crate) use entry::Entry;
+pub use entry::Entry;
 pub(crate) use errors::OperationError;
-pub(crate) use id::Id;
-pub(crate) use operation::Operation;
+pub use id::Id;
+pub use key_delegation::{KeyDelegation, KeyDelegationError};
+pub use operation::Operation;
 pub(crate) use state::State;
 pub(crate) use state_summary::StateSummary;
@@ -1,6 +1,9 @@
 use crate::{
     account::{
-        operations::{Abandon, Deposit, Support, Withdraw},
+        operations::{
+            Abandon, Close, CollectAll, Condition, Deposit, Fee, Fulfillment, Refund, Support,
+            Withdraw,
+        },
         Entry, Id,
     },
     crypto::Identify,
@@ -13,20 +16,78 @@ use talk::crypto::primitives::hash::{self, Hash};
 use zebra::map::Set;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) enum Operation {
+#[non_exhaustive]
+pub enum Operation {
     Withdraw(Withdraw),
     Deposit(Deposit),
     Support(Support),
     Abandon(Abandon),
+    Close(Close),
+    CollectAll(CollectAll),
+    Refund(Refund),
 }
 
 impl Operation {
     pub fn withdraw(beneficiary: Id, slot: u64, amount: u64) -> Self {
-        Operation::Withdraw(Withdraw::new(beneficiary, slot, amount))
+        Operation::Withdraw(Withdraw::new(beneficiary, slot, amount, None, None))
+    }
+
+    // Like `withdraw`, additionally paying `fee` to the account of `broker`
+    // (credited as soon as the resulting `Withdraw` is applied)
+    pub fn withdraw_with_fee(
+        beneficiary: Id,
+        slot: u64,
+        amount: u64,
+        broker: Id,
+        fee: u64,
+    ) -> Self {
+        Operation::Withdraw(Withdraw::new(
+            beneficiary,
+            slot,
+            amount,
+            Some(Fee::new(broker, fee)),
+            None,
+        ))
+    }
+
+    // Like `withdraw`, but `beneficiary` can deposit the resulting `Withdraw`
+    // only by fulfilling `condition` (see `deposit_escrow`). If `beneficiary`
+    // collects `slot` otherwise, the `Withdraw` can be refunded (see `refund`)
+    pub fn escrow(beneficiary: Id, slot: u64, amount: u64, condition: Condition) -> Self {
+        Operation::Withdraw(Withdraw::new(
+            beneficiary,
+            slot,
+            amount,
+            None,
+            Some(condition),
+        ))
     }
 
     pub fn deposit(withdraw: Entry, deposits: Option<&Set<Entry>>, collect: bool) -> Self {
-        Operation::Deposit(Deposit::new(withdraw, deposits, collect))
+        Operation::Deposit(Deposit::new(withdraw, deposits, collect, None))
+    }
+
+    // Escrowed `Withdraw`s are deposited by collecting their slot
+    pub fn deposit_escrow(
+        withdraw: Entry,
+        deposits: Option<&Set<Entry>>,
+        fulfillment: Fulfillment,
+    ) -> Self {
+        Operation::Deposit(Deposit::new(withdraw, deposits, true, Some(fulfillment)))
+    }
+
+    // Returns the escrowed `Withdraw` at `withdraw` to its issuer: `closure` is the
+    // operation with which its beneficiary collected its slot, depositing `collected`
+    pub fn refund(withdraw: Entry, closure: Entry, collected: Entry) -> Self {
+        Operation::Refund(Refund::new(withdraw, closure, collected))
+    }
+
+    // Deposits all of `withdraws` in one operation, then collects
+    pub fn collect_all<W>(withdraws: W, deposits: Option<&Set<Entry>>) -> Self
+    where
+        W: IntoIterator<Item = Entry>,
+    {
+        Operation::CollectAll(CollectAll::new(withdraws, deposits))
     }
 
     pub fn support(motion: Hash) -> Self {
@@ -37,12 +98,19 @@ impl Operation {
         Operation::Abandon(Abandon::new(motion))
     }
 
-    pub fn dependency(&self) -> Option<Entry> {
+    pub fn close() -> Self {
+        Operation::Close(Close::new())
+    }
+
+    pub fn dependencies(&self) -> Vec<Entry> {
         match self {
-            Operation::Withdraw(withdraw) => withdraw.dependency(),
-            Operation::Deposit(deposit) => deposit.dependency(),
-            Operation::Support(support) => support.dependency(),
-            Operation::Abandon(abandon) => abandon.dependency(),
+            Operation::Withdraw(withdraw) => withdraw.dependencies(),
+            Operation::Deposit(deposit) => deposit.dependencies(),
+            Operation::Support(support) => support.dependencies(),
+            Operation::Abandon(abandon) => abandon.dependencies(),
+            Operation::Close(close) => close.dependencies(),
+            Operation::CollectAll(collect_all) => collect_all.dependencies(),
+            Operation::Refund(refund) => refund.dependencies(),
         }
     }
 }
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use talk::crypto::primitives::hash::Hash;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Abandon {
+pub struct Abandon {
     motion: Hash,
 }
 
@@ -18,7 +18,7 @@ impl Abandon {
         self.motion
     }
 
-    pub fn dependency(&self) -> Option<Entry> {
-        None
+    pub fn dependencies(&self) -> Vec<Entry> {
+        Vec::new()
     }
 }
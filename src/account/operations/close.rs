@@ -0,0 +1,18 @@
+use crate::account::Entry;
+
+use serde::{Deserialize, Serialize};
+
+// Closing an account finalizes it at the height of the closing `Payload`:
+// no further operation is applied to the account afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Close;
+
+impl Close {
+    pub fn new() -> Self {
+        Close
+    }
+
+    pub fn dependencies(&self) -> Vec<Entry> {
+        Vec::new()
+    }
+}
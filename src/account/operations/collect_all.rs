@@ -0,0 +1,55 @@
+use crate::account::{Entry, Id};
+
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeSet, ops::Range};
+
+use zebra::map::Set;
+
+// A `CollectAll` deposits several `Withdraw`s at once, then collects (i.e.,
+// advances the deposit slot, as a collecting `Deposit` does). A single
+// exclusion proves that none of `withdraws` was previously deposited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectAll {
+    // Sorted and deduplicated (see `CorrectState::apply`)
+    withdraws: Vec<Entry>,
+    exclusion: Option<Set<Entry>>,
+}
+
+impl CollectAll {
+    pub fn new<W>(withdraws: W, deposits: Option<&Set<Entry>>) -> Self
+    where
+        W: IntoIterator<Item = Entry>,
+    {
+        let withdraws = withdraws
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let exclusion = deposits.map(|deposits| deposits.export(withdraws.iter()).unwrap());
+
+        CollectAll {
+            withdraws,
+            exclusion,
+        }
+    }
+
+    // Sweeps the `Withdraw`s issued by `id` at each of `heights` (e.g., to
+    // collect a stream of payments from the same account up to a given height)
+    pub fn range(id: Id, heights: Range<u64>, deposits: Option<&Set<Entry>>) -> Self {
+        CollectAll::new(heights.map(|height| Entry { id, height }), deposits)
+    }
+
+    pub fn withdraws(&self) -> &[Entry] {
+        self.withdraws.as_slice()
+    }
+
+    pub fn exclusion(&self) -> &Option<Set<Entry>> {
+        &self.exclusion
+    }
+
+    pub fn dependencies(&self) -> Vec<Entry> {
+        self.withdraws.clone()
+    }
+}
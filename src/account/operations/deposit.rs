@@ -1,24 +1,31 @@
-use crate::account::Entry;
+use crate::account::{operations::Fulfillment, Entry};
 
 use serde::{Deserialize, Serialize};
 
 use zebra::map::Set;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Deposit {
+pub struct Deposit {
     withdraw: Entry,
     exclusion: Option<Set<Entry>>,
     collect: bool,
+    fulfillment: Option<Fulfillment>,
 }
 
 impl Deposit {
-    pub fn new(withdraw: Entry, deposits: Option<&Set<Entry>>, collect: bool) -> Self {
+    pub fn new(
+        withdraw: Entry,
+        deposits: Option<&Set<Entry>>,
+        collect: bool,
+        fulfillment: Option<Fulfillment>,
+    ) -> Self {
         let exclusion = deposits.map(|deposits| deposits.export([&withdraw]).unwrap());
 
         Deposit {
             withdraw,
             exclusion,
             collect,
+            fulfillment,
         }
     }
 
@@ -34,7 +41,11 @@ impl Deposit {
         self.collect
     }
 
-    pub fn dependency(&self) -> Option<Entry> {
-        Some(self.withdraw)
+    pub fn fulfillment(&self) -> Option<&Fulfillment> {
+        self.fulfillment.as_ref()
+    }
+
+    pub fn dependencies(&self) -> Vec<Entry> {
+        vec![self.withdraw]
     }
 }
@@ -0,0 +1,130 @@
+use crate::{
+    account::{Entry, Id},
+    crypto::Header,
+};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::{
+    primitives::{
+        hash::{self, Hash},
+        sign::Signature,
+    },
+    KeyCard, KeyChain, Statement,
+};
+
+// A `Condition` locks a `Withdraw` in escrow: its beneficiary can deposit
+// it only by providing a `Fulfillment` of the `Condition` (should the
+// beneficiary collect the escrow's slot otherwise, see `Refund`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Condition {
+    // Fulfilled by any preimage of the hash (e.g., for hash-locked swaps)
+    Preimage(Hash),
+    // Fulfilled by the arbiter's signature on the corresponding `EscrowRelease`
+    Arbiter(KeyCard),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum Fulfillment {
+    Preimage(Vec<u8>),
+    Arbiter(Signature),
+}
+
+// Released by an arbiter to allow `beneficiary` to deposit `withdraw`
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct EscrowRelease {
+    pub withdraw: Entry,
+    pub beneficiary: Id,
+}
+
+impl Condition {
+    pub fn preimage(preimage: &[u8]) -> Self {
+        Condition::Preimage(hash::hash(&preimage).unwrap())
+    }
+
+    pub fn arbiter(arbiter: KeyCard) -> Self {
+        Condition::Arbiter(arbiter)
+    }
+
+    // `withdraw` is the `Entry` of the `Withdraw` locked by `self`
+    pub fn fulfilled(&self, fulfillment: &Fulfillment, withdraw: Entry, beneficiary: Id) -> bool {
+        match (self, fulfillment) {
+            (Condition::Preimage(image), Fulfillment::Preimage(preimage)) => {
+                hash::hash(&preimage.as_slice()).unwrap() == *image
+            }
+            (Condition::Arbiter(arbiter), Fulfillment::Arbiter(signature)) => {
+                let release = EscrowRelease {
+                    withdraw,
+                    beneficiary,
+                };
+
+                signature.verify(arbiter, &release).is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Fulfillment {
+    pub fn preimage(preimage: Vec<u8>) -> Self {
+        Fulfillment::Preimage(preimage)
+    }
+
+    // Releases `withdraw` (locked by `Condition::arbiter(arbiter.keycard())`)
+    // to `beneficiary`
+    pub fn arbiter(arbiter: &KeyChain, withdraw: Entry, beneficiary: Id) -> Self {
+        let release = EscrowRelease {
+            withdraw,
+            beneficiary,
+        };
+
+        Fulfillment::Arbiter(arbiter.sign(&release).unwrap())
+    }
+}
+
+impl Statement for EscrowRelease {
+    type Header = Header;
+    const HEADER: Header = Header::EscrowRelease;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preimage() {
+        let withdraw = Entry { id: 0, height: 1 };
+        let condition = Condition::preimage(b"secret");
+
+        assert!(condition.fulfilled(&Fulfillment::Preimage(b"secret".to_vec()), withdraw, 1));
+        assert!(!condition.fulfilled(&Fulfillment::Preimage(b"guess".to_vec()), withdraw, 1));
+    }
+
+    #[test]
+    fn arbiter() {
+        let arbiter = KeyChain::random();
+
+        let withdraw = Entry { id: 0, height: 1 };
+        let condition = Condition::arbiter(arbiter.keycard());
+
+        let release = |beneficiary| Fulfillment::arbiter(&arbiter, withdraw, beneficiary);
+
+        assert!(condition.fulfilled(&release(1), withdraw, 1));
+
+        // A release is bound to its beneficiary
+        assert!(!condition.fulfilled(&release(2), withdraw, 1));
+
+        // An impostor cannot release the escrow
+        let impostor = KeyChain::random();
+        let forged = impostor
+            .sign(&EscrowRelease {
+                withdraw,
+                beneficiary: 1,
+            })
+            .unwrap();
+
+        assert!(!condition.fulfilled(&Fulfillment::Arbiter(forged), withdraw, 1));
+    }
+}
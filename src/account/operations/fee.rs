@@ -0,0 +1,25 @@
+use crate::account::Id;
+
+use serde::{Deserialize, Serialize};
+
+// A `Fee` is withdrawn along with the `Withdraw` carrying it, and credited
+// to `broker`'s account as part of the same batch (see `Database::commit_batch_unlogged`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Fee {
+    broker: Id,
+    amount: u64,
+}
+
+impl Fee {
+    pub fn new(broker: Id, amount: u64) -> Self {
+        Fee { broker, amount }
+    }
+
+    pub fn broker(&self) -> Id {
+        self.broker
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+}
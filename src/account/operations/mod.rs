@@ -1,9 +1,23 @@
 mod abandon;
+mod close;
+mod collect_all;
 mod deposit;
+mod escrow;
+mod fee;
+mod refund;
 mod support;
 mod withdraw;
 
-pub(crate) use abandon::Abandon;
-pub(crate) use deposit::Deposit;
-pub(crate) use support::Support;
-pub(crate) use withdraw::Withdraw;
+pub use abandon::Abandon;
+pub use close::Close;
+pub use collect_all::CollectAll;
+pub use deposit::Deposit;
+pub use escrow::{Condition, Fulfillment};
+
+pub use fee::Fee;
+pub use refund::Refund;
+pub use support::Support;
+pub use withdraw::Withdraw;
+
+#[allow(unused_imports)]
+pub(crate) use escrow::EscrowRelease;
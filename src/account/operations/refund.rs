@@ -0,0 +1,41 @@
+use crate::account::Entry;
+
+use serde::{Deserialize, Serialize};
+
+// A `Refund` returns an escrowed `Withdraw` to its issuer once its beneficiary can no
+// longer deposit it. An escrowed `Withdraw` can only be deposited by a `Deposit` that
+// collects its slot: if the beneficiary collects the slot with `closure` (depositing
+// `collected`, another `Withdraw` of the same slot), the escrow was never deposited,
+// and never will be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refund {
+    withdraw: Entry,
+    closure: Entry,
+    collected: Entry,
+}
+
+impl Refund {
+    pub fn new(withdraw: Entry, closure: Entry, collected: Entry) -> Self {
+        Refund {
+            withdraw,
+            closure,
+            collected,
+        }
+    }
+
+    pub fn withdraw(&self) -> Entry {
+        self.withdraw
+    }
+
+    pub fn closure(&self) -> Entry {
+        self.closure
+    }
+
+    pub fn collected(&self) -> Entry {
+        self.collected
+    }
+
+    pub fn dependencies(&self) -> Vec<Entry> {
+        vec![self.withdraw, self.closure, self.collected]
+    }
+}
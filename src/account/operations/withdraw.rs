@@ -1,20 +1,33 @@
-use crate::account::{Entry, Id};
+use crate::account::{
+    operations::{Condition, Fee},
+    Entry, Id,
+};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Withdraw {
+pub struct Withdraw {
     beneficiary: Id,
     slot: u64,
     amount: u64,
+    fee: Option<Fee>,
+    condition: Option<Condition>,
 }
 
 impl Withdraw {
-    pub fn new(beneficiary: Id, slot: u64, amount: u64) -> Self {
+    pub fn new(
+        beneficiary: Id,
+        slot: u64,
+        amount: u64,
+        fee: Option<Fee>,
+        condition: Option<Condition>,
+    ) -> Self {
         Withdraw {
             beneficiary,
             slot,
             amount,
+            fee,
+            condition,
         }
     }
 
@@ -30,7 +43,22 @@ impl Withdraw {
         self.amount
     }
 
-    pub fn dependency(&self) -> Option<Entry> {
-        None
+    pub fn fee(&self) -> Option<Fee> {
+        self.fee
+    }
+
+    // The total debited from the withdrawing account (`None` if `self.amount`
+    // and `self.fee`'s amount overflow when summed)
+    pub fn total(&self) -> Option<u64> {
+        self.amount
+            .checked_add(self.fee.map(|fee| fee.amount()).unwrap_or(0))
+    }
+
+    pub fn condition(&self) -> Option<&Condition> {
+        self.condition.as_ref()
+    }
+
+    pub fn dependencies(&self) -> Vec<Entry> {
+        Vec::new()
     }
 }
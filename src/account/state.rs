@@ -3,9 +3,9 @@ use crate::{
     crypto::Identify,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum State {
     Correct(CorrectState),
     Corrupted(CorruptedState),
@@ -26,6 +26,21 @@ impl State {
         }
     }
 
+    pub fn is_closed(&self) -> bool {
+        match self {
+            State::Correct(state) => state.is_closed(),
+            _ => false,
+        }
+    }
+
+    // A `Corrupted` state has no meaningful balance
+    pub fn balance(&self) -> Option<u64> {
+        match self {
+            State::Correct(state) => Some(state.balance()),
+            State::Corrupted(_) => None,
+        }
+    }
+
     pub fn summarize(&self) -> StateSummary {
         match self {
             State::Correct(state) => StateSummary::Correct(state.identifier()),
@@ -33,6 +48,15 @@ impl State {
         }
     }
 
+    // A `Fee` credited to a `Corrupted` or closed state is lost
+    pub fn collect_fee(&mut self, amount: u64) {
+        if let State::Correct(state) = self {
+            if !state.is_closed() {
+                state.collect_fee(amount);
+            }
+        }
+    }
+
     pub fn corrupt(&mut self) {
         if let State::Correct(state) = self {
             *self = State::Corrupted(state.corrupted());
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Stage {
+    // From the `Broker` receiving a request, to its batch being flushed
+    SpongeWait,
+    // From the batch being flushed, to enough reduction shards being collected
+    Reduction,
+    // From the batch being submitted, to a witness being assembled
+    WitnessCollection,
+    // From the witness being submitted, to a `BatchCommit` being assembled
+    CommitAggregation,
+}
+
+// A `LatencyBudget` attributes the latency of a brokerage to its stages, in the
+// order they were recorded. In `benchmark` builds, brokers return each client its
+// `LatencyBudget` along with its commit, so that latency regressions can be
+// attributed to a stage without distributed tracing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct LatencyBudget {
+    stages: Vec<(Stage, Duration)>,
+}
+
+impl LatencyBudget {
+    pub fn new() -> Self {
+        LatencyBudget { stages: Vec::new() }
+    }
+
+    pub fn record(&mut self, stage: Stage, elapsed: Duration) {
+        self.stages.push((stage, elapsed));
+    }
+
+    pub fn stages(&self) -> &[(Stage, Duration)] {
+        self.stages.as_slice()
+    }
+
+    pub fn elapsed(&self, stage: Stage) -> Duration {
+        self.stages
+            .iter()
+            .filter(|(recorded, _)| *recorded == stage)
+            .map(|(_, elapsed)| *elapsed)
+            .sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+
+    // Fraction of `self.total()` spent in `stage`
+    pub fn share(&self, stage: Stage) -> f64 {
+        let total = self.total();
+
+        if total.is_zero() {
+            0.
+        } else {
+            self.elapsed(stage).as_secs_f64() / total.as_secs_f64()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares() {
+        let mut budget = LatencyBudget::new();
+
+        assert_eq!(budget.share(Stage::SpongeWait), 0.);
+
+        budget.record(Stage::SpongeWait, Duration::from_millis(100));
+        budget.record(Stage::WitnessCollection, Duration::from_millis(200));
+        budget.record(Stage::CommitAggregation, Duration::from_millis(100));
+
+        assert_eq!(budget.total(), Duration::from_millis(400));
+        assert_eq!(budget.share(Stage::WitnessCollection), 0.5);
+        assert_eq!(budget.share(Stage::Reduction), 0.);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::benchmark::MetricsSettings;
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use tokio::time;
+
+// `Metrics` account for the operations served by a component (a replica, a broker
+// or a benchmark client), by path (e.g., "prepare" or "commit"): how many succeeded,
+// how long they took, and how many failed, by kind of failure. `MetricsSnapshot`s
+// are written to disk (see `MetricsSettings`) as JSON, so that runs can be compared
+// without scraping logs. Latencies are kept in memory (4 bytes per operation)
+// for percentiles to be exact.
+// All clones of a `Metrics` share the same state.
+#[derive(Debug, Clone)]
+pub(crate) struct Metrics {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    settings: MetricsSettings,
+    start: Instant,
+    paths: Mutex<BTreeMap<&'static str, PathMetrics>>,
+}
+
+#[derive(Debug, Default)]
+struct PathMetrics {
+    // In microseconds
+    latencies: Vec<u32>,
+    failures: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct MetricsSnapshot {
+    // Seconds since the `Metrics` were created
+    pub uptime: f64,
+    pub paths: BTreeMap<String, PathSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PathSnapshot {
+    // Successful operations, and their rate (per second of uptime)
+    pub operations: u64,
+    pub throughput: f64,
+    // `None` until an operation succeeds
+    pub latency: Option<Percentiles>,
+    pub failures: BTreeMap<String, u64>,
+}
+
+// In milliseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Doom)]
+pub(crate) enum MetricsError {
+    #[doom(description("Failed to write metrics"))]
+    WriteFailed,
+}
+
+impl Metrics {
+    pub fn new(settings: MetricsSettings) -> Self {
+        Metrics {
+            inner: Arc::new(Inner {
+                settings,
+                start: Instant::now(),
+                paths: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    pub fn success(&self, path: &'static str, latency: Duration) {
+        let latency = latency.as_micros().min(u32::MAX as u128) as u32;
+
+        let mut paths = self.inner.paths.lock().unwrap();
+        paths.entry(path).or_default().latencies.push(latency);
+    }
+
+    pub fn failure(&self, path: &'static str, kind: &'static str) {
+        self.failures(path, kind, 1);
+    }
+
+    pub fn failures(&self, path: &'static str, kind: &'static str, count: u64) {
+        let mut paths = self.inner.paths.lock().unwrap();
+
+        let failures = &mut paths.entry(path).or_default().failures;
+        *failures.entry(kind).or_insert(0) += count;
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let uptime = self.inner.start.elapsed().as_secs_f64();
+
+        let paths = self
+            .inner
+            .paths
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, metrics)| (path.to_string(), metrics.snapshot(uptime)))
+            .collect();
+
+        MetricsSnapshot { uptime, paths }
+    }
+
+    // Periodically writes snapshots to `settings.directory` (if any)
+    pub async fn run(self) {
+        let directory = match self.inner.settings.directory.clone() {
+            Some(directory) => directory,
+            None => return,
+        };
+
+        for sequence in 0.. {
+            time::sleep(self.inner.settings.snapshot_interval).await;
+
+            let path = directory.join(format!("snapshot-{:06}.json", sequence));
+
+            if self.write(path.as_path()).is_err() {
+                log::warn!("Failed to write metrics snapshot to {}", path.display());
+            }
+        }
+    }
+
+    // Writes the run report to `settings.directory` (if any)
+    pub fn report(&self) -> Result<(), Top<MetricsError>> {
+        match self.inner.settings.directory.as_ref() {
+            Some(directory) => self.write(directory.join("report.json").as_path()),
+            None => Ok(()),
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<(), Top<MetricsError>> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).pot(MetricsError::WriteFailed, here!())?;
+        }
+
+        let snapshot =
+            serde_json::to_vec_pretty(&self.snapshot()).pot(MetricsError::WriteFailed, here!())?;
+
+        fs::write(path, snapshot).pot(MetricsError::WriteFailed, here!())
+    }
+}
+
+impl PathMetrics {
+    fn snapshot(&self, uptime: f64) -> PathSnapshot {
+        let operations = self.latencies.len() as u64;
+
+        let throughput = if uptime > 0. {
+            operations as f64 / uptime
+        } else {
+            0.
+        };
+
+        let latency = if self.latencies.is_empty() {
+            None
+        } else {
+            let mut latencies = self.latencies.clone();
+            latencies.sort_unstable();
+
+            let percentile = |fraction: f64| {
+                let index = ((latencies.len() - 1) as f64 * fraction).round() as usize;
+                latencies[index] as f64 / 1000.
+            };
+
+            Some(Percentiles {
+                p50: percentile(0.5),
+                p90: percentile(0.9),
+                p99: percentile(0.99),
+                max: percentile(1.),
+            })
+        };
+
+        let failures = self
+            .failures
+            .iter()
+            .map(|(kind, count)| (kind.to_string(), *count))
+            .collect();
+
+        PathSnapshot {
+            operations,
+            throughput,
+            latency,
+            failures,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    #[test]
+    fn report() {
+        let directory = env::temp_dir().join(format!("metrics-{:016x}", rand::random::<u64>()));
+
+        let metrics = Metrics::new(MetricsSettings {
+            directory: Some(directory.clone()),
+            ..Default::default()
+        });
+
+        for latency in 1..=100 {
+            metrics.success("prepare", Duration::from_millis(latency));
+        }
+
+        metrics.failure("prepare", "unavailable");
+        metrics.failures("commit", "error", 3);
+
+        metrics.report().unwrap();
+
+        let report = fs::read(directory.join("report.json")).unwrap();
+        let _ = fs::remove_dir_all(&directory);
+
+        let report = serde_json::from_slice::<MetricsSnapshot>(report.as_slice()).unwrap();
+
+        let prepare = &report.paths["prepare"];
+        let latency = prepare.latency.as_ref().unwrap();
+
+        assert_eq!(prepare.operations, 100);
+        assert_eq!(latency.p50, 51.);
+        assert_eq!(latency.p99, 99.);
+        assert_eq!(latency.max, 100.);
+        assert_eq!(prepare.failures["unavailable"], 1);
+
+        let commit = &report.paths["commit"];
+
+        assert_eq!(commit.operations, 0);
+        assert!(commit.latency.is_none());
+        assert_eq!(commit.failures["error"], 3);
+    }
+}
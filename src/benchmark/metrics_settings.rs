@@ -0,0 +1,19 @@
+use std::{path::PathBuf, time::Duration};
+
+#[derive(Debug, Clone)]
+pub(crate) struct MetricsSettings {
+    // If `Some`, a `MetricsSnapshot` is written to `directory` every
+    // `snapshot_interval` (as `snapshot-{n}.json`), and a final one at
+    // shutdown (as `report.json`)
+    pub directory: Option<PathBuf>,
+    pub snapshot_interval: Duration,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        MetricsSettings {
+            directory: None,
+            snapshot_interval: Duration::from_secs(10),
+        }
+    }
+}
@@ -0,0 +1,14 @@
+mod latency_budget;
+mod metrics;
+mod metrics_settings;
+mod workload_planner;
+mod workload_settings;
+
+pub(crate) use latency_budget::{LatencyBudget, Stage};
+
+#[allow(unused_imports)]
+pub(crate) use metrics::{Metrics, MetricsError, MetricsSnapshot};
+
+pub(crate) use metrics_settings::MetricsSettings;
+pub(crate) use workload_planner::{WorkloadPlanner, WorkloadPlannerError};
+pub(crate) use workload_settings::WorkloadSettings;
@@ -0,0 +1,232 @@
+use crate::{account::Entry, benchmark::WorkloadSettings};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::time::Duration;
+
+// A `WorkloadPlanner` schedules the `Entry`s of a benchmark's batches. Ids are
+// partitioned in `cycle` groups of `batch_size` ids each: batch `b` draws all its
+// `Entry`s from group `b % cycle`, at height `first_height + b / cycle`. As a result:
+// - No id appears twice in the same batch.
+// - Each id's heights increase by one across the batches the id appears in.
+// - Two batches sharing an id are submitted at least `cycle / rate` apart. Unless
+//   this is no shorter than `latency`, `WorkloadPlanner::new` fails: an id is never
+//   reused before its previous operation commits, lest a batch at a later height
+//   overtake it (and the id be deemed to equivocate).
+pub(crate) struct WorkloadPlanner {
+    settings: WorkloadSettings,
+    cycle: u64,
+}
+
+#[derive(Doom)]
+pub(crate) enum WorkloadPlannerError {
+    #[doom(description("Batch size is zero"))]
+    BatchSizeZero,
+    #[doom(description("Rate must be positive and finite (rate: {})", rate))]
+    RateInvalid { rate: f64 },
+    #[doom(description(
+        "Not enough ids: {} ids in batches of {} are reused every {} batches, but {} batches are submitted within `latency` (increase `ids` or `latency`, or decrease `batch_size` or `rate`)",
+        ids,
+        batch_size,
+        cycle,
+        in_flight
+    ))]
+    IdsInsufficient {
+        ids: u64,
+        batch_size: usize,
+        cycle: u64,
+        in_flight: u64,
+    },
+    #[doom(description("Id range overflows"))]
+    IdsOverflow,
+    #[doom(description("Height overflows"))]
+    HeightOverflow,
+}
+
+impl WorkloadPlanner {
+    pub fn new(settings: WorkloadSettings) -> Result<Self, Top<WorkloadPlannerError>> {
+        if settings.batch_size == 0 {
+            return WorkloadPlannerError::BatchSizeZero.fail().spot(here!());
+        }
+
+        if !(settings.rate.is_finite() && settings.rate > 0.) {
+            return WorkloadPlannerError::RateInvalid {
+                rate: settings.rate,
+            }
+            .fail()
+            .spot(here!());
+        }
+
+        if settings.first_id.checked_add(settings.ids).is_none() {
+            return WorkloadPlannerError::IdsOverflow.fail().spot(here!());
+        }
+
+        let cycle = settings.ids / (settings.batch_size as u64);
+
+        // Batch `b + k` is submitted `k / rate` after batch `b`: batches
+        // `b + 1..b + in_flight` are submitted before `b` is committed
+        let in_flight = ((settings.rate * settings.latency.as_secs_f64()).ceil() as u64).max(1);
+
+        // Ids are reused only if there are more batches than groups
+        if cycle < in_flight.min(settings.batches as u64) {
+            return WorkloadPlannerError::IdsInsufficient {
+                ids: settings.ids,
+                batch_size: settings.batch_size,
+                cycle,
+                in_flight,
+            }
+            .fail()
+            .spot(here!());
+        }
+
+        if settings.batches > 0 {
+            let last = (settings.batches as u64 - 1) / cycle;
+
+            if settings.first_height.checked_add(last).is_none() {
+                return WorkloadPlannerError::HeightOverflow.fail().spot(here!());
+            }
+        }
+
+        Ok(WorkloadPlanner { settings, cycle })
+    }
+
+    pub fn settings(&self) -> &WorkloadSettings {
+        &self.settings
+    }
+
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    pub fn batch(&self, index: usize) -> Vec<Entry> {
+        assert!(index < self.settings.batches, "batch index out of range");
+
+        let index = index as u64;
+        let batch_size = self.settings.batch_size as u64;
+
+        let first_id = self.settings.first_id + (index % self.cycle) * batch_size;
+        let height = self.settings.first_height + index / self.cycle;
+
+        (first_id..first_id + batch_size)
+            .map(|id| Entry { id, height })
+            .collect()
+    }
+
+    pub fn batches(&self) -> impl Iterator<Item = Vec<Entry>> + '_ {
+        (0..self.settings.batches).map(move |index| self.batch(index))
+    }
+
+    // Offset (from the submission of the first batch) at which batch `index` is to be submitted
+    pub fn schedule(&self, index: usize) -> Duration {
+        Duration::from_secs_f64((index as f64) / self.settings.rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::account::Id;
+
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn no_overlap() {
+        let settings = WorkloadSettings {
+            first_id: 100,
+            ids: 70,
+            first_height: 3,
+            batch_size: 8,
+            batches: 50,
+            rate: 4.,
+            latency: Duration::from_millis(1500),
+        };
+
+        let planner = WorkloadPlanner::new(settings.clone()).unwrap();
+        assert_eq!(planner.cycle(), 8);
+
+        // Index and height of the last batch each id appeared in
+        let mut last: HashMap<Id, (usize, u64)> = HashMap::new();
+
+        for (index, batch) in planner.batches().enumerate() {
+            assert_eq!(batch.len(), settings.batch_size);
+
+            let ids = batch.iter().map(|entry| entry.id).collect::<HashSet<_>>();
+            assert_eq!(ids.len(), batch.len());
+
+            for entry in batch {
+                assert!(entry.id >= 100 && entry.id < 170);
+
+                match last.insert(entry.id, (index, entry.height)) {
+                    Some((previous, height)) => {
+                        assert_eq!(entry.height, height + 1);
+                        assert!(
+                            planner.schedule(index) - planner.schedule(previous)
+                                >= settings.latency
+                        );
+                    }
+                    None => assert_eq!(entry.height, 3),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn inconsistent() {
+        let consistent = WorkloadSettings {
+            ids: 64,
+            batch_size: 8,
+            batches: 100,
+            rate: 8.,
+            latency: Duration::from_secs(1),
+            ..Default::default()
+        };
+
+        assert!(WorkloadPlanner::new(consistent.clone()).is_ok());
+
+        for settings in [
+            WorkloadSettings {
+                batch_size: 0,
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                rate: 0.,
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                rate: f64::INFINITY,
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                ids: 63,
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                rate: 9.,
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                latency: Duration::from_millis(1001),
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                first_id: Id::MAX,
+                ..consistent.clone()
+            },
+            WorkloadSettings {
+                first_height: u64::MAX,
+                ..consistent.clone()
+            },
+        ] {
+            assert!(WorkloadPlanner::new(settings).is_err());
+        }
+
+        // Without reuse, `ids` need only cover all batches
+        assert!(WorkloadPlanner::new(WorkloadSettings {
+            ids: 24,
+            batches: 3,
+            ..consistent
+        })
+        .is_ok());
+    }
+}
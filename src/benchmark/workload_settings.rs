@@ -0,0 +1,32 @@
+use crate::account::Id;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub(crate) struct WorkloadSettings {
+    // Ids available to the workload (`first_id..first_id + ids`)
+    pub first_id: Id,
+    pub ids: u64,
+    // Height of each id's first operation (i.e., its account's height, plus one)
+    pub first_height: u64,
+    pub batch_size: usize,
+    pub batches: usize,
+    // Rate (in batches per second) at which batches are submitted
+    pub rate: f64,
+    // Upper bound on the time between a batch's submission and its commit
+    pub latency: Duration,
+}
+
+impl Default for WorkloadSettings {
+    fn default() -> Self {
+        WorkloadSettings {
+            first_id: 0,
+            ids: 65536,
+            first_height: 1,
+            batch_size: 65536,
+            batches: 1,
+            rate: 1.,
+            latency: Duration::from_secs(10),
+        }
+    }
+}
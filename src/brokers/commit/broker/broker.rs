@@ -1,8 +1,12 @@
 use crate::{
-    brokers::commit::{Broker, BrokerFailure, Brokerage, Submission, UnzippedBrokerages},
+    benchmark::Metrics,
+    brokers::commit::{
+        Broker, BrokerFailure, Brokerage, Submission, Substitutions, UnzippedBrokerages,
+    },
     commit::CompletionProof,
-    data::PingBoard,
-    processing::messages::CommitRequest,
+    data::{PingBoard, QuorumMonitor},
+    processing::{messages::CommitRequest, Namespace, Timeout},
+    telemetry::{Registry, Span},
     view::View,
 };
 
@@ -10,7 +14,10 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use futures::stream::{FuturesUnordered, StreamExt};
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use talk::{crypto::Identity, net::SessionConnector};
 
@@ -25,40 +32,128 @@ enum PublishError {
 }
 
 impl Broker {
+    #[allow(clippy::too_many_arguments)]
     pub(in crate::brokers::commit::broker) async fn broker(
+        namespace: Namespace,
         view: View,
         ping_board: PingBoard,
         connector: Arc<SessionConnector>,
         brokerages: Vec<Brokerage>,
+        completion_deadline: Duration,
+        substitutions: Substitutions,
+        receive_timeout: Timeout,
+        quorum_monitor: QuorumMonitor,
+        metrics: Metrics,
+        registry: Registry,
     ) {
+        let flushed = Instant::now();
+
+        // A degraded `Broker` cannot gather a quorum of replicas: fail all
+        // brokerages at once, rather than leaving their clients hanging
+
+        if quorum_monitor.is_degraded() {
+            metrics.failures("commit", "unavailable", brokerages.len() as u64);
+
+            for completion_inlet in brokerages
+                .into_iter()
+                .flat_map(|brokerage| brokerage.completion_inlets)
+            {
+                let _ = completion_inlet.send(Err(BrokerFailure::Unavailable));
+            }
+
+            return;
+        }
+
         // Unzip `brokerages` into its components
 
         let UnzippedBrokerages {
             payloads,
             commit_proofs,
             dependencies,
+            traces,
             completion_inlets,
         } = Brokerage::unzip(brokerages);
 
         let payloads = Vector::new(payloads).unwrap();
         let submission = Submission::new(payloads.clone(), commit_proofs, dependencies);
 
-        // Orchestrate submission to obtain `BatchCompletion`
+        // If any client supplied a trace, the batch is traced by a span
+        // linked to all client traces
+
+        let mut span = if traces.is_empty() {
+            None
+        } else {
+            let mut span = Span::linked("commit.batch", &traces);
+            span.attribute("batch_size", payloads.len() as i64);
+            Some(span)
+        };
+
+        let submission = match &span {
+            Some(span) => submission.with_trace(span.context()),
+            None => submission,
+        };
+
+        // Orchestrate submission to obtain `BatchCompletion`, unless
+        // the `Broker` is degraded in the meantime
+
+        let orchestrate = Broker::orchestrate(
+            namespace,
+            view.clone(),
+            ping_board,
+            connector.clone(),
+            submission,
+            completion_deadline,
+            substitutions,
+            receive_timeout,
+        );
+
+        let batch_completion = tokio::select! {
+            batch_completion = orchestrate => batch_completion.map_err(|_| BrokerFailure::Error),
+            _ = quorum_monitor.await_degraded() => Err(BrokerFailure::Unavailable),
+        };
+
+        if batch_completion.is_err() {
+            log::warn!("Failed to broker a batch of {} payloads", payloads.len());
+        }
+
+        if let Some(span) = span.as_mut() {
+            span.event(if batch_completion.is_ok() {
+                "completed"
+            } else {
+                "failed"
+            });
+        }
 
-        let batch_completion =
-            Broker::orchestrate(view.clone(), ping_board, connector.clone(), submission)
-                .await
-                .map_err(|_| BrokerFailure::Error);
+        match &batch_completion {
+            Ok(_) => {
+                for _ in 0..payloads.len() {
+                    metrics.success("commit", flushed.elapsed());
+                }
+
+                registry
+                    .counter("carbon_broker_batches_total", "Batches brokered")
+                    .increment();
+
+                registry
+                    .histogram("carbon_broker_batch_seconds", "Latency of brokered batches")
+                    .observe(flushed.elapsed());
+            }
+            Err(failure) => {
+                metrics.failures("commit", failure.kind(), payloads.len() as u64);
+            }
+        }
 
         // Dispatch appropriate `CompletionProof` to all `serve` tasks
 
-        for (index, completion_inlet) in completion_inlets.into_iter().enumerate() {
+        for (index, completion_inlets) in completion_inlets.into_iter().enumerate() {
             let completion_proof = batch_completion.clone().map(|batch_completion| {
                 let inclusion = payloads.prove(index);
                 CompletionProof::new(batch_completion, inclusion)
             });
 
-            let _ = completion_inlet.send(completion_proof);
+            for completion_inlet in completion_inlets {
+                let _ = completion_inlet.send(completion_proof.clone());
+            }
         }
 
         // If `completion` is `Ok`, publish `BatchCommit` to all replicas
@@ -1,59 +1,166 @@
 use crate::{
-    brokers::commit::{Broker, BrokerFailure, Brokerage},
-    data::{PingBoard, Sponge},
+    benchmark::Metrics,
+    brokers::commit::{Broker, BrokerFailure, Brokerage, Substitutions},
+    data::{PingBoard, QuorumMonitor, Sponge},
+    processing::{Namespace, Timeout},
+    telemetry::{Badge, Registry},
     view::View,
 };
 
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use talk::{net::SessionConnector, sync::fuse::Fuse};
 
+use tokio::{sync::Semaphore, time::Instant};
+
 impl Broker {
+    #[allow(clippy::too_many_arguments)]
     pub(in crate::brokers::commit::broker) async fn flush(
+        namespace: Namespace,
         view: View,
         brokerage_sponge: Arc<Sponge<Brokerage>>,
         ping_board: PingBoard,
         connector: Arc<SessionConnector>,
+        max_in_flight: Option<usize>,
+        completion_deadline: Duration,
+        substitutions: Substitutions,
+        receive_timeout: Timeout,
+        expired: Arc<AtomicU64>,
+        quorum_monitor: QuorumMonitor,
+        metrics: Metrics,
+        registry: Registry,
     ) {
+        let in_flight = max_in_flight.map(|max_in_flight| Arc::new(Semaphore::new(max_in_flight)));
         let fuse = Fuse::new();
 
         loop {
-            // Remark: `brokerage_sponge.flush()` always returns a non-empty
-            // `Vec<Brokerage>`. Because `Broker::prepare` only filters `Id`
-            // duplicates, it never produces an empty output on a non-empty input.
-            let brokerages = Broker::prepare(brokerage_sponge.flush().await);
+            // A slot is secured before flushing, so that brokerages keep
+            // accumulating in `brokerage_sponge` while all slots are taken
+            let slot = match in_flight.as_ref() {
+                // `in_flight` is never closed
+                Some(in_flight) => Some(in_flight.clone().acquire_owned().await.unwrap()),
+                None => None,
+            };
+
+            let brokerages = Broker::prepare(brokerage_sponge.flush().await, expired.as_ref());
 
+            // All flushed `Brokerage`s might have expired
+            if brokerages.is_empty() {
+                continue;
+            }
+
+            let namespace = namespace.clone();
             let view = view.clone();
             let ping_board = ping_board.clone();
             let connector = connector.clone();
+            let substitutions = substitutions.clone();
+            let receive_timeout = receive_timeout.clone();
+            let quorum_monitor = quorum_monitor.clone();
+            let metrics = metrics.clone();
+            let registry = registry.clone();
+
+            fuse.spawn(Badge::inherit(async move {
+                Broker::broker(
+                    namespace,
+                    view,
+                    ping_board,
+                    connector,
+                    brokerages,
+                    completion_deadline,
+                    substitutions,
+                    receive_timeout,
+                    quorum_monitor,
+                    metrics,
+                    registry,
+                )
+                .await;
 
-            fuse.spawn(async move {
-                Broker::broker(view, ping_board, connector, brokerages).await;
-            });
+                drop(slot);
+            }));
         }
     }
 
-    fn prepare(mut brokerages: Vec<Brokerage>) -> Vec<Brokerage> {
-        // Sort `brokerages` by requestor
+    fn prepare(mut brokerages: Vec<Brokerage>, expired: &AtomicU64) -> Vec<Brokerage> {
+        // Sort `brokerages` by requestor (`sort_by_key` is stable: the
+        // `Brokerage`s of each requestor remain in order of receipt)
 
         brokerages.sort_by_key(|brokerage| brokerage.request.id());
 
+        // Merge `brokerages` of the same `Request`. A client who reconnected
+        // and resubmitted its `Request` joins the clients already awaiting it:
+        // the merged `Brokerage` expires with its freshest client, and its
+        // outcome (expiry included) is delivered to all its clients
+
+        let mut merged: Vec<Brokerage> = Vec::with_capacity(brokerages.len());
+
+        for brokerage in brokerages {
+            let id = brokerage.request.id();
+            let prepare = brokerage.request.commit.payload().prepare();
+
+            let previous = merged
+                .iter_mut()
+                .rev()
+                .take_while(|previous| previous.request.id() == id)
+                .find(|previous| {
+                    let previous = previous.request.commit.payload().prepare();
+
+                    previous.height() == prepare.height()
+                        && previous.commitment() == prepare.commitment()
+                });
+
+            match previous {
+                Some(previous) => {
+                    previous.expiry = previous.expiry.max(brokerage.expiry);
+                    previous
+                        .completion_inlets
+                        .extend(brokerage.completion_inlets);
+                }
+                None => merged.push(brokerage),
+            }
+        }
+
+        // Fail expired `Brokerage`s. Expired `Brokerage`s are filtered before
+        // deduplication, so that the fresh `Brokerage` of a client who
+        // reconnected and submitted a different `Request` is not throttled
+        // in favour of a stale one
+
+        let now = Instant::now();
+
+        let brokerages = merged.into_iter().filter_map(|brokerage| {
+            if brokerage.expiry <= now {
+                for completion_inlet in brokerage.completion_inlets {
+                    let _ = completion_inlet.send(Err(BrokerFailure::Expired));
+                }
+
+                expired.fetch_add(1, Ordering::Relaxed);
+                None
+            } else {
+                Some(brokerage)
+            }
+        });
+
         // Deduplicate and fail `brokerages` by requestor
 
         // The following implementation does not use `Vec::dedup_*` because
         // duplicate `Brokerage`s must be failed explicitly. If an element
-        // of `brokerages` duplicates the previous, its `completion_inlet` is
+        // of `brokerages` duplicates the previous, its `completion_inlets` are
         // consumed to `send` a `BrokerFailure`, to the appropriate `serve`
-        // task, and the element is filtered out of `prepare`'s return.
+        // tasks, and the element is filtered out of `prepare`'s return.
         let mut previous = None;
 
         brokerages
-            .into_iter()
             .filter_map(|brokerage| {
                 if Some(brokerage.request.id()) == previous {
-                    let _ = brokerage
-                        .completion_inlet
-                        .send(Err(BrokerFailure::Throttle));
+                    for completion_inlet in brokerage.completion_inlets {
+                        let _ = completion_inlet.send(Err(BrokerFailure::Throttle));
+                    }
+
                     None
                 } else {
                     previous = Some(brokerage.request.id());
@@ -63,3 +170,161 @@ impl Broker {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::{Entry, Operation},
+        brokers::commit::Request,
+        commit::{Commit, CommitProof, CompletionProof, Payload},
+        prepare::BatchCommit,
+        view::test::InstallGenerator,
+    };
+
+    use std::collections::BTreeSet;
+
+    use talk::crypto::primitives::hash;
+
+    use tokio::sync::oneshot::{self, Receiver};
+
+    use zebra::vector::Vector;
+
+    type CompletionOutlet = Receiver<Result<CompletionProof, BrokerFailure>>;
+
+    // Validity is irrelevant to `Broker::prepare`: `Commit`s are not certified
+    fn brokerage(view: &View, payload: Payload, expiry: Instant) -> (Brokerage, CompletionOutlet) {
+        let batch = BatchCommit::new(
+            view.clone(),
+            &Namespace::default(),
+            hash::hash(&0u64).unwrap(),
+            BTreeSet::new(),
+            [],
+        );
+
+        let inclusion = Vector::new(vec![payload.prepare()]).unwrap().prove(0);
+        let commit = Commit::new(CommitProof::new(batch, inclusion), payload);
+
+        let (completion_inlet, completion_outlet) = oneshot::channel();
+
+        let brokerage = Brokerage {
+            request: Request::new(commit, Vec::new()),
+            completion_inlets: vec![completion_inlet],
+            expiry,
+        };
+
+        (brokerage, completion_outlet)
+    }
+
+    fn withdraw(id: u64, amount: u64) -> Payload {
+        Payload::new(
+            Entry { id, height: 1 },
+            Operation::withdraw(id + 1, 0, amount),
+        )
+    }
+
+    fn failure(completion_outlet: &mut CompletionOutlet) -> Option<BrokerFailure> {
+        match completion_outlet.try_recv() {
+            Ok(Err(failure)) => Some(failure),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnected() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let expired = AtomicU64::new(0);
+        let now = Instant::now();
+
+        // A client reconnects and resubmits its `Request` after the original expired:
+        // the two `Brokerage`s are merged, and submitted to the benefit of both
+
+        let (stale, mut stale_outlet) = brokerage(&view, withdraw(0, 10), now);
+
+        let (fresh, mut fresh_outlet) =
+            brokerage(&view, withdraw(0, 10), now + Duration::from_secs(60));
+
+        let brokerages = Broker::prepare(vec![stale, fresh], &expired);
+
+        assert_eq!(brokerages.len(), 1);
+        assert_eq!(brokerages[0].completion_inlets.len(), 2);
+        assert_eq!(expired.load(Ordering::Relaxed), 0);
+
+        assert!(failure(&mut stale_outlet).is_none());
+        assert!(failure(&mut fresh_outlet).is_none());
+
+        drop(brokerages);
+
+        // If all its clients expired, the merged `Brokerage` expires, and all its
+        // clients are notified
+
+        let (stale, mut stale_outlet) = brokerage(&view, withdraw(0, 10), now);
+        let (reconnected, mut reconnected_outlet) = brokerage(&view, withdraw(0, 10), now);
+
+        let brokerages = Broker::prepare(vec![stale, reconnected], &expired);
+
+        assert!(brokerages.is_empty());
+        assert_eq!(expired.load(Ordering::Relaxed), 1);
+
+        assert!(matches!(
+            failure(&mut stale_outlet),
+            Some(BrokerFailure::Expired)
+        ));
+
+        assert!(matches!(
+            failure(&mut reconnected_outlet),
+            Some(BrokerFailure::Expired)
+        ));
+    }
+
+    #[tokio::test]
+    async fn throttle() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let expired = AtomicU64::new(0);
+        let now = Instant::now();
+        let later = now + Duration::from_secs(60);
+
+        // Different `Request`s by the same requestor: only the first received is submitted
+
+        let (first, mut first_outlet) = brokerage(&view, withdraw(0, 10), later);
+        let (second, mut second_outlet) = brokerage(&view, withdraw(0, 20), later);
+        let (other, _other_outlet) = brokerage(&view, withdraw(1, 10), later);
+
+        let brokerages = Broker::prepare(vec![first, other, second], &expired);
+
+        assert_eq!(brokerages.len(), 2);
+        assert_eq!(brokerages[0].request.id(), 0);
+        assert_eq!(brokerages[1].request.id(), 1);
+
+        assert!(failure(&mut first_outlet).is_none());
+
+        assert!(matches!(
+            failure(&mut second_outlet),
+            Some(BrokerFailure::Throttle)
+        ));
+
+        drop(brokerages);
+
+        // A stale `Request` does not throttle a fresh, different `Request`
+
+        let (stale, mut stale_outlet) = brokerage(&view, withdraw(0, 10), now);
+        let (fresh, mut fresh_outlet) = brokerage(&view, withdraw(0, 20), later);
+
+        let brokerages = Broker::prepare(vec![stale, fresh], &expired);
+
+        assert_eq!(brokerages.len(), 1);
+        assert_eq!(expired.load(Ordering::Relaxed), 1);
+
+        assert!(matches!(
+            failure(&mut stale_outlet),
+            Some(BrokerFailure::Expired)
+        ));
+
+        assert!(failure(&mut fresh_outlet).is_none());
+    }
+}
@@ -1,23 +1,32 @@
 use crate::{
-    brokers::commit::{brokerage::Brokerage, Broker, Request},
-    data::Sponge,
+    brokers::commit::{brokerage::Brokerage, Broker, BrokerFailure, Request},
+    commit::CompletionProof,
+    data::{MemoryGauge, Sponge},
     discovery::Client,
+    handles::Lifecycle,
+    prepare::BatchCommitCache,
+    processing::Timeout,
+    telemetry::Span,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use talk::{net::PlainConnection, sync::fuse::Fuse};
 
-use tokio::{net::TcpListener, sync::oneshot};
+use tokio::{net::TcpListener, sync::oneshot, time::Instant};
 
 #[derive(Doom)]
 enum ServeError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Request invalid"))]
     RequestInvalid,
+    #[doom(description("Request pertains to a foreign namespace"))]
+    ForeignNamespace,
     #[doom(description("`Brokerage` forfeited (most likely, the `Broker` is shutting down)"))]
     #[doom(wrap(request_forfeited))]
     BrokerageForfeited { source: oneshot::error::RecvError },
@@ -27,19 +36,47 @@ impl Broker {
     pub(in crate::brokers::commit::broker) async fn listen(
         discovery: Arc<Client>,
         brokerage_sponge: Arc<Sponge<Brokerage>>,
+        batch_commit_cache: Arc<BatchCommitCache>,
+        memory_gauge: MemoryGauge,
         listener: TcpListener,
+        receive_timeout: Timeout,
+        request_ttl: Duration,
+        lifecycle: Lifecycle,
     ) {
         let fuse = Fuse::new();
 
         loop {
+            // While shedding, new connections are left pending in `listener`'s backlog
+            memory_gauge.await_relieved().await;
+
             if let Ok((stream, _)) = listener.accept().await {
+                // While draining, new connections are dropped unserved
+                let engagement = match lifecycle.engage() {
+                    Some(engagement) => engagement,
+                    None => continue,
+                };
+
                 let connection: PlainConnection = stream.into();
 
                 let discovery = discovery.clone();
                 let brokerage_sponge = brokerage_sponge.clone();
+                let batch_commit_cache = batch_commit_cache.clone();
+                let memory_gauge = memory_gauge.clone();
+                let receive_timeout = receive_timeout.clone();
 
                 fuse.spawn(async move {
-                    let _ = Broker::serve(discovery, brokerage_sponge, connection).await;
+                    let _engagement = engagement;
+
+                    let _ = Broker::serve(
+                        discovery,
+                        brokerage_sponge,
+                        batch_commit_cache,
+                        memory_gauge,
+                        connection,
+                        receive_timeout,
+                        request_ttl,
+                    )
+                    .await;
                 });
             }
         }
@@ -48,26 +85,68 @@ impl Broker {
     async fn serve(
         discovery: Arc<Client>,
         brokerage_sponge: Arc<Sponge<Brokerage>>,
+        batch_commit_cache: Arc<BatchCommitCache>,
+        memory_gauge: MemoryGauge,
         mut connection: PlainConnection,
+        receive_timeout: Timeout,
+        request_ttl: Duration,
     ) -> Result<(), Top<ServeError>> {
         // Receive and validate `Request`
 
-        let request = connection
-            .receive::<Request>()
+        let request = receive_timeout
+            .run(connection.receive::<Request>())
             .await
+            .pot(ServeError::ReceiveTimeout, here!())?
             .pot(ServeError::ConnectionError, here!())?;
 
+        // Connections accepted before the `Broker` started shedding are
+        // served, but their `Request`s are rejected
+
+        if memory_gauge.is_shedding() {
+            connection
+                .send::<Result<CompletionProof, BrokerFailure>>(&Err(BrokerFailure::Busy))
+                .await
+                .pot(ServeError::ConnectionError, here!())?;
+
+            return Ok(());
+        }
+
+        if request.namespace != *discovery.namespace() {
+            connection
+                .send::<Result<CompletionProof, BrokerFailure>>(&Err(
+                    BrokerFailure::ForeignNamespace,
+                ))
+                .await
+                .pot(ServeError::ConnectionError, here!())?;
+
+            return ServeError::ForeignNamespace.fail().spot(here!());
+        }
+
         request
-            .validate(discovery.as_ref())
+            .validate(discovery.as_ref(), batch_commit_cache.as_ref())
             .pot(ServeError::RequestInvalid, here!())?;
 
+        // If the client supplied a trace, the brokerage is traced by a child span
+
+        let _span = request
+            .trace
+            .map(|trace| Span::child("commit.request", trace));
+
         // Build and submit `Brokerage` to `brokerage_sponge`
 
+        let ttl = request.ttl.map_or(request_ttl, |ttl| ttl.min(request_ttl));
+        let expiry = Instant::now() + ttl;
+
         let (completion_inlet, completion_outlet) = oneshot::channel();
 
+        // `request` is accounted for until its `Completion` is delivered
+        let _reservation =
+            memory_gauge.reserve(bincode::serialized_size(&request).unwrap_or(0) as usize);
+
         let brokerage = Brokerage {
             request,
-            completion_inlet,
+            completion_inlets: vec![completion_inlet],
+            expiry,
         };
 
         brokerage_sponge.push(brokerage);
@@ -1,13 +1,25 @@
 use crate::{
-    crypto::Identify,
-    data::{PingBoard, Sponge},
+    benchmark::Metrics,
+    brokers::commit::{brokerage::Brokerage, BrokerSettings, Substitutions},
+    data::{MemoryGauge, PingBoard, QuorumMonitor, Sponge},
     discovery::Client,
+    handles::{DrainError, Lifecycle},
+    prepare::BatchCommitCache,
+    processing::{Namespace, Timeout},
+    telemetry::{Badge, Exporter, Registry, Role},
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use talk::{
     link::context::ConnectDispatcher,
@@ -22,6 +34,15 @@ use tokio::{
 
 pub(crate) struct Broker {
     address: SocketAddr,
+    brokerage_sponge: Arc<Sponge<Brokerage>>,
+    receive_timeout: Timeout,
+    substitutions: Substitutions,
+    expired: Arc<AtomicU64>,
+    quorum_monitor: QuorumMonitor,
+    metrics: Metrics,
+    registry: Registry,
+    memory_gauge: MemoryGauge,
+    lifecycle: Lifecycle,
     _fuse: Fuse,
 }
 
@@ -38,6 +59,7 @@ impl Broker {
         view: View,
         address: A,
         connector: C,
+        settings: BrokerSettings,
     ) -> Result<Self, Top<BrokerError>>
     where
         A: ToSocketAddrs,
@@ -56,42 +78,175 @@ impl Broker {
             .spot(here!())?;
 
         let dispatcher = ConnectDispatcher::new(connector);
-        let context = format!("{:?}::processor::commit", view.identifier());
+        let namespace = discovery.namespace().clone();
+        let context = namespace.context(&view, "commit");
+        let receive_timeout = Timeout::new(settings.receive_timeout);
         let connector = Arc::new(SessionConnector::new(dispatcher.register(context)));
 
-        let brokerage_sponge = Arc::new(Sponge::new(Default::default())); // TODO: Add settings
+        let brokerage_sponge = Arc::new(Sponge::new(settings.brokerage_sponge_settings.clone()));
         let ping_board = PingBoard::new(&view);
+        let substitutions = Substitutions::default();
+        let expired = Arc::new(AtomicU64::new(0));
+        let quorum_monitor = QuorumMonitor::new();
+        let metrics = Metrics::new(settings.metrics.clone());
+        let registry = Registry::new();
+        let memory_gauge = MemoryGauge::new(settings.memory.clone());
+        let lifecycle = Lifecycle::new().with_badge(Badge::broker(Role::CommitBroker, address));
+
+        let batch_commit_cache =
+            Arc::new(BatchCommitCache::new(settings.batch_commit_cache_capacity));
 
         let fuse = Fuse::new();
 
         {
             let discovery = discovery.clone();
-            let brokerage_sponge = brokerage_sponge.clone();
+            let batch_commit_cache = batch_commit_cache.clone();
+            let height = view.height();
 
             fuse.spawn(async move {
-                Broker::listen(discovery, brokerage_sponge, listener).await;
+                batch_commit_cache
+                    .invalidate_on_install(&discovery, height)
+                    .await;
             });
         }
 
         {
+            let discovery = discovery.clone();
+            let brokerage_sponge = brokerage_sponge.clone();
+            let batch_commit_cache = batch_commit_cache.clone();
+            let memory_gauge = memory_gauge.clone();
+            let receive_timeout = receive_timeout.clone();
+            let request_ttl = settings.request_ttl;
+
+            let lifecycle = lifecycle.clone();
+
+            fuse.spawn(lifecycle.clone().guard("listen", async move {
+                Broker::listen(
+                    discovery,
+                    brokerage_sponge,
+                    batch_commit_cache,
+                    memory_gauge,
+                    listener,
+                    receive_timeout,
+                    request_ttl,
+                    lifecycle,
+                )
+                .await;
+            }));
+        }
+
+        {
+            let brokerage_sponge = brokerage_sponge.clone();
+
+            registry.gauge(
+                "carbon_broker_sponge_occupancy",
+                "Brokerages waiting in the brokerage sponge",
+                move || brokerage_sponge.len() as f64,
+            );
+        }
+
+        {
+            let namespace = namespace.clone();
             let view = view.clone();
             let ping_board = ping_board.clone();
             let connector = connector.clone();
+            let max_in_flight = settings.max_in_flight;
+            let completion_deadline = settings.completion_deadline;
+            let substitutions = substitutions.clone();
+            let receive_timeout = receive_timeout.clone();
+            let expired = expired.clone();
+            let quorum_monitor = quorum_monitor.clone();
+            let metrics = metrics.clone();
+            let registry = registry.clone();
+            let brokerage_sponge = brokerage_sponge.clone();
 
-            fuse.spawn(async move {
-                Broker::flush(view, brokerage_sponge, ping_board, connector).await;
-            });
+            fuse.spawn(lifecycle.guard("flush", async move {
+                Broker::flush(
+                    namespace,
+                    view,
+                    brokerage_sponge,
+                    ping_board,
+                    connector,
+                    max_in_flight,
+                    completion_deadline,
+                    substitutions,
+                    receive_timeout,
+                    expired,
+                    quorum_monitor,
+                    metrics,
+                    registry,
+                )
+                .await;
+            }));
+        }
+
+        {
+            let metrics = metrics.clone();
+            fuse.spawn(metrics.run());
+        }
+
+        if let Some(address) = settings.exporter.address {
+            let registry = registry.clone();
+
+            fuse.spawn(lifecycle.guard("exporter", async move {
+                if Exporter::run(registry, address).await.is_err() {
+                    log::error!("Failed to start metrics exporter on {}", address);
+                }
+            }));
         }
 
         for replica in view.members().keys().copied() {
             let ping_board = ping_board.clone();
             let connector = connector.clone();
+            let ping_interval = settings.ping_interval;
+            let ping_retry_interval = settings.ping_retry_interval;
+
+            fuse.spawn(lifecycle.guard("ping", async move {
+                Broker::ping(
+                    ping_board,
+                    connector,
+                    replica,
+                    ping_interval,
+                    ping_retry_interval,
+                )
+                .await
+            }));
+        }
+
+        // The `Broker` is ready once a quorum of replicas is responsive
+        // (i.e., has registered its commit context)
+        {
+            let ping_board = ping_board.clone();
+            let quorum = view.weighted_quorum();
 
-            fuse.spawn(async move { Broker::ping(ping_board, connector, replica).await });
+            fuse.spawn(lifecycle.gate(async move {
+                ping_board.await_responsive(quorum).await;
+            }));
+        }
+
+        {
+            let quorum_monitor = quorum_monitor.clone();
+            let quorum = view.weighted_quorum();
+            let degradation_grace = settings.degradation_grace;
+
+            fuse.spawn(lifecycle.guard("monitor", async move {
+                quorum_monitor
+                    .run(ping_board, quorum, degradation_grace)
+                    .await;
+            }));
         }
 
         Ok(Broker {
             address,
+            brokerage_sponge,
+            receive_timeout,
+            substitutions,
+            expired,
+            quorum_monitor,
+            metrics,
+            registry,
+            memory_gauge,
+            lifecycle,
             _fuse: fuse,
         })
     }
@@ -99,6 +254,53 @@ impl Broker {
     pub fn address(&self) -> SocketAddr {
         self.address
     }
+
+    // Number of `receive`s (from clients or replicas) that timed out
+    pub fn expired_timeouts(&self) -> u64 {
+        self.receive_timeout.expired()
+    }
+
+    // Replicas substituted while collecting `BatchCompletionShard`s
+    pub fn substitutions(&self) -> &Substitutions {
+        &self.substitutions
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    // Stops accepting clients, flushes pending brokerages without waiting for the
+    // brokerage sponge's timeout, and waits (for at most `timeout`) for all clients
+    // being served (hence, all in-flight brokerages) to complete
+    pub async fn drain(&self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        self.brokerage_sponge.expedite();
+        self.lifecycle.drain(timeout).await
+    }
+
+    // Whether fewer than a quorum of replicas have been responsive for too long
+    pub fn quorum_monitor(&self) -> &QuorumMonitor {
+        &self.quorum_monitor
+    }
+
+    // Outcomes of the brokered commits (latencies are measured from flush)
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Instruments of the `Broker`, as served by its `Exporter` (if any)
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    // Whether the `Broker` is shedding load, having exceeded its memory watermarks
+    pub fn memory_gauge(&self) -> &MemoryGauge {
+        &self.memory_gauge
+    }
+
+    // Number of `Request`s dropped for expiring in the brokerage sponge
+    pub fn expired_requests(&self) -> u64 {
+        self.expired.load(Ordering::Relaxed)
+    }
 }
 
 mod broker;
@@ -109,8 +311,6 @@ mod ping;
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
-
     use crate::{
         account::{Entry, Operation},
         brokers::{
@@ -119,11 +319,12 @@ mod tests {
                 BrokerFailure as PrepareBrokerFailure, Inclusion as PrepareInclusion,
                 Request as PrepareRequest,
             },
-            signup::BrokerFailure as SignupBrokerFailure,
+            signup::{BrokerFailure as SignupBrokerFailure, Request as SignupBrokerRequest},
             test::System,
         },
         commit::{Commit, CommitProof, Completion, CompletionProof, Payload},
         prepare::BatchCommit,
+        processing::Namespace,
         signup::{IdAssignment, IdRequest, SignupSettings},
     };
 
@@ -131,12 +332,14 @@ mod tests {
 
     use tokio::net::TcpStream;
 
+    use zebra::vector::Proof;
+
     #[tokio::test]
     async fn develop() {
         let System {
             view,
             discovery_server: _discovery_server,
-            discovery_client: _discovery_client,
+            discovery_client,
             processors,
             mut signup_brokers,
             mut prepare_brokers,
@@ -165,7 +368,13 @@ mod tests {
         let stream = TcpStream::connect(signup_broker.address()).await.unwrap();
         let mut connection: PlainConnection = stream.into();
 
-        connection.send(&request).await.unwrap();
+        connection
+            .send(&SignupBrokerRequest::IdRequest {
+                namespace: Namespace::default(),
+                request,
+            })
+            .await
+            .unwrap();
 
         let assignment = connection
             .receive::<Result<IdAssignment, SignupBrokerFailure>>()
@@ -213,13 +422,13 @@ mod tests {
 
         connection.send(&reduction_shard).await.unwrap();
 
-        let batch_commit = connection
-            .receive::<Result<BatchCommit, PrepareBrokerFailure>>()
+        let (batch_commit, proof) = connection
+            .receive::<Result<(BatchCommit, Proof), PrepareBrokerFailure>>()
             .await
             .unwrap()
             .unwrap();
 
-        let commit_proof = CommitProof::new(batch_commit, inclusion.proof);
+        let commit_proof = CommitProof::new(batch_commit, proof);
 
         let commit = Commit::new(commit_proof, payload.clone());
 
@@ -227,7 +436,7 @@ mod tests {
 
         // Commit
 
-        let request = Request::new(commit, None);
+        let request = Request::new(commit, Vec::new());
 
         let stream = TcpStream::connect(commit_broker.address()).await.unwrap();
         let mut connection: PlainConnection = stream.into();
@@ -282,13 +491,13 @@ mod tests {
 
         connection.send(&reduction_shard).await.unwrap();
 
-        let batch_commit = connection
-            .receive::<Result<BatchCommit, PrepareBrokerFailure>>()
+        let (batch_commit, proof) = connection
+            .receive::<Result<(BatchCommit, Proof), PrepareBrokerFailure>>()
             .await
             .unwrap()
             .unwrap();
 
-        let commit_proof = CommitProof::new(batch_commit, inclusion.proof);
+        let commit_proof = CommitProof::new(batch_commit, proof);
 
         let commit = Commit::new(commit_proof, payload.clone());
 
@@ -296,7 +505,7 @@ mod tests {
 
         // Commit
 
-        let request = Request::new(commit, Some(withdrawal.clone()));
+        let request = Request::new(commit, vec![withdrawal.clone()]);
 
         let stream = TcpStream::connect(commit_broker.address()).await.unwrap();
         let mut connection: PlainConnection = stream.into();
@@ -313,6 +522,9 @@ mod tests {
 
         let deposit = Completion::new(completion_proof, payload);
 
-        tokio::time::sleep(Duration::from_secs(10)).await;
+        // Readiness was awaited by `System::setup`: no settling delay is needed
+        // before or after brokering
+        deposit.validate(&discovery_client).unwrap();
+        assert!(commit_broker.lifecycle().is_ready());
     }
 }
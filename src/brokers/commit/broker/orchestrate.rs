@@ -1,18 +1,25 @@
 use crate::{
-    brokers::commit::{submission::Submission, Broker},
+    brokers::commit::{submission::Submission, Broker, Substitutions},
     commit::{
         BatchCompletion, BatchCompletionAggregator, BatchCompletionShard, CommitProof, Completion,
         WitnessStatement,
     },
     crypto::{Aggregator, Certificate},
     data::PingBoard,
-    processing::messages::{CommitRequest, CommitResponse},
+    processing::{
+        messages::{CommitRequest, CommitResponse},
+        Namespace, Timeout,
+    },
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 use talk::{
     crypto::{
@@ -47,16 +54,21 @@ enum Update {
 
 struct WitnessCollector {
     view: View,
-    root: Hash,
     aggregator: Aggregator<WitnessStatement>,
-    errors: usize,
+    failed: HashSet<Identity>,
 }
 
 struct CompletionCollector {
     view: View,
-    root: Hash,
     aggregator: BatchCompletionAggregator,
-    errors: usize,
+    witness: Certificate,
+    // Replicas whose `BatchCompletionShard` is awaited, yet to respond
+    pending: HashSet<Identity>,
+    // Replicas whose `BatchCompletionShard` is not (yet) awaited, fastest first
+    reserve: VecDeque<Identity>,
+    failed: HashSet<Identity>,
+    completion_deadline: Duration,
+    substitutions: Substitutions,
 }
 
 #[derive(Doom)]
@@ -73,6 +85,8 @@ enum SubmitError {
     ConnectionFailed,
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Unexpected response"))]
     UnexpectedResponse,
     #[doom(description("Malformed response"))]
@@ -83,6 +97,8 @@ enum SubmitError {
     InvalidCompletionShard,
     #[doom(description("`Command` channel closed (most likely, the `Broker` is shutting down)"))]
     CommandChannelClosed,
+    #[doom(description("Unexpected `Command`"))]
+    UnexpectedCommand,
 }
 
 #[derive(Doom)]
@@ -93,10 +109,14 @@ enum CollectorError {
 
 impl Broker {
     pub(in crate::brokers::commit::broker) async fn orchestrate(
+        namespace: Namespace,
         view: View,
         ping_board: PingBoard,
         connector: Arc<SessionConnector>,
         submission: Submission,
+        completion_deadline: Duration,
+        substitutions: Substitutions,
+        receive_timeout: Timeout,
     ) -> Result<BatchCompletion, Top<OrchestrateError>> {
         // Submit a `submit` slave for each replica in `view`
 
@@ -108,22 +128,26 @@ impl Broker {
         let fuse = Fuse::new();
 
         for replica in view.members().values().cloned() {
+            let namespace = namespace.clone();
             let view = view.clone();
             let connector = connector.clone();
             let submission = submission.clone();
             let update_inlet = update_inlet.clone();
+            let receive_timeout = receive_timeout.clone();
 
             let (command_inlet, command_outlet) = mpsc::unbounded_channel();
             command_inlets.insert(replica.identity(), command_inlet);
 
             fuse.spawn(async move {
                 let _ = Broker::submit(
+                    namespace,
                     view,
                     connector,
                     replica,
                     submission,
                     command_outlet,
                     update_inlet,
+                    receive_timeout,
                 )
                 .await;
             });
@@ -133,9 +157,12 @@ impl Broker {
 
         let rankings = ping_board.rankings();
 
+        let plurality = view.reach(&rankings, view.weighted_plurality());
+        let quorum = view.reach(&rankings, view.weighted_quorum());
+
         // Optimistically direct the fastest plurality of slaves to submit `submission`'s signatures
 
-        for replica in &rankings[0..view.plurality()] {
+        for replica in &rankings[0..plurality] {
             let _ = command_inlets
                 .get_mut(replica)
                 .unwrap()
@@ -144,7 +171,8 @@ impl Broker {
 
         // Initialize `WitnessCollector`
 
-        let mut witness_collector = WitnessCollector::new(view.clone(), submission.root());
+        let mut witness_collector =
+            WitnessCollector::new(view.clone(), &namespace, submission.root());
 
         // Wait (or timeout) for the fastest plurality of slaves to produce witness shards
 
@@ -164,7 +192,7 @@ impl Broker {
             .pot(OrchestrateError::WitnessCollectionFailed, here!())?;
 
         if !complete {
-            for replica in &rankings[view.plurality()..view.quorum()] {
+            for replica in &rankings[plurality..quorum] {
                 let _ = command_inlets
                     .get_mut(replica)
                     .unwrap()
@@ -183,20 +211,27 @@ impl Broker {
                 .pot(OrchestrateError::WitnessCollectionFailed, here!())?;
         }
 
-        // Finalize `witness_collector` to obtain witness
+        // Finalize `witness_collector` to obtain witness and initialize a `CompletionCollector`
+        // (shards of the fastest quorum of slaves are awaited first, the rest is kept in reserve)
 
-        let (commit_collector, witness) = witness_collector.finalize();
+        let (failed, witness) = witness_collector.finalize();
 
-        // Direct all slaves to send `witness`
-
-        for command_inlet in command_inlets.values_mut() {
-            let _ = command_inlet.send(Command::SubmitWitness(witness.clone()));
-        }
+        let completion_collector = CompletionCollector::new(
+            view.clone(),
+            &namespace,
+            submission.root(),
+            witness,
+            rankings,
+            failed,
+            completion_deadline,
+            substitutions,
+        );
 
-        // Collect `BatchCommit` from a quorum of slaves
+        // Direct all slaves to send `witness`, then collect `BatchCompletion` from a quorum
+        // of slaves (substituting slow or failed slaves with reserve ones)
 
-        let commit = commit_collector
-            .run(&mut update_outlet)
+        let commit = completion_collector
+            .run(&mut update_outlet, &mut command_inlets)
             .await
             .pot(OrchestrateError::CompletionCollectionFailed, here!())?;
 
@@ -204,12 +239,14 @@ impl Broker {
     }
 
     async fn submit(
+        namespace: Namespace,
         view: View,
         connector: Arc<SessionConnector>,
         replica: KeyCard,
         submission: Arc<Submission>,
         mut command_outlet: CommandOutlet,
         update_inlet: UpdateInlet,
+        receive_timeout: Timeout,
     ) {
         // In order to catch all `Err`s while maintaining `?`-syntax, all
         // operations are executed within the scope of an `async` block
@@ -221,6 +258,15 @@ impl Broker {
                 .await
                 .pot(SubmitError::ConnectionFailed, here!())?;
 
+            // If `submission` is traced, precede the session with its `TraceContext`
+
+            if let Some(trace) = submission.trace() {
+                session
+                    .send(&CommitRequest::Trace(trace))
+                    .await
+                    .pot(SubmitError::ConnectionError, here!())?;
+            }
+
             // Submit `Payload`s (with a `CommitRequest::Batch` request)
 
             session
@@ -253,9 +299,10 @@ impl Broker {
                     // Obtain a witness shard (if requested to do so, first provide `replica` with the
                     // `CommitProof`s it is missing)
 
-                    let response = session
-                        .receive::<CommitResponse>()
+                    let response = receive_timeout
+                        .run(session.receive::<CommitResponse>())
                         .await
+                        .pot(SubmitError::ReceiveTimeout, here!())?
                         .pot(SubmitError::ConnectionError, here!())?;
 
                     let shard = match response {
@@ -290,9 +337,10 @@ impl Broker {
                             // Receive witness shard (a correct `replica` cannot provide any
                             // response other than `WitnessShard`)
 
-                            let response = session
-                                .receive::<CommitResponse>()
+                            let response = receive_timeout
+                                .run(session.receive::<CommitResponse>())
                                 .await
+                                .pot(SubmitError::ReceiveTimeout, here!())?
                                 .pot(SubmitError::ConnectionError, here!())?;
 
                             match response {
@@ -309,7 +357,7 @@ impl Broker {
 
                     // Verify `shard`
 
-                    let statement = WitnessStatement::new(submission.root());
+                    let statement = WitnessStatement::new(&namespace, submission.root());
 
                     shard
                         .verify([&replica], &statement)
@@ -329,9 +377,7 @@ impl Broker {
 
                     match command {
                         Command::SubmitWitness(witness) => witness,
-                        _ => {
-                            panic!("unexpected `Command`");
-                        }
+                        _ => return SubmitError::UnexpectedCommand.fail().spot(here!()),
                     }
                 }
 
@@ -349,16 +395,18 @@ impl Broker {
             // Obtain a `BatchCompletionShard` (if requested to do so, first provide `replica` with the
             // dependencies it is missing)
 
-            let response = session
-                .receive::<CommitResponse>()
+            let response = receive_timeout
+                .run(session.receive::<CommitResponse>())
                 .await
+                .pot(SubmitError::ReceiveTimeout, here!())?
                 .pot(SubmitError::ConnectionError, here!())?;
 
             let shard = match response {
                 CommitResponse::MissingDependencies(missing_ids) => {
                     // Gather the necessary `Completion`s. Dependencies are requested
                     // by `Id`, prompting a binary search on `submission.dependencies()`
-                    // (which was sorted by `Id` by `Broker::prepare`)
+                    // (which was sorted by `Id` by `Broker::prepare`). All `Completion`s
+                    // of each requested `Id` are provided, in order
                     let completions = missing_ids
                         .into_iter()
                         .map(|id| {
@@ -372,7 +420,10 @@ impl Broker {
 
                             Ok(submission.dependencies()[index].1.clone())
                         })
-                        .collect::<Result<Vec<Completion>, Top<SubmitError>>>()?;
+                        .collect::<Result<Vec<Vec<Completion>>, Top<SubmitError>>>()?
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>();
 
                     // Send missing `CommitProof`s
 
@@ -384,9 +435,10 @@ impl Broker {
                     // Receive `BatchCompletionShard` (a correct `replica` cannot provide any
                     // response other than `CompletionShard`)
 
-                    let response = session
-                        .receive::<CommitResponse>()
+                    let response = receive_timeout
+                        .run(session.receive::<CommitResponse>())
                         .await
+                        .pot(SubmitError::ReceiveTimeout, here!())?
                         .pot(SubmitError::ConnectionError, here!())?;
 
                     match response {
@@ -403,7 +455,13 @@ impl Broker {
             // Validate and return `shard`
 
             shard
-                .validate(&view, submission.root(), submission.payloads(), &replica)
+                .validate(
+                    &namespace,
+                    &view,
+                    submission.root(),
+                    submission.payloads(),
+                    &replica,
+                )
                 .pot(SubmitError::InvalidCompletionShard, here!())?;
 
             Ok(shard)
@@ -420,24 +478,23 @@ impl Broker {
 }
 
 impl WitnessCollector {
-    pub fn new(view: View, root: Hash) -> Self {
-        let statement = WitnessStatement::new(root);
+    pub fn new(view: View, namespace: &Namespace, root: Hash) -> Self {
+        let statement = WitnessStatement::new(namespace, root);
         let aggregator = Aggregator::new(view.clone(), statement);
 
         WitnessCollector {
             view,
-            root,
             aggregator,
-            errors: 0,
+            failed: HashSet::new(),
         }
     }
 
     fn succeeded(&self) -> bool {
-        self.aggregator.multiplicity() >= self.view.plurality()
+        self.aggregator.power() >= self.view.weighted_plurality()
     }
 
     fn failed(&self) -> bool {
-        self.errors >= self.view.plurality()
+        self.view.power(&self.failed) >= self.view.weighted_plurality()
     }
 
     async fn progress(&mut self, update_outlet: &mut UpdateOutlet) {
@@ -449,11 +506,13 @@ impl WitnessCollector {
                     let keycard = self.view.members().get(&replica).unwrap();
                     self.aggregator.add(keycard, shard).unwrap();
                 }
-                (_, Update::Error) => {
-                    self.errors += 1;
+                (replica, Update::Error) => {
+                    self.failed.insert(replica);
                 }
-                _ => {
-                    panic!("`WitnessCollector::progress` received an unexpected `Update`");
+                (replica, Update::CompletionShard(_)) => {
+                    // No slave produces a completion shard before being sent a witness:
+                    // an out-of-order `Update` counts as a failure of its replica
+                    self.failed.insert(replica);
                 }
             }
         }
@@ -467,23 +526,41 @@ impl WitnessCollector {
         }
     }
 
-    pub fn finalize(self) -> (CompletionCollector, Certificate) {
-        let completion_collector = CompletionCollector::new(self.view, self.root, self.errors);
+    pub fn finalize(self) -> (HashSet<Identity>, Certificate) {
         let (_, witness) = self.aggregator.finalize();
-
-        (completion_collector, witness)
+        (self.failed, witness)
     }
 }
 
 impl CompletionCollector {
-    fn new(view: View, root: Hash, errors: usize) -> Self {
-        let aggregator = BatchCompletionAggregator::new(view.clone(), root);
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        view: View,
+        namespace: &Namespace,
+        root: Hash,
+        witness: Certificate,
+        rankings: Vec<Identity>,
+        failed: HashSet<Identity>,
+        completion_deadline: Duration,
+        substitutions: Substitutions,
+    ) -> Self {
+        let aggregator = BatchCompletionAggregator::new(view.clone(), namespace, root);
+
+        // Replicas that failed while collecting the witness cannot be awaited
+        let reserve = rankings
+            .into_iter()
+            .filter(|replica| !failed.contains(replica))
+            .collect();
 
         CompletionCollector {
             view,
-            root,
             aggregator,
-            errors,
+            witness,
+            pending: HashSet::new(),
+            reserve,
+            failed,
+            completion_deadline,
+            substitutions,
         }
     }
 
@@ -492,25 +569,94 @@ impl CompletionCollector {
     }
 
     fn failed(&self) -> bool {
-        self.errors >= self.view.plurality()
+        self.view.power(&self.failed) >= self.view.weighted_plurality()
+    }
+
+    // Awaits the `BatchCompletionShard`s of up to `count` reserve slaves, fastest
+    // first (returns the number of slaves awaited)
+    fn engage(&mut self, count: usize) -> usize {
+        let mut engaged = 0;
+
+        while engaged < count {
+            let replica = match self.reserve.pop_front() {
+                Some(replica) => replica,
+                None => break,
+            };
+
+            self.pending.insert(replica);
+            engaged += 1;
+        }
+
+        engaged
+    }
+
+    fn substitute(&mut self, count: usize) {
+        for _ in 0..self.engage(count) {
+            self.substitutions.record_substitution();
+        }
     }
 
     async fn run(
         mut self,
         update_outlet: &mut UpdateOutlet,
+        command_inlets: &mut HashMap<Identity, CommandInlet>,
     ) -> Result<BatchCompletion, Top<CollectorError>> {
+        // Direct all slaves to send `self.witness`: every replica must apply the batch,
+        // even if its `BatchCompletionShard` is not awaited
+
+        for command_inlet in command_inlets.values_mut() {
+            let _ = command_inlet.send(Command::SubmitWitness(self.witness.clone()));
+        }
+
+        // Optimistically await the fastest quorum of slaves (not counting failed slaves,
+        // which are immediately substituted)
+
+        let quorum = self.view.reach(&self.reserve, self.view.weighted_quorum());
+
+        let failures = self.failed.len();
+        let optimistic = quorum.saturating_sub(failures);
+
+        self.engage(optimistic);
+        self.substitute(quorum - optimistic);
+
+        let mut deadline = time::Instant::now() + self.completion_deadline;
+
         while !self.succeeded() && !self.failed() {
-            // A copy of `update_inlet` is held by `orchestrate`.
-            // As a result, `update_outlet.recv()` cannot return `None`.
-            match update_outlet.recv().await.unwrap() {
-                (replica, Update::CompletionShard(shard)) => {
-                    let keycard = self.view.members().get(&replica).unwrap().clone();
-                    self.aggregator.add(&keycard, shard);
-                }
-                (_, Update::Error) => {
-                    self.errors += 1;
+            tokio::select! {
+                // A copy of `update_inlet` is held by `orchestrate`.
+                // As a result, `update_outlet.recv()` cannot return `None`.
+                update = update_outlet.recv() => match update.unwrap() {
+                    (replica, Update::CompletionShard(shard)) => {
+                        // Shards are collected from reserve slaves too, if received
+                        self.pending.remove(&replica);
+                        self.reserve.retain(|reserved| *reserved != replica);
+
+                        let keycard = self.view.members().get(&replica).unwrap().clone();
+                        self.aggregator.add(&keycard, shard);
+                    }
+                    (replica, Update::Error) => {
+                        let awaited = self.pending.remove(&replica);
+                        self.reserve.retain(|reserved| *reserved != replica);
+                        self.failed.insert(replica);
+
+                        // A failed awaited slave is substituted without waiting for the deadline
+                        if awaited {
+                            self.substitute(1);
+                        }
+                    }
+                    (_, Update::WitnessShard(_)) => {}
+                },
+
+                _ = time::sleep_until(deadline) => {
+                    // Each slave still pending is substituted (pending slaves are
+                    // not abandoned: their shards are still collected, if received)
+                    self.substitutions.record_expired_deadline();
+
+                    let stragglers = self.pending.len();
+                    self.substitute(stragglers);
+
+                    deadline = time::Instant::now() + self.completion_deadline;
                 }
-                (_, Update::WitnessShard(_)) => {}
             }
         }
 
@@ -521,3 +667,159 @@ impl CompletionCollector {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::test::InstallGenerator;
+
+    use talk::crypto::primitives::hash;
+
+    fn setup(
+        completion_deadline: Duration,
+    ) -> (
+        InstallGenerator,
+        View,
+        Hash,
+        CompletionCollector,
+        HashMap<Identity, CommandInlet>,
+        Vec<CommandOutlet>,
+    ) {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let root = hash::hash(&42u64).unwrap();
+
+        let statement = WitnessStatement::new(&Namespace::default(), root);
+
+        let witness = Certificate::aggregate_plurality(
+            &view,
+            generator.keychains[..view.plurality()]
+                .iter()
+                .map(|keychain| {
+                    (
+                        keychain.keycard().identity(),
+                        keychain.multisign(&statement).unwrap(),
+                    )
+                }),
+        );
+
+        let rankings = generator
+            .keycards
+            .iter()
+            .map(KeyCard::identity)
+            .collect::<Vec<_>>();
+
+        let collector = CompletionCollector::new(
+            view.clone(),
+            &Namespace::default(),
+            root,
+            witness,
+            rankings.clone(),
+            HashSet::new(),
+            completion_deadline,
+            Substitutions::default(),
+        );
+
+        let (command_inlets, command_outlets) = rankings
+            .into_iter()
+            .map(|replica| {
+                let (command_inlet, command_outlet) = mpsc::unbounded_channel();
+                ((replica, command_inlet), command_outlet)
+            })
+            .unzip();
+
+        (
+            generator,
+            view,
+            root,
+            collector,
+            command_inlets,
+            command_outlets,
+        )
+    }
+
+    fn shard(generator: &InstallGenerator, view: &View, root: Hash, index: usize) -> Update {
+        let keychain = &generator.keychains[index];
+        Update::CompletionShard(BatchCompletionShard::new(
+            keychain,
+            &Namespace::default(),
+            view.identifier(),
+            root,
+            [],
+        ))
+    }
+
+    #[tokio::test]
+    async fn witness_reaches_every_replica() {
+        let (generator, view, root, collector, mut command_inlets, command_outlets) =
+            setup(Duration::from_secs(60));
+
+        let substitutions = collector.substitutions.clone();
+
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+
+        // Only the shards of the fastest quorum are awaited (and provided)
+        for index in 0..view.quorum() {
+            update_inlet
+                .send((
+                    generator.keycards[index].identity(),
+                    shard(&generator, &view, root, index),
+                ))
+                .unwrap();
+        }
+
+        collector
+            .run(&mut update_outlet, &mut command_inlets)
+            .await
+            .unwrap();
+
+        // The slowest replica, whose shard is not awaited, is still directed
+        // to send the witness (and apply the batch)
+        for mut command_outlet in command_outlets {
+            match command_outlet.try_recv().unwrap() {
+                Command::SubmitWitness(_) => {}
+                _ => panic!("expected `Command::SubmitWitness`"),
+            }
+        }
+
+        assert_eq!(substitutions.substituted(), 0);
+    }
+
+    #[tokio::test]
+    async fn reserve_shard_collected() {
+        let (generator, view, root, collector, mut command_inlets, command_outlets) =
+            setup(Duration::from_millis(100));
+
+        let substitutions = collector.substitutions.clone();
+
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+
+        // Replica 2 (awaited) never responds, the slowest replica (in reserve) does
+        for index in [0, 1, 3] {
+            update_inlet
+                .send((
+                    generator.keycards[index].identity(),
+                    shard(&generator, &view, root, index),
+                ))
+                .unwrap();
+        }
+
+        collector
+            .run(&mut update_outlet, &mut command_inlets)
+            .await
+            .unwrap();
+
+        for mut command_outlet in command_outlets {
+            match command_outlet.try_recv().unwrap() {
+                Command::SubmitWitness(_) => {}
+                _ => panic!("expected `Command::SubmitWitness`"),
+            }
+        }
+
+        // The reserve shard is collected as soon as it is received
+        assert_eq!(substitutions.expired_deadlines(), 0);
+        assert_eq!(substitutions.substituted(), 0);
+    }
+}
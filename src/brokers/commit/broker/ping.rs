@@ -30,6 +30,8 @@ impl Broker {
         board: PingBoard,
         connector: Arc<SessionConnector>,
         replica: Identity,
+        ping_interval: Duration,
+        ping_retry_interval: Duration,
     ) {
         loop {
             let start = Instant::now();
@@ -65,7 +67,11 @@ impl Broker {
             let ping = ping.unwrap_or(Duration::MAX);
             board.submit(replica, ping);
 
-            time::sleep(Duration::from_secs(60)).await; // TODO: Add settings
+            if ping < Duration::MAX {
+                time::sleep(ping_interval).await;
+            } else {
+                time::sleep(ping_retry_interval).await;
+            }
         }
     }
 }
@@ -0,0 +1,70 @@
+use crate::{
+    benchmark::MetricsSettings,
+    data::{MemorySettings, SpongeSettings},
+    telemetry::ExporterSettings,
+};
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub(crate) struct BrokerSettings {
+    pub receive_timeout: Duration,
+
+    // Batches are flushed from the brokerage sponge once they reach
+    // `capacity` brokerages, or `timeout` after their first brokerage
+    pub brokerage_sponge_settings: SpongeSettings,
+    // If `Some`, at most `max_in_flight` batches are brokered concurrently:
+    // further brokerages wait in the brokerage sponge
+    pub max_in_flight: Option<usize>,
+
+    // If a replica engaged to provide a `BatchCompletionShard` does not provide
+    // it within `completion_deadline`, the next-fastest replica is engaged in its stead
+    pub completion_deadline: Duration,
+
+    // A `Request` still in the brokerage sponge `request_ttl` after its receipt (or
+    // after its own, shorter `ttl`) is dropped at flush time, and its client is
+    // notified with `BrokerFailure::Expired`
+    pub request_ttl: Duration,
+
+    // Number of `(view, root)` pairs whose validated `BatchCommit`s are cached
+    pub batch_commit_cache_capacity: usize,
+
+    pub ping_interval: Duration,
+    // Replicas whose ping failed (e.g., because they are still starting up)
+    // are pinged again after `ping_retry_interval`
+    pub ping_retry_interval: Duration,
+
+    // If fewer than a quorum of replicas are responsive for longer than
+    // `degradation_grace`, the `Broker` is degraded: pending and new brokerages
+    // fail with `BrokerFailure::Unavailable` until a quorum is responsive again
+    pub degradation_grace: Duration,
+
+    // Watermarks on the (serialized) size of the `Request`s held by the `Broker`:
+    // while shedding, the `Broker` stops accepting connections, and fails the
+    // `Request`s it receives with `BrokerFailure::Busy`
+    pub memory: MemorySettings,
+
+    // Brokered commits are accounted for in the `Broker`'s `Metrics`
+    pub metrics: MetricsSettings,
+    // Serves the `Broker`'s `Registry` to Prometheus
+    pub exporter: ExporterSettings,
+}
+
+impl Default for BrokerSettings {
+    fn default() -> Self {
+        BrokerSettings {
+            receive_timeout: Duration::from_secs(10),
+            brokerage_sponge_settings: SpongeSettings::default(),
+            max_in_flight: None,
+            completion_deadline: Duration::from_secs(1),
+            request_ttl: Duration::from_secs(60),
+            batch_commit_cache_capacity: 1024,
+            ping_interval: Duration::from_secs(60),
+            ping_retry_interval: Duration::from_secs(1),
+            degradation_grace: Duration::from_secs(10),
+            memory: MemorySettings::default(),
+            metrics: MetricsSettings::default(),
+            exporter: ExporterSettings::default(),
+        }
+    }
+}
@@ -2,23 +2,28 @@ use crate::{
     account::Id,
     brokers::commit::{BrokerFailure, Request},
     commit::{Commit, CommitProof, Completion, CompletionProof, Payload},
+    telemetry::TraceContext,
 };
 
-use tokio::sync::oneshot::Sender;
+use tokio::{sync::oneshot::Sender, time::Instant};
 
 type CompletionInlet = Sender<Result<CompletionProof, BrokerFailure>>;
 
 pub(in crate::brokers::commit) struct Brokerage {
     pub request: Request,
-    pub completion_inlet: CompletionInlet,
+    // One for each client awaiting `request` (clients that reconnect and
+    // resubmit `request` are merged into the same `Brokerage`, see `Broker::prepare`)
+    pub completion_inlets: Vec<CompletionInlet>,
+    pub expiry: Instant,
 }
 
 pub(in crate::brokers::commit) struct UnzippedBrokerages {
     pub payloads: Vec<Payload>,
     pub commit_proofs: Vec<(Id, CommitProof)>,
-    pub dependencies: Vec<(Id, Completion)>,
+    pub dependencies: Vec<(Id, Vec<Completion>)>,
+    pub traces: Vec<TraceContext>,
 
-    pub completion_inlets: Vec<CompletionInlet>,
+    pub completion_inlets: Vec<Vec<CompletionInlet>>,
 }
 
 impl Brokerage {
@@ -26,13 +31,16 @@ impl Brokerage {
         let mut payloads = Vec::new();
         let mut commit_proofs = Vec::new();
         let mut dependencies = Vec::new();
+        let mut traces = Vec::new();
 
         let mut completion_inlets = Vec::new();
 
         for brokerage in brokerages {
             let Request {
                 commit: Commit { proof, payload },
-                dependency,
+                dependencies: completions,
+                trace,
+                ..
             } = brokerage.request;
 
             let id = payload.id();
@@ -40,17 +48,20 @@ impl Brokerage {
             payloads.push(payload);
             commit_proofs.push((id, proof));
 
-            if let Some(dependency) = dependency {
-                dependencies.push((id, dependency));
+            if !completions.is_empty() {
+                dependencies.push((id, completions));
             }
 
-            completion_inlets.push(brokerage.completion_inlet);
+            traces.extend(trace);
+
+            completion_inlets.push(brokerage.completion_inlets);
         }
 
         UnzippedBrokerages {
             payloads,
             commit_proofs,
             dependencies,
+            traces,
 
             completion_inlets,
         }
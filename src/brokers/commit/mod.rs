@@ -1,8 +1,10 @@
 mod broker;
 mod broker_failure;
+mod broker_settings;
 mod brokerage;
 mod request;
 mod submission;
+mod substitutions;
 
 use brokerage::{Brokerage, UnzippedBrokerages};
 use submission::Submission;
@@ -11,4 +13,8 @@ use submission::Submission;
 pub(crate) use broker::Broker;
 
 pub(crate) use broker_failure::BrokerFailure;
+pub(crate) use broker_settings::BrokerSettings;
 pub(crate) use request::Request;
+
+#[allow(unused_imports)]
+pub(crate) use substitutions::Substitutions;
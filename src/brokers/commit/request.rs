@@ -1,23 +1,42 @@
 use crate::{
     account::Id,
     commit::{Commit, Completion},
+    data::enveloped,
     discovery::Client,
+    prepare::BatchCommitCache,
+    processing::Namespace,
+    telemetry::TraceContext,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
 use serde::{Deserialize, Serialize};
 
+use std::time::Duration;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) struct Request {
+    // Must match the `Broker`'s (see `BrokerFailure::ForeignNamespace`)
+    pub namespace: Namespace,
     pub commit: Commit,
-    pub dependency: Option<Completion>,
+    // One `Completion` for each of `commit`'s dependencies, in order
+    pub dependencies: Vec<Completion>,
+    // If the `Request` is not submitted within `ttl` (capped by the `Broker`'s
+    // `request_ttl`), it is dropped: its client is assumed to have given up
+    pub ttl: Option<Duration>,
+    // If `Some`, the brokerage of `self` is traced within the client's trace
+    pub trace: Option<TraceContext>,
 }
 
+enveloped!(Request);
+
 #[derive(Doom)]
 pub(crate) enum RequestError {
     #[doom(description("`Commit` invalid"))]
     CommitInvalid,
+    #[doom(description("Operation exceeds its `KeyDelegation`'s limits"))]
+    DelegationExceeded,
     #[doom(description("Dependency mismatch"))]
     DependencyMismatch,
     #[doom(description("Dependency invalid"))]
@@ -25,35 +44,64 @@ pub(crate) enum RequestError {
 }
 
 impl Request {
-    pub fn new(commit: Commit, dependency: Option<Completion>) -> Self {
-        Request { commit, dependency }
+    pub fn new(commit: Commit, dependencies: Vec<Completion>) -> Self {
+        Request {
+            namespace: Namespace::default(),
+            commit,
+            dependencies,
+            ttl: None,
+            trace: None,
+        }
+    }
+
+    pub fn with_namespace(mut self, namespace: Namespace) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_trace(mut self, trace: TraceContext) -> Self {
+        self.trace = Some(trace);
+        self
     }
 
     pub fn id(&self) -> Id {
         self.commit.payload().id()
     }
 
-    pub fn validate(&self, discovery: &Client) -> Result<(), Top<RequestError>> {
+    // Clients retrying a `Request` resubmit the same `Commit`: `cache`
+    // spares the re-verification of its `BatchCommit`
+    pub fn validate(
+        &self,
+        discovery: &Client,
+        cache: &BatchCommitCache,
+    ) -> Result<(), Top<RequestError>> {
         self.commit
-            .validate(discovery)
+            .validate_cached(discovery, cache)
             .pot(RequestError::CommitInvalid, here!())?;
 
-        match (self.commit.operation().dependency(), &self.dependency) {
-            (Some(dependency), Some(completion)) => {
-                if completion.entry() != dependency {
-                    return RequestError::DependencyMismatch.fail().spot(here!());
-                }
+        if !self.commit.payload().authorized() {
+            return RequestError::DelegationExceeded.fail().spot(here!());
+        }
 
-                completion
-                    .validate(discovery)
-                    .pot(RequestError::DependencyInvalid, here!())?;
-            }
+        let dependencies = self.commit.operation().dependencies();
 
-            (None, None) => {}
+        if dependencies.len() != self.dependencies.len() {
+            return RequestError::DependencyMismatch.fail().spot(here!());
+        }
 
-            _ => {
+        for (dependency, completion) in dependencies.into_iter().zip(self.dependencies.iter()) {
+            if completion.entry() != dependency {
                 return RequestError::DependencyMismatch.fail().spot(here!());
             }
+
+            completion
+                .validate(discovery)
+                .pot(RequestError::DependencyInvalid, here!())?;
         }
 
         Ok(())
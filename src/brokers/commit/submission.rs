@@ -2,6 +2,7 @@ use crate::{
     account::Id,
     commit::{CommitProof, Completion, Payload},
     processing::messages::CommitRequest,
+    telemetry::TraceContext,
 };
 
 use talk::crypto::primitives::hash::Hash;
@@ -11,7 +12,8 @@ use zebra::vector::Vector;
 pub(in crate::brokers::commit) struct Submission {
     root: Hash,
     commit_proofs: Vec<(Id, CommitProof)>,
-    dependencies: Vec<(Id, Completion)>,
+    dependencies: Vec<(Id, Vec<Completion>)>,
+    trace: Option<TraceContext>,
     pub requests: Requests,
 }
 
@@ -23,18 +25,29 @@ impl Submission {
     pub fn new(
         payloads: Vector<Payload>,
         commit_proofs: Vec<(Id, CommitProof)>,
-        dependencies: Vec<(Id, Completion)>,
+        dependencies: Vec<(Id, Vec<Completion>)>,
     ) -> Self {
         Submission {
             root: payloads.root(),
             commit_proofs,
             dependencies,
+            trace: None,
             requests: Requests {
                 batch: CommitRequest::Batch(payloads),
             },
         }
     }
 
+    // If `Some`, each replica session is preceded by a `CommitRequest::Trace`
+    pub fn with_trace(mut self, trace: TraceContext) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn trace(&self) -> Option<TraceContext> {
+        self.trace
+    }
+
     pub fn root(&self) -> Hash {
         self.root
     }
@@ -50,7 +63,7 @@ impl Submission {
         self.commit_proofs.as_slice()
     }
 
-    pub fn dependencies(&self) -> &[(Id, Completion)] {
+    pub fn dependencies(&self) -> &[(Id, Vec<Completion>)] {
         self.dependencies.as_slice()
     }
 }
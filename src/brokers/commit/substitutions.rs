@@ -0,0 +1,35 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+// Counts the replicas substituted by a commit `Broker` while collecting
+// `BatchCompletionShard`s. A replica is substituted when its shard is not
+// received by the completion deadline, or when it fails. Frequent substitutions
+// indicate stale ping rankings, or persistently slow replicas. All clones of a
+// `Substitutions` share the same counts.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Substitutions {
+    expired_deadlines: Arc<AtomicU64>,
+    substituted: Arc<AtomicU64>,
+}
+
+impl Substitutions {
+    // Number of completion deadlines that expired before a `BatchCompletion` was collected
+    pub fn expired_deadlines(&self) -> u64 {
+        self.expired_deadlines.load(Ordering::Relaxed)
+    }
+
+    // Number of replicas engaged to replace a slow or failed replica
+    pub fn substituted(&self) -> u64 {
+        self.substituted.load(Ordering::Relaxed)
+    }
+
+    pub(in crate::brokers::commit) fn record_expired_deadline(&self) {
+        self.expired_deadlines.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::brokers::commit) fn record_substitution(&self) {
+        self.substituted.fetch_add(1, Ordering::Relaxed);
+    }
+}
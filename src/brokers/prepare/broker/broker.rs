@@ -1,12 +1,15 @@
 use crate::{
+    benchmark::{LatencyBudget, Stage},
     brokers::prepare::{
         broker::{Brokerage, Reduction},
         broker_settings::BrokerTaskSettings,
-        Broker, BrokerFailure, Inclusion, Submission, UnzippedBrokerages,
+        Broker, BrokerFailure, DryRunReport, Inclusion, Submission, UnzippedBrokerages,
     },
     data::{PingBoard, Sponge, SpongeSettings},
     discovery::Client,
+    prepare::{BatchCommit, BatchDefect, Delegated},
     processing::messages::PrepareRequest,
+    telemetry::Span,
     view::View,
 };
 
@@ -14,14 +17,24 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use futures::stream::{FuturesUnordered, StreamExt};
 
-use std::{iter, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashSet},
+    iter,
+    sync::Arc,
+    time::Instant,
+};
 
 use talk::{
-    crypto::{primitives::multi::Signature as MultiSignature, Identity},
+    crypto::{
+        primitives::{multi::Signature as MultiSignature, sign::Signature},
+        Identity,
+    },
     net::SessionConnector,
 };
 
-use zebra::vector::Vector;
+use tokio::task;
+
+use zebra::vector::{Proof, Vector};
 
 #[derive(Doom)]
 enum PublishError {
@@ -40,55 +53,82 @@ impl Broker {
         brokerages: Vec<Brokerage>,
         settings: BrokerTaskSettings,
     ) {
+        let flushed = Instant::now();
+
+        // A degraded `Broker` cannot gather a quorum of replicas: fail all
+        // brokerages at once, rather than leaving their clients hanging
+
+        if settings.quorum_monitor.is_degraded() {
+            settings
+                .metrics
+                .failures("prepare", "unavailable", brokerages.len() as u64);
+
+            for brokerage in brokerages {
+                brokerage.fail(BrokerFailure::Unavailable);
+            }
+
+            return;
+        }
+
         // Unzip `brokerages` into its components
 
         let UnzippedBrokerages {
             assignments,
             prepares,
             signatures,
+            delegations,
+            traces,
+            arrivals,
+            mut journal_entries,
             reduction_inlets,
             commit_inlets,
+            budget_inlets,
         } = Brokerage::unzip(brokerages);
 
-        // Initialize `Vec<Option<_>>` of individual signatures
+        // If any client supplied a trace, the batch is traced by a span
+        // linked to all client traces
 
-        let mut individual_signatures = signatures
-            .into_iter()
-            .map(|signature| Some(signature))
-            .collect::<Vec<_>>();
+        let mut span = if traces.is_empty() {
+            None
+        } else {
+            let mut span = Span::linked("prepare.batch", &traces);
+            span.attribute("batch_size", prepares.len() as i64);
+            Some(span)
+        };
+
+        // Initialize `Vec<Option<_>>` of individual signatures (`signatures` is
+        // retained, should the batch be retried without some of its elements)
+
+        let mut individual_signatures = signatures.iter().cloned().map(Some).collect::<Vec<_>>();
 
-        // Wrap `prepares` into a `Vector`, generate an `Inclusion`
-        // for each element of `prepares`
+        // Wrap `prepares` into a `Vector`
 
         let prepares = Vector::new(prepares).unwrap();
-        let inclusions = Inclusion::batch(&prepares);
 
         // Initialize reduction sponge
 
         // The capacity of `reduction_sponge` is expressed by `settings.reduction_threshold`
-        // as a fraction of `inclusions.len()`: `reduction_sponge` flushes as soon as
+        // as a fraction of `prepares.len()`: `reduction_sponge` flushes as soon as
         // a `settings.reduction_threshold`-th of the reduction shards are collected.
         let reduction_sponge = Arc::new(Sponge::new(SpongeSettings {
-            capacity: ((inclusions.len() as f64) * settings.reduction_threshold) as usize,
+            capacity: ((prepares.len() as f64) * settings.reduction_threshold) as usize,
             timeout: settings.reduction_timeout,
         }));
 
-        // Build vector of `Reduction`s
+        // Generate an `Inclusion` for each element of `prepares`, and send the
+        // corresponding `Reduction` to the appropriate `serve` task (`Inclusion`s
+        // are generated one at a time, and never collected)
 
-        let reductions = inclusions
-            .into_iter()
+        let reductions = Inclusion::batch(&prepares)
             .zip(iter::repeat(reduction_sponge.clone()))
             .enumerate()
             .map(|(index, (inclusion, reduction_sponge))| Reduction {
                 index,
                 inclusion,
                 reduction_sponge,
-            })
-            .collect::<Vec<_>>();
-
-        // Send each element of `reductions` to the appropriate `serve` task
+            });
 
-        for (reduction, reduction_inlet) in reductions.into_iter().zip(reduction_inlets) {
+        for (reduction, reduction_inlet) in reductions.zip(reduction_inlets) {
             let _ = reduction_inlet.send(Ok(reduction));
         }
 
@@ -96,6 +136,11 @@ impl Broker {
 
         let reduction_shards = reduction_sponge.flush().await;
 
+        // Stages shared by all brokerages in the batch are recorded in `budget`
+
+        let mut budget = LatencyBudget::new();
+        budget.record(Stage::Reduction, flushed.elapsed());
+
         // Aggregate reduction signature
 
         // Each element of `reduction_shards` has been previously verified, and can be
@@ -107,6 +152,58 @@ impl Broker {
             }))
             .unwrap();
 
+        // If dry-running, report the batch that would have been submitted, then
+        // fail all clients: no replica is contacted, and nothing is journaled
+
+        if let Some(dry_run) = &settings.dry_run {
+            let submission = Submission::new(
+                assignments,
+                prepares,
+                reduction_signature,
+                individual_signatures,
+                delegations,
+            );
+
+            let submission = Broker::compress(submission, settings.compression_threshold).await;
+
+            dry_run.record(DryRunReport::new(&submission));
+
+            for entry in journal_entries {
+                entry.complete();
+            }
+
+            for commit_inlet in commit_inlets {
+                let _ = commit_inlet.send(Err(BrokerFailure::DryRun));
+            }
+
+            return;
+        }
+
+        // If journaling is enabled, persist the batch until brokered (a journaling
+        // failure only prevents recovery, and does not fail the brokerage). Once
+        // the batch is persisted, its pending requests are covered by its entry.
+
+        let entry = match &settings.journal {
+            Some(journal) => journal
+                .record(
+                    &view,
+                    &assignments,
+                    &prepares,
+                    &reduction_signature,
+                    &individual_signatures,
+                    &delegations,
+                )
+                .await
+                .ok(),
+            None => None,
+        };
+
+        if entry.is_some() {
+            for pending in journal_entries.drain(..) {
+                pending.complete();
+            }
+        }
+
         // Prepare `Submission`
 
         let submission = Submission::new(
@@ -114,41 +211,302 @@ impl Broker {
             prepares,
             reduction_signature,
             individual_signatures,
+            delegations.clone(),
         );
 
-        // Orchestrate submission of `submission`
+        let submission = match &span {
+            Some(span) => submission.with_trace(span.context()),
+            None => submission,
+        };
+
+        let submission =
+            Arc::new(Broker::compress(submission, settings.compression_threshold).await);
 
-        let commit = Broker::orchestrate(
-            discovery,
+        // Orchestrate submission of `submission`, unless the `Broker` is degraded in the meantime
+
+        let metrics = settings.metrics.clone();
+        let registry = settings.registry.clone();
+
+        let mut defects = Vec::new();
+
+        let mut commit = Broker::attempt(
+            discovery.clone(),
             view.clone(),
-            ping_board,
+            ping_board.clone(),
             connector.clone(),
-            submission,
-            settings,
+            submission.clone(),
+            settings.clone(),
+            &mut budget,
+            &mut defects,
         )
-        .await
-        .map_err(|_| BrokerFailure::Error);
+        .await;
+
+        // If replicas rejected `submission` as malformed, evict (and fail) its defective
+        // elements, then retry the remainder. Each remaining `serve` task is sent the `Proof`
+        // of inclusion of its element in the retried batch, along with the retried `commit`.
+
+        let mut evicted = vec![false; commit_inlets.len()];
+        let mut proofs = vec![None; commit_inlets.len()];
+
+        if !defects.is_empty() {
+            let retry = Broker::evict(submission.as_ref(), &signatures, &delegations, &defects);
+
+            for defect in defects.iter().filter(|defect| defect.reason.in_element()) {
+                if let Some(evicted) = evicted.get_mut(defect.index) {
+                    *evicted = true;
+                }
+            }
+
+            log::warn!(
+                "Evicting {} defective prepares from a malformed batch of {}",
+                evicted.iter().filter(|evicted| **evicted).count(),
+                commit_inlets.len()
+            );
+
+            if let Some((remaining, retry)) = retry {
+                for (index, proof) in remaining {
+                    proofs[index] = Some(proof);
+                }
+
+                let retry = match &span {
+                    Some(span) => retry.with_trace(span.context()),
+                    None => retry,
+                };
+
+                let retry = Broker::compress(retry, settings.compression_threshold).await;
+
+                commit = Broker::attempt(
+                    discovery,
+                    view.clone(),
+                    ping_board,
+                    connector.clone(),
+                    Arc::new(retry),
+                    settings,
+                    &mut budget,
+                    &mut Vec::new(),
+                )
+                .await;
+            }
+        }
 
-        // Send a copy of `commit` to each `serve` task (note that `commit` is
-        // a `Result<BatchCommit, Failure>`)
+        let brokered = evicted.iter().filter(|evicted| !**evicted).count();
 
-        for commit_inlet in commit_inlets {
-            let _ = commit_inlet.send(commit.clone());
+        if commit.is_err() {
+            log::warn!("Failed to broker a batch of {} prepares", brokered);
+        }
+
+        if let Some(span) = span.as_mut() {
+            span.event(if commit.is_ok() {
+                "committed"
+            } else {
+                "failed"
+            });
+        }
+
+        if brokered < evicted.len() {
+            metrics.failures(
+                "prepare",
+                BrokerFailure::Error.kind(),
+                (evicted.len() - brokered) as u64,
+            );
+        }
+
+        match &commit {
+            Ok(_) => {
+                for (arrival, _) in arrivals
+                    .iter()
+                    .zip(evicted.iter())
+                    .filter(|(_, evicted)| !**evicted)
+                {
+                    metrics.success("prepare", arrival.elapsed());
+                }
+
+                registry
+                    .counter("carbon_broker_batches_total", "Batches brokered")
+                    .increment();
+
+                registry
+                    .histogram("carbon_broker_batch_seconds", "Latency of brokered batches")
+                    .observe(flushed.elapsed());
+            }
+            Err(failure) => {
+                metrics.failures("prepare", failure.kind(), brokered as u64);
+            }
+        }
+
+        // If `commit` is `Ok`, send each `serve` task its client's `LatencyBudget`
+        // before `commit`, so that `serve` never waits on `budget_outlet`
+
+        if commit.is_ok() {
+            for ((arrival, budget_inlet), _) in arrivals
+                .into_iter()
+                .zip(budget_inlets)
+                .zip(evicted.iter())
+                .filter(|(_, evicted)| !**evicted)
+            {
+                let mut client_budget = LatencyBudget::new();
+                client_budget.record(Stage::SpongeWait, flushed.duration_since(arrival));
+
+                for (stage, elapsed) in budget.stages() {
+                    client_budget.record(*stage, *elapsed);
+                }
+
+                let _ = budget_inlet.send(client_budget);
+            }
+        }
+
+        // Send a copy of `commit` to each `serve` task (note that `commit` is
+        // a `Result<BatchCommit, Failure>`), along with its element's `Proof`
+        // of inclusion in the retried batch (if any). Evicted elements fail.
+
+        for ((commit_inlet, evicted), proof) in commit_inlets.into_iter().zip(evicted).zip(proofs) {
+            let _ = if evicted {
+                commit_inlet.send(Err(BrokerFailure::Error))
+            } else {
+                commit_inlet.send(commit.clone().map(|commit| (commit, proof)))
+            };
         }
 
         // If `commit` is `Ok`, publish `BatchCommit` to all replicas
 
         if let Ok(commit) = commit {
-            let request = PrepareRequest::Commit(commit);
-
-            view.members()
-                .keys()
-                .copied()
-                .map(|replica| Broker::publish(connector.as_ref(), &request, replica))
-                .collect::<FuturesUnordered<_>>()
-                .collect::<Vec<_>>()
-                .await;
+            Broker::publish_commit(&view, connector.as_ref(), commit).await;
+        }
+
+        // The brokerage is over: its journal entry (if any) is no longer necessary
+
+        if let Some(entry) = entry {
+            entry.complete();
+        }
+
+        for pending in journal_entries {
+            pending.complete();
+        }
+    }
+
+    // Orchestrates the submission of `submission`, unless the `Broker` is degraded in the meantime
+    #[allow(clippy::too_many_arguments)]
+    async fn attempt(
+        discovery: Arc<Client>,
+        view: View,
+        ping_board: PingBoard,
+        connector: Arc<SessionConnector>,
+        submission: Arc<Submission>,
+        settings: BrokerTaskSettings,
+        budget: &mut LatencyBudget,
+        defects: &mut Vec<BatchDefect>,
+    ) -> Result<BatchCommit, BrokerFailure> {
+        let quorum_monitor = settings.quorum_monitor.clone();
+
+        let orchestrate = Broker::orchestrate(
+            discovery, view, ping_board, connector, submission, settings, budget, defects,
+        );
+
+        tokio::select! {
+            commit = orchestrate => commit.map_err(|_| BrokerFailure::Error),
+            _ = quorum_monitor.await_degraded() => Err(BrokerFailure::Unavailable),
+        }
+    }
+
+    // Compression is CPU-bound: it runs off the asynchronous runtime
+    pub(in crate::brokers::prepare::broker) async fn compress(
+        submission: Submission,
+        threshold: Option<usize>,
+    ) -> Submission {
+        match threshold {
+            Some(threshold) => task::spawn_blocking(move || submission.compress(threshold))
+                .await
+                .unwrap(),
+            None => submission,
+        }
+    }
+
+    // Rebuilds `submission` without the elements located by `defects`, returning the
+    // index (in `submission`) of each remaining element along with its `Proof` of
+    // inclusion in the retried batch, and the retried `Submission` (`None` if no element
+    // remains). Clients of the remaining elements already traded their reduction shards
+    // for `submission`: in the retried batch, each element is covered by its individual
+    // signature (out of `signatures`)
+    fn evict(
+        submission: &Submission,
+        signatures: &[Signature],
+        delegations: &BTreeMap<usize, Delegated>,
+        defects: &[BatchDefect],
+    ) -> Option<(Vec<(usize, Proof)>, Submission)> {
+        let evicted = defects
+            .iter()
+            .filter(|defect| defect.reason.in_element())
+            .map(|defect| defect.index)
+            .collect::<HashSet<_>>();
+
+        let remaining = (0..submission.prepares().len())
+            .filter(|index| !evicted.contains(index))
+            .collect::<Vec<_>>();
+
+        if remaining.is_empty() {
+            return None;
         }
+
+        let assignments = remaining
+            .iter()
+            .map(|index| submission.assignments()[*index].clone())
+            .collect::<Vec<_>>();
+
+        let prepares = remaining
+            .iter()
+            .map(|index| submission.prepares()[*index].clone())
+            .collect::<Vec<_>>();
+
+        let individual_signatures = remaining
+            .iter()
+            .map(|index| Some(signatures[*index].clone()))
+            .collect::<Vec<_>>();
+
+        let delegations = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(position, index)| {
+                delegations
+                    .get(index)
+                    .map(|delegated| (position, delegated.clone()))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let prepares = Vector::new(prepares).unwrap();
+
+        let remaining = remaining
+            .into_iter()
+            .zip(Inclusion::batch(&prepares).map(|inclusion| inclusion.proof))
+            .collect::<Vec<_>>();
+
+        // No element relies on the reduction signature
+        let reduction_signature = MultiSignature::aggregate(iter::empty()).unwrap();
+
+        let submission = Submission::new(
+            assignments,
+            prepares,
+            reduction_signature,
+            individual_signatures,
+            delegations,
+        );
+
+        Some((remaining, submission))
+    }
+
+    pub(in crate::brokers::prepare::broker) async fn publish_commit(
+        view: &View,
+        connector: &SessionConnector,
+        commit: BatchCommit,
+    ) {
+        let request = PrepareRequest::Commit(commit);
+
+        view.members()
+            .keys()
+            .copied()
+            .map(|replica| Broker::publish(connector, &request, replica))
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>()
+            .await;
     }
 
     async fn publish(
@@ -171,3 +529,135 @@ impl Broker {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::{Entry, Id, KeyDelegation},
+        prepare::{DefectReason, Prepare},
+        processing::Namespace,
+        signup::test::assignment,
+        view::test::InstallGenerator,
+    };
+
+    use talk::crypto::{primitives::hash, KeyChain};
+
+    // Builds a `Submission` of one individually signed `Prepare` for each of `ids` (in order),
+    // returning it along with its individual signatures
+    fn signed(
+        generator: &InstallGenerator,
+        view: &View,
+        ids: &[Id],
+        delegations: &BTreeMap<usize, Delegated>,
+    ) -> (Submission, Vec<Signature>) {
+        let clients = ids.iter().map(|_| KeyChain::random()).collect::<Vec<_>>();
+
+        let assignments = clients
+            .iter()
+            .zip(ids.iter().copied())
+            .map(|(client, id)| {
+                assignment(
+                    &generator.keychains,
+                    view,
+                    &Namespace::default(),
+                    client,
+                    id,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let prepares = ids
+            .iter()
+            .map(|id| Prepare::new(Entry { id: *id, height: 1 }, hash::hash(id).unwrap()))
+            .collect::<Vec<_>>();
+
+        let signatures = clients
+            .iter()
+            .zip(prepares.iter())
+            .map(|(client, prepare)| client.sign(prepare).unwrap())
+            .collect::<Vec<_>>();
+
+        let reduction_signature = clients[0].multisign(&prepares[0]).unwrap();
+
+        let submission = Submission::new(
+            assignments,
+            Vector::new(prepares).unwrap(),
+            reduction_signature,
+            signatures.iter().cloned().map(Some).collect(),
+            delegations.clone(),
+        );
+
+        (submission, signatures)
+    }
+
+    #[test]
+    fn evict() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        // Element 2 duplicates the `Id` of element 1, element 3 is signed by a delegate key
+        let delegated = Delegated::new(
+            KeyDelegation::new(&KeyChain::random(), 2, &KeyChain::random(), 100, 10),
+            hash::hash(&2u64).unwrap(),
+        );
+
+        let delegations = iter::once((3, delegated)).collect::<BTreeMap<_, _>>();
+        let (submission, signatures) = signed(&generator, &view, &[0, 1, 1, 2], &delegations);
+
+        let defects = submission.defects();
+
+        assert_eq!(
+            defects,
+            vec![BatchDefect {
+                index: 2,
+                reason: DefectReason::DuplicateId,
+            }]
+        );
+
+        let (remaining, retry) =
+            Broker::evict(&submission, &signatures, &delegations, &defects).unwrap();
+
+        // The retried batch is well-formed, and covered by individual signatures only
+        assert!(retry.defects().is_empty());
+        assert_eq!(retry.individual_signatures(), 3);
+
+        assert_eq!(
+            retry.prepares().iter().map(Prepare::id).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 3]
+        );
+
+        // Each remaining element is proven to be included in the retried batch
+        for ((index, proof), prepare) in remaining.iter().zip(retry.prepares()) {
+            assert_eq!(prepare.id(), submission.prepares()[*index].id());
+            assert!(proof.verify(retry.root(), prepare).is_ok());
+        }
+
+        // Delegations follow their elements to the retried batch
+        match retry.requests.signatures() {
+            PrepareRequest::DelegatedSignatures(_, _, delegations) => {
+                assert_eq!(delegations.keys().copied().collect::<Vec<_>>(), vec![2]);
+            }
+            _ => panic!("expected `PrepareRequest::DelegatedSignatures`"),
+        }
+
+        // If all elements are evicted, nothing is retried
+        let (submission, signatures) = signed(&generator, &view, &[0], &BTreeMap::new());
+
+        let defects = vec![BatchDefect {
+            index: 0,
+            reason: DefectReason::SignatureMissing,
+        }];
+
+        assert!(Broker::evict(&submission, &signatures, &BTreeMap::new(), &defects).is_none());
+    }
+}
@@ -1,9 +1,12 @@
 use crate::{
     brokers::prepare::{
-        broker::Brokerage, broker_settings::BrokerTaskSettings, Broker, BrokerFailure,
+        broker::Brokerage,
+        broker_settings::{BrokerTaskSettings, FlushTaskSettings},
+        Broker, BrokerFailure, FairQueue, Normalization,
     },
     data::{PingBoard, Sponge},
     discovery::Client,
+    telemetry::Badge,
     view::View,
 };
 
@@ -11,6 +14,8 @@ use std::sync::Arc;
 
 use talk::{net::SessionConnector, sync::fuse::Fuse};
 
+use tokio::{sync::Semaphore, time};
+
 impl Broker {
     pub(in crate::brokers::prepare::broker) async fn flush(
         discovery: Arc<Client>,
@@ -18,15 +23,48 @@ impl Broker {
         brokerage_sponge: Arc<Sponge<Brokerage>>,
         ping_board: PingBoard,
         connector: Arc<SessionConnector>,
+        flush_settings: FlushTaskSettings,
         settings: BrokerTaskSettings,
     ) {
+        let FlushTaskSettings {
+            brokerage_sponge_settings,
+            client_quota,
+            client_weights,
+            max_in_flight,
+        } = flush_settings;
+
+        let mut queue = FairQueue::new(client_quota, client_weights);
+
+        let in_flight = max_in_flight.map(|max_in_flight| Arc::new(Semaphore::new(max_in_flight)));
         let fuse = Fuse::new();
 
         loop {
-            // Remark: `brokerage_sponge.flush()` always returns a non-empty
-            // `Vec<Brokerage>`. Because `Broker::prepare` only filters `Id`
-            // duplicates, it never produces an empty output on a non-empty input.
-            let brokerages = Broker::prepare(brokerage_sponge.flush().await);
+            // A slot is secured before flushing, so that brokerages keep
+            // accumulating in `brokerage_sponge` while all slots are taken
+            let slot = match in_flight.as_ref() {
+                // `in_flight` is never closed
+                Some(in_flight) => Some(in_flight.clone().acquire_owned().await.unwrap()),
+                None => None,
+            };
+
+            // If the previous batch left brokerages behind, they are batched
+            // (along with any new brokerage) after the sponge's timeout,
+            // whether or not `brokerage_sponge` fills up in the meantime
+            let flushed = if queue.is_empty() {
+                brokerage_sponge.flush().await
+            } else {
+                time::sleep(brokerage_sponge_settings.timeout).await;
+                brokerage_sponge.drain()
+            };
+
+            for brokerage in flushed {
+                queue.push(brokerage.client, brokerage);
+            }
+
+            // Remark: because `queue` is non-empty, `queue.batch()` returns a non-empty
+            // `Vec<Brokerage>`. Because `Broker::prepare` only filters `Id` duplicates,
+            // it never produces an empty output on a non-empty input.
+            let brokerages = Broker::prepare(queue.batch(brokerage_sponge_settings.capacity));
 
             let discovery = discovery.clone();
             let view = view.clone();
@@ -34,37 +72,28 @@ impl Broker {
             let connector = connector.clone();
             let settings = settings.clone();
 
-            fuse.spawn(async move {
+            fuse.spawn(Badge::inherit(async move {
                 Broker::broker(discovery, view, ping_board, connector, brokerages, settings).await;
-            });
+                drop(slot);
+            }));
         }
     }
 
-    fn prepare(mut brokerages: Vec<Brokerage>) -> Vec<Brokerage> {
-        // Sort `brokerages` by requestor
-
-        brokerages.sort_by_key(|brokerage| brokerage.request.id());
-
-        // Deduplicate and fail `brokerages` by requestor
-
-        // The following implementation does not use `Vec::dedup_*` because
-        // duplicate `Brokerage`s must be failed explicitly. If an element
-        // of `brokerages` duplicates the previous, its `reduction_inlet` is
-        // consumed to `send` a `BrokerFailure`, to the appropriate `serve`
-        // task, and the element is filtered out of `prepare`'s return.
-        let mut previous = None;
-
-        brokerages
-            .into_iter()
-            .filter_map(|brokerage| {
-                if Some(brokerage.request.id()) == previous {
-                    let _ = brokerage.reduction_inlet.send(Err(BrokerFailure::Throttle));
-                    None
-                } else {
-                    previous = Some(brokerage.request.id());
-                    Some(brokerage)
-                }
-            })
-            .collect()
+    fn prepare(brokerages: Vec<Brokerage>) -> Vec<Brokerage> {
+        // Sort and deduplicate `brokerages` by requestor
+
+        let Normalization {
+            batch, duplicates, ..
+        } = Normalization::new(brokerages, |brokerage| brokerage.request.id());
+
+        // Duplicate `Brokerage`s must be failed explicitly: the `reduction_inlet`
+        // of each duplicate is consumed to `send` a `BrokerFailure` to the
+        // appropriate `serve` task. Each `Brokerage` carries its own inlets, so
+        // no further index mapping is needed to route responses to clients.
+        for (_, brokerage) in duplicates {
+            brokerage.fail(BrokerFailure::Throttle);
+        }
+
+        batch
     }
 }
@@ -1,16 +1,19 @@
 use crate::{
     brokers::prepare::{
-        broker::{Brokerage, Reduction},
+        broker::{Brokerage, Journal, Reduction},
         Broker, BrokerFailure, Inclusion, Request,
     },
-    data::Sponge,
-    discovery::Client,
+    data::{MemoryGauge, Sponge},
+    handles::Lifecycle,
     prepare::ReductionStatement,
+    processing::{Namespace, Timeout},
+    signup::AssignmentVerifier,
+    telemetry::Span,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc, time::Instant};
 
 use talk::{
     crypto::primitives::multi::Signature as MultiSignature, net::PlainConnection, sync::fuse::Fuse,
@@ -22,8 +25,12 @@ use tokio::{net::TcpListener, sync::oneshot};
 enum ServeError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Request invalid"))]
     RequestInvalid,
+    #[doom(description("Request pertains to a foreign namespace"))]
+    ForeignNamespace,
     #[doom(description("`Brokerage` forfeited (most likely, the `Broker` is shutting down)"))]
     #[doom(wrap(request_forfeited))]
     BrokerageForfeited { source: oneshot::error::RecvError },
@@ -32,54 +39,138 @@ enum ServeError {
 }
 
 impl Broker {
+    #[allow(clippy::too_many_arguments)]
     pub(in crate::brokers::prepare::broker) async fn listen(
-        discovery: Arc<Client>,
+        namespace: Namespace,
+        verifier: AssignmentVerifier,
         brokerage_sponge: Arc<Sponge<Brokerage>>,
+        memory_gauge: MemoryGauge,
         listener: TcpListener,
+        receive_timeout: Timeout,
+        journal: Option<Journal>,
+        lifecycle: Lifecycle,
     ) {
         let fuse = Fuse::new();
 
         loop {
-            if let Ok((stream, _)) = listener.accept().await {
+            // While shedding, new connections are left pending in `listener`'s backlog
+            memory_gauge.await_relieved().await;
+
+            if let Ok((stream, address)) = listener.accept().await {
+                // While draining, new connections are dropped unserved
+                let engagement = match lifecycle.engage() {
+                    Some(engagement) => engagement,
+                    None => continue,
+                };
+
+                let client = address.ip();
                 let connection: PlainConnection = stream.into();
 
-                let discovery = discovery.clone();
+                let namespace = namespace.clone();
+                let verifier = verifier.clone();
                 let brokerage_sponge = brokerage_sponge.clone();
+                let memory_gauge = memory_gauge.clone();
+                let receive_timeout = receive_timeout.clone();
+                let journal = journal.clone();
 
                 fuse.spawn(async move {
-                    let _ = Broker::serve(discovery, brokerage_sponge, connection).await;
+                    let _engagement = engagement;
+
+                    let _ = Broker::serve(
+                        namespace,
+                        verifier,
+                        brokerage_sponge,
+                        memory_gauge,
+                        client,
+                        connection,
+                        receive_timeout,
+                        journal,
+                    )
+                    .await;
                 });
             }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn serve(
-        discovery: Arc<Client>,
+        namespace: Namespace,
+        verifier: AssignmentVerifier,
         brokerage_sponge: Arc<Sponge<Brokerage>>,
+        memory_gauge: MemoryGauge,
+        client: IpAddr,
         mut connection: PlainConnection,
+        receive_timeout: Timeout,
+        journal: Option<Journal>,
     ) -> Result<(), Top<ServeError>> {
         // Receive and validate `Request`
 
-        let request = connection
-            .receive::<Request>()
+        let request = receive_timeout
+            .run(connection.receive::<Request>())
             .await
+            .pot(ServeError::ReceiveTimeout, here!())?
             .pot(ServeError::ConnectionError, here!())?;
 
+        let arrival = Instant::now();
+
+        // Connections accepted before the `Broker` started shedding are
+        // served, but their `Request`s are rejected
+
+        if memory_gauge.is_shedding() {
+            connection
+                .send::<Result<Inclusion, BrokerFailure>>(&Err(BrokerFailure::Busy))
+                .await
+                .pot(ServeError::ConnectionError, here!())?;
+
+            return Ok(());
+        }
+
+        if request.namespace != namespace {
+            connection
+                .send::<Result<Inclusion, BrokerFailure>>(&Err(BrokerFailure::ForeignNamespace))
+                .await
+                .pot(ServeError::ConnectionError, here!())?;
+
+            return ServeError::ForeignNamespace.fail().spot(here!());
+        }
+
         request
-            .validate(discovery.as_ref())
+            .validate(&verifier)
             .pot(ServeError::RequestInvalid, here!())?;
 
+        // If the client supplied a trace, the brokerage is traced by a child span
+
+        let _span = request
+            .trace
+            .map(|trace| Span::child("prepare.request", trace));
+
         // Build and submit `Brokerage` to `brokerage_sponge`
 
         let keycard = request.keycard().clone(); // Needed to later verify the client's reduction shard
 
         let (reduction_inlet, reduction_outlet) = oneshot::channel();
         let (commit_inlet, commit_outlet) = oneshot::channel();
+        let (budget_inlet, budget_outlet) = oneshot::channel();
+
+        // `request` is accounted for until its brokerage is over
+        let _reservation =
+            memory_gauge.reserve(bincode::serialized_size(&request).unwrap_or(0) as usize);
+
+        // If journaling is enabled, persist `request` until assembled in a batch (a
+        // journaling failure only prevents recovery, and does not fail the brokerage)
+        let journal_entry = match journal {
+            Some(journal) => journal.record_request(&request).await.ok(),
+            None => None,
+        };
 
         let brokerage = Brokerage {
+            client,
             request,
+            arrival,
             reduction_inlet,
             commit_inlet,
+            budget_inlet,
+            journal_entry,
         };
 
         brokerage_sponge.push(brokerage);
@@ -114,6 +205,7 @@ impl Broker {
         };
 
         let root = inclusion.root(); // Needed to later verify the client's reduction shard
+        let proof = inclusion.proof.clone(); // Sent back to the client along with `BatchCommit`
 
         // Trade `inclusion` for a (valid) reduction shard
 
@@ -122,9 +214,10 @@ impl Broker {
             .await
             .pot(ServeError::ConnectionError, here!())?;
 
-        let reduction_shard = connection
-            .receive::<MultiSignature>()
+        let reduction_shard = receive_timeout
+            .run(connection.receive::<MultiSignature>())
             .await
+            .pot(ServeError::ReceiveTimeout, here!())?
             .pot(ServeError::ConnectionError, here!())?;
 
         reduction_shard
@@ -143,13 +236,35 @@ impl Broker {
             .map_err(Doom::into_top)
             .spot(here!())?;
 
-        // Send `commit` to the served client (note that `commit` is a `Result<BatchCommit, Failure>`)
+        // Send `commit` to the served client, along with the `Proof` of inclusion of its
+        // `Prepare` (which differs from `inclusion`'s if the batch was retried, see
+        // `Broker::broker`). Note that `commit` is a `Result<(BatchCommit, Proof), Failure>`.
+
+        let commit = commit.map(|(commit, retried)| (commit, retried.unwrap_or(proof)));
 
         connection
             .send(&commit)
             .await
             .pot(ServeError::ConnectionError, here!())?;
 
+        // In `benchmark` builds, a successful brokerage is followed by its `LatencyBudget`
+        // (`budget_inlet` is dropped if the brokerage failed)
+
+        let budget = budget_outlet.await;
+
+        #[cfg(feature = "benchmark")]
+        {
+            if let Ok(budget) = budget {
+                connection
+                    .send(&budget)
+                    .await
+                    .pot(ServeError::ConnectionError, here!())?;
+            }
+        }
+
+        #[cfg(not(feature = "benchmark"))]
+        let _ = budget;
+
         // Successfully delivering a `BrokerFailure` to the served client is not a shortcoming
         // of `serve`, and should not result in an `Err` (see above)
         Ok(())
@@ -0,0 +1,84 @@
+use crate::brokers::prepare::{
+    broker::Journal, broker_settings::HandoffTaskSettings, Broker, HandoffMessage,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use talk::net::SessionConnector;
+
+use tokio::{sync::mpsc::UnboundedReceiver, time};
+
+type MirrorOutlet = UnboundedReceiver<HandoffMessage>;
+
+#[derive(Doom)]
+enum HandoffError {
+    #[doom(description("Failed to connect to standby"))]
+    ConnectFailed,
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Failed to snapshot journal"))]
+    SnapshotFailed,
+}
+
+impl Broker {
+    pub(in crate::brokers::prepare::broker) async fn handoff(
+        journal: Journal,
+        mut mirror_outlet: MirrorOutlet,
+        connector: SessionConnector,
+        settings: HandoffTaskSettings,
+    ) {
+        loop {
+            let _ =
+                Broker::stream_journal(&journal, &mut mirror_outlet, &connector, &settings).await;
+            time::sleep(settings.handoff_interval).await;
+        }
+    }
+
+    async fn stream_journal(
+        journal: &Journal,
+        mirror_outlet: &mut MirrorOutlet,
+        connector: &SessionConnector,
+        settings: &HandoffTaskSettings,
+    ) -> Result<(), Top<HandoffError>> {
+        // The session authenticates the `Broker` to the `Standby` (and vice versa)
+        let mut session = connector
+            .connect(settings.standby)
+            .await
+            .pot(HandoffError::ConnectFailed, here!())?;
+
+        // Upon (re)connection, the standby is synchronized with a snapshot of the
+        // whole journal, which supersedes all messages queued while disconnected:
+        // its closing `Listing` removes the entries completed in the meantime
+        // (messages queued after the snapshot may be redundant, and are idempotent)
+
+        while mirror_outlet.try_recv().is_ok() {}
+
+        let snapshot = journal
+            .snapshot()
+            .await
+            .pot(HandoffError::SnapshotFailed, here!())?;
+
+        for message in snapshot {
+            session
+                .send(&message)
+                .await
+                .pot(HandoffError::ConnectionError, here!())?;
+        }
+
+        // Stream all further records and completions, heartbeating in between
+
+        let mut heartbeat = time::interval(settings.handoff_interval);
+
+        loop {
+            let message = tokio::select! {
+                Some(message) = mirror_outlet.recv() => message,
+                _ = heartbeat.tick() => HandoffMessage::Heartbeat,
+            };
+
+            session
+                .send(&message)
+                .await
+                .pot(HandoffError::ConnectionError, here!())?;
+        }
+    }
+}
@@ -0,0 +1,614 @@
+use crate::{
+    brokers::prepare::{HandoffMessage, Request, Submission},
+    crypto::Identify,
+    prepare::{Delegated, Prepare},
+    signup::IdAssignment,
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    io::{BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use talk::crypto::primitives::{hash::Hash, multi::Signature as MultiSignature, sign::Signature};
+
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task,
+};
+
+use zebra::vector::Vector;
+
+type MirrorInlet = UnboundedSender<HandoffMessage>;
+type MirrorOutlet = UnboundedReceiver<HandoffMessage>;
+
+// Records are written as tuples of references (to avoid cloning large batches), and read
+// back as tuples of owned values (`bincode` encodes both identically)
+type Record = (
+    Hash,
+    Vec<IdAssignment>,
+    Vec<Prepare>,
+    MultiSignature,
+    Vec<Option<Signature>>,
+    BTreeMap<usize, Delegated>,
+);
+
+type Recovered = (
+    Vec<(Submission, JournalEntry)>,
+    Vec<(Request, JournalEntry)>,
+);
+
+// A `Journal` persists every accepted request until it is assembled in a batch, and
+// every assembled batch until its `BatchCommit` is published (or its brokerage fails),
+// allowing a restarted `Broker` to resume in-flight brokerages. Clients of in-flight
+// brokerages cannot be notified across a restart: they observe the broker's
+// connections drop, and retry.
+// A mirrored `Journal` additionally reports every batch record and completion
+// (e.g., to be streamed to a `Standby`). Pending requests are not mirrored.
+#[derive(Debug, Clone)]
+pub(in crate::brokers::prepare) struct Journal {
+    directory: PathBuf,
+    mirror: Option<MirrorInlet>,
+}
+
+pub(in crate::brokers::prepare) struct JournalEntry {
+    path: PathBuf,
+    mirror: Option<MirrorInlet>,
+}
+
+#[derive(Doom)]
+pub(in crate::brokers::prepare) enum JournalError {
+    #[doom(description("Failed to write journal entry"))]
+    WriteFailed,
+    #[doom(description("Failed to read journal"))]
+    ReadFailed,
+    #[doom(description("Malformed journal entry name"))]
+    MalformedName,
+}
+
+impl Journal {
+    pub fn new(directory: PathBuf) -> Self {
+        Journal {
+            directory,
+            mirror: None,
+        }
+    }
+
+    // Mirrors all further records and completions to the returned outlet
+    pub fn mirror(&mut self) -> MirrorOutlet {
+        let (mirror_inlet, mirror_outlet) = mpsc::unbounded_channel();
+        self.mirror = Some(mirror_inlet);
+        mirror_outlet
+    }
+
+    pub async fn record(
+        &self,
+        view: &View,
+        assignments: &[IdAssignment],
+        prepares: &Vector<Prepare>,
+        reduction_signature: &MultiSignature,
+        individual_signatures: &[Option<Signature>],
+        delegations: &BTreeMap<usize, Delegated>,
+    ) -> Result<JournalEntry, Top<JournalError>> {
+        let path = self
+            .directory
+            .join(format!("brokerage-{:016x}.journal", rand::random::<u64>()));
+
+        // Serialization is CPU-bound, and cannot be moved to a blocking thread
+        // without cloning the whole batch: only the (IO-bound) write is offloaded
+        let record = bincode::serialize(&(
+            view.identifier(),
+            assignments,
+            prepares.items(),
+            reduction_signature,
+            individual_signatures,
+            delegations,
+        ))
+        .pot(JournalError::WriteFailed, here!())?;
+
+        let mirrored = self.mirror.as_ref().map(|_| record.clone());
+
+        let entry = JournalEntry {
+            path,
+            mirror: self.mirror.clone(),
+        };
+
+        let path = entry.path.clone();
+
+        task::spawn_blocking(move || Journal::persist(&path, &record))
+            .await
+            .unwrap()?;
+
+        if let (Some(mirror), Some(record)) = (&self.mirror, mirrored) {
+            let _ = mirror.send(HandoffMessage::Record {
+                name: entry.name(),
+                record,
+            });
+        }
+
+        Ok(entry)
+    }
+
+    // Persists `request` until it is assembled in a batch (or its brokerage fails)
+    pub async fn record_request(
+        &self,
+        request: &Request,
+    ) -> Result<JournalEntry, Top<JournalError>> {
+        let path = self
+            .directory
+            .join(format!("request-{:016x}.pending", rand::random::<u64>()));
+
+        // Unlike batches, requests are not bound to a `View`
+        let record = bincode::serialize(request).pot(JournalError::WriteFailed, here!())?;
+
+        let entry = JournalEntry { path, mirror: None };
+        let path = entry.path.clone();
+
+        task::spawn_blocking(move || Journal::persist(&path, &record))
+            .await
+            .unwrap()?;
+
+        Ok(entry)
+    }
+
+    // Loads all batches and pending requests left behind by a previous run. Entries
+    // that cannot be resumed in `view` (corrupted, or batches assembled in a different
+    // `View`) are deterministically failed, i.e., removed.
+    pub async fn recover(&self, view: &View) -> Result<Recovered, Top<JournalError>> {
+        let directory = self.directory.clone();
+        let mirror = self.mirror.clone();
+        let view = view.identifier();
+
+        task::spawn_blocking(move || -> Result<Recovered, Top<JournalError>> {
+            let mut submissions = Vec::new();
+            let mut requests = Vec::new();
+
+            for dir_entry in fs::read_dir(&directory).pot(JournalError::ReadFailed, here!())? {
+                let path = dir_entry.pot(JournalError::ReadFailed, here!())?.path();
+
+                let extension = match path.extension().and_then(|extension| extension.to_str()) {
+                    Some(extension) => extension.to_owned(),
+                    None => continue,
+                };
+
+                match extension.as_str() {
+                    "journal" => {
+                        let entry = JournalEntry {
+                            path,
+                            mirror: mirror.clone(),
+                        };
+
+                        match Journal::load(&entry, view) {
+                            Some(submission) => submissions.push((submission, entry)),
+                            None => entry.complete(),
+                        }
+                    }
+                    "pending" => {
+                        let entry = JournalEntry { path, mirror: None };
+
+                        match Journal::load_request(&entry) {
+                            Some(request) => requests.push((request, entry)),
+                            None => entry.complete(),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            Ok((submissions, requests))
+        })
+        .await
+        .unwrap()
+    }
+
+    // Returns one `HandoffMessage::Record` for each entry currently in the journal,
+    // followed by the `HandoffMessage::Listing` of all recorded entries
+    pub async fn snapshot(&self) -> Result<Vec<HandoffMessage>, Top<JournalError>> {
+        let directory = self.directory.clone();
+
+        task::spawn_blocking(move || -> Result<Vec<HandoffMessage>, Top<JournalError>> {
+            let mut snapshot = Vec::new();
+            let mut names = Vec::new();
+
+            for dir_entry in fs::read_dir(&directory).pot(JournalError::ReadFailed, here!())? {
+                let path = dir_entry.pot(JournalError::ReadFailed, here!())?.path();
+
+                if path
+                    .extension()
+                    .map_or(true, |extension| extension != "journal")
+                {
+                    continue;
+                }
+
+                // Entries completed since `read_dir` are skipped
+                if let Ok(record) = fs::read(&path) {
+                    let entry = JournalEntry { path, mirror: None };
+
+                    names.push(entry.name());
+
+                    snapshot.push(HandoffMessage::Record {
+                        name: entry.name(),
+                        record,
+                    });
+                }
+            }
+
+            snapshot.push(HandoffMessage::Listing { names });
+
+            Ok(snapshot)
+        })
+        .await
+        .unwrap()
+    }
+
+    // Applies a `HandoffMessage` received from the `Journal` this `Journal` mirrors.
+    // Applying the same message more than once has no further effect.
+    pub async fn apply(&self, message: HandoffMessage) -> Result<(), Top<JournalError>> {
+        match message {
+            HandoffMessage::Record { name, record } => {
+                let path = self.locate(&name)?;
+
+                task::spawn_blocking(move || Journal::persist(&path, &record))
+                    .await
+                    .unwrap()
+            }
+            HandoffMessage::Complete { name } => {
+                let entry = JournalEntry {
+                    path: self.locate(&name)?,
+                    mirror: None,
+                };
+
+                entry.complete();
+                Ok(())
+            }
+            HandoffMessage::Listing { names } => {
+                let directory = self.directory.clone();
+                let names = names.into_iter().collect::<HashSet<_>>();
+
+                task::spawn_blocking(move || Journal::retain(&directory, &names))
+                    .await
+                    .unwrap()
+            }
+            HandoffMessage::Heartbeat => Ok(()),
+        }
+    }
+
+    // Removes all batch entries in `directory` whose name is not in `names`
+    fn retain(directory: &Path, names: &HashSet<String>) -> Result<(), Top<JournalError>> {
+        for dir_entry in fs::read_dir(directory).pot(JournalError::ReadFailed, here!())? {
+            let path = dir_entry.pot(JournalError::ReadFailed, here!())?.path();
+
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "journal")
+            {
+                continue;
+            }
+
+            let entry = JournalEntry { path, mirror: None };
+
+            if !names.contains(&entry.name()) {
+                entry.complete();
+            }
+        }
+
+        Ok(())
+    }
+
+    // Names are received from the network: only plain journal file names are
+    // accepted, so that no path outside `self.directory` is ever written
+    fn locate(&self, name: &str) -> Result<PathBuf, Top<JournalError>> {
+        let path = Path::new(name);
+
+        if path.file_name().map_or(true, |file_name| file_name != name)
+            || path
+                .extension()
+                .map_or(true, |extension| extension != "journal")
+        {
+            return JournalError::MalformedName.fail().spot(here!());
+        }
+
+        Ok(self.directory.join(path))
+    }
+
+    // Writes `record` to a temporary file, then renames it to `path`: a crash mid-write
+    // must not leave a truncated entry behind. Both the file and its directory are
+    // synced, so that the entry survives a crash once `persist` returns.
+    fn persist(path: &Path, record: &[u8]) -> Result<(), Top<JournalError>> {
+        let temporary = path.with_extension("tmp");
+
+        let mut file = File::create(&temporary).pot(JournalError::WriteFailed, here!())?;
+
+        file.write_all(record)
+            .pot(JournalError::WriteFailed, here!())?;
+
+        file.sync_all().pot(JournalError::WriteFailed, here!())?;
+
+        fs::rename(&temporary, path).pot(JournalError::WriteFailed, here!())?;
+
+        // `path` is always a file in the journal's directory
+        File::open(path.parent().unwrap())
+            .and_then(|directory| directory.sync_all())
+            .pot(JournalError::WriteFailed, here!())
+    }
+
+    fn load_request(entry: &JournalEntry) -> Option<Request> {
+        let file = File::open(&entry.path).ok()?;
+        bincode::deserialize_from(BufReader::new(file)).ok()
+    }
+
+    fn load(entry: &JournalEntry, view: Hash) -> Option<Submission> {
+        let file = File::open(&entry.path).ok()?;
+
+        let record: Record = bincode::deserialize_from(BufReader::new(file)).ok()?;
+        let (
+            record_view,
+            assignments,
+            prepares,
+            reduction_signature,
+            individual_signatures,
+            delegations,
+        ) = record;
+
+        if record_view != view || individual_signatures.len() != prepares.len() {
+            return None;
+        }
+
+        // Rebuilding `prepares` recomputes its Merkle root
+        let prepares = Vector::new(prepares).ok()?;
+
+        Some(Submission::new(
+            assignments,
+            prepares,
+            reduction_signature,
+            individual_signatures,
+            delegations,
+        ))
+    }
+}
+
+impl JournalEntry {
+    fn name(&self) -> String {
+        // `self.path` is always a file in the journal's directory
+        self.path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    // Called once the brokerage is over (successfully or not)
+    pub fn complete(self) {
+        // Best-effort cleanup: a stale entry is resumed (to no effect) upon recovery
+        let _ = fs::remove_file(&self.path);
+
+        if let Some(mirror) = &self.mirror {
+            let _ = mirror.send(HandoffMessage::Complete { name: self.name() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Id, prepare::ReductionStatement, processing::Namespace, signup::test::assignment,
+        view::test::InstallGenerator,
+    };
+
+    use std::env;
+
+    use talk::crypto::{primitives::hash, KeyChain};
+
+    fn request(generator: &InstallGenerator, view: &View, id: Id) -> Request {
+        let client = KeyChain::random();
+
+        let assignment = assignment(
+            &generator.keychains,
+            view,
+            &Namespace::default(),
+            &client,
+            id,
+        );
+
+        Request::new(&client, assignment, 1, hash::hash(&id).unwrap())
+    }
+
+    fn journal() -> Journal {
+        let directory = env::temp_dir().join(format!("journal-{:016x}", rand::random::<u64>()));
+        fs::create_dir(&directory).unwrap();
+
+        Journal::new(directory)
+    }
+
+    async fn record(journal: &Journal, view: &View, request: &Request) -> JournalEntry {
+        let prepares = Vector::new(vec![request.prepare.clone()]).unwrap();
+
+        let reduction_signature = KeyChain::random()
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        journal
+            .record(
+                view,
+                &[request.assignment.clone()],
+                &prepares,
+                &reduction_signature,
+                &[Some(request.signature.clone())],
+                &BTreeMap::new(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn batches() {
+        let generator = InstallGenerator::new(8);
+        let view = generator.view(4);
+        let journal = journal();
+
+        let request = request(&generator, &view, 3);
+        let root = Vector::new(vec![request.prepare.clone()]).unwrap().root();
+
+        record(&journal, &view, &request).await;
+
+        let (submissions, requests) = journal.recover(&view).await.unwrap();
+
+        assert_eq!(submissions.len(), 1);
+        assert!(requests.is_empty());
+        assert_eq!(submissions[0].0.root(), root);
+
+        // Completed entries are not recovered
+        for (_, entry) in submissions {
+            entry.complete();
+        }
+
+        let (submissions, _) = journal.recover(&view).await.unwrap();
+        assert!(submissions.is_empty());
+
+        // Batches assembled in a different `View` cannot be resumed, and are removed
+        record(&journal, &view, &request).await;
+
+        let (submissions, _) = journal.recover(&generator.view(5)).await.unwrap();
+        assert!(submissions.is_empty());
+
+        let (submissions, _) = journal.recover(&view).await.unwrap();
+        assert!(submissions.is_empty());
+
+        fs::remove_dir_all(&journal.directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn requests() {
+        let generator = InstallGenerator::new(8);
+        let view = generator.view(4);
+        let journal = journal();
+
+        let request = request(&generator, &view, 3);
+        journal.record_request(&request).await.unwrap();
+
+        // Pending requests are not bound to a `View`
+        let (submissions, requests) = journal.recover(&generator.view(5)).await.unwrap();
+
+        assert!(submissions.is_empty());
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].0.prepare.id(), 3);
+
+        for (_, entry) in requests {
+            entry.complete();
+        }
+
+        let (_, requests) = journal.recover(&view).await.unwrap();
+        assert!(requests.is_empty());
+
+        fs::remove_dir_all(&journal.directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn corrupted() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+        let journal = journal();
+
+        fs::write(journal.directory.join("brokerage-0.journal"), b"garbage").unwrap();
+        fs::write(journal.directory.join("request-0.pending"), b"garbage").unwrap();
+
+        let (submissions, requests) = journal.recover(&view).await.unwrap();
+
+        assert!(submissions.is_empty());
+        assert!(requests.is_empty());
+
+        // Corrupted entries are deterministically failed
+        assert_eq!(fs::read_dir(&journal.directory).unwrap().count(), 0);
+
+        fs::remove_dir_all(&journal.directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn mirror() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let mut journal = journal();
+        let mut mirror_outlet = journal.mirror();
+
+        let standby = self::journal();
+
+        let request = request(&generator, &view, 3);
+        let entry = record(&journal, &view, &request).await;
+
+        // Pending requests are not mirrored
+        journal.record_request(&request).await.unwrap();
+
+        let message = mirror_outlet.recv().await.unwrap();
+        assert!(matches!(message, HandoffMessage::Record { .. }));
+
+        standby.apply(message).await.unwrap();
+
+        let (submissions, requests) = standby.recover(&view).await.unwrap();
+        assert_eq!(submissions.len(), 1);
+        assert!(requests.is_empty());
+
+        entry.complete();
+
+        let message = mirror_outlet.recv().await.unwrap();
+        assert!(matches!(message, HandoffMessage::Complete { .. }));
+
+        standby.apply(message).await.unwrap();
+
+        let (submissions, _) = standby.recover(&view).await.unwrap();
+        assert!(submissions.is_empty());
+
+        // A `Listing` removes all entries it does not list (e.g., entries whose
+        // `Complete` was lost while the standby was disconnected)
+        let kept = record(&journal, &view, &request).await;
+        let stale = record(&journal, &view, &request).await;
+
+        for _ in 0..2 {
+            standby
+                .apply(mirror_outlet.recv().await.unwrap())
+                .await
+                .unwrap();
+        }
+
+        stale.complete();
+        mirror_outlet.recv().await.unwrap();
+
+        let snapshot = journal.snapshot().await.unwrap();
+        assert!(matches!(
+            snapshot.last(),
+            Some(HandoffMessage::Listing { .. })
+        ));
+
+        for message in snapshot {
+            standby.apply(message).await.unwrap();
+        }
+
+        let (submissions, _) = standby.recover(&view).await.unwrap();
+        assert_eq!(submissions.len(), 1);
+
+        let names = fs::read_dir(&standby.directory)
+            .unwrap()
+            .map(|dir_entry| dir_entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec![kept.name()]);
+
+        // Names received from the network cannot escape the journal's directory
+        assert!(standby
+            .apply(HandoffMessage::Complete {
+                name: "../brokerage-0.journal".to_owned(),
+            })
+            .await
+            .is_err());
+
+        fs::remove_dir_all(&journal.directory).unwrap();
+        fs::remove_dir_all(&standby.directory).unwrap();
+    }
+}
@@ -1,14 +1,18 @@
 use crate::{
-    brokers::prepare::{BrokerSettings, BrokerSettingsComponents, Brokerage, Reduction},
-    crypto::Identify,
-    data::{PingBoard, Sponge},
+    benchmark::Metrics,
+    brokers::prepare::{BrokerSettings, BrokerSettingsComponents, Brokerage, DryRunLog, Reduction},
+    data::{ClockBoard, MemoryGauge, PingBoard, QuorumMonitor, Sponge, StragglerBoard},
     discovery::Client,
+    handles::{DrainError, Lifecycle},
+    processing::Timeout,
+    signup::AssignmentVerifier,
+    telemetry::{Badge, Exporter, Registry, Role},
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use talk::{
     link::context::ConnectDispatcher,
@@ -23,6 +27,16 @@ use tokio::{
 
 pub(crate) struct Broker {
     address: SocketAddr,
+    brokerage_sponge: Arc<Sponge<Brokerage>>,
+    receive_timeout: Timeout,
+    clock_board: ClockBoard,
+    dry_run: Option<DryRunLog>,
+    quorum_monitor: QuorumMonitor,
+    straggler_board: StragglerBoard,
+    metrics: Metrics,
+    registry: Registry,
+    memory_gauge: MemoryGauge,
+    lifecycle: Lifecycle,
     _fuse: Fuse,
 }
 
@@ -45,12 +59,37 @@ impl Broker {
         A: ToSocketAddrs,
         C: Connector,
     {
+        let namespace = discovery.namespace().clone();
+        let context = namespace.context(&view, "prepare");
+        let handoff_context = namespace.context(&view, "handoff");
         let BrokerSettingsComponents {
             flush: flush_settings,
-            broker: broker_settings,
+            broker: mut broker_settings,
             ping: ping_settings,
+            clock: clock_settings,
+            degradation_grace,
+            handoff: handoff_settings,
+            memory: memory_settings,
+            assignment_verifier: assignment_verifier_settings,
+            exporter: exporter_settings,
         } = settings.into_components();
 
+        // Client and replica `receive`s share the same `Timeout`
+        let receive_timeout = broker_settings.receive_timeout.clone();
+
+        // If a `Standby` is configured, the journal is mirrored to it (a dry-running
+        // `Broker` journals nothing, and has nothing to hand off)
+
+        let dry_running = broker_settings.dry_run.is_some();
+
+        let handoff = match (broker_settings.journal.as_mut(), handoff_settings) {
+            (Some(journal), Some(handoff_settings)) if !dry_running => {
+                let mirror_outlet = journal.mirror();
+                Some((journal.clone(), mirror_outlet, handoff_settings))
+            }
+            _ => None,
+        };
+
         let listener = TcpListener::bind(address)
             .await
             .map_err(BrokerError::initialize_failed)
@@ -64,54 +103,185 @@ impl Broker {
             .spot(here!())?;
 
         let dispatcher = ConnectDispatcher::new(connector);
-        let context = format!("{:?}::processor::prepare", view.identifier());
         let connector = Arc::new(SessionConnector::new(dispatcher.register(context)));
+        let handoff_connector = SessionConnector::new(dispatcher.register(handoff_context));
 
-        let brokerage_sponge = Arc::new(Sponge::new(flush_settings.brokerage_sponge_settings));
+        let brokerage_sponge = Arc::new(Sponge::new(
+            flush_settings.brokerage_sponge_settings.clone(),
+        ));
         let ping_board = PingBoard::new(&view);
+        let clock_board = ClockBoard::new(&view, clock_settings);
+
+        let dry_run = broker_settings.dry_run.clone();
+        let quorum_monitor = broker_settings.quorum_monitor.clone();
+        let straggler_board = broker_settings.straggler_board.clone();
+        let metrics = broker_settings.metrics.clone();
+        let registry = broker_settings.registry.clone();
+        let memory_gauge = MemoryGauge::new(memory_settings);
+        let verifier = AssignmentVerifier::new(discovery.clone(), assignment_verifier_settings);
+        let lifecycle = Lifecycle::new().with_badge(Badge::broker(Role::PrepareBroker, address));
+
+        // A dry-running `Broker` journals nothing
+        let journal = broker_settings
+            .journal
+            .clone()
+            .filter(|_| dry_run.is_none());
 
         let fuse = Fuse::new();
 
         {
+            let namespace = namespace.clone();
+            let brokerage_sponge = brokerage_sponge.clone();
+            let memory_gauge = memory_gauge.clone();
+            let receive_timeout = receive_timeout.clone();
+            let journal = journal.clone();
+            let lifecycle = lifecycle.clone();
+
+            fuse.spawn(lifecycle.clone().guard("listen", async move {
+                Broker::listen(
+                    namespace,
+                    verifier,
+                    brokerage_sponge,
+                    memory_gauge,
+                    listener,
+                    receive_timeout,
+                    journal,
+                    lifecycle,
+                )
+                .await;
+            }));
+        }
+
+        {
+            let metrics = metrics.clone();
+            fuse.spawn(metrics.run());
+        }
+
+        {
+            let brokerage_sponge = brokerage_sponge.clone();
+
+            registry.gauge(
+                "carbon_broker_sponge_occupancy",
+                "Brokerages waiting in the brokerage sponge",
+                move || brokerage_sponge.len() as f64,
+            );
+        }
+
+        if let Some(address) = exporter_settings.address {
+            let registry = registry.clone();
+
+            fuse.spawn(lifecycle.guard("exporter", async move {
+                if Exporter::run(registry, address).await.is_err() {
+                    log::error!("Failed to start metrics exporter on {}", address);
+                }
+            }));
+        }
+
+        // A dry-running `Broker` resumes no brokerage (this would contact replicas)
+
+        if let Some(journal) = journal {
             let discovery = discovery.clone();
+            let view = view.clone();
+            let ping_board = ping_board.clone();
+            let connector = connector.clone();
             let brokerage_sponge = brokerage_sponge.clone();
+            let broker_settings = broker_settings.clone();
 
             fuse.spawn(async move {
-                Broker::listen(discovery, brokerage_sponge, listener).await;
+                Broker::recover(
+                    discovery,
+                    view,
+                    ping_board,
+                    connector,
+                    brokerage_sponge,
+                    journal,
+                    broker_settings,
+                )
+                .await;
             });
         }
 
+        if let Some((journal, mirror_outlet, handoff_settings)) = handoff {
+            fuse.spawn(lifecycle.guard("handoff", async move {
+                Broker::handoff(journal, mirror_outlet, handoff_connector, handoff_settings).await;
+            }));
+        }
+
         {
             let discovery = discovery.clone();
             let view = view.clone();
             let ping_board = ping_board.clone();
             let connector = connector.clone();
+            let brokerage_sponge = brokerage_sponge.clone();
 
-            fuse.spawn(async move {
+            fuse.spawn(lifecycle.guard("flush", async move {
                 Broker::flush(
                     discovery,
                     view,
                     brokerage_sponge,
                     ping_board,
                     connector,
+                    flush_settings,
                     broker_settings,
                 )
                 .await;
-            });
+            }));
         }
 
-        for replica in view.members().keys().copied() {
+        // A dry-running `Broker` does not ping replicas
+
+        let replicas = if dry_run.is_none() {
+            view.members().keys().copied().collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        let responsive = view.power(&replicas).min(view.weighted_quorum());
+
+        for replica in replicas {
             let ping_board = ping_board.clone();
+            let clock_board = clock_board.clone();
             let connector = connector.clone();
             let ping_settings = ping_settings.clone();
 
-            fuse.spawn(
-                async move { Broker::ping(ping_board, connector, replica, ping_settings).await },
-            );
+            fuse.spawn(lifecycle.guard("ping", async move {
+                Broker::ping(ping_board, clock_board, connector, replica, ping_settings).await
+            }));
+        }
+
+        // The `Broker` is ready once a quorum of replicas is responsive (i.e., has
+        // registered its prepare context). A dry-running `Broker` is ready immediately.
+        {
+            let ping_board = ping_board.clone();
+
+            fuse.spawn(lifecycle.gate(async move {
+                ping_board.await_responsive(responsive).await;
+            }));
+        }
+
+        // A dry-running `Broker` is never degraded (no replica is ever unresponsive)
+        {
+            let quorum_monitor = quorum_monitor.clone();
+
+            fuse.spawn(lifecycle.guard("monitor", async move {
+                quorum_monitor
+                    .run(ping_board, responsive, degradation_grace)
+                    .await;
+            }));
         }
 
         Ok(Broker {
             address,
+            brokerage_sponge,
+            receive_timeout,
+            clock_board,
+            dry_run,
+            quorum_monitor,
+            straggler_board,
+            metrics,
+            registry,
+            memory_gauge,
+            lifecycle,
             _fuse: fuse,
         })
     }
@@ -119,32 +289,102 @@ impl Broker {
     pub fn address(&self) -> SocketAddr {
         self.address
     }
+
+    // Number of `receive`s (from clients or replicas) that timed out
+    pub fn expired_timeouts(&self) -> u64 {
+        self.receive_timeout.expired()
+    }
+
+    // Clock offsets of the replicas, as estimated from pings
+    pub fn clock_board(&self) -> &ClockBoard {
+        &self.clock_board
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    // Stops accepting clients, flushes pending brokerages without waiting for the
+    // brokerage sponge's timeout, and waits (for at most `timeout`) for all clients
+    // being served (hence, all in-flight brokerages) to complete
+    pub async fn drain(&self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        self.brokerage_sponge.expedite();
+        self.lifecycle.drain(timeout).await
+    }
+
+    // Whether fewer than a quorum of replicas have been responsive for too long
+    pub fn quorum_monitor(&self) -> &QuorumMonitor {
+        &self.quorum_monitor
+    }
+
+    // Replicas that persistently fail to provide witness shards
+    pub fn straggler_board(&self) -> &StragglerBoard {
+        &self.straggler_board
+    }
+
+    // Outcomes of the brokered prepares
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Instruments of the `Broker`, as served by its `Exporter` (if any)
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    // Whether the `Broker` is shedding load, having exceeded its memory watermarks
+    pub fn memory_gauge(&self) -> &MemoryGauge {
+        &self.memory_gauge
+    }
+
+    // Batches that would have been submitted, if dry-running
+    pub fn dry_run(&self) -> Option<&DryRunLog> {
+        self.dry_run.as_ref()
+    }
 }
 
 mod broker;
 mod flush;
 mod frontend;
+mod handoff;
+mod journal;
 mod orchestrate;
 mod ping;
+mod recover;
+
+pub(in crate::brokers::prepare) use journal::{Journal, JournalEntry};
 
 #[cfg(test)]
 mod tests {
     use crate::{
+        account::Id,
+        benchmark::{WorkloadPlanner, WorkloadSettings},
         brokers::{
             prepare::{BrokerFailure, Inclusion, Request},
-            signup::BrokerFailure as SignupBrokerFailure,
+            signup::{BrokerFailure as SignupBrokerFailure, Request as SignupBrokerRequest},
             test::System,
         },
         prepare::BatchCommit,
-        signup::{IdAssignment, IdRequest, SignupSettings},
+        processing::Namespace,
+        signup::{test, IdAssignment, IdRequest, SignupSettings},
+        view::View,
     };
 
+    use futures::future;
+
+    use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
     use talk::{
         crypto::{primitives::hash, KeyChain},
         net::PlainConnection,
     };
 
-    use tokio::net::TcpStream;
+    use tokio::{
+        net::TcpStream,
+        time::{self, Instant},
+    };
+
+    use zebra::vector::Proof;
 
     #[tokio::test]
     async fn develop() {
@@ -175,7 +415,13 @@ mod tests {
         let stream = TcpStream::connect(signup_broker.address()).await.unwrap();
         let mut connection: PlainConnection = stream.into();
 
-        connection.send(&request).await.unwrap();
+        connection
+            .send(&SignupBrokerRequest::IdRequest {
+                namespace: Namespace::default(),
+                request,
+            })
+            .await
+            .unwrap();
 
         let assignment = connection
             .receive::<Result<IdAssignment, SignupBrokerFailure>>()
@@ -205,15 +451,131 @@ mod tests {
 
         connection.send(&reduction_shard).await.unwrap();
 
-        let commit = connection
-            .receive::<Result<BatchCommit, BrokerFailure>>()
+        let (commit, _) = connection
+            .receive::<Result<(BatchCommit, Proof), BrokerFailure>>()
             .await
             .unwrap()
             .unwrap();
 
         println!("{:?}\n", inclusion);
         println!("{:?}", commit);
+    }
+
+    // Assigns each of `ids` to a fresh client, certified by a quorum of `processors`
+    fn assignments(
+        view: &View,
+        processors: &[KeyChain],
+        ids: impl Iterator<Item = Id>,
+    ) -> HashMap<Id, (KeyChain, IdAssignment)> {
+        ids.map(|id| {
+            let client = KeyChain::random();
+            let assignment = test::assignment(processors, view, &Namespace::default(), &client, id);
+
+            (id, (client, assignment))
+        })
+        .collect()
+    }
+
+    async fn brokerage(
+        address: SocketAddr,
+        keychain: KeyChain,
+        assignment: IdAssignment,
+        height: u64,
+    ) -> BatchCommit {
+        let request = Request::new(&keychain, assignment, height, hash::hash(&height).unwrap());
+
+        let stream = TcpStream::connect(address).await.unwrap();
+        let mut connection: PlainConnection = stream.into();
+
+        connection.send(&request).await.unwrap();
+
+        let inclusion = connection
+            .receive::<Result<Inclusion, BrokerFailure>>()
+            .await
+            .unwrap()
+            .unwrap();
 
-        // tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        let reduction_shard = inclusion
+            .certify_reduction(&keychain, request.prepare())
+            .unwrap();
+
+        connection.send(&reduction_shard).await.unwrap();
+
+        let (commit, _) = connection
+            .receive::<Result<(BatchCommit, Proof), BrokerFailure>>()
+            .await
+            .unwrap()
+            .unwrap();
+
+        commit
+    }
+
+    // Submits the batches of a `WorkloadPlanner` at their scheduled offsets:
+    // since ids are reused across batches, every brokerage must complete within
+    // `latency`, lest a later height overtake an earlier one
+    #[tokio::test]
+    #[ignore]
+    async fn workload() {
+        let System {
+            view,
+            discovery_server: _discovery_server,
+            discovery_client: _discovery_client,
+            processors,
+            mut prepare_brokers,
+            ..
+        } = System::setup(4, 0, 1, 0).await;
+
+        let prepare_broker = prepare_brokers.remove(0);
+        let address = prepare_broker.address();
+
+        let settings = WorkloadSettings {
+            ids: 256,
+            batch_size: 32,
+            batches: 64,
+            rate: 2.,
+            latency: Duration::from_secs(4),
+            ..Default::default()
+        };
+
+        let planner = WorkloadPlanner::new(settings.clone()).unwrap();
+
+        let keychains = processors
+            .iter()
+            .map(|(keychain, _)| keychain.clone())
+            .collect::<Vec<_>>();
+
+        let clients = assignments(
+            &view,
+            keychains.as_slice(),
+            settings.first_id..settings.first_id + settings.ids,
+        );
+
+        let start = Instant::now();
+        let mut batches = Vec::new();
+
+        for (index, batch) in planner.batches().enumerate() {
+            time::sleep_until(start + planner.schedule(index)).await;
+
+            let brokerages = batch
+                .into_iter()
+                .map(|entry| {
+                    let (keychain, assignment) = clients.get(&entry.id).unwrap().clone();
+                    brokerage(address, keychain, assignment, entry.height)
+                })
+                .collect::<Vec<_>>();
+
+            batches.push(tokio::spawn(async move {
+                let submission = Instant::now();
+                let commits = future::join_all(brokerages).await;
+                (commits.len(), submission.elapsed())
+            }));
+        }
+
+        for batch in batches {
+            let (commits, latency) = batch.await.unwrap();
+
+            assert_eq!(commits, settings.batch_size);
+            assert!(latency <= settings.latency);
+        }
     }
 }
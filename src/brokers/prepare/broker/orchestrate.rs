@@ -1,17 +1,28 @@
+use bit_vec::BitVec;
+
 use crate::{
+    account::Id,
+    benchmark::{LatencyBudget, Stage},
     brokers::prepare::{broker_settings::BrokerTaskSettings, Broker, Submission},
     crypto::{Aggregator, Certificate},
     data::PingBoard,
     discovery::Client,
-    prepare::{BatchCommit, BatchCommitShard, WitnessStatement},
-    processing::messages::{PrepareRequest, PrepareResponse},
+    prepare::{BatchCommit, BatchCommitShard, BatchDefect, Equivocation, WitnessStatement},
+    processing::{
+        messages::{PrepareRequest, PrepareResponse},
+        Namespace, Timeout,
+    },
     signup::IdAssignment,
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 
 use talk::{
     crypto::{
@@ -35,26 +46,49 @@ type UpdateOutlet = UnboundedReceiver<(Identity, Update)>;
 
 enum Command {
     SubmitSignatures,
-    SubmitWitness(Certificate),
+    SubmitExclusions(BitVec),
+    SubmitWitness(BitVec, Certificate),
+    SubmitExceptions(Vec<Equivocation>),
 }
 
 enum Update {
-    WitnessShard(MultiSignature),
+    WitnessShard(BitVec, MultiSignature),
     CommitShard(BatchCommitShard),
+    Malformed(Vec<BatchDefect>),
     Error,
 }
 
+// Replicas flag the elements they refuse to witness, but a single replica
+// cannot get an element excluded: an element is excluded only if its individual
+// signature fails the broker's own verification, or if it is flagged by a
+// plurality of replicas (hence, by at least one correct replica). Likewise,
+// the batch is deemed malformed only if the defects reported by a replica
+// match the broker's own diagnosis, or are reported by a plurality of replicas.
+// All tallies (`votes`, `reports`, `errors`) are in voting weight
 struct WitnessCollector {
     view: View,
-    root: Hash,
+    namespace: Namespace,
+    submission: Arc<Submission>,
+    partial: bool,
+    excluded: BitVec,
     aggregator: Aggregator<WitnessStatement>,
+    witnesses: Vec<Identity>,
+    flags: HashMap<Identity, BitVec>,
+    votes: Vec<usize>,
+    invalid: Vec<Option<bool>>,
+    reports: HashMap<Vec<BatchDefect>, usize>,
+    defects: Option<Vec<BatchDefect>>,
     errors: usize,
 }
 
 struct CommitCollector {
     view: View,
+    namespace: Namespace,
     root: Hash,
-    shards: Vec<(KeyCard, BatchCommitShard)>,
+    exclusions: BTreeSet<Id>,
+    equivocations: HashMap<Id, Equivocation>,
+    shards: HashMap<Identity, (KeyCard, BatchCommitShard)>,
+    pending: HashSet<Identity>,
     errors: usize,
 }
 
@@ -62,6 +96,8 @@ struct CommitCollector {
 pub(in crate::brokers::prepare::broker) enum OrchestrateError {
     #[doom(description("Failed to collect batch witness"))]
     WitnessCollectionFailed,
+    #[doom(description("Batch rejected as malformed"))]
+    BatchMalformed,
     #[doom(description("Failed to collect `BatchCommit`"))]
     CommitCollectionFailed,
 }
@@ -72,16 +108,24 @@ enum SubmitError {
     ConnectionFailed,
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Unexpected response"))]
     UnexpectedResponse,
     #[doom(description("Malformed response"))]
     MalformedResponse,
+    #[doom(description("Batch rejected as malformed ({} defects)", defects))]
+    BatchMalformed { defects: usize },
     #[doom(description("Invalid witness shard"))]
     InvalidWitnessShard,
     #[doom(description("Invalid `BatchCommitShard`"))]
     InvalidCommitShard,
+    #[doom(description("`BatchCommitShard` fails to account for submitted exceptions"))]
+    UnreconciledCommitShard,
     #[doom(description("`Command` channel closed (most likely, the `Broker` is shutting down)"))]
     CommandChannelClosed,
+    #[doom(description("Unexpected `Command`"))]
+    UnexpectedCommand,
 }
 
 #[derive(Doom)]
@@ -91,17 +135,21 @@ enum CollectorError {
 }
 
 impl Broker {
+    // If replicas reject `submission` as malformed, its (confirmed) defects are stored in `defects`
+    #[allow(clippy::too_many_arguments)]
     pub(in crate::brokers::prepare::broker) async fn orchestrate(
         discovery: Arc<Client>,
         view: View,
         ping_board: PingBoard,
         connector: Arc<SessionConnector>,
-        submission: Submission,
+        submission: Arc<Submission>,
         settings: BrokerTaskSettings,
+        budget: &mut LatencyBudget,
+        defects: &mut Vec<BatchDefect>,
     ) -> Result<BatchCommit, Top<OrchestrateError>> {
-        // Submit a `submit` slave for each replica in `view`
+        let start = Instant::now();
 
-        let submission = Arc::new(submission);
+        // Submit a `submit` slave for each replica in `view`
 
         let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
         let mut command_inlets = HashMap::new();
@@ -114,6 +162,10 @@ impl Broker {
             let connector = connector.clone();
             let submission = submission.clone();
             let update_inlet = update_inlet.clone();
+            let receive_timeout = settings.receive_timeout.clone();
+
+            // Compressed batches are submitted only to replicas that advertised accepting them
+            let compression = ping_board.compression(&replica.identity());
 
             let (command_inlet, command_outlet) = mpsc::unbounded_channel();
             command_inlets.insert(replica.identity(), command_inlet);
@@ -125,99 +177,167 @@ impl Broker {
                     connector,
                     replica,
                     submission,
+                    compression,
                     command_outlet,
                     update_inlet,
+                    receive_timeout,
                 )
                 .await;
             });
         }
 
-        // Obtain `PingBoard` rankings
+        // Obtain `PingBoard` rankings (stragglers are ranked last)
 
-        let rankings = ping_board.rankings();
+        let mut rankings = ping_board.rankings();
+        settings.straggler_board.demote(&mut rankings);
 
         // Optimistically direct the fastest plurality of slaves to submit `submission`'s signatures
 
-        for replica in &rankings[0..view.plurality()] {
+        let plurality = view.reach(&rankings, view.weighted_plurality());
+        let quorum = view.reach(&rankings, view.weighted_quorum());
+
+        for replica in &rankings[0..plurality] {
             let _ = command_inlets
                 .get_mut(replica)
                 .unwrap()
                 .send(Command::SubmitSignatures);
         }
 
+        // At all times, the first `asked` elements of `rankings` were directed to submit
+        // signatures, and the first `stalled` failed to produce enough witness shards
+        // before signatures were submitted to further slaves
+        let mut asked = plurality;
+        let mut stalled = 0;
+
         // Initialize `WitnessCollector`
 
-        let mut witness_collector = WitnessCollector::new(view.clone(), submission.root());
+        let mut witness_collector = WitnessCollector::new(
+            view.clone(),
+            discovery.namespace(),
+            submission.clone(),
+            settings.partial_witness,
+        );
 
         // Wait (or timeout) for the fastest plurality of slaves to produce witness shards
 
         let _ = time::timeout(
             settings.optimistic_witness_timeout,
-            witness_collector.progress(&mut update_outlet),
+            witness_collector.progress(&mut update_outlet, &mut command_inlets),
         )
         .await;
 
+        // If `witness_collector.complete()` is `Err`, then a plurality of slaves
+        // failed already, and collecting a `BatchCommit` is impossible
+        let mut complete = witness_collector.complete();
+
         // If the fastest plurality of slaves failed to produce witness shards,
         // extend signature sumbission to fastest quorum of slaves
 
-        // If `witness_collector.complete()` is `Err`, then a plurality of slaves
-        // failed already, and collecting a `BatchCommit` is impossible
-        let complete = witness_collector
-            .complete()
-            .pot(OrchestrateError::WitnessCollectionFailed, here!())?;
+        if let Ok(false) = complete {
+            for replica in &rankings[asked..quorum] {
+                let _ = command_inlets
+                    .get_mut(replica)
+                    .unwrap()
+                    .send(Command::SubmitSignatures);
+            }
+
+            stalled = asked;
+            asked = quorum;
+
+            let _ = time::timeout(
+                settings.backup_timeout,
+                witness_collector.progress(&mut update_outlet, &mut command_inlets),
+            )
+            .await;
+
+            complete = witness_collector.complete();
+        }
+
+        // If the fastest quorum of slaves is still short of witness shards (e.g.,
+        // some of its replicas are slow or down), re-route signature submission
+        // to all remaining slaves
 
-        if !complete {
-            for replica in &rankings[view.plurality()..view.quorum()] {
+        if let Ok(false) = complete {
+            for replica in &rankings[asked..] {
                 let _ = command_inlets
                     .get_mut(replica)
                     .unwrap()
                     .send(Command::SubmitSignatures);
             }
 
+            stalled = asked;
+            asked = rankings.len();
+
             // Because a quorum of replicas is (theoretically) guaranteed to provide
-            // a plurality of responses, collection of witness shards from a quorum
-            // must carry on, without timeout, until success or failure.
-            witness_collector.progress(&mut update_outlet).await;
+            // a plurality of responses, collection of witness shards from all
+            // replicas must carry on, without timeout, until success or failure.
+            witness_collector
+                .progress(&mut update_outlet, &mut command_inlets)
+                .await;
 
             // Because `witness_collector.progress()` returned, if `witness_collector.complete()`
             // is `Ok`, then a plurality of witness shards was achieved.
-            witness_collector
-                .complete()
-                .pot(OrchestrateError::WitnessCollectionFailed, here!())?;
+            complete = witness_collector.complete();
+        }
+
+        // Slaves that produced a witness shard are responsive. Stalled slaves that did
+        // not are missed: repeated misses deprioritize them in future brokerages
+
+        for (index, replica) in rankings[..asked].iter().enumerate() {
+            if witness_collector.witnesses.contains(replica) {
+                settings.straggler_board.hit(*replica);
+            } else if index < stalled {
+                settings.straggler_board.miss(*replica);
+            }
         }
 
+        if let Some(confirmed) = witness_collector.defects.take() {
+            *defects = confirmed;
+            return OrchestrateError::BatchMalformed.fail().spot(here!());
+        }
+
+        complete.pot(OrchestrateError::WitnessCollectionFailed, here!())?;
+
         // Finalize `witness_collector` to obtain witness
 
-        let (commit_collector, witness) = witness_collector.finalize();
+        let (commit_collector, excluded, witness) = witness_collector.finalize();
 
-        // Direct all slaves to send `witness`
+        budget.record(Stage::WitnessCollection, start.elapsed());
+        let start = Instant::now();
+
+        // Direct all slaves to send `witness` (along with the elements it excludes)
 
         for command_inlet in command_inlets.values_mut() {
-            let _ = command_inlet.send(Command::SubmitWitness(witness.clone()));
+            let _ = command_inlet.send(Command::SubmitWitness(excluded.clone(), witness.clone()));
         }
 
         // Collect `BatchCommit` from a quorum of slaves
 
         let commit = commit_collector
-            .run(&mut update_outlet)
+            .run(&mut update_outlet, &mut command_inlets)
             .await
             .pot(OrchestrateError::CommitCollectionFailed, here!())?;
 
+        budget.record(Stage::CommitAggregation, start.elapsed());
+
         Ok(commit)
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn submit(
         discovery: Arc<Client>,
         view: View,
         connector: Arc<SessionConnector>,
         replica: KeyCard,
         submission: Arc<Submission>,
+        compression: bool,
         mut command_outlet: CommandOutlet,
         update_inlet: UpdateInlet,
+        receive_timeout: Timeout,
     ) {
         // In order to catch all `Err`s while maintaining `?`-syntax, all
         // operations are executed within the scope of an `async` block
-        let result: Result<BatchCommitShard, Top<SubmitError>> = async {
+        let result: Result<(BitVec, BatchCommitShard), Top<SubmitError>> = async {
             // Connect to `replica`
 
             let mut session = connector
@@ -225,10 +345,19 @@ impl Broker {
                 .await
                 .pot(SubmitError::ConnectionFailed, here!())?;
 
+            // If `submission` is traced, precede the session with its `TraceContext`
+
+            if let Some(trace) = submission.trace() {
+                session
+                    .send(&PrepareRequest::Trace(trace))
+                    .await
+                    .pot(SubmitError::ConnectionError, here!())?;
+            }
+
             // Submit `Prepare`s (with a `PrepareRequest::Batch` request)
 
             session
-                .send(&submission.requests.batch())
+                .send(submission.requests.batch(compression))
                 .await
                 .pot(SubmitError::ConnectionError, here!())?;
 
@@ -243,9 +372,10 @@ impl Broker {
             // Obtain a witness: either directly from master; or by submitting signatures,
             // obtaining a witness shard, then trading the shard with master
 
-            let witness = match command {
+            let (excluded, witness) = match command {
                 // If `command` is `SubmitSignatures` then: submit signatures; receive a witness shard;
-                // send the witness shard to master; receive a witness from master
+                // send the witness shard to master; receive a witness from master (possibly, first
+                // providing master with witness shards over further exclusions)
                 Command::SubmitSignatures => {
                     // Submit signatures (with a `PrepareRequest::Signatures` request)
 
@@ -257,63 +387,87 @@ impl Broker {
                     // Obtain a witness shard (if requested to do so, first provide `replica` with the
                     // `IdAssignment`s it is missing)
 
-                    let response = session
-                        .receive::<PrepareResponse>()
+                    let mut response = receive_timeout
+                        .run(session.receive::<PrepareResponse>())
                         .await
+                        .pot(SubmitError::ReceiveTimeout, here!())?
                         .pot(SubmitError::ConnectionError, here!())?;
 
-                    let shard = match response {
-                        // If `response` is `UnknownIds`, then `replica` misses some `IdAssignment`s,
-                        // required to validate the submitted signatures
-                        PrepareResponse::UnknownIds(unknown_ids) => {
-                            // Gather the necessary `IdAssignments`. Assignments are requested
-                            // by `Id`, prompting a binary search on `submission.assignments()`
-                            // (which was sorted by `Id` by `Broker::prepare`)
-                            let id_assignments = unknown_ids
-                                .into_iter()
-                                .map(|id| {
-                                    // If `id` is not present in `submission.assignments()`, then
-                                    // `replica` is Byzantine
-                                    let index = submission
-                                        .assignments()
-                                        .binary_search_by_key(&id, |assignment| assignment.id())
-                                        .map_err(|_| SubmitError::MalformedResponse.into_top())
-                                        .spot(here!())?;
-
-                                    Ok(submission.assignments()[index].clone())
-                                })
-                                .collect::<Result<Vec<IdAssignment>, Top<SubmitError>>>()?;
-
-                            // Send missing `IdAssignments`
-
-                            session
-                                .send(&PrepareRequest::Assignments(id_assignments))
-                                .await
-                                .pot(SubmitError::ConnectionError, here!())?;
-
-                            // Receive witness shard (a correct `replica` cannot provide any
-                            // response other than `WitnessShard`)
-
-                            let response = session
-                                .receive::<PrepareResponse>()
-                                .await
-                                .pot(SubmitError::ConnectionError, here!())?;
-
-                            match response {
-                                PrepareResponse::WitnessShard(shard) => Ok(shard),
-                                _ => SubmitError::UnexpectedResponse.fail().spot(here!()),
-                            }
-                        }
+                    // If `response` is `UnknownIds`, then `replica` misses some `IdAssignment`s,
+                    // required to validate the submitted signatures
+                    if let PrepareResponse::UnknownIds(unknown_ids) = response {
+                        // Gather the necessary `IdAssignments`. Assignments are requested
+                        // by `Id`, prompting a binary search on `submission.assignments()`
+                        // (which was sorted by `Id` by `Broker::prepare`)
+                        let id_assignments = unknown_ids
+                            .into_iter()
+                            .map(|id| {
+                                // If `id` is not present in `submission.assignments()`, then
+                                // `replica` is Byzantine
+                                let index = submission
+                                    .assignments()
+                                    .binary_search_by_key(&id, |assignment| assignment.id())
+                                    .map_err(|_| SubmitError::MalformedResponse.into_top())
+                                    .spot(here!())?;
+
+                                Ok(submission.assignments()[index].clone())
+                            })
+                            .collect::<Result<Vec<IdAssignment>, Top<SubmitError>>>()?;
+
+                        // Send missing `IdAssignments`
+
+                        session
+                            .send(&PrepareRequest::Assignments(id_assignments))
+                            .await
+                            .pot(SubmitError::ConnectionError, here!())?;
+
+                        // Receive witness shard (a correct `replica` cannot provide any
+                        // response other than `WitnessShard` or `PartialWitnessShard`)
+
+                        response = receive_timeout
+                            .run(session.receive::<PrepareResponse>())
+                            .await
+                            .pot(SubmitError::ReceiveTimeout, here!())?
+                            .pot(SubmitError::ConnectionError, here!())?;
+                    }
 
-                        // If `command` is `WitnessShard`, return witness shard
-                        PrepareResponse::WitnessShard(shard) => Ok(shard),
+                    let (flagged, shard) = match response {
+                        PrepareResponse::WitnessShard(shard) => Ok((BitVec::new(), shard)),
+                        PrepareResponse::PartialWitnessShard(flagged, shard) => {
+                            // A partial witness shard must flag each element of the batch
+                            if flagged.len() != submission.prepares().len() {
+                                return SubmitError::MalformedResponse.fail().spot(here!());
+                            }
 
+                            // Flags that exclude no element represent a full witness shard
+                            if flagged.none() {
+                                Ok((BitVec::new(), shard))
+                            } else {
+                                Ok((flagged, shard))
+                            }
+                        }
+                        // A correct `replica` rejects a malformed `submission` as a whole:
+                        // forward its diagnosis to master, which decides whether or not
+                        // to evict the defective elements (see `WitnessCollector`)
+                        PrepareResponse::MalformedBatch(defects) => {
+                            let count = defects.len();
+                            let _ =
+                                update_inlet.send((replica.identity(), Update::Malformed(defects)));
+
+                            SubmitError::BatchMalformed { defects: count }
+                                .fail()
+                                .spot(here!())
+                        }
                         _ => SubmitError::UnexpectedResponse.fail().spot(here!()),
                     }?;
 
                     // Verify `shard`
 
-                    let statement = WitnessStatement::new(submission.root());
+                    let statement = WitnessStatement::partial(
+                        discovery.namespace(),
+                        submission.root(),
+                        submission.exclusions(&flagged),
+                    );
 
                     shard
                         .verify([&replica], &statement)
@@ -321,41 +475,95 @@ impl Broker {
 
                     // Send `shard` to master
 
-                    let _ = update_inlet.send((replica.identity(), Update::WitnessShard(shard)));
-
-                    // Receive witness from master, return witness
-
-                    let command = command_outlet
-                        .recv()
-                        .await
-                        .ok_or(SubmitError::CommandChannelClosed.into_top())
-                        .spot(here!())?;
-
-                    match command {
-                        Command::SubmitWitness(witness) => witness,
-                        _ => {
-                            panic!("unexpected `Command`");
+                    let _ = update_inlet
+                        .send((replica.identity(), Update::WitnessShard(flagged, shard)));
+
+                    // Until a witness is received from master, produce witness shards
+                    // for all exclusions requested by master. Return the witness.
+
+                    loop {
+                        let command = command_outlet
+                            .recv()
+                            .await
+                            .ok_or(SubmitError::CommandChannelClosed.into_top())
+                            .spot(here!())?;
+
+                        match command {
+                            Command::SubmitExclusions(excluded) => {
+                                // Submit exclusions (with a `PrepareRequest::Exclusions` request)
+
+                                session
+                                    .send(&PrepareRequest::Exclusions(excluded.clone()))
+                                    .await
+                                    .pot(SubmitError::ConnectionError, here!())?;
+
+                                // Receive partial witness shard (a correct `replica` cannot provide
+                                // any response other than `PartialWitnessShard` over `excluded`)
+
+                                let response = receive_timeout
+                                    .run(session.receive::<PrepareResponse>())
+                                    .await
+                                    .pot(SubmitError::ReceiveTimeout, here!())?
+                                    .pot(SubmitError::ConnectionError, here!())?;
+
+                                let shard = match response {
+                                    PrepareResponse::PartialWitnessShard(flagged, shard)
+                                        if flagged == excluded =>
+                                    {
+                                        Ok(shard)
+                                    }
+                                    _ => SubmitError::UnexpectedResponse.fail().spot(here!()),
+                                }?;
+
+                                // Verify `shard`, send `shard` to master
+
+                                let statement = WitnessStatement::partial(
+                                    discovery.namespace(),
+                                    submission.root(),
+                                    submission.exclusions(&excluded),
+                                );
+
+                                shard
+                                    .verify([&replica], &statement)
+                                    .pot(SubmitError::InvalidWitnessShard, here!())?;
+
+                                let _ = update_inlet.send((
+                                    replica.identity(),
+                                    Update::WitnessShard(excluded, shard),
+                                ));
+                            }
+                            Command::SubmitWitness(excluded, witness) => {
+                                break (excluded, witness);
+                            }
+                            Command::SubmitSignatures | Command::SubmitExceptions(_) => {
+                                return SubmitError::UnexpectedCommand.fail().spot(here!());
+                            }
                         }
                     }
                 }
 
                 // If `command` is `SubmitWitness`, return witness
-                Command::SubmitWitness(witness) => witness,
+                Command::SubmitWitness(excluded, witness) => (excluded, witness),
+
+                Command::SubmitExclusions(_) | Command::SubmitExceptions(_) => {
+                    return SubmitError::UnexpectedCommand.fail().spot(here!());
+                }
             };
 
             // Send `witness` (with a `PrepareRequest::Witness` request)
 
             session
-                .send(&PrepareRequest::Witness(witness))
+                .send(&PrepareRequest::Witness(excluded.clone(), witness))
                 .await
                 .pot(SubmitError::ConnectionError, here!())?;
 
             // Receive `BatchCommitShard` (a correct `replica` cannot provide
             // any response other than `CommitShard`)
 
-            let response = session
-                .receive::<PrepareResponse>()
+            let response = receive_timeout
+                .run(session.receive::<PrepareResponse>())
                 .await
+                .pot(SubmitError::ReceiveTimeout, here!())?
                 .pot(SubmitError::ConnectionError, here!())?;
 
             let shard = match response {
@@ -370,64 +578,322 @@ impl Broker {
                     discovery.as_ref(),
                     &view,
                     submission.root(),
+                    &submission.exclusions(&excluded),
                     submission.prepares(),
                     &replica,
                 )
                 .pot(SubmitError::InvalidCommitShard, here!())?;
 
-            Ok(shard)
+            Ok((excluded, shard))
         }
         .await;
 
         // If `shard` is `Ok`, send `BatchCommitShard` to master, otherwise signal `Error`
 
-        let _ = match result {
-            Ok(shard) => update_inlet.send((replica.identity(), Update::CommitShard(shard))),
-            Err(_) => update_inlet.send((replica.identity(), Update::Error)),
+        let excluded = match result {
+            Ok((excluded, shard)) => {
+                let _ = update_inlet.send((replica.identity(), Update::CommitShard(shard)));
+                excluded
+            }
+            Err(_) => {
+                let _ = update_inlet.send((replica.identity(), Update::Error));
+                return;
+            }
         };
+
+        // Until master is done collecting `BatchCommitShard`s, re-submit to `replica`
+        // all the exceptions it failed to account for, obtaining reconciled shards
+
+        while let Some(command) = command_outlet.recv().await {
+            let result = match command {
+                Command::SubmitExceptions(equivocations) => {
+                    Broker::reconcile(
+                        discovery.as_ref(),
+                        &view,
+                        connector.as_ref(),
+                        &replica,
+                        submission.as_ref(),
+                        &excluded,
+                        equivocations,
+                        &receive_timeout,
+                    )
+                    .await
+                }
+                _ => SubmitError::UnexpectedCommand.fail().spot(here!()),
+            };
+
+            let _ = match result {
+                Ok(shard) => update_inlet.send((replica.identity(), Update::CommitShard(shard))),
+                Err(_) => update_inlet.send((replica.identity(), Update::Error)),
+            };
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn reconcile(
+        discovery: &Client,
+        view: &View,
+        connector: &SessionConnector,
+        replica: &KeyCard,
+        submission: &Submission,
+        excluded: &BitVec,
+        equivocations: Vec<Equivocation>,
+        receive_timeout: &Timeout,
+    ) -> Result<BatchCommitShard, Top<SubmitError>> {
+        let exceptions = equivocations
+            .iter()
+            .map(Equivocation::id)
+            .collect::<BTreeSet<_>>();
+
+        // Connect to `replica`
+
+        let mut session = connector
+            .connect(replica.identity())
+            .await
+            .pot(SubmitError::ConnectionFailed, here!())?;
+
+        // Submit `equivocations` (with a `PrepareRequest::Reconcile` request)
+
+        session
+            .send(&PrepareRequest::Reconcile(submission.root(), equivocations))
+            .await
+            .pot(SubmitError::ConnectionError, here!())?;
+
+        // Receive `BatchCommitShard` (a correct `replica` cannot provide
+        // any response other than `CommitShard`)
+
+        let response = receive_timeout
+            .run(session.receive::<PrepareResponse>())
+            .await
+            .pot(SubmitError::ReceiveTimeout, here!())?
+            .pot(SubmitError::ConnectionError, here!())?;
+
+        session.end();
+
+        let shard = match response {
+            PrepareResponse::CommitShard(shard) => Ok(shard),
+            _ => SubmitError::UnexpectedResponse.fail().spot(here!()),
+        }?;
+
+        // A correct `replica` accounts for all submitted exceptions
+
+        if !shard.exceptions().is_superset(&exceptions) {
+            return SubmitError::UnreconciledCommitShard.fail().spot(here!());
+        }
+
+        // Validate and return `shard`
+
+        shard
+            .validate(
+                discovery,
+                view,
+                submission.root(),
+                &submission.exclusions(excluded),
+                submission.prepares(),
+                replica,
+            )
+            .pot(SubmitError::InvalidCommitShard, here!())?;
+
+        Ok(shard)
     }
 }
 
 impl WitnessCollector {
-    pub fn new(view: View, root: Hash) -> Self {
-        let statement = WitnessStatement::new(root);
+    pub fn new(
+        view: View,
+        namespace: &Namespace,
+        submission: Arc<Submission>,
+        partial: bool,
+    ) -> Self {
+        let statement = WitnessStatement::new(namespace, submission.root());
         let aggregator = Aggregator::new(view.clone(), statement);
 
+        let votes = vec![0; submission.prepares().len()];
+        let invalid = vec![None; submission.prepares().len()];
+
         WitnessCollector {
             view,
-            root,
+            namespace: namespace.clone(),
+            submission,
+            partial,
+            excluded: BitVec::new(),
             aggregator,
+            witnesses: Vec::new(),
+            flags: HashMap::new(),
+            votes,
+            invalid,
+            reports: HashMap::new(),
+            defects: None,
             errors: 0,
         }
     }
 
     fn succeeded(&self) -> bool {
-        self.aggregator.multiplicity() >= self.view.plurality()
+        self.aggregator.power() >= self.view.weighted_plurality()
     }
 
     fn failed(&self) -> bool {
-        self.errors >= self.view.plurality()
+        // A malformed batch cannot be witnessed by any correct replica
+        if self.defects.is_some() {
+            return true;
+        }
+
+        // Replicas whose flags are not covered by the current target cannot
+        // witness it: if too many, a plurality of witness shards is out of reach
+        let blocked = self.view.power(
+            self.flags
+                .iter()
+                .filter(|(_, flagged)| !WitnessCollector::covers(&self.excluded, flagged))
+                .map(|(replica, _)| replica),
+        );
+
+        self.errors >= self.view.weighted_plurality()
+            || self.errors + blocked > self.view.total_weight() - self.view.weighted_plurality()
     }
 
-    async fn progress(&mut self, update_outlet: &mut UpdateOutlet) {
+    async fn progress(
+        &mut self,
+        update_outlet: &mut UpdateOutlet,
+        command_inlets: &mut HashMap<Identity, CommandInlet>,
+    ) {
         while !self.succeeded() && !self.failed() {
             // A copy of `update_inlet` is held by `orchestrate`.
             // As a result, `update_outlet.recv()` cannot return `None`.
             match update_outlet.recv().await.unwrap() {
-                (replica, Update::WitnessShard(shard)) => {
-                    let keycard = self.view.members().get(&replica).unwrap();
-                    self.aggregator.add(keycard, shard).unwrap();
+                (replica, Update::WitnessShard(flagged, shard)) => {
+                    if !flagged.is_empty() && !self.partial {
+                        // Partial witness shards are useless outside of partial-witness mode
+                        self.errors += self.view.weight(&replica);
+                        continue;
+                    }
+
+                    // The first shard of `replica` carries its flags: tally them,
+                    // then (if needed) extend the target, asking all witnesses
+                    // whose flags the new target covers to witness it
+                    if !self.witnesses.contains(&replica) {
+                        self.witnesses.push(replica);
+                        self.flag(replica, &flagged);
+
+                        let target = self.target();
+
+                        if target != self.excluded {
+                            self.excluded = target;
+
+                            let statement = WitnessStatement::partial(
+                                &self.namespace,
+                                self.submission.root(),
+                                self.submission.exclusions(&self.excluded),
+                            );
+
+                            self.aggregator = Aggregator::new(self.view.clone(), statement);
+
+                            for (witness, flags) in self.flags.iter() {
+                                if *witness != replica
+                                    && WitnessCollector::covers(&self.excluded, flags)
+                                {
+                                    let _ = command_inlets
+                                        .get_mut(witness)
+                                        .unwrap()
+                                        .send(Command::SubmitExclusions(self.excluded.clone()));
+                                }
+                            }
+                        }
+                    }
+
+                    if flagged == self.excluded {
+                        // `shard` witnesses exactly the elements currently targeted
+                        let keycard = self.view.members().get(&replica).unwrap();
+                        self.aggregator.add(keycard, shard).unwrap();
+                    } else if WitnessCollector::covers(&self.excluded, &flagged) {
+                        // `shard` excludes fewer elements than currently targeted:
+                        // ask `replica` to witness the current target instead
+                        let _ = command_inlets
+                            .get_mut(&replica)
+                            .unwrap()
+                            .send(Command::SubmitExclusions(self.excluded.clone()));
+                    }
+
+                    // Otherwise, `replica` flags elements that are not excluded: it
+                    // cannot witness the current target (nor contribute to it, unless
+                    // the target is later extended to cover its flags)
+                }
+                (replica, Update::Malformed(defects)) => {
+                    // `replica` also signals an `Error`, tallied separately
+                    self.report(replica, defects);
                 }
-                (_, Update::Error) => {
-                    self.errors += 1;
+                (replica, Update::Error) => {
+                    self.errors += self.view.weight(&replica);
                 }
-                _ => {
-                    panic!("`WitnessCollector::progress` received an unexpected `Update`");
+                (replica, Update::CommitShard(..)) => {
+                    // No slave produces a commit shard before being sent a witness:
+                    // an out-of-order `Update` counts as an error of its replica
+                    self.errors += self.view.weight(&replica);
                 }
             }
         }
     }
 
+    // Tallies the elements flagged by `replica`, checking the individual
+    // signature of each element flagged for the first time
+    fn flag(&mut self, replica: Identity, flagged: &BitVec) {
+        for (index, flag) in flagged.iter().enumerate() {
+            if flag {
+                self.votes[index] += self.view.weight(&replica);
+
+                if self.invalid[index].is_none() {
+                    self.invalid[index] = Some(self.submission.invalid_signature(index));
+                }
+            }
+        }
+
+        self.flags.insert(replica, flagged.clone());
+    }
+
+    // Tallies the defects reported by a replica: if they match the broker's own
+    // diagnosis of the batch, or a plurality of replicas reported them, they are confirmed
+    fn report(&mut self, replica: Identity, defects: Vec<BatchDefect>) {
+        if defects.is_empty() {
+            return;
+        }
+
+        let reports = self.reports.entry(defects.clone()).or_insert(0);
+        *reports += self.view.weight(&replica);
+
+        if *reports >= self.view.weighted_plurality() || defects == self.submission.defects() {
+            self.defects = Some(defects);
+        }
+    }
+
+    // Flags all elements whose individual signature is invalid,
+    // or that a plurality of replicas flagged
+    fn target(&self) -> BitVec {
+        let target = self
+            .votes
+            .iter()
+            .zip(self.invalid.iter())
+            .map(|(votes, invalid)| {
+                *invalid == Some(true) || *votes >= self.view.weighted_plurality()
+            })
+            .collect::<BitVec>();
+
+        // An empty `BitVec` represents a full witness
+        if target.none() {
+            BitVec::new()
+        } else {
+            target
+        }
+    }
+
+    // Determines whether or not `excluded` flags all elements flagged by `flagged`
+    fn covers(excluded: &BitVec, flagged: &BitVec) -> bool {
+        flagged.none()
+            || (excluded.len() == flagged.len()
+                && excluded
+                    .iter()
+                    .zip(flagged.iter())
+                    .all(|(excluded, flagged)| excluded || !flagged))
+    }
+
     pub fn complete(&self) -> Result<bool, Top<CollectorError>> {
         if self.failed() {
             CollectorError::ErrorPlurality.fail().spot(here!())
@@ -436,55 +902,736 @@ impl WitnessCollector {
         }
     }
 
-    pub fn finalize(self) -> (CommitCollector, Certificate) {
-        let commit_collector = CommitCollector::new(self.view, self.root, self.errors);
+    pub fn finalize(self) -> (CommitCollector, BitVec, Certificate) {
+        let exclusions = self.submission.exclusions(&self.excluded);
+
+        let commit_collector = CommitCollector::new(
+            self.view,
+            self.namespace,
+            self.submission.root(),
+            exclusions,
+            self.errors,
+        );
+
         let (_, witness) = self.aggregator.finalize();
 
-        (commit_collector, witness)
+        (commit_collector, self.excluded, witness)
     }
 }
 
 impl CommitCollector {
-    fn new(view: View, root: Hash, errors: usize) -> Self {
+    fn new(
+        view: View,
+        namespace: Namespace,
+        root: Hash,
+        exclusions: BTreeSet<Id>,
+        errors: usize,
+    ) -> Self {
         CommitCollector {
             view,
+            namespace,
             root,
-            shards: Vec::new(),
+            exclusions,
+            equivocations: HashMap::new(),
+            shards: HashMap::new(),
+            pending: HashSet::new(),
             errors,
         }
     }
 
+    // Determines whether or not `shard` excepts exactly the reconciled exceptions
+    fn reconciled(&self, shard: &BatchCommitShard) -> bool {
+        let exceptions = shard.exceptions();
+
+        exceptions.len() == self.equivocations.len()
+            && exceptions
+                .iter()
+                .all(|id| self.equivocations.contains_key(id))
+    }
+
     fn succeeded(&self) -> bool {
-        self.shards.len() >= self.view.quorum()
+        let power = self.view.power(
+            self.shards
+                .iter()
+                .filter(|(_, (_, shard))| self.reconciled(shard))
+                .map(|(replica, _)| replica),
+        );
+
+        power >= self.view.weighted_quorum()
     }
 
     fn failed(&self) -> bool {
-        self.errors >= self.view.plurality()
+        self.errors >= self.view.weighted_plurality()
+    }
+
+    // Directs each replica whose latest shard disagrees with the reconciled
+    // exceptions (and is not already reconciling) to submit them
+    fn requery(&mut self, command_inlets: &mut HashMap<Identity, CommandInlet>) {
+        let disagreeing = self
+            .shards
+            .iter()
+            .filter(|(replica, (_, shard))| {
+                !self.pending.contains(*replica) && !self.reconciled(shard)
+            })
+            .map(|(replica, _)| *replica)
+            .collect::<Vec<_>>();
+
+        for replica in disagreeing {
+            let equivocations = self.equivocations.values().cloned().collect();
+
+            let _ = command_inlets
+                .get_mut(&replica)
+                .unwrap()
+                .send(Command::SubmitExceptions(equivocations));
+
+            self.pending.insert(replica);
+        }
     }
 
     async fn run(
         mut self,
         update_outlet: &mut UpdateOutlet,
+        command_inlets: &mut HashMap<Identity, CommandInlet>,
     ) -> Result<BatchCommit, Top<CollectorError>> {
         while !self.succeeded() && !self.failed() {
             // A copy of `update_inlet` is held by `orchestrate`.
             // As a result, `update_outlet.recv()` cannot return `None`.
             match update_outlet.recv().await.unwrap() {
                 (replica, Update::CommitShard(shard)) => {
+                    self.pending.remove(&replica);
+
+                    // Because `shard` was validated by the relevant `submit` slave, each of its
+                    // exceptions is backed by a valid `Equivocation`: extend the reconciled exceptions
+                    for equivocation in shard.equivocations() {
+                        self.equivocations
+                            .entry(equivocation.id())
+                            .or_insert_with(|| equivocation.clone());
+                    }
+
                     let keycard = self.view.members().get(&replica).unwrap().clone();
-                    self.shards.push((keycard, shard));
+                    self.shards.insert(replica, (keycard, shard));
+
+                    self.requery(command_inlets);
                 }
-                (_, Update::Error) => {
-                    self.errors += 1;
+                (replica, Update::Error) => {
+                    // A shard from a replica that failed to reconcile cannot count towards quorum
+                    self.pending.remove(&replica);
+                    self.shards.remove(&replica);
+
+                    self.errors += self.view.weight(&replica);
                 }
-                (_, Update::WitnessShard(_)) => {}
+                (_, Update::WitnessShard(..)) | (_, Update::Malformed(..)) => {}
             }
         }
 
         if self.succeeded() {
-            Ok(BatchCommit::new(self.view, self.root, self.shards))
+            // Finalize `BatchCommit` only over shards that agree with the reconciled exceptions
+            let shards = self
+                .shards
+                .values()
+                .filter(|(_, shard)| self.reconciled(shard))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            Ok(BatchCommit::new(
+                self.view,
+                &self.namespace,
+                self.root,
+                self.exclusions,
+                shards,
+            ))
         } else {
             CollectorError::ErrorPlurality.fail().spot(here!())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Entry,
+        discovery::Embedded,
+        prepare::{DefectReason, Extract, Prepare, WitnessedBatch},
+        signup::test::assignment,
+        view::test::InstallGenerator,
+    };
+
+    use std::{collections::BTreeMap, time::Duration};
+
+    use talk::{
+        crypto::{primitives::hash, KeyChain},
+        net::{test::System, SessionListener},
+    };
+
+    use zebra::vector::Vector;
+
+    // Builds a `Submission` of `len` `Prepare`s, whose `forged`-th individual signature is invalid
+    fn submission(
+        generator: &InstallGenerator,
+        view: &View,
+        len: usize,
+        forged: usize,
+    ) -> Submission {
+        let ids = (0..len).map(|id| id as Id).collect::<Vec<_>>();
+        submission_of(generator, view, &ids, forged)
+    }
+
+    // Builds a `Submission` of one `Prepare` for each of `ids` (in order),
+    // whose `forged`-th individual signature is invalid
+    fn submission_of(
+        generator: &InstallGenerator,
+        view: &View,
+        ids: &[Id],
+        forged: usize,
+    ) -> Submission {
+        let clients = ids.iter().map(|_| KeyChain::random()).collect::<Vec<_>>();
+
+        let assignments = clients
+            .iter()
+            .zip(ids.iter().copied())
+            .map(|(client, id)| {
+                assignment(
+                    &generator.keychains,
+                    view,
+                    &Namespace::default(),
+                    client,
+                    id,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let prepares = ids
+            .iter()
+            .map(|id| Prepare::new(Entry { id: *id, height: 1 }, hash::hash(id).unwrap()))
+            .collect::<Vec<_>>();
+
+        let individual_signatures = clients
+            .iter()
+            .zip(prepares.iter())
+            .enumerate()
+            .map(|(index, (client, prepare))| {
+                let signature = if index == forged {
+                    KeyChain::random().sign(prepare)
+                } else {
+                    client.sign(prepare)
+                };
+
+                Some(signature.unwrap())
+            })
+            .collect::<Vec<_>>();
+
+        let reduction_signature = clients[0].multisign(&prepares[0]).unwrap();
+
+        Submission::new(
+            assignments,
+            Vector::new(prepares).unwrap(),
+            reduction_signature,
+            individual_signatures,
+            BTreeMap::new(),
+        )
+    }
+
+    // Builds an `Extract` of a single-element batch for `Id` 1, committing to `commitment`
+    fn extract(generator: &InstallGenerator, view: &View, commitment: u64) -> Extract {
+        let prepares = Vector::new(vec![Prepare::new(
+            Entry { id: 1, height: 1 },
+            hash::hash(&commitment).unwrap(),
+        )])
+        .unwrap();
+
+        let statement =
+            WitnessStatement::partial(&Namespace::default(), prepares.root(), BTreeSet::new());
+
+        let components = generator
+            .keychains
+            .iter()
+            .take(view.plurality())
+            .map(|keychain| {
+                (
+                    keychain.keycard().identity(),
+                    keychain.multisign(&statement).unwrap(),
+                )
+            });
+
+        let witness = Certificate::aggregate_plurality(view, components);
+
+        WitnessedBatch::new(view.identifier(), prepares, BitVec::new(), witness).extract(0)
+    }
+
+    fn flags(len: usize, flagged: &[usize]) -> BitVec {
+        let mut flags = BitVec::from_elem(len, false);
+
+        for index in flagged {
+            flags.set(*index, true);
+        }
+
+        flags
+    }
+
+    fn shard(keychain: &KeyChain, submission: &Submission, flagged: &BitVec) -> MultiSignature {
+        let statement = WitnessStatement::partial(
+            &Namespace::default(),
+            submission.root(),
+            submission.exclusions(flagged),
+        );
+
+        keychain.multisign(&statement).unwrap()
+    }
+
+    fn channels(
+        view: &View,
+    ) -> (
+        HashMap<Identity, CommandInlet>,
+        HashMap<Identity, CommandOutlet>,
+    ) {
+        view.members()
+            .keys()
+            .map(|member| {
+                let (command_inlet, command_outlet) = mpsc::unbounded_channel();
+                ((*member, command_inlet), (*member, command_outlet))
+            })
+            .unzip()
+    }
+
+    #[tokio::test]
+    async fn malformed() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let members = view.members().keys().copied().collect::<Vec<_>>();
+
+        // Element 2 duplicates the `Id` of element 1
+        let submission = Arc::new(submission_of(&generator, &view, &[0, 1, 1, 2], usize::MAX));
+
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+        let (mut command_inlets, _command_outlets) = channels(&view);
+
+        let mut collector = WitnessCollector::new(
+            view.clone(),
+            &Namespace::default(),
+            submission.clone(),
+            true,
+        );
+
+        // A lone report that does not match the broker's own diagnosis is not trusted
+        let bogus = vec![BatchDefect {
+            index: 3,
+            reason: DefectReason::UnsortedId,
+        }];
+
+        update_inlet
+            .send((members[0], Update::Malformed(bogus)))
+            .unwrap();
+
+        update_inlet.send((members[0], Update::Error)).unwrap();
+
+        let _ = time::timeout(
+            Duration::from_millis(100),
+            collector.progress(&mut update_outlet, &mut command_inlets),
+        )
+        .await;
+
+        assert!(collector.defects.is_none());
+        assert!(matches!(collector.complete(), Ok(false)));
+
+        // A report that matches the broker's own diagnosis is confirmed at once
+        update_inlet
+            .send((members[1], Update::Malformed(submission.defects())))
+            .unwrap();
+
+        collector
+            .progress(&mut update_outlet, &mut command_inlets)
+            .await;
+
+        assert!(collector.complete().is_err());
+
+        assert_eq!(
+            collector.defects,
+            Some(vec![BatchDefect {
+                index: 2,
+                reason: DefectReason::DuplicateId,
+            }])
+        );
+
+        // Defects the broker cannot diagnose are confirmed by a plurality of reports
+        let submission = Arc::new(submission(&generator, &view, 3, usize::MAX));
+        let mut collector =
+            WitnessCollector::new(view.clone(), &Namespace::default(), submission, true);
+
+        let reported = vec![BatchDefect {
+            index: 1,
+            reason: DefectReason::SignatureMissing,
+        }];
+
+        for member in &members[..view.plurality()] {
+            update_inlet
+                .send((*member, Update::Malformed(reported.clone())))
+                .unwrap();
+        }
+
+        collector
+            .progress(&mut update_outlet, &mut command_inlets)
+            .await;
+
+        assert_eq!(collector.defects, Some(reported));
+    }
+
+    #[tokio::test]
+    async fn out_of_order() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let submission = Arc::new(submission(&generator, &view, 3, usize::MAX));
+
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+        let (mut command_inlets, _command_outlets) = channels(&view);
+
+        let mut collector = WitnessCollector::new(
+            view.clone(),
+            &Namespace::default(),
+            submission.clone(),
+            true,
+        );
+
+        // Commit shards received before any witness count as errors of their replicas
+
+        for keychain in generator.keychains.iter().take(view.plurality()) {
+            let shard = BatchCommitShard::new(
+                keychain,
+                &Namespace::default(),
+                view.identifier(),
+                submission.root(),
+                BTreeSet::new(),
+                Vec::new(),
+            );
+
+            update_inlet
+                .send((keychain.keycard().identity(), Update::CommitShard(shard)))
+                .unwrap();
+        }
+
+        collector
+            .progress(&mut update_outlet, &mut command_inlets)
+            .await;
+
+        assert!(collector.complete().is_err());
+    }
+
+    #[tokio::test]
+    async fn lone_flags() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        // Element 1 carries an invalid individual signature
+        let submission = Arc::new(submission(&generator, &view, 3, 1));
+
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+        let (mut command_inlets, mut command_outlets) = channels(&view);
+
+        let mut collector = WitnessCollector::new(
+            view.clone(),
+            &Namespace::default(),
+            submission.clone(),
+            true,
+        );
+
+        // A faulty replica flags element 1 (correctly) and element 2 (which is valid)
+        let faulty = &generator.keychains[0];
+        let flagged = flags(3, &[1, 2]);
+
+        update_inlet
+            .send((
+                faulty.keycard().identity(),
+                Update::WitnessShard(flagged.clone(), shard(faulty, &submission, &flagged)),
+            ))
+            .unwrap();
+
+        // Correct replicas flag element 1 only
+        for correct in &generator.keychains[1..3] {
+            let flagged = flags(3, &[1]);
+
+            update_inlet
+                .send((
+                    correct.keycard().identity(),
+                    Update::WitnessShard(flagged.clone(), shard(correct, &submission, &flagged)),
+                ))
+                .unwrap();
+        }
+
+        collector
+            .progress(&mut update_outlet, &mut command_inlets)
+            .await;
+
+        assert!(collector.complete().unwrap());
+
+        // Element 2 is not excluded: the faulty replica alone cannot censor it
+        let (_, excluded, _) = collector.finalize();
+        assert_eq!(excluded, flags(3, &[1]));
+        assert_eq!(
+            submission.exclusions(&excluded),
+            vec![1].into_iter().collect::<BTreeSet<Id>>()
+        );
+
+        // No replica was asked to witness further exclusions
+        for command_outlet in command_outlets.values_mut() {
+            assert!(command_outlet.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn plurality_flags() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        // All individual signatures are valid (element 3 is out of range)
+        let submission = Arc::new(submission(&generator, &view, 3, 3));
+
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+        let (mut command_inlets, mut command_outlets) = channels(&view);
+
+        let mut collector = WitnessCollector::new(
+            view.clone(),
+            &Namespace::default(),
+            submission.clone(),
+            true,
+        );
+
+        // A plurality of replicas flags element 2 (e.g., its account is closed)
+        let flagged = flags(3, &[2]);
+
+        for keychain in &generator.keychains[0..2] {
+            update_inlet
+                .send((
+                    keychain.keycard().identity(),
+                    Update::WitnessShard(flagged.clone(), shard(keychain, &submission, &flagged)),
+                ))
+                .unwrap();
+        }
+
+        // The first replica to flag element 2 cannot witness until a second one
+        // does, and is then asked to witness the extended target
+        time::timeout(
+            Duration::from_millis(100),
+            collector.progress(&mut update_outlet, &mut command_inlets),
+        )
+        .await
+        .unwrap_err();
+
+        assert!(!collector.complete().unwrap());
+
+        let first = generator.keychains[0].keycard().identity();
+
+        match command_outlets.get_mut(&first).unwrap().try_recv() {
+            Ok(Command::SubmitExclusions(excluded)) => assert_eq!(excluded, flagged),
+            _ => panic!("expected `Command::SubmitExclusions`"),
+        }
+
+        update_inlet
+            .send((
+                first,
+                Update::WitnessShard(
+                    flagged.clone(),
+                    shard(&generator.keychains[0], &submission, &flagged),
+                ),
+            ))
+            .unwrap();
+
+        collector
+            .progress(&mut update_outlet, &mut command_inlets)
+            .await;
+
+        assert!(collector.complete().unwrap());
+
+        let (_, excluded, _) = collector.finalize();
+        assert_eq!(excluded, flagged);
+    }
+
+    #[tokio::test]
+    async fn unresponsive() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let submission = Arc::new(submission(&generator, &view, 3, 3));
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = Arc::new(embedded.client(Default::default()));
+
+        let replica = generator.keychains[0].clone();
+
+        let System {
+            mut connectors,
+            mut listeners,
+            ..
+        } = System::setup_with_keychains(vec![KeyChain::random(), replica.clone()]).await;
+
+        let connector = Arc::new(SessionConnector::new(connectors.remove(0)));
+        let mut listener = SessionListener::new(listeners.remove(1));
+
+        let (command_inlet, command_outlet) = mpsc::unbounded_channel();
+        let (update_inlet, mut update_outlet) = mpsc::unbounded_channel();
+
+        let receive_timeout = Timeout::new(Duration::from_millis(100));
+
+        let slave = tokio::spawn(Broker::submit(
+            discovery,
+            view,
+            connector,
+            replica.keycard(),
+            submission,
+            false,
+            command_outlet,
+            update_inlet,
+            receive_timeout.clone(),
+        ));
+
+        // `replica` receives the batch and its signatures, but never responds
+
+        let (_, mut session) = listener.accept().await;
+        session.receive::<PrepareRequest>().await.unwrap();
+
+        command_inlet.send(Command::SubmitSignatures).unwrap();
+        session.receive::<PrepareRequest>().await.unwrap();
+
+        match update_outlet.recv().await {
+            Some((identity, Update::Error)) => assert_eq!(identity, replica.keycard().identity()),
+            _ => panic!("expected `Update::Error`"),
+        }
+
+        assert_eq!(receive_timeout.expired(), 1);
+
+        slave.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconcile() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let submission = submission(&generator, &view, 3, 3);
+        let root = submission.root();
+        let exclusions = submission.exclusions(&BitVec::new());
+
+        let equivocation =
+            Equivocation::new(extract(&generator, &view, 0), extract(&generator, &view, 1));
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = embedded.client(Default::default());
+
+        let replica = generator.keychains[0].clone();
+
+        let System {
+            mut connectors,
+            mut listeners,
+            ..
+        } = System::setup_with_keychains(vec![KeyChain::random(), replica.clone()]).await;
+
+        let connector = SessionConnector::new(connectors.remove(0));
+        let mut listener = SessionListener::new(listeners.remove(1));
+
+        let receive_timeout = Timeout::new(Duration::from_millis(100));
+
+        // `replica` first reconciles the submitted exception, then
+        // ignores it, and finally never responds
+
+        let responder = {
+            let replica = replica.clone();
+            let view = view.identifier();
+            let exclusions = exclusions.clone();
+
+            tokio::spawn(async move {
+                let mut sessions = Vec::new();
+
+                for accounted in [true, false] {
+                    let (_, mut session) = listener.accept().await;
+
+                    let equivocations = match session.receive::<PrepareRequest>().await.unwrap() {
+                        PrepareRequest::Reconcile(request_root, equivocations) => {
+                            assert_eq!(request_root, root);
+                            equivocations
+                        }
+                        _ => panic!("expected `PrepareRequest::Reconcile`"),
+                    };
+
+                    let equivocations = if accounted { equivocations } else { Vec::new() };
+
+                    let shard = BatchCommitShard::new(
+                        &replica,
+                        &Namespace::default(),
+                        view,
+                        root,
+                        exclusions.clone(),
+                        equivocations,
+                    );
+
+                    session
+                        .send(&PrepareResponse::CommitShard(shard))
+                        .await
+                        .unwrap();
+
+                    sessions.push(session);
+                }
+
+                let (_, mut session) = listener.accept().await;
+                session.receive::<PrepareRequest>().await.unwrap();
+
+                sessions.push(session);
+                sessions
+            })
+        };
+
+        let shard = Broker::reconcile(
+            &discovery,
+            &view,
+            &connector,
+            &replica.keycard(),
+            &submission,
+            &BitVec::new(),
+            vec![equivocation.clone()],
+            &receive_timeout,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(shard.exceptions(), BTreeSet::from([1]));
+
+        // A shard that fails to account for the submitted exception is rejected
+        assert!(Broker::reconcile(
+            &discovery,
+            &view,
+            &connector,
+            &replica.keycard(),
+            &submission,
+            &BitVec::new(),
+            vec![equivocation.clone()],
+            &receive_timeout,
+        )
+        .await
+        .is_err());
+
+        // An unresponsive `replica` cannot stall reconciliation
+        assert!(Broker::reconcile(
+            &discovery,
+            &view,
+            &connector,
+            &replica.keycard(),
+            &submission,
+            &BitVec::new(),
+            vec![equivocation],
+            &receive_timeout,
+        )
+        .await
+        .is_err());
+
+        assert_eq!(receive_timeout.expired(), 1);
+
+        responder.await.unwrap();
+    }
+}
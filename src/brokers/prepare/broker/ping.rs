@@ -1,6 +1,6 @@
 use crate::{
     brokers::prepare::{broker_settings::PingTaskSettings, Broker},
-    data::PingBoard,
+    data::{self, ClockBoard, PingBoard},
     processing::messages::{PrepareRequest, PrepareResponse},
 };
 
@@ -28,21 +28,24 @@ enum PingError {
 impl Broker {
     pub(in crate::brokers::prepare::broker) async fn ping(
         board: PingBoard,
+        clock_board: ClockBoard,
         connector: Arc<SessionConnector>,
         replica: Identity,
         settings: PingTaskSettings,
     ) {
         loop {
             let start = Instant::now();
+            let sent = data::timestamp();
 
-            let ping: Result<Duration, Top<PingError>> = (async {
+            // Pings piggyback `replica`'s clock, allowing `clock_board` to estimate its offset
+            let ping: Result<(Duration, u64, bool), Top<PingError>> = (async {
                 let mut session = connector
                     .connect(replica)
                     .await
                     .pot(PingError::ConnectionFailed, here!())?;
 
                 session
-                    .send(&PrepareRequest::Ping)
+                    .send(&PrepareRequest::ClockPing)
                     .await
                     .pot(PingError::ConnectionError, here!())?;
 
@@ -51,22 +54,50 @@ impl Broker {
                     .await
                     .pot(PingError::ConnectionError, here!())?;
 
-                match response {
-                    PrepareResponse::Pong => Ok(()),
+                let remote = match response {
+                    PrepareResponse::ClockPong(remote) => Ok(remote),
                     _ => PingError::UnexpectedResponse.fail().spot(here!()),
                 }?;
 
-                Ok(start.elapsed())
+                let ping = start.elapsed();
+
+                // Replicas unaware of `PrepareResponse::Capabilities` end the
+                // session after `ClockPong`: they do not accept compression
+                let compression = match session.receive::<PrepareResponse>().await {
+                    Ok(PrepareResponse::Capabilities(compression)) => compression,
+                    _ => false,
+                };
+
+                session.end();
+
+                Ok((ping, remote, compression))
             })
             .await;
 
             // If pinging was impossible, assign `replica` the highest
             // possible score (replicas whose pings failed are at the
-            // end of the `PingBoard`)
-            let ping = ping.unwrap_or(Duration::MAX);
+            // end of the `PingBoard`), and forget its clock offset
+            // and capabilities
+            let ping = match ping {
+                Ok((ping, remote, compression)) => {
+                    clock_board.submit(replica, sent, remote, data::timestamp());
+                    board.set_compression(replica, compression);
+                    ping
+                }
+                Err(_) => {
+                    clock_board.reset(replica);
+                    board.set_compression(replica, false);
+                    Duration::MAX
+                }
+            };
+
             board.submit(replica, ping);
 
-            time::sleep(settings.ping_interval).await;
+            if ping < Duration::MAX {
+                time::sleep(settings.ping_interval).await;
+            } else {
+                time::sleep(settings.ping_retry_interval).await;
+            }
         }
     }
 }
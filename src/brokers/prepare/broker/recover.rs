@@ -0,0 +1,107 @@
+use crate::{
+    benchmark::LatencyBudget,
+    brokers::prepare::{
+        broker::{Brokerage, Journal},
+        broker_settings::BrokerTaskSettings,
+        Broker,
+    },
+    data::{PingBoard, Sponge},
+    discovery::Client,
+    view::View,
+};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+    time::Instant,
+};
+
+use talk::net::SessionConnector;
+
+use tokio::sync::oneshot;
+
+impl Broker {
+    pub(in crate::brokers::prepare::broker) async fn recover(
+        discovery: Arc<Client>,
+        view: View,
+        ping_board: PingBoard,
+        connector: Arc<SessionConnector>,
+        brokerage_sponge: Arc<Sponge<Brokerage>>,
+        journal: Journal,
+        settings: BrokerTaskSettings,
+    ) {
+        // If `journal` cannot be read, no brokerage can be resumed
+
+        let (recovered, requests) = match journal.recover(&view).await {
+            Ok(recovered) => recovered,
+            Err(_) => return,
+        };
+
+        // Requests that were pending in the previous run's sponge are brokered anew.
+        // Their clients are gone: nobody listens on their brokerages' inlets (and no
+        // reduction shard is ever provided, so each request retains its individual
+        // signature).
+
+        for (request, journal_entry) in requests {
+            let (reduction_inlet, _) = oneshot::channel();
+            let (commit_inlet, _) = oneshot::channel();
+            let (budget_inlet, _) = oneshot::channel();
+
+            brokerage_sponge.push(Brokerage {
+                client: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                request,
+                arrival: Instant::now(),
+                reduction_inlet,
+                commit_inlet,
+                budget_inlet,
+                journal_entry: Some(journal_entry),
+            });
+        }
+
+        // Resume each recovered brokerage: clients of in-flight brokerages lost
+        // their connection to the previous run, but orchestrating and publishing
+        // the batch allows a retrying client's `Prepare` to be committed
+
+        recovered
+            .into_iter()
+            .map(|(submission, entry)| {
+                let discovery = discovery.clone();
+                let view = view.clone();
+                let ping_board = ping_board.clone();
+                let connector = connector.clone();
+                let settings = settings.clone();
+
+                async move {
+                    let submission =
+                        Broker::compress(submission, settings.compression_threshold).await;
+
+                    let commit = Broker::orchestrate(
+                        discovery,
+                        view.clone(),
+                        ping_board,
+                        connector.clone(),
+                        Arc::new(submission),
+                        settings,
+                        // Clients of recovered brokerages are gone: nobody collects their budget
+                        &mut LatencyBudget::new(),
+                        // A recovered batch is not retried if malformed: its elements covered
+                        // by the reduction signature carry no individual signature
+                        &mut Vec::new(),
+                    )
+                    .await;
+
+                    if let Ok(commit) = commit {
+                        Broker::publish_commit(&view, connector.as_ref(), commit).await;
+                    }
+
+                    // Successful or not, the brokerage is over
+                    entry.complete();
+                }
+            })
+            .collect::<FuturesUnordered<_>>()
+            .collect::<Vec<_>>()
+            .await;
+    }
+}
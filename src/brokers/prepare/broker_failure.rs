@@ -4,4 +4,23 @@ use serde::{Deserialize, Serialize};
 pub(crate) enum BrokerFailure {
     Throttle,
     Error,
+    DryRun,
+    Unavailable,
+    Busy,
+    // The `Request` pertains to a `Namespace` other than the `Broker`'s
+    ForeignNamespace,
+}
+
+impl BrokerFailure {
+    // Label under which the failure is accounted for in `Metrics`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BrokerFailure::Throttle => "throttle",
+            BrokerFailure::Error => "error",
+            BrokerFailure::DryRun => "dry_run",
+            BrokerFailure::Unavailable => "unavailable",
+            BrokerFailure::Busy => "busy",
+            BrokerFailure::ForeignNamespace => "foreign_namespace",
+        }
+    }
 }
@@ -1,26 +1,115 @@
-use crate::data::SpongeSettings;
+use crate::{
+    benchmark::{Metrics, MetricsSettings},
+    brokers::prepare::{broker::Journal, DryRunLog},
+    data::{ClockSettings, MemorySettings, QuorumMonitor, SpongeSettings, StragglerBoard},
+    processing::Timeout,
+    signup::AssignmentVerifierSettings,
+    telemetry::{ExporterSettings, Registry},
+};
 
-use std::time::Duration;
+use std::{collections::HashMap, net::IpAddr, path::PathBuf, time::Duration};
+
+use talk::crypto::Identity;
 
 #[derive(Debug, Clone)]
 pub(crate) struct BrokerSettings {
     pub brokerage_sponge_settings: SpongeSettings,
 
+    // Batches are assembled fairly across clients (identified by IP address):
+    // each client contributes at most `client_quota` brokerages to a batch, and
+    // is served in proportion to its weight in `client_weights` (by default, 1)
+    pub client_quota: Option<usize>,
+    pub client_weights: HashMap<IpAddr, usize>,
+
+    // If `Some`, at most `max_in_flight` batches are brokered concurrently:
+    // further brokerages wait in the brokerage sponge
+    pub max_in_flight: Option<usize>,
+
     pub reduction_threshold: f64,
     pub reduction_timeout: Duration,
     pub optimistic_witness_timeout: Duration,
+    // If the fastest quorum of replicas fails to provide a witness within
+    // `backup_timeout` (past `optimistic_witness_timeout`), signatures are
+    // submitted to all remaining replicas as well
+    pub backup_timeout: Duration,
+    // Replicas that fail to provide a witness shard for `straggler_threshold`
+    // consecutive batches are reported, and asked for shards last
+    pub straggler_threshold: usize,
+
+    // If `true`, batches can be witnessed by all replicas but for the elements
+    // whose individual signature is invalid (or that a plurality of replicas
+    // refuse to witness), instead of failing altogether. No single replica can
+    // get an element excluded (see `WitnessCollector`), hence enabled by default.
+    pub partial_witness: bool,
+
+    // If `Some`, batches whose serialized form exceeds `compression_threshold`
+    // bytes are compressed before submission (all replicas must support
+    // `PrepareRequest::CompressedBatch`)
+    pub compression_threshold: Option<usize>,
 
     pub ping_interval: Duration,
+    // Replicas whose ping failed (e.g., because they are still starting up)
+    // are pinged again after `ping_retry_interval`
+    pub ping_retry_interval: Duration,
+    pub clock_settings: ClockSettings,
+
+    // If fewer than a quorum of replicas are responsive for longer than
+    // `degradation_grace`, the `Broker` is degraded: pending and new brokerages
+    // fail with `BrokerFailure::Unavailable` until a quorum is responsive again
+    pub degradation_grace: Duration,
+
+    pub receive_timeout: Duration,
+
+    // If `Some`, assembled batches are persisted in `journal_directory`
+    // until brokered, and resumed by a `Broker` restarted on the same directory
+    pub journal_directory: Option<PathBuf>,
+
+    // If `Some` (and `journal_directory` is `Some`), the journal is streamed to the
+    // `Standby` identified by `standby`, which takes over the journal (and its in-flight
+    // brokerages) if this `Broker` fails. The `Standby` is reached through the `Broker`'s
+    // `Connector`, whose identity it must be configured with (see `StandbySettings`).
+    // Heartbeats are sent every `handoff_interval`, which must be shorter than the
+    // `Standby`'s `failover_timeout`.
+    pub standby: Option<Identity>,
+    pub handoff_interval: Duration,
+
+    // If `true`, batches are assembled and reduced as usual, but never submitted:
+    // no replica is contacted, each batch is reported to the `Broker`'s `DryRunLog`
+    // and its clients are failed with `BrokerFailure::DryRun`
+    pub dry_run: bool,
+
+    // Watermarks on the (serialized) size of the `Request`s held by the `Broker`:
+    // while shedding, the `Broker` stops accepting connections, and fails the
+    // `Request`s it receives with `BrokerFailure::Busy`
+    pub memory: MemorySettings,
+
+    // Caches the `IdAssignment`s of validated `Request`s (see `AssignmentVerifier`)
+    pub assignment_verifier: AssignmentVerifierSettings,
+
+    // Brokered prepares are accounted for in the `Broker`'s `Metrics`
+    pub metrics: MetricsSettings,
+    // Serves the `Broker`'s `Registry` to Prometheus
+    pub exporter: ExporterSettings,
 }
 
 pub(in crate::brokers::prepare) struct BrokerSettingsComponents {
     pub flush: FlushTaskSettings,
     pub broker: BrokerTaskSettings,
     pub ping: PingTaskSettings,
+    pub clock: ClockSettings,
+    pub degradation_grace: Duration,
+    pub handoff: Option<HandoffTaskSettings>,
+    pub memory: MemorySettings,
+    pub assignment_verifier: AssignmentVerifierSettings,
+    pub exporter: ExporterSettings,
 }
+
 #[derive(Debug, Clone)]
 pub(in crate::brokers::prepare) struct FlushTaskSettings {
     pub brokerage_sponge_settings: SpongeSettings,
+    pub client_quota: Option<usize>,
+    pub client_weights: HashMap<IpAddr, usize>,
+    pub max_in_flight: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -28,27 +117,76 @@ pub(in crate::brokers::prepare) struct BrokerTaskSettings {
     pub reduction_threshold: f64,
     pub reduction_timeout: Duration,
     pub optimistic_witness_timeout: Duration,
+    pub backup_timeout: Duration,
+    pub partial_witness: bool,
+    pub compression_threshold: Option<usize>,
+    pub receive_timeout: Timeout,
+    pub journal: Option<Journal>,
+    pub dry_run: Option<DryRunLog>,
+    pub quorum_monitor: QuorumMonitor,
+    pub straggler_board: StragglerBoard,
+    pub metrics: Metrics,
+    pub registry: Registry,
+}
+
+#[derive(Debug, Clone)]
+pub(in crate::brokers::prepare) struct HandoffTaskSettings {
+    pub standby: Identity,
+    pub handoff_interval: Duration,
 }
 
 #[derive(Debug, Clone)]
 pub(in crate::brokers::prepare) struct PingTaskSettings {
     pub ping_interval: Duration,
+    pub ping_retry_interval: Duration,
 }
 
 impl BrokerSettings {
     pub(in crate::brokers::prepare) fn into_components(self) -> BrokerSettingsComponents {
+        let handoff = match (&self.journal_directory, self.standby) {
+            (Some(_), Some(standby)) => Some(HandoffTaskSettings {
+                standby,
+                handoff_interval: self.handoff_interval,
+            }),
+            _ => None,
+        };
+
         BrokerSettingsComponents {
             flush: FlushTaskSettings {
                 brokerage_sponge_settings: self.brokerage_sponge_settings,
+                client_quota: self.client_quota,
+                client_weights: self.client_weights,
+                max_in_flight: self.max_in_flight,
             },
             broker: BrokerTaskSettings {
                 reduction_threshold: self.reduction_threshold,
                 reduction_timeout: self.reduction_timeout,
                 optimistic_witness_timeout: self.optimistic_witness_timeout,
+                backup_timeout: self.backup_timeout,
+                partial_witness: self.partial_witness,
+                compression_threshold: self.compression_threshold,
+                receive_timeout: Timeout::new(self.receive_timeout),
+                journal: self.journal_directory.map(Journal::new),
+                dry_run: if self.dry_run {
+                    Some(DryRunLog::new())
+                } else {
+                    None
+                },
+                quorum_monitor: QuorumMonitor::new(),
+                straggler_board: StragglerBoard::new(self.straggler_threshold),
+                metrics: Metrics::new(self.metrics),
+                registry: Registry::new(),
             },
             ping: PingTaskSettings {
                 ping_interval: self.ping_interval,
+                ping_retry_interval: self.ping_retry_interval,
             },
+            clock: self.clock_settings,
+            degradation_grace: self.degradation_grace,
+            handoff,
+            memory: self.memory,
+            assignment_verifier: self.assignment_verifier,
+            exporter: self.exporter,
         }
     }
 }
@@ -58,11 +196,41 @@ impl Default for BrokerSettings {
         BrokerSettings {
             brokerage_sponge_settings: Default::default(),
 
+            client_quota: None,
+            client_weights: HashMap::new(),
+
+            max_in_flight: None,
+
             reduction_threshold: 1.,
             reduction_timeout: Duration::from_secs(1),
             optimistic_witness_timeout: Duration::from_secs(1),
+            backup_timeout: Duration::from_secs(2),
+            straggler_threshold: 3,
+            partial_witness: true,
+
+            compression_threshold: None,
 
             ping_interval: Duration::from_secs(60),
+            ping_retry_interval: Duration::from_secs(1),
+            clock_settings: Default::default(),
+
+            degradation_grace: Duration::from_secs(10),
+
+            receive_timeout: Duration::from_secs(10),
+
+            journal_directory: None,
+
+            standby: None,
+            handoff_interval: Duration::from_secs(1),
+
+            dry_run: false,
+
+            memory: MemorySettings::default(),
+
+            assignment_verifier: AssignmentVerifierSettings::default(),
+
+            metrics: MetricsSettings::default(),
+            exporter: ExporterSettings::default(),
         }
     }
 }
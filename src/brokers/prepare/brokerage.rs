@@ -1,65 +1,121 @@
 use crate::{
-    brokers::prepare::{BrokerFailure, Reduction, Request},
-    prepare::{BatchCommit, Prepare},
+    benchmark::LatencyBudget,
+    brokers::prepare::{broker::JournalEntry, BrokerFailure, Reduction, Request},
+    prepare::{BatchCommit, Delegated, Prepare},
     signup::IdAssignment,
+    telemetry::TraceContext,
 };
 
+use std::{collections::BTreeMap, net::IpAddr, time::Instant};
+
 use talk::crypto::primitives::sign::Signature;
 use tokio::sync::oneshot::Sender;
 
+use zebra::vector::Proof;
+
 type ReductionInlet = Sender<Result<Reduction, BrokerFailure>>;
-type CommitInlet = Sender<Result<BatchCommit, BrokerFailure>>;
+// If the batch was retried (see `Broker::broker`), `BatchCommit` comes with the
+// `Proof` of inclusion of the brokerage's `Prepare` in the retried batch
+type CommitInlet = Sender<Result<(BatchCommit, Option<Proof>), BrokerFailure>>;
+type BudgetInlet = Sender<LatencyBudget>;
 
 pub(in crate::brokers::prepare) struct Brokerage {
+    pub client: IpAddr,
     pub request: Request,
+    // Time at which `request` was received
+    pub arrival: Instant,
     pub reduction_inlet: ReductionInlet,
     pub commit_inlet: CommitInlet,
+    // Only fed if the brokerage succeeds
+    pub budget_inlet: BudgetInlet,
+    // If journaling is enabled, `request` is persisted until assembled in a batch
+    pub journal_entry: Option<JournalEntry>,
 }
 
 pub(in crate::brokers::prepare) struct UnzippedBrokerages {
     pub assignments: Vec<IdAssignment>,
     pub prepares: Vec<Prepare>,
     pub signatures: Vec<Signature>,
+    pub delegations: BTreeMap<usize, Delegated>,
+    pub traces: Vec<TraceContext>,
+    pub arrivals: Vec<Instant>,
+    pub journal_entries: Vec<JournalEntry>,
 
     pub reduction_inlets: Vec<ReductionInlet>,
     pub commit_inlets: Vec<CommitInlet>,
+    pub budget_inlets: Vec<BudgetInlet>,
 }
 
 impl Brokerage {
+    // Fails the brokerage before it is assembled in a batch
+    pub fn fail(self, failure: BrokerFailure) {
+        let _ = self.reduction_inlet.send(Err(failure));
+
+        if let Some(entry) = self.journal_entry {
+            entry.complete();
+        }
+    }
+
     pub fn unzip(brokerages: Vec<Brokerage>) -> UnzippedBrokerages {
         let mut assignments = Vec::new();
         let mut prepares = Vec::new();
         let mut signatures = Vec::new();
+        let mut delegations = BTreeMap::new();
+        let mut traces = Vec::new();
+        let mut arrivals = Vec::new();
+        let mut journal_entries = Vec::new();
 
         let mut reduction_inlets = Vec::new();
         let mut commit_inlets = Vec::new();
+        let mut budget_inlets = Vec::new();
 
-        for brokerage in brokerages {
+        for (index, brokerage) in brokerages.into_iter().enumerate() {
             let Brokerage {
                 request:
                     Request {
                         assignment,
                         prepare,
                         signature,
+                        delegated,
+                        trace,
+                        ..
                     },
+                arrival,
                 reduction_inlet,
                 commit_inlet,
+                budget_inlet,
+                journal_entry,
+                ..
             } = brokerage;
 
             assignments.push(assignment);
             prepares.push(prepare);
             signatures.push(signature);
 
+            if let Some(delegated) = delegated {
+                delegations.insert(index, delegated);
+            }
+
+            traces.extend(trace);
+            arrivals.push(arrival);
+            journal_entries.extend(journal_entry);
+
             reduction_inlets.push(reduction_inlet);
             commit_inlets.push(commit_inlet);
+            budget_inlets.push(budget_inlet);
         }
 
         UnzippedBrokerages {
             assignments,
             prepares,
             signatures,
+            delegations,
+            traces,
+            arrivals,
+            journal_entries,
             reduction_inlets,
             commit_inlets,
+            budget_inlets,
         }
     }
 }
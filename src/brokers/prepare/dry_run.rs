@@ -0,0 +1,102 @@
+use crate::brokers::prepare::Submission;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use talk::crypto::primitives::hash::Hash;
+
+// Number of most recent `DryRunReport`s retained by a `DryRunLog`
+const HISTORY: usize = 1024;
+
+// A `DryRunReport` describes a batch that a dry-running `Broker` would have
+// submitted to the replicas
+#[derive(Debug, Clone)]
+pub(crate) struct DryRunReport {
+    pub root: Hash,
+    pub prepares: usize,
+    pub assignments: usize,
+    // Number of `Prepare`s whose individual signature would have been submitted
+    // (their issuers did not provide a reduction shard in time)
+    pub individual_signatures: usize,
+    // Size (in bytes) of the batch request, after compression (if enabled)
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DryRunLog {
+    reports: Arc<Mutex<VecDeque<DryRunReport>>>,
+}
+
+impl DryRunReport {
+    pub(in crate::brokers::prepare) fn new(submission: &Submission) -> Self {
+        let individual_signatures = submission.individual_signatures();
+
+        let batch_size =
+            bincode::serialized_size(submission.requests.batch(true)).unwrap() as usize;
+
+        DryRunReport {
+            root: submission.root(),
+            prepares: submission.prepares().len(),
+            assignments: submission.assignments().len(),
+            individual_signatures,
+            batch_size,
+        }
+    }
+}
+
+impl DryRunLog {
+    pub fn new() -> Self {
+        DryRunLog {
+            reports: Arc::new(Mutex::new(VecDeque::with_capacity(HISTORY))),
+        }
+    }
+
+    pub(in crate::brokers::prepare) fn record(&self, report: DryRunReport) {
+        let mut reports = self.reports.lock().unwrap();
+
+        if reports.len() == HISTORY {
+            reports.pop_front();
+        }
+
+        reports.push_back(report);
+    }
+
+    // Most recent reports, oldest first
+    pub fn reports(&self) -> Vec<DryRunReport> {
+        self.reports.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use talk::crypto::primitives::hash;
+
+    fn report(index: usize) -> DryRunReport {
+        DryRunReport {
+            root: hash::hash(&(index as u64)).unwrap(),
+            prepares: index,
+            assignments: 0,
+            individual_signatures: 0,
+            batch_size: 0,
+        }
+    }
+
+    #[test]
+    fn history() {
+        let log = DryRunLog::new();
+
+        for index in 0..(HISTORY + 10) {
+            log.record(report(index));
+        }
+
+        let reports = log.reports();
+
+        assert_eq!(reports.len(), HISTORY);
+        assert_eq!(reports.first().unwrap().prepares, 10);
+        assert_eq!(reports.last().unwrap().prepares, HISTORY + 9);
+    }
+}
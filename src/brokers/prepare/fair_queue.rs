@@ -0,0 +1,169 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+};
+
+// A `FairQueue` assembles batches out of the brokerages of multiple clients
+// (identified by their IP address), so that no client can fill a batch at the
+// expense of others. Backlogged clients are served in round-robin fashion, each
+// contributing up to its weight (by default, 1) per round, and at most `quota`
+// items per batch. Items that do not fit in a batch are carried to the next.
+pub(in crate::brokers::prepare) struct FairQueue<Item> {
+    queues: HashMap<IpAddr, VecDeque<Item>>,
+    order: VecDeque<IpAddr>,
+    quota: Option<usize>,
+    weights: HashMap<IpAddr, usize>,
+}
+
+impl<Item> FairQueue<Item> {
+    pub fn new(quota: Option<usize>, weights: HashMap<IpAddr, usize>) -> Self {
+        FairQueue {
+            queues: HashMap::new(),
+            order: VecDeque::new(),
+            quota,
+            weights,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queues.values().map(VecDeque::len).sum()
+    }
+
+    pub fn push(&mut self, client: IpAddr, item: Item) {
+        let order = &mut self.order;
+
+        self.queues
+            .entry(client)
+            .or_insert_with(|| {
+                order.push_back(client);
+                VecDeque::new()
+            })
+            .push_back(item);
+    }
+
+    pub fn batch(&mut self, capacity: usize) -> Vec<Item> {
+        let mut batch = Vec::new();
+        let mut taken = HashMap::<IpAddr, usize>::new();
+
+        loop {
+            let mut progress = false;
+
+            // Serve each backlogged client once. Because served clients are moved
+            // to the back of `self.order`, clients left unserved when the batch
+            // fills up are served first in the next batch.
+            for _ in 0..self.order.len() {
+                if batch.len() >= capacity {
+                    return batch;
+                }
+
+                let client = self.order.pop_front().unwrap();
+                let taken = taken.entry(client).or_insert(0);
+
+                let weight = self.weights.get(&client).copied().unwrap_or(1).max(1);
+                let quota = self.quota.map_or(usize::MAX, |quota| quota - *taken);
+
+                let allowance = weight.min(quota).min(capacity - batch.len());
+
+                let queue = self.queues.get_mut(&client).unwrap();
+                let allowance = allowance.min(queue.len());
+
+                batch.extend(queue.drain(..allowance));
+                *taken += allowance;
+                progress |= allowance > 0;
+
+                if queue.is_empty() {
+                    self.queues.remove(&client);
+                } else {
+                    self.order.push_back(client);
+                }
+            }
+
+            if !progress || batch.len() >= capacity {
+                return batch;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    fn client(index: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, index))
+    }
+
+    fn count(batch: &[(IpAddr, usize)], index: u8) -> usize {
+        batch
+            .iter()
+            .filter(|(item_client, _)| *item_client == client(index))
+            .count()
+    }
+
+    #[test]
+    fn round_robin() {
+        let mut queue = FairQueue::new(None, HashMap::new());
+
+        for item in 0..10 {
+            queue.push(client(0), (client(0), item));
+        }
+
+        for item in 0..2 {
+            queue.push(client(1), (client(1), item));
+        }
+
+        let batch = queue.batch(6);
+
+        assert_eq!(count(&batch, 0), 4);
+        assert_eq!(count(&batch, 1), 2);
+
+        // Overflow is carried to the next batch, in order
+        assert_eq!(queue.len(), 6);
+        assert_eq!(
+            queue.batch(100),
+            (4..10).map(|item| (client(0), item)).collect::<Vec<_>>()
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn weights() {
+        let mut queue = FairQueue::new(None, vec![(client(0), 2)].into_iter().collect());
+
+        for item in 0..10 {
+            queue.push(client(0), (client(0), item));
+            queue.push(client(1), (client(1), item));
+        }
+
+        let batch = queue.batch(6);
+
+        assert_eq!(count(&batch, 0), 4);
+        assert_eq!(count(&batch, 1), 2);
+    }
+
+    #[test]
+    fn quota() {
+        let mut queue = FairQueue::new(Some(2), HashMap::new());
+
+        for item in 0..5 {
+            queue.push(client(0), (client(0), item));
+        }
+
+        queue.push(client(1), (client(1), 0));
+
+        let batch = queue.batch(6);
+
+        assert_eq!(count(&batch, 0), 2);
+        assert_eq!(count(&batch, 1), 1);
+
+        assert_eq!(queue.batch(6).len(), 2);
+        assert_eq!(queue.batch(6).len(), 1);
+        assert!(queue.is_empty());
+    }
+}
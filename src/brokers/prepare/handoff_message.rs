@@ -0,0 +1,20 @@
+use crate::data::enveloped;
+
+use serde::{Deserialize, Serialize};
+
+// Messages streamed by an active `Broker` to its `Standby`, mirroring the
+// active's `Journal` entry by entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+#[repr(u8)]
+pub(in crate::brokers::prepare) enum HandoffMessage {
+    Record { name: String, record: Vec<u8> },
+    Complete { name: String },
+    Heartbeat,
+    // Names of all entries of the active `Journal` as of a snapshot (following the
+    // snapshot's `Record`s): the `Standby` removes all other entries (e.g., entries
+    // whose `Complete` was lost while disconnected)
+    Listing { names: Vec<String> },
+}
+
+enveloped!(HandoffMessage);
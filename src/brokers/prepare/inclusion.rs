@@ -21,16 +21,24 @@ pub(crate) struct Inclusion {
 pub(crate) enum InclusionError {
     #[doom(description("`Proof` invalid"))]
     ProofInvalid,
+    #[doom(description("Batch is not strictly increasing by `Id`"))]
+    BatchUnordered,
+    #[doom(description("Batch does not include the `Prepare`"))]
+    PrepareMissing,
+    #[doom(description("Batch root does not match"))]
+    RootMismatch,
 }
 
 impl Inclusion {
-    pub fn batch(prepares: &Vector<Prepare>) -> Vec<Inclusion> {
-        (0..prepares.len())
-            .map(|index| Inclusion {
-                root: prepares.root(),
-                proof: prepares.prove(index),
-            })
-            .collect()
+    // Proofs are generated lazily, as the returned iterator is consumed: for
+    // large batches, this avoids materializing all `Inclusion`s at once
+    pub fn batch(prepares: &Vector<Prepare>) -> impl Iterator<Item = Inclusion> + '_ {
+        let root = prepares.root();
+
+        (0..prepares.len()).map(move |index| Inclusion {
+            root,
+            proof: prepares.prove(index),
+        })
     }
 
     pub fn root(&self) -> Hash {
@@ -50,4 +58,97 @@ impl Inclusion {
             .multisign(&ReductionStatement::new(self.root))
             .unwrap())
     }
+
+    // Recomputes the root of `prepares` (the batch the broker claims to have built),
+    // checking that it matches `self.root`, and that `prepares` is a well-formed
+    // batch (i.e., strictly increasing by `Id`, as replicas require) including `prepare`
+    pub fn verify_batch(
+        &self,
+        prepare: &Prepare,
+        prepares: &[Prepare],
+    ) -> Result<(), Top<InclusionError>> {
+        if !prepares
+            .windows(2)
+            .all(|window| window[0].id() < window[1].id())
+        {
+            return InclusionError::BatchUnordered.fail().spot(here!());
+        }
+
+        match prepares.binary_search_by_key(&prepare.id(), Prepare::id) {
+            Ok(index)
+                if prepares[index].height() == prepare.height()
+                    && prepares[index].commitment() == prepare.commitment() => {}
+            _ => return InclusionError::PrepareMissing.fail().spot(here!()),
+        }
+
+        let root = Vector::new(prepares.to_vec()).unwrap().root();
+
+        if root != self.root {
+            return InclusionError::RootMismatch.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+
+    // Like `certify_reduction`, but only signs if the whole batch is verified
+    // against `self` (see `verify_batch`), rather than just the inclusion of `prepare`
+    pub fn certify_reduction_with_batch(
+        &self,
+        keychain: &KeyChain,
+        prepare: &Prepare,
+        prepares: &[Prepare],
+    ) -> Result<MultiSignature, Top<InclusionError>> {
+        self.verify_batch(prepare, prepares)?;
+        self.certify_reduction(keychain, prepare)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::account::Entry;
+
+    use talk::crypto::primitives::hash;
+
+    fn prepares(ids: &[u64]) -> Vec<Prepare> {
+        ids.iter()
+            .map(|id| Prepare::new(Entry { id: *id, height: 1 }, hash::hash(id).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn verify_batch() {
+        let prepares = prepares(&[1, 3, 5]);
+        let batch = Vector::new(prepares.clone()).unwrap();
+
+        let inclusion = Inclusion::batch(&batch).nth(1).unwrap();
+        let prepare = &prepares[1];
+
+        inclusion
+            .verify_batch(prepare, prepares.as_slice())
+            .unwrap();
+
+        let keychain = KeyChain::random();
+
+        inclusion
+            .certify_reduction_with_batch(&keychain, prepare, prepares.as_slice())
+            .unwrap();
+
+        // The broker cannot claim a different batch than the one it reduces
+
+        assert!(inclusion.verify_batch(prepare, &prepares[0..2]).is_err());
+
+        let mut unordered = prepares.clone();
+        unordered.swap(0, 2);
+
+        assert!(inclusion
+            .verify_batch(prepare, unordered.as_slice())
+            .is_err());
+
+        let foreign = Prepare::new(Entry { id: 3, height: 2 }, hash::hash(&3u64).unwrap());
+        assert!(inclusion
+            .verify_batch(&foreign, prepares.as_slice())
+            .is_err());
+    }
 }
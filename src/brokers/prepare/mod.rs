@@ -2,18 +2,35 @@ mod broker;
 mod broker_failure;
 mod broker_settings;
 mod brokerage;
+mod dry_run;
+mod fair_queue;
+mod handoff_message;
 mod inclusion;
+mod normalization;
 mod reduction;
 mod request;
+mod standby;
+mod standby_settings;
 mod submission;
 
 use broker_settings::BrokerSettingsComponents;
 use brokerage::{Brokerage, UnzippedBrokerages};
+use fair_queue::FairQueue;
+use handoff_message::HandoffMessage;
+use normalization::Normalization;
 use reduction::Reduction;
 use submission::Submission;
 
 pub(crate) use broker::Broker;
 pub(crate) use broker_failure::BrokerFailure;
 pub(crate) use broker_settings::BrokerSettings;
+#[allow(unused_imports)]
+pub(crate) use dry_run::{DryRunLog, DryRunReport};
 pub(crate) use inclusion::Inclusion;
 pub(crate) use request::Request;
+
+#[allow(unused_imports)]
+pub(crate) use standby::Standby;
+
+#[allow(unused_imports)]
+pub(crate) use standby_settings::StandbySettings;
@@ -0,0 +1,96 @@
+// A `Normalization` brings a batch in the canonical form required by replicas,
+// i.e., strictly increasing by key. Items are sorted (stably) by key, and all
+// but the first item with each key are set aside as duplicates. Each item of
+// the normalized batch is mapped back to its position in the original input,
+// so that responses indexed by batch position can be routed to their client.
+pub(in crate::brokers::prepare) struct Normalization<Item> {
+    pub batch: Vec<Item>,
+    pub positions: Vec<usize>,
+    pub duplicates: Vec<(usize, Item)>,
+}
+
+impl<Item> Normalization<Item> {
+    pub fn new<K, F>(items: Vec<Item>, mut key: F) -> Self
+    where
+        K: Ord,
+        F: FnMut(&Item) -> K,
+    {
+        let mut items = items.into_iter().enumerate().collect::<Vec<_>>();
+
+        // `sort_by` is stable: among items sharing a key, the
+        // earliest in the original input is kept in `batch`
+        items.sort_by(|(_, left), (_, right)| key(left).cmp(&key(right)));
+
+        let mut batch = Vec::with_capacity(items.len());
+        let mut positions = Vec::with_capacity(items.len());
+        let mut duplicates = Vec::new();
+
+        for (position, item) in items {
+            if batch.last().map_or(false, |last| key(last) == key(&item)) {
+                duplicates.push((position, item));
+            } else {
+                batch.push(item);
+                positions.push(position);
+            }
+        }
+
+        Normalization {
+            batch,
+            positions,
+            duplicates,
+        }
+    }
+
+    // Position, in the original input, of the `index`-th item of `self.batch`
+    pub fn position(&self, index: usize) -> usize {
+        self.positions[index]
+    }
+
+    // Index, in `self.batch`, of the item at `position` in the original
+    // input (`None` if that item was set aside as a duplicate)
+    pub fn index(&self, position: usize) -> Option<usize> {
+        self.positions
+            .iter()
+            .position(|candidate| *candidate == position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prepare::BatchDefect;
+
+    use std::iter;
+
+    #[test]
+    fn normalize() {
+        let items = vec![(5, 'a'), (1, 'b'), (5, 'c'), (3, 'd'), (1, 'e')];
+
+        let normalization = Normalization::new(items, |(id, _)| *id);
+
+        assert_eq!(normalization.batch, vec![(1, 'b'), (3, 'd'), (5, 'a')]);
+        assert_eq!(normalization.positions, vec![1, 3, 0]);
+
+        assert_eq!(normalization.duplicates, vec![(4, (1, 'e')), (2, (5, 'c'))]);
+
+        for index in 0..normalization.batch.len() {
+            let position = normalization.position(index);
+            assert_eq!(normalization.index(position), Some(index));
+        }
+
+        assert_eq!(normalization.index(2), None);
+        assert_eq!(normalization.index(4), None);
+    }
+
+    #[test]
+    fn canonical() {
+        // A normalized batch is never rejected by replicas as malformed
+        let ids = vec![9, 1, 2, 9, 10, 0, 2, 7];
+
+        let normalization = Normalization::new(ids, |id| *id);
+        let len = normalization.batch.len();
+
+        assert!(BatchDefect::diagnose(normalization.batch, len, iter::empty()).is_empty());
+    }
+}
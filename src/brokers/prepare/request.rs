@@ -1,26 +1,41 @@
 use crate::{
-    account::{Entry, Id},
-    discovery::Client,
-    prepare::Prepare,
-    signup::IdAssignment,
+    account::{Entry, Id, KeyDelegation},
+    data::enveloped,
+    prepare::{Delegated, Prepare},
+    processing::Namespace,
+    signup::{AssignmentVerifier, IdAssignment},
+    telemetry::TraceContext,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
+use rayon::prelude::*;
+
 use serde::{Deserialize, Serialize};
 
+use std::collections::HashMap;
+
 use talk::crypto::{
     primitives::{hash::Hash, sign::Signature},
     KeyCard, KeyChain,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) struct Request {
+    // Must match the `Broker`'s (see `BrokerFailure::ForeignNamespace`)
+    pub namespace: Namespace,
     pub assignment: IdAssignment,
     pub prepare: Prepare,
     pub signature: Signature,
+    // If `Some`, `signature` is issued by a delegate key
+    pub delegated: Option<Delegated>,
+    // If `Some`, the brokerage of `self` is traced within the client's trace
+    pub trace: Option<TraceContext>,
 }
 
+enveloped!(Request);
+
 #[derive(Doom)]
 pub(crate) enum RequestError {
     #[doom(description("`IdAssignment`'s `Id` does not match `Prepare`'s `Id`"))]
@@ -29,6 +44,12 @@ pub(crate) enum RequestError {
     AssignmentInvalid,
     #[doom(description("`Signature` invalid"))]
     SignatureInvalid,
+    #[doom(description("`Delegated` invalid"))]
+    DelegatedInvalid,
+    #[doom(description("`Id` duplicated within batch"))]
+    IdDuplicated,
+    #[doom(description("`Id`s not sorted within batch"))]
+    IdsUnsorted,
 }
 
 impl Request {
@@ -48,37 +69,216 @@ impl Request {
         let signature = keychain.sign(&prepare).unwrap();
 
         Request {
+            namespace: Namespace::default(),
+            assignment,
+            prepare,
+            signature,
+            delegated: None,
+            trace: None,
+        }
+    }
+
+    // Prepares `operation` on behalf of the master key of `assignment`,
+    // using the key delegated by `delegation`
+    pub fn delegated(
+        delegate: &KeyChain,
+        assignment: IdAssignment,
+        height: u64,
+        delegation: KeyDelegation,
+        operation: Hash,
+    ) -> Self {
+        let delegated = Delegated::new(delegation, operation);
+
+        let prepare = Prepare::new(
+            Entry {
+                id: assignment.id(),
+                height,
+            },
+            delegated.commitment(),
+        );
+
+        let signature = delegate.sign(&prepare).unwrap();
+
+        Request {
+            namespace: Namespace::default(),
             assignment,
             prepare,
             signature,
+            delegated: Some(delegated),
+            trace: None,
         }
     }
 
+    pub fn with_namespace(mut self, namespace: Namespace) -> Self {
+        self.namespace = namespace;
+        self
+    }
+
+    pub fn with_trace(mut self, trace: TraceContext) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
     pub fn id(&self) -> Id {
         self.assignment.id()
     }
 
+    // The `KeyCard` signing for `self` (a delegate's, if `self` is delegated)
     pub fn keycard(&self) -> &KeyCard {
-        self.assignment.keycard()
+        match &self.delegated {
+            Some(delegated) => delegated.delegate(),
+            None => self.assignment.keycard(),
+        }
     }
 
     pub fn prepare(&self) -> &Prepare {
         &self.prepare
     }
 
-    pub fn validate(&self, discovery: &Client) -> Result<(), Top<RequestError>> {
+    pub fn validate(&self, verifier: &AssignmentVerifier) -> Result<(), Top<RequestError>> {
         if self.assignment.id() != self.prepare.id() {
             return RequestError::IdsMismatched.fail().spot(here!());
         }
 
-        self.assignment
-            .validate(&discovery)
+        verifier
+            .verify(&self.assignment)
             .pot(RequestError::AssignmentInvalid, here!())?;
 
+        if let Some(delegated) = &self.delegated {
+            delegated
+                .validate(self.assignment.keycard(), &self.prepare)
+                .pot(RequestError::DelegatedInvalid, here!())?;
+        }
+
         self.signature
-            .verify(&self.assignment.keycard(), &self.prepare)
+            .verify(self.keycard(), &self.prepare)
             .pot(RequestError::SignatureInvalid, here!())?;
 
         Ok(())
     }
+
+    // Performs locally, on each element of `requests`, the same checks replicas would
+    // perform on the corresponding batch entries. On failure, returns the index of each
+    // invalid element of `requests`, along with the reason for its invalidity.
+    pub fn validate_batch(
+        verifier: &AssignmentVerifier,
+        requests: &[Request],
+    ) -> Result<(), Vec<(usize, Top<RequestError>)>> {
+        // Replicas reject batches with repeated `Id`s: all but the first
+        // `Request` for each `Id` are flagged as duplicated
+
+        let mut firsts = HashMap::new();
+
+        let duplicated = requests
+            .iter()
+            .enumerate()
+            .map(|(index, request)| *firsts.entry(request.id()).or_insert(index) != index)
+            .collect::<Vec<_>>();
+
+        // Replicas also reject batches whose `Id`s are not sorted: each `Request`
+        // whose `Id` is smaller than its predecessor's is flagged as unsorted
+
+        let unsorted = (0..requests.len())
+            .map(|index| index > 0 && requests[index].id() < requests[index - 1].id())
+            .collect::<Vec<_>>();
+
+        // Validate each element of `requests` (in parallel)
+
+        let errors = requests
+            .par_iter()
+            .zip(duplicated)
+            .zip(unsorted)
+            .enumerate()
+            .filter_map(|(index, ((request, duplicated), unsorted))| {
+                let result = if duplicated {
+                    RequestError::IdDuplicated.fail().spot(here!())
+                } else if unsorted {
+                    RequestError::IdsUnsorted.fail().spot(here!())
+                } else {
+                    request.validate(verifier)
+                };
+
+                result.err().map(|error| (index, error))
+            })
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        discovery::Embedded,
+        processing::Namespace,
+        signup::test::assignment,
+        view::{test::InstallGenerator, View},
+    };
+
+    use std::sync::Arc;
+
+    use talk::crypto::primitives::hash;
+
+    fn request(generator: &InstallGenerator, view: &View, id: Id) -> Request {
+        let client = KeyChain::random();
+
+        let assignment = assignment(
+            &generator.keychains,
+            view,
+            &Namespace::default(),
+            &client,
+            id,
+        );
+
+        Request::new(&client, assignment, 1, hash::hash(&id).unwrap())
+    }
+
+    async fn verifier(view: &View) -> AssignmentVerifier {
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = Arc::new(embedded.client(Default::default()));
+        AssignmentVerifier::new(discovery, Default::default())
+    }
+
+    #[tokio::test]
+    async fn valid() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+        let verifier = verifier(&view).await;
+
+        let requests = (0..4)
+            .map(|id| request(&generator, &view, id))
+            .collect::<Vec<_>>();
+
+        assert!(Request::validate_batch(&verifier, &requests).is_ok());
+    }
+
+    #[tokio::test]
+    async fn invalid() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+        let verifier = verifier(&view).await;
+
+        let mut requests = [0, 1, 1, 3, 2, 5]
+            .iter()
+            .map(|id| request(&generator, &view, *id))
+            .collect::<Vec<_>>();
+
+        // The signature of the last `Request` no longer matches its `Prepare`
+        requests[5].signature = requests[0].signature.clone();
+
+        let errors = Request::validate_batch(&verifier, &requests).unwrap_err();
+        let indices = errors.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+
+        // `Request` 2 is duplicated, `Request` 4 is unsorted
+        assert_eq!(indices, vec![2, 4, 5]);
+    }
 }
@@ -0,0 +1,252 @@
+use crate::{
+    brokers::prepare::{broker::Journal, HandoffMessage, StandbySettings},
+    view::View,
+};
+
+use std::path::PathBuf;
+
+use talk::{
+    crypto::Identity,
+    link::context::ListenDispatcher,
+    net::{Listener, SessionListener},
+    sync::fuse::Fuse,
+};
+
+use tokio::{
+    sync::watch::{self, Receiver as WatchReceiver, Sender as WatchSender},
+    time,
+};
+
+// A `Standby` receives the journal of an active prepare `Broker` (configured with
+// `BrokerSettings::standby`), and mirrors it to `journal_directory`. The journal
+// is accepted only from `active`, the identity of the `Broker`'s `Connector`.
+// Once the active `Broker` fails, the `Standby` fails over: a `Broker` started on
+// `journal_directory` then resumes all brokerages that were in flight on the active
+// `Broker`. Clients are redirected to the standby's `Broker` by their `Client`
+// (see `BrokerAddresses::prepare_standby`).
+pub(crate) struct Standby {
+    failover_outlet: WatchReceiver<bool>,
+    _fuse: Fuse,
+}
+
+impl Standby {
+    pub fn new<L>(
+        view: &View,
+        active: Identity,
+        listener: L,
+        journal_directory: PathBuf,
+        settings: StandbySettings,
+    ) -> Self
+    where
+        L: Listener,
+    {
+        let dispatcher =
+            ListenDispatcher::new(listener, settings.listen_dispatcher_settings.clone());
+        let context = settings.namespace.context(view, "handoff");
+        let listener = SessionListener::new(dispatcher.register(context));
+
+        let journal = Journal::new(journal_directory);
+
+        let (failover_inlet, failover_outlet) = watch::channel(false);
+
+        let fuse = Fuse::new();
+
+        fuse.spawn(async move {
+            Standby::listen(active, listener, journal, failover_inlet, settings).await;
+        });
+
+        Standby {
+            failover_outlet,
+            _fuse: fuse,
+        }
+    }
+
+    pub fn failed_over(&self) -> bool {
+        *self.failover_outlet.borrow()
+    }
+
+    // Resolves once the active `Broker` has failed, and the journal is ready
+    // to be resumed
+    pub async fn await_failover(&self) {
+        let mut failover_outlet = self.failover_outlet.clone();
+
+        while !*failover_outlet.borrow_and_update() {
+            // `failover_inlet` is held by `listen` until failover
+            if failover_outlet.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn listen(
+        active: Identity,
+        mut listener: SessionListener,
+        journal: Journal,
+        failover_inlet: WatchSender<bool>,
+        settings: StandbySettings,
+    ) {
+        // Until the active `Broker` first connects, there is nothing to fail over
+
+        let mut session = loop {
+            let (remote, session) = listener.accept().await;
+
+            // Sessions from any other peer are dropped unread
+            if remote == active {
+                break session;
+            }
+        };
+
+        loop {
+            let message = time::timeout(
+                settings.failover_timeout,
+                session.receive::<HandoffMessage>(),
+            )
+            .await;
+
+            match message {
+                Ok(Ok(message)) => {
+                    // Malformed entries are dropped: an entry that cannot be
+                    // mirrored cannot be resumed either
+                    let _ = journal.apply(message).await;
+                }
+                _ => {
+                    // The active `Broker` is silent, or lost its session: it is
+                    // given `failover_timeout` to reconnect before failing over
+
+                    let reconnect = async {
+                        loop {
+                            let (remote, session) = listener.accept().await;
+
+                            if remote == active {
+                                break session;
+                            }
+                        }
+                    };
+
+                    match time::timeout(settings.failover_timeout, reconnect).await {
+                        Ok(reconnected) => {
+                            session = reconnected;
+                        }
+                        Err(_) => break,
+                    }
+                }
+            }
+        }
+
+        let _ = failover_inlet.send(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{processing::Namespace, view::test::InstallGenerator};
+
+    use std::{env, fs, time::Duration};
+
+    use talk::{
+        crypto::KeyChain,
+        link::context::ConnectDispatcher,
+        net::{test::System as NetSystem, SessionConnector},
+    };
+
+    #[tokio::test]
+    async fn failover() {
+        let directory = env::temp_dir().join(format!("standby-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let view = InstallGenerator::new(4).view(4);
+
+        // The active `Broker`, the `Standby` and a foreign peer
+        let mut keychains = (0..3).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        keychains.sort_by_key(|keychain| keychain.keycard().identity());
+
+        let NetSystem {
+            mut connectors,
+            mut listeners,
+            ..
+        } = NetSystem::setup_with_keychains(keychains.clone()).await;
+
+        let context = Namespace::default().context(&view, "handoff");
+
+        let active = SessionConnector::new(
+            ConnectDispatcher::new(connectors.remove(0)).register(context.clone()),
+        );
+
+        let foreign =
+            SessionConnector::new(ConnectDispatcher::new(connectors.remove(1)).register(context));
+
+        let standby = Standby::new(
+            &view,
+            keychains[0].keycard().identity(),
+            listeners.remove(1),
+            directory.clone(),
+            StandbySettings {
+                failover_timeout: Duration::from_millis(200),
+                ..Default::default()
+            },
+        );
+
+        let standby_identity = keychains[1].keycard().identity();
+
+        // Journal records from peers other than the active `Broker` are ignored
+        if let Ok(mut session) = foreign.connect(standby_identity).await {
+            let _ = session
+                .send(&HandoffMessage::Record {
+                    name: "brokerage-3.journal".to_string(),
+                    record: vec![3],
+                })
+                .await;
+        }
+
+        let mut session = active.connect(standby_identity).await.unwrap();
+
+        for message in [
+            HandoffMessage::Record {
+                name: "brokerage-0.journal".to_string(),
+                record: vec![0],
+            },
+            HandoffMessage::Record {
+                name: "brokerage-1.journal".to_string(),
+                record: vec![1],
+            },
+            HandoffMessage::Complete {
+                name: "brokerage-0.journal".to_string(),
+            },
+            // Names escaping the journal directory are ignored
+            HandoffMessage::Record {
+                name: "../brokerage-2.journal".to_string(),
+                record: vec![2],
+            },
+        ] {
+            session.send(&message).await.unwrap();
+        }
+
+        // Heartbeats delay failover
+        for _ in 0..3 {
+            time::sleep(Duration::from_millis(100)).await;
+            session.send(&HandoffMessage::Heartbeat).await.unwrap();
+        }
+
+        assert!(!standby.failed_over());
+
+        drop(session);
+
+        time::timeout(Duration::from_secs(5), standby.await_failover())
+            .await
+            .unwrap();
+
+        let mut entries = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+
+        entries.sort();
+
+        assert_eq!(entries, vec!["brokerage-1.journal".to_string()]);
+        assert!(!directory.join("../brokerage-2.journal").exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,25 @@
+use crate::processing::Namespace;
+
+use std::time::Duration;
+
+use talk::link::context::ListenDispatcherSettings;
+
+#[derive(Debug, Clone)]
+pub(crate) struct StandbySettings {
+    // Must match the `Namespace` of the active `Broker`'s discovery `Client`
+    pub namespace: Namespace,
+    pub listen_dispatcher_settings: ListenDispatcherSettings,
+    // A `Standby` fails over once its active `Broker` has been silent (and
+    // disconnected) for longer than `failover_timeout`
+    pub failover_timeout: Duration,
+}
+
+impl Default for StandbySettings {
+    fn default() -> Self {
+        StandbySettings {
+            namespace: Default::default(),
+            listen_dispatcher_settings: Default::default(),
+            failover_timeout: Duration::from_secs(5),
+        }
+    }
+}
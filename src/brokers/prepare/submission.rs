@@ -1,4 +1,15 @@
-use crate::{prepare::Prepare, processing::messages::PrepareRequest, signup::IdAssignment};
+use bit_vec::BitVec;
+
+use crate::{
+    account::Id,
+    data::Compressed,
+    prepare::{BatchDefect, Delegated, Prepare},
+    processing::messages::PrepareRequest,
+    signup::IdAssignment,
+    telemetry::TraceContext,
+};
+
+use std::collections::{BTreeMap, BTreeSet};
 
 use talk::crypto::primitives::{hash::Hash, multi::Signature as MultiSignature, sign::Signature};
 
@@ -6,11 +17,13 @@ use zebra::vector::Vector;
 
 pub(in crate::brokers::prepare) struct Submission {
     assignments: Vec<IdAssignment>,
+    trace: Option<TraceContext>,
     pub requests: Requests,
 }
 
 pub(in crate::brokers::prepare) struct Requests {
     batch: PrepareRequest,
+    compressed_batch: Option<PrepareRequest>,
     signatures: PrepareRequest,
 }
 
@@ -28,16 +41,50 @@ impl Submission {
         prepares: Vector<Prepare>,
         reduction_signature: MultiSignature,
         individual_signatures: Vec<Option<Signature>>,
+        delegations: BTreeMap<usize, Delegated>,
     ) -> Self {
+        // Batches without delegated `Prepare`s are submitted as `Signatures`,
+        // which replicas that predate key delegation also understand
+        let signatures = if delegations.is_empty() {
+            PrepareRequest::Signatures(reduction_signature, individual_signatures)
+        } else {
+            PrepareRequest::DelegatedSignatures(
+                reduction_signature,
+                individual_signatures,
+                delegations,
+            )
+        };
+
         Submission {
             assignments,
+            trace: None,
             requests: Requests {
                 batch: PrepareRequest::Batch(prepares),
-                signatures: PrepareRequest::Signatures(reduction_signature, individual_signatures),
+                compressed_batch: None,
+                signatures,
             },
         }
     }
 
+    // Compresses the batch request, if its serialized form reaches `threshold` bytes
+    // (the uncompressed request is kept to access the items of (2))
+    pub fn compress(mut self, threshold: usize) -> Self {
+        self.requests.compressed_batch = Compressed::compress(self.requests.prepares(), threshold)
+            .map(PrepareRequest::CompressedBatch);
+
+        self
+    }
+
+    // If `Some`, each replica session is preceded by a `PrepareRequest::Trace`
+    pub fn with_trace(mut self, trace: TraceContext) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    pub fn trace(&self) -> Option<TraceContext> {
+        self.trace
+    }
+
     pub fn root(&self) -> Hash {
         self.requests.prepares().root()
     }
@@ -49,11 +96,91 @@ impl Submission {
     pub fn prepares(&self) -> &[Prepare] {
         self.requests.prepares().items()
     }
+
+    // Number of individual signatures in the signatures request
+    pub fn individual_signatures(&self) -> usize {
+        match &self.requests.signatures {
+            PrepareRequest::Signatures(_, individual_signatures)
+            | PrepareRequest::DelegatedSignatures(_, individual_signatures, _) => {
+                individual_signatures
+                    .iter()
+                    .filter(|signature| signature.is_some())
+                    .count()
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Determines whether or not the individual signature of the `index`-th `Prepare`
+    // is invalid. `Prepare`s covered by the reduction signature, or signed by a
+    // delegate key, cannot be checked on their own, and are never deemed invalid
+    pub fn invalid_signature(&self, index: usize) -> bool {
+        let (individual_signatures, delegations) = match &self.requests.signatures {
+            PrepareRequest::Signatures(_, individual_signatures) => (individual_signatures, None),
+            PrepareRequest::DelegatedSignatures(_, individual_signatures, delegations) => {
+                (individual_signatures, Some(delegations))
+            }
+            _ => unreachable!(),
+        };
+
+        if delegations.map_or(false, |delegations| delegations.contains_key(&index)) {
+            return false;
+        }
+
+        let (prepare, signature) =
+            match (self.prepares().get(index), individual_signatures.get(index)) {
+                (Some(prepare), Some(Some(signature))) => (prepare, signature),
+                _ => return false,
+            };
+
+        // `assignments` is sorted by `Id` (see `Broker::prepare`)
+        match self
+            .assignments
+            .binary_search_by_key(&prepare.id(), |assignment| assignment.id())
+        {
+            Ok(position) => signature
+                .verify(self.assignments[position].keycard(), prepare)
+                .is_err(),
+            Err(_) => false,
+        }
+    }
+
+    // Diagnoses the batch exactly as replicas do (see `SignedBatch::defects`)
+    pub fn defects(&self) -> Vec<BatchDefect> {
+        let (individual_signatures, delegations) = match &self.requests.signatures {
+            PrepareRequest::Signatures(_, individual_signatures) => (individual_signatures, None),
+            PrepareRequest::DelegatedSignatures(_, individual_signatures, delegations) => {
+                (individual_signatures, Some(delegations))
+            }
+            _ => unreachable!(),
+        };
+
+        BatchDefect::diagnose(
+            self.prepares().iter().map(Prepare::id),
+            individual_signatures.len(),
+            delegations
+                .into_iter()
+                .flat_map(|delegations| delegations.keys().copied()),
+        )
+    }
+
+    // Maps each element flagged by `excluded` to the `Id` of the corresponding `Prepare`
+    pub fn exclusions(&self, excluded: &BitVec) -> BTreeSet<Id> {
+        self.prepares()
+            .iter()
+            .zip(excluded.iter())
+            .filter_map(|(prepare, excluded)| if excluded { Some(prepare.id()) } else { None })
+            .collect()
+    }
 }
 
 impl Requests {
-    pub fn batch(&self) -> &PrepareRequest {
-        &self.batch
+    // The compressed batch request is returned only if `compression` is allowed
+    pub fn batch(&self, compression: bool) -> &PrepareRequest {
+        match &self.compressed_batch {
+            Some(compressed_batch) if compression => compressed_batch,
+            _ => &self.batch,
+        }
     }
 
     pub fn signatures(&self) -> &PrepareRequest {
@@ -1,9 +1,14 @@
 use crate::{
-    brokers::signup::{BrokerFailure, BrokerSettings},
+    brokers::signup::{BrokerFailure, BrokerSettings, Request},
     crypto::Identify,
     data::Sponge,
-    processing::messages::{SignupRequest, SignupResponse},
+    handles::{DrainError, Lifecycle},
+    processing::{
+        messages::{SignupRequest, SignupResponse},
+        Namespace, Timeout,
+    },
     signup::{IdAssignment, IdAssignmentAggregator, IdClaim, IdRequest, SignupSettings},
+    telemetry::{Badge, Role},
     view::View,
 };
 
@@ -11,7 +16,15 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use futures::stream::{FuturesUnordered, StreamExt};
 
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use talk::{
     crypto::Identity,
@@ -31,6 +44,9 @@ type OutcomeOutlet = Receiver<Result<IdAssignment, BrokerFailure>>;
 
 pub(crate) struct Broker {
     address: SocketAddr,
+    sponges: Arc<HashMap<Identity, Sponge<Brokerage>>>,
+    receive_timeout: Timeout,
+    lifecycle: Lifecycle,
     _fuse: Fuse,
 }
 
@@ -57,12 +73,18 @@ pub(crate) enum BrokerError {
 enum ServeError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Request invalid"))]
     RequestInvalid,
     #[doom(description("Request pertains to a foreign view"))]
     ForeignView,
+    #[doom(description("Request pertains to a foreign namespace"))]
+    ForeignNamespace,
     #[doom(description("Request directed to a foreign allocator"))]
     ForeignAllocator,
+    #[doom(description("Request not directed to its beacon allocator"))]
+    BiasedAllocator,
     #[doom(description("`Brokerage` forfeited (most likely, the `Broker` is shutting down)"))]
     #[doom(wrap(request_forfeited))]
     BrokerageForfeited { source: oneshot::error::RecvError },
@@ -88,6 +110,8 @@ enum SubmitError {
     NotACollision,
     #[doom(description("Insufficient multiplicity to reach a quorum"))]
     MultiplicityInsufficient,
+    #[doom(description("Allocator requires a higher work difficulty"))]
+    WorkInsufficient,
 }
 
 impl Broker {
@@ -113,8 +137,10 @@ impl Broker {
             .map_err(Doom::into_top)
             .spot(here!())?;
 
+        let namespace = settings.namespace;
+
         let dispatcher = ConnectDispatcher::new(connector);
-        let context = format!("{:?}::processor::signup", view.identifier());
+        let context = namespace.context(&view, "signup");
         let connector = Arc::new(SessionConnector::new(dispatcher.register(context)));
 
         let sponges = Arc::new(
@@ -125,31 +151,74 @@ impl Broker {
         );
 
         let signup_settings = settings.signup_settings;
+
+        // Last work difficulty advertised by each allocator (see `Request::WorkDifficulty`)
+        let difficulties = Arc::new(
+            view.members()
+                .keys()
+                .map(|member| (*member, AtomicU64::new(signup_settings.work_difficulty)))
+                .collect::<HashMap<_, _>>(),
+        );
+
+        let receive_timeout = Timeout::new(settings.receive_timeout);
+        let lifecycle = Lifecycle::new().with_badge(Badge::broker(Role::SignupBroker, address));
         let fuse = Fuse::new();
 
         {
             let view = view.clone();
+            let namespace = namespace.clone();
             let sponges = sponges.clone();
+            let difficulties = difficulties.clone();
+            let connector = connector.clone();
             let signup_settings = signup_settings.clone();
-
-            fuse.spawn(async move {
-                Broker::listen(view, sponges, listener, signup_settings).await;
-            });
+            let receive_timeout = receive_timeout.clone();
+            let lifecycle = lifecycle.clone();
+
+            fuse.spawn(lifecycle.clone().guard("listen", async move {
+                Broker::listen(
+                    view,
+                    namespace,
+                    sponges,
+                    difficulties,
+                    connector,
+                    listener,
+                    signup_settings,
+                    receive_timeout,
+                    lifecycle,
+                )
+                .await;
+            }));
         }
 
         for allocator in view.members().keys().cloned() {
             let view = view.clone();
+            let namespace = namespace.clone();
             let sponges = sponges.clone();
+            let difficulties = difficulties.clone();
             let connector = connector.clone();
             let signup_settings = signup_settings.clone();
 
-            fuse.spawn(async move {
-                Broker::flush(view, allocator, sponges, connector, signup_settings).await;
-            });
+            fuse.spawn(lifecycle.guard("flush", async move {
+                Broker::flush(
+                    view,
+                    namespace,
+                    allocator,
+                    sponges,
+                    difficulties,
+                    connector,
+                    signup_settings,
+                )
+                .await;
+            }));
         }
 
+        lifecycle.set_ready();
+
         Ok(Broker {
             address,
+            sponges,
+            receive_timeout,
+            lifecycle,
             _fuse: fuse,
         })
     }
@@ -158,24 +227,71 @@ impl Broker {
         self.address
     }
 
+    // Number of client `receive`s that timed out
+    pub fn expired_timeouts(&self) -> u64 {
+        self.receive_timeout.expired()
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    // Stops accepting clients, flushes pending brokerages without waiting for the
+    // sponges' timeout, and waits (for at most `timeout`) for all clients being
+    // served to obtain their outcome
+    pub async fn drain(&self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        for sponge in self.sponges.values() {
+            sponge.expedite();
+        }
+
+        self.lifecycle.drain(timeout).await
+    }
+
     async fn listen(
         view: View,
+        namespace: Namespace,
         sponges: Arc<HashMap<Identity, Sponge<Brokerage>>>,
+        difficulties: Arc<HashMap<Identity, AtomicU64>>,
+        connector: Arc<SessionConnector>,
         listener: TcpListener,
         signup_settings: SignupSettings,
+        receive_timeout: Timeout,
+        lifecycle: Lifecycle,
     ) {
         let fuse = Fuse::new();
 
         loop {
             if let Ok((stream, _)) = listener.accept().await {
+                // While draining, new connections are dropped unserved
+                let engagement = match lifecycle.engage() {
+                    Some(engagement) => engagement,
+                    None => continue,
+                };
+
                 let connection: PlainConnection = stream.into();
 
                 let view = view.clone();
+                let namespace = namespace.clone();
                 let sponges = sponges.clone();
+                let difficulties = difficulties.clone();
+                let connector = connector.clone();
                 let signup_settings = signup_settings.clone();
+                let receive_timeout = receive_timeout.clone();
 
                 fuse.spawn(async move {
-                    let _ = Broker::serve(connection, view, sponges, signup_settings).await;
+                    let _engagement = engagement;
+
+                    let _ = Broker::serve(
+                        connection,
+                        view,
+                        namespace,
+                        sponges,
+                        difficulties,
+                        connector,
+                        signup_settings,
+                        receive_timeout,
+                    )
+                    .await;
                 });
             }
         }
@@ -184,19 +300,87 @@ impl Broker {
     async fn serve(
         mut connection: PlainConnection,
         view: View,
+        namespace: Namespace,
         sponges: Arc<HashMap<Identity, Sponge<Brokerage>>>,
+        difficulties: Arc<HashMap<Identity, AtomicU64>>,
+        connector: Arc<SessionConnector>,
         signup_settings: SignupSettings,
+        receive_timeout: Timeout,
     ) -> Result<(), Top<ServeError>> {
-        let request = connection
-            .receive::<IdRequest>()
+        let request = receive_timeout
+            .run(connection.receive::<Request>())
             .await
+            .pot(ServeError::ReceiveTimeout, here!())?
             .pot(ServeError::ConnectionError, here!())?;
 
+        match request {
+            Request::IdRequest {
+                namespace: requested,
+                request,
+            } => {
+                if requested != namespace {
+                    let outcome: Result<IdAssignment, BrokerFailure> =
+                        Err(BrokerFailure::ForeignNamespace);
+
+                    connection
+                        .send(&outcome)
+                        .await
+                        .pot(ServeError::ConnectionError, here!())?;
+
+                    return ServeError::ForeignNamespace.fail().spot(here!());
+                }
+
+                Broker::serve_id_request(
+                    connection,
+                    view,
+                    sponges,
+                    difficulties.as_ref(),
+                    signup_settings,
+                    request,
+                )
+                .await
+            }
+            Request::WorkDifficulty { allocator } => {
+                Broker::serve_work_difficulty(
+                    connection,
+                    difficulties.as_ref(),
+                    connector.as_ref(),
+                    allocator,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn serve_id_request(
+        mut connection: PlainConnection,
+        view: View,
+        sponges: Arc<HashMap<Identity, Sponge<Brokerage>>>,
+        difficulties: &HashMap<Identity, AtomicU64>,
+        signup_settings: SignupSettings,
+        request: IdRequest,
+    ) -> Result<(), Top<ServeError>> {
         request
             .validate(signup_settings.work_difficulty)
             .pot(ServeError::RequestInvalid, here!())?;
 
+        if signup_settings.beacon_allocation {
+            request
+                .validate_allocator()
+                .pot(ServeError::BiasedAllocator, here!())?;
+        }
+
         if request.view() != view.identifier() {
+            // Point the client to `view`, so that it can re-target its `IdRequest`
+            let outcome: Result<IdAssignment, BrokerFailure> = Err(BrokerFailure::ForeignView {
+                view: view.identifier(),
+            });
+
+            connection
+                .send(&outcome)
+                .await
+                .pot(ServeError::ConnectionError, here!())?;
+
             return ServeError::ForeignView.fail().spot(here!());
         }
 
@@ -204,6 +388,20 @@ impl Broker {
             .get(&request.allocator())
             .ok_or(ServeError::ForeignAllocator.into_top().spot(here!()))?;
 
+        // `difficulties` has the same keys as `sponges`
+        let difficulty = difficulties[&request.allocator()].load(Ordering::Relaxed);
+
+        // Spare the allocator `request`s that would fail its batch (see `Broker::broker`)
+        if !request.meets_difficulty(difficulty) {
+            let outcome: Result<IdAssignment, BrokerFailure> =
+                Err(BrokerFailure::InsufficientWork { difficulty });
+
+            return connection
+                .send(&outcome)
+                .await
+                .pot(ServeError::ConnectionError, here!());
+        }
+
         let (outcome_inlet, outcome_outlet) = oneshot::channel();
 
         let brokerage = Brokerage {
@@ -227,10 +425,39 @@ impl Broker {
         Ok(())
     }
 
+    async fn serve_work_difficulty(
+        mut connection: PlainConnection,
+        difficulties: &HashMap<Identity, AtomicU64>,
+        connector: &SessionConnector,
+        allocator: Identity,
+    ) -> Result<(), Top<ServeError>> {
+        let difficulty = difficulties
+            .get(&allocator)
+            .ok_or(ServeError::ForeignAllocator.into_top().spot(here!()))?;
+
+        let outcome =
+            match Broker::request(allocator, connector, &SignupRequest::WorkDifficulty).await {
+                Ok(SignupResponse::WorkDifficulty(current)) => {
+                    difficulty.store(current, Ordering::Relaxed);
+                    Ok(current)
+                }
+                _ => Err(BrokerFailure::Error),
+            };
+
+        connection
+            .send(&outcome)
+            .await
+            .pot(ServeError::ConnectionError, here!())?;
+
+        Ok(())
+    }
+
     async fn flush(
         view: View,
+        namespace: Namespace,
         allocator: Identity,
         sponges: Arc<HashMap<Identity, Sponge<Brokerage>>>,
+        difficulties: Arc<HashMap<Identity, AtomicU64>>,
         connector: Arc<SessionConnector>,
         signup_settings: SignupSettings,
     ) {
@@ -245,11 +472,25 @@ impl Broker {
             }
 
             let view = view.clone();
+            let namespace = namespace.clone();
+            let difficulties = difficulties.clone();
             let connector = connector.clone();
             let signup_settings = signup_settings.clone();
 
             fuse.spawn(async move {
-                Broker::broker(view, allocator, connector, brokerages, signup_settings).await;
+                // `difficulties` has an entry for each allocator
+                let difficulty = &difficulties[&allocator];
+
+                Broker::broker(
+                    view,
+                    namespace,
+                    allocator,
+                    difficulty,
+                    connector,
+                    brokerages,
+                    signup_settings,
+                )
+                .await;
             });
         }
     }
@@ -283,38 +524,83 @@ impl Broker {
     // Contract: all `brokerages` provided to `Broker::broker` are eventually resolved
     async fn broker(
         view: View,
+        namespace: Namespace,
         allocator: Identity,
+        difficulty: &AtomicU64,
         connector: Arc<SessionConnector>,
-        brokerages: Vec<Brokerage>,
+        mut brokerages: Vec<Brokerage>,
         signup_settings: SignupSettings,
     ) {
-        let (requests, outcome_inlets): (Vec<_>, Vec<_>) = brokerages
-            .into_iter()
-            .map(|brokerage| (brokerage.request, brokerage.outcome_inlet))
-            .unzip();
-
-        match Broker::submit(
-            &view,
-            allocator,
-            connector.as_ref(),
-            requests,
-            &signup_settings,
-        )
-        .await
-        {
-            Ok(assignments) => {
-                for (assignment, outcome_inlet) in assignments.iter().cloned().zip(outcome_inlets) {
-                    // All `outcome_inlets` are guaranteed to be alive unless `Broker` is shutting down
-                    let _ = outcome_inlet.send(assignment.map_err(Into::into));
-                }
+        loop {
+            // A single `IdRequest` below `allocator`'s difficulty fails the whole batch:
+            // fail such `brokerages` upfront, and resubmit the others whenever
+            // `allocator` is found to have raised its difficulty
+            let current = difficulty.load(Ordering::Relaxed);
+
+            let (requests, outcome_inlets): (Vec<_>, Vec<_>) = brokerages
+                .into_iter()
+                .filter_map(|brokerage| {
+                    if brokerage.request.meets_difficulty(current) {
+                        Some((brokerage.request, brokerage.outcome_inlet))
+                    } else {
+                        let failure = BrokerFailure::InsufficientWork {
+                            difficulty: current,
+                        };
+
+                        let _ = brokerage.outcome_inlet.send(Err(failure));
+
+                        None
+                    }
+                })
+                .unzip();
 
-                let assignments = assignments.into_iter().filter_map(Result::ok).collect();
-                Broker::publish_assignments(&view, connector.as_ref(), assignments).await;
+            if requests.is_empty() {
+                return;
             }
-            Err(_) => {
-                for outcome_inlet in outcome_inlets {
-                    // All `outcome_inlets` are guaranteed to be alive unless `Broker` is shutting down
-                    let _ = outcome_inlet.send(Err(BrokerFailure::Error));
+
+            match Broker::submit(
+                &view,
+                &namespace,
+                allocator,
+                difficulty,
+                connector.as_ref(),
+                requests.clone(),
+                &signup_settings,
+            )
+            .await
+            {
+                Ok(assignments) => {
+                    for (assignment, outcome_inlet) in
+                        assignments.iter().cloned().zip(outcome_inlets)
+                    {
+                        // All `outcome_inlets` are guaranteed to be alive unless `Broker` is shutting down
+                        let _ = outcome_inlet.send(assignment.map_err(Into::into));
+                    }
+
+                    let assignments = assignments.into_iter().filter_map(Result::ok).collect();
+                    Broker::publish_assignments(&view, connector.as_ref(), assignments).await;
+
+                    return;
+                }
+                // `difficulty` only grows across iterations, so that this eventually
+                // terminates (each `IdRequest`'s `Work` has a finite difficulty)
+                Err(_) if difficulty.load(Ordering::Relaxed) > current => {
+                    brokerages = requests
+                        .into_iter()
+                        .zip(outcome_inlets)
+                        .map(|(request, outcome_inlet)| Brokerage {
+                            request,
+                            outcome_inlet,
+                        })
+                        .collect();
+                }
+                Err(_) => {
+                    for outcome_inlet in outcome_inlets {
+                        // All `outcome_inlets` are guaranteed to be alive unless `Broker` is shutting down
+                        let _ = outcome_inlet.send(Err(BrokerFailure::Error));
+                    }
+
+                    return;
                 }
             }
         }
@@ -322,19 +608,26 @@ impl Broker {
 
     async fn submit(
         view: &View,
+        namespace: &Namespace,
         allocator: Identity,
+        difficulty: &AtomicU64,
         connector: &SessionConnector,
         requests: Vec<IdRequest>,
         signup_settings: &SignupSettings,
     ) -> Result<Vec<Result<IdAssignment, Collision>>, Top<SubmitError>> {
-        let claims = Broker::submit_requests(allocator, connector, requests).await?;
-        let assignments = Broker::submit_claims(view, connector, claims, signup_settings).await?;
+        let claims =
+            Broker::submit_requests(namespace, allocator, difficulty, connector, requests).await?;
+
+        let assignments =
+            Broker::submit_claims(view, namespace, connector, claims, signup_settings).await?;
 
         Ok(assignments)
     }
 
     async fn submit_requests(
+        namespace: &Namespace,
         allocator: Identity,
+        difficulty: &AtomicU64,
         connector: &SessionConnector,
         requests: Vec<IdRequest>,
     ) -> Result<Vec<IdClaim>, Top<SubmitError>> {
@@ -349,6 +642,10 @@ impl Broker {
 
             let allocations = match response {
                 SignupResponse::IdAllocations(allocations) => allocations,
+                SignupResponse::WorkDifficulty(current) => {
+                    difficulty.store(current, Ordering::Relaxed);
+                    return SubmitError::WorkInsufficient.fail().spot(here!());
+                }
                 _ => {
                     return SubmitError::UnexpectedResponse.fail().spot(here!());
                 }
@@ -371,7 +668,7 @@ impl Broker {
             .map(|(request, allocation)| {
                 // Each `allocation` must be valid against the corresponding `request`
                 allocation
-                    .validate(&request)
+                    .validate(namespace, &request)
                     .pot(SubmitError::InvalidAllocation, here!())?;
 
                 Ok(IdClaim::new(request, allocation))
@@ -383,6 +680,7 @@ impl Broker {
 
     async fn submit_claims(
         view: &View,
+        namespace: &Namespace,
         connector: &SessionConnector,
         claims: Vec<IdClaim>,
         signup_settings: &SignupSettings,
@@ -438,15 +736,16 @@ impl Broker {
             .map(|claim| {
                 Ok(IdAssignmentAggregator::new(
                     view.clone(),
+                    namespace,
                     claim.id(),
                     claim.client(),
                 ))
             })
             .collect::<Vec<_>>();
 
-        // At all times, `multiplicity` counts the valid shards received from
-        // the members of `view`
-        let mut multiplicity = 0;
+        // At all times, `power` is the total weight of the members of `view`
+        // whose shards were all valid
+        let mut power = 0;
 
         while let Some((assigner, result)) = unordered.next().await {
             // Extract unvalidated `shards` from `result`
@@ -488,7 +787,7 @@ impl Broker {
                             // Validate `collided_claim`
 
                             collided_claim
-                                .validate(signup_settings.work_difficulty)
+                                .validate(namespace, signup_settings.work_difficulty)
                                 .pot(SubmitError::InvalidClaim, here!())?;
 
                             // `collided_claim` must claim the same id for a different client
@@ -516,15 +815,15 @@ impl Broker {
 
             // `result` is `Ok` only if all `shards` are correctly validated.
             // As a result, because signatures are aggregated on the fly, some
-            // aggregators in `slots` might aggregate more than `multiplicity`
-            // signatures. This, however, is not a a security issue, and is expected
-            // to happen very rarely (i.e., upon accountable replica misbehaviour).
+            // aggregators in `slots` might aggregate signatures from members not
+            // counted in `power`. This, however, is not a a security issue, and is
+            // expected to happen very rarely (i.e., upon accountable replica misbehaviour).
             if result.is_ok() {
-                multiplicity += 1;
+                power += view.weight(&assigner.identity());
             }
 
             // At least each aggregator in `slots` has a quorum of signatures: finalize and return
-            if multiplicity >= view.quorum() {
+            if power >= view.weighted_quorum() {
                 let assignments = slots
                     .into_iter()
                     .map(|slot| {
@@ -649,6 +948,11 @@ mod tests {
                     let stream = TcpStream::connect(address).await.unwrap();
                     let mut connection: PlainConnection = stream.into();
 
+                    let request = Request::IdRequest {
+                        namespace: Namespace::default(),
+                        request,
+                    };
+
                     connection.send(&request).await.unwrap();
 
                     let assignment = connection
@@ -667,4 +971,34 @@ mod tests {
             task.await.unwrap();
         }
     }
+
+    #[tokio::test]
+    async fn work_difficulty() {
+        let System {
+            discovery_server: _discovery_server,
+            processors,
+            mut signup_brokers,
+            ..
+        } = System::setup(4, 1, 0, 0).await;
+
+        let signup_broker = signup_brokers.remove(0);
+        let allocator = processors[0].0.keycard().identity();
+
+        let stream = TcpStream::connect(signup_broker.address()).await.unwrap();
+        let mut connection: PlainConnection = stream.into();
+
+        connection
+            .send(&Request::WorkDifficulty { allocator })
+            .await
+            .unwrap();
+
+        let difficulty = connection
+            .receive::<Result<u64, BrokerFailure>>()
+            .await
+            .unwrap()
+            .unwrap();
+
+        // No signup pressure: the base difficulty applies
+        assert_eq!(difficulty, SignupSettings::default().work_difficulty);
+    }
 }
@@ -2,6 +2,8 @@ use crate::signup::IdClaim;
 
 use serde::{Deserialize, Serialize};
 
+use talk::crypto::primitives::hash::Hash;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum BrokerFailure {
     Throttle,
@@ -10,4 +12,14 @@ pub(crate) enum BrokerFailure {
         brokered: IdClaim,
         collided: IdClaim,
     },
+    // The `IdRequest`'s `Work` does not meet the allocator's current difficulty
+    InsufficientWork {
+        difficulty: u64,
+    },
+    // The `IdRequest` pertains to a view other than the `Broker`'s `view`
+    ForeignView {
+        view: Hash,
+    },
+    // The `IdRequest` pertains to a `Namespace` other than the `Broker`'s
+    ForeignNamespace,
 }
@@ -1,7 +1,22 @@
-use crate::{data::SpongeSettings, signup::SignupSettings};
+use crate::{data::SpongeSettings, processing::Namespace, signup::SignupSettings};
 
-#[derive(Debug, Clone, Default)]
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
 pub(crate) struct BrokerSettings {
+    pub namespace: Namespace,
     pub signup_settings: SignupSettings,
     pub sponge_settings: SpongeSettings,
+    pub receive_timeout: Duration,
+}
+
+impl Default for BrokerSettings {
+    fn default() -> Self {
+        BrokerSettings {
+            namespace: Default::default(),
+            signup_settings: Default::default(),
+            sponge_settings: Default::default(),
+            receive_timeout: Duration::from_secs(10),
+        }
+    }
 }
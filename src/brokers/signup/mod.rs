@@ -1,8 +1,10 @@
 mod broker;
 mod broker_failure;
 mod broker_settings;
+mod request;
 
 #[allow(unused_imports)]
 pub(crate) use broker::Broker;
 pub(crate) use broker_failure::BrokerFailure;
 pub(crate) use broker_settings::BrokerSettings;
+pub(crate) use request::Request;
@@ -0,0 +1,24 @@
+use crate::{data::enveloped, processing::Namespace, signup::IdRequest};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::Identity;
+
+// Sent by clients to a signup `Broker` as the first message of a connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub(crate) enum Request {
+    // Answered with a `Result<IdAssignment, BrokerFailure>`. `namespace` must match
+    // the `Broker`'s (see `BrokerFailure::ForeignNamespace`)
+    IdRequest {
+        namespace: Namespace,
+        request: IdRequest,
+    },
+    // Pre-flight, answered with a `Result<u64, BrokerFailure>`: the work difficulty
+    // `allocator` currently requires of `IdRequest`s (see `IdRequest::new`)
+    WorkDifficulty {
+        allocator: Identity,
+    },
+}
+
+enveloped!(Request);
@@ -1,21 +1,29 @@
 use crate::{
+    account::AccountSettings,
     brokers::{
         commit::Broker as CommitBroker, prepare::Broker as PrepareBroker,
         signup::Broker as SignupBroker,
     },
     database::Database,
-    discovery::{self, Client, Mode, Server},
+    discovery::{Client, ClientSettings, Embedded, Mode},
     processing::Processor,
-    view::View,
+    view::{test::InstallGenerator, Attachment, Change, View},
 };
 
-use std::{net::Ipv4Addr, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    net::Ipv4Addr,
+    sync::Arc,
+    time::Duration,
+};
 
 use talk::{crypto::KeyChain, net::test::System as NetSystem};
 
+const READINESS_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub(crate) struct System {
     pub view: View,
-    pub discovery_server: Server,
+    pub discovery_server: Embedded,
     pub discovery_client: Arc<Client>,
     pub processors: Vec<(KeyChain, Processor)>,
     pub signup_brokers: Vec<SignupBroker>,
@@ -30,12 +38,86 @@ impl System {
         prepare_brokers: usize,
         commit_brokers: usize,
     ) -> Self {
-        let (install_generator, discovery_server, _, mut discovery_clients, _) =
-            discovery::test::setup(processors, processors, Mode::Full).await;
+        System::setup_with_account_settings(
+            processors,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+            Default::default(),
+        )
+        .await
+    }
 
-        let discovery_client = Arc::new(discovery_clients.next().unwrap());
+    // Each processor's `Database` is initialized with `account_settings`
+    pub async fn setup_with_account_settings(
+        processors: usize,
+        signup_brokers: usize,
+        prepare_brokers: usize,
+        commit_brokers: usize,
+        account_settings: AccountSettings,
+    ) -> Self {
+        let install_generator = InstallGenerator::new(processors);
         let view = install_generator.view(processors);
 
+        System::setup_with_view(
+            install_generator,
+            view,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+            account_settings,
+        )
+        .await
+    }
+
+    // The `index`-th processor (by `Identity`) has voting weight `weights[index]`
+    pub async fn setup_with_weights(
+        weights: &[u64],
+        signup_brokers: usize,
+        prepare_brokers: usize,
+        commit_brokers: usize,
+    ) -> Self {
+        let install_generator = InstallGenerator::new(weights.len());
+        let view = install_generator.view(weights.len());
+
+        let weights = view
+            .members()
+            .keys()
+            .copied()
+            .zip(weights.iter().copied())
+            .collect::<BTreeMap<_, _>>();
+
+        let attachment = Attachment::new(View::WEIGHTS, 0, &weights).unwrap();
+        let view = view.extend(BTreeSet::from([Change::Attach(attachment)]));
+
+        System::setup_with_view(
+            install_generator,
+            view,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+            Default::default(),
+        )
+        .await
+    }
+
+    async fn setup_with_view(
+        install_generator: InstallGenerator,
+        view: View,
+        signup_brokers: usize,
+        prepare_brokers: usize,
+        commit_brokers: usize,
+        account_settings: AccountSettings,
+    ) -> Self {
+        let discovery_server = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery_client = Arc::new(discovery_server.client(ClientSettings {
+            mode: Mode::Full,
+            ..Default::default()
+        }));
+
         let mut processor_keychains = install_generator.keychains.clone();
         processor_keychains.sort_by_key(|keychain| keychain.keycard().identity());
 
@@ -80,7 +162,7 @@ impl System {
                         keychain,
                         discovery_client.clone(),
                         view.clone(),
-                        Database::new(),
+                        Database::with_account_settings(account_settings.clone()),
                         connectors.remove(0),
                         listeners.remove(0),
                         Default::default(),
@@ -89,6 +171,14 @@ impl System {
             })
             .collect::<Vec<(KeyChain, Processor)>>();
 
+        for (_, processor) in processors.iter() {
+            processor
+                .lifecycle()
+                .await_ready_within(READINESS_TIMEOUT)
+                .await
+                .unwrap();
+        }
+
         let mut signup_brokers = Vec::new();
 
         for _ in signup_broker_keychains {
@@ -129,12 +219,25 @@ impl System {
                     view.clone(),
                     (Ipv4Addr::LOCALHOST, 0),
                     connectors.remove(0),
+                    Default::default(),
                 )
                 .await
                 .unwrap(),
             );
         }
 
+        for lifecycle in signup_brokers
+            .iter()
+            .map(SignupBroker::lifecycle)
+            .chain(prepare_brokers.iter().map(PrepareBroker::lifecycle))
+            .chain(commit_brokers.iter().map(CommitBroker::lifecycle))
+        {
+            lifecycle
+                .await_ready_within(READINESS_TIMEOUT)
+                .await
+                .unwrap();
+        }
+
         System {
             view,
             discovery_server,
@@ -54,7 +54,7 @@ impl Churn {
         }
     }
 
-    fn change(&self) -> Change {
+    pub fn change(&self) -> Change {
         match self {
             Churn::Resolution(resolution_claim) => resolution_claim.change(),
             Churn::Resignation(resignation_claim) => resignation_claim.change(),
@@ -0,0 +1,578 @@
+use crate::{
+    account::Id,
+    brokers::{
+        commit::{BrokerFailure as CommitBrokerFailure, Request as CommitRequest},
+        prepare::{BrokerFailure as PrepareBrokerFailure, Inclusion, Request as PrepareRequest},
+        signup::{BrokerFailure as SignupBrokerFailure, Request as SignupRequest},
+    },
+    client::{BrokerAddresses, ClientSettings, Completion},
+    commit::{
+        BatchCredits, Commit, CommitProof, Completion as CommitCompletion, CompletionProof,
+        CreditSummary, Payload,
+    },
+    discovery::{Client as DiscoveryClient, ClientSettings as DiscoverySettings},
+    prepare::{BatchCommit, BatchCommitCache},
+    signup::{IdAssignment, IdRequest},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{fmt::Debug, net::SocketAddr, sync::Arc};
+
+use talk::{
+    crypto::KeyChain,
+    net::{traits::TcpConnect, PlainConnection},
+    sync::fuse::Fuse,
+};
+
+use tokio::net::TcpStream;
+
+use zebra::vector::Proof;
+
+// A `Client` operates a single account through the brokers of a `View`: it
+// signs up (obtaining the account's `Id`), then prepares and commits `Payload`s.
+// Broker sessions, certificates and proofs are handled internally: each step
+// either succeeds with a validated outcome, or fails with a `ClientError`.
+pub struct Client {
+    keychain: KeyChain,
+    view: View,
+    discovery: Arc<DiscoveryClient>,
+    brokers: BrokerAddresses,
+    settings: ClientSettings,
+    assignment: Option<IdAssignment>,
+    batch_commit_cache: Arc<BatchCommitCache>,
+    _fuse: Fuse,
+}
+
+// A `Payload` whose `Prepare` was committed, ready to be passed to `Client::commit`
+pub struct Prepared {
+    commit: Commit,
+    payload: Payload,
+}
+
+#[derive(Doom)]
+pub enum ClientError {
+    #[doom(description("Client has not signed up"))]
+    NotSignedUp,
+    #[doom(description("`Payload` pertains to a foreign `Id`"))]
+    ForeignPayload,
+    #[doom(description("Failed to generate `IdRequest`"))]
+    GenerationFailed,
+    #[doom(description("Work difficulty {} exceeds the maximum", difficulty))]
+    DifficultyExceeded { difficulty: u64 },
+    #[doom(description("Failed to connect to broker"))]
+    ConnectFailed,
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Brokerage failed: {}", failure))]
+    BrokerageFailed { failure: String },
+    #[doom(description("`IdAssignment` invalid"))]
+    AssignmentInvalid,
+    #[doom(description("`Inclusion` invalid"))]
+    InclusionInvalid,
+    #[doom(description("`Commit` invalid"))]
+    CommitInvalid,
+    #[doom(description("`Completion` invalid"))]
+    CompletionInvalid,
+    #[doom(description("`BatchCredits` invalid"))]
+    CreditsInvalid,
+}
+
+impl Client {
+    // `discovery` is the address of a discovery server aware of `view`
+    pub fn new<T>(
+        keychain: KeyChain,
+        view: View,
+        discovery: T,
+        brokers: BrokerAddresses,
+        settings: ClientSettings,
+    ) -> Self
+    where
+        T: 'static + Clone + TcpConnect,
+    {
+        let discovery = Arc::new(DiscoveryClient::new(
+            view.clone(),
+            discovery,
+            DiscoverySettings {
+                namespace: settings.namespace.clone(),
+                ..Default::default()
+            },
+        ));
+
+        let batch_commit_cache =
+            Arc::new(BatchCommitCache::new(settings.batch_commit_cache_capacity));
+
+        let fuse = Fuse::new();
+
+        {
+            let discovery = discovery.clone();
+            let batch_commit_cache = batch_commit_cache.clone();
+            let height = view.height();
+
+            fuse.spawn(async move {
+                batch_commit_cache
+                    .invalidate_on_install(&discovery, height)
+                    .await;
+            });
+        }
+
+        Client {
+            keychain,
+            view,
+            discovery,
+            brokers,
+            settings,
+            assignment: None,
+            batch_commit_cache,
+            _fuse: fuse,
+        }
+    }
+
+    // `Some` once `signup` succeeds
+    pub fn id(&self) -> Option<Id> {
+        self.assignment.as_ref().map(IdAssignment::id)
+    }
+
+    pub async fn signup(&mut self) -> Result<Id, Top<ClientError>> {
+        let allocator = IdRequest::beacon_allocator(&self.view, &self.keychain.keycard());
+        let mut difficulty = self.settings.work_difficulty;
+
+        let assignment = loop {
+            let request = IdRequest::generate(&self.keychain, &self.view, allocator, difficulty)
+                .wait()
+                .await
+                .pot(ClientError::GenerationFailed, here!())?;
+
+            let mut connection = Client::connect(self.brokers.signup).await?;
+
+            connection
+                .send(&SignupRequest::IdRequest {
+                    namespace: self.settings.namespace.clone(),
+                    request,
+                })
+                .await
+                .pot(ClientError::ConnectionError, here!())?;
+
+            let outcome = connection
+                .receive::<Result<IdAssignment, SignupBrokerFailure>>()
+                .await
+                .pot(ClientError::ConnectionError, here!())?;
+
+            // The allocator requires more work than provided: retry at its difficulty
+            match outcome {
+                Ok(assignment) => break assignment,
+                Err(SignupBrokerFailure::InsufficientWork {
+                    difficulty: required,
+                }) if required > difficulty => {
+                    if required > self.settings.max_work_difficulty {
+                        return ClientError::DifficultyExceeded {
+                            difficulty: required,
+                        }
+                        .fail()
+                        .spot(here!());
+                    }
+
+                    difficulty = required;
+                }
+                Err(failure) => return Err(Client::brokerage_failed(failure)).spot(here!()),
+            }
+        };
+
+        assignment
+            .validate(&self.discovery)
+            .pot(ClientError::AssignmentInvalid, here!())?;
+
+        let id = assignment.id();
+        self.assignment = Some(assignment);
+
+        Ok(id)
+    }
+
+    pub async fn prepare(&self, payload: Payload) -> Result<Prepared, Top<ClientError>> {
+        let assignment = self
+            .assignment
+            .clone()
+            .ok_or(ClientError::NotSignedUp.into_top())
+            .spot(here!())?;
+
+        if payload.id() != assignment.id() {
+            return ClientError::ForeignPayload.fail().spot(here!());
+        }
+
+        let prepare = payload.prepare();
+
+        let request = PrepareRequest::new(
+            &self.keychain,
+            assignment,
+            prepare.height(),
+            prepare.commitment(),
+        )
+        .with_namespace(self.settings.namespace.clone());
+
+        // Should the prepare broker be lost before answering (e.g., upon crashing),
+        // `request` is redirected to its standby, which resumes its brokerages
+        let mut lost = false;
+
+        let outcome = self
+            .prepare_through(self.brokers.prepare, &request, &mut lost)
+            .await;
+
+        let (batch_commit, proof) = match (outcome, self.brokers.prepare_standby) {
+            (Err(_), Some(standby)) if lost => {
+                self.prepare_through(standby, &request, &mut lost).await?
+            }
+            (outcome, _) => outcome?,
+        };
+
+        proof
+            .verify(batch_commit.root(), request.prepare())
+            .pot(ClientError::InclusionInvalid, here!())?;
+
+        let commit_proof = CommitProof::new(batch_commit, proof);
+        let commit = Commit::new(commit_proof, payload.clone());
+
+        commit
+            .validate_cached(&self.discovery, &self.batch_commit_cache)
+            .pot(ClientError::CommitInvalid, here!())?;
+
+        Ok(Prepared { commit, payload })
+    }
+
+    // `dependencies` must contain one `Completion` for each
+    // of `prepared`'s dependencies, in order
+    pub async fn commit(
+        &self,
+        prepared: Prepared,
+        dependencies: Vec<Completion>,
+    ) -> Result<Completion, Top<ClientError>> {
+        let Prepared { commit, payload } = prepared;
+
+        let dependencies = dependencies
+            .into_iter()
+            .map(|dependency| dependency.0)
+            .collect();
+
+        let request = CommitRequest::new(commit, dependencies)
+            .with_namespace(self.settings.namespace.clone());
+
+        let mut connection = Client::connect(self.brokers.commit).await?;
+
+        connection
+            .send(&request)
+            .await
+            .pot(ClientError::ConnectionError, here!())?;
+
+        let completion_proof = connection
+            .receive::<Result<CompletionProof, CommitBrokerFailure>>()
+            .await
+            .pot(ClientError::ConnectionError, here!())?
+            .map_err(Client::brokerage_failed)
+            .spot(here!())?;
+
+        let completion = CommitCompletion::new(completion_proof, payload);
+
+        completion
+            .validate(&self.discovery)
+            .pot(ClientError::CompletionInvalid, here!())?;
+
+        Ok(Completion(completion))
+    }
+
+    // Verifies `credits` (obtained, e.g., from a replica or a broker), summarizing
+    // the credits it proves committed to `credits.recipient()`
+    pub fn verify_credits(
+        &self,
+        credits: &BatchCredits,
+    ) -> Result<CreditSummary, Top<ClientError>> {
+        credits
+            .verify(&self.discovery)
+            .pot(ClientError::CreditsInvalid, here!())
+    }
+
+    // Brokers the prepare of `request` through the broker at `address`. Sets `lost`
+    // if the broker was unreachable, or dropped the connection before answering
+    async fn prepare_through(
+        &self,
+        address: SocketAddr,
+        request: &PrepareRequest,
+        lost: &mut bool,
+    ) -> Result<(BatchCommit, Proof), Top<ClientError>> {
+        *lost = true;
+
+        let mut connection = Client::connect(address).await?;
+
+        connection
+            .send(request)
+            .await
+            .pot(ClientError::ConnectionError, here!())?;
+
+        let inclusion = connection
+            .receive::<Result<Inclusion, PrepareBrokerFailure>>()
+            .await
+            .pot(ClientError::ConnectionError, here!())?;
+
+        *lost = false;
+
+        let inclusion = inclusion.map_err(Client::brokerage_failed).spot(here!())?;
+
+        let reduction_shard = inclusion
+            .certify_reduction(&self.keychain, request.prepare())
+            .pot(ClientError::InclusionInvalid, here!())?;
+
+        *lost = true;
+
+        connection
+            .send(&reduction_shard)
+            .await
+            .pot(ClientError::ConnectionError, here!())?;
+
+        // The broker sends back the `Proof` of inclusion of `request`'s `Prepare` along
+        // with `BatchCommit`: should the batch have been retried, it differs from `inclusion`'s
+
+        let outcome = connection
+            .receive::<Result<(BatchCommit, Proof), PrepareBrokerFailure>>()
+            .await
+            .pot(ClientError::ConnectionError, here!())?;
+
+        *lost = false;
+
+        outcome.map_err(Client::brokerage_failed).spot(here!())
+    }
+
+    async fn connect(address: SocketAddr) -> Result<PlainConnection, Top<ClientError>> {
+        let stream = TcpStream::connect(address)
+            .await
+            .pot(ClientError::ConnectFailed, here!())?;
+
+        Ok(stream.into())
+    }
+
+    fn brokerage_failed<F>(failure: F) -> Top<ClientError>
+    where
+        F: Debug,
+    {
+        ClientError::BrokerageFailed {
+            failure: format!("{:?}", failure),
+        }
+        .into_top()
+    }
+}
+
+impl Prepared {
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::{Entry, Operation},
+        brokers::test::System,
+        commit::{Credit, CreditKind},
+    };
+
+    use std::net::Ipv4Addr;
+
+    use tokio::net::TcpListener;
+
+    use zebra::vector::Vector;
+
+    #[tokio::test]
+    async fn develop() {
+        let System {
+            view,
+            discovery_server,
+            discovery_client: _discovery_client,
+            processors: _processors,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+        } = System::setup(4, 1, 1, 1).await;
+
+        let brokers = BrokerAddresses {
+            signup: signup_brokers[0].address(),
+            prepare: prepare_brokers[0].address(),
+            prepare_standby: None,
+            commit: commit_brokers[0].address(),
+        };
+
+        let mut client = Client::new(
+            KeyChain::random(),
+            view,
+            discovery_server.address(),
+            brokers,
+            Default::default(),
+        );
+
+        let payload = Payload::new(Entry { id: 0, height: 1 }, Operation::withdraw(0, 0, 0));
+
+        assert!(client.prepare(payload).await.is_err());
+
+        let id = client.signup().await.unwrap();
+        assert_eq!(client.id(), Some(id));
+
+        let payload = Payload::new(Entry { id, height: 1 }, Operation::withdraw(id, 0, 0));
+
+        let prepared = client.prepare(payload).await.unwrap();
+        let completion = client.commit(prepared, Vec::new()).await.unwrap();
+
+        assert_eq!(completion.entry(), Entry { id, height: 1 });
+    }
+
+    #[tokio::test]
+    async fn credits() {
+        let System {
+            view,
+            discovery_server,
+            discovery_client: _discovery_client,
+            processors: _processors,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+        } = System::setup(4, 1, 1, 1).await;
+
+        let brokers = BrokerAddresses {
+            signup: signup_brokers[0].address(),
+            prepare: prepare_brokers[0].address(),
+            prepare_standby: None,
+            commit: commit_brokers[0].address(),
+        };
+
+        let mut client = Client::new(
+            KeyChain::random(),
+            view,
+            discovery_server.address(),
+            brokers,
+            Default::default(),
+        );
+
+        let id = client.signup().await.unwrap();
+
+        let payload = Payload::new(Entry { id, height: 1 }, Operation::withdraw(id, 3, 0));
+
+        let prepared = client.prepare(payload.clone()).await.unwrap();
+        let completion = client.commit(prepared, Vec::new()).await.unwrap();
+
+        // The commit broker submitted `payload` alone: the batch consists of `payload` only
+        let batch = completion.batch().clone();
+        let payloads = Vector::new(vec![payload]).unwrap();
+        assert_eq!(payloads.root(), batch.root());
+
+        let credits = BatchCredits::extract(batch.clone(), &payloads, id);
+        let summary = client.verify_credits(&credits).unwrap();
+
+        assert_eq!(summary.recipient, id);
+        assert_eq!(summary.root, batch.root());
+        assert_eq!(
+            summary.credits,
+            vec![Credit {
+                withdraw: Entry { id, height: 1 },
+                slot: 3,
+                amount: 0,
+                kind: CreditKind::Transfer,
+            }]
+        );
+        assert_eq!(summary.total, 0);
+
+        // Other recipients are credited nothing
+        let credits = BatchCredits::extract(batch, &payloads, id + 1);
+        let summary = client.verify_credits(&credits).unwrap();
+
+        assert!(summary.credits.is_empty());
+        assert_eq!(summary.total, 0);
+    }
+
+    #[tokio::test]
+    async fn heavy() {
+        // The first processor weighs 4 out of 7: along with any other
+        // processor, it reaches a quorum (5) and certifies alone a plurality (3)
+        let System {
+            view,
+            discovery_server,
+            discovery_client: _discovery_client,
+            mut processors,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+        } = System::setup_with_weights(&[4, 1, 1, 1], 1, 1, 1).await;
+
+        let heavy = processors[0].0.keycard().identity();
+        assert_eq!(view.weight(&heavy), 4);
+
+        // Only two processors out of four remain: too few to reach
+        // a quorum by count, but enough to reach a quorum by weight
+        processors.truncate(2);
+
+        let brokers = BrokerAddresses {
+            signup: signup_brokers[0].address(),
+            prepare: prepare_brokers[0].address(),
+            prepare_standby: None,
+            commit: commit_brokers[0].address(),
+        };
+
+        let mut client = Client::new(
+            KeyChain::random(),
+            view,
+            discovery_server.address(),
+            brokers,
+            Default::default(),
+        );
+
+        let id = client.signup().await.unwrap();
+
+        let payload = Payload::new(Entry { id, height: 1 }, Operation::withdraw(id, 0, 0));
+
+        let prepared = client.prepare(payload).await.unwrap();
+        let completion = client.commit(prepared, Vec::new()).await.unwrap();
+
+        assert_eq!(completion.entry(), Entry { id, height: 1 });
+    }
+
+    #[tokio::test]
+    async fn redirect() {
+        let System {
+            view,
+            discovery_server,
+            discovery_client: _discovery_client,
+            processors: _processors,
+            signup_brokers,
+            prepare_brokers,
+            commit_brokers,
+        } = System::setup(4, 1, 1, 1).await;
+
+        // No broker listens at `lost`: prepares are redirected to the standby
+        let lost = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let brokers = BrokerAddresses {
+            signup: signup_brokers[0].address(),
+            prepare: lost,
+            prepare_standby: Some(prepare_brokers[0].address()),
+            commit: commit_brokers[0].address(),
+        };
+
+        let mut client = Client::new(
+            KeyChain::random(),
+            view,
+            discovery_server.address(),
+            brokers,
+            Default::default(),
+        );
+
+        let id = client.signup().await.unwrap();
+
+        let payload = Payload::new(Entry { id, height: 1 }, Operation::withdraw(id, 0, 0));
+
+        let prepared = client.prepare(payload).await.unwrap();
+        let completion = client.commit(prepared, Vec::new()).await.unwrap();
+
+        assert_eq!(completion.entry(), Entry { id, height: 1 });
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{processing::Namespace, signup::SignupSettings};
+
+use std::net::SocketAddr;
+
+// Addresses of the brokers through which a `Client` operates
+#[derive(Debug, Clone)]
+pub struct BrokerAddresses {
+    pub signup: SocketAddr,
+    pub prepare: SocketAddr,
+    // Address of the `Broker` resuming the journal of the prepare broker upon
+    // failover (see `Standby`): prepares are redirected to it if the prepare
+    // broker is lost
+    pub prepare_standby: Option<SocketAddr>,
+    pub commit: SocketAddr,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientSettings {
+    // `Namespace` of the instance served by the brokers (see `BrokerAddresses`)
+    pub namespace: Namespace,
+    // Difficulty of the `Work` attached to the first signup attempt. If the allocator
+    // requires more (see `BrokerFailure::InsufficientWork`), signup is retried at the
+    // required difficulty, unless it exceeds `max_work_difficulty`
+    pub work_difficulty: u64,
+    pub max_work_difficulty: u64,
+    // Number of `(view, root)` pairs whose validated `BatchCommit`s are cached
+    pub batch_commit_cache_capacity: usize,
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        let work_difficulty = SignupSettings::default().work_difficulty;
+
+        ClientSettings {
+            namespace: Namespace::default(),
+            work_difficulty,
+            max_work_difficulty: work_difficulty + 8,
+            batch_commit_cache_capacity: 1024,
+        }
+    }
+}
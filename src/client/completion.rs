@@ -0,0 +1,37 @@
+use crate::{
+    account::{Entry, Id, Operation},
+    commit::{BatchCompletion, Completion as CommitCompletion, Payload},
+};
+
+// Proof that a `Payload` was committed, as obtained by `Client::commit`.
+// `Completion`s are passed back to `Client::commit` to satisfy the
+// dependencies of subsequent `Payload`s (see `Payload::dependencies`).
+#[derive(Debug, Clone)]
+pub struct Completion(pub(crate) CommitCompletion);
+
+impl Completion {
+    pub fn payload(&self) -> &Payload {
+        self.0.payload()
+    }
+
+    pub fn entry(&self) -> Entry {
+        self.0.entry()
+    }
+
+    pub fn id(&self) -> Id {
+        self.0.id()
+    }
+
+    pub fn height(&self) -> u64 {
+        self.0.height()
+    }
+
+    pub fn operation(&self) -> &Operation {
+        self.0.operation()
+    }
+
+    // The `BatchCompletion` of the batch `self` was committed in (see `BatchCredits::extract`)
+    pub fn batch(&self) -> &BatchCompletion {
+        self.0.proof().batch()
+    }
+}
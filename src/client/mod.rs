@@ -0,0 +1,7 @@
+mod client;
+mod client_settings;
+mod completion;
+
+pub use client::{Client, ClientError, Prepared};
+pub use client_settings::{BrokerAddresses, ClientSettings};
+pub use completion::Completion;
@@ -3,6 +3,7 @@ use crate::{
     commit::{BatchCompletionShard, BatchCompletionStatement},
     crypto::{Aggregator, Certificate, Identify},
     discovery::Client,
+    processing::Namespace,
     view::View,
 };
 
@@ -15,7 +16,7 @@ use std::collections::{BTreeSet, HashMap};
 use talk::crypto::{primitives::hash::Hash, KeyCard};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct BatchCompletion {
+pub struct BatchCompletion {
     view: Hash,
     root: Hash,
     exceptions: BTreeSet<Id>,
@@ -24,6 +25,7 @@ pub(crate) struct BatchCompletion {
 
 pub(crate) struct BatchCompletionAggregator {
     view: View,
+    namespace: Namespace,
     root: Hash,
     aggregators: HashMap<BTreeSet<Id>, Aggregator<BatchCompletionStatement>>,
 }
@@ -45,14 +47,18 @@ impl BatchCompletion {
         self.exceptions.contains(&id)
     }
 
-    pub fn validate(&self, discovery: &Client) -> Result<(), Top<BatchCompletionError>> {
+    pub(crate) fn validate(&self, discovery: &Client) -> Result<(), Top<BatchCompletionError>> {
         let view = discovery
             .view(&self.view)
             .ok_or(BatchCompletionError::ViewUnknown.into_top())
             .spot(here!())?;
 
-        let statement =
-            BatchCompletionStatement::new(self.view, self.root, self.exceptions.clone());
+        let statement = BatchCompletionStatement::new(
+            discovery.namespace(),
+            self.view,
+            self.root,
+            self.exceptions.clone(),
+        );
 
         self.certificate
             .verify_quorum(&view, &statement)
@@ -63,17 +69,22 @@ impl BatchCompletion {
 }
 
 impl BatchCompletionAggregator {
-    pub fn new(view: View, root: Hash) -> Self {
+    pub fn new(view: View, namespace: &Namespace, root: Hash) -> Self {
         BatchCompletionAggregator {
             view,
+            namespace: namespace.clone(),
             root,
             aggregators: HashMap::new(),
         }
     }
 
     pub fn add(&mut self, completer: &KeyCard, shard: BatchCompletionShard) {
-        let statement =
-            BatchCompletionStatement::new(self.view.identifier(), self.root, shard.exceptions());
+        let statement = BatchCompletionStatement::new(
+            &self.namespace,
+            self.view.identifier(),
+            self.root,
+            shard.exceptions(),
+        );
 
         let aggregator = Aggregator::new(self.view.clone(), statement);
 
@@ -89,7 +100,7 @@ impl BatchCompletionAggregator {
     pub fn complete(&self) -> bool {
         self.aggregators
             .iter()
-            .find(|(_, aggregator)| aggregator.multiplicity() >= self.view.quorum())
+            .find(|(_, aggregator)| aggregator.power() >= self.view.weighted_quorum())
             .is_some()
     }
 
@@ -98,12 +109,13 @@ impl BatchCompletionAggregator {
             view,
             root,
             aggregators,
+            ..
         } = self;
 
-        // Assuming that `self.complete()`, exactly one `Aggregator` in `aggregators` has reached a quorum multiplicity
+        // Assuming that `self.complete()`, exactly one `Aggregator` in `aggregators` has reached a quorum power
         let (exceptions, aggregator) = aggregators
             .into_iter()
-            .find(|(_, aggregator)| aggregator.multiplicity() >= view.quorum())
+            .find(|(_, aggregator)| aggregator.power() >= view.weighted_quorum())
             .unwrap();
 
         let (_, certificate) = aggregator.finalize();
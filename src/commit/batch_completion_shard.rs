@@ -2,6 +2,7 @@ use crate::{
     account::Id,
     commit::{BatchCompletionStatement, Payload},
     crypto::Identify,
+    processing::Namespace,
     view::View,
 };
 
@@ -31,13 +32,19 @@ pub(crate) enum BatchCompletionShardError {
 }
 
 impl BatchCompletionShard {
-    pub fn new<I>(keychain: &KeyChain, view: Hash, root: Hash, exceptions: I) -> Self
+    pub fn new<I>(
+        keychain: &KeyChain,
+        namespace: &Namespace,
+        view: Hash,
+        root: Hash,
+        exceptions: I,
+    ) -> Self
     where
         I: IntoIterator<Item = Id>,
     {
         let exceptions = exceptions.into_iter().collect::<BTreeSet<_>>();
 
-        let statement = BatchCompletionStatement::new(view, root, exceptions.clone());
+        let statement = BatchCompletionStatement::new(namespace, view, root, exceptions.clone());
         let signature = keychain.multisign(&statement).unwrap();
 
         BatchCompletionShard {
@@ -56,6 +63,7 @@ impl BatchCompletionShard {
 
     pub fn validate(
         &self,
+        namespace: &Namespace,
         view: &View,
         root: Hash,
         payloads: &[Payload],
@@ -71,7 +79,8 @@ impl BatchCompletionShard {
         }
 
         let exceptions = self.exceptions.clone();
-        let statement = BatchCompletionStatement::new(view.identifier(), root, exceptions);
+        let statement =
+            BatchCompletionStatement::new(namespace, view.identifier(), root, exceptions);
 
         self.signature
             .verify([completer], &statement)
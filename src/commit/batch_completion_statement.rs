@@ -1,4 +1,4 @@
-use crate::{account::Id, crypto::Header};
+use crate::{account::Id, crypto::Header, processing::Namespace};
 
 use serde::Serialize;
 
@@ -8,14 +8,16 @@ use talk::crypto::{primitives::hash::Hash, Statement};
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct BatchCompletionStatement {
+    namespace: Namespace,
     view: Hash,
     root: Hash,
     exceptions: BTreeSet<Id>,
 }
 
 impl BatchCompletionStatement {
-    pub fn new(view: Hash, root: Hash, exceptions: BTreeSet<Id>) -> Self {
+    pub fn new(namespace: &Namespace, view: Hash, root: Hash, exceptions: BTreeSet<Id>) -> Self {
         BatchCompletionStatement {
+            namespace: namespace.clone(),
             view,
             root,
             exceptions,
@@ -0,0 +1,216 @@
+use crate::{
+    account::{Entry, Id, Operation},
+    commit::{BatchCompletion, Payload},
+    discovery::Client,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+use talk::crypto::primitives::hash::Hash;
+
+use zebra::vector::{Proof, Vector};
+
+// `BatchCredits` packs, out of a completed batch, only the `Payload`s crediting
+// `recipient` (each along with its inclusion `Proof`). This allows `recipient`
+// to verify its credits without obtaining the whole batch. Remark: a `BatchCredits`
+// proves that each of its credits was committed, not that no credit was omitted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCredits {
+    batch: BatchCompletion,
+    recipient: Id,
+    payloads: Vec<(Payload, Proof)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreditSummary {
+    pub recipient: Id,
+    pub root: Hash,
+    pub credits: Vec<Credit>,
+    pub total: u64,
+}
+
+// To collect a `Credit`, its recipient deposits `withdraw` in `slot`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credit {
+    pub withdraw: Entry,
+    pub slot: u64,
+    pub amount: u64,
+    pub kind: CreditKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreditKind {
+    Transfer,
+    // Depositing requires the fulfillment of the withdrawal's `Condition`,
+    // and collects `slot`
+    Escrow,
+}
+
+#[derive(Doom)]
+pub enum BatchCreditsError {
+    #[doom(description("`BatchCompletion` invalid"))]
+    BatchCompletionInvalid,
+    #[doom(description("Inclusion `Proof` invalid"))]
+    InclusionInvalid,
+    #[doom(description("`Payload` is in `BatchCompletion`'s exceptions"))]
+    PayloadException,
+    #[doom(description("`Payload` does not credit the recipient"))]
+    PayloadIrrelevant,
+    #[doom(description("`Payload` included more than once"))]
+    PayloadDuplicated,
+    #[doom(description("Total credit overflows"))]
+    TotalOverflow,
+}
+
+impl BatchCredits {
+    // `payloads` must be the batch completed by `batch`
+    pub fn extract(batch: BatchCompletion, payloads: &Vector<Payload>, recipient: Id) -> Self {
+        let payloads = payloads
+            .items()
+            .iter()
+            .enumerate()
+            .filter(|(_, payload)| {
+                !batch.excepts(payload.id()) && Credit::credit(payload, recipient).is_some()
+            })
+            .map(|(index, payload)| (payload.clone(), payloads.prove(index)))
+            .collect();
+
+        BatchCredits {
+            batch,
+            recipient,
+            payloads,
+        }
+    }
+
+    pub fn recipient(&self) -> Id {
+        self.recipient
+    }
+
+    pub fn root(&self) -> Hash {
+        self.batch.root()
+    }
+
+    // Applications verify `BatchCredits` through `client::Client::verify_credits`
+    pub(crate) fn verify(
+        &self,
+        discovery: &Client,
+    ) -> Result<CreditSummary, Top<BatchCreditsError>> {
+        self.batch
+            .validate(discovery)
+            .pot(BatchCreditsError::BatchCompletionInvalid, here!())?;
+
+        let mut entries = HashSet::with_capacity(self.payloads.len());
+        let mut credits = Vec::with_capacity(self.payloads.len());
+
+        for (payload, inclusion) in self.payloads.iter() {
+            inclusion
+                .verify(self.batch.root(), payload)
+                .pot(BatchCreditsError::InclusionInvalid, here!())?;
+
+            if self.batch.excepts(payload.id()) {
+                return BatchCreditsError::PayloadException.fail().spot(here!());
+            }
+
+            if !entries.insert(payload.entry()) {
+                return BatchCreditsError::PayloadDuplicated.fail().spot(here!());
+            }
+
+            let credit = Credit::credit(payload, self.recipient)
+                .ok_or(BatchCreditsError::PayloadIrrelevant.into_top())
+                .spot(here!())?;
+
+            credits.push(credit);
+        }
+
+        let total = credits
+            .iter()
+            .try_fold(0u64, |total, credit| total.checked_add(credit.amount))
+            .ok_or(BatchCreditsError::TotalOverflow.into_top())
+            .spot(here!())?;
+
+        Ok(CreditSummary {
+            recipient: self.recipient,
+            root: self.batch.root(),
+            credits,
+            total,
+        })
+    }
+}
+
+impl Credit {
+    // `Fee`s are credited to their broker as their `Withdraw` is applied,
+    // and need not be deposited: only beneficiaries are credited here
+    fn credit(payload: &Payload, recipient: Id) -> Option<Credit> {
+        let withdraw = match payload.operation() {
+            Operation::Withdraw(withdraw) => withdraw,
+            _ => return None,
+        };
+
+        if withdraw.beneficiary() != recipient {
+            return None;
+        }
+
+        Some(Credit {
+            withdraw: payload.entry(),
+            slot: withdraw.slot(),
+            amount: withdraw.amount(),
+            kind: if withdraw.condition().is_some() {
+                CreditKind::Escrow
+            } else {
+                CreditKind::Transfer
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::account::operations::Condition;
+
+    #[test]
+    fn credits() {
+        let entry = Entry { id: 1, height: 4 };
+
+        let credits = |operation: Operation, recipient: Id| {
+            Credit::credit(&Payload::new(entry, operation), recipient)
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            credits(Operation::withdraw(2, 7, 100), 2),
+            vec![Credit {
+                withdraw: entry,
+                slot: 7,
+                amount: 100,
+                kind: CreditKind::Transfer,
+            }]
+        );
+
+        assert!(credits(Operation::withdraw(2, 7, 100), 3).is_empty());
+        assert!(credits(Operation::deposit(entry, None, false), 1).is_empty());
+
+        // Fees are not deposited by their broker
+        assert!(credits(Operation::withdraw_with_fee(2, 7, 100, 3, 5), 3).is_empty());
+
+        assert_eq!(
+            credits(Operation::withdraw_with_fee(2, 7, 100, 2, 5), 2)[0].amount,
+            100
+        );
+
+        assert_eq!(
+            credits(
+                Operation::escrow(2, 7, 100, Condition::preimage(b"secret")),
+                2
+            )[0]
+            .kind,
+            CreditKind::Escrow
+        );
+    }
+}
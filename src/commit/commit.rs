@@ -2,6 +2,7 @@ use crate::{
     account::Operation,
     commit::{CommitProof, CommitProofError, Payload},
     discovery::Client,
+    prepare::BatchCommitCache,
 };
 
 use doomstack::Top;
@@ -31,4 +32,13 @@ impl Commit {
         let prepare = self.payload.prepare();
         self.proof.validate(&discovery, &prepare)
     }
+
+    pub fn validate_cached(
+        &self,
+        discovery: &Client,
+        cache: &BatchCommitCache,
+    ) -> Result<(), Top<CommitProofError>> {
+        let prepare = self.payload.prepare();
+        self.proof.validate_cached(discovery, &prepare, cache)
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     discovery::Client,
-    prepare::{BatchCommit, Prepare},
+    prepare::{BatchCommit, BatchCommitCache, Prepare},
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
@@ -39,6 +39,24 @@ impl CommitProof {
             .validate(discovery)
             .pot(CommitProofError::BatchCommitInvalid, here!())?;
 
+        self.validate_inclusion(prepare)
+    }
+
+    // Like `validate`, but `self`'s `BatchCommit` is not re-verified if `cache` already validated it
+    pub fn validate_cached(
+        &self,
+        discovery: &Client,
+        prepare: &Prepare,
+        cache: &BatchCommitCache,
+    ) -> Result<(), Top<CommitProofError>> {
+        cache
+            .validate(&self.batch, discovery)
+            .pot(CommitProofError::BatchCommitInvalid, here!())?;
+
+        self.validate_inclusion(prepare)
+    }
+
+    fn validate_inclusion(&self, prepare: &Prepare) -> Result<(), Top<CommitProofError>> {
         self.inclusion
             .verify(self.batch.root(), prepare)
             .pot(CommitProofError::InclusionInvalid, here!())?;
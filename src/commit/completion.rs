@@ -1,6 +1,6 @@
 use crate::{
     account::{Entry, Id, Operation},
-    commit::{CompletionProof, CompletionProofError, Payload},
+    commit::{CompletionProof, CompletionProofError, ConsistencyToken, Payload},
     discovery::Client,
 };
 
@@ -19,6 +19,10 @@ impl Completion {
         Completion { proof, payload }
     }
 
+    pub fn proof(&self) -> &CompletionProof {
+        &self.proof
+    }
+
     pub fn payload(&self) -> &Payload {
         &self.payload
     }
@@ -39,6 +43,11 @@ impl Completion {
         self.payload.operation()
     }
 
+    // Token to be passed along with subsequent queries in order to observe `self`
+    pub fn token(&self) -> ConsistencyToken {
+        ConsistencyToken::new(self.proof.root(), self.height())
+    }
+
     pub fn validate(&self, discovery: &Client) -> Result<(), Top<CompletionProofError>> {
         self.proof.validate(discovery, &self.payload)
     }
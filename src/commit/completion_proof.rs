@@ -7,6 +7,8 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use serde::{Deserialize, Serialize};
 
+use talk::crypto::primitives::hash::Hash;
+
 use zebra::vector::Proof;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +32,14 @@ impl CompletionProof {
         CompletionProof { batch, inclusion }
     }
 
+    pub fn root(&self) -> Hash {
+        self.batch.root()
+    }
+
+    pub fn batch(&self) -> &BatchCompletion {
+        &self.batch
+    }
+
     pub fn validate(
         &self,
         discovery: &Client,
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::primitives::hash::Hash;
+
+// A `ConsistencyToken` identifies a write (by the root of the batch that
+// committed it, and the height it reached on its account). A replica answers a
+// query carrying a `ConsistencyToken` only once it applied the token's batch, so
+// that a client observes its own writes even when querying a lagging replica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ConsistencyToken {
+    root: Hash,
+    height: u64,
+}
+
+impl ConsistencyToken {
+    pub fn new(root: Hash, height: u64) -> Self {
+        ConsistencyToken { root, height }
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    // A client performing successive writes on the same account need only keep
+    // its latest token: the batch of a later write is applied after all
+    // batches of earlier writes on the same account
+    pub fn latest(self, other: Self) -> Self {
+        if other.height > self.height {
+            other
+        } else {
+            self
+        }
+    }
+}
@@ -75,7 +75,7 @@ impl Extract {
             .ok_or(ExtractError::ViewUnknown.into_top())
             .spot(here!())?;
 
-        let statement = WitnessStatement::new(self.root);
+        let statement = WitnessStatement::new(discovery.namespace(), self.root);
 
         self.witness
             .verify_plurality(&view, &statement)
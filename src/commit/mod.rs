@@ -1,26 +1,32 @@
 mod batch_completion;
 mod batch_completion_shard;
 mod batch_completion_statement;
+mod batch_credits;
 mod commit;
 mod commit_proof;
 mod completion;
 mod completion_proof;
+mod consistency_token;
 mod extract;
 mod payload;
 mod witness_statement;
 mod witnessed_batch;
 
-pub(crate) use batch_completion::{BatchCompletion, BatchCompletionAggregator};
+pub use batch_completion::BatchCompletion;
+pub(crate) use batch_completion::BatchCompletionAggregator;
 pub(crate) use batch_completion_shard::BatchCompletionShard;
 pub(crate) use batch_completion_statement::BatchCompletionStatement;
 
+pub use batch_credits::{BatchCredits, BatchCreditsError, Credit, CreditKind, CreditSummary};
+
 #[allow(unused_imports)]
 pub(crate) use commit::Commit;
 
 pub(crate) use commit_proof::{CommitProof, CommitProofError};
 pub(crate) use completion::Completion;
 pub(crate) use completion_proof::{CompletionProof, CompletionProofError};
+pub(crate) use consistency_token::ConsistencyToken;
 pub(crate) use extract::Extract;
-pub(crate) use payload::Payload;
+pub use payload::Payload;
 pub(crate) use witness_statement::WitnessStatement;
 pub(crate) use witnessed_batch::WitnessedBatch;
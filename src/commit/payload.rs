@@ -1,5 +1,5 @@
 use crate::{
-    account::{Entry, Id, Operation},
+    account::{Entry, Id, KeyDelegation, Operation},
     crypto::Identify,
     prepare::Prepare,
 };
@@ -7,14 +7,28 @@ use crate::{
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Payload {
+pub struct Payload {
     entry: Entry,
     operation: Operation,
+    delegation: Option<KeyDelegation>,
 }
 
 impl Payload {
     pub fn new(entry: Entry, operation: Operation) -> Self {
-        Payload { entry, operation }
+        Payload {
+            entry,
+            operation,
+            delegation: None,
+        }
+    }
+
+    // A `Payload` whose `Prepare` was signed by the delegate of `delegation`
+    pub fn delegated(entry: Entry, operation: Operation, delegation: KeyDelegation) -> Self {
+        Payload {
+            entry,
+            operation,
+            delegation: Some(delegation),
+        }
     }
 
     pub fn entry(&self) -> Entry {
@@ -33,11 +47,29 @@ impl Payload {
         &self.operation
     }
 
-    pub fn dependency(&self) -> Option<Entry> {
-        self.operation.dependency()
+    pub fn delegation(&self) -> Option<&KeyDelegation> {
+        self.delegation.as_ref()
+    }
+
+    // Determines whether or not `self.operation` is within the limits of
+    // `self.delegation` (if any). The validity of `self.delegation` is checked
+    // while preparing: the commitment of `self.prepare()` binds `self.delegation`.
+    pub fn authorized(&self) -> bool {
+        self.delegation.as_ref().map_or(true, |delegation| {
+            delegation.covers(self.entry) && delegation.permits(&self.operation)
+        })
     }
 
-    pub fn prepare(&self) -> Prepare {
-        Prepare::new(self.entry, self.operation.identifier())
+    pub fn dependencies(&self) -> Vec<Entry> {
+        self.operation.dependencies()
+    }
+
+    pub(crate) fn prepare(&self) -> Prepare {
+        let commitment = match &self.delegation {
+            Some(delegation) => delegation.commitment(self.operation.identifier()),
+            None => self.operation.identifier(),
+        };
+
+        Prepare::new(self.entry, commitment)
     }
 }
@@ -1,15 +1,21 @@
-use crate::crypto::Header;
+use crate::{crypto::Header, processing::Namespace};
 
 use serde::Serialize;
 
 use talk::crypto::{primitives::hash::Hash, Statement};
 
 #[derive(Debug, Clone, Serialize)]
-pub(crate) struct WitnessStatement(Hash);
+pub(crate) struct WitnessStatement {
+    namespace: Namespace,
+    root: Hash,
+}
 
 impl WitnessStatement {
-    pub fn new(root: Hash) -> Self {
-        WitnessStatement(root)
+    pub fn new(namespace: &Namespace, root: Hash) -> Self {
+        WitnessStatement {
+            namespace: namespace.clone(),
+            root,
+        }
     }
 }
 
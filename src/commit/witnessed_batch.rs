@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use talk::crypto::primitives::hash::Hash;
 
-use zebra::vector::Vector;
+use zebra::vector::{Proof, Vector};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct WitnessedBatch {
@@ -44,6 +44,10 @@ impl WitnessedBatch {
         self.payloads.items()
     }
 
+    pub fn inclusion(&self, index: usize) -> Proof {
+        self.payloads.prove(index)
+    }
+
     pub fn extract(&self, index: usize) -> Extract {
         Extract::new(
             self.view,
@@ -60,7 +64,7 @@ impl WitnessedBatch {
             .ok_or(WitnessedBatchError::ViewUnknown.into_top())
             .spot(here!())?;
 
-        let statement = WitnessStatement::new(self.payloads.root());
+        let statement = WitnessStatement::new(discovery.namespace(), self.payloads.root());
 
         self.witness
             .verify_plurality(&view, &statement)
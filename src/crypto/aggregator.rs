@@ -59,6 +59,11 @@ where
         self.components.len()
     }
 
+    // Total weight of the members whose components were added
+    pub fn power(&self) -> usize {
+        self.view.power(self.components.keys())
+    }
+
     pub fn finalize(self) -> (S, Certificate) {
         let components = self.components.into_iter().collect::<Vec<_>>();
         let certificate = Certificate::aggregate(&self.view, components);
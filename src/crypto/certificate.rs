@@ -66,7 +66,7 @@ impl Certificate {
 
         #[cfg(debug_assertions)]
         {
-            if certificate.power() < view.plurality() {
+            if certificate.weighted_power(view) < view.weighted_plurality() {
                 panic!("Called `Certificate::aggregate` with an insufficient number of signers for a plurality");
             }
         }
@@ -82,7 +82,7 @@ impl Certificate {
 
         #[cfg(debug_assertions)]
         {
-            if certificate.power() < view.quorum() {
+            if certificate.weighted_power(view) < view.weighted_quorum() {
                 panic!("Called `Certificate::aggregate` with an insufficient number of signers for a quorum");
             }
         }
@@ -94,6 +94,16 @@ impl Certificate {
         self.signers.iter().filter(|mask| *mask).count()
     }
 
+    // Total weight of `self`'s signers in `view`
+    pub fn weighted_power(&self, view: &View) -> usize {
+        view.power(
+            view.members()
+                .keys()
+                .zip(self.signers.iter())
+                .filter_map(|(member, mask)| if mask { Some(member) } else { None }),
+        )
+    }
+
     pub fn verify_raw<S>(&self, view: &View, message: &S) -> Result<(), Top<CertificateError>>
     where
         S: Statement,
@@ -115,6 +125,7 @@ impl Certificate {
             .pot(CertificateError::CertificateInvalid, here!())
     }
 
+    // `threshold` is a weight (see `View::power`)
     pub fn verify_threshold<S>(
         &self,
         view: &View,
@@ -124,28 +135,42 @@ impl Certificate {
     where
         S: Statement,
     {
-        if self.power() >= threshold {
+        if self.weighted_power(view) >= threshold {
             self.verify_raw(view, message)
         } else {
             CertificateError::NotEnoughSigners.fail()
         }
     }
 
+    // Pluralities and quorums are weighted by the members' voting weights in `view`
+
     pub fn verify_plurality<S>(&self, view: &View, message: &S) -> Result<(), Top<CertificateError>>
     where
         S: Statement,
     {
-        self.verify_threshold(view, message, view.plurality())
+        if self.weighted_power(view) >= view.weighted_plurality() {
+            self.verify_raw(view, message)
+        } else {
+            CertificateError::NotEnoughSigners.fail()
+        }
     }
 
     pub fn verify_quorum<S>(&self, view: &View, message: &S) -> Result<(), Top<CertificateError>>
     where
         S: Statement,
     {
-        self.verify_threshold(view, message, view.quorum())
+        if self.weighted_power(view) >= view.weighted_quorum() {
+            self.verify_raw(view, message)
+        } else {
+            CertificateError::NotEnoughSigners.fail()
+        }
     }
 
-    pub fn distinct_power<'c, C>(certificates: C) -> Result<usize, Top<CertificateError>>
+    // Total weight in `view` of the signers of `certificates`, which must be pairwise disjoint
+    pub fn distinct_power<'c, C>(
+        view: &View,
+        certificates: C,
+    ) -> Result<usize, Top<CertificateError>>
     where
         C: IntoIterator<Item = &'c Certificate>,
     {
@@ -176,7 +201,12 @@ impl Certificate {
                 .collect::<Result<Vec<bool>, Top<CertificateError>>>()?;
         }
 
-        Ok(cover.into_iter().filter(|mask| *mask).count())
+        Ok(view.power(
+            view.members()
+                .keys()
+                .zip(cover)
+                .filter_map(|(member, mask)| if mask { Some(member) } else { None }),
+        ))
     }
 }
 
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+// Every `Header` variant is owned by exactly one `Statement` type. Explicit
+// discriminants prevent two variants from sharing a byte (the compiler rejects
+// duplicates), and `tests::bytes` checks that each variant is serialized as its byte.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(i8)]
 pub(crate) enum Header {
@@ -24,4 +27,54 @@ pub(crate) enum Header {
     CommitWitness = 12,
 
     Completion = 13,
+
+    Genesis = 14,
+
+    EscrowRelease = 15,
+
+    KeyDelegation = 16,
+
+    LatticeDisclosure = 17,
+
+    Snapshot = 18,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes() {
+        let headers = [
+            Header::RogueChallenge,
+            Header::Install,
+            Header::LatticeDecisions,
+            Header::Resolution,
+            Header::Resignation,
+            Header::IdRequest,
+            Header::IdAllocation,
+            Header::IdAssignment,
+            Header::Prepare,
+            Header::PrepareReduction,
+            Header::PrepareWitness,
+            Header::Commit,
+            Header::CommitWitness,
+            Header::Completion,
+            Header::Genesis,
+            Header::EscrowRelease,
+            Header::KeyDelegation,
+            Header::LatticeDisclosure,
+            Header::Snapshot,
+        ];
+
+        // `Header`s are serialized by variant index, which must match their byte
+        for (index, header) in headers.iter().enumerate() {
+            assert_eq!(*header as i8 as usize, index);
+
+            assert_eq!(
+                bincode::serialize(header).unwrap(),
+                bincode::serialize(&(index as u32)).unwrap()
+            );
+        }
+    }
 }
@@ -7,5 +7,5 @@ mod rogue;
 pub(crate) use aggregator::Aggregator;
 pub(crate) use certificate::Certificate;
 pub(crate) use header::Header;
-pub(crate) use identify::Identify;
+pub use identify::Identify;
 pub(crate) use rogue::Rogue;
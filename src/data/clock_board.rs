@@ -0,0 +1,187 @@
+use crate::{data::ClockSettings, view::View};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use talk::crypto::Identity;
+
+// A `ClockBoard` estimates the offset between the local clock and the clock of each
+// replica in a `View` (offsets are expressed in milliseconds, positive if the
+// replica's clock is ahead). Expiry-based features should rely on `ClockBoard`
+// rather than on the local clock alone: if the local clock cannot be trusted
+// (i.e., it is skewed with respect to a quorum of replicas), nothing expires.
+#[derive(Clone)]
+pub(crate) struct ClockBoard {
+    view: View,
+    offsets: Arc<Mutex<HashMap<Identity, Option<i64>>>>,
+    settings: ClockSettings,
+}
+
+#[derive(Doom)]
+pub(crate) enum ClockError {
+    #[doom(description("Timestamp is implausibly far in the future"))]
+    TimestampInFuture,
+    #[doom(description("Local clock is not synchronized with a quorum of replicas"))]
+    Unsynchronized,
+}
+
+// Milliseconds elapsed since the UNIX epoch, according to the local clock
+pub(crate) fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl ClockBoard {
+    pub fn new(view: &View, settings: ClockSettings) -> Self {
+        let offsets = view
+            .members()
+            .keys()
+            .copied()
+            .map(|replica| (replica, None))
+            .collect::<HashMap<_, _>>();
+
+        let offsets = Arc::new(Mutex::new(offsets));
+
+        ClockBoard {
+            view: view.clone(),
+            offsets,
+            settings,
+        }
+    }
+
+    // `remote` was read from `replica`'s clock between `sent` and `received`
+    // (both read from the local clock): `replica`'s clock is assumed to have
+    // been read halfway through the round trip
+    pub fn submit(&self, replica: Identity, sent: u64, remote: u64, received: u64) {
+        let midpoint = (sent as i64) + ((received as i64) - (sent as i64)) / 2;
+        let offset = (remote as i64) - midpoint;
+
+        let mut offsets = self.offsets.lock().unwrap();
+        offsets.insert(replica, Some(offset));
+    }
+
+    // Forgets the offset of `replica` (e.g., if `replica` cannot be reached)
+    pub fn reset(&self, replica: Identity) {
+        let mut offsets = self.offsets.lock().unwrap();
+        offsets.insert(replica, None);
+    }
+
+    pub fn offset(&self, replica: Identity) -> Option<i64> {
+        let offsets = self.offsets.lock().unwrap();
+        offsets.get(&replica).copied().flatten()
+    }
+
+    // Replicas whose clock is known to exceed the maximum tolerated skew
+    pub fn skewed(&self) -> Vec<Identity> {
+        let max_skew = self.max_skew();
+        let offsets = self.offsets.lock().unwrap();
+
+        offsets
+            .iter()
+            .filter_map(|(replica, offset)| match offset {
+                Some(offset) if offset.abs() > max_skew => Some(*replica),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The local clock is synchronized if it is within the maximum tolerated
+    // skew of at least a quorum of replicas
+    pub fn synchronized(&self) -> bool {
+        let max_skew = self.max_skew();
+        let offsets = self.offsets.lock().unwrap();
+
+        let power = self.view.power(
+            offsets
+                .iter()
+                .filter(|(_, offset)| matches!(offset, Some(offset) if offset.abs() <= max_skew))
+                .map(|(replica, _)| replica),
+        );
+
+        power >= self.view.weighted_quorum()
+    }
+
+    // Flags `timestamp` (e.g., attached to a statement) as implausible if it
+    // exceeds the local clock by more than the maximum tolerated skew. Past
+    // timestamps are plausible (statements can legitimately be delayed).
+    pub fn check(&self, timestamp: u64) -> Result<(), Top<ClockError>> {
+        if !self.synchronized() {
+            return ClockError::Unsynchronized.fail().spot(here!());
+        }
+
+        if timestamp > self::timestamp().saturating_add(self.max_skew() as u64) {
+            return ClockError::TimestampInFuture.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+
+    // Conservatively establishes whether `expiry` has passed on every clock
+    // within the maximum tolerated skew. If the local clock is unsynchronized,
+    // no expiry is deemed to have passed.
+    pub fn expired(&self, expiry: u64) -> bool {
+        self.synchronized() && self::timestamp() > expiry.saturating_add(self.max_skew() as u64)
+    }
+
+    fn max_skew(&self) -> i64 {
+        self.settings.max_skew.as_millis() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::test::InstallGenerator;
+
+    use std::time::Duration;
+
+    #[test]
+    fn manual() {
+        let generator = InstallGenerator::new(4);
+
+        let view = generator.view(4);
+        let identities = view.members().keys().copied().collect::<Vec<_>>();
+
+        let board = ClockBoard::new(
+            &view,
+            ClockSettings {
+                max_skew: Duration::from_secs(1),
+            },
+        );
+
+        assert!(!board.synchronized());
+        assert!(board.check(timestamp()).is_err());
+
+        board.submit(identities[0], 1000, 1100, 1200);
+        board.submit(identities[1], 1000, 600, 1200);
+        board.submit(identities[2], 1000, 4100, 1200);
+
+        assert_eq!(board.offset(identities[0]), Some(0));
+        assert_eq!(board.offset(identities[1]), Some(-500));
+        assert_eq!(board.offset(identities[2]), Some(3000));
+        assert_eq!(board.offset(identities[3]), None);
+
+        assert_eq!(board.skewed(), vec![identities[2]]);
+        assert!(!board.synchronized());
+
+        board.submit(identities[3], 1000, 1300, 1200);
+        assert!(board.synchronized());
+
+        let now = timestamp();
+
+        assert!(board.check(now).is_ok());
+        assert!(board.check(now - 60_000).is_ok());
+        assert!(board.check(now + 60_000).is_err());
+
+        assert!(board.expired(now - 60_000));
+        assert!(!board.expired(now));
+    }
+}
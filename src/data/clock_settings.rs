@@ -0,0 +1,15 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub(crate) struct ClockSettings {
+    // Maximum tolerated difference between the local clock and a replica's clock
+    pub max_skew: Duration,
+}
+
+impl Default for ClockSettings {
+    fn default() -> Self {
+        ClockSettings {
+            max_skew: Duration::from_secs(5),
+        }
+    }
+}
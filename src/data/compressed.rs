@@ -0,0 +1,131 @@
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use std::convert::TryInto;
+
+// A `Compressed<T>` carries a `T` either as is or, if its serialized form
+// exceeds a threshold, LZ4-compressed (small messages do not compress well
+// enough to be worth the CPU time). The compressed form is prefixed with
+// its decompressed size, which is checked before any decompression.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum Compressed<T> {
+    Plain(T),
+    Lz4(Vec<u8>),
+}
+
+#[derive(Doom)]
+pub(crate) enum CompressedError {
+    #[doom(description("Decompressed size exceeds the maximum allowed"))]
+    TooLarge,
+    #[doom(description("Failed to decompress"))]
+    DecompressFailed,
+    #[doom(description("Failed to deserialize decompressed message"))]
+    DeserializeFailed,
+}
+
+impl<T> Compressed<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn new(message: T, threshold: usize) -> Self {
+        Compressed::compress(&message, threshold).unwrap_or(Compressed::Plain(message))
+    }
+
+    // Returns `None` if the serialized form of `message` is below `threshold`
+    pub fn compress(message: &T, threshold: usize) -> Option<Self> {
+        let serialized = bincode::serialize(message).unwrap();
+
+        if serialized.len() < threshold {
+            None
+        } else {
+            Some(Compressed::Lz4(lz4_flex::compress_prepend_size(
+                serialized.as_slice(),
+            )))
+        }
+    }
+
+    pub fn decompress(self, max_size: usize) -> Result<T, Top<CompressedError>> {
+        match self {
+            Compressed::Plain(message) => Ok(message),
+            Compressed::Lz4(compressed) => {
+                // Check the size prefix before allocating: a small
+                // malicious message could otherwise decompress to gigabytes
+                let size = compressed
+                    .get(0..4)
+                    .ok_or(CompressedError::DecompressFailed.into_top())
+                    .spot(here!())?;
+
+                let size = u32::from_le_bytes(size.try_into().unwrap()) as usize;
+
+                if size > max_size {
+                    return CompressedError::TooLarge.fail().spot(here!());
+                }
+
+                let serialized = lz4_flex::decompress_size_prepended(compressed.as_slice())
+                    .pot(CompressedError::DecompressFailed, here!())?;
+
+                bincode::deserialize(serialized.as_slice())
+                    .pot(CompressedError::DeserializeFailed, here!())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{account::Entry, prepare::Prepare};
+
+    use talk::crypto::primitives::hash;
+
+    use zebra::vector::Vector;
+
+    fn batch(size: u64) -> Vector<Prepare> {
+        let prepares = (0..size)
+            .map(|id| {
+                Prepare::new(
+                    Entry {
+                        id: id * 8,
+                        height: 1,
+                    },
+                    hash::hash(&id).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Vector::new(prepares).unwrap()
+    }
+
+    #[test]
+    fn threshold() {
+        let small = Compressed::new(batch(4), 4096);
+        assert!(matches!(small, Compressed::Plain(_)));
+
+        let large = Compressed::new(batch(1024), 4096);
+        assert!(matches!(large, Compressed::Lz4(_)));
+
+        let decompressed = large.decompress(1 << 20).unwrap();
+        assert_eq!(decompressed.root(), batch(1024).root());
+    }
+
+    #[test]
+    fn too_large() {
+        let large = Compressed::new(batch(1024), 0);
+        assert!(large.decompress(1024).is_err());
+    }
+
+    // Compression saves bandwidth on batches of any size
+    #[test]
+    fn savings() {
+        for size in [64, 1024, 16384] {
+            let plain = bincode::serialize(&batch(size)).unwrap().len();
+            let compressed = bincode::serialize(&Compressed::new(batch(size), 0))
+                .unwrap()
+                .len();
+
+            assert!(compressed < plain);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use std::{fmt, marker::PhantomData};
+
+// Version of the wire format. Any change to the serialized form of a wire
+// message must bump `WIRE_VERSION` (and record a new set of golden vectors,
+// see `data::golden`). `MIN_WIRE_VERSION` is the oldest version whose messages
+// can still be deserialized by this version: wire messages are enveloped only
+// since `WIRE_VERSION` 17, and `IdRequest`s carry chunked `Work` since 18.
+pub(crate) const WIRE_VERSION: u16 = 18;
+pub(crate) const MIN_WIRE_VERSION: u16 = 18;
+
+// Wire messages (see `enveloped!`) are serialized within an `Envelope`, whose
+// `version` is checked before its `payload` is deserialized
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Envelope<Payload> {
+    version: u16,
+    payload: Payload,
+}
+
+#[derive(Doom)]
+pub(crate) enum EnvelopeError {
+    #[doom(description("Version obsolete: {}", version))]
+    VersionObsolete { version: u16 },
+    #[doom(description("Version unsupported: {}", version))]
+    VersionUnsupported { version: u16 },
+}
+
+// Implemented (through `enveloped!`) by wire messages, exposing their unenveloped
+// serialization (as derived with `#[serde(remote = "Self")]`)
+pub(crate) trait Enveloped: Sized {
+    fn serialize_payload<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+
+    fn deserialize_payload<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+// Implements `Serialize` and `Deserialize` for a wire message, which is sent and
+// received within an `Envelope`. The message must derive `Serialize` and
+// `Deserialize` with `#[serde(remote = "Self")]`.
+macro_rules! enveloped {
+    ($message:ident $(<$($generic:ident),+>)?) => {
+        impl$(<$($generic),+>)? $crate::data::Enveloped for $message$(<$($generic),+>)?
+        where
+            $($($generic: ::serde::Serialize + ::serde::de::DeserializeOwned,)+)?
+        {
+            fn serialize_payload<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                $message::serialize(self, serializer)
+            }
+
+            fn deserialize_payload<'de, D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                $message::deserialize(deserializer)
+            }
+        }
+
+        impl$(<$($generic),+>)? ::serde::Serialize for $message$(<$($generic),+>)?
+        where
+            $($($generic: ::serde::Serialize + ::serde::de::DeserializeOwned,)+)?
+        {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                $crate::data::seal(self, serializer)
+            }
+        }
+
+        impl<'de, $($($generic),+)?> ::serde::Deserialize<'de> for $message$(<$($generic),+>)?
+        where
+            $($($generic: ::serde::Serialize + ::serde::de::DeserializeOwned,)+)?
+        {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                $crate::data::unseal(deserializer)
+            }
+        }
+    };
+}
+
+pub(crate) use enveloped;
+
+struct Sealed<'m, Message>(&'m Message);
+
+struct Unsealed<Message>(Message);
+
+struct EnvelopeVisitor<Payload>(PhantomData<Payload>);
+
+impl<Payload> Envelope<Payload> {
+    pub fn new(payload: Payload) -> Self {
+        Envelope {
+            version: WIRE_VERSION,
+            payload,
+        }
+    }
+
+    fn check(version: u16) -> Result<(), Top<EnvelopeError>> {
+        if version < MIN_WIRE_VERSION {
+            EnvelopeError::VersionObsolete { version }
+                .fail()
+                .spot(here!())
+        } else if version > WIRE_VERSION {
+            EnvelopeError::VersionUnsupported { version }
+                .fail()
+                .spot(here!())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Serializes `message` within an `Envelope` (see `enveloped!`)
+pub(crate) fn seal<Message, S>(message: &Message, serializer: S) -> Result<S::Ok, S::Error>
+where
+    Message: Enveloped,
+    S: Serializer,
+{
+    Envelope::new(Sealed(message)).serialize(serializer)
+}
+
+// Deserializes a `Message` from its `Envelope` (see `enveloped!`)
+pub(crate) fn unseal<'de, Message, D>(deserializer: D) -> Result<Message, D::Error>
+where
+    Message: Enveloped,
+    D: Deserializer<'de>,
+{
+    Envelope::<Unsealed<Message>>::deserialize(deserializer).map(|envelope| envelope.payload.0)
+}
+
+impl<'de, Payload> Deserialize<'de> for Envelope<Payload>
+where
+    Payload: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            "Envelope",
+            &["version", "payload"],
+            EnvelopeVisitor(PhantomData),
+        )
+    }
+}
+
+impl<'de, Payload> Visitor<'de> for EnvelopeVisitor<Payload>
+where
+    Payload: Deserialize<'de>,
+{
+    type Value = Envelope<Payload>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an `Envelope`")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let version = seq
+            .next_element::<u16>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        // A `payload` of an obsolete or unsupported version is not even parsed
+        Envelope::<Payload>::check(version)
+            .map_err(|error| de::Error::custom(format!("{:?}", error)))?;
+
+        let payload = seq
+            .next_element::<Payload>()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        Ok(Envelope { version, payload })
+    }
+}
+
+impl<Message> Serialize for Sealed<'_, Message>
+where
+    Message: Enveloped,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_payload(serializer)
+    }
+}
+
+impl<'de, Message> Deserialize<'de> for Unsealed<Message>
+where
+    Message: Enveloped,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Message::deserialize_payload(deserializer).map(Unsealed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(remote = "Self")]
+    enum Message {
+        Ping,
+        Value(u32),
+    }
+
+    enveloped!(Message);
+
+    fn reopen(envelope: Envelope<u32>) -> Option<u32> {
+        let serialized = bincode::serialize(&envelope).unwrap();
+
+        bincode::deserialize::<Envelope<u32>>(serialized.as_slice())
+            .ok()
+            .map(|envelope| envelope.payload)
+    }
+
+    #[test]
+    fn current() {
+        let envelope = Envelope::new(42u32);
+        assert_eq!(envelope.version, WIRE_VERSION);
+        assert_eq!(reopen(envelope), Some(42));
+    }
+
+    #[test]
+    fn unsupported() {
+        let envelope = Envelope {
+            version: WIRE_VERSION + 1,
+            payload: 42u32,
+        };
+
+        assert_eq!(reopen(envelope), None);
+    }
+
+    #[test]
+    fn obsolete() {
+        let envelope = Envelope {
+            version: MIN_WIRE_VERSION - 1,
+            payload: 42u32,
+        };
+
+        assert_eq!(reopen(envelope), None);
+    }
+
+    #[test]
+    fn wire() {
+        let serialized = bincode::serialize(&Message::Value(42)).unwrap();
+
+        // `Message`s are prefixed by `WIRE_VERSION`
+        assert_eq!(&serialized[..2], &WIRE_VERSION.to_le_bytes());
+
+        assert_eq!(
+            bincode::deserialize::<Message>(serialized.as_slice()).unwrap(),
+            Message::Value(42)
+        );
+
+        // `Message`s of other versions are rejected, whatever their payload
+
+        for version in [MIN_WIRE_VERSION - 1, WIRE_VERSION + 1] {
+            let mut serialized = bincode::serialize(&Message::Ping).unwrap();
+            serialized[..2].copy_from_slice(&version.to_le_bytes());
+
+            assert!(bincode::deserialize::<Message>(serialized.as_slice()).is_err());
+        }
+    }
+}
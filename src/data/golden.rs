@@ -0,0 +1,117 @@
+use crate::data::WIRE_VERSION;
+
+use lazy_static::lazy_static;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::{env, fs, path::PathBuf};
+
+use talk::crypto::KeyChain;
+
+const RECORD_GOLDEN_VARIABLE: &str = "CARBON_RECORD_GOLDEN";
+const KEYCHAINS: usize = 4;
+
+// First `WIRE_VERSION` with golden vectors: every later version keeps its own set
+const FIRST_GOLDEN_VERSION: u16 = 12;
+
+lazy_static! {
+    static ref GOLDEN_KEYCHAINS: Vec<KeyChain> = load_keychains();
+}
+
+// Golden vectors capture the serialized form of wire messages, committed under
+// `fixtures/golden/v{WIRE_VERSION}`. A golden test fails if the serialized form
+// of its message changes, or if its fixture is missing. An intentional change to
+// the wire format must bump `WIRE_VERSION`, which starts a new set of fixtures
+// while preserving the old ones: the new set is recorded by running the golden
+// tests with `RECORD_GOLDEN_VARIABLE` set, then committed. Signed messages are
+// captured by signing with `keychains()`.
+pub(crate) fn check<M>(name: &str, message: &M)
+where
+    M: Serialize + DeserializeOwned,
+{
+    let serialized = bincode::serialize(message).unwrap();
+
+    // The serialized form of `message` must survive a round trip
+    let deserialized = bincode::deserialize::<M>(serialized.as_slice()).unwrap();
+    assert_eq!(bincode::serialize(&deserialized).unwrap(), serialized);
+
+    let path = directory().join(format!("{}.bin", name));
+
+    match fs::read(&path) {
+        Ok(fixture) => {
+            assert!(
+                fixture == serialized,
+                "serialized form of `{}` does not match its golden vector (if the change is intentional, bump `WIRE_VERSION`)",
+                name
+            );
+        }
+        Err(_) if env::var_os(RECORD_GOLDEN_VARIABLE).is_some() => {
+            fs::create_dir_all(directory()).unwrap();
+            fs::write(&path, serialized).unwrap();
+        }
+        Err(_) => {
+            panic!(
+                "golden vector of `{}` is missing (set `{}` to record it)",
+                name, RECORD_GOLDEN_VARIABLE
+            );
+        }
+    }
+}
+
+// Deterministic `KeyChain`s, to sign the messages captured by golden vectors. They
+// are generated once, recorded (like golden vectors) and shared by all versions.
+pub(crate) fn keychains() -> Vec<KeyChain> {
+    GOLDEN_KEYCHAINS.clone()
+}
+
+fn load_keychains() -> Vec<KeyChain> {
+    let path = root().join("keychains.bin");
+
+    match fs::read(&path) {
+        Ok(fixture) => bincode::deserialize(fixture.as_slice()).unwrap(),
+        Err(_) if env::var_os(RECORD_GOLDEN_VARIABLE).is_some() => {
+            let keychains = (0..KEYCHAINS)
+                .map(|_| KeyChain::random())
+                .collect::<Vec<_>>();
+
+            fs::create_dir_all(root()).unwrap();
+            fs::write(&path, bincode::serialize(&keychains).unwrap()).unwrap();
+
+            keychains
+        }
+        Err(_) => {
+            panic!(
+                "golden `KeyChain`s are missing (set `{}` to record them)",
+                RECORD_GOLDEN_VARIABLE
+            );
+        }
+    }
+}
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join("golden")
+}
+
+fn directory() -> PathBuf {
+    root().join(format!("v{}", WIRE_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserved() {
+        for version in FIRST_GOLDEN_VERSION..=WIRE_VERSION {
+            let directory = root().join(format!("v{}", version));
+
+            assert!(
+                directory.is_dir(),
+                "golden vectors of version {} are missing",
+                version
+            );
+        }
+    }
+}
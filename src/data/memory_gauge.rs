@@ -0,0 +1,215 @@
+use crate::data::MemorySettings;
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use tokio::sync::watch;
+
+// A `MemoryGauge` accounts for the estimated memory footprint of a component
+// (e.g., the requests held by a broker, or a replica's `Database`) against the
+// watermarks of its `MemorySettings`. Once the footprint exceeds the high
+// watermark, the component is shedding: rather than growing until it is killed
+// for running out of memory, it rejects new load until the footprint falls back
+// to the low watermark. The gap between the two watermarks prevents shedding
+// from flapping while the footprint hovers around a single threshold.
+// All clones of a `MemoryGauge` share the same state.
+#[derive(Debug, Clone)]
+pub(crate) struct MemoryGauge {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    settings: MemorySettings,
+    usage: AtomicUsize,
+    shedding_inlet: watch::Sender<bool>,
+    shedding_outlet: watch::Receiver<bool>,
+    episodes: AtomicU64,
+}
+
+// A `MemoryReservation` accounts for its bytes in its `MemoryGauge`
+// until it is dropped
+pub(crate) struct MemoryReservation {
+    gauge: MemoryGauge,
+    bytes: usize,
+}
+
+impl MemoryGauge {
+    pub fn new(settings: MemorySettings) -> Self {
+        let (shedding_inlet, shedding_outlet) = watch::channel(false);
+
+        MemoryGauge {
+            inner: Arc::new(Inner {
+                settings,
+                usage: AtomicUsize::new(0),
+                shedding_inlet,
+                shedding_outlet,
+                episodes: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    // Estimated footprint, in bytes
+    pub fn usage(&self) -> usize {
+        self.inner.usage.load(Ordering::Relaxed)
+    }
+
+    pub fn is_shedding(&self) -> bool {
+        *self.inner.shedding_outlet.borrow()
+    }
+
+    // Number of times the high watermark was exceeded
+    pub fn episodes(&self) -> u64 {
+        self.inner.episodes.load(Ordering::Relaxed)
+    }
+
+    pub async fn await_relieved(&self) {
+        let mut shedding_outlet = self.inner.shedding_outlet.clone();
+
+        loop {
+            let shedding = *shedding_outlet.borrow();
+
+            if !shedding {
+                return;
+            }
+
+            // This cannot fail: `self.inner` holds the sender
+            let _ = shedding_outlet.changed().await;
+        }
+    }
+
+    pub fn reserve(&self, bytes: usize) -> MemoryReservation {
+        let usage = self.inner.usage.fetch_add(bytes, Ordering::Relaxed);
+        self.update(usage.saturating_add(bytes));
+
+        MemoryReservation {
+            gauge: self.clone(),
+            bytes,
+        }
+    }
+
+    // Overrides the footprint (for components whose footprint is
+    // periodically estimated rather than incrementally accounted for)
+    pub fn set(&self, usage: usize) {
+        self.inner.usage.store(usage, Ordering::Relaxed);
+        self.update(usage);
+    }
+
+    fn release(&self, bytes: usize) {
+        let usage = self.inner.usage.fetch_sub(bytes, Ordering::Relaxed);
+        self.update(usage.saturating_sub(bytes));
+    }
+
+    fn update(&self, usage: usize) {
+        let settings = &self.inner.settings;
+
+        if usage > settings.high_watermark && !self.is_shedding() {
+            log::warn!(
+                "Memory footprint ({} B) above high watermark ({} B): shedding load",
+                usage,
+                settings.high_watermark
+            );
+
+            self.inner.episodes.fetch_add(1, Ordering::Relaxed);
+            self.set_shedding(true);
+        } else if usage <= settings.low_watermark && self.is_shedding() {
+            log::info!(
+                "Memory footprint ({} B) back to low watermark ({} B): resuming",
+                usage,
+                settings.low_watermark
+            );
+
+            self.set_shedding(false);
+        }
+    }
+
+    fn set_shedding(&self, shedding: bool) {
+        // This cannot fail: `self.inner` holds a receiver
+        let _ = self.inner.shedding_inlet.send(shedding);
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.gauge.release(self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tokio::time;
+
+    fn gauge(high_watermark: usize, low_watermark: usize) -> MemoryGauge {
+        MemoryGauge::new(MemorySettings {
+            high_watermark,
+            low_watermark,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn hysteresis() {
+        let gauge = gauge(100, 50);
+
+        let first = gauge.reserve(60);
+        let second = gauge.reserve(40);
+
+        assert_eq!(gauge.usage(), 100);
+        assert!(!gauge.is_shedding());
+
+        let third = gauge.reserve(1);
+
+        assert!(gauge.is_shedding());
+        assert_eq!(gauge.episodes(), 1);
+
+        // Shedding continues until the low watermark is reached
+
+        drop(third);
+        drop(second);
+
+        assert_eq!(gauge.usage(), 60);
+        assert!(gauge.is_shedding());
+
+        drop(first);
+
+        assert_eq!(gauge.usage(), 0);
+        assert!(!gauge.is_shedding());
+
+        gauge.set(101);
+
+        assert!(gauge.is_shedding());
+        assert_eq!(gauge.episodes(), 2);
+    }
+
+    #[test]
+    fn disabled() {
+        let gauge = MemoryGauge::new(MemorySettings::default());
+        let _reservation = gauge.reserve(usize::MAX);
+
+        assert!(!gauge.is_shedding());
+    }
+
+    #[tokio::test]
+    async fn relief() {
+        let gauge = gauge(10, 0);
+        let reservation = gauge.reserve(11);
+
+        assert!(
+            time::timeout(Duration::from_millis(50), gauge.await_relieved())
+                .await
+                .is_err()
+        );
+
+        drop(reservation);
+
+        time::timeout(Duration::from_secs(1), gauge.await_relieved())
+            .await
+            .unwrap();
+    }
+}
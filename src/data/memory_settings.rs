@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MemorySettings {
+    // Once the estimated footprint (in bytes) exceeds `high_watermark`, load is
+    // shed until the footprint falls to `low_watermark` or below (by default,
+    // load is never shed)
+    pub high_watermark: usize,
+    pub low_watermark: usize,
+    // Interval between two estimates of a footprint that is not accounted for
+    // incrementally (e.g., a replica's `Database`)
+    pub probe_interval: Duration,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        MemorySettings {
+            high_watermark: usize::MAX,
+            low_watermark: usize::MAX,
+            probe_interval: Duration::from_secs(1),
+        }
+    }
+}
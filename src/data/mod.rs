@@ -1,9 +1,37 @@
+mod clock_board;
+mod clock_settings;
+mod compressed;
+mod envelope;
+mod memory_gauge;
+mod memory_settings;
 mod ping_board;
+mod quorum_monitor;
 mod shift_vec;
 mod sponge;
 mod sponge_settings;
+mod straggler_board;
 
+#[cfg(test)]
+pub(crate) mod golden;
+
+#[allow(unused_imports)]
+pub(crate) use clock_board::{timestamp, ClockBoard, ClockError};
+
+pub(crate) use clock_settings::ClockSettings;
+
+#[allow(unused_imports)]
+pub(crate) use compressed::{Compressed, CompressedError};
+
+#[allow(unused_imports)]
+pub(crate) use envelope::{
+    enveloped, seal, unseal, Envelope, EnvelopeError, Enveloped, MIN_WIRE_VERSION, WIRE_VERSION,
+};
+#[allow(unused_imports)]
+pub(crate) use memory_gauge::{MemoryGauge, MemoryReservation};
+pub(crate) use memory_settings::MemorySettings;
 pub(crate) use ping_board::PingBoard;
+pub(crate) use quorum_monitor::QuorumMonitor;
 pub(crate) use shift_vec::ShiftVec;
 pub(crate) use sponge::Sponge;
 pub(crate) use sponge_settings::SpongeSettings;
+pub(crate) use straggler_board::StragglerBoard;
@@ -1,15 +1,23 @@
 use crate::view::View;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
     time::Duration,
 };
 
 use talk::crypto::Identity;
 
+use tokio::sync::Notify;
+
+// Replicas whose last ping failed are scored `Duration::MAX`
 #[derive(Clone)]
-pub(crate) struct PingBoard(Arc<Mutex<HashMap<Identity, Duration>>>);
+pub(crate) struct PingBoard {
+    view: View,
+    board: Arc<Mutex<HashMap<Identity, Duration>>>,
+    compression: Arc<Mutex<HashSet<Identity>>>,
+    update: Arc<Notify>,
+}
 
 impl PingBoard {
     pub fn new(view: &View) -> Self {
@@ -21,17 +29,80 @@ impl PingBoard {
             .collect::<HashMap<_, _>>();
 
         let board = Arc::new(Mutex::new(board));
-
-        PingBoard(board)
+        let compression = Arc::new(Mutex::new(HashSet::new()));
+        let update = Arc::new(Notify::new());
+
+        PingBoard {
+            view: view.clone(),
+            board,
+            compression,
+            update,
+        }
     }
 
     pub fn submit(&self, replica: Identity, ping: Duration) {
-        let mut board = self.0.lock().unwrap();
-        board.insert(replica, ping);
+        self.board.lock().unwrap().insert(replica, ping);
+        self.update.notify_waiters();
+    }
+
+    pub fn set_compression(&self, replica: Identity, compression: bool) {
+        let mut board = self.compression.lock().unwrap();
+
+        if compression {
+            board.insert(replica);
+        } else {
+            board.remove(&replica);
+        }
+    }
+
+    // Whether `replica` advertised (upon its last successful ping)
+    // that it accepts compressed batches
+    pub fn compression(&self, replica: &Identity) -> bool {
+        self.compression.lock().unwrap().contains(replica)
+    }
+
+    // Voting weight of the replicas whose last ping succeeded
+    pub fn responsive(&self) -> usize {
+        let board = self.board.lock().unwrap();
+
+        self.view.power(
+            board
+                .iter()
+                .filter(|(_, ping)| **ping < Duration::MAX)
+                .map(|(replica, _)| replica),
+        )
+    }
+
+    // Waits until the replicas responsive weigh at least `power`
+    pub async fn await_responsive(&self, power: usize) {
+        loop {
+            // `notified` is created before checking `self.responsive()`,
+            // so that no `submit` can be missed in between
+            let notified = self.update.notified();
+
+            if self.responsive() >= power {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+
+    // Waits until the replicas responsive weigh less than `power`
+    pub async fn await_unresponsive(&self, power: usize) {
+        loop {
+            let notified = self.update.notified();
+
+            if self.responsive() < power {
+                return;
+            }
+
+            notified.await;
+        }
     }
 
     pub fn rankings(&self) -> Vec<Identity> {
-        let board = self.0.lock().unwrap();
+        let board = self.board.lock().unwrap();
 
         let mut pings = board
             .iter()
@@ -65,5 +136,31 @@ mod tests {
 
         let rankings = board.rankings();
         assert_eq!(rankings, identities);
+
+        assert_eq!(board.responsive(), 3);
+
+        board.submit(identities[0], Duration::MAX);
+        assert_eq!(board.responsive(), 2);
+    }
+
+    #[test]
+    fn compression() {
+        let generator = InstallGenerator::new(4);
+
+        let view = generator.view(4);
+        let identities = view.members().keys().copied().collect::<Vec<_>>();
+
+        let board = PingBoard::new(&view);
+
+        assert!(identities.iter().all(|replica| !board.compression(replica)));
+
+        board.set_compression(identities[0], true);
+        board.set_compression(identities[1], false);
+
+        assert!(board.compression(&identities[0]));
+        assert!(!board.compression(&identities[1]));
+
+        board.set_compression(identities[0], false);
+        assert!(!board.compression(&identities[0]));
     }
 }
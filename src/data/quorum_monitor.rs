@@ -0,0 +1,172 @@
+use crate::data::PingBoard;
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{sync::watch, time};
+
+// A `QuorumMonitor` declares a broker degraded once fewer than a quorum of
+// replicas remain responsive (according to its `PingBoard`) for longer than a
+// grace period. A degraded broker cannot make progress: rather than hanging, its
+// brokerages fail with `BrokerFailure::Unavailable`. The broker recovers from
+// degradation as soon as a quorum of replicas is responsive again.
+//
+// Remark: replicas are declared unresponsive only when pinged, so degradation
+// is detected at most one ping interval (plus the grace period) late.
+// All clones of a `QuorumMonitor` share the same state.
+#[derive(Debug, Clone)]
+pub(crate) struct QuorumMonitor {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    degraded_inlet: watch::Sender<bool>,
+    degraded_outlet: watch::Receiver<bool>,
+    episodes: AtomicU64,
+}
+
+impl QuorumMonitor {
+    pub fn new() -> Self {
+        let (degraded_inlet, degraded_outlet) = watch::channel(false);
+
+        QuorumMonitor {
+            inner: Arc::new(Inner {
+                degraded_inlet,
+                degraded_outlet,
+                episodes: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        *self.inner.degraded_outlet.borrow()
+    }
+
+    // Number of times the broker entered degradation
+    pub fn episodes(&self) -> u64 {
+        self.inner.episodes.load(Ordering::Relaxed)
+    }
+
+    pub async fn await_degraded(&self) {
+        let mut degraded_outlet = self.inner.degraded_outlet.clone();
+
+        loop {
+            let degraded = *degraded_outlet.borrow();
+
+            if degraded {
+                return;
+            }
+
+            // This cannot fail: `self.inner` holds the sender
+            let _ = degraded_outlet.changed().await;
+        }
+    }
+
+    // Monitors `ping_board`, never returns. Degradation is monitored only after
+    // `quorum` replicas are first responsive (i.e., once the broker is ready)
+    pub async fn run(&self, ping_board: PingBoard, quorum: usize, grace: Duration) {
+        ping_board.await_responsive(quorum).await;
+
+        loop {
+            ping_board.await_unresponsive(quorum).await;
+
+            // Unavailability is sustained only if it outlasts `grace`
+            if time::timeout(grace, ping_board.await_responsive(quorum))
+                .await
+                .is_ok()
+            {
+                continue;
+            }
+
+            log::error!(
+                "Responsive replicas weigh only {} (quorum: {}), failing brokerages",
+                ping_board.responsive(),
+                quorum
+            );
+
+            self.inner.episodes.fetch_add(1, Ordering::Relaxed);
+            self.set_degraded(true);
+
+            ping_board.await_responsive(quorum).await;
+
+            log::info!("A quorum of replicas is responsive again: resuming brokerages");
+
+            self.set_degraded(false);
+        }
+    }
+
+    fn set_degraded(&self, degraded: bool) {
+        // This cannot fail: `self.inner` holds a receiver
+        let _ = self.inner.degraded_inlet.send(degraded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::test::InstallGenerator;
+
+    use tokio::task;
+
+    #[tokio::test]
+    async fn degrade_and_recover() {
+        let view = InstallGenerator::new(4).view(4);
+        let identities = view.members().keys().copied().collect::<Vec<_>>();
+
+        let ping_board = PingBoard::new(&view);
+        let monitor = QuorumMonitor::new();
+
+        {
+            let ping_board = ping_board.clone();
+            let monitor = monitor.clone();
+
+            task::spawn(async move {
+                monitor
+                    .run(
+                        ping_board,
+                        view.weighted_quorum(),
+                        Duration::from_millis(50),
+                    )
+                    .await;
+            });
+        }
+
+        for identity in identities.iter().copied() {
+            ping_board.submit(identity, Duration::from_millis(1));
+        }
+
+        // Short unavailability is tolerated
+
+        ping_board.submit(identities[0], Duration::MAX);
+        ping_board.submit(identities[1], Duration::MAX);
+        time::sleep(Duration::from_millis(10)).await;
+        ping_board.submit(identities[0], Duration::from_millis(1));
+
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(!monitor.is_degraded());
+
+        // Sustained unavailability degrades the broker
+
+        ping_board.submit(identities[0], Duration::MAX);
+
+        time::timeout(Duration::from_secs(1), monitor.await_degraded())
+            .await
+            .unwrap();
+
+        assert_eq!(monitor.episodes(), 1);
+
+        // Connectivity returns
+
+        ping_board.submit(identities[0], Duration::from_millis(1));
+        time::sleep(Duration::from_millis(10)).await;
+
+        assert!(!monitor.is_degraded());
+    }
+}
@@ -2,7 +2,10 @@ use crate::data::SpongeSettings;
 
 use std::{
     mem,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::Instant,
 };
 
@@ -14,6 +17,8 @@ pub(crate) struct Sponge<Item> {
     database: Mutex<Database<Item>>,
     notify: Arc<Notify>,
     settings: SpongeSettings,
+    // Once expedited, `flush` returns as soon as `self` is non-empty
+    expedited: AtomicBool,
     fuse: Fuse,
 }
 
@@ -38,6 +43,7 @@ impl<Item> Sponge<Item> {
             notify,
             fuse,
             settings,
+            expedited: AtomicBool::new(false),
         }
     }
 
@@ -58,7 +64,7 @@ impl<Item> Sponge<Item> {
             });
         }
 
-        if database.items.len() >= self.settings.capacity {
+        if database.items.len() >= self.settings.capacity || self.is_expedited() {
             self.notify.notify_one();
         }
     }
@@ -75,6 +81,7 @@ impl<Item> Sponge<Item> {
 
             if database.items.len() >= self.settings.capacity
                 || database.start.elapsed() > self.settings.timeout
+                || self.is_expedited()
             {
                 let mut flush = Vec::new();
                 mem::swap(&mut flush, &mut database.items);
@@ -83,6 +90,31 @@ impl<Item> Sponge<Item> {
             }
         }
     }
+
+    // Stops batching (e.g., to drain `self` before shutting down): items are
+    // flushed without waiting for `self`'s capacity or timeout
+    pub fn expedite(&self) {
+        self.expedited.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    pub fn is_expedited(&self) -> bool {
+        self.expedited.load(Ordering::Relaxed)
+    }
+
+    // Number of items currently in `self`
+    pub fn len(&self) -> usize {
+        self.database.lock().unwrap().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Takes all items in `self` without waiting for its capacity or timeout
+    pub fn drain(&self) -> Vec<Item> {
+        mem::take(&mut self.database.lock().unwrap().items)
+    }
 }
 
 #[cfg(test)]
@@ -204,4 +236,21 @@ mod tests {
             time::sleep(Duration::from_millis(1)).await;
         }
     }
+
+    #[tokio::test]
+    async fn expedite() {
+        let sponge = Arc::new(Sponge::new(SpongeSettings {
+            capacity: 10,
+            timeout: Duration::from_secs(60),
+        }));
+
+        sponge.push(42u32);
+        sponge.expedite();
+
+        let flush = time::timeout(Duration::from_secs(1), sponge.flush())
+            .await
+            .unwrap();
+
+        assert_eq!(flush, vec![42]);
+    }
 }
@@ -0,0 +1,99 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use talk::crypto::Identity;
+
+// A `StragglerBoard` counts, for each replica, the consecutive brokerages in which
+// the replica was asked for a shard but failed to provide one in time. Replicas
+// that miss `threshold` consecutive brokerages are stragglers: they are reported,
+// and ranked after all other replicas (see `demote`) until they provide a shard again.
+// Unlike pings, misses reflect the replicas' actual performance on batches.
+// All clones of a `StragglerBoard` share the same state.
+#[derive(Debug, Clone)]
+pub(crate) struct StragglerBoard {
+    threshold: usize,
+    misses: Arc<Mutex<HashMap<Identity, usize>>>,
+}
+
+impl StragglerBoard {
+    pub fn new(threshold: usize) -> Self {
+        StragglerBoard {
+            threshold,
+            misses: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn hit(&self, replica: Identity) {
+        let misses = self.misses.lock().unwrap().remove(&replica);
+
+        if misses.map_or(false, |misses| misses >= self.threshold) {
+            log::info!("Replica {:?} is responsive again", replica);
+        }
+    }
+
+    pub fn miss(&self, replica: Identity) {
+        let mut board = self.misses.lock().unwrap();
+
+        let misses = board.entry(replica).or_insert(0);
+        *misses += 1;
+
+        if *misses == self.threshold {
+            log::warn!(
+                "Replica {:?} missed {} consecutive brokerages: deprioritizing",
+                replica,
+                misses
+            );
+        }
+    }
+
+    pub fn stragglers(&self) -> Vec<Identity> {
+        self.misses
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, misses)| **misses >= self.threshold)
+            .map(|(replica, _)| *replica)
+            .collect()
+    }
+
+    // Moves stragglers to the end of `rankings`, preserving relative order otherwise
+    pub fn demote(&self, rankings: &mut Vec<Identity>) {
+        let stragglers = self.stragglers();
+        rankings.sort_by_key(|replica| stragglers.contains(replica));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::test::InstallGenerator;
+
+    #[test]
+    fn demote() {
+        let generator = InstallGenerator::new(4);
+
+        let view = generator.view(4);
+        let mut rankings = view.members().keys().copied().collect::<Vec<_>>();
+
+        let board = StragglerBoard::new(2);
+
+        board.miss(rankings[0]);
+        board.miss(rankings[1]);
+        board.miss(rankings[1]);
+
+        assert_eq!(board.stragglers(), vec![rankings[1]]);
+
+        let expected = vec![rankings[0], rankings[2], rankings[3], rankings[1]];
+        board.demote(&mut rankings);
+
+        assert_eq!(rankings, expected);
+
+        // A single hit clears a straggler
+
+        board.hit(rankings[3]);
+        assert!(board.stragglers().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::account::{Account, AccountSettings, AccountSummary, Id};
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    mem,
+    sync::{Mutex, MutexGuard},
+};
+
+const SHARDS: usize = 64;
+
+// `Account`s are sharded by `Id` across independently locked shards, so that commit
+// batches touching disjoint shards apply concurrently (see `CommitApplication`).
+//
+// Locking order: the lock of the owning `Database` (if needed) is always acquired
+// before any shard, and shards are always acquired in increasing index. Guards are
+// acquired all at once (see `lock`), so that operations spanning multiple accounts
+// (e.g., a `Withdraw` crediting a fee to its broker) never deadlock.
+pub(crate) struct Accounts {
+    shards: Vec<Mutex<HashMap<Id, Account>>>,
+    // Effects of applied batches on the rest of the `Database`, yet to be
+    // settled under the `Database`'s lock (see `Database::settle`)
+    unsettled: Mutex<Vec<Settlement>>,
+}
+
+pub(crate) struct AccountsGuard<'a> {
+    shards: BTreeMap<usize, MutexGuard<'a, HashMap<Id, Account>>>,
+}
+
+#[derive(Default)]
+pub(in crate::database) struct Settlement {
+    pub summaries: HashMap<Id, AccountSummary>,
+    pub closures: Vec<(Id, u64)>,
+}
+
+impl Accounts {
+    pub fn new() -> Self {
+        Accounts {
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            unsettled: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn shard(id: Id) -> usize {
+        (id % (SHARDS as u64)) as usize
+    }
+
+    // Locks the shards owning `ids`, in increasing index
+    pub fn lock<I>(&self, ids: I) -> AccountsGuard
+    where
+        I: IntoIterator<Item = Id>,
+    {
+        let indices = ids
+            .into_iter()
+            .map(Accounts::shard)
+            .collect::<BTreeSet<_>>();
+
+        let shards = indices
+            .into_iter()
+            .map(|index| (index, self.shards[index].lock().unwrap()))
+            .collect();
+
+        AccountsGuard { shards }
+    }
+
+    pub fn lock_all(&self) -> AccountsGuard {
+        self.lock(0..(SHARDS as u64))
+    }
+
+    pub(in crate::database) fn defer(&self, settlement: Settlement) {
+        self.unsettled.lock().unwrap().push(settlement);
+    }
+
+    pub(in crate::database) fn unsettled(&self) -> Vec<Settlement> {
+        mem::take(&mut *self.unsettled.lock().unwrap())
+    }
+}
+
+impl AccountsGuard<'_> {
+    // `id`'s shard must be locked by `self`
+    pub fn get(&self, id: Id) -> Option<&Account> {
+        self.shards[&Accounts::shard(id)].get(&id)
+    }
+
+    // `id`'s shard must be locked by `self`. If no operation was previously applied
+    // from `id`, an empty `Account` is initialized.
+    pub fn entry(&mut self, id: Id, settings: &AccountSettings) -> &mut Account {
+        self.shards
+            .get_mut(&Accounts::shard(id))
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| Account::new(id, settings))
+    }
+
+    // `id`'s shard must be locked by `self`
+    pub fn insert(&mut self, id: Id, account: Account) {
+        self.shards
+            .get_mut(&Accounts::shard(id))
+            .unwrap()
+            .insert(id, account);
+    }
+
+    // Every `Account` in the shards locked by `self`, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &Account)> {
+        self.shards
+            .values()
+            .flat_map(|shard| shard.iter().map(|(id, account)| (*id, account)))
+    }
+
+    // Each locked shard, along with its index
+    pub(in crate::database) fn shards_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (usize, &mut HashMap<Id, Account>)> {
+        self.shards
+            .iter_mut()
+            .map(|(index, shard)| (*index, &mut **shard))
+    }
+}
@@ -0,0 +1,547 @@
+use buckets::Split;
+
+use crate::{
+    account::{operations::Fee, Account, AccountSettings, AccountSummary, Id, Operation},
+    commit::{BatchCompletion, Payload, WitnessedBatch},
+    database::{
+        accounts::{Accounts, AccountsGuard, Settlement},
+        commit::{BatchHolder, PayloadHandle},
+        Database, RecordRef, StorageError,
+    },
+};
+
+use doomstack::Top;
+
+use rayon::prelude::*;
+
+use std::collections::HashMap;
+
+use zebra::database::TableTransaction;
+
+impl Database {
+    // Stores `completion` in `completion.root()`'s `BatchHolder`, if still available
+    pub fn attach_batch_completion(
+        &mut self,
+        completion: BatchCompletion,
+    ) -> Result<(), Top<StorageError>> {
+        if !self.commit.batches.contains_key(&completion.root()) {
+            return Ok(());
+        }
+
+        self.write(RecordRef::BatchCompletion(&completion))?;
+        self.batch_completion_unlogged(completion);
+
+        Ok(())
+    }
+
+    // Determines whether every `Entry` in `batch.payloads()` is applicable
+    // to the relevant element of `self.accounts` (leaving `self` unaffected)
+    pub(in crate::database) fn commit_applicable(&self, batch: &WitnessedBatch) -> bool {
+        let guard = self.accounts.lock(Database::touched(batch));
+        Database::applicable(&guard, &self.account_settings, batch)
+    }
+
+    // Logs and stores `batch`, locking the `Account`s it touches, or returns `None`
+    // (leaving `self` unaffected) if `batch` is inapplicable. `accounts` must be
+    // `self.accounts`. `batch` is applied to `accounts` by `CommitApplication::join`,
+    // which can (and should) be called after `self` is released: batches touching
+    // disjoint shards of `accounts` are then applied concurrently, while batches
+    // touching a common shard are applied in the order they were dispatched.
+    pub fn dispatch_commit_batch<'a>(
+        &mut self,
+        accounts: &'a Accounts,
+        batch: WitnessedBatch,
+        dependencies: Vec<Vec<Operation>>,
+    ) -> Result<Option<CommitApplication<'a>>, Top<StorageError>> {
+        self.settle();
+
+        let guard = accounts.lock(Database::touched(&batch));
+
+        if !Database::applicable(&guard, &self.account_settings, &batch) {
+            return Ok(None);
+        }
+
+        self.write(RecordRef::CommitBatch(&batch, dependencies.as_slice()))?;
+
+        Ok(Some(self.commit_batch_dispatched(
+            accounts,
+            guard,
+            batch,
+            dependencies,
+        )))
+    }
+
+    // `batch` must be applicable (see `commit_applicable`)
+    pub(in crate::database) fn commit_batch_unlogged(
+        &mut self,
+        batch: WitnessedBatch,
+        dependencies: Vec<Vec<Operation>>,
+    ) -> Vec<Id> {
+        let accounts = self.accounts.clone();
+        let guard = accounts.lock(Database::touched(&batch));
+
+        let exceptions = self
+            .commit_batch_dispatched(&accounts, guard, batch, dependencies)
+            .join();
+
+        self.settle();
+        exceptions
+    }
+
+    // Settles the effects of the batches applied by `CommitApplication::join` on the
+    // rest of `self`: account summaries, closures, and the prepare state of closed accounts
+    pub fn settle(&mut self) {
+        for Settlement {
+            summaries,
+            closures,
+        } in self.accounts.unsettled()
+        {
+            let mut transaction = TableTransaction::new();
+
+            for (id, summary) in summaries {
+                transaction.set(id, summary).unwrap();
+            }
+
+            self.imminent.execute(transaction);
+
+            // No further `Prepare` is accepted from a closed account: its prepare state is pruned
+
+            let closed = closures.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+            self.prepare
+                .states
+                .apply(Split::with_key(closed, |id| *id), |states, id| {
+                    states.remove(&id);
+                })
+                .join();
+
+            self.commit.closures.extend(closures);
+        }
+    }
+
+    // `Id`s of the `Account`s touched by `batch`: those of its payloads, along
+    // with the brokers credited by its `Fee`s
+    fn touched(batch: &WitnessedBatch) -> Vec<Id> {
+        let mut ids = Vec::with_capacity(batch.payloads().len());
+
+        for payload in batch.payloads() {
+            ids.push(payload.id());
+
+            if let Operation::Withdraw(withdraw) = payload.operation() {
+                ids.extend(withdraw.fee().map(|fee| fee.broker()));
+            }
+        }
+
+        ids
+    }
+
+    fn applicable(
+        guard: &AccountsGuard,
+        settings: &AccountSettings,
+        batch: &WitnessedBatch,
+    ) -> bool {
+        batch.payloads().iter().all(|payload| {
+            // If no operation was previously processed from `payload.id()`,
+            // check `payload.height()` against an empty `Account`
+            match guard.get(payload.id()) {
+                Some(account) => account.applicable(payload.height()),
+                None => Account::new(payload.id(), settings).applicable(payload.height()),
+            }
+        })
+    }
+
+    // Stores `batch` in `self`, to be applied to the `Account`s locked by `guard`
+    fn commit_batch_dispatched<'a>(
+        &mut self,
+        accounts: &'a Accounts,
+        guard: AccountsGuard<'a>,
+        batch: WitnessedBatch,
+        dependencies: Vec<Vec<Operation>>,
+    ) -> CommitApplication<'a> {
+        let root = batch.root();
+
+        // Store (a reference to) each element of `batch.payloads()` in `self.commit.payloads`
+
+        let handles = Split::with_key(
+            batch.payloads().iter().map(Payload::entry).enumerate(),
+            |(_, entry)| *entry,
+        );
+
+        buckets::apply_attached(
+            &mut self.commit.payloads,
+            &root,
+            handles,
+            |payloads, root, (index, entry)| {
+                payloads.insert(
+                    entry,
+                    PayloadHandle {
+                        batch: *root,
+                        index,
+                    },
+                );
+            },
+        )
+        .join();
+
+        let applications = batch
+            .payloads()
+            .iter()
+            .cloned()
+            .zip(dependencies)
+            .collect::<Vec<_>>();
+
+        // Store `batch` in `self.commit.batches`
+
+        self.commit.batches.insert(root, BatchHolder::new(batch));
+        self.commit.applied.insert(root);
+
+        CommitApplication {
+            accounts,
+            guard,
+            settings: self.account_settings.clone(),
+            applications,
+        }
+    }
+
+    pub(in crate::database) fn batch_completion_unlogged(&mut self, completion: BatchCompletion) {
+        if let Some(holder) = self.commit.batches.get_mut(&completion.root()) {
+            holder.attach(completion);
+        }
+    }
+}
+
+// Outcome of applying a `Payload`: the summary of its `Account`, its `Id` if it failed
+// to apply, its closing height if it closed its `Account`, and the `Fee` it owes
+type Outcome = ((Id, AccountSummary), Option<Id>, Option<u64>, Option<Fee>);
+
+// A commit batch stored by `Database::dispatch_commit_batch`, yet to be applied
+// to its `Account`s (whose shards remain locked until `join` returns)
+pub(crate) struct CommitApplication<'a> {
+    accounts: &'a Accounts,
+    guard: AccountsGuard<'a>,
+    settings: AccountSettings,
+    // Each payload of the batch, along with its dependencies
+    applications: Vec<(Payload, Vec<Operation>)>,
+}
+
+impl CommitApplication<'_> {
+    // Applies the batch (in parallel across shards), returning the `Id` of each payload
+    // that failed to apply. The effects of the batch on the rest of the `Database` are
+    // deferred until `Database::settle`.
+    pub fn join(self) -> Vec<Id> {
+        let CommitApplication {
+            accounts,
+            mut guard,
+            settings,
+            applications,
+        } = self;
+
+        let mut shards = HashMap::<usize, Vec<_>>::new();
+
+        for (payload, dependencies) in applications {
+            shards
+                .entry(Accounts::shard(payload.id()))
+                .or_default()
+                .push((payload, dependencies));
+        }
+
+        let shards = guard
+            .shards_mut()
+            .filter_map(|(index, shard)| {
+                shards
+                    .remove(&index)
+                    .map(|applications| (shard, applications))
+            })
+            .collect::<Vec<_>>();
+
+        let flush = shards
+            .into_par_iter()
+            .map(|(shard, applications)| {
+                applications
+                    .into_iter()
+                    .map(|(payload, dependencies)| {
+                        CommitApplication::apply(shard, &settings, payload, dependencies)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut summaries = HashMap::new();
+        let mut closures = Vec::new();
+        let mut fees = Vec::new();
+
+        let exceptions = flush
+            .into_iter()
+            .flatten()
+            .filter_map(|((id, summary), exception, closure, fee)| {
+                summaries.insert(id, summary);
+
+                if let Some(height) = closure {
+                    closures.push((id, height));
+                }
+
+                fees.extend(fee);
+
+                exception
+            })
+            .collect::<Vec<_>>();
+
+        // Credit each `Fee` to its broker (whose shard is locked by `guard`). No reader
+        // can observe a `Withdraw` applied without its `Fee` being credited (or vice versa)
+
+        for fee in fees {
+            let account = guard.entry(fee.broker(), &settings);
+            account.collect_fee(fee.amount());
+
+            // A broker's summary supersedes the one produced by its own `Payload`, if any
+            summaries.insert(fee.broker(), account.summarize());
+        }
+
+        // Settlements are deferred while `guard` is held, hence in the
+        // order in which batches touching a common shard are applied
+        accounts.defer(Settlement {
+            summaries,
+            closures,
+        });
+
+        exceptions
+    }
+
+    fn apply(
+        shard: &mut HashMap<Id, Account>,
+        settings: &AccountSettings,
+        payload: Payload,
+        dependencies: Vec<Operation>,
+    ) -> Outcome {
+        // Fetch `payload.id()`'s `Account` (if no operation was previously
+        // processed from `payload.id()`, initialize an empty `Account`)
+        let account = shard
+            .entry(payload.id())
+            .or_insert_with(|| Account::new(payload.id(), settings));
+
+        // `payload` is newly applied only if it exceeds `account`'s height
+        // (otherwise, `account.apply` leaves `account` unaffected)
+        let fresh = payload.height() > account.height();
+
+        let exception = if account.apply(&payload, dependencies.as_slice(), settings) {
+            None
+        } else {
+            Some(payload.id())
+        };
+
+        // If `payload` is a newly applied `Withdraw`, its `Fee` is owed to its broker
+        let fee = match (payload.operation(), &exception) {
+            (Operation::Withdraw(withdraw), None) if fresh => withdraw.fee(),
+            _ => None,
+        };
+
+        let id = payload.id();
+        let summary = account.summarize();
+
+        // If `payload` successfully closed `account`, record the closure
+        let closure = match (payload.operation(), &exception) {
+            (Operation::Close(_), None) if account.is_closed() => Some(payload.height()),
+            _ => None,
+        };
+
+        ((id, summary), exception, closure, fee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::{AccountSettings, Entry},
+        crypto::{Certificate, Identify},
+        database::prepare::{PrepareHandle, State},
+        prepare::ReductionStatement,
+        view::test::InstallGenerator,
+    };
+
+    use std::{
+        sync::{mpsc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use talk::crypto::primitives::hash;
+
+    use zebra::vector::Vector;
+
+    // Validity is irrelevant to `Database`: `batch` is witnessed by a single signer
+    fn batch(generator: &InstallGenerator, entry: Entry, operation: Operation) -> WitnessedBatch {
+        let view = generator.view(4);
+        let payloads = Vector::new(vec![Payload::new(entry, operation)]).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(payloads.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        WitnessedBatch::new(view.identifier(), payloads, witness)
+    }
+
+    #[test]
+    fn closure() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        let state = State::Consistent {
+            height: 1,
+            commitment: hash::hash(&0u64).unwrap(),
+            handle: PrepareHandle::Batched {
+                batch: hash::hash(&1u64).unwrap(),
+                index: 0,
+            },
+        };
+
+        database
+            .prepare
+            .states
+            .apply(Split::with_key(vec![7], |id| *id), |states, id| {
+                states.insert(id, state.clone());
+            })
+            .join();
+
+        // Account 7 starts with an empty balance, and can be closed
+        let exceptions = database.commit_batch_unlogged(
+            batch(&generator, Entry { id: 7, height: 1 }, Operation::close()),
+            vec![vec![]],
+        );
+
+        assert!(exceptions.is_empty());
+        assert_eq!(database.commit.closures.get(&7), Some(&1));
+
+        assert!(database
+            .commit
+            .credits_closed(&Operation::withdraw(7, 0, 0)));
+
+        assert!(!database
+            .commit
+            .credits_closed(&Operation::withdraw(8, 0, 0)));
+
+        let pruned = database
+            .prepare
+            .states
+            .apply(Split::with_key(vec![7], |id| *id), |states, id| {
+                !states.contains_key(&id)
+            })
+            .join();
+
+        assert_eq!(pruned, vec![true]);
+    }
+
+    #[test]
+    fn fees() {
+        let generator = InstallGenerator::new(4);
+
+        let mut database = Database::with_account_settings(AccountSettings {
+            initial_balance: 10,
+            minimum_fee: 1,
+            ..Default::default()
+        });
+
+        // Account 1 pays 5 to account 2, and a fee of 2 to broker 3
+        let withdraw = batch(
+            &generator,
+            Entry { id: 1, height: 1 },
+            Operation::withdraw_with_fee(2, 0, 5, 3, 2),
+        );
+
+        let exceptions = database.commit_batch_unlogged(withdraw.clone(), vec![vec![]]);
+        assert!(exceptions.is_empty());
+
+        // The fee is credited along with the withdrawal (the transfer is yet to be deposited)
+        assert_eq!(
+            database.balances(vec![1, 2, 3]),
+            vec![Some(3), Some(10), Some(12)]
+        );
+
+        // Applying `withdraw` again credits no further fee
+        database.commit_batch_unlogged(withdraw, vec![vec![]]);
+        assert_eq!(database.balances(vec![3]), vec![Some(12)]);
+
+        // A `Withdraw` paying less than `minimum_fee` corrupts its account, and credits no fee
+        let exceptions = database.commit_batch_unlogged(
+            batch(
+                &generator,
+                Entry { id: 1, height: 2 },
+                Operation::withdraw(2, 0, 1),
+            ),
+            vec![vec![]],
+        );
+
+        assert_eq!(exceptions, vec![1]);
+        assert_eq!(database.balances(vec![1, 3]), vec![None, Some(12)]);
+    }
+
+    #[test]
+    fn concurrent_batches() {
+        let generator = InstallGenerator::new(4);
+
+        let database = Mutex::new(Database::new());
+        let accounts = database.lock().unwrap().accounts.clone();
+
+        let dispatch = |id| {
+            database
+                .lock()
+                .unwrap()
+                .dispatch_commit_batch(
+                    &accounts,
+                    batch(&generator, Entry { id, height: 1 }, Operation::close()),
+                    vec![vec![]],
+                )
+                .unwrap()
+                .unwrap()
+        };
+
+        // `Id`s 1 and 2 belong to distinct shards, `Id`s 1 and 65 to the same shard
+
+        assert_ne!(Accounts::shard(1), Accounts::shard(2));
+        assert_eq!(Accounts::shard(1), Accounts::shard(65));
+
+        let first = dispatch(1);
+
+        // While `first` is yet to be applied, a batch over disjoint accounts
+        // is dispatched and applied to completion
+
+        let second = dispatch(2);
+        assert!(second.join().is_empty());
+
+        database.lock().unwrap().settle();
+        assert_eq!(database.lock().unwrap().commit.closures.get(&2), Some(&1));
+        assert_eq!(database.lock().unwrap().commit.closures.get(&1), None);
+
+        // A batch touching `first`'s shard is applied only after `first`
+
+        thread::scope(|scope| {
+            let (inlet, outlet) = mpsc::channel();
+            let dispatch = &dispatch;
+
+            scope.spawn(move || {
+                let third = dispatch(65);
+                inlet.send(()).unwrap();
+                assert!(third.join().is_empty());
+            });
+
+            thread::sleep(Duration::from_millis(100));
+            assert!(outlet.try_recv().is_err());
+
+            assert!(first.join().is_empty());
+            outlet.recv().unwrap();
+        });
+
+        let mut database = database.into_inner().unwrap();
+        database.settle();
+
+        for id in [1, 2, 65] {
+            assert_eq!(database.commit.closures.get(&id), Some(&1));
+        }
+
+        assert_eq!(database.commit.batches.len(), 3);
+    }
+}
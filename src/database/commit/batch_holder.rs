@@ -1,8 +1,11 @@
 use crate::commit::{BatchCompletion, WitnessedBatch};
 
+use std::time::Instant;
+
 pub(crate) struct BatchHolder {
     batch: WitnessedBatch,
     completion: Option<BatchCompletion>,
+    applied: Instant,
 }
 
 impl BatchHolder {
@@ -10,6 +13,17 @@ impl BatchHolder {
         BatchHolder {
             batch,
             completion: None,
+            applied: Instant::now(),
+        }
+    }
+
+    // Rebuilds a `BatchHolder` from its parts (see `Database::restore`): as
+    // the original application time is lost, `batch` counts as applied now
+    pub fn restore(batch: WitnessedBatch, completion: Option<BatchCompletion>) -> Self {
+        BatchHolder {
+            batch,
+            completion,
+            applied: Instant::now(),
         }
     }
 
@@ -21,6 +35,10 @@ impl BatchHolder {
         self.completion.as_ref()
     }
 
+    pub fn applied(&self) -> Instant {
+        self.applied
+    }
+
     pub fn attach(&mut self, completion: BatchCompletion) {
         self.completion = Some(completion);
     }
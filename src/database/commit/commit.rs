@@ -1,24 +1,46 @@
 use buckets::Buckets;
 
 use crate::{
-    account::Entry,
+    account::{Entry, Id, Operation},
     database::commit::{BatchHolder, PayloadHandle},
 };
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use talk::crypto::primitives::hash::Hash;
 
 pub(crate) struct Commit {
     pub batches: HashMap<Hash, BatchHolder>,
+    // Roots of the applied batches, pruned along with their `BatchHolder`
+    // (see `Database::prune_commit_batches`)
+    pub applied: HashSet<Hash>,
     pub payloads: Buckets<HashMap<Entry, PayloadHandle>>,
+    // Closing height of each closed account (the prepare state
+    // of a closed `Id` is pruned once its closure is recorded)
+    pub closures: HashMap<Id, u64>,
 }
 
 impl Commit {
     pub fn new() -> Self {
         Commit {
             batches: HashMap::new(),
+            applied: HashSet::new(),
             payloads: Buckets::new(),
+            closures: HashMap::new(),
+        }
+    }
+
+    // Determines whether `operation` credits an account known to be closed (which
+    // never deposits again: the credited amount would be lost)
+    pub fn credits_closed(&self, operation: &Operation) -> bool {
+        match operation {
+            Operation::Withdraw(withdraw) => {
+                self.closures.contains_key(&withdraw.beneficiary())
+                    || withdraw
+                        .fee()
+                        .map_or(false, |fee| self.closures.contains_key(&fee.broker()))
+            }
+            _ => false,
         }
     }
 }
@@ -0,0 +1,371 @@
+use buckets::Split;
+
+use crate::{
+    account::{Entry, Id},
+    commit::{CompletionProof, ConsistencyToken, Payload},
+    database::commit::Commit,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::ops::Range;
+
+// A `HistoryQuery` selects the committed `Entry`s of `id` whose height is in
+// `heights`, returning at most `limit` of them (in increasing order of height).
+// Larger ranges are walked by following `HistoryPage::next`. If `token` is
+// set, the query is answered only if it reflects the write identified by `token`.
+// Entries of pruned batches (see `Database::prune_commit_batches`) are skipped: a
+// page falling entirely before the retained history of `id` is empty (and last).
+#[derive(Debug, Clone)]
+pub(crate) struct HistoryQuery {
+    pub id: Id,
+    pub heights: Range<u64>,
+    pub limit: usize,
+    pub proofs: bool,
+    pub token: Option<ConsistencyToken>,
+}
+
+pub(crate) struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub next: Option<HistoryQuery>,
+}
+
+pub(crate) struct HistoryEntry {
+    pub payload: Payload,
+    pub proof: Option<CompletionProof>,
+}
+
+#[derive(Doom)]
+pub(crate) enum HistoryError {
+    #[doom(description("The batch of the query's `ConsistencyToken` was not yet applied"))]
+    Lagging,
+    #[doom(description("A payload handle references a batch missing from the `Database`"))]
+    BatchMissing,
+    #[doom(description("Database void"))]
+    DatabaseVoid,
+}
+
+impl HistoryQuery {
+    pub fn new(id: Id, heights: Range<u64>, limit: usize) -> Self {
+        HistoryQuery {
+            id,
+            heights,
+            limit,
+            proofs: false,
+            token: None,
+        }
+    }
+
+    pub fn with_proofs(self) -> Self {
+        HistoryQuery {
+            proofs: true,
+            ..self
+        }
+    }
+
+    pub fn after(self, token: ConsistencyToken) -> Self {
+        HistoryQuery {
+            token: Some(token),
+            ..self
+        }
+    }
+}
+
+impl Commit {
+    // Batches are applied before they can be completed: a replica that did not
+    // yet apply the batch of `token` is lagging (as is, conservatively, a replica
+    // that pruned it from `self.batches`)
+    pub fn reflects(&self, token: &ConsistencyToken) -> bool {
+        self.applied.contains(&token.root())
+    }
+
+    // If `self` does not reflect `query.token`, the query is rejected (without
+    // blocking, as `self` is accessed under `Database`'s lock): the caller can
+    // either retry once more batches are applied, or redirect the query
+    pub fn history(&mut self, query: &HistoryQuery) -> Result<HistoryPage, Top<HistoryError>> {
+        if let Some(token) = &query.token {
+            if !self.reflects(token) {
+                return HistoryError::Lagging.fail().spot(here!());
+            }
+        }
+
+        let id = query.id;
+
+        let heights = query.heights.clone().take(query.limit).collect::<Vec<_>>();
+
+        let entries = heights
+            .iter()
+            .map(|height| Entry {
+                id,
+                height: *height,
+            })
+            .collect::<Split<_>>();
+
+        let batches = &self.batches;
+
+        // Map each element of `entries` onto its `HistoryEntry`, if `entry` was
+        // committed (and not pruned). If `query.proofs` is set, attach to each
+        // `HistoryEntry` a `CompletionProof` (available only once the relevant
+        // batch is completed)
+        let entries = buckets::apply_attached(
+            &mut self.payloads,
+            batches,
+            entries,
+            |payloads, batches, entry| {
+                let handle = match payloads.get(&entry) {
+                    Some(handle) => handle,
+                    None => return Ok(None),
+                };
+
+                // Payload handles are pruned along with their batch
+                // (see `Database::prune_commit_batches`)
+                let holder = batches
+                    .get(&handle.batch)
+                    .ok_or_else(|| HistoryError::BatchMissing.into_top())
+                    .spot(here!())?;
+
+                let payload = holder.batch().payloads()[handle.index].clone();
+
+                let proof = if query.proofs {
+                    holder
+                        .completion()
+                        .filter(|completion| !completion.excepts(entry.id))
+                        .map(|completion| {
+                            CompletionProof::new(
+                                completion.clone(),
+                                holder.batch().inclusion(handle.index),
+                            )
+                        })
+                } else {
+                    None
+                };
+
+                Ok(Some(HistoryEntry { payload, proof }))
+            },
+        )
+        .join()
+        .into_iter()
+        .collect::<Result<Vec<_>, Top<HistoryError>>>()?;
+
+        // The retained history of `id` is contiguous, and starts at height 1 (or past
+        // its pruned entries): skip the gap preceding it, then truncate to the gap
+        // following it (every later height is yet to be committed)
+        let exhausted = entries.last().map_or(true, Option::is_none);
+
+        let entries = entries
+            .into_iter()
+            .skip_while(Option::is_none)
+            .take_while(Option::is_some)
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // A further page is available only if the current page is full (up to
+        // its last height) and `query.heights` is not exhausted
+        let next = if !exhausted && heights.len() == query.limit {
+            let start = query.heights.start + query.limit as u64;
+
+            if start < query.heights.end {
+                Some(HistoryQuery {
+                    heights: start..query.heights.end,
+                    ..query.clone()
+                })
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Ok(HistoryPage { entries, next })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Operation,
+        commit::WitnessedBatch,
+        crypto::{Certificate, Identify},
+        database::Database,
+        prepare::ReductionStatement,
+        view::test::InstallGenerator,
+    };
+
+    use std::time::Duration;
+
+    use zebra::vector::Vector;
+
+    // Validity is irrelevant to `Database`: `batch` is witnessed by a single signer
+    fn batch(generator: &InstallGenerator, entries: &[(Id, u64)]) -> WitnessedBatch {
+        let view = generator.view(4);
+
+        let payloads = entries
+            .iter()
+            .map(|(id, height)| {
+                Payload::new(
+                    Entry {
+                        id: *id,
+                        height: *height,
+                    },
+                    Operation::withdraw(0, 0, 0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let payloads = Vector::new(payloads).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(payloads.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        WitnessedBatch::new(view.identifier(), payloads, witness)
+    }
+
+    #[test]
+    fn pages() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        for height in 1..=3 {
+            database.commit_batch_unlogged(batch(&generator, &[(7, height)]), vec![vec![]]);
+        }
+
+        let page = database
+            .commit
+            .history(&HistoryQuery::new(7, 1..10, 2))
+            .unwrap();
+
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[1].payload.entry(), Entry { id: 7, height: 2 });
+
+        // The next page picks up at height 3, and is the last
+        let page = database.commit.history(&page.next.unwrap()).unwrap();
+
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].payload.entry(), Entry { id: 7, height: 3 });
+        assert!(page.next.is_none());
+    }
+
+    #[test]
+    fn consistency() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        let applied = batch(&generator, &[(7, 1)]);
+        let pending = batch(&generator, &[(7, 2)]);
+
+        let applied_token = ConsistencyToken::new(applied.root(), 1);
+        let pending_token = ConsistencyToken::new(pending.root(), 2);
+
+        database.commit_batch_unlogged(applied, vec![vec![]]);
+
+        let query = HistoryQuery::new(7, 1..10, 10);
+
+        assert_eq!(
+            database
+                .commit
+                .history(&query.clone().after(applied_token))
+                .unwrap()
+                .entries
+                .len(),
+            1
+        );
+
+        // A read-your-writes query is rejected until the batch of its token is applied
+        assert!(database
+            .commit
+            .history(&query.clone().after(applied_token.latest(pending_token)))
+            .is_err());
+
+        database.commit_batch_unlogged(pending, vec![vec![]]);
+
+        assert_eq!(
+            database
+                .commit
+                .history(&query.after(pending_token))
+                .unwrap()
+                .entries
+                .len(),
+            2
+        );
+
+        // Tokens of pruned batches are no longer reflected
+        database.prune_commit_batches(Duration::from_secs(0));
+
+        assert!(!database.commit.reflects(&applied_token));
+        assert!(database
+            .commit
+            .history(&HistoryQuery::new(7, 1..10, 10).after(applied_token))
+            .is_err());
+    }
+
+    #[test]
+    fn from_zero() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        for height in 1..=3 {
+            database.commit_batch_unlogged(batch(&generator, &[(7, height)]), vec![vec![]]);
+        }
+
+        // No `Entry` is ever committed at height 0
+        let page = database
+            .commit
+            .history(&HistoryQuery::new(7, 0..10, 3))
+            .unwrap();
+
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[0].payload.entry(), Entry { id: 7, height: 1 });
+
+        let page = database.commit.history(&page.next.unwrap()).unwrap();
+
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].payload.entry(), Entry { id: 7, height: 3 });
+        assert!(page.next.is_none());
+    }
+
+    #[test]
+    fn pruned() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        for height in 1..=2 {
+            database.commit_batch_unlogged(batch(&generator, &[(7, height)]), vec![vec![]]);
+        }
+
+        database.prune_commit_batches(Duration::from_secs(0));
+
+        for height in 3..=5 {
+            database.commit_batch_unlogged(batch(&generator, &[(7, height)]), vec![vec![]]);
+        }
+
+        // The retained history of 7 starts at height 3
+        let page = database
+            .commit
+            .history(&HistoryQuery::new(7, 1..10, 3))
+            .unwrap();
+
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].payload.entry(), Entry { id: 7, height: 3 });
+
+        let page = database.commit.history(&page.next.unwrap()).unwrap();
+
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[1].payload.entry(), Entry { id: 7, height: 5 });
+        assert!(page.next.is_none());
+
+        // A page falling entirely before the retained history is empty
+        let page = database
+            .commit
+            .history(&HistoryQuery::new(7, 1..10, 2))
+            .unwrap();
+
+        assert!(page.entries.is_empty());
+        assert!(page.next.is_none());
+    }
+}
@@ -1,7 +1,13 @@
+mod apply;
 mod batch_holder;
 mod commit;
+mod history;
 mod payload_handle;
+mod prune;
 
+pub(crate) use apply::CommitApplication;
 pub(crate) use batch_holder::BatchHolder;
 pub(crate) use commit::Commit;
+#[allow(unused_imports)]
+pub(crate) use history::{HistoryEntry, HistoryError, HistoryPage, HistoryQuery};
 pub(crate) use payload_handle::PayloadHandle;
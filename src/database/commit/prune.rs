@@ -0,0 +1,153 @@
+use buckets::Split;
+
+use crate::{commit::Payload, database::Database};
+
+use std::time::{Duration, Instant};
+
+impl Database {
+    // Drops every commit batch applied at least `retention` ago, along with its root in
+    // `applied` and the handles of its payloads, returning the number of batches dropped.
+    // The history of an account (see `Commit::history`) then starts at its first entry
+    // still retained, and the `ConsistencyToken`s of dropped batches are no longer
+    // reflected (clients holding them must query without a token). `Operation`s
+    // committed by dropped batches are fetched from brokers, should a later batch depend
+    // on them (see `commit::steps::fetch_dependencies`).
+    pub fn prune_commit_batches(&mut self, retention: Duration) -> usize {
+        let now = Instant::now();
+
+        let expired = self
+            .commit
+            .batches
+            .iter()
+            .filter(|(_, holder)| now.duration_since(holder.applied()) >= retention)
+            .map(|(root, _)| *root)
+            .collect::<Vec<_>>();
+
+        for root in expired.iter() {
+            // `expired` was collected from `self.commit.batches`,
+            // so the following `unwrap` is guaranteed to succeed
+            let holder = self.commit.batches.remove(root).unwrap();
+            self.commit.applied.remove(root);
+
+            let entries = holder
+                .batch()
+                .payloads()
+                .iter()
+                .map(Payload::entry)
+                .collect::<Vec<_>>();
+
+            buckets::apply_attached(
+                &mut self.commit.payloads,
+                root,
+                Split::with_key(entries, |entry| *entry),
+                |payloads, root, entry| {
+                    // A later batch might have re-applied `entry`, replacing its handle
+                    if payloads
+                        .get(&entry)
+                        .map_or(false, |handle| handle.batch == *root)
+                    {
+                        payloads.remove(&entry);
+                    }
+                },
+            )
+            .join();
+        }
+
+        expired.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::{Account, Entry, Id, Operation},
+        commit::WitnessedBatch,
+        crypto::{Certificate, Identify},
+        prepare::ReductionStatement,
+        view::test::InstallGenerator,
+    };
+
+    use zebra::vector::Vector;
+
+    // Validity is irrelevant to `Database`: `batch` is witnessed by a single signer
+    fn batch(generator: &InstallGenerator, entries: &[(Id, u64)]) -> WitnessedBatch {
+        let view = generator.view(4);
+
+        let payloads = entries
+            .iter()
+            .map(|(id, height)| {
+                Payload::new(
+                    Entry {
+                        id: *id,
+                        height: *height,
+                    },
+                    Operation::withdraw(0, 0, 0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let payloads = Vector::new(payloads).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(payloads.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        WitnessedBatch::new(view.identifier(), payloads, witness)
+    }
+
+    #[test]
+    fn prune() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        let first = batch(&generator, &[(1, 1), (2, 1)]);
+        let second = batch(&generator, &[(1, 2)]);
+
+        database.commit_batch_unlogged(first.clone(), vec![vec![]; 2]);
+
+        // Batches are retained for `retention`
+
+        assert_eq!(database.prune_commit_batches(Duration::from_secs(3600)), 0);
+        assert_eq!(database.commit.batches.len(), 1);
+
+        assert_eq!(database.prune_commit_batches(Duration::from_secs(0)), 1);
+
+        assert!(database.commit.batches.is_empty());
+        assert!(database.commit.applied.is_empty());
+
+        // Payload handles are dropped along with their batch
+
+        let handles = database
+            .commit
+            .payloads
+            .apply(
+                Split::with_key(
+                    vec![Entry { id: 1, height: 1 }, Entry { id: 2, height: 1 }],
+                    |entry| *entry,
+                ),
+                |payloads, entry| payloads.contains_key(&entry),
+            )
+            .join();
+
+        assert_eq!(handles, vec![false, false]);
+
+        // Pruning leaves accounts unaffected
+
+        let accounts = database.accounts.lock(vec![1, 2]);
+
+        assert_eq!(accounts.get(1).map(Account::height), Some(1));
+        assert_eq!(accounts.get(2).map(Account::height), Some(1));
+
+        drop(accounts);
+
+        database.commit_batch_unlogged(second.clone(), vec![vec![]]);
+
+        assert!(database.commit.applied.contains(&second.root()));
+        assert!(!database.commit.applied.contains(&first.root()));
+    }
+}
@@ -1,18 +1,33 @@
-use buckets::Buckets;
+use buckets::{Buckets, Split};
 
 use crate::{
-    account::{Account, AccountSummary, Id},
-    database::{Commit, Prepare, Signup, Zebras},
+    account::{AccountSettings, AccountSummary, Id},
+    database::{
+        Accounts, Commit, FileStorage, Prepare, Record, RecordRef, Signup, Storage, StorageError,
+        WriteOutcome, Zebras,
+    },
     signup::IdAssignment,
 };
 
-use std::collections::HashMap;
+use doomstack::{here, ResultExt, Top};
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    mem,
+    path::Path,
+    sync::Arc,
+};
+
+use tokio::sync::broadcast::Sender;
 
 use zebra::database::Table;
 
 pub(crate) struct Database {
     pub assignments: Buckets<HashMap<Id, IdAssignment>>,
-    pub accounts: Buckets<HashMap<Id, Account>>,
+    // The `Id` of each element of `assignments`, in order (see `snapshot`)
+    pub assigned: BTreeSet<Id>,
+    // Locked independently of `self` (see `dispatch_commit_batch`)
+    pub accounts: Arc<Accounts>,
     pub imminent: Table<Id, AccountSummary>,
 
     pub signup: Signup,
@@ -20,15 +35,27 @@ pub(crate) struct Database {
     pub commit: Commit,
 
     pub families: Zebras,
+
+    // Rules by which operations are applied to `accounts`
+    pub(in crate::database) account_settings: AccountSettings,
+
+    storage: Option<Box<dyn Storage>>,
+    // Streams written records to subscribers (see `subscribe`)
+    pub(in crate::database) feed: Option<Sender<Arc<Vec<u8>>>>,
 }
 
 impl Database {
     pub fn new() -> Self {
+        Database::with_account_settings(Default::default())
+    }
+
+    pub fn with_account_settings(account_settings: AccountSettings) -> Self {
         let zebras = Zebras::new();
 
         Database {
             assignments: Buckets::new(),
-            accounts: Buckets::new(),
+            assigned: BTreeSet::new(),
+            accounts: Arc::new(Accounts::new()),
             imminent: zebras.ids_to_account_summaries.empty_table(),
 
             signup: Signup::new(&zebras),
@@ -36,6 +63,174 @@ impl Database {
             commit: Commit::new(),
 
             families: zebras,
+
+            account_settings,
+
+            storage: None,
+            feed: None,
         }
     }
+
+    // Opens a `Database` persisted (in a `FileStorage`) in `directory`,
+    // recovering the state left behind by a previous run
+    pub fn open<P>(
+        directory: P,
+        account_settings: AccountSettings,
+    ) -> Result<Self, Top<StorageError>>
+    where
+        P: AsRef<Path>,
+    {
+        Database::with_storage(Box::new(FileStorage::open(directory)?), account_settings)
+    }
+
+    // Replays all records in `storage`, then persists further updates to `storage`
+    // (`account_settings` must match those of the run that produced `storage`)
+    pub fn with_storage(
+        mut storage: Box<dyn Storage>,
+        account_settings: AccountSettings,
+    ) -> Result<Self, Top<StorageError>> {
+        let mut database = Database::with_account_settings(account_settings);
+
+        for record in storage.records()? {
+            let record = bincode::deserialize::<Record>(record.as_slice())
+                .pot(StorageError::MalformedRecord, here!())?;
+
+            database.apply_record(record, &mut WriteOutcome::default());
+        }
+
+        database.storage = Some(storage);
+
+        Ok(database)
+    }
+
+    // Stores `assignments` in `self.assignments`
+    pub fn insert_assignments(
+        &mut self,
+        assignments: Vec<IdAssignment>,
+    ) -> Result<(), Top<StorageError>> {
+        self.write(RecordRef::Assignments(assignments.as_slice()))?;
+        self.assignments_unlogged(assignments);
+
+        Ok(())
+    }
+
+    // Estimated footprint (in bytes) of the batches held by `self`, which dominate
+    // its memory usage (heap-allocated fields of batch elements are not accounted for)
+    pub fn footprint(&self) -> usize {
+        let prepares = self
+            .prepare
+            .batches
+            .values()
+            .map(|holder| mem::size_of_val(holder.batch().prepares()))
+            .sum::<usize>();
+
+        let payloads = self
+            .commit
+            .batches
+            .values()
+            .map(|holder| mem::size_of_val(holder.batch().payloads()))
+            .sum::<usize>();
+
+        prepares + payloads
+    }
+
+    // Persists `record` (if `self` is persistent) and streams it to the subscribers
+    // of `self` (if any): `record`'s update must be applied to `self` before `self`
+    // is unlocked
+    pub(in crate::database) fn write(
+        &mut self,
+        record: RecordRef,
+    ) -> Result<(), Top<StorageError>> {
+        let subscribed = self.subscribed();
+
+        if self.storage.is_none() && !subscribed {
+            return Ok(());
+        }
+
+        let record = bincode::serialize(&record).pot(StorageError::WriteFailed, here!())?;
+
+        if let Some(storage) = self.storage.as_mut() {
+            storage.append(record.as_slice())?;
+        }
+
+        if subscribed {
+            self.publish(record);
+        }
+
+        Ok(())
+    }
+
+    // Size (in bytes) of the records persisted so far (`None` if `self` is not persistent)
+    pub fn log_size(&self) -> Option<u64> {
+        self.storage.as_ref().map(|storage| storage.size())
+    }
+
+    // Replaces all records persisted so far with a single `Image` of `self` (if `self`
+    // is persistent). Remark: this copies the whole state of `self` (which remains
+    // locked in the meantime).
+    pub fn compact(&mut self) -> Result<(), Top<StorageError>> {
+        if self.storage.is_none() {
+            return Ok(());
+        }
+
+        let image = self.image();
+
+        let record = bincode::serialize(&RecordRef::Image(&image))
+            .pot(StorageError::CompactionFailed, here!())?;
+
+        // `self.storage` was checked to be `Some` above
+        self.storage.as_mut().unwrap().compact(vec![record])
+    }
+
+    // Applies `record`'s update to `self`, collecting its effects in `outcome`
+    pub(in crate::database) fn apply_record(&mut self, record: Record, outcome: &mut WriteOutcome) {
+        match record {
+            Record::Assignments(assignments) => self.assignments_unlogged(assignments),
+            Record::PrepareBatch(batch) => {
+                let equivocations = self.prepare_batch_unlogged(batch);
+                outcome.equivocations.extend(equivocations);
+            }
+            Record::BatchCommit(commit) => self.batch_commit_unlogged(commit),
+            Record::Equivocations(equivocations) => self.equivocations_unlogged(equivocations),
+            Record::CommitBatch(batch, dependencies) => {
+                // Only applicable batches are recorded
+                let exceptions = self.commit_batch_unlogged(batch, dependencies);
+                outcome.exceptions.extend(exceptions);
+            }
+            Record::BatchCompletion(completion) => self.batch_completion_unlogged(completion),
+            Record::Batch(records) => {
+                for record in records {
+                    self.apply_record(record, outcome);
+                }
+            }
+            Record::Image(image) => self.restore(image),
+        }
+    }
+
+    pub(in crate::database) fn assignments_unlogged(&mut self, assignments: Vec<IdAssignment>) {
+        self.assigned
+            .extend(assignments.iter().map(IdAssignment::id));
+
+        let assignments = assignments.into_iter().collect::<Split<_>>();
+
+        self.assignments
+            .apply(assignments, |assignments, assignment| {
+                assignments.insert(assignment.id(), assignment);
+            })
+            .join();
+    }
+
+    // Returns the balance of each element of `ids`, as of the last applied batch
+    // (`None` if the corresponding account is corrupted). An `Id` from which no
+    // operation was ever applied holds its initial balance.
+    pub fn balances(&mut self, ids: Vec<Id>) -> Vec<Option<u64>> {
+        let guard = self.accounts.lock(ids.iter().copied());
+
+        ids.into_iter()
+            .map(|id| match guard.get(id) {
+                Some(account) => account.balance(),
+                None => Some(self.account_settings.initial_balance),
+            })
+            .collect()
+    }
 }
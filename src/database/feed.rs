@@ -0,0 +1,137 @@
+use crate::database::{Database, Record, RecordRef, StorageError, WriteOutcome};
+
+use doomstack::{here, ResultExt, Top};
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast::{self, Receiver};
+
+// The records written by a `Database` (see `Record`) can be streamed to subscribers,
+// each mirroring the `Database` on its own (see `Delegate`). A subscriber starts from
+// an `Image` of the `Database`, taken under the same lock as its subscription: applying
+// the `Image`, then every record received, in order, reproduces the `Database`'s state.
+// Records are streamed as written to `Storage`: the `Database` and its subscribers must
+// run the same version. Remark: pruning (see `prune_prepare_batches` and
+// `prune_commit_batches`) is not recorded, hence not streamed.
+impl Database {
+    // Subscribes to the records written by `self` from now on. Returns the serialized
+    // `Image` record of `self` to be replayed first (see `replay`), along with the
+    // `Receiver` of all further records. A subscriber that falls more than `capacity`
+    // records behind misses records (as reported by its `Receiver`), and must subscribe
+    // anew. `capacity` is set by the first of concurrent subscribers. Remark: like
+    // `compact`, this copies the whole state of `self`.
+    pub fn subscribe(
+        &mut self,
+        capacity: usize,
+    ) -> Result<(Vec<u8>, Receiver<Arc<Vec<u8>>>), Top<StorageError>> {
+        let image = self.image();
+
+        let image = bincode::serialize(&RecordRef::Image(&image))
+            .pot(StorageError::WriteFailed, here!())?;
+
+        let receiver = match self.feed.as_ref() {
+            Some(feed) if feed.receiver_count() > 0 => feed.subscribe(),
+            _ => {
+                let (feed, receiver) = broadcast::channel(capacity.max(1));
+                self.feed = Some(feed);
+                receiver
+            }
+        };
+
+        Ok((image, receiver))
+    }
+
+    // Applies a record streamed by another `Database` (see `subscribe`), without
+    // persisting it. The first record replayed (on an empty `self`) must be the `Image`
+    // returned by `subscribe`, and all others must follow in the order received.
+    pub fn replay(&mut self, record: &[u8]) -> Result<(), Top<StorageError>> {
+        let record =
+            bincode::deserialize::<Record>(record).pot(StorageError::MalformedRecord, here!())?;
+
+        self.apply_record(record, &mut WriteOutcome::default());
+
+        Ok(())
+    }
+
+    pub(in crate::database) fn subscribed(&self) -> bool {
+        self.feed
+            .as_ref()
+            .map(|feed| feed.receiver_count() > 0)
+            .unwrap_or(false)
+    }
+
+    pub(in crate::database) fn publish(&self, record: Vec<u8>) {
+        if let Some(feed) = self.feed.as_ref() {
+            // Fails only if every subscriber dropped its `Receiver` in the meantime
+            let _ = feed.send(Arc::new(record));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        database::{
+            image::tests::{commit_batch, prepare_batch},
+            WriteBatch,
+        },
+        view::test::InstallGenerator,
+    };
+
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    #[test]
+    fn mirror() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        let mut updates = WriteBatch::new();
+        updates.apply_prepare_batch(prepare_batch(&generator, &[1, 2]));
+        updates.apply_commit_batch(commit_batch(&generator, &[(1, 1)]), vec![vec![]]);
+
+        database.apply(updates).unwrap().unwrap();
+
+        let (image, mut receiver) = database.subscribe(16).unwrap();
+
+        // Updates that follow the subscription are streamed
+
+        let committed = commit_batch(&generator, &[(1, 2), (2, 1)]);
+
+        let mut updates = WriteBatch::new();
+        updates.apply_commit_batch(committed.clone(), vec![vec![]; 2]);
+
+        database.apply(updates).unwrap().unwrap();
+
+        let mut mirror = Database::new();
+        mirror.replay(image.as_slice()).unwrap();
+
+        loop {
+            match receiver.try_recv() {
+                Ok(record) => mirror.replay(record.as_slice()).unwrap(),
+                Err(TryRecvError::Empty) => break,
+                Err(error) => panic!("unexpected error: {:?}", error),
+            }
+        }
+
+        assert!(mirror.commit.applied.contains(&committed.root()));
+        assert_eq!(mirror.balances(vec![1, 2]), database.balances(vec![1, 2]));
+        assert_eq!(mirror.snapshot(2).root(), database.snapshot(2).root());
+
+        // A subscriber that falls behind misses records
+
+        drop(receiver);
+
+        let (_, mut lagging) = database.subscribe(1).unwrap();
+
+        for height in 3..5 {
+            let mut updates = WriteBatch::new();
+            updates.apply_commit_batch(commit_batch(&generator, &[(1, height)]), vec![vec![]]);
+
+            database.apply(updates).unwrap().unwrap();
+        }
+
+        assert!(matches!(lagging.try_recv(), Err(TryRecvError::Lagged(_))));
+    }
+}
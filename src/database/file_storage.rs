@@ -0,0 +1,298 @@
+use crate::database::{Storage, StorageError};
+
+use doomstack::{here, ResultExt, Top};
+
+use std::{
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    mem,
+    path::{Path, PathBuf},
+};
+
+const LOG: &str = "database.log";
+const COMPACTED: &str = "database.log.compacted";
+
+// Each record is prefixed by its length and its CRC-32
+const HEADER: usize = mem::size_of::<u64>() + mem::size_of::<u32>();
+
+// A `FileStorage` appends checksummed, length-prefixed records to a single log file,
+// syncing the file after each record. A record left torn by a crash mid-append
+// (truncated, or complete in length but not in content) is discarded (and the
+// log truncated to its last intact record) upon opening. Compaction rewrites
+// the log aside, then renames it over the original.
+pub(crate) struct FileStorage {
+    directory: PathBuf,
+    log: File,
+    length: u64,
+}
+
+impl FileStorage {
+    // Opens (or creates) the log in `directory`
+    pub fn open<P>(directory: P) -> Result<Self, Top<StorageError>>
+    where
+        P: AsRef<Path>,
+    {
+        let directory = directory.as_ref().to_path_buf();
+
+        fs::create_dir_all(&directory).pot(StorageError::OpenFailed, here!())?;
+
+        // A compacted log left behind was never renamed over the log:
+        // the log is still whole, and the compacted log is discarded
+        let _ = fs::remove_file(directory.join(COMPACTED));
+
+        let log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(directory.join(LOG))
+            .pot(StorageError::OpenFailed, here!())?;
+
+        let mut storage = FileStorage {
+            directory,
+            log,
+            length: 0,
+        };
+
+        // Records are read once here, to find the end of the last intact record
+
+        let length = storage
+            .read_all()?
+            .iter()
+            .map(|record| (HEADER + record.len()) as u64)
+            .sum::<u64>();
+
+        storage
+            .log
+            .set_len(length)
+            .pot(StorageError::OpenFailed, here!())?;
+
+        storage
+            .log
+            .seek(SeekFrom::End(0))
+            .pot(StorageError::OpenFailed, here!())?;
+
+        storage.length = length;
+
+        Ok(storage)
+    }
+
+    fn frame(record: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(HEADER + record.len());
+
+        frame.extend_from_slice(&(record.len() as u64).to_le_bytes());
+        frame.extend_from_slice(&crc32fast::hash(record).to_le_bytes());
+        frame.extend_from_slice(record);
+
+        frame
+    }
+
+    fn read_all(&mut self) -> Result<Vec<Vec<u8>>, Top<StorageError>> {
+        let mut log = Vec::new();
+
+        self.log
+            .seek(SeekFrom::Start(0))
+            .pot(StorageError::ReadFailed, here!())?;
+
+        self.log
+            .read_to_end(&mut log)
+            .pot(StorageError::ReadFailed, here!())?;
+
+        let mut records = Vec::new();
+        let mut cursor = log.as_slice();
+
+        while cursor.len() >= HEADER {
+            let (length, rest) = cursor.split_at(mem::size_of::<u64>());
+            let (checksum, rest) = rest.split_at(mem::size_of::<u32>());
+
+            let length = u64::from_le_bytes(length.try_into().unwrap()) as usize;
+            let checksum = u32::from_le_bytes(checksum.try_into().unwrap());
+
+            // Truncated record: everything from here on is discarded
+            if rest.len() < length {
+                break;
+            }
+
+            let (record, rest) = rest.split_at(length);
+
+            // Torn record (as records are synced one at a time, only the
+            // last record can be torn): everything from here on is discarded
+            if crc32fast::hash(record) != checksum {
+                break;
+            }
+
+            records.push(record.to_vec());
+            cursor = rest;
+        }
+
+        Ok(records)
+    }
+}
+
+impl Storage for FileStorage {
+    fn append(&mut self, record: &[u8]) -> Result<(), Top<StorageError>> {
+        let frame = FileStorage::frame(record);
+
+        self.log
+            .write_all(frame.as_slice())
+            .pot(StorageError::WriteFailed, here!())?;
+
+        self.log
+            .sync_data()
+            .pot(StorageError::WriteFailed, here!())?;
+
+        self.length += frame.len() as u64;
+
+        Ok(())
+    }
+
+    fn records(&mut self) -> Result<Vec<Vec<u8>>, Top<StorageError>> {
+        let records = self.read_all()?;
+
+        self.log
+            .seek(SeekFrom::End(0))
+            .pot(StorageError::ReadFailed, here!())?;
+
+        Ok(records)
+    }
+
+    fn size(&self) -> u64 {
+        self.length
+    }
+
+    fn compact(&mut self, records: Vec<Vec<u8>>) -> Result<(), Top<StorageError>> {
+        let compacted = self.directory.join(COMPACTED);
+
+        let mut log = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&compacted)
+            .pot(StorageError::CompactionFailed, here!())?;
+
+        let mut length = 0;
+
+        for record in records.iter() {
+            let frame = FileStorage::frame(record);
+
+            log.write_all(frame.as_slice())
+                .pot(StorageError::CompactionFailed, here!())?;
+
+            length += frame.len() as u64;
+        }
+
+        log.sync_all()
+            .pot(StorageError::CompactionFailed, here!())?;
+
+        fs::rename(&compacted, self.directory.join(LOG))
+            .pot(StorageError::CompactionFailed, here!())?;
+
+        // From here on, records must be appended to the compacted log
+        self.log = log;
+        self.length = length;
+
+        // The rename itself is durable only once `directory` is synced
+        File::open(&self.directory)
+            .and_then(|directory| directory.sync_all())
+            .pot(StorageError::CompactionFailed, here!())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn directory() -> PathBuf {
+        env::temp_dir().join(format!("file-storage-{:016x}", rand::random::<u64>()))
+    }
+
+    #[test]
+    fn torn_append() {
+        let directory = directory();
+
+        {
+            let mut storage = FileStorage::open(&directory).unwrap();
+
+            storage.append(b"first").unwrap();
+            storage.append(b"second").unwrap();
+
+            // Simulate a crash in the middle of a third `append`
+            storage.log.write_all(&100u64.to_le_bytes()).unwrap();
+            storage.log.write_all(b"thi").unwrap();
+        }
+
+        let mut storage = FileStorage::open(&directory).unwrap();
+        storage.append(b"third").unwrap();
+
+        assert_eq!(
+            storage.records().unwrap(),
+            vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()]
+        );
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn torn_content() {
+        let directory = directory();
+
+        {
+            let mut storage = FileStorage::open(&directory).unwrap();
+            storage.append(b"first").unwrap();
+
+            // Simulate a crash that persisted a whole length prefix,
+            // but not the whole content of a second `append`
+            let mut frame = FileStorage::frame(b"second");
+            let last = frame.len() - 1;
+            frame[last] = 0;
+
+            storage.log.write_all(frame.as_slice()).unwrap();
+        }
+
+        let mut storage = FileStorage::open(&directory).unwrap();
+        assert_eq!(storage.records().unwrap(), vec![b"first".to_vec()]);
+
+        storage.append(b"third").unwrap();
+
+        assert_eq!(
+            storage.records().unwrap(),
+            vec![b"first".to_vec(), b"third".to_vec()]
+        );
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn compact() {
+        let directory = directory();
+
+        {
+            let mut storage = FileStorage::open(&directory).unwrap();
+
+            storage.append(b"first").unwrap();
+            storage.append(b"second").unwrap();
+
+            storage.compact(vec![b"compacted".to_vec()]).unwrap();
+            assert_eq!(storage.size(), (HEADER + b"compacted".len()) as u64);
+
+            storage.append(b"third").unwrap();
+        }
+
+        // A compacted log left behind by an interrupted compaction is discarded
+        fs::write(directory.join(COMPACTED), b"garbage").unwrap();
+
+        let mut storage = FileStorage::open(&directory).unwrap();
+
+        assert_eq!(
+            storage.records().unwrap(),
+            vec![b"compacted".to_vec(), b"third".to_vec()]
+        );
+
+        assert!(!directory.join(COMPACTED).exists());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -0,0 +1,381 @@
+use bit_vec::BitVec;
+
+use buckets::Split;
+
+use crate::{
+    account::{Account, Entry, Id},
+    commit::{BatchCompletion, Payload, WitnessedBatch as CommitBatch},
+    database::{
+        commit::{BatchHolder as CommitHolder, PayloadHandle},
+        prepare::{BatchHolder as PrepareHolder, State},
+        Database,
+    },
+    prepare::{BatchCommit, WitnessedBatch as PrepareBatch},
+    signup::IdAssignment,
+};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeSet;
+
+use talk::crypto::primitives::hash::Hash;
+
+use zebra::database::TableTransaction;
+
+// An `Image` captures all the state of a `Database` that is rebuilt by replaying
+// its records: restoring an `Image` is equivalent to replaying all the records
+// that preceded it (see `Database::compact`). All fields are sorted (by `Id`, root
+// or `Entry`): `Image`s of `Database`s that applied the same updates are identical
+// (see `Snapshot`). Remark: prepare and commit batches restored from an `Image`
+// count as applied upon restoration (see `prune_prepare_batches` and
+// `prune_commit_batches`).
+#[derive(Serialize, Deserialize)]
+pub(in crate::database) struct Image {
+    pub assignments: Vec<IdAssignment>,
+    pub accounts: Vec<(Id, Account)>,
+    pub states: Vec<(Id, State)>,
+    pub prepare_batches: Vec<(PrepareBatch, BitVec, Option<BatchCommit>)>,
+    pub commit_batches: Vec<(CommitBatch, Option<BatchCompletion>)>,
+    pub applied: Vec<Hash>,
+    pub payloads: Vec<(Entry, Hash, usize)>,
+    pub closures: Vec<(Id, u64)>,
+}
+
+impl Database {
+    // Remark: this copies the whole state of `self`
+    pub(in crate::database) fn image(&mut self) -> Image {
+        // Batches dispatched but not yet applied hold (some of) the shards of
+        // `self.accounts`: they are waited for, and their effects settled
+        let shards = self.accounts.clone();
+        let guard = shards.lock_all();
+        self.settle();
+
+        // Every `Id` with a prepare state is assigned
+        let assigned = self.assigned.iter().copied().collect::<Vec<_>>();
+
+        let entries = self
+            .commit
+            .batches
+            .values()
+            .flat_map(|holder| holder.batch().payloads().iter().map(Payload::entry))
+            .collect::<BTreeSet<_>>();
+
+        let assignments = self
+            .assignments
+            .apply(
+                Split::with_key(assigned.clone(), |id| *id),
+                |assignments, id| {
+                    // `self.assigned` tracks the keys of `self.assignments`,
+                    // so the following `unwrap` is guaranteed to succeed
+                    assignments.get(&id).unwrap().clone()
+                },
+            )
+            .join();
+
+        let mut accounts = guard
+            .iter()
+            .map(|(id, account)| (id, account.clone()))
+            .collect::<Vec<_>>();
+
+        accounts.sort_by_key(|(id, _)| *id);
+
+        let states = self
+            .prepare
+            .states
+            .apply(Split::with_key(assigned, |id| *id), |states, id| {
+                states.get(&id).cloned().map(|state| (id, state))
+            })
+            .join()
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let mut prepare_batches = self
+            .prepare
+            .batches
+            .values()
+            .map(|holder| {
+                (
+                    holder.batch().clone(),
+                    holder.references().clone(),
+                    holder.commit().cloned(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        prepare_batches.sort_by_key(|(batch, _, _)| batch.root());
+
+        let mut commit_batches = self
+            .commit
+            .batches
+            .values()
+            .map(|holder| (holder.batch().clone(), holder.completion().cloned()))
+            .collect::<Vec<_>>();
+
+        commit_batches.sort_by_key(|(batch, _)| batch.root());
+
+        let mut applied = self.commit.applied.iter().copied().collect::<Vec<_>>();
+        applied.sort();
+
+        let mut closures = self
+            .commit
+            .closures
+            .iter()
+            .map(|(id, height)| (*id, *height))
+            .collect::<Vec<_>>();
+
+        closures.sort();
+
+        // Payload handles referencing dropped commit batches are useless, and not captured
+        let payloads = self
+            .commit
+            .payloads
+            .apply(
+                Split::with_key(entries.into_iter().collect::<Vec<_>>(), |entry| *entry),
+                |payloads, entry| {
+                    payloads
+                        .get(&entry)
+                        .map(|handle| (entry, handle.batch, handle.index))
+                },
+            )
+            .join()
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Image {
+            assignments,
+            accounts,
+            states,
+            prepare_batches,
+            commit_batches,
+            applied,
+            payloads,
+            closures,
+        }
+    }
+
+    // `self` must be empty (as when replaying records from scratch)
+    pub(in crate::database) fn restore(&mut self, image: Image) {
+        let Image {
+            assignments,
+            accounts,
+            states,
+            prepare_batches,
+            commit_batches,
+            applied,
+            payloads,
+            closures,
+        } = image;
+
+        self.assignments_unlogged(assignments);
+
+        let mut transaction = TableTransaction::new();
+
+        for (id, account) in accounts.iter() {
+            transaction.set(*id, account.summarize()).unwrap();
+        }
+
+        self.imminent.execute(transaction);
+
+        let mut guard = self.accounts.lock_all();
+
+        for (id, account) in accounts {
+            guard.insert(id, account);
+        }
+
+        drop(guard);
+
+        // Every restored `State` is stale (i.e., yet to be advertised)
+        self.prepare
+            .states
+            .apply(
+                Split::with_key(states.clone(), |(id, _)| *id),
+                |states, (id, state)| {
+                    states.insert(id, state);
+                },
+            )
+            .join();
+
+        self.prepare
+            .stale
+            .apply(Split::with_key(states, |(id, _)| *id), |stale, (id, _)| {
+                stale.insert(id);
+            })
+            .join();
+
+        for (batch, references, commit) in prepare_batches {
+            let root = batch.root();
+            let holder = PrepareHolder::restore(batch, references, commit);
+
+            self.prepare.batches.insert(root, holder);
+        }
+
+        for (batch, completion) in commit_batches {
+            let root = batch.root();
+            let holder = CommitHolder::restore(batch, completion);
+
+            self.commit.batches.insert(root, holder);
+        }
+
+        self.commit.applied.extend(applied);
+
+        self.commit
+            .payloads
+            .apply(
+                Split::with_key(payloads, |(entry, _, _)| *entry),
+                |payloads, (entry, batch, index)| {
+                    payloads.insert(entry, PayloadHandle { batch, index });
+                },
+            )
+            .join();
+
+        self.commit.closures.extend(closures);
+    }
+}
+
+#[cfg(test)]
+pub(in crate::database) mod tests {
+    use super::*;
+
+    use crate::{
+        account::Operation,
+        crypto::{Certificate, Identify},
+        database::WriteBatch,
+        prepare::{Prepare, ReductionStatement},
+        view::test::InstallGenerator,
+    };
+
+    use std::{env, fs};
+
+    use talk::crypto::primitives::hash;
+
+    use zebra::vector::Vector;
+
+    // Validity is irrelevant to `Database`: batches are witnessed by a single signer
+
+    pub(in crate::database) fn prepare_batch(
+        generator: &InstallGenerator,
+        ids: &[Id],
+    ) -> PrepareBatch {
+        let view = generator.view(4);
+
+        let prepares = ids
+            .iter()
+            .map(|id| Prepare::new(Entry { id: *id, height: 1 }, hash::hash(id).unwrap()))
+            .collect::<Vec<_>>();
+
+        let prepares = Vector::new(prepares).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        PrepareBatch::new(view.identifier(), prepares, BitVec::new(), witness)
+    }
+
+    pub(in crate::database) fn commit_batch(
+        generator: &InstallGenerator,
+        entries: &[(Id, u64)],
+    ) -> CommitBatch {
+        let view = generator.view(4);
+
+        let payloads = entries
+            .iter()
+            .map(|(id, height)| {
+                Payload::new(
+                    Entry {
+                        id: *id,
+                        height: *height,
+                    },
+                    Operation::withdraw(0, 0, 0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let payloads = Vector::new(payloads).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(payloads.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        CommitBatch::new(view.identifier(), payloads, witness)
+    }
+
+    fn heights(database: &mut Database, ids: &[Id]) -> Vec<Option<u64>> {
+        database
+            .prepare
+            .states
+            .apply(
+                Split::with_key(ids.to_vec(), |id| *id),
+                |states, id| match states.get(&id) {
+                    Some(State::Consistent { height, .. }) => Some(*height),
+                    _ => None,
+                },
+            )
+            .join()
+    }
+
+    #[test]
+    fn compact() {
+        let generator = InstallGenerator::new(4);
+
+        let directory =
+            env::temp_dir().join(format!("database-image-{:016x}", rand::random::<u64>()));
+
+        let prepared = prepare_batch(&generator, &[1, 2, 3]);
+        let committed = commit_batch(&generator, &[(1, 1), (2, 1)]);
+
+        {
+            let mut database = Database::open(&directory, Default::default()).unwrap();
+
+            let mut updates = WriteBatch::new();
+            updates.apply_prepare_batch(prepared.clone());
+            updates.apply_commit_batch(committed.clone(), vec![vec![]; 2]);
+
+            database.apply(updates).unwrap().unwrap();
+
+            let size = database.log_size().unwrap();
+            database.compact().unwrap();
+
+            // The log now holds a single record (which captures the same state)
+            assert_ne!(database.log_size().unwrap(), size);
+
+            // Records are appended to the compacted log as usual
+            let mut updates = WriteBatch::new();
+            updates.apply_commit_batch(commit_batch(&generator, &[(3, 1)]), vec![vec![]]);
+
+            database.apply(updates).unwrap().unwrap();
+        }
+
+        let mut database = Database::open(&directory, Default::default()).unwrap();
+
+        assert_eq!(
+            heights(&mut database, &[1, 2, 3, 4]),
+            vec![Some(1), Some(1), Some(1), None]
+        );
+
+        assert!(database.prepare.batches.contains_key(&prepared.root()));
+        assert!(database.commit.batches.contains_key(&committed.root()));
+        assert!(database.commit.applied.contains(&committed.root()));
+        assert_eq!(database.commit.batches.len(), 2);
+
+        // Every committed `Id` was restored at height 1: height 2 is applicable
+        let mut updates = WriteBatch::new();
+
+        updates.apply_commit_batch(
+            commit_batch(&generator, &[(1, 2), (2, 2), (3, 2)]),
+            vec![vec![]; 3],
+        );
+
+        assert!(database.apply(updates).unwrap().is_some());
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
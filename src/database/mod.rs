@@ -1,12 +1,28 @@
+mod accounts;
 mod database;
+mod feed;
+mod file_storage;
+mod image;
+mod record;
 mod signup;
+mod snapshot;
+mod storage;
+mod write_batch;
 mod zebras;
 
 pub(crate) mod commit;
 pub(crate) mod prepare;
 
-pub(crate) use commit::Commit;
+pub(crate) use accounts::Accounts;
+pub(crate) use commit::{Commit, CommitApplication};
 pub(crate) use database::Database;
+pub(crate) use file_storage::FileStorage;
+pub(in crate::database) use image::Image;
 pub(crate) use prepare::Prepare;
+pub(in crate::database) use record::{Record, RecordRef};
 pub(crate) use signup::Signup;
+pub(crate) use snapshot::{Snapshot, SnapshotChunk};
+#[allow(unused_imports)]
+pub(crate) use storage::{Storage, StorageError};
+pub(crate) use write_batch::{WriteBatch, WriteOutcome};
 pub(crate) use zebras::Zebras;
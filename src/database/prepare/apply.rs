@@ -0,0 +1,205 @@
+use buckets::Split;
+
+use crate::{
+    database::{
+        prepare::{BatchHolder, PrepareHandle, State},
+        Database, RecordRef, StorageError,
+    },
+    prepare::{BatchCommit, Equivocation, WitnessedBatch},
+};
+
+use doomstack::Top;
+
+impl Database {
+    // Stores `commit` in `commit.root()`'s `BatchHolder`, if still available
+    pub fn attach_batch_commit(&mut self, commit: BatchCommit) -> Result<(), Top<StorageError>> {
+        if !self.prepare.batches.contains_key(&commit.root()) {
+            return Ok(());
+        }
+
+        self.write(RecordRef::BatchCommit(&commit))?;
+        self.batch_commit_unlogged(commit);
+
+        Ok(())
+    }
+
+    // Records the adoption of `equivocations` (which the caller must apply to
+    // `self.prepare.states` before unlocking `self`, see `equivocations_unlogged`)
+    pub fn record_equivocations<'e, E>(&mut self, equivocations: E) -> Result<(), Top<StorageError>>
+    where
+        E: IntoIterator<Item = &'e Equivocation>,
+    {
+        let equivocations = equivocations.into_iter().collect::<Vec<_>>();
+
+        if equivocations.is_empty() {
+            return Ok(());
+        }
+
+        self.write(RecordRef::Equivocations(equivocations))
+    }
+
+    pub(in crate::database) fn prepare_batch_unlogged(
+        &mut self,
+        batch: WitnessedBatch,
+    ) -> Vec<Equivocation> {
+        // Prepare `Split` to feed `self`'s `Buckets`
+
+        // Each element of the `Split` contains an enumerated `Prepare`
+        // (`Prepare`s excluded from `batch`'s witness are not applied)
+        let split = Split::with_key(
+            batch
+                .prepares()
+                .iter()
+                .cloned()
+                .enumerate()
+                .filter(|(index, _)| !batch.excludes(*index)),
+            |(_, prepare)| prepare.id(),
+        );
+
+        let states = &mut self.prepare.states;
+        let stale = &mut self.prepare.stale;
+        let batches = &self.prepare.batches;
+
+        // The following applies each enumerated `Prepare` in `split` to `states` and
+        // `stales`, while attaching immutable references to `batches` and `batch`
+        let exceptions = buckets::apply_sparse_attached(
+            (states, stale),
+            &(batches, &batch),
+            split,
+            |(states, stale), &(batches, batch), (index, prepare)| {
+                // Build `PrepareHandle` relevant to `prepare`
+                let handle = PrepareHandle::Batched {
+                    batch: batch.root(),
+                    index,
+                };
+
+                let state = match states.get(&prepare.id()) {
+                    Some(state) => match state {
+                        State::Consistent {
+                            height: state_height,
+                            commitment: state_commitment,
+                            handle: state_handle,
+                        } => {
+                            if prepare.height() == *state_height {
+                                // A `Prepare` for this `prepare.height()` was previously received.
+
+                                if prepare.commitment() == *state_commitment {
+                                    // `prepare` does not collide with the previously observed `Prepare`:
+                                    // `prepare` is valid, and no further update is required
+                                    return None;
+                                } else {
+                                    // `prepare` collides with a previously observed `Prepare`:
+                                    // retrieve `Extract` to prove `Equivocation`
+                                    let state_extract = match state_handle {
+                                        PrepareHandle::Batched { batch, index } => {
+                                            // `batch` is still in `self`, obtain `Extract` from there
+                                            batches.get(batch).unwrap().extract(*index)
+                                        }
+
+                                        // The batch was garbage collected, leaving a ready-made `Extract` behind
+                                        PrepareHandle::Standalone(extract) => extract.clone(),
+                                    };
+
+                                    // Obtain conflicting `Extract` from `batch`, build `Equivocation`
+                                    let extract = batch.extract(index);
+                                    let equivocation = Equivocation::new(extract, state_extract);
+
+                                    // State must be updated to reflect the equivocation
+                                    State::Equivocated(equivocation)
+                                }
+                            } else {
+                                // No `Prepare` was previously observed for this height: initialize
+                                // the state to `Consistent`.
+
+                                // (*) Remark: currently, no further check is performed on `prepare.height()`.
+                                // In the future, a proof will be optionally provided by the broker to
+                                // prove that the client successfully reached `prepare.height() - 1`.
+                                //
+                                // As a result, the following should apply:
+                                //  - If `prepare.height()` is greater than both the highest observed
+                                //    `Commit` for `prepare.id()` AND `state_height`, then the `state`
+                                //    should be updated as done below.
+                                //  - Otherwise, a higher `Commit` should be provided to the broker
+                                //    as evidence of misbehaviour / delay, and `prepare.id()` should
+                                //    be represented in `exceptions`.
+
+                                State::Consistent {
+                                    height: prepare.height(),
+                                    commitment: prepare.commitment(),
+                                    handle,
+                                }
+                            }
+                        }
+
+                        // `State::Equivocated` is absorbing and must not be updated
+                        equivocated => equivocated.clone(),
+                    },
+                    None => State::Consistent {
+                        // No `Prepare` was previously observed for this height: initialize
+                        // the state to `Consistent`
+
+                        // Remark: see above (*)
+                        height: prepare.height(),
+                        commitment: prepare.commitment(),
+                        handle,
+                    },
+                };
+
+                // Extract, if available, the appropriate `Equivocation` from `state`
+                let exception = if let State::Equivocated(equivocation) = &state {
+                    Some(equivocation.clone())
+                } else {
+                    None
+                };
+
+                // Update `states`, flag new state in `stale` to allow efficient
+                // flushing to `advertisements` (performed immediately before
+                // state transfer)
+                states.insert(prepare.id(), state);
+                stale.insert(prepare.id());
+
+                // If `exception` is `Some`, it is collected in `exceptions`
+                exception
+            },
+        );
+
+        // Store `batch` in `batches`
+
+        let root = batch.root();
+        let holder = BatchHolder::new(batch);
+
+        self.prepare.batches.insert(root, holder);
+
+        exceptions
+    }
+
+    pub(in crate::database) fn batch_commit_unlogged(&mut self, commit: BatchCommit) {
+        if let Some(holder) = self.prepare.batches.get_mut(&commit.root()) {
+            holder.attach(commit);
+        }
+    }
+
+    // Adopts each element of `equivocations` (unless its `Id` already equivocated)
+    pub(in crate::database) fn equivocations_unlogged(&mut self, equivocations: Vec<Equivocation>) {
+        let split = Split::with_key(equivocations, |equivocation| equivocation.id());
+
+        let states = &mut self.prepare.states;
+        let stale = &mut self.prepare.stale;
+
+        buckets::apply_sparse(
+            (states, stale),
+            split,
+            |(states, stale), equivocation| -> Option<()> {
+                let id = equivocation.id();
+
+                // `State::Equivocated` is absorbing and must not be updated
+                if !matches!(states.get(&id), Some(State::Equivocated(_))) {
+                    states.insert(id, State::Equivocated(equivocation));
+                    stale.insert(id);
+                }
+
+                None
+            },
+        );
+    }
+}
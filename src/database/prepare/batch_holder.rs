@@ -2,12 +2,13 @@ use bit_vec::BitVec;
 
 use crate::prepare::{BatchCommit, Extract, WitnessedBatch};
 
-use std::iter;
+use std::{iter, time::Instant};
 
 pub(crate) struct BatchHolder {
     batch: WitnessedBatch,
     references: BitVec,
     commit: Option<BatchCommit>,
+    applied: Instant,
 }
 
 impl BatchHolder {
@@ -20,13 +21,45 @@ impl BatchHolder {
             batch,
             references,
             commit: None,
+            applied: Instant::now(),
         }
     }
 
+    // Rebuilds a `BatchHolder` from its parts (see `Database::restore`): as
+    // the original application time is lost, `batch` counts as applied now
+    pub fn restore(batch: WitnessedBatch, references: BitVec, commit: Option<BatchCommit>) -> Self {
+        BatchHolder {
+            batch,
+            references,
+            commit,
+            applied: Instant::now(),
+        }
+    }
+
+    pub fn batch(&self) -> &WitnessedBatch {
+        &self.batch
+    }
+
     pub fn extract(&self, index: usize) -> Extract {
         self.batch.extract(index)
     }
 
+    pub fn applied(&self) -> Instant {
+        self.applied
+    }
+
+    // Indices of the elements of `self.batch` that might still be referenced by a `State`
+    pub fn referenced(&self) -> impl Iterator<Item = usize> + '_ {
+        self.references
+            .iter()
+            .enumerate()
+            .filter_map(|(index, referenced)| if referenced { Some(index) } else { None })
+    }
+
+    pub fn references(&self) -> &BitVec {
+        &self.references
+    }
+
     pub fn commit(&self) -> Option<&BatchCommit> {
         self.commit.as_ref()
     }
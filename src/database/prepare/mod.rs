@@ -1,7 +1,9 @@
 mod advertisement;
+mod apply;
 mod batch_holder;
 mod prepare;
 mod prepare_handle;
+mod prune;
 mod state;
 
 pub(crate) use advertisement::Advertisement;
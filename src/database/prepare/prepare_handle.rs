@@ -1,8 +1,10 @@
 use crate::prepare::Extract;
 
+use serde::{Deserialize, Serialize};
+
 use talk::crypto::primitives::hash::Hash;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) enum PrepareHandle {
     Batched { batch: Hash, index: usize },
     Standalone(Extract),
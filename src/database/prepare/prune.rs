@@ -0,0 +1,204 @@
+use buckets::Split;
+
+use crate::database::{
+    prepare::{PrepareHandle, State},
+    Database,
+};
+
+use std::time::{Duration, Instant};
+
+impl Database {
+    // Drops every batch that no `State` references any longer, and that was applied
+    // at least `retention` ago, returning the number of batches dropped. Referenced
+    // batches are retained regardless of `retention`: the `Extract`s of their `Prepare`s
+    // are needed to commit them without a `CommitProof` (see `commit::steps::validate_batch`),
+    // and to prove an `Equivocation`, should a conflicting `Prepare` be received.
+    pub fn prune_prepare_batches(&mut self, retention: Duration) -> usize {
+        let now = Instant::now();
+
+        let expired = self
+            .prepare
+            .batches
+            .iter()
+            .filter(|(_, holder)| now.duration_since(holder.applied()) >= retention)
+            .map(|(root, _)| *root)
+            .collect::<Vec<_>>();
+
+        let mut dropped = 0;
+
+        for root in expired.iter() {
+            // `expired` was collected from `self.prepare.batches`,
+            // so the following `unwrap`s are guaranteed to succeed
+            let holder = self.prepare.batches.get(root).unwrap();
+            let prepares = holder.batch().prepares();
+
+            let split = Split::with_key(holder.referenced(), |index| prepares[*index].id());
+
+            // Collect the elements of `holder` whose `State` no longer references them (a
+            // more recent `Prepare`, or an `Equivocation`, might have replaced the reference)
+            let released = buckets::apply_sparse_attached(
+                &mut self.prepare.states,
+                holder,
+                split,
+                |states, holder, index| {
+                    let id = holder.batch().prepares()[index].id();
+
+                    let referencing = match states.get(&id) {
+                        Some(State::Consistent {
+                            handle:
+                                PrepareHandle::Batched {
+                                    batch,
+                                    index: handle_index,
+                                },
+                            ..
+                        }) => *batch == holder.batch().root() && *handle_index == index,
+                        _ => false,
+                    };
+
+                    if referencing {
+                        None
+                    } else {
+                        Some(index)
+                    }
+                },
+            );
+
+            let holder = self.prepare.batches.get_mut(root).unwrap();
+
+            // References, once released, are never restored: elements of `holder`
+            // released here are not checked again by later prunings
+            for index in released {
+                holder.unref(index);
+            }
+
+            if holder.referenced().next().is_none() {
+                self.prepare.batches.remove(root);
+                dropped += 1;
+            }
+        }
+
+        dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Entry,
+        crypto::{Certificate, Identify},
+        database::WriteBatch,
+        prepare::{Prepare, ReductionStatement, WitnessedBatch},
+        view::test::InstallGenerator,
+    };
+
+    use bit_vec::BitVec;
+
+    use talk::crypto::primitives::hash;
+
+    use zebra::vector::Vector;
+
+    // Validity is irrelevant to `Database`: `batch` is witnessed by a single signer
+    fn batch(generator: &InstallGenerator, ids: &[u64], height: u64) -> WitnessedBatch {
+        let view = generator.view(4);
+
+        let prepares = ids
+            .iter()
+            .map(|id| {
+                Prepare::new(
+                    Entry { id: *id, height },
+                    hash::hash(&(id, height)).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let prepares = Vector::new(prepares).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        WitnessedBatch::new(view.identifier(), prepares, BitVec::new(), witness)
+    }
+
+    fn apply(database: &mut Database, batch: WitnessedBatch) {
+        let mut updates = WriteBatch::new();
+        updates.apply_prepare_batch(batch);
+
+        database.apply(updates).unwrap().unwrap();
+    }
+
+    fn referenced(database: &Database, batch: &WitnessedBatch) -> Vec<usize> {
+        database
+            .prepare
+            .batches
+            .get(&batch.root())
+            .unwrap()
+            .referenced()
+            .collect()
+    }
+
+    #[test]
+    fn prune() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        let first = batch(&generator, &[1, 2], 1);
+        apply(&mut database, first.clone());
+
+        assert_eq!(database.prune_prepare_batches(Duration::from_secs(3600)), 0);
+        assert_eq!(database.prepare.batches.len(), 1);
+
+        // `first` is still referenced by the `State`s of both 1 and 2
+
+        assert_eq!(database.prune_prepare_batches(Duration::from_secs(0)), 0);
+        assert_eq!(referenced(&database, &first), vec![0, 1]);
+
+        // Once 1 prepares again, only 2 references `first`
+
+        let second = batch(&generator, &[1], 2);
+        apply(&mut database, second.clone());
+
+        assert_eq!(database.prune_prepare_batches(Duration::from_secs(0)), 0);
+        assert_eq!(referenced(&database, &first), vec![1]);
+        assert_eq!(referenced(&database, &second), vec![0]);
+
+        // Unreferenced batches are retained for `retention`
+
+        let third = batch(&generator, &[2], 2);
+        apply(&mut database, third.clone());
+
+        assert_eq!(database.prune_prepare_batches(Duration::from_secs(3600)), 0);
+        assert_eq!(database.prepare.batches.len(), 3);
+
+        assert_eq!(database.prune_prepare_batches(Duration::from_secs(0)), 1);
+        assert!(!database.prepare.batches.contains_key(&first.root()));
+
+        // States still reference the remaining batches
+
+        let handles = database
+            .prepare
+            .states
+            .apply(
+                Split::with_key(vec![1, 2], |id| *id),
+                |states, id| match states.get(&id) {
+                    Some(State::Consistent {
+                        height,
+                        handle: PrepareHandle::Batched { batch, .. },
+                        ..
+                    }) => Some((*height, *batch)),
+                    _ => None,
+                },
+            )
+            .join();
+
+        assert_eq!(
+            handles,
+            vec![Some((2, second.root())), Some((2, third.root()))]
+        );
+    }
+}
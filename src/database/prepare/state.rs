@@ -1,8 +1,10 @@
 use crate::{database::prepare::PrepareHandle, prepare::Equivocation};
 
+use serde::{Deserialize, Serialize};
+
 use talk::crypto::primitives::hash::Hash;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) enum State {
     Consistent {
         height: u64,
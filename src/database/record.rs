@@ -0,0 +1,42 @@
+use crate::{
+    account::Operation,
+    commit::{BatchCompletion, WitnessedBatch as CommitBatch},
+    database::Image,
+    prepare::{BatchCommit, Equivocation, WitnessedBatch as PrepareBatch},
+    signup::IdAssignment,
+};
+
+use serde::{Deserialize, Serialize};
+
+// A `Record` describes an update to a `Database`. Records are written to the
+// `Database`'s `Storage` before the update they describe is acknowledged (e.g.,
+// by a `BatchCommitShard`): replaying all records, in order, on an empty
+// `Database` restores its state.
+// Records are written by reference (to avoid cloning batches), and read back as
+// owned values: `RecordRef` must list the same variants as `Record`, in the same
+// order (`bincode` then encodes both identically). A `Batch` record holds the
+// records of a `WriteBatch`, which are persisted (and replayed) all at once. An
+// `Image` record (written by `Database::compact`) replaces all records preceding it.
+#[derive(Serialize, Deserialize)]
+pub(in crate::database) enum Record {
+    Assignments(Vec<IdAssignment>),
+    PrepareBatch(PrepareBatch),
+    BatchCommit(BatchCommit),
+    Equivocations(Vec<Equivocation>),
+    CommitBatch(CommitBatch, Vec<Vec<Operation>>),
+    BatchCompletion(BatchCompletion),
+    Batch(Vec<Record>),
+    Image(Image),
+}
+
+#[derive(Serialize)]
+pub(in crate::database) enum RecordRef<'a> {
+    Assignments(&'a [IdAssignment]),
+    PrepareBatch(&'a PrepareBatch),
+    BatchCommit(&'a BatchCommit),
+    Equivocations(Vec<&'a Equivocation>),
+    CommitBatch(&'a CommitBatch, &'a [Vec<Operation>]),
+    BatchCompletion(&'a BatchCompletion),
+    Batch(&'a [Record]),
+    Image(&'a Image),
+}
@@ -0,0 +1,312 @@
+use bit_vec::BitVec;
+
+use crate::{
+    account::{Account, AccountSettings, Entry, Id},
+    commit::{BatchCompletion, WitnessedBatch as CommitBatch},
+    database::{prepare::State, Database, Image},
+    prepare::{BatchCommit, WitnessedBatch as PrepareBatch},
+    signup::IdAssignment,
+};
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap};
+
+use talk::crypto::primitives::hash::Hash;
+
+use zebra::vector::{Proof, Vector};
+
+// A `Snapshot` captures the state of a `Database` (see `Image`), to be transferred
+// to a joining replica. Its `SnapshotChunk`s are Merkle-rooted: each chunk can be
+// fetched and verified against `root()` independently. Chunks cover, in order:
+// contiguous ranges of assigned `Id`s, then one prepare batch each, then one commit
+// batch each, then the rest of the commit state. Snapshots of `Database`s that
+// applied the same updates are identical.
+pub(crate) struct Snapshot {
+    chunks: Vector<SnapshotChunk>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum SnapshotChunk {
+    // Each `IdAssignment`, along with the `Account` and prepare `State` of its `Id`
+    // (`None` if no operation was ever applied / prepared from that `Id`)
+    Ids(Vec<(IdAssignment, Option<Account>, Option<State>)>),
+    PrepareBatch(PrepareBatch, BitVec, Option<BatchCommit>),
+    CommitBatch(CommitBatch, Option<BatchCompletion>),
+    // `accounts` lists the `Account`s of unassigned `Id`s (referenced by commit batches)
+    Commit {
+        accounts: Vec<(Id, Account)>,
+        applied: Vec<Hash>,
+        payloads: Vec<(Entry, Hash, usize)>,
+        closures: Vec<(Id, u64)>,
+    },
+}
+
+// Chunks are ordered by kind, then by `Id` (or root) within each kind
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum ChunkKey {
+    Id(Id),
+    PrepareBatch(Hash),
+    CommitBatch(Hash),
+    Commit,
+}
+
+impl Snapshot {
+    pub fn root(&self) -> Hash {
+        self.chunks.root()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.items().len()
+    }
+
+    pub fn chunk(&self, index: usize) -> Option<(SnapshotChunk, Proof)> {
+        let chunk = self.chunks.items().get(index)?.clone();
+        Some((chunk, self.chunks.prove(index)))
+    }
+}
+
+impl SnapshotChunk {
+    // Number of `Id`s covered by `self`
+    pub fn len(&self) -> usize {
+        match self {
+            SnapshotChunk::Ids(entries) => entries.len(),
+            _ => 0,
+        }
+    }
+
+    // Inclusion `Proof`s do not bind chunks to their index: `chunks` (e.g., as fetched
+    // by `CatchUp`) are well-ordered only if their keys strictly increase, and end with
+    // the (single) `Commit` chunk. This rules out duplicated (hence missing) chunks.
+    pub fn ordered(chunks: &[SnapshotChunk]) -> bool {
+        let keys = chunks
+            .iter()
+            .flat_map(SnapshotChunk::keys)
+            .collect::<Vec<_>>();
+
+        keys.windows(2).all(|window| window[0] < window[1])
+            && keys.last() == Some(&ChunkKey::Commit)
+    }
+
+    fn keys(&self) -> Vec<ChunkKey> {
+        match self {
+            SnapshotChunk::Ids(entries) => entries
+                .iter()
+                .map(|(assignment, _, _)| ChunkKey::Id(assignment.id()))
+                .collect(),
+            SnapshotChunk::PrepareBatch(batch, _, _) => vec![ChunkKey::PrepareBatch(batch.root())],
+            SnapshotChunk::CommitBatch(batch, _) => vec![ChunkKey::CommitBatch(batch.root())],
+            SnapshotChunk::Commit { .. } => vec![ChunkKey::Commit],
+        }
+    }
+}
+
+impl Database {
+    // Captures `self`, covering (at most) `chunk_size` `Id`s per chunk. Remark: this
+    // copies the whole state of `self` (which remains locked in the meantime).
+    pub fn snapshot(&mut self, chunk_size: usize) -> Snapshot {
+        let Image {
+            assignments,
+            accounts,
+            states,
+            prepare_batches,
+            commit_batches,
+            applied,
+            payloads,
+            closures,
+        } = self.image();
+
+        // Every `Id` with a `State` is assigned, but not every `Id` with an `Account`:
+        // accounts left over once all assigned `Id`s are covered belong in `Commit`
+
+        let mut accounts = accounts.into_iter().collect::<BTreeMap<_, _>>();
+        let mut states = states.into_iter().collect::<HashMap<_, _>>();
+
+        let entries = assignments
+            .into_iter()
+            .map(|assignment| {
+                let account = accounts.remove(&assignment.id());
+                let state = states.remove(&assignment.id());
+
+                (assignment, account, state)
+            })
+            .collect::<Vec<_>>();
+
+        let mut chunks = entries
+            .chunks(chunk_size.max(1))
+            .map(|entries| SnapshotChunk::Ids(entries.to_vec()))
+            .collect::<Vec<_>>();
+
+        chunks.extend(
+            prepare_batches
+                .into_iter()
+                .map(|(batch, references, commit)| {
+                    SnapshotChunk::PrepareBatch(batch, references, commit)
+                }),
+        );
+
+        chunks.extend(
+            commit_batches
+                .into_iter()
+                .map(|(batch, completion)| SnapshotChunk::CommitBatch(batch, completion)),
+        );
+
+        chunks.push(SnapshotChunk::Commit {
+            accounts: accounts.into_iter().collect(),
+            applied,
+            payloads,
+            closures,
+        });
+
+        Snapshot {
+            chunks: Vector::new(chunks).unwrap(),
+        }
+    }
+
+    // Builds a `Database` from the chunks of a `Snapshot` (which must be
+    // verified and well-ordered beforehand, e.g., by `CatchUp`)
+    pub fn from_snapshot<C>(chunks: C, account_settings: AccountSettings) -> Self
+    where
+        C: IntoIterator<Item = SnapshotChunk>,
+    {
+        let mut database = Database::with_account_settings(account_settings);
+
+        let mut image = Image {
+            assignments: Vec::new(),
+            accounts: Vec::new(),
+            states: Vec::new(),
+            prepare_batches: Vec::new(),
+            commit_batches: Vec::new(),
+            applied: Vec::new(),
+            payloads: Vec::new(),
+            closures: Vec::new(),
+        };
+
+        for chunk in chunks {
+            match chunk {
+                SnapshotChunk::Ids(entries) => {
+                    for (assignment, account, state) in entries {
+                        let id = assignment.id();
+
+                        image.assignments.push(assignment);
+                        image.accounts.extend(account.map(|account| (id, account)));
+                        image.states.extend(state.map(|state| (id, state)));
+                    }
+                }
+                SnapshotChunk::PrepareBatch(batch, references, commit) => {
+                    image.prepare_batches.push((batch, references, commit));
+                }
+                SnapshotChunk::CommitBatch(batch, completion) => {
+                    image.commit_batches.push((batch, completion));
+                }
+                SnapshotChunk::Commit {
+                    accounts,
+                    applied,
+                    payloads,
+                    closures,
+                } => {
+                    image.accounts.extend(accounts);
+                    image.applied = applied;
+                    image.payloads = payloads;
+                    image.closures = closures;
+                }
+            }
+        }
+
+        database.restore(image);
+        database
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        database::{
+            image::tests::{commit_batch, prepare_batch},
+            WriteBatch,
+        },
+        processing::Namespace,
+        signup::test::assignment,
+        view::test::InstallGenerator,
+    };
+
+    use talk::crypto::KeyChain;
+
+    fn assignments(generator: &InstallGenerator, ids: &[Id]) -> Vec<IdAssignment> {
+        let view = generator.view(4);
+
+        ids.iter()
+            .map(|id| {
+                assignment(
+                    &generator.keychains,
+                    &view,
+                    &Namespace::default(),
+                    &KeyChain::random(),
+                    *id,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let generator = InstallGenerator::new(4);
+
+        let mut database = Database::new();
+        database.assignments_unlogged(assignments(&generator, &[7, 3, 5, 1, 9]));
+
+        database
+            .accounts
+            .lock(vec![3])
+            .insert(3, Account::new(3, &Default::default()));
+
+        // `Id` 11 is unassigned, but gets an `Account` once committed
+
+        let prepared = prepare_batch(&generator, &[1, 3]);
+        let committed = commit_batch(&generator, &[(3, 1), (11, 1)]);
+
+        let mut updates = WriteBatch::new();
+        updates.apply_prepare_batch(prepared.clone());
+        updates.apply_commit_batch(committed.clone(), vec![vec![]; 2]);
+
+        database.apply(updates).unwrap().unwrap();
+
+        let snapshot = database.snapshot(2);
+        assert_eq!(snapshot.len(), 6);
+
+        let chunks = (0..snapshot.len())
+            .map(|index| {
+                let (chunk, proof) = snapshot.chunk(index).unwrap();
+                proof.verify(snapshot.root(), &chunk).unwrap();
+                chunk
+            })
+            .collect::<Vec<_>>();
+
+        assert!(snapshot.chunk(6).is_none());
+        assert!(SnapshotChunk::ordered(chunks.as_slice()));
+
+        // A duplicated chunk (in place of another) is detected
+
+        let mut duplicated = chunks.clone();
+        duplicated[1] = duplicated[0].clone();
+
+        assert!(!SnapshotChunk::ordered(duplicated.as_slice()));
+
+        let mut restored = Database::from_snapshot(chunks, Default::default());
+
+        assert!(restored.prepare.batches.contains_key(&prepared.root()));
+        assert!(restored.commit.applied.contains(&committed.root()));
+
+        let restored = restored.snapshot(2);
+        assert_eq!(restored.root(), snapshot.root());
+
+        // Snapshots of empty `Database`s have a single `Commit` chunk
+
+        let empty = Database::new().snapshot(2);
+
+        assert_eq!(empty.len(), 1);
+        assert_eq!(empty.chunk(0).unwrap().0.len(), 0);
+    }
+}
@@ -0,0 +1,33 @@
+use doomstack::{Doom, Top};
+
+// A `Storage` durably persists the records written by a `Database` (see `Record`),
+// to be replayed by `Database::with_storage` upon restart. Records are opaque
+// to `Storage`, which only needs to preserve them, in order.
+pub(crate) trait Storage: Send {
+    // Once `append` returns, `record` must survive a crash
+    fn append(&mut self, record: &[u8]) -> Result<(), Top<StorageError>>;
+
+    // All records appended so far, in order
+    fn records(&mut self) -> Result<Vec<Vec<u8>>, Top<StorageError>>;
+
+    // Total size (in bytes) of the records held by `self`
+    fn size(&self) -> u64;
+
+    // Atomically replaces all records held by `self` with `records`: upon
+    // a crash, either all previous records or `records` alone survive
+    fn compact(&mut self, records: Vec<Vec<u8>>) -> Result<(), Top<StorageError>>;
+}
+
+#[derive(Doom)]
+pub(crate) enum StorageError {
+    #[doom(description("Failed to open storage"))]
+    OpenFailed,
+    #[doom(description("Failed to write record"))]
+    WriteFailed,
+    #[doom(description("Failed to read records"))]
+    ReadFailed,
+    #[doom(description("Malformed record"))]
+    MalformedRecord,
+    #[doom(description("Failed to compact records"))]
+    CompactionFailed,
+}
@@ -0,0 +1,151 @@
+use crate::{
+    account::{Id, Operation},
+    commit::WitnessedBatch as CommitBatch,
+    database::{Database, Record, RecordRef, StorageError},
+    prepare::{Equivocation, WitnessedBatch as PrepareBatch},
+    signup::IdAssignment,
+};
+
+use doomstack::Top;
+
+// A `WriteBatch` stages the updates of a serve path, to be applied to a `Database`
+// all at once by `Database::apply`. A serve path that bails before applying its
+// `WriteBatch` (e.g., because a witness turns out to be invalid) leaves the
+// `Database` untouched. Staged updates are applied in order, under a single lock,
+// and persisted as a single record.
+#[derive(Default)]
+pub(crate) struct WriteBatch {
+    records: Vec<Record>,
+}
+
+// The outcome of applying a `WriteBatch`
+#[derive(Default)]
+pub(crate) struct WriteOutcome {
+    // One `Equivocation` for each staged `Prepare` that collided
+    // with a previously observed `Prepare`
+    pub equivocations: Vec<Equivocation>,
+    // The `Id` of each staged payload that failed to apply
+    pub exceptions: Vec<Id>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        WriteBatch::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn insert_assignments(&mut self, assignments: Vec<IdAssignment>) {
+        if !assignments.is_empty() {
+            self.records.push(Record::Assignments(assignments));
+        }
+    }
+
+    pub fn apply_prepare_batch(&mut self, batch: PrepareBatch) {
+        self.records.push(Record::PrepareBatch(batch));
+    }
+
+    // Each element of `batch.payloads()` is applied along with
+    // the corresponding element of `dependencies`
+    pub fn apply_commit_batch(&mut self, batch: CommitBatch, dependencies: Vec<Vec<Operation>>) {
+        self.records.push(Record::CommitBatch(batch, dependencies));
+    }
+}
+
+impl Database {
+    // Applies (and persists) every update staged in `updates`. Returns `None`, leaving
+    // `self` unaffected, if some staged commit batch is inapplicable (applicability is
+    // checked against the state of `self` before any update is applied).
+    pub fn apply(
+        &mut self,
+        updates: WriteBatch,
+    ) -> Result<Option<WriteOutcome>, Top<StorageError>> {
+        let applicable = updates.records.iter().all(|record| match record {
+            Record::CommitBatch(batch, _) => self.commit_applicable(batch),
+            _ => true,
+        });
+
+        if !applicable {
+            return Ok(None);
+        }
+
+        if !updates.is_empty() {
+            self.write(RecordRef::Batch(updates.records.as_slice()))?;
+        }
+
+        let mut outcome = WriteOutcome::default();
+
+        for record in updates.records {
+            self.apply_record(record, &mut outcome);
+        }
+
+        Ok(Some(outcome))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Entry,
+        commit::Payload,
+        crypto::{Certificate, Identify},
+        prepare::ReductionStatement,
+        view::test::InstallGenerator,
+    };
+
+    use zebra::vector::Vector;
+
+    // Validity is irrelevant to `Database`: `batch` is witnessed by a single signer
+    fn batch(generator: &InstallGenerator, entries: &[(Id, u64)]) -> CommitBatch {
+        let view = generator.view(4);
+
+        let payloads = entries
+            .iter()
+            .map(|(id, height)| {
+                Payload::new(
+                    Entry {
+                        id: *id,
+                        height: *height,
+                    },
+                    Operation::withdraw(0, 0, 0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let payloads = Vector::new(payloads).unwrap();
+
+        let keychain = &generator.keychains[0];
+        let signature = keychain
+            .multisign(&ReductionStatement::new(payloads.root()))
+            .unwrap();
+
+        let witness = Certificate::aggregate(&view, [(keychain.keycard().identity(), signature)]);
+
+        CommitBatch::new(view.identifier(), payloads, witness)
+    }
+
+    #[test]
+    fn all_or_nothing() {
+        let generator = InstallGenerator::new(4);
+        let mut database = Database::new();
+
+        let mut updates = WriteBatch::new();
+        updates.apply_commit_batch(batch(&generator, &[(1, 1), (2, 1)]), vec![vec![]; 2]);
+
+        assert!(database.apply(updates).unwrap().is_some());
+        assert_eq!(database.commit.batches.len(), 1);
+
+        // `Id` 2 cannot skip height 2: the first (applicable) batch must not be applied either
+
+        let mut updates = WriteBatch::new();
+        updates.apply_commit_batch(batch(&generator, &[(1, 2)]), vec![vec![]]);
+        updates.apply_commit_batch(batch(&generator, &[(2, 3)]), vec![vec![]]);
+
+        assert!(database.apply(updates).unwrap().is_none());
+        assert_eq!(database.commit.batches.len(), 1);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     crypto::Identify,
     discovery::{ClientSettings, Mode, Request, Response},
+    processing::Namespace,
     view::{Install, Transition, View},
 };
 
@@ -8,7 +9,7 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use std::{
     borrow::BorrowMut,
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     io,
     sync::{Arc, Mutex as StdMutex},
     time::Duration,
@@ -44,6 +45,11 @@ pub(crate) struct Client {
 
 struct Database {
     views: HashMap<Hash, View>,
+    // Identifier of the installed view at each height (indexing `views`)
+    heights: BTreeMap<usize, Hash>,
+    // Views announced in install tails, above the top height (once the top
+    // reaches a tail view's height, that view is either installed or superseded)
+    tails: BTreeMap<usize, View>,
     installs: HashMap<Hash, Install>,
 }
 
@@ -115,15 +121,24 @@ impl Client {
     {
         let top = genesis.height();
 
+        let mut heights = BTreeMap::new();
+        heights.insert(genesis.height(), genesis.identifier());
+
         let mut views = HashMap::new();
         views.insert(genesis.identifier(), genesis);
 
+        let tails = BTreeMap::new();
         let installs = HashMap::new();
 
         let family = Family::new();
         let discovered = Lender::new(family.empty_collection());
 
-        let database = Arc::new(StdMutex::new(Database { views, installs }));
+        let database = Arc::new(StdMutex::new(Database {
+            views,
+            heights,
+            tails,
+            installs,
+        }));
 
         let sync = Sync { top, discovered };
 
@@ -153,10 +168,33 @@ impl Client {
         }
     }
 
+    pub(crate) fn namespace(&self) -> &Namespace {
+        &self.settings.namespace
+    }
+
     pub(crate) fn view(&self, identifier: &Hash) -> Option<View> {
         self.database.lock().unwrap().views.get(identifier).cloned()
     }
 
+    // Returns the view installed at `height` or, failing that, the view
+    // announced at `height` by the tail of some install
+    pub(crate) fn view_at(&self, height: usize) -> Option<View> {
+        let database = self.database.lock().unwrap();
+
+        match database.heights.get(&height) {
+            Some(identifier) => database.views.get(identifier).cloned(),
+            None => database.tails.get(&height).cloned(),
+        }
+    }
+
+    // Returns the height of the highest view installed so far
+    pub(crate) fn top(&self) -> usize {
+        let database = self.database.lock().unwrap();
+
+        // This cannot fail: `heights` always contains `genesis`
+        *database.heights.keys().next_back().unwrap()
+    }
+
     pub(crate) fn install(&self, hash: &Hash) -> Option<Install> {
         self.database.lock().unwrap().installs.get(hash).cloned()
     }
@@ -421,6 +459,23 @@ impl Client {
                     transition.destination().clone(),
                 );
 
+                database.heights.insert(
+                    transition.destination().height(),
+                    transition.destination().identifier(),
+                );
+
+                // Views in `transition`'s tail are indexed by height only if above the
+                // top and no installed view is known at that height (installed views
+                // take precedence)
+                for view in transition.tail() {
+                    if view.height() > sync.top && !database.heights.contains_key(&view.height()) {
+                        database
+                            .tails
+                            .entry(view.height())
+                            .or_insert_with(|| view.clone());
+                    }
+                }
+
                 let identifier = install.identifier();
                 database.installs.insert(identifier, install);
 
@@ -435,6 +490,9 @@ impl Client {
                 if transition.destination().height() > sync.top {
                     sync.top = transition.destination().height();
 
+                    // Tail views at or below `sync.top` are installed or superseded
+                    database.tails = database.tails.split_off(&(sync.top + 1));
+
                     // This fails only if the corresponding `transition_outlet` is dropped,
                     // in which case the whole `Client` is being dropped, and losing
                     // `transition` is irrelevant.
@@ -1,4 +1,4 @@
-use crate::discovery::Mode;
+use crate::{discovery::Mode, processing::Namespace};
 
 use std::{sync::Arc, time::Duration};
 
@@ -7,6 +7,9 @@ use talk::time::{sleep_schedules::CappedExponential, SleepSchedule};
 #[derive(Debug, Clone)]
 pub(crate) struct ClientSettings {
     pub mode: Mode,
+    // `Namespace` in which certificates are verified against the views
+    // discovered by the `Client`
+    pub namespace: Namespace,
     pub keepalive_interval: Duration,
     pub retry_schedule: Arc<dyn SleepSchedule>,
 }
@@ -15,6 +18,7 @@ impl Default for ClientSettings {
     fn default() -> Self {
         ClientSettings {
             mode: Mode::Full,
+            namespace: Namespace::default(),
             keepalive_interval: Duration::from_secs(10),
             retry_schedule: Arc::new(CappedExponential::new(
                 Duration::from_secs(5),
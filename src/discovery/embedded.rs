@@ -0,0 +1,74 @@
+use crate::{
+    discovery::{Client, ClientSettings, Server, ServerError, ServerSettings},
+    view::View,
+};
+
+use doomstack::Top;
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+// An `Embedded` discovery server runs within the local process, listening on an
+// ephemeral loopback port. `Frame`s are served by a regular `Server`, and `Client`s
+// connect to it over TCP: single-process deployments (e.g., local clusters and
+// integration tests) exercise the same code paths as a standalone deployment,
+// without depending on an external discovery server.
+pub(crate) struct Embedded {
+    genesis: View,
+    server: Server,
+}
+
+impl Embedded {
+    pub async fn new(genesis: View, settings: ServerSettings) -> Result<Self, Top<ServerError>> {
+        let server = Server::new(genesis.clone(), (Ipv4Addr::LOCALHOST, 0), settings).await?;
+        Ok(Embedded { genesis, server })
+    }
+
+    pub fn genesis(&self) -> &View {
+        &self.genesis
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.server.address()
+    }
+
+    pub fn client(&self, settings: ClientSettings) -> Client {
+        Client::new(self.genesis.clone(), self.server.address(), settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{discovery::Mode, view::test::InstallGenerator};
+
+    #[tokio::test]
+    async fn clients() {
+        let generator = InstallGenerator::new(8);
+        let genesis = generator.view(4);
+
+        let embedded = Embedded::new(genesis.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let alice = embedded.client(ClientSettings {
+            mode: Mode::Full,
+            ..Default::default()
+        });
+
+        let bob = embedded.client(ClientSettings {
+            mode: Mode::Full,
+            ..Default::default()
+        });
+
+        // `Install`s published by a `Client` are discovered by all `Client`s
+
+        let install = generator.install(4, 5, []);
+        alice.publish(install).await;
+
+        for client in [alice, bob] {
+            let transition = client.beyond(4).await;
+            assert_eq!(transition.destination().height(), 5);
+        }
+    }
+}
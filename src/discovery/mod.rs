@@ -1,5 +1,6 @@
 mod client;
 mod client_settings;
+mod embedded;
 mod frame;
 mod mode;
 mod request;
@@ -18,9 +19,13 @@ use response::Response;
 pub(crate) use client::Client;
 
 pub(crate) use client_settings::ClientSettings;
+
+#[allow(unused_imports)]
+pub(crate) use embedded::Embedded;
+
 pub(crate) use mode::Mode;
 
 #[allow(unused_imports)]
-pub(crate) use server::Server;
+pub(crate) use server::{Server, ServerError};
 
 pub(crate) use server_settings::ServerSettings;
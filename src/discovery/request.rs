@@ -1,8 +1,9 @@
-use crate::view::Install;
+use crate::{data::enveloped, view::Install};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 #[repr(u8)]
 pub(in crate::discovery) enum Request {
     Publish(Install),
@@ -10,3 +11,5 @@ pub(in crate::discovery) enum Request {
     FullSubscribe,
     KeepAlive,
 }
+
+enveloped!(Request);
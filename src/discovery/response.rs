@@ -1,11 +1,14 @@
-use crate::view::Install;
+use crate::{data::enveloped, view::Install};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 #[repr(u8)]
 pub(in crate::discovery) enum Response {
     Update(Vec<Install>),
     AcknowledgePublish,
     KeepAlive,
 }
+
+enveloped!(Response);
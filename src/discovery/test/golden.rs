@@ -0,0 +1,42 @@
+use crate::{
+    data::golden,
+    discovery::{Request, Response},
+    view::test::InstallGenerator,
+};
+
+#[test]
+fn request() {
+    golden::check(
+        "discovery_request_light_subscribe",
+        &Request::LightSubscribe(42),
+    );
+    golden::check("discovery_request_full_subscribe", &Request::FullSubscribe);
+    golden::check("discovery_request_keep_alive", &Request::KeepAlive);
+
+    let generator = InstallGenerator::from_keychains(golden::keychains());
+
+    golden::check(
+        "discovery_request_publish",
+        &Request::Publish(generator.install(3, 4, [])),
+    );
+}
+
+#[test]
+fn response() {
+    golden::check("discovery_response_update", &Response::Update(vec![]));
+    golden::check(
+        "discovery_response_acknowledge_publish",
+        &Response::AcknowledgePublish,
+    );
+    golden::check("discovery_response_keep_alive", &Response::KeepAlive);
+
+    let generator = InstallGenerator::from_keychains(golden::keychains());
+
+    golden::check(
+        "discovery_response_update_installs",
+        &Response::Update(vec![
+            generator.install(2, 3, []),
+            generator.install(3, 4, []),
+        ]),
+    );
+}
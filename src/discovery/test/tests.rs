@@ -61,6 +61,57 @@ async fn light_single_publish_then_beyond() {
     }
 }
 
+#[tokio::test]
+async fn light_single_view_at() {
+    let (generator, _server, _proxy, client) = setup_single(32, 8, Mode::Light).await;
+
+    let install = generator.install(8, 10, [12]);
+    client.publish(install).await;
+
+    client.beyond(8).await;
+
+    for height in [8, 10, 12] {
+        assert_eq!(
+            client.view_at(height).unwrap().identifier(),
+            generator.view(height).identifier()
+        );
+    }
+
+    for height in [7, 9, 11, 13] {
+        assert!(client.view_at(height).is_none());
+    }
+
+    let install = generator.install(10, 12, []);
+    client.publish(install).await;
+
+    client.beyond(10).await;
+
+    assert_eq!(
+        client.view_at(12).unwrap().identifier(),
+        generator.view(12).identifier()
+    );
+
+    let install = generator.install(12, 14, [16]);
+    client.publish(install).await;
+
+    client.beyond(12).await;
+
+    assert!(client.view_at(16).is_some());
+
+    // Height 16 is skipped: its tail view is superseded, and no longer indexed
+    let install = generator.install(14, 18, []);
+    client.publish(install).await;
+
+    client.beyond(14).await;
+
+    assert!(client.view_at(16).is_none());
+
+    assert_eq!(
+        client.view_at(18).unwrap().identifier(),
+        generator.view(18).identifier()
+    );
+}
+
 #[tokio::test]
 async fn light_single_adjacent_publishes_then_beyond() {
     let (generator, _server, _proxy, client) = setup_single(32, 8, Mode::Light).await;
@@ -0,0 +1,365 @@
+use crate::{
+    brokers::{
+        commit::Broker as CommitBroker, prepare::Broker as PrepareBroker,
+        signup::Broker as SignupBroker,
+    },
+    discovery::Client,
+    handles::{DrainError, Failure, Lifecycle, ReadinessError},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use talk::net::{traits::TcpConnect, Connector};
+
+use tokio::{net::ToSocketAddrs, sync::broadcast::Receiver};
+
+// A `BrokerHandle` owns a running signup, prepare or commit broker. Dropping a
+// `BrokerHandle` stops the broker (as `shutdown` does) without reporting `Failure`s.
+pub struct BrokerHandle {
+    broker: Option<Broker>,
+    lifecycle: Lifecycle,
+}
+
+pub(crate) enum Broker {
+    Signup(SignupBroker),
+    Prepare(PrepareBroker),
+    Commit(CommitBroker),
+}
+
+#[derive(Doom)]
+pub enum BrokerHandleError {
+    #[doom(description("Failed to start broker"))]
+    StartFailed,
+}
+
+impl BrokerHandle {
+    // Starts a signup broker for `view` (with default settings), serving clients on `address`
+    pub async fn start_signup<A, C>(
+        view: View,
+        address: A,
+        connector: C,
+    ) -> Result<Self, Top<BrokerHandleError>>
+    where
+        A: ToSocketAddrs,
+        C: Connector,
+    {
+        let broker = SignupBroker::new(view, address, connector, Default::default())
+            .await
+            .pot(BrokerHandleError::StartFailed, here!())?;
+
+        Ok(BrokerHandle::signup(broker))
+    }
+
+    // Starts a prepare broker for `view` (with default settings), serving clients on
+    // `address`. `discovery` is the address of a discovery server aware of `view`
+    pub async fn start_prepare<T, A, C>(
+        view: View,
+        discovery: T,
+        address: A,
+        connector: C,
+    ) -> Result<Self, Top<BrokerHandleError>>
+    where
+        T: 'static + Clone + TcpConnect,
+        A: ToSocketAddrs,
+        C: Connector,
+    {
+        let discovery = Arc::new(Client::new(view.clone(), discovery, Default::default()));
+
+        let broker = PrepareBroker::new(discovery, view, address, connector, Default::default())
+            .await
+            .pot(BrokerHandleError::StartFailed, here!())?;
+
+        Ok(BrokerHandle::prepare(broker))
+    }
+
+    // Starts a commit broker for `view` (with default settings), serving clients on
+    // `address`. `discovery` is the address of a discovery server aware of `view`
+    pub async fn start_commit<T, A, C>(
+        view: View,
+        discovery: T,
+        address: A,
+        connector: C,
+    ) -> Result<Self, Top<BrokerHandleError>>
+    where
+        T: 'static + Clone + TcpConnect,
+        A: ToSocketAddrs,
+        C: Connector,
+    {
+        let discovery = Arc::new(Client::new(view.clone(), discovery, Default::default()));
+
+        let broker = CommitBroker::new(discovery, view, address, connector, Default::default())
+            .await
+            .pot(BrokerHandleError::StartFailed, here!())?;
+
+        Ok(BrokerHandle::commit(broker))
+    }
+
+    pub(crate) fn signup(broker: SignupBroker) -> Self {
+        let lifecycle = broker.lifecycle().clone();
+        BrokerHandle::new(Broker::Signup(broker), lifecycle)
+    }
+
+    pub(crate) fn prepare(broker: PrepareBroker) -> Self {
+        let lifecycle = broker.lifecycle().clone();
+        BrokerHandle::new(Broker::Prepare(broker), lifecycle)
+    }
+
+    pub(crate) fn commit(broker: CommitBroker) -> Self {
+        let lifecycle = broker.lifecycle().clone();
+        BrokerHandle::new(Broker::Commit(broker), lifecycle)
+    }
+
+    fn new(broker: Broker, lifecycle: Lifecycle) -> Self {
+        BrokerHandle {
+            broker: Some(broker),
+            lifecycle,
+        }
+    }
+
+    pub(crate) fn broker(&self) -> &Broker {
+        // `self.broker` is taken only by `shutdown`, which consumes `self`
+        self.broker.as_ref().unwrap()
+    }
+
+    // Address on which the broker serves clients
+    pub fn address(&self) -> SocketAddr {
+        match self.broker() {
+            Broker::Signup(broker) => broker.address(),
+            Broker::Prepare(broker) => broker.address(),
+            Broker::Commit(broker) => broker.address(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.lifecycle.is_ready()
+    }
+
+    pub async fn await_ready(&self) {
+        self.lifecycle.await_ready().await
+    }
+
+    pub async fn await_ready_within(&self, timeout: Duration) -> Result<(), Top<ReadinessError>> {
+        self.lifecycle.await_ready_within(timeout).await
+    }
+
+    // A prepare or commit broker is degraded while fewer than a quorum of replicas
+    // are responsive: its brokerages fail until connectivity returns
+    pub fn is_degraded(&self) -> bool {
+        match self.broker() {
+            Broker::Signup(_) => false,
+            Broker::Prepare(broker) => broker.quorum_monitor().is_degraded(),
+            Broker::Commit(broker) => broker.quorum_monitor().is_degraded(),
+        }
+    }
+
+    // Number of times the broker was degraded
+    pub fn degradations(&self) -> u64 {
+        match self.broker() {
+            Broker::Signup(_) => 0,
+            Broker::Prepare(broker) => broker.quorum_monitor().episodes(),
+            Broker::Commit(broker) => broker.quorum_monitor().episodes(),
+        }
+    }
+
+    // A prepare or commit broker sheds load while the requests it holds exceed
+    // its memory watermarks: it stops accepting connections, and fails new
+    // requests with `BrokerFailure::Busy`
+    pub fn is_shedding(&self) -> bool {
+        match self.broker() {
+            Broker::Signup(_) => false,
+            Broker::Prepare(broker) => broker.memory_gauge().is_shedding(),
+            Broker::Commit(broker) => broker.memory_gauge().is_shedding(),
+        }
+    }
+
+    // Estimated memory footprint (in bytes) of the requests held by the broker
+    pub fn memory_usage(&self) -> usize {
+        match self.broker() {
+            Broker::Signup(_) => 0,
+            Broker::Prepare(broker) => broker.memory_gauge().usage(),
+            Broker::Commit(broker) => broker.memory_gauge().usage(),
+        }
+    }
+
+    // Notifies the failure of any of the broker's long-running tasks
+    pub fn failures(&self) -> Receiver<Failure> {
+        self.lifecycle.failures()
+    }
+
+    // Stops the broker, after writing its run report (see `MetricsSettings`)
+    pub fn shutdown(mut self) {
+        self.lifecycle.shut_down();
+
+        let report = match self.broker() {
+            Broker::Signup(_) => Ok(()),
+            Broker::Prepare(broker) => broker.metrics().report(),
+            Broker::Commit(broker) => broker.metrics().report(),
+        };
+
+        if report.is_err() {
+            log::warn!("Failed to write metrics report");
+        }
+
+        self.broker = None;
+    }
+
+    // Stops accepting clients, flushes pending brokerages and lets in-flight
+    // brokerages complete (for at most `timeout`), then shuts down. Fails (still
+    // shutting down) if some client was still being served after `timeout`.
+    pub async fn shutdown_gracefully(self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        let drained = match self.broker() {
+            Broker::Signup(broker) => broker.drain(timeout).await,
+            Broker::Prepare(broker) => broker.drain(timeout).await,
+            Broker::Commit(broker) => broker.drain(timeout).await,
+        };
+
+        self.shutdown();
+        drained
+    }
+}
+
+impl Drop for BrokerHandle {
+    fn drop(&mut self) {
+        self.lifecycle.shut_down();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        account::{Entry, Operation},
+        client::{BrokerAddresses, Client},
+        commit::Payload,
+        discovery::Embedded,
+        handles::{BrokerHandle, ProcessorHandle},
+        view::test::InstallGenerator,
+    };
+
+    use std::{iter, net::Ipv4Addr, time::Duration};
+
+    use talk::{crypto::KeyChain, net::test::System as NetSystem};
+
+    const READINESS_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[tokio::test]
+    async fn start() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let discovery_server = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = discovery_server.address();
+
+        let broker_keychains = iter::repeat_with(KeyChain::random)
+            .take(3)
+            .collect::<Vec<_>>();
+
+        let NetSystem {
+            mut connectors,
+            mut listeners,
+            ..
+        } = NetSystem::setup_with_keychains(
+            generator
+                .keychains
+                .iter()
+                .cloned()
+                .chain(broker_keychains.iter().cloned()),
+        )
+        .await;
+
+        // Handles are all an application needs to run replicas and brokers
+
+        let processors = generator
+            .keychains
+            .iter()
+            .cloned()
+            .map(|keychain| {
+                ProcessorHandle::start(
+                    keychain,
+                    view.clone(),
+                    discovery,
+                    connectors.remove(0),
+                    listeners.remove(0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for processor in processors.iter() {
+            processor
+                .await_ready_within(READINESS_TIMEOUT)
+                .await
+                .unwrap();
+        }
+
+        let signup = BrokerHandle::start_signup(
+            view.clone(),
+            (Ipv4Addr::LOCALHOST, 0),
+            connectors.remove(0),
+        )
+        .await
+        .unwrap();
+
+        let prepare = BrokerHandle::start_prepare(
+            view.clone(),
+            discovery,
+            (Ipv4Addr::LOCALHOST, 0),
+            connectors.remove(0),
+        )
+        .await
+        .unwrap();
+
+        let commit = BrokerHandle::start_commit(
+            view.clone(),
+            discovery,
+            (Ipv4Addr::LOCALHOST, 0),
+            connectors.remove(0),
+        )
+        .await
+        .unwrap();
+
+        for broker in [&signup, &prepare, &commit] {
+            broker.await_ready_within(READINESS_TIMEOUT).await.unwrap();
+        }
+
+        let brokers = BrokerAddresses {
+            signup: signup.address(),
+            prepare: prepare.address(),
+            prepare_standby: None,
+            commit: commit.address(),
+        };
+
+        let mut client = Client::new(
+            KeyChain::random(),
+            view,
+            discovery,
+            brokers,
+            Default::default(),
+        );
+
+        let id = client.signup().await.unwrap();
+
+        let payload = Payload::new(Entry { id, height: 1 }, Operation::withdraw(id, 0, 0));
+
+        let prepared = client.prepare(payload).await.unwrap();
+        client.commit(prepared, Vec::new()).await.unwrap();
+
+        for broker in [signup, prepare, commit] {
+            broker
+                .shutdown_gracefully(Duration::from_secs(1))
+                .await
+                .unwrap();
+        }
+
+        for processor in processors {
+            processor
+                .shutdown_gracefully(Duration::from_secs(1))
+                .await
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use crate::telemetry::Badge;
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{broadcast, watch, Notify},
+    time,
+};
+
+const FAILURES_CAPACITY: usize = 64;
+
+// A `Lifecycle` tracks whether a subsystem is ready to serve, and notifies the
+// failures of its long-running tasks. A task fails if it returns or panics before
+// its subsystem is shut down. A subsystem is shut down gracefully by first draining
+// it: its sessions are `engage`d, and `drain` refuses new sessions while waiting
+// for engaged ones to complete. All clones of a `Lifecycle` share the same state.
+#[derive(Clone)]
+pub(crate) struct Lifecycle {
+    inner: Arc<Inner>,
+    badge: Option<Badge>,
+}
+
+struct Inner {
+    ready_inlet: watch::Sender<bool>,
+    ready_outlet: watch::Receiver<bool>,
+    failure_inlet: broadcast::Sender<Failure>,
+    unavailable: AtomicBool,
+    shutting_down: AtomicBool,
+    draining: AtomicBool,
+    engaged: AtomicUsize,
+    settled: Notify,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    task: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    // Not ready yet
+    Starting,
+    Serving,
+    // Can no longer serve (e.g., its state was lost) until restarted
+    Unavailable,
+}
+
+#[derive(Doom)]
+pub enum ReadinessError {
+    #[doom(description("Timed out waiting for readiness"))]
+    Timeout,
+}
+
+#[derive(Doom)]
+pub enum DrainError {
+    #[doom(description("Timed out waiting for in-flight sessions"))]
+    Timeout,
+}
+
+// Keeps a session engaged (see `Lifecycle::engage`) until dropped
+pub(crate) struct Engagement {
+    lifecycle: Lifecycle,
+}
+
+// Reports a `Failure` for `task` when dropped, unless its subsystem is shutting down
+struct Sentinel {
+    lifecycle: Lifecycle,
+    task: &'static str,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        let (ready_inlet, ready_outlet) = watch::channel(false);
+        let (failure_inlet, _) = broadcast::channel(FAILURES_CAPACITY);
+
+        Lifecycle {
+            inner: Arc::new(Inner {
+                ready_inlet,
+                ready_outlet,
+                failure_inlet,
+                unavailable: AtomicBool::new(false),
+                shutting_down: AtomicBool::new(false),
+                draining: AtomicBool::new(false),
+                engaged: AtomicUsize::new(0),
+                settled: Notify::new(),
+            }),
+            badge: None,
+        }
+    }
+
+    // Tasks guarded by `self` log under `badge`
+    pub fn with_badge(mut self, badge: Badge) -> Self {
+        self.badge = Some(badge);
+        self
+    }
+
+    pub fn set_ready(&self) {
+        // This cannot fail: `self.inner` holds a receiver
+        let _ = self.inner.ready_inlet.send(true);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self.inner.ready_outlet.borrow()
+    }
+
+    pub async fn await_ready(&self) {
+        let mut ready_outlet = self.inner.ready_outlet.clone();
+
+        loop {
+            let ready = *ready_outlet.borrow();
+
+            if ready {
+                return;
+            }
+
+            // This cannot fail: `self.inner` holds the sender
+            let _ = ready_outlet.changed().await;
+        }
+    }
+
+    // Waits for readiness, for at most `timeout` (dependents should not
+    // assume readiness after a fixed delay)
+    pub async fn await_ready_within(&self, timeout: Duration) -> Result<(), Top<ReadinessError>> {
+        time::timeout(timeout, self.await_ready())
+            .await
+            .pot(ReadinessError::Timeout, here!())
+    }
+
+    // Wraps `gate`, so that `self` becomes ready as soon as `gate` completes
+    pub fn gate<F>(&self, gate: F) -> impl Future<Output = ()>
+    where
+        F: Future<Output = ()>,
+    {
+        let lifecycle = self.clone();
+
+        async move {
+            gate.await;
+            lifecycle.set_ready();
+        }
+    }
+
+    // Once unavailable, a subsystem remains unavailable
+    pub fn set_unavailable(&self) {
+        self.inner.unavailable.store(true, Ordering::Relaxed);
+    }
+
+    pub fn health(&self) -> Health {
+        if self.inner.unavailable.load(Ordering::Relaxed) {
+            Health::Unavailable
+        } else if self.is_ready() {
+            Health::Serving
+        } else {
+            Health::Starting
+        }
+    }
+
+    pub fn failures(&self) -> broadcast::Receiver<Failure> {
+        self.inner.failure_inlet.subscribe()
+    }
+
+    // Tasks of a subsystem that is shutting down are expected to stop
+    pub fn shut_down(&self) {
+        self.inner.shutting_down.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.shutting_down.load(Ordering::Relaxed)
+    }
+
+    // Engages a new session, unless `self` is draining (in which case the
+    // session should be dropped without being served)
+    pub fn engage(&self) -> Option<Engagement> {
+        // `engaged` is incremented before `draining` is checked, so that
+        // `drain` never observes zero sessions while one is being engaged
+        self.inner.engaged.fetch_add(1, Ordering::SeqCst);
+
+        let engagement = Engagement {
+            lifecycle: self.clone(),
+        };
+
+        if self.is_draining() {
+            None
+        } else {
+            Some(engagement)
+        }
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    // Refuses new sessions, then waits (for at most `timeout`) for
+    // all engaged sessions to complete
+    pub async fn drain(&self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        self.inner.draining.store(true, Ordering::SeqCst);
+
+        let settled = async {
+            loop {
+                // `notified` is created before `engaged` is checked,
+                // so that no `Engagement` can be dropped unnoticed
+                let notified = self.inner.settled.notified();
+
+                if self.inner.engaged.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+
+                notified.await;
+            }
+        };
+
+        time::timeout(timeout, settled)
+            .await
+            .pot(DrainError::Timeout, here!())
+    }
+
+    // Wraps the (never-ending) `future` of `task`, so that its termination is reported
+    pub fn guard<F>(&self, task: &'static str, future: F) -> impl Future<Output = F::Output>
+    where
+        F: Future,
+    {
+        let sentinel = Sentinel {
+            lifecycle: self.clone(),
+            task,
+        };
+
+        let badge = self.badge.clone().or_else(Badge::current);
+
+        Badge::scope(badge, async move {
+            let _sentinel = sentinel;
+            future.await
+        })
+    }
+}
+
+impl Failure {
+    // Name of the task that failed
+    pub fn task(&self) -> &'static str {
+        self.task
+    }
+}
+
+impl Drop for Engagement {
+    fn drop(&mut self) {
+        let inner = &self.lifecycle.inner;
+
+        if inner.engaged.fetch_sub(1, Ordering::SeqCst) == 1 {
+            inner.settled.notify_waiters();
+        }
+    }
+}
+
+impl Drop for Sentinel {
+    fn drop(&mut self) {
+        if !self.lifecycle.is_shutting_down() {
+            log::error!("Task `{}` failed", self.task);
+
+            // Failures are dropped if nobody subscribed
+            let _ = self
+                .lifecycle
+                .inner
+                .failure_inlet
+                .send(Failure { task: self.task });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::task;
+
+    #[tokio::test]
+    async fn ready() {
+        let lifecycle = Lifecycle::new();
+        assert!(!lifecycle.is_ready());
+
+        {
+            let lifecycle = lifecycle.clone();
+
+            task::spawn(async move {
+                time::sleep(Duration::from_millis(10)).await;
+                lifecycle.set_ready();
+            });
+        }
+
+        lifecycle
+            .await_ready_within(Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        assert!(lifecycle.is_ready());
+    }
+
+    #[tokio::test]
+    async fn gate() {
+        let lifecycle = Lifecycle::new();
+        let (gate_inlet, gate_outlet) = tokio::sync::oneshot::channel::<()>();
+
+        task::spawn(lifecycle.gate(async move {
+            let _ = gate_outlet.await;
+        }));
+
+        assert!(lifecycle
+            .await_ready_within(Duration::from_millis(50))
+            .await
+            .is_err());
+
+        gate_inlet.send(()).unwrap();
+
+        lifecycle
+            .await_ready_within(Duration::from_secs(1))
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn health() {
+        let lifecycle = Lifecycle::new();
+        assert_eq!(lifecycle.health(), Health::Starting);
+
+        lifecycle.set_ready();
+        assert_eq!(lifecycle.health(), Health::Serving);
+
+        lifecycle.set_unavailable();
+        assert_eq!(lifecycle.health(), Health::Unavailable);
+        assert!(lifecycle.is_ready());
+    }
+
+    #[tokio::test]
+    async fn failures() {
+        let lifecycle = Lifecycle::new();
+        let mut failures = lifecycle.failures();
+
+        let _ = task::spawn(lifecycle.guard("panicking", async { panic!() })).await;
+        assert_eq!(failures.recv().await.unwrap().task(), "panicking");
+
+        lifecycle.guard("returning", async {}).await;
+        assert_eq!(failures.recv().await.unwrap().task(), "returning");
+
+        // Tasks stopped by a shutdown do not fail
+        lifecycle.shut_down();
+        lifecycle.guard("stopped", async {}).await;
+
+        assert!(failures.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn drain() {
+        let lifecycle = Lifecycle::new();
+
+        let engagement = lifecycle.engage().unwrap();
+
+        task::spawn(async move {
+            time::sleep(Duration::from_millis(50)).await;
+            drop(engagement);
+        });
+
+        assert!(lifecycle.drain(Duration::from_millis(10)).await.is_err());
+
+        // Draining refuses new sessions
+        assert!(lifecycle.engage().is_none());
+
+        lifecycle.drain(Duration::from_secs(1)).await.unwrap();
+    }
+}
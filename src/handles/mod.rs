@@ -0,0 +1,9 @@
+mod broker_handle;
+mod lifecycle;
+mod processor_handle;
+
+pub use broker_handle::{BrokerHandle, BrokerHandleError};
+pub use lifecycle::{DrainError, Failure, Health, ReadinessError};
+pub use processor_handle::{ProcessorHandle, ProcessorHandleError};
+
+pub(crate) use lifecycle::Lifecycle;
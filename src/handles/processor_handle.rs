@@ -0,0 +1,164 @@
+use crate::{
+    database::Database,
+    discovery::Client,
+    handles::{DrainError, Failure, Health, Lifecycle, ReadinessError},
+    processing::{processor_settings::Persistence, Processor, ProcessorSettings},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use talk::{
+    crypto::KeyChain,
+    net::{traits::TcpConnect, Connector, Listener},
+};
+
+use tokio::sync::broadcast::Receiver;
+
+// A `ProcessorHandle` owns a running `Processor`. Dropping a `ProcessorHandle`
+// stops the `Processor` (as `shutdown` does) without reporting `Failure`s.
+pub struct ProcessorHandle {
+    processor: Option<Processor>,
+    lifecycle: Lifecycle,
+}
+
+#[derive(Doom)]
+pub enum ProcessorHandleError {
+    #[doom(description("Failed to recover the persisted `Database`"))]
+    DatabaseUnrecoverable,
+}
+
+impl ProcessorHandle {
+    // Starts a replica of `view` (with default settings, from an empty `Database`).
+    // `discovery` is the address of a discovery server aware of `view`
+    pub fn start<T, C, L>(
+        keychain: KeyChain,
+        view: View,
+        discovery: T,
+        connector: C,
+        listener: L,
+    ) -> Self
+    where
+        T: 'static + Clone + TcpConnect,
+        C: Connector,
+        L: Listener,
+    {
+        let discovery = Arc::new(Client::new(view.clone(), discovery, Default::default()));
+
+        let processor = Processor::new(
+            keychain,
+            discovery,
+            view,
+            Database::new(),
+            connector,
+            listener,
+            Default::default(),
+        );
+
+        ProcessorHandle::new(processor)
+    }
+
+    // Starts a replica of `view` (with default settings) whose `Database` is persisted in
+    // `directory`, recovering the state left behind by a previous run in `directory`
+    pub fn open<T, C, L, P>(
+        keychain: KeyChain,
+        view: View,
+        discovery: T,
+        connector: C,
+        listener: L,
+        directory: P,
+    ) -> Result<Self, Top<ProcessorHandleError>>
+    where
+        T: 'static + Clone + TcpConnect,
+        C: Connector,
+        L: Listener,
+        P: AsRef<Path>,
+    {
+        let discovery = Arc::new(Client::new(view.clone(), discovery, Default::default()));
+
+        let settings = ProcessorSettings {
+            persistence: Persistence {
+                directory: Some(directory.as_ref().to_path_buf()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let processor = Processor::open(keychain, discovery, view, connector, listener, settings)
+            .pot(ProcessorHandleError::DatabaseUnrecoverable, here!())?;
+
+        Ok(ProcessorHandle::new(processor))
+    }
+
+    pub(crate) fn new(processor: Processor) -> Self {
+        let lifecycle = processor.lifecycle().clone();
+
+        ProcessorHandle {
+            processor: Some(processor),
+            lifecycle,
+        }
+    }
+
+    pub(crate) fn processor(&self) -> &Processor {
+        // `self.processor` is taken only by `shutdown`, which consumes `self`
+        self.processor.as_ref().unwrap()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.lifecycle.is_ready()
+    }
+
+    pub async fn await_ready(&self) {
+        self.lifecycle.await_ready().await
+    }
+
+    pub async fn await_ready_within(&self, timeout: Duration) -> Result<(), Top<ReadinessError>> {
+        self.lifecycle.await_ready_within(timeout).await
+    }
+
+    // `Health::Unavailable` if the `Processor` lost its `Database` (e.g., a serving
+    // task panicked while holding its lock): the `Processor` then serves no further
+    // requests, and must be restarted from a recovered `Database`
+    pub fn health(&self) -> Health {
+        self.lifecycle.health()
+    }
+
+    // A `Processor` sheds load while the (estimated) footprint of its `Database`
+    // exceeds its memory watermarks: new batches are rejected as `Busy`
+    pub fn is_shedding(&self) -> bool {
+        self.processor().memory_gauge().is_shedding()
+    }
+
+    // Estimated memory footprint (in bytes) of the `Processor`'s `Database`
+    pub fn memory_usage(&self) -> usize {
+        self.processor().memory_gauge().usage()
+    }
+
+    // Notifies the failure of any of the `Processor`'s serving tasks
+    pub fn failures(&self) -> Receiver<Failure> {
+        self.lifecycle.failures()
+    }
+
+    pub fn shutdown(mut self) {
+        if let Some(processor) = self.processor.take() {
+            processor.shutdown();
+        }
+    }
+
+    // Stops accepting sessions, lets in-flight sessions complete (for at most
+    // `timeout`), then shuts down. Fails (still shutting down) if some session
+    // was still in flight after `timeout`.
+    pub async fn shutdown_gracefully(self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        let drained = self.processor().drain(timeout).await;
+        self.shutdown();
+        drained
+    }
+}
+
+impl Drop for ProcessorHandle {
+    fn drop(&mut self) {
+        self.lifecycle.shut_down();
+    }
+}
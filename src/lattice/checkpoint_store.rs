@@ -0,0 +1,91 @@
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use talk::crypto::primitives::hash::Hash;
+
+// Storage for the checkpoints of `LatticeRunner`s, by instance identifier (see
+// `LatticeAgreement::resume`). A checkpoint must be durable once `store` returns:
+// it is stored before the messages that depend on it are sent.
+pub(crate) trait CheckpointStore: 'static + Send + Sync {
+    fn store(&self, instance: Hash, checkpoint: Vec<u8>) -> Result<(), Top<CheckpointStoreError>>;
+    fn load(&self, instance: Hash) -> Result<Option<Vec<u8>>, Top<CheckpointStoreError>>;
+}
+
+#[derive(Doom)]
+pub(crate) enum CheckpointStoreError {
+    #[doom(description("Failed to store checkpoint"))]
+    StoreFailed,
+    #[doom(description("Failed to load checkpoint"))]
+    LoadFailed,
+}
+
+// Keeps checkpoints in memory (e.g., to test recovery, or when replicas
+// restart agreements without restarting the process)
+#[derive(Clone, Default)]
+pub(crate) struct MemoryCheckpointStore {
+    checkpoints: Arc<Mutex<HashMap<Hash, Vec<u8>>>>,
+}
+
+// Keeps each checkpoint in a file named after its instance identifier
+pub(crate) struct DirectoryCheckpointStore {
+    directory: PathBuf,
+}
+
+impl MemoryCheckpointStore {
+    pub fn new() -> Self {
+        MemoryCheckpointStore::default()
+    }
+}
+
+impl CheckpointStore for MemoryCheckpointStore {
+    fn store(&self, instance: Hash, checkpoint: Vec<u8>) -> Result<(), Top<CheckpointStoreError>> {
+        self.checkpoints
+            .lock()
+            .unwrap()
+            .insert(instance, checkpoint);
+
+        Ok(())
+    }
+
+    fn load(&self, instance: Hash) -> Result<Option<Vec<u8>>, Top<CheckpointStoreError>> {
+        Ok(self.checkpoints.lock().unwrap().get(&instance).cloned())
+    }
+}
+
+impl DirectoryCheckpointStore {
+    pub fn new(directory: PathBuf) -> Self {
+        DirectoryCheckpointStore { directory }
+    }
+
+    fn path(&self, instance: Hash) -> PathBuf {
+        self.directory.join(format!("{:?}.checkpoint", instance))
+    }
+}
+
+impl CheckpointStore for DirectoryCheckpointStore {
+    fn store(&self, instance: Hash, checkpoint: Vec<u8>) -> Result<(), Top<CheckpointStoreError>> {
+        let path = self.path(instance);
+
+        // Write to a temporary file, then rename: a crash mid-write
+        // must not leave a truncated checkpoint behind
+        let temporary = path.with_extension("tmp");
+
+        fs::write(&temporary, checkpoint).pot(CheckpointStoreError::StoreFailed, here!())?;
+        fs::rename(&temporary, &path).pot(CheckpointStoreError::StoreFailed, here!())
+    }
+
+    fn load(&self, instance: Hash) -> Result<Option<Vec<u8>>, Top<CheckpointStoreError>> {
+        match fs::read(self.path(instance)) {
+            Ok(checkpoint) => Ok(Some(checkpoint)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).pot(CheckpointStoreError::LoadFailed, here!()),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{
+    crypto::Identify,
+    lattice::{DisclosureStatement, Instance as LatticeInstance},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::{
+    primitives::{hash::Hash, sign::Signature},
+    Identity,
+};
+
+// Proof that `origin` disclosed two different proposals in the same lattice
+// agreement (Bracha broadcast tolerates this, but `origin` is provably Byzantine)
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct DisclosureEquivocation<Instance> {
+    origin: Identity,
+    view: Hash,
+    instance: Instance,
+    disclosures: [(Hash, Signature); 2],
+}
+
+#[derive(Doom)]
+pub(crate) enum DisclosureEquivocationError {
+    #[doom(description("The `DisclosureEquivocation` pertains to a foreign `View`"))]
+    ForeignView,
+    #[doom(description("The `DisclosureEquivocation`'s origin is foreign to the `View`"))]
+    ForeignOrigin,
+    #[doom(description("Consistent disclosures"))]
+    ConsistentDisclosures,
+    #[doom(description("Invalid signature"))]
+    InvalidSignature,
+}
+
+impl<Instance> DisclosureEquivocation<Instance>
+where
+    Instance: LatticeInstance,
+{
+    pub(in crate::lattice) fn new(
+        origin: Identity,
+        view: Hash,
+        instance: Instance,
+        first: (Hash, Signature),
+        second: (Hash, Signature),
+    ) -> Self {
+        DisclosureEquivocation {
+            origin,
+            view,
+            instance,
+            disclosures: [first, second],
+        }
+    }
+
+    pub fn origin(&self) -> Identity {
+        self.origin
+    }
+
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    pub fn validate(&self, view: &View) -> Result<(), Top<DisclosureEquivocationError>> {
+        if self.view != view.identifier() {
+            return DisclosureEquivocationError::ForeignView
+                .fail()
+                .spot(here!());
+        }
+
+        let origin = view
+            .members()
+            .get(&self.origin)
+            .ok_or(DisclosureEquivocationError::ForeignOrigin.into_top())
+            .spot(here!())?;
+
+        if self.disclosures[0].0 == self.disclosures[1].0 {
+            return DisclosureEquivocationError::ConsistentDisclosures
+                .fail()
+                .spot(here!());
+        }
+
+        for (proposal, signature) in self.disclosures.iter() {
+            let statement = DisclosureStatement {
+                view: self.view,
+                instance: self.instance.clone(),
+                proposal: *proposal,
+            };
+
+            signature
+                .verify(origin, &statement)
+                .pot(DisclosureEquivocationError::InvalidSignature, here!())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use talk::crypto::{primitives::hash, KeyChain};
+
+    fn disclosure(keychain: &KeyChain, view: &View, proposal: u32) -> (Hash, Signature) {
+        let statement = DisclosureStatement {
+            view: view.identifier(),
+            instance: 0u32,
+            proposal: hash::hash(&proposal).unwrap(),
+        };
+
+        (statement.proposal, keychain.sign(&statement).unwrap())
+    }
+
+    #[test]
+    fn validate() {
+        let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let view = View::genesis(keychains.iter().map(KeyChain::keycard));
+
+        let origin = keychains[0].keycard().identity();
+
+        let equivocation = DisclosureEquivocation::new(
+            origin,
+            view.identifier(),
+            0u32,
+            disclosure(&keychains[0], &view, 1),
+            disclosure(&keychains[0], &view, 2),
+        );
+
+        equivocation.validate(&view).unwrap();
+
+        // The same proposal, disclosed twice
+        let consistent = DisclosureEquivocation::new(
+            origin,
+            view.identifier(),
+            0u32,
+            disclosure(&keychains[0], &view, 1),
+            disclosure(&keychains[0], &view, 1),
+        );
+
+        assert!(consistent.validate(&view).is_err());
+
+        // Disclosures signed by another replica
+        let forged = DisclosureEquivocation::new(
+            origin,
+            view.identifier(),
+            0u32,
+            disclosure(&keychains[0], &view, 1),
+            disclosure(&keychains[1], &view, 2),
+        );
+
+        assert!(forged.validate(&view).is_err());
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{crypto::Header, lattice::Instance as LatticeInstance};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::{primitives::hash::Hash, Statement};
+
+// Signed by each replica upon disclosing its proposal. Two `DisclosureStatement`s
+// signed by the same replica, for the same `view` and `instance` but different
+// proposals, prove that the replica equivocated (see `DisclosureEquivocation`).
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct DisclosureStatement<Instance> {
+    pub view: Hash,
+    pub instance: Instance,
+    pub proposal: Hash,
+}
+
+impl<Instance> Statement for DisclosureStatement<Instance>
+where
+    Instance: LatticeInstance,
+{
+    type Header = Header;
+    const HEADER: Header = Header::LatticeDisclosure;
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::primitives::hash::Hash;
+
+// Wire format of a `LatticeMultiplexer`: `message` is a serialized `Message`,
+// addressed to the lattice instance whose `Instance` hashes to `instance`
+#[derive(Clone, Serialize, Deserialize)]
+pub(in crate::lattice) struct Envelope {
+    pub instance: Hash,
+    pub message: Vec<u8>,
+}
@@ -2,30 +2,34 @@ use crate::{
     crypto::Certificate,
     discovery::Client,
     lattice::{
-        Element as LatticeElement, Instance as LatticeInstance, LatticeAgreementSettings,
-        LatticeRunner, Message,
+        CheckpointStore, DisclosureEquivocation, Element as LatticeElement, Envelope,
+        Instance as LatticeInstance, LatticeAgreementSettings, LatticeMultiplexer, LatticeRunner,
+        Rejections, Statistics, StatisticsSnapshot,
     },
     view::View,
 };
 
-use doomstack::{Doom, Top};
+use doomstack::{here, Doom, ResultExt, Top};
 
 use std::sync::Arc;
 
 use talk::{
-    crypto::KeyChain,
+    crypto::{primitives::hash::Hash, Identity, KeyChain},
     net::{Connector, Listener},
     sync::fuse::Fuse,
-    unicast::{Receiver, Sender},
+    unicast::{Acknowledger, Sender},
 };
 
 use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
     oneshot,
     oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender},
 };
 
-type ProposalInlet<Element> = OneshotSender<(Element, ResultInlet)>;
-type ProposalOutlet<Element> = OneshotReceiver<(Element, ResultInlet)>;
+type ProposalInlet<Element> = UnboundedSender<(Element, ResultInlet)>;
+type ProposalOutlet<Element> = UnboundedReceiver<(Element, ResultInlet)>;
+
+type InboxOutlet = UnboundedReceiver<(Identity, Envelope, Acknowledger)>;
 
 type ResultInlet = OneshotSender<bool>;
 type ResultOutlet = OneshotReceiver<bool>;
@@ -33,10 +37,21 @@ type ResultOutlet = OneshotReceiver<bool>;
 type DecisionInlet<Element> = OneshotSender<(Vec<Element>, Certificate)>;
 type DecisionOutlet<Element> = OneshotReceiver<(Vec<Element>, Certificate)>;
 
+type EvidenceOutlet<Instance> = UnboundedReceiver<DisclosureEquivocation<Instance>>;
+
+type AbortInlet<Element> = UnboundedSender<PartialInlet<Element>>;
+type PartialInlet<Element> = OneshotSender<Vec<Element>>;
+
 pub(crate) struct LatticeAgreement<Instance: LatticeInstance, Element: LatticeElement> {
     instance: Instance,
-    proposal_inlet: Option<ProposalInlet<Element>>,
+    proposal_inlet: ProposalInlet<Element>,
     decision_outlet: DecisionOutlet<Element>,
+    decision: Option<(Vec<Element>, Certificate)>,
+    evidence_outlet: EvidenceOutlet<Instance>,
+    abort_inlet: AbortInlet<Element>,
+    rejections: Rejections,
+    statistics: Statistics,
+    _multiplexer: LatticeMultiplexer,
     _fuse: Fuse,
 }
 
@@ -44,6 +59,12 @@ pub(crate) struct LatticeAgreement<Instance: LatticeInstance, Element: LatticeEl
 pub(crate) enum LatticeAgreementError {
     #[doom(description("Proposal superseded"))]
     ProposalSuperseded,
+    #[doom(description("Failed to restore checkpoint"))]
+    RestoreFailed,
+    #[doom(description("Invalid disclosure thresholds"))]
+    InvalidThresholds,
+    #[doom(description("`Instance` already registered on the multiplexer"))]
+    RegistrationFailed,
 }
 
 impl<Instance, Element> LatticeAgreement<Instance, Element>
@@ -59,18 +80,123 @@ where
         connector: C,
         listener: L,
         settings: LatticeAgreementSettings,
-    ) -> Self
+    ) -> Result<Self, Top<LatticeAgreementError>>
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let multiplexer = LatticeMultiplexer::new(
+            connector,
+            listener,
+            settings.sender_settings.clone(),
+            settings.receiver_settings.clone(),
+        );
+
+        // `instance` is the only `Instance` registered on `multiplexer`:
+        // this fails only if `settings` is invalid
+        LatticeAgreement::multiplexed(view, instance, keychain, discovery, &multiplexer, settings)
+    }
+
+    // Runs the agreement on `multiplexer`'s transport, which can be shared with
+    // other agreements (`settings.sender_settings` and `settings.receiver_settings`
+    // are ignored). Fails if an agreement on `instance` is already running on `multiplexer`,
+    // or if `settings` is invalid.
+    pub fn multiplexed(
+        view: View,
+        instance: Instance,
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        multiplexer: &LatticeMultiplexer,
+        settings: LatticeAgreementSettings,
+    ) -> Result<Self, Top<LatticeAgreementError>> {
+        let (instance_identifier, sender, inbox_outlet) = multiplexer
+            .register(&instance)
+            .pot(LatticeAgreementError::RegistrationFailed, here!())?;
+
+        LatticeAgreement::spawn(
+            view,
+            instance,
+            instance_identifier,
+            keychain,
+            discovery,
+            multiplexer,
+            sender,
+            inbox_outlet,
+            settings,
+            None,
+        )
+    }
+
+    // Like `new`, but checkpoints the agreement's progress to `checkpoints`. If
+    // `checkpoints` holds a checkpoint of `instance` (e.g., left behind by a replica
+    // that crashed mid-instance), the agreement resumes from that checkpoint (in
+    // particular, it never discloses a second element).
+    #[allow(clippy::too_many_arguments)]
+    pub fn resume<C, L>(
+        view: View,
+        instance: Instance,
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        connector: C,
+        listener: L,
+        settings: LatticeAgreementSettings,
+        checkpoints: Arc<dyn CheckpointStore>,
+    ) -> Result<Self, Top<LatticeAgreementError>>
     where
         C: Connector,
         L: Listener,
     {
-        let sender: Sender<Message<Element>> = Sender::new(connector, settings.sender_settings);
+        let multiplexer = LatticeMultiplexer::new(
+            connector,
+            listener,
+            settings.sender_settings.clone(),
+            settings.receiver_settings.clone(),
+        );
+
+        // This cannot fail: `instance` is the only `Instance` registered on `multiplexer`
+        let (instance_identifier, sender, inbox_outlet) = multiplexer.register(&instance).unwrap();
+
+        LatticeAgreement::spawn(
+            view,
+            instance,
+            instance_identifier,
+            keychain,
+            discovery,
+            &multiplexer,
+            sender,
+            inbox_outlet,
+            settings,
+            Some(checkpoints),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn spawn(
+        view: View,
+        instance: Instance,
+        instance_identifier: Hash,
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        multiplexer: &LatticeMultiplexer,
+        sender: Sender<Envelope>,
+        inbox_outlet: InboxOutlet,
+        settings: LatticeAgreementSettings,
+        checkpoints: Option<Arc<dyn CheckpointStore>>,
+    ) -> Result<Self, Top<LatticeAgreementError>> {
+        // Invalid thresholds could compromise the safety of disclosure:
+        // these are a configuration error, and are never silently adjusted
+        let thresholds = settings
+            .disclosure_thresholds
+            .resolve(&view)
+            .pot(LatticeAgreementError::InvalidThresholds, here!())?;
 
-        let receiver: Receiver<Message<Element>> =
-            Receiver::new(listener, settings.receiver_settings);
+        let rejections = Rejections::default();
+        let statistics = Statistics::new(&view);
 
-        let (proposal_inlet, proposal_outlet) = oneshot::channel();
+        let (proposal_inlet, proposal_outlet) = mpsc::unbounded_channel();
         let (decision_inlet, decision_outlet) = oneshot::channel();
+        let (evidence_inlet, evidence_outlet) = mpsc::unbounded_channel();
+        let (abort_inlet, abort_outlet) = mpsc::unbounded_channel();
 
         let fuse = Fuse::new();
 
@@ -79,37 +205,57 @@ where
             let mut runner = LatticeRunner::new(
                 view,
                 instance,
+                instance_identifier,
                 keychain,
                 discovery,
                 sender,
-                receiver,
+                inbox_outlet,
                 proposal_outlet,
                 decision_inlet,
+                evidence_inlet,
+                abort_outlet,
                 settings.push_settings,
+                settings.response_push_settings,
+                thresholds,
+                settings.rejection_interval,
+                rejections.clone(),
+                statistics.clone(),
+                checkpoints,
             );
 
+            runner
+                .restore()
+                .pot(LatticeAgreementError::RestoreFailed, here!())?;
+
             fuse.spawn(async move {
                 let _ = runner.run().await;
             });
         }
 
-        LatticeAgreement {
+        Ok(LatticeAgreement {
             instance,
-            proposal_inlet: Some(proposal_inlet),
+            proposal_inlet,
             decision_outlet: decision_outlet,
+            decision: None,
+            evidence_outlet,
+            abort_inlet,
+            rejections,
+            statistics,
+            _multiplexer: multiplexer.clone(),
             _fuse: fuse,
-        }
+        })
     }
 
+    // Each replica discloses exactly one element: `propose` succeeds if `element`
+    // is (or was already) disclosed by the local replica, or is otherwise known to
+    // be part of the local proposal. A superseded `propose` can be retried, e.g.,
+    // with the element that superseded it, but never discloses a second element.
     pub async fn propose(&mut self, element: Element) -> Result<(), Top<LatticeAgreementError>> {
-        let proposal_inlet = self
-            .proposal_inlet
-            .take()
-            .expect("called `LatticeAgreement::propose` more than once");
-
         let (result_inlet, result_outlet) = oneshot::channel();
 
-        let _ = proposal_inlet.send((element, result_inlet));
+        // This cannot fail: the corresponding `proposal_outlet` is held
+        // by `run`, which keeps running for as long as `self` exists
+        let _ = self.proposal_inlet.send((element, result_inlet));
 
         // This cannot fail as the corresponding `result_inlet` is
         // sent to `run`, which keeps running for as long as
@@ -121,7 +267,62 @@ where
         }
     }
 
+    // Waits for the next `DisclosureEquivocation` observed by this agreement
+    // (e.g., to be turned into a `Resolution` expelling its origin). At most one
+    // `DisclosureEquivocation` is observed for each origin.
+    pub async fn evidence(&mut self) -> DisclosureEquivocation<Instance> {
+        // This cannot fail: the corresponding `evidence_inlet` is held by
+        // `run`, which keeps running for as long as `self` exists
+        self.evidence_outlet.recv().await.unwrap()
+    }
+
+    // Like `evidence`, but returns `None` (instead of waiting) if no further
+    // `DisclosureEquivocation` was observed
+    pub fn try_evidence(&mut self) -> Option<DisclosureEquivocation<Instance>> {
+        self.evidence_outlet.try_recv().ok()
+    }
+
+    // Tears down the agreement (e.g., once superseded by a newer `View`) without
+    // waiting for a decision, cancelling all in-flight broadcasts. Returns the
+    // `Element`s found safe so far (in no particular order).
+    pub async fn abort(self) -> Vec<Element> {
+        let (partial_inlet, partial_outlet) = oneshot::channel();
+
+        // These cannot fail: the corresponding `abort_outlet` and `partial_inlet`
+        // are held by `run`, which keeps running for as long as `self` exists
+        let _ = self.abort_inlet.send(partial_inlet);
+        partial_outlet.await.unwrap()
+    }
+
+    // `ElementRejection`s sent and received by this agreement
+    pub fn rejections(&self) -> &Rejections {
+        &self.rejections
+    }
+
+    // Protocol statistics of this agreement, for debugging slow decisions
+    pub fn statistics(&self) -> StatisticsSnapshot {
+        self.statistics.snapshot()
+    }
+
+    // Waits for the decided elements, along with the quorum `Certificate` of their
+    // decision. `decide` can be called repeatedly (and cancelled): all calls
+    // return the same decision.
     pub async fn decide(&mut self) -> (Vec<Element>, Certificate) {
-        (&mut self.decision_outlet).await.unwrap()
+        if self.decision.is_none() {
+            // This cannot fail: the corresponding `decision_inlet` is held by
+            // `run`, which keeps running for as long as `self` exists
+            self.decision = Some((&mut self.decision_outlet).await.unwrap());
+        }
+
+        self.decision.clone().unwrap()
+    }
+
+    // Like `decide`, but returns `None` (instead of waiting) if no decision was reached yet
+    pub fn try_decide(&mut self) -> Option<(Vec<Element>, Certificate)> {
+        if self.decision.is_none() {
+            self.decision = self.decision_outlet.try_recv().ok();
+        }
+
+        self.decision.clone()
     }
 }
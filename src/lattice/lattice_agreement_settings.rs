@@ -1,8 +1,200 @@
+use crate::view::View;
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::time::Duration;
+
 use talk::unicast::{PartialPushSettings, ReceiverSettings, SenderSettings};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub(crate) struct LatticeAgreementSettings {
+    // Ignored by agreements running on a shared `LatticeMultiplexer`
     pub sender_settings: SenderSettings,
     pub receiver_settings: ReceiverSettings,
+    // Settings of broadcast pushes (disclosures, certification requests)
     pub push_settings: PartialPushSettings,
+    // Settings of pushes responding to a single replica (e.g., certification
+    // confirmations and updates, `ElementRejection`s)
+    pub response_push_settings: PartialPushSettings,
+    pub disclosure_thresholds: DisclosureThresholds,
+    // Minimum interval between two `ElementRejection`s sent to the same replica
+    pub rejection_interval: Duration,
+}
+
+// Overrides for the thresholds of disclosure's Bracha broadcast. Thresholds are
+// expressed in voting weight: each threshold left to `None` defaults to the
+// `View`'s weighted quorum or plurality.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DisclosureThresholds {
+    // Echoes required to issue a ready message (default: quorum)
+    pub echo: Option<usize>,
+    // Ready messages required to issue a ready message (default: plurality)
+    pub ready: Option<usize>,
+    // Ready messages required to deliver (default: quorum)
+    pub deliver: Option<usize>,
+}
+
+impl Default for LatticeAgreementSettings {
+    fn default() -> Self {
+        LatticeAgreementSettings {
+            sender_settings: Default::default(),
+            receiver_settings: Default::default(),
+            push_settings: Default::default(),
+            response_push_settings: Default::default(),
+            disclosure_thresholds: Default::default(),
+            rejection_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Thresholds {
+    pub echo: usize,
+    pub ready: usize,
+    pub deliver: usize,
+}
+
+#[derive(Doom)]
+pub(crate) enum DisclosureThresholdsError {
+    #[doom(description("Echo threshold does not guarantee consistency"))]
+    EchoUnsafe,
+    #[doom(description("Ready threshold can be reached by faulty replicas alone"))]
+    ReadyUnsafe,
+    #[doom(description("Deliver threshold does not guarantee totality"))]
+    DeliverUnsafe,
+    #[doom(description("Threshold cannot be reached by correct replicas alone"))]
+    Unreachable,
+}
+
+impl DisclosureThresholds {
+    pub fn resolve(&self, view: &View) -> Result<Thresholds, Top<DisclosureThresholdsError>> {
+        let thresholds = Thresholds {
+            echo: self.echo.unwrap_or(view.weighted_quorum()),
+            ready: self.ready.unwrap_or(view.weighted_plurality()),
+            deliver: self.deliver.unwrap_or(view.weighted_quorum()),
+        };
+
+        thresholds.validate(view)?;
+
+        Ok(thresholds)
+    }
+}
+
+impl Thresholds {
+    pub fn validate(&self, view: &View) -> Result<(), Top<DisclosureThresholdsError>> {
+        let total = view.total_weight();
+        let faulty = view.weighted_plurality() - 1;
+
+        // Liveness: all thresholds must be reachable without faulty replicas
+        if [self.echo, self.ready, self.deliver]
+            .iter()
+            .any(|threshold| *threshold > total - faulty)
+        {
+            return DisclosureThresholdsError::Unreachable.fail().spot(here!());
+        }
+
+        // Any two sets of `self.echo` echoes must intersect in at least one correct replica
+        if 2 * self.echo <= total + faulty {
+            return DisclosureThresholdsError::EchoUnsafe.fail().spot(here!());
+        }
+
+        // At least one correct replica must back any set of `self.ready` ready messages
+        if self.ready <= faulty {
+            return DisclosureThresholdsError::ReadyUnsafe.fail().spot(here!());
+        }
+
+        // Any set of `self.deliver` ready messages must include at least `self.ready`
+        // correct ready messages, so that all correct replicas eventually issue ready messages
+        if self.deliver < self.ready + faulty {
+            return DisclosureThresholdsError::DeliverUnsafe
+                .fail()
+                .spot(here!());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::{test::InstallGenerator, Attachment, Change};
+
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn default() {
+        let view = InstallGenerator::new(4).view(4);
+
+        let thresholds = DisclosureThresholds::default().resolve(&view).unwrap();
+
+        assert_eq!(
+            thresholds,
+            Thresholds {
+                echo: view.quorum(),
+                ready: view.plurality(),
+                deliver: view.quorum(),
+            }
+        );
+    }
+
+    #[test]
+    fn unsafe_overrides() {
+        let view = InstallGenerator::new(4).view(4);
+
+        let faulty = view.plurality() - 1;
+
+        for thresholds in [
+            DisclosureThresholds {
+                echo: Some(view.plurality()),
+                ..Default::default()
+            },
+            DisclosureThresholds {
+                ready: Some(faulty),
+                ..Default::default()
+            },
+            DisclosureThresholds {
+                deliver: Some(view.plurality()),
+                ..Default::default()
+            },
+            DisclosureThresholds {
+                echo: Some(view.members().len()),
+                ..Default::default()
+            },
+        ] {
+            assert!(thresholds.resolve(&view).is_err());
+        }
+    }
+
+    #[test]
+    fn weighted() {
+        let generator = InstallGenerator::new(4);
+
+        let weights = BTreeMap::from([(generator.keycards[0].identity(), 4u64)]);
+        let attachment = Attachment::new(View::WEIGHTS, 0, &weights).unwrap();
+
+        let view = generator
+            .view(4)
+            .extend(BTreeSet::from([Change::Attach(attachment)]));
+
+        let thresholds = DisclosureThresholds::default().resolve(&view).unwrap();
+
+        assert_eq!(
+            thresholds,
+            Thresholds {
+                echo: 5,
+                ready: 3,
+                deliver: 5,
+            }
+        );
+
+        // Thresholds are validated against weights, not members
+        let thresholds = DisclosureThresholds {
+            echo: Some(view.quorum()),
+            ..Default::default()
+        };
+
+        assert!(thresholds.resolve(&view).is_err());
+    }
 }
@@ -0,0 +1,116 @@
+use crate::lattice::{Envelope, Instance as LatticeInstance};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use talk::{
+    crypto::{
+        primitives::hash::{self, Hash},
+        Identity,
+    },
+    net::{Connector, Listener},
+    sync::fuse::Fuse,
+    unicast::{Acknowledger, Receiver, ReceiverSettings, Sender, SenderSettings},
+};
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+type InboxInlet = UnboundedSender<(Identity, Envelope, Acknowledger)>;
+type InboxOutlet = UnboundedReceiver<(Identity, Envelope, Acknowledger)>;
+
+type Routes = Arc<Mutex<HashMap<Hash, InboxInlet>>>;
+
+// A `LatticeMultiplexer` runs any number of `LatticeAgreement`s (possibly over
+// different `Element` types) on a single unicast `Sender` / `Receiver` pair,
+// routing each `Envelope` to the agreement whose `Instance` it is addressed to.
+// Every agreement holds a clone of its `LatticeMultiplexer`: the shared transport
+// runs for as long as any of them exists.
+#[derive(Clone)]
+pub(crate) struct LatticeMultiplexer {
+    sender: Sender<Envelope>,
+    routes: Routes,
+    _fuse: Arc<Fuse>,
+}
+
+#[derive(Doom)]
+pub(crate) enum LatticeMultiplexerError {
+    #[doom(description("`Instance` already registered"))]
+    InstanceRegistered,
+}
+
+impl LatticeMultiplexer {
+    pub fn new<C, L>(
+        connector: C,
+        listener: L,
+        sender_settings: SenderSettings,
+        receiver_settings: ReceiverSettings,
+    ) -> Self
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let sender = Sender::new(connector, sender_settings);
+        let receiver = Receiver::new(listener, receiver_settings);
+
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+
+        let fuse = Fuse::new();
+        fuse.spawn(LatticeMultiplexer::dispatch(receiver, routes.clone()));
+
+        LatticeMultiplexer {
+            sender,
+            routes,
+            _fuse: Arc::new(fuse),
+        }
+    }
+
+    // Returns the identifier of `instance`, the shared `Sender`, and the outlet
+    // of all `Envelope`s addressed to `instance`
+    pub(in crate::lattice) fn register<Instance>(
+        &self,
+        instance: &Instance,
+    ) -> Result<(Hash, Sender<Envelope>, InboxOutlet), Top<LatticeMultiplexerError>>
+    where
+        Instance: LatticeInstance,
+    {
+        let identifier = hash::hash(instance).unwrap();
+
+        let mut routes = self.routes.lock().unwrap();
+
+        // The route of a dropped agreement can be registered again
+        if routes
+            .get(&identifier)
+            .map_or(false, |inbox_inlet| !inbox_inlet.is_closed())
+        {
+            return LatticeMultiplexerError::InstanceRegistered
+                .fail()
+                .spot(here!());
+        }
+
+        let (inbox_inlet, inbox_outlet) = mpsc::unbounded_channel();
+        routes.insert(identifier, inbox_inlet);
+
+        Ok((identifier, self.sender.clone(), inbox_outlet))
+    }
+
+    async fn dispatch(mut receiver: Receiver<Envelope>, routes: Routes) {
+        loop {
+            let (source, envelope, acknowledger) = receiver.receive().await;
+            let instance = envelope.instance;
+
+            let mut routes = routes.lock().unwrap();
+
+            // `Envelope`s addressed to unknown (or dropped) agreements are
+            // discarded without acknowledgement
+            if let Some(inbox_inlet) = routes.get(&instance) {
+                if inbox_inlet.send((source, envelope, acknowledger)).is_err() {
+                    routes.remove(&instance);
+                }
+            }
+        }
+    }
+}
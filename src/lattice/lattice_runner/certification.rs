@@ -17,6 +17,8 @@ where
     Element: LatticeElement,
 {
     pub(in crate::lattice::lattice_runner) fn certify(&mut self, elements: BTreeSet<Hash>) {
+        self.statistics.record_certification();
+
         let identifier = elements.identifier();
 
         let decision = Decision {
@@ -32,7 +34,7 @@ where
         let broadcast = BestEffort::new(
             self.sender.clone(),
             self.view.members().keys().cloned(),
-            Message::CertificationRequest(message),
+            self.envelope(Message::CertificationRequest(message)),
             self.configuration.broadcast.clone(),
         );
 
@@ -50,6 +52,7 @@ where
 
     pub(in crate::lattice::lattice_runner) fn decide(&mut self) {
         self.state = State::Decided;
+        self.statistics.record_decision();
 
         let (decision, certificate) = self
             .database
@@ -66,10 +69,15 @@ where
             .cloned()
             .collect::<Vec<_>>();
 
+        self.database.decision = Some((decision.elements, certificate.clone()));
+        self.checkpoint();
+
         let _ = self
             .decision_inlet
             .take()
             .unwrap()
             .send((elements, certificate));
+
+        self.prune_elements();
     }
 }
@@ -0,0 +1,142 @@
+use crate::{
+    crypto::Certificate,
+    lattice::{
+        lattice_runner::{DisclosureDatabase, State},
+        Element as LatticeElement, Instance as LatticeInstance, LatticeRunner,
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::collections::{BTreeSet, HashMap};
+
+use talk::crypto::primitives::hash::Hash;
+
+// Checkpoints are written as tuples of references (to avoid cloning the
+// `Database`), and read back as tuples of owned values (`bincode` encodes both identically)
+type Checkpoint<Element> = (
+    State,
+    DisclosureDatabase,
+    HashMap<Hash, Element>,
+    usize,
+    BTreeSet<Hash>,
+    BTreeSet<Hash>,
+    BTreeSet<Hash>,
+    Option<(BTreeSet<Hash>, Certificate)>,
+);
+
+#[derive(Doom)]
+pub(in crate::lattice) enum RestoreError {
+    #[doom(description("Failed to load checkpoint"))]
+    LoadFailed,
+    #[doom(description("Malformed checkpoint"))]
+    CheckpointMalformed,
+}
+
+impl<Instance, Element> LatticeRunner<Instance, Element>
+where
+    Instance: LatticeInstance,
+    Element: LatticeElement,
+{
+    // Stores all state the local replica committed to by sending messages (i.e.,
+    // everything but ongoing certification, which can be safely restarted). Must be
+    // called before sending any message that depends on such state.
+    pub(in crate::lattice::lattice_runner) fn checkpoint(&self) {
+        let checkpoints = match &self.checkpoints {
+            Some(checkpoints) => checkpoints,
+            None => return,
+        };
+
+        let database = &self.database;
+
+        let checkpoint = bincode::serialize(&(
+            &self.state,
+            &database.disclosure,
+            &database.elements,
+            database.disclosures,
+            &database.safe_set,
+            &database.proposed_set,
+            &database.accepted_set,
+            &database.decision,
+        ))
+        .unwrap();
+
+        // Messages cannot be withheld at this point: a replica that fails
+        // to checkpoint must not be restarted mid-instance
+        if checkpoints
+            .store(self.instance_identifier, checkpoint)
+            .is_err()
+        {
+            log::error!("Failed to checkpoint lattice agreement");
+        }
+    }
+
+    // Restores the latest checkpoint of `self.instance` (if any). Must be
+    // called before `run`.
+    pub fn restore(&mut self) -> Result<(), Top<RestoreError>> {
+        let checkpoint = match &self.checkpoints {
+            Some(checkpoints) => checkpoints
+                .load(self.instance_identifier)
+                .pot(RestoreError::LoadFailed, here!())?,
+            None => None,
+        };
+
+        let checkpoint = match checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+
+        let checkpoint: Checkpoint<Element> = bincode::deserialize(checkpoint.as_slice())
+            .pot(RestoreError::CheckpointMalformed, here!())?;
+
+        let (
+            state,
+            disclosure,
+            elements,
+            disclosures,
+            safe_set,
+            proposed_set,
+            accepted_set,
+            decision,
+        ) = checkpoint;
+
+        self.database.disclosure = disclosure;
+        self.database.elements = elements;
+        self.database.disclosures = disclosures;
+        self.database.safe_set = safe_set;
+        self.database.proposed_set = proposed_set;
+        self.database.accepted_set = accepted_set;
+
+        match state {
+            State::Disclosing => {}
+            State::Proposing => {
+                // Certification is restarted from scratch
+                self.state = State::Proposing;
+                self.certify(self.database.proposed_set.clone());
+            }
+            State::Decided => {
+                let (identifiers, certificate) = decision
+                    .ok_or(RestoreError::CheckpointMalformed.into_top())
+                    .spot(here!())?;
+
+                let elements = identifiers
+                    .iter()
+                    .map(|identifier| self.database.elements.get(identifier).cloned())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(RestoreError::CheckpointMalformed.into_top())
+                    .spot(here!())?;
+
+                self.state = State::Decided;
+                self.database.decision = Some((identifiers, certificate.clone()));
+
+                let _ = self
+                    .decision_inlet
+                    .take()
+                    .unwrap()
+                    .send((elements, certificate));
+            }
+        }
+
+        Ok(())
+    }
+}
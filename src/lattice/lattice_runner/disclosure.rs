@@ -1,9 +1,24 @@
-use crate::lattice::{
-    lattice_runner::State, messages::DisclosureSend, Element as LatticeElement,
-    Instance as LatticeInstance, LatticeRunner, Message,
+use crate::{
+    crypto::Identify,
+    lattice::{
+        lattice_runner::{PendingBrief, State},
+        messages::{DisclosureRequest, DisclosureSend},
+        DisclosureEquivocation, DisclosureStatement, Element as LatticeElement,
+        Instance as LatticeInstance, LatticeRunner, Message, MessageError,
+    },
 };
 
-use talk::broadcast::BestEffort;
+use doomstack::{here, ResultExt, Top};
+
+use std::collections::HashSet;
+
+use talk::{
+    broadcast::BestEffort,
+    crypto::{
+        primitives::{hash::Hash, sign::Signature},
+        Identity, KeyCard,
+    },
+};
 
 impl<Instance, Element> LatticeRunner<Instance, Element>
 where
@@ -24,27 +39,42 @@ where
         self.database.safe_set.insert(identifier);
         self.database.proposed_set.insert(identifier);
 
+        let signature = self
+            .keychain
+            .sign(&self.disclosure_statement(identifier))
+            .unwrap();
+
         let brief = DisclosureSend::Brief {
             proposal: identifier,
+            signature,
         };
 
-        let expanded = DisclosureSend::Expanded { proposal };
+        let expanded = DisclosureSend::Expanded {
+            proposal,
+            signature,
+        };
+
+        self.checkpoint();
 
         let broadcast = BestEffort::brief(
             self.sender.clone(),
             self.view.members().keys().cloned(),
-            Message::DisclosureSend(brief),
-            Message::DisclosureSend(expanded),
+            self.envelope(Message::DisclosureSend(brief)),
+            self.envelope(Message::DisclosureSend(expanded)),
             self.configuration.broadcast.clone(),
         );
 
         broadcast.spawn(&self.fuse);
     }
 
-    pub(in crate::lattice::lattice_runner) fn deliver_disclosure(&mut self, proposal: Element) {
+    pub(in crate::lattice::lattice_runner) fn deliver_disclosure(
+        &mut self,
+        origin: Identity,
+        proposal: Element,
+    ) {
         let identifier = proposal.identifier();
 
-        self.database.disclosures += 1;
+        self.database.disclosures += self.view.weight(&origin);
         self.database.safe_set.insert(identifier);
 
         if self.state == State::Disclosing {
@@ -54,10 +84,387 @@ where
 
             self.database.proposed_set.insert(identifier);
 
-            if self.database.disclosures >= self.view.quorum() {
+            if self.database.disclosures >= self.view.weighted_quorum() {
                 self.state = State::Proposing;
                 self.certify(self.database.proposed_set.clone());
             }
         }
     }
+
+    pub(in crate::lattice::lattice_runner) fn disclosure_statement(
+        &self,
+        proposal: Hash,
+    ) -> DisclosureStatement<Instance> {
+        DisclosureStatement {
+            view: self.view.identifier(),
+            instance: self.instance.clone(),
+            proposal,
+        }
+    }
+
+    // Verifies `origin`'s `signature` on `proposal`. Signatures serve only as
+    // evidence: a signature that would not be recorded (see `record_disclosure`)
+    // is not verified, so that each origin's disclosure is verified only once.
+    pub(in crate::lattice::lattice_runner) fn verify_disclosure(
+        &self,
+        origin: &KeyCard,
+        proposal: Hash,
+        signature: &Signature,
+    ) -> Result<(), Top<MessageError>> {
+        let disclosure = &self.database.disclosure;
+
+        if disclosure.equivocators.contains(&origin.identity())
+            || disclosure
+                .signatures
+                .get(&origin.identity())
+                .map_or(false, |(signed, _)| *signed == proposal)
+        {
+            return Ok(());
+        }
+
+        signature
+            .verify(origin, &self.disclosure_statement(proposal))
+            .pot(MessageError::InvalidSignature, here!())
+    }
+
+    // Records `origin`'s (verified) `signature` on `proposal`, reporting a
+    // `DisclosureEquivocation` if `origin` was previously observed disclosing
+    // a different proposal
+    pub(in crate::lattice::lattice_runner) fn record_disclosure(
+        &mut self,
+        origin: Identity,
+        proposal: Hash,
+        signature: Signature,
+    ) {
+        if self.database.disclosure.equivocators.contains(&origin) {
+            return;
+        }
+
+        let first = match self.database.disclosure.signatures.get(&origin).cloned() {
+            Some(first) => first,
+            None => {
+                self.database
+                    .disclosure
+                    .signatures
+                    .insert(origin, (proposal, signature));
+
+                return;
+            }
+        };
+
+        if first.0 == proposal {
+            return;
+        }
+
+        self.database.disclosure.equivocators.insert(origin);
+
+        let equivocation = DisclosureEquivocation::new(
+            origin,
+            self.view.identifier(),
+            self.instance.clone(),
+            first,
+            (proposal, signature),
+        );
+
+        let _ = self.evidence_inlet.send(equivocation);
+    }
+
+    // Requests the `Element` identified by `identifier` from the source of `brief`,
+    // which is processed once the corresponding `DisclosureReply` is received
+    pub(in crate::lattice::lattice_runner) fn request_disclosure(
+        &mut self,
+        brief: PendingBrief,
+        identifier: Hash,
+    ) {
+        let source = match brief {
+            PendingBrief::Echo { source, .. } | PendingBrief::Ready { source, .. } => source,
+        };
+
+        // Each source can have at most one pending brief per kind and origin
+        // (a retransmitted brief does not trigger a second request)
+        if self
+            .database
+            .disclosure
+            .pending
+            .insert(brief, identifier)
+            .is_some()
+        {
+            return;
+        }
+
+        let request = DisclosureRequest {
+            proposal: identifier,
+        };
+
+        self.sender.spawn_push(
+            source,
+            self.envelope(Message::DisclosureRequest(request)),
+            self.configuration.response.clone(),
+            &self.fuse,
+        );
+    }
+
+    // Whether an echo message from `source` for `origin` is superfluous, i.e., `origin`
+    // was delivered, or an echo from `source` for `origin` was already collected.
+    // The `Element`s of superfluous messages are neither stored nor requested: as only
+    // the first echo and ready message of each source for each origin are processed,
+    // at most `2 * n + 1` `Element`s are stored on behalf of each origin, `n` being
+    // the number of members of the `View` (the extra one being sent by the origin)
+    pub(in crate::lattice::lattice_runner) fn echo_superfluous(
+        &self,
+        source: Identity,
+        origin: Identity,
+    ) -> bool {
+        let disclosure = &self.database.disclosure;
+
+        disclosure.delivered.contains(&origin)
+            || disclosure.echoes_collected.contains(&(source, origin))
+    }
+
+    // Like `echo_superfluous`, for ready messages
+    pub(in crate::lattice::lattice_runner) fn ready_superfluous(
+        &self,
+        source: Identity,
+        origin: Identity,
+    ) -> bool {
+        let disclosure = &self.database.disclosure;
+
+        disclosure.delivered.contains(&origin)
+            || disclosure.ready_collected.contains(&(source, origin))
+    }
+
+    // Drops the echo and ready state pertaining to `origin`, whose disclosure
+    // was delivered: `ready_sent` and `delivered` (one entry per origin) are
+    // enough to ignore all further echo and ready messages for `origin`
+    pub(in crate::lattice::lattice_runner) fn prune_disclosure(&mut self, origin: Identity) {
+        let disclosure = &mut self.database.disclosure;
+
+        disclosure
+            .echoes_collected
+            .retain(|(_, collected)| *collected != origin);
+
+        disclosure
+            .ready_collected
+            .retain(|(_, collected)| *collected != origin);
+
+        disclosure
+            .echo_support
+            .retain(|(supported, _), _| *supported != origin);
+
+        disclosure
+            .ready_support
+            .retain(|(supported, _), _| *supported != origin);
+
+        disclosure.pending.retain(|brief, _| match brief {
+            PendingBrief::Echo {
+                origin: pending, ..
+            }
+            | PendingBrief::Ready {
+                origin: pending, ..
+            } => *pending != origin,
+        });
+    }
+
+    // Called once `State::Decided` is reached: drops all `Element`s that are
+    // neither safe nor supported by the Bracha instance of an undelivered origin
+    // (the local replica keeps echoing and readying for other replicas)
+    pub(in crate::lattice::lattice_runner) fn prune_elements(&mut self) {
+        let disclosure = &self.database.disclosure;
+
+        let supported = disclosure
+            .echo_support
+            .keys()
+            .chain(disclosure.ready_support.keys())
+            .map(|(_, identifier)| *identifier)
+            .collect::<HashSet<_>>();
+
+        let safe_set = &self.database.safe_set;
+
+        self.database.elements.retain(|identifier, _| {
+            safe_set.contains(identifier) || supported.contains(identifier)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        discovery::Embedded,
+        lattice::{DisclosureThresholds, Element as LatticeElement, ElementError, Statistics},
+        view::View,
+    };
+
+    use serde::{Deserialize, Serialize};
+
+    use std::sync::Arc;
+
+    use talk::{
+        crypto::{primitives::hash, KeyChain},
+        net::test::System as NetSystem,
+        unicast::Sender,
+    };
+
+    use tokio::sync::{mpsc, oneshot};
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    struct Value(u32);
+
+    impl LatticeElement for Value {
+        fn validate(
+            &self,
+            _client: &crate::discovery::Client,
+            _view: &View,
+        ) -> Result<(), Top<ElementError>> {
+            Ok(())
+        }
+    }
+
+    impl Identify for Value {
+        fn identifier(&self) -> Hash {
+            hash::hash(&self).unwrap()
+        }
+    }
+
+    async fn setup(members: usize) -> (Embedded, Vec<Identity>, LatticeRunner<u32, Value>) {
+        let keychains = (0..members).map(|_| KeyChain::random()).collect::<Vec<_>>();
+
+        let view = View::genesis(keychains.iter().map(KeyChain::keycard));
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = Arc::new(embedded.client(Default::default()));
+
+        let NetSystem { mut connectors, .. } =
+            NetSystem::setup_with_keychains(keychains.clone()).await;
+
+        let (_, inbox_outlet) = mpsc::unbounded_channel();
+        let (_, proposal_outlet) = mpsc::unbounded_channel();
+        let (decision_inlet, _) = oneshot::channel();
+        let (evidence_inlet, _) = mpsc::unbounded_channel();
+        let (_, abort_outlet) = mpsc::unbounded_channel();
+
+        let thresholds = DisclosureThresholds::default().resolve(&view).unwrap();
+        let statistics = Statistics::new(&view);
+
+        let runner = LatticeRunner::new(
+            view,
+            0,
+            hash::hash(&0u32).unwrap(),
+            keychains[0].clone(),
+            discovery,
+            Sender::new(connectors.remove(0), Default::default()),
+            inbox_outlet,
+            proposal_outlet,
+            decision_inlet,
+            evidence_inlet,
+            abort_outlet,
+            Default::default(),
+            Default::default(),
+            thresholds,
+            Default::default(),
+            Default::default(),
+            statistics,
+            None,
+        );
+
+        let identities = keychains
+            .iter()
+            .map(|keychain| keychain.keycard().identity())
+            .collect();
+
+        (embedded, identities, runner)
+    }
+
+    #[tokio::test]
+    async fn superfluous() {
+        let (_embedded, identities, mut runner) = setup(4).await;
+
+        let origin = identities[0];
+
+        // A source that keeps echoing (equivocating) proposals for `origin`
+        // has only the first of them stored
+        for (value, source) in identities.iter().copied().enumerate() {
+            for relay in 0..16 {
+                let proposal = Value((value * 16 + relay) as u32);
+
+                if !runner.echo_superfluous(source, origin) {
+                    let identifier = proposal.identifier();
+
+                    runner
+                        .database
+                        .elements
+                        .insert(identifier, proposal.clone());
+                    runner.apply_disclosure_echo(source, origin, identifier, proposal);
+                }
+            }
+
+            assert!(runner.echo_superfluous(source, origin));
+            assert!(!runner.ready_superfluous(source, origin));
+        }
+
+        assert_eq!(runner.database.elements.len(), identities.len());
+
+        // Echoes for other origins are not superfluous
+        assert!(!runner.echo_superfluous(identities[0], identities[1]));
+    }
+
+    #[tokio::test]
+    async fn prune() {
+        let (_embedded, identities, mut runner) = setup(4).await;
+
+        let values = (0..4).map(Value).collect::<Vec<_>>();
+
+        for value in values.iter() {
+            runner
+                .database
+                .elements
+                .insert(value.identifier(), value.clone());
+        }
+
+        // `values[0]` and `values[1]` are echoed for `identities[0]`, `values[2]`
+        // for `identities[1]`, and `values[3]` is safe
+
+        for (source, value) in [(1, 0), (2, 1)] {
+            runner.apply_disclosure_echo(
+                identities[source],
+                identities[0],
+                values[value].identifier(),
+                values[value].clone(),
+            );
+        }
+
+        runner.apply_disclosure_echo(
+            identities[1],
+            identities[1],
+            values[2].identifier(),
+            values[2].clone(),
+        );
+
+        runner.database.safe_set.insert(values[3].identifier());
+
+        runner.prune_disclosure(identities[0]);
+
+        let disclosure = &runner.database.disclosure;
+
+        assert!(disclosure
+            .echo_support
+            .keys()
+            .all(|(origin, _)| *origin == identities[1]));
+
+        assert_eq!(disclosure.echoes_collected.len(), 1);
+
+        runner.prune_elements();
+
+        let mut elements = runner.database.elements.keys().copied().collect::<Vec<_>>();
+        let mut expected = vec![values[2].identifier(), values[3].identifier()];
+
+        elements.sort();
+        expected.sort();
+
+        assert_eq!(elements, expected);
+    }
 }
@@ -57,7 +57,7 @@ where
             .unwrap();
 
         // If this is reached, then `self.state == State::Proposing` (as `message` passed validation)
-        if certification_database.aggregator.multiplicity() >= self.view.quorum() {
+        if certification_database.aggregator.power() >= self.view.weighted_quorum() {
             self.decide();
         }
     }
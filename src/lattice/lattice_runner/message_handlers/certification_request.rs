@@ -43,9 +43,29 @@ where
     ) {
         acknowledger.strong();
 
-        if message.elements.is_superset(&self.database.accepted_set) {
-            let identifier = message.elements.identifier();
+        let identifier = message.elements.identifier();
 
+        // `self.database.accepted_set` only grows, and must be checkpointed
+        // before confirming (an acceptor that restarted with a smaller
+        // `accepted_set` could confirm incomparable sets)
+
+        let differences = self
+            .database
+            .accepted_set
+            .difference(&message.elements)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        self.database.accepted_set = self
+            .database
+            .accepted_set
+            .union(&message.elements)
+            .cloned()
+            .collect::<BTreeSet<_>>();
+
+        self.checkpoint();
+
+        if differences.is_empty() {
             let decision = Decision {
                 view: self.view.identifier(),
                 instance: self.instance.clone(),
@@ -61,20 +81,11 @@ where
 
             self.sender.spawn_push(
                 source.identity(),
-                Message::CertificationConfirmation(message),
+                self.envelope(Message::CertificationConfirmation(message)),
                 self.configuration.response.clone(),
                 &self.fuse,
             );
         } else {
-            let identifier = message.elements.identifier();
-
-            let differences = self
-                .database
-                .accepted_set
-                .difference(&message.elements)
-                .cloned()
-                .collect::<BTreeSet<_>>();
-
             let message = CertificationUpdate {
                 identifier,
                 differences,
@@ -82,17 +93,10 @@ where
 
             self.sender.spawn_push(
                 source.identity(),
-                Message::CertificationUpdate(message),
+                self.envelope(Message::CertificationUpdate(message)),
                 self.configuration.response.clone(),
                 &self.fuse,
             );
         }
-
-        self.database.accepted_set = self
-            .database
-            .accepted_set
-            .union(&message.elements)
-            .cloned()
-            .collect::<BTreeSet<_>>();
     }
 }
@@ -1,11 +1,16 @@
 use crate::lattice::{
+    lattice_runner::PendingBrief,
     messages::{DisclosureEcho, DisclosureReady},
     Element as LatticeElement, Instance as LatticeInstance, LatticeRunner, Message, MessageError,
 };
 
-use doomstack::{here, ResultExt, Top};
+use doomstack::{here, Doom, ResultExt, Top};
 
-use talk::{broadcast::BestEffort, crypto::KeyCard, unicast::Acknowledger};
+use talk::{
+    broadcast::BestEffort,
+    crypto::{primitives::hash::Hash, Identity, KeyCard},
+    unicast::Acknowledger,
+};
 
 impl<Instance, Element> LatticeRunner<Instance, Element>
 where
@@ -17,12 +22,33 @@ where
         _source: &KeyCard,
         message: &DisclosureEcho<Element>,
     ) -> Result<(), Top<MessageError>> {
-        match message {
-            DisclosureEcho::Brief { .. } => Ok(()),
-            DisclosureEcho::Expanded { proposal, .. } => proposal
-                .validate(&self.discovery, &self.view)
-                .pot(MessageError::InvalidElement, here!()),
-        }
+        let origin = match message {
+            DisclosureEcho::Brief { origin, .. } | DisclosureEcho::Expanded { origin, .. } => {
+                origin
+            }
+        };
+
+        let origin = self
+            .view
+            .members()
+            .get(origin)
+            .ok_or(MessageError::ForeignOrigin.into_top())
+            .spot(here!())?;
+
+        let (identifier, signature) = match message {
+            DisclosureEcho::Brief {
+                proposal,
+                signature,
+                ..
+            } => (*proposal, signature),
+            DisclosureEcho::Expanded {
+                proposal,
+                signature,
+                ..
+            } => (proposal.identifier(), signature),
+        };
+
+        self.verify_disclosure(origin, identifier, signature)
     }
 
     pub(in crate::lattice::lattice_runner) fn process_disclosure_echo(
@@ -33,24 +59,69 @@ where
     ) {
         let source = source.identity();
 
+        let origin = match &message {
+            DisclosureEcho::Brief { origin, .. } | DisclosureEcho::Expanded { origin, .. } => {
+                *origin
+            }
+        };
+
+        // Once the disclosure from `origin` is delivered, its Bracha state is
+        // pruned, and further echo messages for `origin` are moot (except as
+        // evidence of `origin` equivocating). So are all echo messages from `source`
+        // for `origin` but the first, whose `Element`s are not stored
+
+        if self.echo_superfluous(source, origin) {
+            let (identifier, signature) = match message {
+                DisclosureEcho::Brief {
+                    proposal,
+                    signature,
+                    ..
+                } => (proposal, signature),
+                DisclosureEcho::Expanded {
+                    proposal,
+                    signature,
+                    ..
+                } => (proposal.identifier(), signature),
+            };
+
+            self.record_disclosure(origin, identifier, signature);
+
+            acknowledger.strong();
+            return;
+        }
+
         let (origin, identifier, proposal) = match message {
             DisclosureEcho::Brief {
                 origin,
                 proposal: identifier,
+                signature,
             } => {
+                self.record_disclosure(origin, identifier, signature);
+
                 let proposal = match self.database.elements.get(&identifier).cloned() {
                     Some(proposal) => proposal,
                     None => {
+                        self.statistics.record_expansion();
                         acknowledger.expand();
+
+                        // Should the expanded message never arrive (e.g., `source`
+                        // gave up on it), the `Element` is pulled from `source`
+                        self.request_disclosure(PendingBrief::Echo { source, origin }, identifier);
+
                         return;
                     }
                 };
 
                 (origin, identifier, proposal)
             }
-            DisclosureEcho::Expanded { origin, proposal } => {
+            DisclosureEcho::Expanded {
+                origin,
+                proposal,
+                signature,
+            } => {
                 let identifier = proposal.identifier();
 
+                self.record_disclosure(origin, identifier, signature);
                 self.database.elements.insert(identifier, proposal.clone());
 
                 (origin, identifier, proposal)
@@ -59,6 +130,21 @@ where
 
         acknowledger.strong();
 
+        self.apply_disclosure_echo(source, origin, identifier, proposal);
+    }
+
+    pub(in crate::lattice::lattice_runner) fn apply_disclosure_echo(
+        &mut self,
+        source: Identity,
+        origin: Identity,
+        identifier: Hash,
+        proposal: Element,
+    ) {
+        // `origin` might have been delivered while the `Element` was pending
+        if self.database.disclosure.delivered.contains(&origin) {
+            return;
+        }
+
         if self
             .database
             .disclosure
@@ -72,10 +158,12 @@ where
                 .entry((origin, identifier))
                 .or_insert(0);
 
-            *support += 1;
+            *support += self.view.weight(&source);
             let support = *support;
 
-            if support >= self.view.quorum() && self.database.disclosure.ready_sent.insert(origin) {
+            if support >= self.configuration.thresholds.echo
+                && self.database.disclosure.ready_sent.insert(origin)
+            {
                 let brief = DisclosureReady::Brief {
                     origin,
                     proposal: identifier,
@@ -83,16 +171,20 @@ where
 
                 let expanded = DisclosureReady::Expanded { origin, proposal };
 
+                self.checkpoint();
+
                 let broadcast = BestEffort::brief(
                     self.sender.clone(),
                     self.view.members().keys().cloned(),
-                    Message::DisclosureReady(brief),
-                    Message::DisclosureReady(expanded),
+                    self.envelope(Message::DisclosureReady(brief)),
+                    self.envelope(Message::DisclosureReady(expanded)),
                     self.configuration.broadcast.clone(),
                 );
 
                 broadcast.spawn(&self.fuse);
             }
+        } else {
+            self.statistics.record_duplicate();
         }
     }
 }
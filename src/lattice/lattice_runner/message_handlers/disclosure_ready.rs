@@ -1,11 +1,15 @@
 use crate::lattice::{
-    messages::DisclosureReady, Element as LatticeElement, Instance as LatticeInstance,
-    LatticeRunner, Message, MessageError,
+    lattice_runner::PendingBrief, messages::DisclosureReady, Element as LatticeElement,
+    Instance as LatticeInstance, LatticeRunner, Message, MessageError,
 };
 
 use doomstack::{here, ResultExt, Top};
 
-use talk::{broadcast::BestEffort, crypto::KeyCard, unicast::Acknowledger};
+use talk::{
+    broadcast::BestEffort,
+    crypto::{primitives::hash::Hash, Identity, KeyCard},
+    unicast::Acknowledger,
+};
 
 impl<Instance, Element> LatticeRunner<Instance, Element>
 where
@@ -17,12 +21,17 @@ where
         _source: &KeyCard,
         message: &DisclosureReady<Element>,
     ) -> Result<(), Top<MessageError>> {
-        match message {
-            DisclosureReady::Brief { .. } => Ok(()),
-            DisclosureReady::Expanded { proposal, .. } => proposal
-                .validate(&self.discovery, &self.view)
-                .pot(MessageError::InvalidElement, here!()),
+        let origin = match message {
+            DisclosureReady::Brief { origin, .. } | DisclosureReady::Expanded { origin, .. } => {
+                origin
+            }
+        };
+
+        if !self.view.members().contains_key(origin) {
+            return MessageError::ForeignOrigin.fail().spot(here!());
         }
+
+        Ok(())
     }
 
     pub(in crate::lattice::lattice_runner) fn process_disclosure_ready(
@@ -33,6 +42,22 @@ where
     ) {
         let source = source.identity();
 
+        let origin = match &message {
+            DisclosureReady::Brief { origin, .. } | DisclosureReady::Expanded { origin, .. } => {
+                *origin
+            }
+        };
+
+        // Once the disclosure from `origin` is delivered, its Bracha state is
+        // pruned, and further ready messages for `origin` are moot. So are all
+        // ready messages from `source` for `origin` but the first, whose
+        // `Element`s are not stored
+
+        if self.ready_superfluous(source, origin) {
+            acknowledger.strong();
+            return;
+        }
+
         let (origin, identifier, proposal) = match message {
             DisclosureReady::Brief {
                 origin,
@@ -41,7 +66,13 @@ where
                 let proposal = match self.database.elements.get(&identifier).cloned() {
                     Some(proposal) => proposal,
                     None => {
+                        self.statistics.record_expansion();
                         acknowledger.expand();
+
+                        // Should the expanded message never arrive (e.g., `source`
+                        // gave up on it), the `Element` is pulled from `source`
+                        self.request_disclosure(PendingBrief::Ready { source, origin }, identifier);
+
                         return;
                     }
                 };
@@ -59,6 +90,21 @@ where
 
         acknowledger.strong();
 
+        self.apply_disclosure_ready(source, origin, identifier, proposal);
+    }
+
+    pub(in crate::lattice::lattice_runner) fn apply_disclosure_ready(
+        &mut self,
+        source: Identity,
+        origin: Identity,
+        identifier: Hash,
+        proposal: Element,
+    ) {
+        // `origin` might have been delivered while the `Element` was pending
+        if self.database.disclosure.delivered.contains(&origin) {
+            return;
+        }
+
         if self
             .database
             .disclosure
@@ -72,10 +118,10 @@ where
                 .entry((origin, identifier))
                 .or_insert(0);
 
-            *support += 1;
+            *support += self.view.weight(&source);
             let support = *support;
 
-            if support >= self.view.plurality()
+            if support >= self.configuration.thresholds.ready
                 && self.database.disclosure.ready_sent.insert(origin)
             {
                 let brief = DisclosureReady::Brief {
@@ -88,20 +134,27 @@ where
                     proposal: proposal.clone(),
                 };
 
+                self.checkpoint();
+
                 let broadcast = BestEffort::brief(
                     self.sender.clone(),
                     self.view.members().keys().cloned(),
-                    Message::DisclosureReady(brief),
-                    Message::DisclosureReady(expanded),
+                    self.envelope(Message::DisclosureReady(brief)),
+                    self.envelope(Message::DisclosureReady(expanded)),
                     self.configuration.broadcast.clone(),
                 );
 
                 broadcast.spawn(&self.fuse);
             }
 
-            if support >= self.view.quorum() && self.database.disclosure.delivered.insert(origin) {
-                self.deliver_disclosure(proposal);
+            if support >= self.configuration.thresholds.deliver
+                && self.database.disclosure.delivered.insert(origin)
+            {
+                self.prune_disclosure(origin);
+                self.deliver_disclosure(origin, proposal);
             }
+        } else {
+            self.statistics.record_duplicate();
         }
     }
 }
@@ -0,0 +1,72 @@
+use crate::lattice::{
+    lattice_runner::PendingBrief, messages::DisclosureReply, Element as LatticeElement,
+    Instance as LatticeInstance, LatticeRunner, MessageError,
+};
+
+use doomstack::{here, ResultExt, Top};
+
+use talk::{crypto::KeyCard, unicast::Acknowledger};
+
+impl<Instance, Element> LatticeRunner<Instance, Element>
+where
+    Instance: LatticeInstance,
+    Element: LatticeElement,
+{
+    pub(in crate::lattice::lattice_runner) fn validate_disclosure_reply(
+        &self,
+        _source: &KeyCard,
+        message: &DisclosureReply<Element>,
+    ) -> Result<(), Top<MessageError>> {
+        let identifier = message.proposal.identifier();
+
+        if !self
+            .database
+            .disclosure
+            .pending
+            .values()
+            .any(|pending| *pending == identifier)
+        {
+            return MessageError::UnrequestedDisclosure.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+
+    pub(in crate::lattice::lattice_runner) fn process_disclosure_reply(
+        &mut self,
+        _source: &KeyCard,
+        message: DisclosureReply<Element>,
+        acknowledger: Acknowledger,
+    ) {
+        acknowledger.strong();
+
+        let proposal = message.proposal;
+        let identifier = proposal.identifier();
+
+        self.database.elements.insert(identifier, proposal.clone());
+
+        // Process all briefs that were waiting for `proposal`
+
+        let mut briefs = Vec::new();
+
+        self.database.disclosure.pending.retain(|brief, pending| {
+            if *pending == identifier {
+                briefs.push(*brief);
+                false
+            } else {
+                true
+            }
+        });
+
+        for brief in briefs {
+            match brief {
+                PendingBrief::Echo { source, origin } => {
+                    self.apply_disclosure_echo(source, origin, identifier, proposal.clone());
+                }
+                PendingBrief::Ready { source, origin } => {
+                    self.apply_disclosure_ready(source, origin, identifier, proposal.clone());
+                }
+            }
+        }
+    }
+}
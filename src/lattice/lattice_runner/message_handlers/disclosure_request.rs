@@ -0,0 +1,44 @@
+use crate::lattice::{
+    messages::{DisclosureReply, DisclosureRequest},
+    Element as LatticeElement, Instance as LatticeInstance, LatticeRunner, Message, MessageError,
+};
+
+use doomstack::Top;
+
+use talk::{crypto::KeyCard, unicast::Acknowledger};
+
+impl<Instance, Element> LatticeRunner<Instance, Element>
+where
+    Instance: LatticeInstance,
+    Element: LatticeElement,
+{
+    pub(in crate::lattice::lattice_runner) fn validate_disclosure_request(
+        &self,
+        _source: &KeyCard,
+        _message: &DisclosureRequest,
+    ) -> Result<(), Top<MessageError>> {
+        Ok(())
+    }
+
+    pub(in crate::lattice::lattice_runner) fn process_disclosure_request(
+        &mut self,
+        source: &KeyCard,
+        message: DisclosureRequest,
+        acknowledger: Acknowledger,
+    ) {
+        acknowledger.strong();
+
+        // Requests for unknown `Element`s are ignored: the requester
+        // can pull the `Element` from any other replica that echoed it
+        if let Some(proposal) = self.database.elements.get(&message.proposal).cloned() {
+            let reply = DisclosureReply { proposal };
+
+            self.sender.spawn_push(
+                source.identity(),
+                self.envelope(Message::DisclosureReply(reply)),
+                self.configuration.response.clone(),
+                &self.fuse,
+            );
+        }
+    }
+}
@@ -3,7 +3,7 @@ use crate::lattice::{
     Element as LatticeElement, Instance as LatticeInstance, LatticeRunner, Message, MessageError,
 };
 
-use doomstack::{here, ResultExt, Top};
+use doomstack::Top;
 
 use talk::{broadcast::BestEffort, crypto::KeyCard, unicast::Acknowledger};
 
@@ -14,15 +14,21 @@ where
 {
     pub(in crate::lattice::lattice_runner) fn validate_disclosure_send(
         &self,
-        _source: &KeyCard,
+        source: &KeyCard,
         message: &DisclosureSend<Element>,
     ) -> Result<(), Top<MessageError>> {
-        match message {
-            DisclosureSend::Brief { .. } => Ok(()),
-            DisclosureSend::Expanded { proposal } => proposal
-                .validate(&self.discovery, &self.view)
-                .pot(MessageError::InvalidElement, here!()),
-        }
+        let (identifier, signature) = match message {
+            DisclosureSend::Brief {
+                proposal,
+                signature,
+            } => (*proposal, signature),
+            DisclosureSend::Expanded {
+                proposal,
+                signature,
+            } => (proposal.identifier(), signature),
+        };
+
+        self.verify_disclosure(source, identifier, signature)
     }
 
     pub(in crate::lattice::lattice_runner) fn process_disclosure_send(
@@ -33,51 +39,85 @@ where
     ) {
         let source = source.identity();
 
-        let (identifier, proposal) = match message {
+        // Only the first disclosure of `source` is echoed: further disclosures serve
+        // only as evidence of `source` equivocating, and their `Element`s are not stored
+
+        if self.database.disclosure.echoes_sent.contains(&source) {
+            let (identifier, signature) = match message {
+                DisclosureSend::Brief {
+                    proposal,
+                    signature,
+                } => (proposal, signature),
+                DisclosureSend::Expanded {
+                    proposal,
+                    signature,
+                } => (proposal.identifier(), signature),
+            };
+
+            self.record_disclosure(source, identifier, signature);
+            self.statistics.record_duplicate();
+
+            acknowledger.strong();
+            return;
+        }
+
+        let (identifier, proposal, signature) = match message {
             DisclosureSend::Brief {
                 proposal: identifier,
+                signature,
             } => {
+                self.record_disclosure(source, identifier, signature);
+
                 let proposal = match self.database.elements.get(&identifier).cloned() {
                     Some(proposal) => proposal,
                     None => {
+                        self.statistics.record_expansion();
                         acknowledger.expand();
                         return;
                     }
                 };
 
-                (identifier, proposal)
+                (identifier, proposal, signature)
             }
-            DisclosureSend::Expanded { proposal } => {
+            DisclosureSend::Expanded {
+                proposal,
+                signature,
+            } => {
                 let identifier = proposal.identifier();
 
+                self.record_disclosure(source, identifier, signature);
                 self.database.elements.insert(identifier, proposal.clone());
 
-                (identifier, proposal)
+                (identifier, proposal, signature)
             }
         };
 
         acknowledger.strong();
 
-        if self.database.disclosure.echoes_sent.insert(source) {
-            let brief = DisclosureEcho::Brief {
-                origin: source,
-                proposal: identifier,
-            };
+        self.database.disclosure.echoes_sent.insert(source);
 
-            let expanded = DisclosureEcho::Expanded {
-                origin: source,
-                proposal,
-            };
+        let brief = DisclosureEcho::Brief {
+            origin: source,
+            proposal: identifier,
+            signature,
+        };
+
+        let expanded = DisclosureEcho::Expanded {
+            origin: source,
+            proposal,
+            signature,
+        };
 
-            let broadcast = BestEffort::brief(
-                self.sender.clone(),
-                self.view.members().keys().cloned(),
-                Message::DisclosureEcho(brief),
-                Message::DisclosureEcho(expanded),
-                self.configuration.broadcast.clone(),
-            );
+        self.checkpoint();
 
-            broadcast.spawn(&self.fuse);
-        }
+        let broadcast = BestEffort::brief(
+            self.sender.clone(),
+            self.view.members().keys().cloned(),
+            self.envelope(Message::DisclosureEcho(brief)),
+            self.envelope(Message::DisclosureEcho(expanded)),
+            self.configuration.broadcast.clone(),
+        );
+
+        broadcast.spawn(&self.fuse);
     }
 }
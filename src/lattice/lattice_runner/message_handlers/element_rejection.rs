@@ -0,0 +1,41 @@
+use crate::{
+    crypto::Identify,
+    lattice::{
+        messages::ElementRejection, Element as LatticeElement, Instance as LatticeInstance,
+        LatticeRunner, MessageError,
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use talk::{crypto::KeyCard, unicast::Acknowledger};
+
+impl<Instance, Element> LatticeRunner<Instance, Element>
+where
+    Instance: LatticeInstance,
+    Element: LatticeElement,
+{
+    pub(in crate::lattice::lattice_runner) fn validate_element_rejection(
+        &self,
+        _source: &KeyCard,
+        message: &ElementRejection,
+    ) -> Result<(), Top<MessageError>> {
+        // Rejections are purely diagnostic, and do not affect the state of the agreement:
+        // only rejections issued in `self.view` are counted
+        if message.view != self.view.identifier() {
+            return MessageError::ForeignView.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+
+    pub(in crate::lattice::lattice_runner) fn process_element_rejection(
+        &mut self,
+        _source: &KeyCard,
+        _message: ElementRejection,
+        acknowledger: Acknowledger,
+    ) {
+        acknowledger.strong();
+        self.rejections.record_received();
+    }
+}
@@ -3,4 +3,7 @@ mod certification_request;
 mod certification_update;
 mod disclosure_echo;
 mod disclosure_ready;
+mod disclosure_reply;
+mod disclosure_request;
 mod disclosure_send;
+mod element_rejection;
@@ -2,29 +2,42 @@ use crate::{
     crypto::{Aggregator, Certificate},
     discovery::Client,
     lattice::{
-        Decision, Element as LatticeElement, Instance as LatticeInstance, Message, MessageError,
+        lattice_agreement_settings::Thresholds, CheckpointStore, Decision, DisclosureEquivocation,
+        Element as LatticeElement, Envelope, Instance as LatticeInstance, Message, MessageError,
+        Rejections, Statistics,
     },
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
+use serde::{Deserialize, Serialize};
+
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use talk::{
     broadcast::BestEffortSettings,
-    crypto::{primitives::hash::Hash, Identity, KeyCard, KeyChain},
+    crypto::{
+        primitives::{hash::Hash, sign::Signature},
+        Identity, KeyCard, KeyChain,
+    },
     sync::fuse::Fuse,
-    unicast::{Acknowledgement, Acknowledger, PartialPushSettings, PushSettings, Receiver, Sender},
+    unicast::{Acknowledgement, Acknowledger, PartialPushSettings, PushSettings, Sender},
 };
 
-use tokio::sync::oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender};
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender},
+};
 
-type ProposalInlet<Element> = OneshotSender<(Element, ResultInlet)>;
-type ProposalOutlet<Element> = OneshotReceiver<(Element, ResultInlet)>;
+type ProposalInlet<Element> = UnboundedSender<(Element, ResultInlet)>;
+type ProposalOutlet<Element> = UnboundedReceiver<(Element, ResultInlet)>;
+
+type InboxOutlet = UnboundedReceiver<(Identity, Envelope, Acknowledger)>;
 
 type ResultInlet = OneshotSender<bool>;
 type ResultOutlet = OneshotReceiver<bool>;
@@ -32,9 +45,18 @@ type ResultOutlet = OneshotReceiver<bool>;
 type DecisionInlet<Element> = OneshotSender<(Vec<Element>, Certificate)>;
 type DecisionOutlet<Element> = OneshotReceiver<(Vec<Element>, Certificate)>;
 
+type EvidenceInlet<Instance> = UnboundedSender<DisclosureEquivocation<Instance>>;
+
+type AbortOutlet<Element> = UnboundedReceiver<PartialInlet<Element>>;
+type PartialInlet<Element> = OneshotSender<Vec<Element>>;
+
+type ValidationInlet<Element> = UnboundedSender<(KeyCard, Message<Element>, Acknowledger, bool)>;
+type ValidationOutlet<Element> = UnboundedReceiver<(KeyCard, Message<Element>, Acknowledger, bool)>;
+
 pub(in crate::lattice) struct LatticeRunner<Instance: LatticeInstance, Element: LatticeElement> {
     view: View,
     instance: Instance,
+    instance_identifier: Hash,
 
     keychain: KeyChain,
 
@@ -42,17 +64,27 @@ pub(in crate::lattice) struct LatticeRunner<Instance: LatticeInstance, Element:
     database: Database<Instance, Element>,
 
     discovery: Arc<Client>,
-    sender: Sender<Message<Element>>,
-    receiver: Receiver<Message<Element>>,
+    sender: Sender<Envelope>,
+    inbox_outlet: InboxOutlet,
 
     proposal_outlet: ProposalOutlet<Element>,
     decision_inlet: Option<DecisionInlet<Element>>,
+    evidence_inlet: EvidenceInlet<Instance>,
+    abort_outlet: AbortOutlet<Element>,
+
+    // Outcomes of `Element` validations offloaded to the crypto pool
+    validation_inlet: ValidationInlet<Element>,
+    validation_outlet: ValidationOutlet<Element>,
+    pending_validations: usize,
 
     configuration: Configuration,
+    rejections: Rejections,
+    statistics: Statistics,
+    checkpoints: Option<Arc<dyn CheckpointStore>>,
     fuse: Fuse,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
 pub(in crate::lattice) enum State {
     Disclosing,
     Proposing,
@@ -63,15 +95,33 @@ struct Database<Instance: LatticeInstance, Element: LatticeElement> {
     disclosure: DisclosureDatabase,
     certification: Option<CertificationDatabase<Instance>>,
 
+    // Bounded per origin (see `LatticeRunner::echo_superfluous`)
     elements: HashMap<Hash, Element>,
 
+    // Total weight of the origins of all delivered disclosures
     disclosures: usize,
     safe_set: BTreeSet<Hash>,
 
     proposed_set: BTreeSet<Hash>,
     accepted_set: BTreeSet<Hash>,
+
+    // source -> last time an `ElementRejection` was sent to source
+    rejections_sent: HashMap<Identity, Instant>,
+
+    // Identifiers of the decided `Element`s, along with their `Certificate`
+    // (kept to be checkpointed)
+    decision: Option<(BTreeSet<Hash>, Certificate)>,
+}
+
+// A brief echo or ready message whose `Element` is unknown to the local replica,
+// awaiting the corresponding `DisclosureReply`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(in crate::lattice::lattice_runner) enum PendingBrief {
+    Echo { source: Identity, origin: Identity },
+    Ready { source: Identity, origin: Identity },
 }
 
+#[derive(Serialize, Deserialize)]
 struct DisclosureDatabase {
     // `true` iff the local replica disclosed a value
     disclosed: bool,
@@ -84,8 +134,8 @@ struct DisclosureDatabase {
     // received an echo from source for _any_ message from origin
     echoes_collected: HashSet<(Identity, Identity)>,
 
-    // (origin, identifier) -> number of distinct echoes received
-    // (must be at least `self.view.quorum()` to issue a ready message)
+    // (origin, identifier) -> total weight of the distinct echoes received
+    // (must be at least `thresholds.echo` to issue a ready message)
     echo_support: HashMap<(Identity, Hash), usize>,
 
     // origin is in `ready_sent` iff the local replica issued a ready message
@@ -96,14 +146,26 @@ struct DisclosureDatabase {
     // received a ready message from source for _any_ message from origin
     ready_collected: HashSet<(Identity, Identity)>,
 
-    // (origin, identifier) -> number of distinct ready messages received
-    // (must be at least `self.view.plurality()` to issue a ready message)
-    // (must be at least `self.view.quorum()` to deliver)
+    // (origin, identifier) -> total weight of the distinct ready messages received
+    // (must be at least `thresholds.ready` to issue a ready message)
+    // (must be at least `thresholds.deliver` to deliver)
     ready_support: HashMap<(Identity, Hash), usize>,
 
     // origin is in `disclosures_delivered` iff the local replica has delivered
     // (the only possible) disclosure from origin
     delivered: HashSet<Identity>,
+
+    // brief -> identifier of the `Element` requested (by `DisclosureRequest`) to
+    // process brief (at most one brief per kind, source and origin)
+    pending: HashMap<PendingBrief, Hash>,
+
+    // origin -> first disclosure signed by origin, as sent by origin or relayed
+    // by an echo (signatures are verified before being recorded)
+    signatures: HashMap<Identity, (Hash, Signature)>,
+
+    // origin is in `equivocators` iff a `DisclosureEquivocation` was
+    // reported for origin
+    equivocators: HashSet<Identity>,
 }
 
 pub(in crate::lattice) struct CertificationDatabase<Instance: LatticeInstance> {
@@ -115,6 +177,8 @@ pub(in crate::lattice) struct CertificationDatabase<Instance: LatticeInstance> {
 struct Configuration {
     broadcast: BestEffortSettings,
     response: PushSettings,
+    thresholds: Thresholds,
+    rejection_interval: Duration,
 }
 
 #[derive(Doom)]
@@ -123,6 +187,10 @@ enum HandleError {
     ForeignSource,
     #[doom(description("Invalid message"))]
     InvalidMessage,
+    #[doom(description("Message from a recently rejected origin"))]
+    RateLimited,
+    #[doom(description("Failed to deserialize message"))]
+    MalformedMessage,
 }
 
 impl<Instance, Element> LatticeRunner<Instance, Element>
@@ -133,13 +201,22 @@ where
     pub fn new(
         view: View,
         instance: Instance,
+        instance_identifier: Hash,
         keychain: KeyChain,
         discovery: Arc<Client>,
-        sender: Sender<Message<Element>>,
-        receiver: Receiver<Message<Element>>,
+        sender: Sender<Envelope>,
+        inbox_outlet: InboxOutlet,
         proposal_outlet: ProposalOutlet<Element>,
         decision_inlet: DecisionInlet<Element>,
+        evidence_inlet: EvidenceInlet<Instance>,
+        abort_outlet: AbortOutlet<Element>,
         push_settings: PartialPushSettings,
+        response_push_settings: PartialPushSettings,
+        thresholds: Thresholds,
+        rejection_interval: Duration,
+        rejections: Rejections,
+        statistics: Statistics,
+        checkpoints: Option<Arc<dyn CheckpointStore>>,
     ) -> Self {
         let state = State::Disclosing;
 
@@ -153,6 +230,9 @@ where
                 ready_collected: HashSet::new(),
                 ready_support: HashMap::new(),
                 delivered: HashSet::new(),
+                pending: HashMap::new(),
+                signatures: HashMap::new(),
+                equivocators: HashSet::new(),
             },
 
             certification: None,
@@ -164,62 +244,129 @@ where
 
             proposed_set: BTreeSet::new(),
             accepted_set: BTreeSet::new(),
+
+            rejections_sent: HashMap::new(),
+
+            decision: None,
         };
 
         let configuration = Configuration {
             broadcast: BestEffortSettings {
-                push_settings: PushSettings::compose(
-                    Acknowledgement::Strong,
-                    push_settings.clone(),
-                ),
+                push_settings: PushSettings::compose(Acknowledgement::Strong, push_settings),
             },
-            response: PushSettings::compose(Acknowledgement::Weak, push_settings),
+            response: PushSettings::compose(Acknowledgement::Weak, response_push_settings),
+            thresholds,
+            rejection_interval,
         };
 
+        let (validation_inlet, validation_outlet) = mpsc::unbounded_channel();
+
         let fuse = Fuse::new();
 
         LatticeRunner {
             view,
             instance,
+            instance_identifier,
             keychain,
             state,
             database,
             discovery,
             sender,
-            receiver,
+            inbox_outlet,
             proposal_outlet,
             decision_inlet: Some(decision_inlet),
+            evidence_inlet,
+            abort_outlet,
+            validation_inlet,
+            validation_outlet,
+            pending_validations: 0,
             configuration,
+            rejections,
+            statistics,
+            checkpoints,
             fuse,
         }
     }
 
     pub async fn run(&mut self) {
-        let mut proposed = false;
-
         loop {
             tokio::select! {
-                Ok((proposal, result_inlet)) = &mut self.proposal_outlet, if !proposed => {
-                    proposed = true;
+                Some((proposal, result_inlet)) = self.proposal_outlet.recv() => {
                     self.handle_proposal(proposal, result_inlet);
                 }
 
-                (source, message, acknowledger) = self.receiver.receive() => {
-                    let _ = self.handle_message(source, message, acknowledger);
+                Some((source, envelope, acknowledger)) = self.inbox_outlet.recv() => {
+                    let _ = self.handle_envelope(source, envelope, acknowledger);
                 }
+
+                // `self.validation_inlet` is never dropped: polling `self.validation_outlet`
+                // only while validations are pending preserves the `else` branch
+                Some((source, message, acknowledger, valid)) = self.validation_outlet.recv(),
+                    if self.pending_validations > 0 =>
+                {
+                    self.pending_validations -= 1;
+                    let _ = self.handle_validation(source, message, acknowledger, valid);
+                }
+
+                Some(partial_inlet) = self.abort_outlet.recv() => {
+                    // Returning drops `self`, along with `self.fuse`: all
+                    // in-flight broadcasts and pushes are cancelled
+                    let _ = partial_inlet.send(self.safe_elements());
+                    return;
+                }
+
+                else => return,
             }
         }
     }
 
+    // `Element`s delivered (or disclosed) so far, in no particular order
+    fn safe_elements(&self) -> Vec<Element> {
+        self.database
+            .safe_set
+            .iter()
+            .filter_map(|identifier| self.database.elements.get(identifier))
+            .cloned()
+            .collect()
+    }
+
+    // Wraps `message` in an `Envelope` addressed to `self.instance`
+    pub(in crate::lattice::lattice_runner) fn envelope(
+        &self,
+        message: Message<Element>,
+    ) -> Envelope {
+        Envelope {
+            instance: self.instance_identifier,
+            message: bincode::serialize(&message).unwrap(),
+        }
+    }
+
     fn handle_proposal(&mut self, proposal: Element, result_inlet: ResultInlet) {
         if !self.disclosed() {
             self.disclose(proposal);
             let _ = result_inlet.send(true);
         } else {
-            let _ = result_inlet.send(false);
+            // Disclosure is one-shot: a re-proposal succeeds only if `proposal`
+            // is already part of the local proposal (e.g., a retry of a
+            // successful proposal, or the element that superseded it)
+            let proposed = self.database.proposed_set.contains(&proposal.identifier());
+
+            let _ = result_inlet.send(proposed);
         }
     }
 
+    fn handle_envelope(
+        &mut self,
+        source: Identity,
+        envelope: Envelope,
+        acknowledger: Acknowledger,
+    ) -> Result<(), Top<HandleError>> {
+        let message = bincode::deserialize::<Message<Element>>(envelope.message.as_slice())
+            .pot(HandleError::MalformedMessage, here!())?;
+
+        self.handle_message(source, message, acknowledger)
+    }
+
     fn handle_message(
         &mut self,
         source: Identity,
@@ -230,7 +377,24 @@ where
             self.validate_message(&keycard, &message)
                 .pot(HandleError::InvalidMessage, here!())?;
 
-            self.process_message(&keycard, message, acknowledger);
+            if self.requires_validation(&message) {
+                // An `Element` is validated only if its origin was not rejected recently:
+                // otherwise, a faulty origin could have its invalid `Element`s validated
+                // again and again (rejections are rate-limited, validations are not)
+                if let Some(element) =
+                    Self::carried_element(&message).map(|element| element.identifier())
+                {
+                    let origin = self.origin(&keycard, &message, element);
+
+                    if self.rejected_recently(&origin) {
+                        return HandleError::RateLimited.fail().spot(here!());
+                    }
+                }
+
+                self.offload_validation(keycard, message, acknowledger);
+            } else {
+                self.accept_message(&keycard, message, acknowledger);
+            }
 
             Ok(())
         } else {
@@ -238,6 +402,36 @@ where
         }
     }
 
+    // Message processing does not rely on the state observed by `validate_message`:
+    // `message` can be safely accepted even if that state changed while its
+    // `Element` was being validated
+    fn handle_validation(
+        &mut self,
+        source: KeyCard,
+        message: Message<Element>,
+        acknowledger: Acknowledger,
+        valid: bool,
+    ) -> Result<(), Top<HandleError>> {
+        if !valid {
+            self.reject(&source, &message);
+            return HandleError::InvalidMessage.fail().spot(here!());
+        }
+
+        self.accept_message(&source, message, acknowledger);
+
+        Ok(())
+    }
+
+    fn accept_message(
+        &mut self,
+        source: &KeyCard,
+        message: Message<Element>,
+        acknowledger: Acknowledger,
+    ) {
+        self.record_message(source, &message);
+        self.process_message(source, message, acknowledger);
+    }
+
     fn validate_message(
         &self,
         source: &KeyCard,
@@ -256,6 +450,27 @@ where
             Message::CertificationUpdate(message) => {
                 self.validate_certification_update(source, message)
             }
+            Message::ElementRejection(message) => self.validate_element_rejection(source, message),
+            Message::DisclosureRequest(message) => {
+                self.validate_disclosure_request(source, message)
+            }
+            Message::DisclosureReply(message) => self.validate_disclosure_reply(source, message),
+        }
+    }
+
+    fn record_message(&self, source: &KeyCard, message: &Message<Element>) {
+        self.statistics.record_source(source.identity());
+
+        match message {
+            Message::DisclosureSend(_) => self.statistics.record_proposal(),
+            Message::DisclosureEcho(_) => self.statistics.record_echo(),
+            Message::DisclosureReady(_) => self.statistics.record_ready(),
+            Message::CertificationRequest(_)
+            | Message::CertificationConfirmation(_)
+            | Message::CertificationUpdate(_) => self.statistics.record_certification_message(),
+            Message::ElementRejection(_)
+            | Message::DisclosureRequest(_)
+            | Message::DisclosureReply(_) => {}
         }
     }
 
@@ -284,6 +499,15 @@ where
             Message::CertificationUpdate(message) => {
                 self.process_certification_update(source, message, acknowledger);
             }
+            Message::ElementRejection(message) => {
+                self.process_element_rejection(source, message, acknowledger);
+            }
+            Message::DisclosureRequest(message) => {
+                self.process_disclosure_request(source, message, acknowledger);
+            }
+            Message::DisclosureReply(message) => {
+                self.process_disclosure_reply(source, message, acknowledger);
+            }
         }
     }
 }
@@ -291,5 +515,8 @@ where
 // Implementations
 
 mod certification;
+mod checkpoint;
 mod disclosure;
 mod message_handlers;
+mod rejection;
+mod validation;
@@ -0,0 +1,101 @@
+use crate::{
+    crypto::Identify,
+    lattice::{
+        lattice_runner::PendingBrief,
+        messages::{
+            DisclosureEcho, DisclosureReady, DisclosureReply, DisclosureSend, ElementRejection,
+            RejectionReason,
+        },
+        Element as LatticeElement, Instance as LatticeInstance, LatticeRunner, Message,
+    },
+};
+
+use std::time::Instant;
+
+use talk::crypto::{primitives::hash::Hash, Identity, KeyCard};
+
+impl<Instance, Element> LatticeRunner<Instance, Element>
+where
+    Instance: LatticeInstance,
+    Element: LatticeElement,
+{
+    // Called on `message`s carrying an invalid `Element`: reject the `Element` back
+    // to its origin (at most once every `rejection_interval`, so that a faulty origin
+    // cannot use its own invalid messages to amplify traffic)
+    pub(in crate::lattice::lattice_runner) fn reject(
+        &mut self,
+        source: &KeyCard,
+        message: &Message<Element>,
+    ) {
+        let element = match Self::carried_element(message) {
+            Some(element) => element.identifier(),
+            None => {
+                return;
+            }
+        };
+
+        let origin = self.origin(source, message, element);
+
+        if self.rejected_recently(&origin) {
+            return;
+        }
+
+        self.database.rejections_sent.insert(origin, Instant::now());
+
+        let rejection = ElementRejection {
+            element,
+            view: self.view.identifier(),
+            reason: RejectionReason::ElementInvalid,
+        };
+
+        self.sender.spawn_push(
+            origin,
+            self.envelope(Message::ElementRejection(rejection)),
+            self.configuration.response.clone(),
+            &self.fuse,
+        );
+
+        self.rejections.record_sent();
+    }
+
+    // `true` iff an `ElementRejection` was sent to `origin` within the last
+    // `rejection_interval`. While this holds, unvalidated `Element`s from
+    // `origin` are dropped without being validated (see `handle_message`).
+    pub(in crate::lattice::lattice_runner) fn rejected_recently(&self, origin: &Identity) -> bool {
+        self.database
+            .rejections_sent
+            .get(origin)
+            .map_or(false, |last| {
+                last.elapsed() < self.configuration.rejection_interval
+            })
+    }
+
+    // Returns the origin of the `Element` (identified by `element`) carried by `message`:
+    // echoes and readies relay the `Element` of their origin, while replies answer a
+    // `DisclosureRequest` issued for some origin's brief
+    pub(in crate::lattice::lattice_runner) fn origin(
+        &self,
+        source: &KeyCard,
+        message: &Message<Element>,
+        element: Hash,
+    ) -> Identity {
+        match message {
+            Message::DisclosureSend(DisclosureSend::Expanded { .. }) => source.identity(),
+            Message::DisclosureEcho(DisclosureEcho::Expanded { origin, .. }) => *origin,
+            Message::DisclosureReady(DisclosureReady::Expanded { origin, .. }) => *origin,
+            Message::DisclosureReply(DisclosureReply { .. }) => self
+                .database
+                .disclosure
+                .pending
+                .iter()
+                .find(|(_, pending)| **pending == element)
+                .map(|(brief, _)| match brief {
+                    PendingBrief::Echo { origin, .. } | PendingBrief::Ready { origin, .. } => {
+                        *origin
+                    }
+                })
+                .unwrap_or_else(|| source.identity()),
+            _ => source.identity(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{
+    crypto::Identify,
+    lattice::{
+        messages::{DisclosureEcho, DisclosureReady, DisclosureReply, DisclosureSend},
+        Element as LatticeElement, Instance as LatticeInstance, LatticeRunner, Message,
+    },
+};
+
+use talk::{crypto::KeyCard, unicast::Acknowledger};
+
+impl<Instance, Element> LatticeRunner<Instance, Element>
+where
+    Instance: LatticeInstance,
+    Element: LatticeElement,
+{
+    // Returns the `Element` carried by `message`, if any
+    pub(in crate::lattice::lattice_runner) fn carried_element(
+        message: &Message<Element>,
+    ) -> Option<&Element> {
+        match message {
+            Message::DisclosureSend(DisclosureSend::Expanded { proposal, .. }) => Some(proposal),
+            Message::DisclosureEcho(DisclosureEcho::Expanded { proposal, .. }) => Some(proposal),
+            Message::DisclosureReady(DisclosureReady::Expanded { proposal, .. }) => Some(proposal),
+            Message::DisclosureReply(DisclosureReply { proposal }) => Some(proposal),
+            _ => None,
+        }
+    }
+
+    // `true` iff `message` carries an `Element` that was not validated yet
+    // (all `Element`s in `self.database.elements` are valid)
+    pub(in crate::lattice::lattice_runner) fn requires_validation(
+        &self,
+        message: &Message<Element>,
+    ) -> bool {
+        Self::carried_element(message).map_or(false, |element| {
+            !self.database.elements.contains_key(&element.identifier())
+        })
+    }
+
+    // Validates the `Element` carried by `message` on the crypto pool, so that
+    // expensive validations do not stall the runner's loop. The outcome is
+    // re-injected through `self.validation_outlet`.
+    pub(in crate::lattice::lattice_runner) fn offload_validation(
+        &mut self,
+        source: KeyCard,
+        message: Message<Element>,
+        acknowledger: Acknowledger,
+    ) {
+        self.pending_validations += 1;
+
+        let discovery = self.discovery.clone();
+        let view = self.view.clone();
+        let validation_inlet = self.validation_inlet.clone();
+
+        rayon::spawn(move || {
+            let valid = Self::carried_element(&message)
+                .map_or(true, |element| element.validate(&discovery, &view).is_ok());
+
+            let _ = validation_inlet.send((source, message, acknowledger, valid));
+        });
+    }
+}
@@ -1,6 +1,9 @@
-use crate::lattice::messages::{
-    CertificationConfirmation, CertificationRequest, CertificationUpdate, DisclosureEcho,
-    DisclosureReady, DisclosureSend,
+use crate::{
+    data::enveloped,
+    lattice::messages::{
+        CertificationConfirmation, CertificationRequest, CertificationUpdate, DisclosureEcho,
+        DisclosureReady, DisclosureReply, DisclosureRequest, DisclosureSend, ElementRejection,
+    },
 };
 
 use doomstack::Doom;
@@ -8,6 +11,7 @@ use doomstack::Doom;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(in crate::lattice) enum Message<Element> {
     DisclosureSend(DisclosureSend<Element>),
     DisclosureEcho(DisclosureEcho<Element>),
@@ -15,8 +19,13 @@ pub(in crate::lattice) enum Message<Element> {
     CertificationRequest(CertificationRequest),
     CertificationConfirmation(CertificationConfirmation),
     CertificationUpdate(CertificationUpdate),
+    ElementRejection(ElementRejection),
+    DisclosureRequest(DisclosureRequest),
+    DisclosureReply(DisclosureReply<Element>),
 }
 
+enveloped!(Message<Element>);
+
 #[derive(Doom)]
 pub(in crate::lattice) enum MessageError {
     #[doom(description("`Message` contains an invalid `Element`"))]
@@ -41,4 +50,10 @@ pub(in crate::lattice) enum MessageError {
         "`Message::CertificationUpdate` contains `Element`s that overlap with `proposed_set`"
     ))]
     OverlappingCertificationUpdate,
+    #[doom(description("`Message` pertains to an origin foreign to the `View`"))]
+    ForeignOrigin,
+    #[doom(description(
+        "`Message::DisclosureReply` contains an `Element` that was not requested"
+    ))]
+    UnrequestedDisclosure,
 }
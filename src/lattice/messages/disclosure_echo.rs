@@ -1,9 +1,22 @@
 use serde::{Deserialize, Serialize};
 
-use talk::crypto::{primitives::hash::Hash, Identity};
+use talk::crypto::{
+    primitives::{hash::Hash, sign::Signature},
+    Identity,
+};
 
+// `signature` is relayed from origin's `DisclosureSend`: conflicting echoes
+// for the same origin make up a `DisclosureEquivocation`
 #[derive(Clone, Serialize, Deserialize)]
 pub(in crate::lattice) enum DisclosureEcho<Element> {
-    Brief { origin: Identity, proposal: Hash },
-    Expanded { origin: Identity, proposal: Element },
+    Brief {
+        origin: Identity,
+        proposal: Hash,
+        signature: Signature,
+    },
+    Expanded {
+        origin: Identity,
+        proposal: Element,
+        signature: Signature,
+    },
 }
@@ -0,0 +1,6 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(in crate::lattice) struct DisclosureReply<Element> {
+    pub proposal: Element,
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::primitives::hash::Hash;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(in crate::lattice) struct DisclosureRequest {
+    pub proposal: Hash,
+}
@@ -1,9 +1,16 @@
 use serde::{Deserialize, Serialize};
 
-use talk::crypto::primitives::hash::Hash;
+use talk::crypto::primitives::{hash::Hash, sign::Signature};
 
+// `signature` signs the `DisclosureStatement` of `proposal`
 #[derive(Clone, Serialize, Deserialize)]
 pub(in crate::lattice) enum DisclosureSend<Element> {
-    Brief { proposal: Hash },
-    Expanded { proposal: Element },
+    Brief {
+        proposal: Hash,
+        signature: Signature,
+    },
+    Expanded {
+        proposal: Element,
+        signature: Signature,
+    },
 }
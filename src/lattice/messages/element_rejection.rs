@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::primitives::hash::Hash;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(in crate::lattice) struct ElementRejection {
+    pub element: Hash, // Identifier of the rejected element
+    pub view: Hash,    // Identifier of the rejecting replica's view
+    pub reason: RejectionReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(in crate::lattice) enum RejectionReason {
+    // `Element::validate` failed
+    ElementInvalid,
+}
@@ -3,11 +3,17 @@ mod certification_request;
 mod certification_update;
 mod disclosure_echo;
 mod disclosure_ready;
+mod disclosure_reply;
+mod disclosure_request;
 mod disclosure_send;
+mod element_rejection;
 
 pub(in crate::lattice) use certification_confirmation::CertificationConfirmation;
 pub(in crate::lattice) use certification_request::CertificationRequest;
 pub(in crate::lattice) use certification_update::CertificationUpdate;
 pub(in crate::lattice) use disclosure_echo::DisclosureEcho;
 pub(in crate::lattice) use disclosure_ready::DisclosureReady;
+pub(in crate::lattice) use disclosure_reply::DisclosureReply;
+pub(in crate::lattice) use disclosure_request::DisclosureRequest;
 pub(in crate::lattice) use disclosure_send::DisclosureSend;
+pub(in crate::lattice) use element_rejection::{ElementRejection, RejectionReason};
@@ -1,20 +1,39 @@
+mod checkpoint_store;
 mod decision;
+mod disclosure_equivocation;
+mod disclosure_statement;
 mod element;
+mod envelope;
 mod instance;
 mod lattice_agreement;
+mod lattice_multiplexer;
 mod lattice_runner;
 mod message;
+mod rejections;
+mod statistics;
 
 mod messages;
 
 pub(crate) mod lattice_agreement_settings;
 
+use envelope::Envelope;
 use lattice_runner::LatticeRunner;
 use message::{Message, MessageError};
 
+#[allow(unused_imports)]
+pub(crate) use checkpoint_store::{
+    CheckpointStore, CheckpointStoreError, DirectoryCheckpointStore, MemoryCheckpointStore,
+};
+
 #[allow(unused_imports)]
 pub(crate) use decision::Decision;
 
+#[allow(unused_imports)]
+pub(crate) use disclosure_equivocation::{DisclosureEquivocation, DisclosureEquivocationError};
+
+#[allow(unused_imports)]
+pub(crate) use disclosure_statement::DisclosureStatement;
+
 #[allow(unused_imports)]
 pub(crate) use element::Element;
 
@@ -27,8 +46,20 @@ pub(crate) use instance::Instance;
 #[allow(unused_imports)]
 pub(crate) use lattice_agreement::LatticeAgreement;
 
+#[allow(unused_imports)]
+pub(crate) use lattice_agreement_settings::DisclosureThresholds;
+
 #[allow(unused_imports)]
 pub(crate) use lattice_agreement_settings::LatticeAgreementSettings;
 
+#[allow(unused_imports)]
+pub(crate) use lattice_multiplexer::{LatticeMultiplexer, LatticeMultiplexerError};
+
+#[allow(unused_imports)]
+pub(crate) use rejections::Rejections;
+
+#[allow(unused_imports)]
+pub(crate) use statistics::{Statistics, StatisticsSnapshot};
+
 #[cfg(test)]
 mod test;
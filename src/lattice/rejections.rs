@@ -0,0 +1,32 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+// Counts `ElementRejection`s sent and received by a `LatticeAgreement`. A replica
+// receiving rejections for its own `Element`s is likely misconfigured (or
+// validating against a different `View`) with respect to the rejecting replicas.
+// All clones of a `Rejections` share the same counts.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Rejections {
+    sent: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+}
+
+impl Rejections {
+    pub fn sent(&self) -> u64 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    pub(in crate::lattice) fn record_sent(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::view::View;
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use talk::crypto::Identity;
+
+// Protocol statistics of a `LatticeAgreement`. A slow decision with most members
+// `silent` points to stragglers, while a high count of `duplicates` or `expansions`
+// points to message loss (retransmitted messages, and brief messages whose expanded
+// counterpart was never received, respectively). All clones of a `Statistics`
+// share the same counts.
+#[derive(Debug, Clone)]
+pub(crate) struct Statistics {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    started: Instant,
+    members: Vec<Identity>,
+
+    proposals: AtomicU64,
+    echoes: AtomicU64,
+    readies: AtomicU64,
+    certification_messages: AtomicU64,
+
+    certifications: AtomicU64,
+
+    duplicates: AtomicU64,
+    expansions: AtomicU64,
+
+    heard: Mutex<HashSet<Identity>>,
+    decided: Mutex<Option<Duration>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StatisticsSnapshot {
+    // `DisclosureSend`s received
+    pub proposals: u64,
+    // `DisclosureEcho`s received
+    pub echoes: u64,
+    // `DisclosureReady`s received
+    pub readies: u64,
+    // Certification requests, confirmations and updates received
+    pub certification_messages: u64,
+    // Certification attempts by the local replica (each update restarts certification)
+    pub certifications: u64,
+    // Messages received more than once from the same source
+    pub duplicates: u64,
+    // Brief messages that required expansion
+    pub expansions: u64,
+    pub elapsed: Duration,
+    pub time_to_decision: Option<Duration>,
+    // Members of the `View` from which no valid message was received yet
+    pub silent: Vec<Identity>,
+}
+
+impl Statistics {
+    pub fn new(view: &View) -> Self {
+        Statistics {
+            inner: Arc::new(Inner {
+                started: Instant::now(),
+                members: view.members().keys().copied().collect(),
+                proposals: AtomicU64::new(0),
+                echoes: AtomicU64::new(0),
+                readies: AtomicU64::new(0),
+                certification_messages: AtomicU64::new(0),
+                certifications: AtomicU64::new(0),
+                duplicates: AtomicU64::new(0),
+                expansions: AtomicU64::new(0),
+                heard: Mutex::new(HashSet::new()),
+                decided: Mutex::new(None),
+            }),
+        }
+    }
+
+    pub fn snapshot(&self) -> StatisticsSnapshot {
+        let heard = self.inner.heard.lock().unwrap();
+
+        let silent = self
+            .inner
+            .members
+            .iter()
+            .filter(|member| !heard.contains(member))
+            .copied()
+            .collect();
+
+        StatisticsSnapshot {
+            proposals: self.inner.proposals.load(Ordering::Relaxed),
+            echoes: self.inner.echoes.load(Ordering::Relaxed),
+            readies: self.inner.readies.load(Ordering::Relaxed),
+            certification_messages: self.inner.certification_messages.load(Ordering::Relaxed),
+            certifications: self.inner.certifications.load(Ordering::Relaxed),
+            duplicates: self.inner.duplicates.load(Ordering::Relaxed),
+            expansions: self.inner.expansions.load(Ordering::Relaxed),
+            elapsed: self.inner.started.elapsed(),
+            time_to_decision: *self.inner.decided.lock().unwrap(),
+            silent,
+        }
+    }
+
+    pub(in crate::lattice) fn record_source(&self, source: Identity) {
+        self.inner.heard.lock().unwrap().insert(source);
+    }
+
+    pub(in crate::lattice) fn record_proposal(&self) {
+        self.inner.proposals.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_echo(&self) {
+        self.inner.echoes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_ready(&self) {
+        self.inner.readies.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_certification_message(&self) {
+        self.inner
+            .certification_messages
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_certification(&self) {
+        self.inner.certifications.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_duplicate(&self) {
+        self.inner.duplicates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_expansion(&self) {
+        self.inner.expansions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(in crate::lattice) fn record_decision(&self) {
+        let mut decided = self.inner.decided.lock().unwrap();
+
+        if decided.is_none() {
+            *decided = Some(self.inner.started.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::test::InstallGenerator;
+
+    #[test]
+    fn silent() {
+        let view = InstallGenerator::new(4).view(4);
+        let statistics = Statistics::new(&view);
+
+        let members = view.members().keys().copied().collect::<Vec<_>>();
+
+        statistics.record_source(members[0]);
+        statistics.record_source(members[2]);
+        statistics.record_echo();
+        statistics.record_echo();
+
+        let snapshot = statistics.snapshot();
+
+        assert_eq!(snapshot.echoes, 2);
+        assert_eq!(snapshot.silent, vec![members[1], members[3]]);
+        assert!(snapshot.time_to_decision.is_none());
+
+        statistics.record_decision();
+        let decision = statistics.snapshot().time_to_decision.unwrap();
+
+        // Only the first decision is recorded
+        statistics.record_decision();
+        assert_eq!(statistics.snapshot().time_to_decision, Some(decision));
+    }
+}
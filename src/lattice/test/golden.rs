@@ -0,0 +1,107 @@
+use crate::{
+    crypto::Identify,
+    data::golden,
+    lattice::{
+        messages::{
+            CertificationConfirmation, CertificationRequest, CertificationUpdate, DisclosureEcho,
+            DisclosureReady, DisclosureRequest, DisclosureSend, ElementRejection, RejectionReason,
+        },
+        Decision, DisclosureStatement, Message,
+    },
+    view::View,
+};
+
+use std::collections::BTreeSet;
+
+use talk::crypto::{primitives::hash, KeyChain};
+
+#[test]
+fn message() {
+    let keychains = golden::keychains();
+    let view = View::genesis(keychains.iter().map(KeyChain::keycard));
+
+    let instance = 7u64;
+    let proposal = 42u64;
+
+    let statement = DisclosureStatement {
+        view: view.identifier(),
+        instance,
+        proposal: hash::hash(&proposal).unwrap(),
+    };
+
+    let origin = keychains[0].keycard().identity();
+    let signature = keychains[0].sign(&statement).unwrap();
+
+    golden::check(
+        "lattice_message_disclosure_send",
+        &Message::DisclosureSend(DisclosureSend::Expanded {
+            proposal,
+            signature: signature.clone(),
+        }),
+    );
+
+    golden::check(
+        "lattice_message_disclosure_echo",
+        &Message::<u64>::DisclosureEcho(DisclosureEcho::Brief {
+            origin,
+            proposal: statement.proposal,
+            signature,
+        }),
+    );
+
+    golden::check(
+        "lattice_message_disclosure_ready",
+        &Message::DisclosureReady(DisclosureReady::Expanded { origin, proposal }),
+    );
+
+    golden::check(
+        "lattice_message_disclosure_request",
+        &Message::<u64>::DisclosureRequest(DisclosureRequest {
+            proposal: statement.proposal,
+        }),
+    );
+
+    let elements = vec![statement.proposal]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+
+    golden::check(
+        "lattice_message_certification_request",
+        &Message::<u64>::CertificationRequest(CertificationRequest {
+            elements: elements.clone(),
+        }),
+    );
+
+    let decision = Decision {
+        view: view.identifier(),
+        instance,
+        elements: elements.clone(),
+    };
+
+    let identifier = hash::hash(&elements).unwrap();
+
+    golden::check(
+        "lattice_message_certification_confirmation",
+        &Message::<u64>::CertificationConfirmation(CertificationConfirmation {
+            identifier,
+            signature: keychains[1].multisign(&decision).unwrap(),
+        }),
+    );
+
+    golden::check(
+        "lattice_message_certification_update",
+        &Message::<u64>::CertificationUpdate(CertificationUpdate {
+            identifier,
+            differences: elements,
+        }),
+    );
+
+    golden::check(
+        "lattice_message_element_rejection",
+        &Message::<u64>::ElementRejection(ElementRejection {
+            element: statement.proposal,
+            view: view.identifier(),
+            reason: RejectionReason::ElementInvalid,
+        }),
+    );
+}
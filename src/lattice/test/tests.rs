@@ -1,10 +1,15 @@
 use crate::{
     crypto::Identify,
     discovery::{Client, ClientSettings, Mode, Server},
-    lattice::{Element as LatticeElement, LatticeAgreement},
+    lattice::{
+        DisclosureThresholds, Element as LatticeElement, LatticeAgreement,
+        LatticeAgreementSettings, LatticeMultiplexer, MemoryCheckpointStore,
+    },
     view::View,
 };
 
+use doomstack::Doom;
+
 use serde::{Deserialize, Serialize};
 
 use std::{
@@ -12,6 +17,7 @@ use std::{
     iter::{self, FromIterator, Iterator},
     net::Ipv4Addr,
     sync::Arc,
+    time::Duration,
 };
 
 use talk::{
@@ -22,6 +28,8 @@ use talk::{
     net::test::System,
 };
 
+use tokio::time;
+
 pub(crate) async fn setup_discovery(
     genesis: View,
     mode: Mode,
@@ -72,6 +80,30 @@ impl Identify for Element {
     }
 }
 
+// `Picky(13)` is invalid
+#[derive(PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Clone, Debug)]
+struct Picky(u32);
+
+impl LatticeElement for Picky {
+    fn validate(
+        &self,
+        _client: &crate::discovery::Client,
+        _view: &crate::view::View,
+    ) -> Result<(), doomstack::Top<crate::lattice::ElementError>> {
+        if self.0 == 13 {
+            crate::lattice::ElementError::ElementInvalid.fail()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Identify for Picky {
+    fn identifier(&self) -> Hash {
+        hash::hash(&self).unwrap()
+    }
+}
+
 async fn lattice_run() {
     let keychains = (0..10).map(|_| KeyChain::random()).collect::<Vec<_>>();
     let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
@@ -99,6 +131,7 @@ async fn lattice_run() {
                 listener,
                 Default::default(),
             )
+            .unwrap()
         })
         .collect::<Vec<_>>();
 
@@ -109,6 +142,10 @@ async fn lattice_run() {
     let mut decisions = Vec::new();
     for lattice in lattices.iter_mut() {
         let (decision, _certificate) = lattice.decide().await;
+
+        // Decisions are stable across calls
+        assert_eq!(lattice.try_decide().unwrap().0, decision);
+
         decisions.push(decision);
     }
 
@@ -130,3 +167,325 @@ async fn develop() {
         lattice_run().await;
     }
 }
+
+#[tokio::test]
+async fn repeated_proposals() {
+    let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+    let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
+    let (_server, mut clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let System {
+        mut connectors,
+        mut listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    let mut lattice = LatticeAgreement::<i32, Element>::new(
+        genesis,
+        0,
+        keychains[0].clone(),
+        Arc::new(clients.next().unwrap()),
+        connectors.remove(0),
+        listeners.remove(0),
+        Default::default(),
+    )
+    .unwrap();
+
+    lattice.propose(Element(0)).await.unwrap();
+
+    // Retrying a successful proposal succeeds, proposing a second element does not
+
+    lattice.propose(Element(0)).await.unwrap();
+    assert!(lattice.propose(Element(1)).await.is_err());
+}
+
+#[tokio::test]
+async fn invalid_thresholds() {
+    let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+    let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
+    let (_server, mut clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let System {
+        mut connectors,
+        mut listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    // A single echo cannot guarantee the consistency of disclosure
+    let settings = LatticeAgreementSettings {
+        disclosure_thresholds: DisclosureThresholds {
+            echo: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert!(LatticeAgreement::<i32, Element>::new(
+        genesis,
+        0,
+        keychains[0].clone(),
+        Arc::new(clients.next().unwrap()),
+        connectors.remove(0),
+        listeners.remove(0),
+        settings,
+    )
+    .is_err());
+}
+
+#[tokio::test]
+async fn abort() {
+    let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+    let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
+    let (_server, mut clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let System {
+        mut connectors,
+        mut listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    let mut lattice = LatticeAgreement::<i32, Element>::new(
+        genesis,
+        0,
+        keychains[0].clone(),
+        Arc::new(clients.next().unwrap()),
+        connectors.remove(0),
+        listeners.remove(0),
+        Default::default(),
+    )
+    .unwrap();
+
+    lattice.propose(Element(0)).await.unwrap();
+
+    // No other replica is running: the disclosed element is the only safe one
+
+    assert!(lattice.try_decide().is_none());
+    assert_eq!(lattice.abort().await, vec![Element(0)]);
+}
+
+#[tokio::test]
+async fn resume() {
+    let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+    let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
+    let (_server, mut clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let checkpoints = MemoryCheckpointStore::new();
+
+    let System {
+        connectors,
+        listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    let mut lattices = keychains
+        .iter()
+        .cloned()
+        .zip(clients.by_ref())
+        .zip(connectors)
+        .zip(listeners)
+        .enumerate()
+        .map(|(index, (((keychain, client), connector), listener))| {
+            if index == 0 {
+                LatticeAgreement::<i32, Element>::resume(
+                    genesis.clone(),
+                    0,
+                    keychain,
+                    Arc::new(client),
+                    connector,
+                    listener,
+                    Default::default(),
+                    Arc::new(checkpoints.clone()),
+                )
+                .unwrap()
+            } else {
+                LatticeAgreement::<i32, Element>::new(
+                    genesis.clone(),
+                    0,
+                    keychain,
+                    Arc::new(client),
+                    connector,
+                    listener,
+                    Default::default(),
+                )
+                .unwrap()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for (proposal, lattice) in lattices.iter_mut().enumerate() {
+        lattice.propose(Element(proposal as u32)).await.unwrap();
+    }
+
+    let (decision, _certificate) = lattices[0].decide().await;
+
+    // Simulate a crash of replica 0, followed by a restart
+
+    drop(lattices);
+
+    let System {
+        mut connectors,
+        mut listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    let mut lattice = LatticeAgreement::<i32, Element>::resume(
+        genesis,
+        0,
+        keychains[0].clone(),
+        Arc::new(clients.next().unwrap()),
+        connectors.remove(0),
+        listeners.remove(0),
+        Default::default(),
+        Arc::new(checkpoints),
+    )
+    .unwrap();
+
+    // The decision is restored, and no second element is disclosed
+
+    assert_eq!(lattice.decide().await.0, decision);
+
+    lattice.propose(Element(0)).await.unwrap();
+    assert!(lattice.propose(Element(100)).await.is_err());
+}
+
+#[tokio::test]
+async fn multiplexed() {
+    let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+    let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
+    let (_server, clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let System {
+        connectors,
+        listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    let mut lattices = keychains
+        .into_iter()
+        .zip(clients)
+        .zip(connectors)
+        .zip(listeners)
+        .map(|(((keychain, client), connector), listener)| {
+            let client = Arc::new(client);
+
+            let multiplexer = LatticeMultiplexer::new(
+                connector,
+                listener,
+                Default::default(),
+                Default::default(),
+            );
+
+            let lattices = (0..2)
+                .map(|instance| {
+                    LatticeAgreement::<i32, Element>::multiplexed(
+                        genesis.clone(),
+                        instance,
+                        keychain.clone(),
+                        client.clone(),
+                        &multiplexer,
+                        Default::default(),
+                    )
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            // Each `Instance` can be registered only once
+            assert!(LatticeAgreement::<i32, Element>::multiplexed(
+                genesis.clone(),
+                0,
+                keychain,
+                client,
+                &multiplexer,
+                Default::default(),
+            )
+            .is_err());
+
+            lattices
+        })
+        .collect::<Vec<_>>();
+
+    for (replica, lattices) in lattices.iter_mut().enumerate() {
+        for (instance, lattice) in lattices.iter_mut().enumerate() {
+            lattice
+                .propose(Element((instance * 100 + replica) as u32))
+                .await
+                .unwrap();
+        }
+    }
+
+    // Messages of each instance are delivered to that instance only
+
+    for lattices in lattices.iter_mut() {
+        for (instance, lattice) in lattices.iter_mut().enumerate() {
+            let (decision, _certificate) = lattice.decide().await;
+
+            assert!(decision
+                .iter()
+                .all(|Element(element)| (*element / 100) as usize == instance));
+        }
+    }
+}
+
+#[tokio::test]
+async fn rejections() {
+    let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+    let genesis = View::genesis(keychains.iter().map(KeyChain::keycard));
+    let (_server, clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let System {
+        connectors,
+        listeners,
+        ..
+    } = System::setup_with_keychains(keychains.clone()).await;
+
+    let mut lattices = keychains
+        .into_iter()
+        .zip(clients)
+        .zip(connectors)
+        .zip(listeners)
+        .map(|(((keychain, client), connector), listener)| {
+            LatticeAgreement::<i32, Picky>::new(
+                genesis.clone(),
+                0,
+                keychain,
+                Arc::new(client),
+                connector,
+                listener,
+                LatticeAgreementSettings {
+                    rejection_interval: Duration::from_secs(3600),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // Replica 0 proposes an invalid element, which all other replicas reject
+
+    lattices[0].propose(Picky(13)).await.unwrap();
+
+    for (proposal, lattice) in lattices.iter_mut().enumerate().skip(1) {
+        lattice.propose(Picky(proposal as u32)).await.unwrap();
+    }
+
+    for lattice in lattices.iter_mut().skip(1) {
+        let (decision, _certificate) = lattice.decide().await;
+        assert!(!decision.contains(&Picky(13)));
+    }
+
+    // Each rejecting replica notifies replica 0 (the origin of the invalid element)
+    // exactly once, no matter how many times it receives the invalid element
+
+    let origin = lattices[0].rejections().clone();
+
+    while origin.received() < 3 {
+        time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(origin.sent(), 0);
+
+    for lattice in lattices.iter().skip(1) {
+        assert_eq!(lattice.rejections().sent(), 1);
+        assert_eq!(lattice.rejections().received(), 0);
+    }
+}
@@ -1,6 +1,9 @@
 #[allow(dead_code)]
 mod account;
 
+#[allow(dead_code)]
+mod benchmark;
+
 #[allow(dead_code)]
 mod brokers;
 
@@ -19,6 +22,9 @@ mod database;
 #[allow(dead_code)]
 mod discovery;
 
+#[allow(dead_code)]
+mod handles;
+
 #[allow(dead_code)]
 mod lattice;
 
@@ -28,9 +34,22 @@ mod prepare;
 #[allow(dead_code)]
 mod processing;
 
+// Per-role runtimes, for executables separating network I/O, crypto and maintenance
+pub mod runtime;
+
+#[allow(dead_code)]
+mod self_test;
+
 #[allow(dead_code)]
 mod signup;
 
+// Long-running soak test of an in-process deployment (see `soak::soak`)
+#[cfg(test)]
+mod soak;
+
+#[allow(dead_code)]
+mod telemetry;
+
 #[allow(dead_code)]
 mod view;
 
@@ -39,3 +58,52 @@ mod view_generator;
 
 #[allow(dead_code)]
 mod churn;
+
+// Typed client for signing up, preparing and committing through a `View`'s brokers
+pub mod client;
+
+// Deterministic fabrication of view histories, for integration tests and
+// downstream experiments (enabled by the `test-support` feature)
+#[cfg(feature = "test-support")]
+pub mod test_support {
+    pub use crate::{
+        crypto::Identify,
+        view::{
+            test::{generate_installs, last_installable, Client as FrameClient, InstallGenerator},
+            Change, Increment, Install, Transition, View, ViewError,
+        },
+    };
+}
+
+// Account data model, for applications constructing and inspecting operations.
+// These types are stable: their serialized form is that of the wire protocol,
+// hence new variants are only ever appended (matches must be non-exhaustive).
+pub mod model {
+    pub use crate::{
+        account::{
+            operations::{
+                Abandon, Close, CollectAll, Condition, Deposit, Fee, Fulfillment, Support, Withdraw,
+            },
+            Entry, Id, KeyDelegation, KeyDelegationError, Operation,
+        },
+        commit::{
+            BatchCompletion, BatchCredits, BatchCreditsError, Credit, CreditKind, CreditSummary,
+            Payload,
+        },
+    };
+}
+
+// Main types for orchestrating Carbon's subsystems from application code
+pub mod prelude {
+    pub use crate::{
+        crypto::Identify,
+        handles::{
+            BrokerHandle, BrokerHandleError, DrainError, Failure, ProcessorHandle,
+            ProcessorHandleError, ReadinessError,
+        },
+        processing::Namespace,
+        self_test::{SelfTest, SelfTestReport, SelfTestSettings},
+        telemetry::init_logger,
+        view::{Change, Install, Transition, View, ViewError},
+    };
+}
@@ -3,6 +3,7 @@ use crate::{
     crypto::{Aggregator, Certificate, Identify},
     discovery::Client,
     prepare::{BatchCommitShard, BatchCommitStatement},
+    processing::Namespace,
     view::View,
 };
 
@@ -12,12 +13,16 @@ use serde::{Deserialize, Serialize};
 
 use std::collections::{BTreeSet, HashMap};
 
-use talk::crypto::{primitives::hash::Hash, KeyCard};
+use talk::crypto::{
+    primitives::hash::{self, Hash},
+    KeyCard,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct BatchCommit {
     view: Hash,
     root: Hash,
+    exclusions: BTreeSet<Id>,
     patches: Vec<Patch>,
 }
 
@@ -40,7 +45,13 @@ pub(crate) enum BatchCommitError {
 }
 
 impl BatchCommit {
-    pub fn new<S>(view: View, root: Hash, shards: S) -> Self
+    pub fn new<S>(
+        view: View,
+        namespace: &Namespace,
+        root: Hash,
+        exclusions: BTreeSet<Id>,
+        shards: S,
+    ) -> Self
     where
         S: IntoIterator<Item = (KeyCard, BatchCommitShard)>,
     {
@@ -49,8 +60,13 @@ impl BatchCommit {
 
         for (committer, shard) in shards {
             let aggregator = aggregators.entry(shard.exceptions()).or_insert_with(|| {
-                let statement =
-                    BatchCommitStatement::new(view.identifier(), root, shard.exceptions());
+                let statement = BatchCommitStatement::new(
+                    namespace,
+                    view.identifier(),
+                    root,
+                    exclusions.clone(),
+                    shard.exceptions(),
+                );
 
                 Aggregator::new(view.clone(), statement)
             });
@@ -76,20 +92,28 @@ impl BatchCommit {
         BatchCommit {
             view,
             root,
+            exclusions,
             patches,
         }
     }
 
+    pub fn view(&self) -> Hash {
+        self.view
+    }
+
     pub fn root(&self) -> Hash {
         self.root
     }
 
     pub fn excepts(&self, id: Id) -> bool {
-        // In order to be excepted by `self`, it is sufficient for
-        // `id` to be in any of `self.patches`'s `exceptions`
-        self.patches
-            .iter()
-            .any(|patch| patch.exceptions.contains(&id))
+        // In order to be excepted by `self`, it is sufficient for `id` to
+        // be excluded from the batch's witness, or to be in any of
+        // `self.patches`'s `exceptions`
+        self.exclusions.contains(&id)
+            || self
+                .patches
+                .iter()
+                .any(|patch| patch.exceptions.contains(&id))
     }
 
     pub fn validate(&self, discovery: &Client) -> Result<(), Top<BatchCommitError>> {
@@ -99,8 +123,13 @@ impl BatchCommit {
             .spot(here!())?;
 
         for patch in self.patches.iter() {
-            let statement =
-                BatchCommitStatement::new(view.identifier(), self.root, patch.exceptions.clone());
+            let statement = BatchCommitStatement::new(
+                discovery.namespace(),
+                view.identifier(),
+                self.root,
+                self.exclusions.clone(),
+                patch.exceptions.clone(),
+            );
 
             // Verify only the validity of `patch.certificate`, regardless of power
             // (`distinct_power` is invoked later to determine if quorum is reached overall)
@@ -111,13 +140,19 @@ impl BatchCommit {
         }
 
         let power =
-            Certificate::distinct_power(self.patches.iter().map(|patch| &patch.certificate))
+            Certificate::distinct_power(&view, self.patches.iter().map(|patch| &patch.certificate))
                 .pot(BatchCommitError::OverlappingPatches, here!())?;
 
-        if power < view.quorum() {
+        if power < view.weighted_quorum() {
             return BatchCommitError::InsufficientPower.fail().spot(here!());
         }
 
         Ok(())
     }
 }
+
+impl Identify for BatchCommit {
+    fn identifier(&self) -> Hash {
+        hash::hash(&self).unwrap()
+    }
+}
@@ -0,0 +1,264 @@
+use crate::{
+    crypto::Identify,
+    discovery::Client,
+    prepare::{BatchCommit, BatchCommitError},
+    view::View,
+};
+
+use doomstack::Top;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+};
+
+use talk::crypto::primitives::hash::Hash;
+
+// A `BatchCommitCache` remembers which `BatchCommit`s were successfully validated,
+// so that validating the same `BatchCommit` again (e.g., across the `CommitProof`s
+// of a batch, or across retries of the same request) does not re-verify its
+// certificates. Entries are keyed by `(view, root)`, and matched against the
+// digest of the whole `BatchCommit`: a `BatchCommit` with the same `(view, root)`
+// but different exclusions or patches is validated from scratch. When full, the
+// oldest `(view, root)` is evicted first.
+pub(crate) struct BatchCommitCache {
+    capacity: usize,
+    database: Mutex<Database>,
+}
+
+struct Database {
+    verified: HashMap<(Hash, Hash), HashSet<Hash>>,
+    order: VecDeque<(Hash, Hash)>,
+}
+
+impl BatchCommitCache {
+    pub fn new(capacity: usize) -> Self {
+        BatchCommitCache {
+            capacity,
+            database: Mutex::new(Database {
+                verified: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn validate(
+        &self,
+        batch: &BatchCommit,
+        discovery: &Client,
+    ) -> Result<(), Top<BatchCommitError>> {
+        let key = (batch.view(), batch.root());
+        let digest = batch.identifier();
+
+        if self.contains(key, digest) {
+            return Ok(());
+        }
+
+        // Verification happens outside of `self.database`'s lock: concurrent
+        // validations of the same (uncached) `BatchCommit` are not serialized
+        batch.validate(discovery)?;
+
+        self.insert(key, digest);
+
+        Ok(())
+    }
+
+    // Forgets all `BatchCommit`s of `view` (e.g., once `view` is superseded)
+    pub fn invalidate(&self, view: Hash) {
+        let mut database = self.database.lock().unwrap();
+
+        database
+            .verified
+            .retain(|(entry_view, _), _| *entry_view != view);
+        database.order.retain(|(entry_view, _)| *entry_view != view);
+    }
+
+    // Invalidates the `BatchCommit`s of every view below the latest installed
+    // by `discovery`, starting beyond `height`. Never returns: meant to be
+    // spawned alongside the owner of the cache
+    pub async fn invalidate_on_install(&self, discovery: &Client, mut height: usize) {
+        loop {
+            height = discovery.beyond(height).await.destination().height();
+
+            let superseded = {
+                let database = self.database.lock().unwrap();
+
+                database
+                    .order
+                    .iter()
+                    .map(|(view, _)| *view)
+                    .filter(|view| View::get(*view).map_or(true, |view| view.height() < height))
+                    .collect::<HashSet<_>>()
+            };
+
+            for view in superseded {
+                self.invalidate(view);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut database = self.database.lock().unwrap();
+
+        database.verified.clear();
+        database.order.clear();
+    }
+
+    fn contains(&self, key: (Hash, Hash), digest: Hash) -> bool {
+        let database = self.database.lock().unwrap();
+
+        database
+            .verified
+            .get(&key)
+            .map_or(false, |digests| digests.contains(&digest))
+    }
+
+    fn insert(&self, key: (Hash, Hash), digest: Hash) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut database = self.database.lock().unwrap();
+
+        if !database.verified.contains_key(&key) {
+            if database.order.len() >= self.capacity {
+                if let Some(oldest) = database.order.pop_front() {
+                    database.verified.remove(&oldest);
+                }
+            }
+
+            database.order.push_back(key);
+        }
+
+        database.verified.entry(key).or_default().insert(digest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        discovery::Embedded, prepare::BatchCommitShard, processing::Namespace,
+        view::test::InstallGenerator,
+    };
+
+    use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+    use talk::crypto::primitives::hash;
+
+    use tokio::time;
+
+    fn commit(generator: &InstallGenerator, view: &View, root: Hash) -> BatchCommit {
+        let shards = generator
+            .keychains
+            .iter()
+            .zip(generator.keycards.iter())
+            .take(view.members().len())
+            .map(|(keychain, keycard)| {
+                let shard = BatchCommitShard::new(
+                    keychain,
+                    &Namespace::default(),
+                    view.identifier(),
+                    root,
+                    BTreeSet::new(),
+                    [],
+                );
+
+                (keycard.clone(), shard)
+            });
+
+        BatchCommit::new(
+            view.clone(),
+            &Namespace::default(),
+            root,
+            BTreeSet::new(),
+            shards,
+        )
+    }
+
+    #[tokio::test]
+    async fn validate() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = embedded.client(Default::default());
+
+        let root = hash::hash(&42u32).unwrap();
+
+        let batch = commit(&generator, &view, root);
+        let key = (view.identifier(), root);
+
+        let cache = BatchCommitCache::new(1);
+
+        cache.validate(&batch, &discovery).unwrap();
+        assert!(cache.contains(key, batch.identifier()));
+
+        // Invalid `BatchCommit`s are never cached
+
+        let insufficient = BatchCommit::new(
+            view.clone(),
+            &Namespace::default(),
+            root,
+            BTreeSet::new(),
+            [],
+        );
+
+        assert!(cache.validate(&insufficient, &discovery).is_err());
+        assert!(!cache.contains(key, insufficient.identifier()));
+
+        cache.invalidate(view.identifier());
+        assert!(!cache.contains(key, batch.identifier()));
+    }
+
+    #[tokio::test]
+    async fn invalidate_on_install() {
+        let generator = InstallGenerator::new(8);
+        let view = generator.view(4);
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = Arc::new(embedded.client(Default::default()));
+
+        let root = hash::hash(&42u32).unwrap();
+        let batch = commit(&generator, &view, root);
+        let key = (view.identifier(), root);
+
+        let cache = Arc::new(BatchCommitCache::new(8));
+        cache.validate(&batch, &discovery).unwrap();
+
+        {
+            let cache = cache.clone();
+            let discovery = discovery.clone();
+
+            tokio::spawn(async move {
+                cache.invalidate_on_install(&discovery, 4).await;
+            });
+        }
+
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(cache.contains(key, batch.identifier()));
+
+        discovery.publish(generator.install(4, 6, [])).await;
+        discovery.beyond(4).await;
+
+        // `view` is superseded: its `BatchCommit`s are eventually forgotten
+        while cache.contains(key, batch.identifier()) {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // `BatchCommit`s of the current view are retained
+        let current = generator.view(6);
+        let batch = commit(&generator, &current, root);
+
+        cache.validate(&batch, &discovery).unwrap();
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(cache.contains((current.identifier(), root), batch.identifier()));
+    }
+}
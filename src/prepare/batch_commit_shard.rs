@@ -3,6 +3,7 @@ use crate::{
     crypto::Identify,
     discovery::Client,
     prepare::{BatchCommitStatement, Equivocation, Prepare},
+    processing::Namespace,
     view::View,
 };
 
@@ -36,7 +37,14 @@ pub(crate) enum BatchCommitShardError {
 }
 
 impl BatchCommitShard {
-    pub fn new<E>(keychain: &KeyChain, view: Hash, root: Hash, exceptions: E) -> Self
+    pub fn new<E>(
+        keychain: &KeyChain,
+        namespace: &Namespace,
+        view: Hash,
+        root: Hash,
+        exclusions: BTreeSet<Id>,
+        exceptions: E,
+    ) -> Self
     where
         E: IntoIterator<Item = Equivocation>,
     {
@@ -45,7 +53,14 @@ impl BatchCommitShard {
             .map(|equivocation| (equivocation.id(), equivocation))
             .collect::<HashMap<_, _>>();
 
-        let statement = BatchCommitStatement::new(view, root, exceptions.keys().copied().collect());
+        let statement = BatchCommitStatement::new(
+            namespace,
+            view,
+            root,
+            exclusions,
+            exceptions.keys().copied().collect(),
+        );
+
         let signature = keychain.multisign(&statement).unwrap();
 
         BatchCommitShard {
@@ -58,6 +73,10 @@ impl BatchCommitShard {
         self.exceptions.keys().copied().collect()
     }
 
+    pub fn equivocations(&self) -> impl Iterator<Item = &Equivocation> {
+        self.exceptions.values()
+    }
+
     pub fn signature(&self) -> MultiSignature {
         self.signature.clone()
     }
@@ -67,6 +86,7 @@ impl BatchCommitShard {
         discovery: &Client,
         view: &View,
         root: Hash,
+        exclusions: &BTreeSet<Id>,
         prepares: &[Prepare],
         committer: &KeyCard,
     ) -> Result<(), Top<BatchCommitShardError>> {
@@ -88,7 +108,13 @@ impl BatchCommitShard {
         }
 
         let exceptions = self.exceptions.keys().copied().collect();
-        let statement = BatchCommitStatement::new(view.identifier(), root, exceptions);
+        let statement = BatchCommitStatement::new(
+            discovery.namespace(),
+            view.identifier(),
+            root,
+            exclusions.clone(),
+            exceptions,
+        );
 
         self.signature
             .verify([committer], &statement)
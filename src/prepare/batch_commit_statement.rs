@@ -1,4 +1,4 @@
-use crate::{account::Id, crypto::Header};
+use crate::{account::Id, crypto::Header, processing::Namespace};
 
 use serde::Serialize;
 
@@ -8,16 +8,26 @@ use talk::crypto::{primitives::hash::Hash, Statement};
 
 #[derive(Debug, Clone, Serialize)]
 pub(crate) struct BatchCommitStatement {
+    namespace: Namespace,
     view: Hash,
     root: Hash,
+    exclusions: BTreeSet<Id>,
     exceptions: BTreeSet<Id>,
 }
 
 impl BatchCommitStatement {
-    pub fn new(view: Hash, root: Hash, exceptions: BTreeSet<Id>) -> Self {
+    pub fn new(
+        namespace: &Namespace,
+        view: Hash,
+        root: Hash,
+        exclusions: BTreeSet<Id>,
+        exceptions: BTreeSet<Id>,
+    ) -> Self {
         BatchCommitStatement {
+            namespace: namespace.clone(),
             view,
             root,
+            exclusions,
             exceptions,
         }
     }
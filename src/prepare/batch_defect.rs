@@ -0,0 +1,173 @@
+use crate::account::Id;
+
+use serde::{Deserialize, Serialize};
+
+// A `BatchDefect` locates an element that makes a batch malformed. Replicas
+// reject malformed batches as a whole, reporting all their defects, so that
+// brokers can identify (and evict) the offending elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct BatchDefect {
+    pub index: usize,
+    pub reason: DefectReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum DefectReason {
+    // The element's `Id` is smaller than that of a preceding element
+    UnsortedId,
+    // The element's `Id` equals that of a preceding element
+    DuplicateId,
+    // A delegation refers to an index beyond the end of the batch
+    DelegationOutOfRange,
+    // The element has no individual signature slot
+    SignatureMissing,
+    // An individual signature slot lies beyond the end of the batch
+    SignatureOutOfRange,
+}
+
+impl DefectReason {
+    // Determines whether or not the defect lies in an element of the batch (and
+    // can be fixed by evicting the element), rather than in a dangling index
+    pub fn in_element(&self) -> bool {
+        matches!(
+            self,
+            DefectReason::UnsortedId | DefectReason::DuplicateId | DefectReason::SignatureMissing
+        )
+    }
+}
+
+impl BatchDefect {
+    // Batches must be strictly increasing by `Id` (this ensures searchability and
+    // non-duplication of `Id`s), each element must have exactly one individual signature
+    // slot (out of `signatures`), and delegations must refer to elements of the batch.
+    // Each element whose `Id` does not exceed all preceding (non-defective) `Id`s is
+    // defective: evicting all defective elements always leaves a well-formed batch.
+    pub fn diagnose<I, D>(ids: I, signatures: usize, delegations: D) -> Vec<BatchDefect>
+    where
+        I: IntoIterator<Item = Id>,
+        D: IntoIterator<Item = usize>,
+    {
+        let mut defects = Vec::new();
+
+        let mut len = 0;
+        let mut top = None;
+
+        for (index, id) in ids.into_iter().enumerate() {
+            len = index + 1;
+
+            let reason = match top {
+                Some(top) if id < top => DefectReason::UnsortedId,
+                Some(top) if id == top => DefectReason::DuplicateId,
+                _ if index >= signatures => DefectReason::SignatureMissing,
+                _ => {
+                    top = Some(id);
+                    continue;
+                }
+            };
+
+            defects.push(BatchDefect { index, reason });
+        }
+
+        defects.extend((len..signatures).map(|index| BatchDefect {
+            index,
+            reason: DefectReason::SignatureOutOfRange,
+        }));
+
+        defects.extend(
+            delegations
+                .into_iter()
+                .filter(|index| *index >= len)
+                .map(|index| BatchDefect {
+                    index,
+                    reason: DefectReason::DelegationOutOfRange,
+                }),
+        );
+
+        defects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defects(ids: &[Id], delegations: &[usize]) -> Vec<(usize, DefectReason)> {
+        signature_defects(ids, ids.len(), delegations)
+    }
+
+    fn signature_defects(
+        ids: &[Id],
+        signatures: usize,
+        delegations: &[usize],
+    ) -> Vec<(usize, DefectReason)> {
+        BatchDefect::diagnose(ids.iter().copied(), signatures, delegations.iter().copied())
+            .into_iter()
+            .map(|defect| (defect.index, defect.reason))
+            .collect()
+    }
+
+    #[test]
+    fn well_formed() {
+        assert!(defects(&[], &[]).is_empty());
+        assert!(defects(&[1, 2, 5, 8], &[0, 3]).is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            defects(&[1, 5, 2, 8], &[]),
+            vec![(2, DefectReason::UnsortedId)]
+        );
+
+        assert_eq!(
+            defects(&[1, 2, 2, 8], &[]),
+            vec![(2, DefectReason::DuplicateId)]
+        );
+
+        // Elements are compared against the largest preceding `Id`
+        assert_eq!(
+            defects(&[9, 1, 2, 9, 10], &[]),
+            vec![
+                (1, DefectReason::UnsortedId),
+                (2, DefectReason::UnsortedId),
+                (3, DefectReason::DuplicateId),
+            ]
+        );
+
+        assert_eq!(
+            defects(&[1, 2], &[1, 2]),
+            vec![(2, DefectReason::DelegationOutOfRange)]
+        );
+    }
+
+    #[test]
+    fn signatures() {
+        assert!(signature_defects(&[1, 2, 5], 3, &[]).is_empty());
+
+        assert_eq!(
+            signature_defects(&[1, 2, 5], 1, &[]),
+            vec![
+                (1, DefectReason::SignatureMissing),
+                (2, DefectReason::SignatureMissing),
+            ]
+        );
+
+        assert_eq!(
+            signature_defects(&[1, 2], 4, &[]),
+            vec![
+                (2, DefectReason::SignatureOutOfRange),
+                (3, DefectReason::SignatureOutOfRange),
+            ]
+        );
+
+        // An element missing its signature is evicted: it does not raise the bar
+        // for the `Id`s that follow
+        assert_eq!(
+            signature_defects(&[1, 2, 9, 3], 2, &[]),
+            vec![
+                (2, DefectReason::SignatureMissing),
+                (3, DefectReason::SignatureMissing),
+            ]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{account::KeyDelegation, prepare::Prepare};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::{primitives::hash::Hash, KeyCard};
+
+// A `Delegated` accompanies a `Prepare` signed by a delegate key. It carries
+// the identifier of the prepared `Operation`, allowing replicas to check that
+// the `Prepare`'s commitment binds `delegation` (see `KeyDelegation::commitment`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Delegated {
+    delegation: KeyDelegation,
+    operation: Hash,
+}
+
+#[derive(Doom)]
+pub(crate) enum DelegatedError {
+    #[doom(description("`KeyDelegation` invalid"))]
+    DelegationInvalid,
+    #[doom(description("`KeyDelegation` does not cover `Prepare`"))]
+    NotCovered,
+    #[doom(description("`Prepare`'s commitment does not bind `KeyDelegation`"))]
+    CommitmentMismatch,
+}
+
+impl Delegated {
+    pub fn new(delegation: KeyDelegation, operation: Hash) -> Self {
+        Delegated {
+            delegation,
+            operation,
+        }
+    }
+
+    pub fn delegation(&self) -> &KeyDelegation {
+        &self.delegation
+    }
+
+    pub fn delegate(&self) -> &KeyCard {
+        self.delegation.delegate()
+    }
+
+    pub fn commitment(&self) -> Hash {
+        self.delegation.commitment(self.operation)
+    }
+
+    // `master` is the `KeyCard` assigned to `prepare.id()`
+    pub fn validate(&self, master: &KeyCard, prepare: &Prepare) -> Result<(), Top<DelegatedError>> {
+        if !self.delegation.covers(prepare.entry()) {
+            return DelegatedError::NotCovered.fail().spot(here!());
+        }
+
+        if prepare.commitment() != self.commitment() {
+            return DelegatedError::CommitmentMismatch.fail().spot(here!());
+        }
+
+        self.delegation
+            .validate(master)
+            .pot(DelegatedError::DelegationInvalid, here!())
+    }
+}
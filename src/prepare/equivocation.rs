@@ -1,9 +1,11 @@
-use crate::{account::Id, discovery::Client, prepare::Extract};
+use crate::{account::Id, discovery::Client, prepare::Extract, processing::Namespace, view::View};
 
 use doomstack::{here, Doom, ResultExt, Top};
 
 use serde::{Deserialize, Serialize};
 
+use talk::crypto::primitives::hash::Hash;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Equivocation(Extract, Extract);
 
@@ -28,6 +30,19 @@ impl Equivocation {
     }
 
     pub fn validate(&self, discovery: &Client) -> Result<(), Top<EquivocationError>> {
+        self.validate_with(discovery.namespace(), |view| discovery.view(view))
+    }
+
+    // Like `validate`, in `namespace` and with `View`s resolved
+    // by `resolve` (see `Extract::validate_with`)
+    pub fn validate_with<R>(
+        &self,
+        namespace: &Namespace,
+        resolve: R,
+    ) -> Result<(), Top<EquivocationError>>
+    where
+        R: Fn(&Hash) -> Option<View>,
+    {
         if self.0.id() != self.1.id() {
             return EquivocationError::IdMismatch.fail().spot(here!());
         }
@@ -38,7 +53,7 @@ impl Equivocation {
 
         for extract in [&self.0, &self.1] {
             extract
-                .validate(discovery)
+                .validate_with(namespace, &resolve)
                 .pot(EquivocationError::InvalidExtract, here!())?;
         }
 
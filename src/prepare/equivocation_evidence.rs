@@ -0,0 +1,214 @@
+use crate::{
+    account::Id,
+    crypto::Identify,
+    prepare::Equivocation,
+    processing::Namespace,
+    view::{Install, View},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, fs, path::Path};
+
+use talk::crypto::primitives::hash::Hash;
+
+// An `EquivocationEvidence` is a self-contained proof of an `Equivocation`, to be
+// adjudicated outside the replica set. Along with both `Extract`s (and their batch
+// witnesses), it carries the chain of `Install`s leading from a `View` known to
+// the adjudicator (e.g., genesis) to the `View`s in which the `Extract`s were
+// witnessed, so that no discovery `Client` is needed to verify it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EquivocationEvidence {
+    base: Hash,
+    // `Install`s are serialized individually: deserializing an `Install` checks
+    // its certificate against its source `View`, which must be installed first
+    installs: Vec<Vec<u8>>,
+    equivocation: Equivocation,
+}
+
+#[derive(Doom)]
+pub(crate) enum EquivocationEvidenceError {
+    #[doom(description("Evidence does not start from the known `View`"))]
+    BaseMismatch,
+    #[doom(description("`Install` invalid"))]
+    InstallInvalid,
+    #[doom(description("`Install` does not extend the chain of `Install`s"))]
+    InstallUnchained,
+    #[doom(description("`Equivocation` invalid"))]
+    EquivocationInvalid,
+    #[doom(description("Failed to write evidence"))]
+    WriteFailed,
+    #[doom(description("Failed to read evidence"))]
+    ReadFailed,
+}
+
+impl EquivocationEvidence {
+    // `installs` must be ordered, each extending `base` or the destination
+    // (or tail) of a preceding `Install`
+    pub fn new<I>(base: &View, installs: I, equivocation: Equivocation) -> Self
+    where
+        I: IntoIterator<Item = Install>,
+    {
+        let installs = installs
+            .into_iter()
+            .map(|install| bincode::serialize(&install).unwrap())
+            .collect();
+
+        EquivocationEvidence {
+            base: base.identifier(),
+            installs,
+            equivocation,
+        }
+    }
+
+    pub fn equivocation(&self) -> &Equivocation {
+        &self.equivocation
+    }
+
+    // Verifies `self` against `base` (the `Extract`s must have been witnessed
+    // in `namespace`), returning the `Id` of the equivocating client
+    pub fn verify(
+        &self,
+        base: &View,
+        namespace: &Namespace,
+    ) -> Result<Id, Top<EquivocationEvidenceError>> {
+        if base.identifier() != self.base {
+            return EquivocationEvidenceError::BaseMismatch.fail().spot(here!());
+        }
+
+        let mut views = HashMap::new();
+        views.insert(base.identifier(), base.clone());
+
+        for install in self.installs.iter() {
+            let install = bincode::deserialize::<Install>(install.as_slice())
+                .pot(EquivocationEvidenceError::InstallInvalid, here!())?;
+
+            // Any `View` known to the local process is accepted as source upon
+            // deserialization: only `View`s reached from `base` are accepted here
+            if !views.contains_key(&install.source()) {
+                return EquivocationEvidenceError::InstallUnchained
+                    .fail()
+                    .spot(here!());
+            }
+
+            let transition = install.into_transition();
+
+            for view in Some(transition.destination())
+                .into_iter()
+                .chain(transition.tail())
+            {
+                views.insert(view.identifier(), view.clone());
+            }
+        }
+
+        self.equivocation
+            .validate_with(namespace, |view| views.get(view).cloned())
+            .pot(EquivocationEvidenceError::EquivocationInvalid, here!())?;
+
+        Ok(self.equivocation.id())
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), Top<EquivocationEvidenceError>>
+    where
+        P: AsRef<Path>,
+    {
+        let evidence =
+            bincode::serialize(self).pot(EquivocationEvidenceError::WriteFailed, here!())?;
+
+        fs::write(path, evidence).pot(EquivocationEvidenceError::WriteFailed, here!())
+    }
+
+    // Loading does not verify the evidence (see `verify`)
+    pub fn load<P>(path: P) -> Result<Self, Top<EquivocationEvidenceError>>
+    where
+        P: AsRef<Path>,
+    {
+        let evidence = fs::read(path).pot(EquivocationEvidenceError::ReadFailed, here!())?;
+
+        bincode::deserialize(evidence.as_slice())
+            .pot(EquivocationEvidenceError::ReadFailed, here!())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Entry,
+        crypto::Certificate,
+        prepare::{Extract, Prepare, WitnessStatement, WitnessedBatch},
+        view::test::InstallGenerator,
+    };
+
+    use bit_vec::BitVec;
+
+    use std::{collections::BTreeSet, env};
+
+    use talk::crypto::primitives::hash;
+
+    use zebra::vector::Vector;
+
+    fn extract(generator: &InstallGenerator, view: &View, commitment: u64) -> Extract {
+        let prepares = Vector::new(vec![Prepare::new(
+            Entry { id: 1, height: 1 },
+            hash::hash(&commitment).unwrap(),
+        )])
+        .unwrap();
+
+        let statement =
+            WitnessStatement::partial(&Namespace::default(), prepares.root(), BTreeSet::new());
+
+        let components = generator
+            .keychains
+            .iter()
+            .filter(|keychain| view.members().contains_key(&keychain.keycard().identity()))
+            .take(view.plurality())
+            .map(|keychain| {
+                (
+                    keychain.keycard().identity(),
+                    keychain.multisign(&statement).unwrap(),
+                )
+            });
+
+        let witness = Certificate::aggregate_plurality(view, components);
+
+        WitnessedBatch::new(view.identifier(), prepares, BitVec::new(), witness).extract(0)
+    }
+
+    #[test]
+    fn verify() {
+        let generator = InstallGenerator::new(8);
+
+        let base = generator.view(4);
+        let install = generator.install(4, 5, []);
+        let view = generator.view(5);
+
+        let equivocation =
+            Equivocation::new(extract(&generator, &view, 0), extract(&generator, &view, 1));
+
+        let evidence = EquivocationEvidence::new(&base, [install], equivocation.clone());
+
+        let path = env::temp_dir().join(format!("evidence-{:016x}", rand::random::<u64>()));
+
+        evidence.save(&path).unwrap();
+        let evidence = EquivocationEvidence::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let namespace = Namespace::default();
+
+        assert_eq!(evidence.verify(&base, &namespace).unwrap(), 1);
+
+        // Evidence must start from the adjudicator's `View`
+        assert!(evidence.verify(&view, &namespace).is_err());
+
+        // Witnesses are void outside of the `Namespace` they were issued in
+        assert!(evidence.verify(&base, &Namespace::new("other")).is_err());
+
+        // Without the `Install`, the `Extract`s' `View` cannot be reached from `base`
+        let evidence = EquivocationEvidence::new(&base, [], equivocation);
+        assert!(evidence.verify(&base, &namespace).is_err());
+    }
+}
@@ -3,12 +3,16 @@ use crate::{
     crypto::Certificate,
     discovery::Client,
     prepare::{Prepare, WitnessStatement},
+    processing::Namespace,
+    view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
 use serde::{Deserialize, Serialize};
 
+use std::collections::BTreeSet;
+
 use talk::crypto::primitives::hash::Hash;
 
 use zebra::vector::Proof;
@@ -17,6 +21,7 @@ use zebra::vector::Proof;
 pub(crate) struct Extract {
     view: Hash,
     root: Hash,
+    exclusions: BTreeSet<Id>,
     witness: Certificate,
     inclusion: Proof,
     prepare: Prepare,
@@ -30,12 +35,15 @@ pub(crate) enum ExtractError {
     WitnessInvalid,
     #[doom(description("Inclusion proof invalid"))]
     InclusionProofInvalid,
+    #[doom(description("`Prepare` excluded from witness"))]
+    PrepareExcluded,
 }
 
 impl Extract {
     pub fn new(
         view: Hash,
         root: Hash,
+        exclusions: BTreeSet<Id>,
         witness: Certificate,
         inclusion: Proof,
         prepare: Prepare,
@@ -43,6 +51,7 @@ impl Extract {
         Extract {
             view,
             root,
+            exclusions,
             witness,
             inclusion,
             prepare,
@@ -58,12 +67,29 @@ impl Extract {
     }
 
     pub fn validate(&self, discovery: &Client) -> Result<(), Top<ExtractError>> {
-        let view = discovery
-            .view(&self.view)
+        self.validate_with(discovery.namespace(), |view| discovery.view(view))
+    }
+
+    // Like `validate`, in `namespace` and with `View`s resolved by `resolve` instead
+    // of a discovery `Client` (e.g., to validate outside the replica set)
+    pub fn validate_with<R>(
+        &self,
+        namespace: &Namespace,
+        resolve: R,
+    ) -> Result<(), Top<ExtractError>>
+    where
+        R: Fn(&Hash) -> Option<View>,
+    {
+        let view = resolve(&self.view)
             .ok_or(ExtractError::ViewUnknown.into_top())
             .spot(here!())?;
 
-        let statement = WitnessStatement::new(self.root);
+        // A partial witness does not cover the `Prepare`s it excludes
+        if self.exclusions.contains(&self.prepare.id()) {
+            return ExtractError::PrepareExcluded.fail().spot(here!());
+        }
+
+        let statement = WitnessStatement::partial(namespace, self.root, self.exclusions.clone());
 
         self.witness
             .verify_plurality(&view, &statement)
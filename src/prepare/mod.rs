@@ -1,7 +1,11 @@
 mod batch_commit;
+mod batch_commit_cache;
 mod batch_commit_shard;
 mod batch_commit_statement;
+mod batch_defect;
+mod delegated;
 mod equivocation;
+mod equivocation_evidence;
 mod extract;
 mod prepare;
 mod reduction_statement;
@@ -9,10 +13,18 @@ mod signed_batch;
 mod witness_statement;
 mod witnessed_batch;
 
-pub(crate) use batch_commit::BatchCommit;
+pub(crate) use batch_commit::{BatchCommit, BatchCommitError};
+#[allow(unused_imports)]
+pub(crate) use batch_commit_cache::BatchCommitCache;
 pub(crate) use batch_commit_shard::BatchCommitShard;
 pub(crate) use batch_commit_statement::BatchCommitStatement;
+#[allow(unused_imports)]
+pub(crate) use batch_defect::{BatchDefect, DefectReason};
+#[allow(unused_imports)]
+pub(crate) use delegated::{Delegated, DelegatedError};
 pub(crate) use equivocation::Equivocation;
+#[allow(unused_imports)]
+pub(crate) use equivocation_evidence::{EquivocationEvidence, EquivocationEvidenceError};
 pub(crate) use extract::Extract;
 pub(crate) use prepare::Prepare;
 pub(crate) use reduction_statement::ReductionStatement;
@@ -1,10 +1,14 @@
+use bit_vec::BitVec;
+
 use crate::{
     crypto::Certificate,
-    prepare::{Prepare, WitnessedBatch},
+    prepare::{BatchDefect, Delegated, Prepare, WitnessedBatch},
 };
 
 use serde::{Deserialize, Serialize};
 
+use std::collections::BTreeMap;
+
 use talk::crypto::primitives::{hash::Hash, multi::Signature as MultiSignature, sign::Signature};
 
 use zebra::vector::Vector;
@@ -14,6 +18,8 @@ pub(crate) struct SignedBatch {
     prepares: Vector<Prepare>,
     reduction_signature: MultiSignature,
     individual_signatures: Vec<Option<Signature>>,
+    // index -> `Delegated` of each `Prepare` signed by a delegate key
+    delegations: BTreeMap<usize, Delegated>,
 }
 
 impl SignedBatch {
@@ -26,9 +32,15 @@ impl SignedBatch {
             prepares,
             reduction_signature,
             individual_signatures,
+            delegations: BTreeMap::new(),
         }
     }
 
+    pub fn with_delegations(mut self, delegations: BTreeMap<usize, Delegated>) -> Self {
+        self.delegations = delegations;
+        self
+    }
+
     pub fn root(&self) -> Hash {
         self.prepares.root()
     }
@@ -45,7 +57,24 @@ impl SignedBatch {
         self.individual_signatures.as_slice()
     }
 
-    pub fn into_witnessed(self, view: Hash, witness: Certificate) -> WitnessedBatch {
-        WitnessedBatch::new(view, self.prepares, witness)
+    pub fn defects(&self) -> Vec<BatchDefect> {
+        BatchDefect::diagnose(
+            self.prepares.items().iter().map(Prepare::id),
+            self.individual_signatures.len(),
+            self.delegations.keys().copied(),
+        )
+    }
+
+    pub fn delegations(&self) -> &BTreeMap<usize, Delegated> {
+        &self.delegations
+    }
+
+    pub fn into_witnessed(
+        self,
+        view: Hash,
+        excluded: BitVec,
+        witness: Certificate,
+    ) -> WitnessedBatch {
+        WitnessedBatch::new(view, self.prepares, excluded, witness)
     }
 }
@@ -1,15 +1,31 @@
-use crate::crypto::Header;
+use crate::{account::Id, crypto::Header, processing::Namespace};
 
 use serde::Serialize;
 
+use std::collections::BTreeSet;
+
 use talk::crypto::{primitives::hash::Hash, Statement};
 
 #[derive(Debug, Clone, Serialize)]
-pub(crate) struct WitnessStatement(Hash);
+pub(crate) struct WitnessStatement {
+    namespace: Namespace,
+    root: Hash,
+    exclusions: BTreeSet<Id>,
+}
 
 impl WitnessStatement {
-    pub fn new(root: Hash) -> Self {
-        WitnessStatement(root)
+    pub fn new(namespace: &Namespace, root: Hash) -> Self {
+        WitnessStatement::partial(namespace, root, BTreeSet::new())
+    }
+
+    // A partial `WitnessStatement` witnesses all elements of the batch
+    // rooted at `root`, except for those whose `Id` is in `exclusions`
+    pub fn partial(namespace: &Namespace, root: Hash, exclusions: BTreeSet<Id>) -> Self {
+        WitnessStatement {
+            namespace: namespace.clone(),
+            root,
+            exclusions,
+        }
     }
 }
 
@@ -1,4 +1,7 @@
+use bit_vec::BitVec;
+
 use crate::{
+    account::Id,
     crypto::Certificate,
     discovery::Client,
     prepare::{Extract, Prepare, WitnessStatement},
@@ -8,6 +11,8 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use serde::{Deserialize, Serialize};
 
+use std::collections::BTreeSet;
+
 use talk::crypto::primitives::hash::Hash;
 
 use zebra::vector::Vector;
@@ -16,6 +21,7 @@ use zebra::vector::Vector;
 pub(crate) struct WitnessedBatch {
     view: Hash,
     prepares: Vector<Prepare>,
+    excluded: BitVec,
     witness: Certificate,
 }
 
@@ -23,15 +29,23 @@ pub(crate) struct WitnessedBatch {
 pub(crate) enum WitnessedBatchError {
     #[doom(description("`View` unknown"))]
     ViewUnknown,
+    #[doom(description("Exclusions malformed"))]
+    ExclusionsMalformed,
     #[doom(description("Certificate invalid"))]
     CertificateInvalid,
 }
 
 impl WitnessedBatch {
-    pub fn new(view: Hash, prepares: Vector<Prepare>, witness: Certificate) -> Self {
+    pub fn new(
+        view: Hash,
+        prepares: Vector<Prepare>,
+        excluded: BitVec,
+        witness: Certificate,
+    ) -> Self {
         WitnessedBatch {
             view,
             prepares,
+            excluded,
             witness,
         }
     }
@@ -44,10 +58,25 @@ impl WitnessedBatch {
         self.prepares.items()
     }
 
+    pub fn excludes(&self, index: usize) -> bool {
+        // An empty `self.excluded` represents a full witness
+        self.excluded.get(index).unwrap_or(false)
+    }
+
+    pub fn exclusions(&self) -> BTreeSet<Id> {
+        self.prepares
+            .items()
+            .iter()
+            .zip(self.excluded.iter())
+            .filter_map(|(prepare, excluded)| if excluded { Some(prepare.id()) } else { None })
+            .collect()
+    }
+
     pub fn extract(&self, index: usize) -> Extract {
         Extract::new(
             self.view,
             self.prepares.root(),
+            self.exclusions(),
             self.witness.clone(),
             self.prepares.prove(index),
             self.prepares.items()[index].clone(),
@@ -60,7 +89,17 @@ impl WitnessedBatch {
             .ok_or(WitnessedBatchError::ViewUnknown.into_top())
             .spot(here!())?;
 
-        let statement = WitnessStatement::new(self.prepares.root());
+        if !self.excluded.is_empty() && self.excluded.len() != self.prepares.items().len() {
+            return WitnessedBatchError::ExclusionsMalformed
+                .fail()
+                .spot(here!());
+        }
+
+        let statement = WitnessStatement::partial(
+            discovery.namespace(),
+            self.prepares.root(),
+            self.exclusions(),
+        );
 
         self.witness
             .verify_plurality(&view, &statement)
@@ -0,0 +1,261 @@
+use crate::{
+    account::Id,
+    database::{
+        commit::{HistoryError, HistoryPage, HistoryQuery},
+        Database,
+    },
+    processing::{delegate::DelegateSettings, messages::StreamUpdate},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::sync::{Arc, Mutex};
+
+use talk::{
+    crypto::Identity,
+    net::{Session, SessionConnector},
+    sync::fuse::Fuse,
+};
+
+use tokio::time;
+
+type Mirror = Arc<Mutex<Option<Database>>>;
+
+// A `Delegate` mirrors the `Database` of a replica that designated it (see
+// `processor_settings::Delegation`) from the stream of its records (see
+// `Database::subscribe`), and answers queries from its mirror, so that query load
+// does not contend with the replica's serve paths for its `Database`. `connector`
+// must be registered on the "stream" context of the replica (see `Namespace::context`),
+// and authenticate with the `KeyChain` of a designated delegate. Whenever its stream
+// ends (e.g., because the `Delegate` fell behind), the `Delegate` resynchronizes from a
+// new `Image`, serving queries from its previous mirror in the meantime.
+pub(crate) struct Delegate {
+    mirror: Mirror,
+    _fuse: Fuse,
+}
+
+#[derive(Doom)]
+enum StreamError {
+    #[doom(description("Connection failed"))]
+    ConnectionFailed,
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Stream timed out"))]
+    Timeout,
+    #[doom(description("Unexpected update"))]
+    UnexpectedUpdate,
+    #[doom(description("Malformed record"))]
+    MalformedRecord,
+}
+
+impl Delegate {
+    pub fn new(replica: Identity, connector: SessionConnector, settings: DelegateSettings) -> Self {
+        let mirror = Arc::new(Mutex::new(None));
+        let fuse = Fuse::new();
+
+        {
+            let mirror = mirror.clone();
+            let settings = settings.clone();
+
+            fuse.spawn(async move {
+                Delegate::run(replica, connector, mirror, settings).await;
+            });
+        }
+
+        {
+            let mirror = mirror.clone();
+
+            fuse.spawn(async move {
+                Delegate::prune(mirror, settings).await;
+            });
+        }
+
+        Delegate {
+            mirror,
+            _fuse: fuse,
+        }
+    }
+
+    // Answers `query` from the mirror (see `Commit::history`). Until the
+    // `Delegate` first synchronizes, every query is rejected as lagging.
+    pub fn history(&self, query: &HistoryQuery) -> Result<HistoryPage, Top<HistoryError>> {
+        match self.mirror.lock().unwrap().as_mut() {
+            Some(database) => database.commit.history(query),
+            None => HistoryError::Lagging.fail().spot(here!()),
+        }
+    }
+
+    // Balance of each element of `ids` (see `Database::balances`),
+    // or `None` if the `Delegate` is yet to synchronize
+    pub fn balances(&self, ids: Vec<Id>) -> Option<Vec<Option<u64>>> {
+        self.mirror
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(|database| database.balances(ids))
+    }
+
+    async fn run(
+        replica: Identity,
+        connector: SessionConnector,
+        mirror: Mirror,
+        settings: DelegateSettings,
+    ) {
+        loop {
+            if let Err(error) = Delegate::stream(replica, &connector, &mirror, &settings).await {
+                log::debug!("Stream from {:?} ended: {:?}", replica, error);
+            }
+
+            time::sleep(settings.retry_interval).await;
+        }
+    }
+
+    async fn stream(
+        replica: Identity,
+        connector: &SessionConnector,
+        mirror: &Mirror,
+        settings: &DelegateSettings,
+    ) -> Result<(), Top<StreamError>> {
+        let mut session = connector
+            .connect(replica)
+            .await
+            .pot(StreamError::ConnectionFailed, here!())?;
+
+        // The first update of a stream holds the `Image` to start from: it is
+        // replayed off the lock of `mirror`, which is then replaced at once
+
+        let image = match Delegate::receive(&mut session, settings).await? {
+            StreamUpdate::Record(image) => image,
+            StreamUpdate::KeepAlive => {
+                return StreamError::UnexpectedUpdate.fail().spot(here!());
+            }
+        };
+
+        let mut database = Database::with_account_settings(settings.account_settings.clone());
+
+        database
+            .replay(image.as_slice())
+            .pot(StreamError::MalformedRecord, here!())?;
+
+        *mirror.lock().unwrap() = Some(database);
+
+        loop {
+            if let StreamUpdate::Record(record) = Delegate::receive(&mut session, settings).await? {
+                // `mirror` was set above, and is never reset
+                mirror
+                    .lock()
+                    .unwrap()
+                    .as_mut()
+                    .unwrap()
+                    .replay(record.as_slice())
+                    .pot(StreamError::MalformedRecord, here!())?;
+            }
+        }
+    }
+
+    async fn receive(
+        session: &mut Session,
+        settings: &DelegateSettings,
+    ) -> Result<StreamUpdate, Top<StreamError>> {
+        time::timeout(settings.stream_timeout, session.receive::<StreamUpdate>())
+            .await
+            .pot(StreamError::Timeout, here!())?
+            .pot(StreamError::ConnectionError, here!())
+    }
+
+    async fn prune(mirror: Mirror, settings: DelegateSettings) {
+        loop {
+            time::sleep(settings.prune_interval).await;
+
+            if let Some(database) = mirror.lock().unwrap().as_mut() {
+                database.prune_prepare_batches(settings.batch_retention);
+                database.prune_commit_batches(settings.commit_retention);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        account::Id,
+        crypto::Identify,
+        processing::test::{System, TestBroker},
+        signup::{IdRequest, SignupSettings},
+        view::View,
+    };
+
+    use std::time::Duration;
+
+    use talk::crypto::{Identity, KeyChain};
+
+    use tokio::time;
+
+    async fn signup(broker: &TestBroker, view: &View, replica: Identity) -> Id {
+        let request = IdRequest::new(
+            &KeyChain::random(),
+            view,
+            replica,
+            SignupSettings::default().work_difficulty,
+        );
+
+        let assignment = broker.signup(vec![request]).await.remove(0).unwrap();
+        broker
+            .id_assignments(replica, vec![assignment.clone()])
+            .await;
+
+        assignment.id()
+    }
+
+    #[tokio::test]
+    async fn mirror() {
+        let System {
+            view,
+            mut brokers,
+            processors,
+            ..
+        } = System::setup_with_delegates(4, 3, 1).await;
+
+        let replica = processors[0].0.keycard().identity();
+
+        // `first` is assigned before the `Delegate` subscribes, `second` after
+
+        let first = signup(&brokers[2], &view, replica).await;
+
+        let delegate = brokers.remove(0).delegate(replica);
+        let undesignated = brokers.remove(0).delegate(replica);
+
+        let assigned = |id| {
+            delegate
+                .mirror
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|database| database.assigned.contains(&id))
+                .unwrap_or(false)
+        };
+
+        time::timeout(Duration::from_secs(10), async {
+            while !assigned(first) {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let second = signup(&brokers[0], &view, replica).await;
+
+        time::timeout(Duration::from_secs(10), async {
+            while !assigned(second) {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(delegate.balances(vec![first]).unwrap().len(), 1);
+
+        // Delegates not designated by the replica are not streamed to
+        assert!(undesignated.balances(vec![first]).is_none());
+    }
+}
@@ -0,0 +1,33 @@
+use crate::account::AccountSettings;
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub(crate) struct DelegateSettings {
+    // Bounds each receive from the replica: must exceed the interval of its
+    // keep-alives (see `processor_settings::Delegation`)
+    pub stream_timeout: Duration,
+    // Interval between the end of a stream and the next attempt to resynchronize
+    pub retry_interval: Duration,
+    // Pruning is not streamed: the mirror drops prepare (resp., commit)
+    // batches once applied for `batch_retention` (resp., `commit_retention`),
+    // every `prune_interval` (see `processor_settings::Compaction`)
+    pub prune_interval: Duration,
+    pub batch_retention: Duration,
+    pub commit_retention: Duration,
+    // Must match those of the replica (see `Database::with_account_settings`)
+    pub account_settings: AccountSettings,
+}
+
+impl Default for DelegateSettings {
+    fn default() -> Self {
+        DelegateSettings {
+            stream_timeout: Duration::from_secs(15),
+            retry_interval: Duration::from_secs(1),
+            prune_interval: Duration::from_secs(10),
+            batch_retention: Duration::from_secs(300),
+            commit_retention: Duration::from_secs(3600),
+            account_settings: Default::default(),
+        }
+    }
+}
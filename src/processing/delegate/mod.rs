@@ -0,0 +1,6 @@
+mod delegate;
+mod delegate_settings;
+
+#[allow(unused_imports)]
+pub(crate) use delegate::Delegate;
+pub(crate) use delegate_settings::DelegateSettings;
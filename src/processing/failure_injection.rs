@@ -0,0 +1,106 @@
+#[cfg(feature = "benchmark")]
+use std::time::Duration;
+
+#[cfg(feature = "benchmark")]
+use tokio::time;
+
+// `FailureInjection` simulates a straggling replica by delaying (and, with
+// probability `drop_probability`, dropping) each message received on the serve
+// paths (see `Timeout::run`). Faults are decided independently for each message.
+// Outside of the `benchmark` feature, `FailureInjection` carries no parameters
+// and injects nothing.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FailureInjection {
+    #[cfg(feature = "benchmark")]
+    pub delay: Duration,
+    #[cfg(feature = "benchmark")]
+    pub drop_probability: f64,
+}
+
+impl FailureInjection {
+    // Delays the message just received, then returns `false` if it is to be dropped
+    pub async fn inject(&self) -> bool {
+        #[cfg(feature = "benchmark")]
+        {
+            time::sleep(self.delay).await;
+
+            if rand::random::<f64>() < self.drop_probability {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(all(test, feature = "benchmark"))]
+mod tests {
+    use super::*;
+
+    use crate::processing::Timeout;
+
+    use std::time::Instant;
+
+    const MESSAGES: usize = 10000;
+
+    #[tokio::test]
+    async fn drop_rate() {
+        let failure_injection = FailureInjection {
+            delay: Duration::from_secs(0),
+            drop_probability: 0.3,
+        };
+
+        let mut dropped = 0;
+
+        for _ in 0..MESSAGES {
+            if !failure_injection.inject().await {
+                dropped += 1;
+            }
+        }
+
+        let rate = (dropped as f64) / (MESSAGES as f64);
+        assert!((rate - 0.3).abs() < 0.03, "drop rate {}", rate);
+    }
+
+    #[tokio::test]
+    async fn delay() {
+        let failure_injection = FailureInjection {
+            delay: Duration::from_millis(20),
+            drop_probability: 0.,
+        };
+
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            assert!(failure_injection.inject().await);
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn per_message() {
+        let timeout =
+            Timeout::new(Duration::from_millis(1)).with_failure_injection(FailureInjection {
+                delay: Duration::from_secs(0),
+                drop_probability: 0.5,
+            });
+
+        let mut delivered = 0;
+
+        for message in 0..1000 {
+            if let Ok(received) = timeout.run(async { message }).await {
+                assert_eq!(received, message);
+                delivered += 1;
+            }
+        }
+
+        // Each message is dropped (expiring `timeout`) or delivered on its own
+        assert_eq!(delivered + timeout.expired(), 1000);
+        assert!(
+            delivered > 400 && delivered < 600,
+            "{} delivered",
+            delivered
+        );
+    }
+}
@@ -5,9 +5,12 @@ use zebra::vector::Vector;
 use crate::{
     commit::{BatchCompletion, CommitProof, Completion, Payload},
     crypto::Certificate,
+    data::enveloped,
+    telemetry::TraceContext,
 };
 
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) enum CommitRequest {
     Ping,
     Batch(Vector<Payload>),
@@ -16,4 +19,8 @@ pub(crate) enum CommitRequest {
     Witness(Certificate),
     Dependencies(Vec<Completion>),
     Completion(BatchCompletion),
+    // Optional session preamble, carrying the trace of the request that follows
+    Trace(TraceContext),
 }
+
+enveloped!(CommitRequest);
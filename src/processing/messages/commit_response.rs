@@ -1,14 +1,18 @@
-use crate::{account::Id, commit::BatchCompletionShard};
+use crate::{account::Id, commit::BatchCompletionShard, data::enveloped};
 
 use serde::{Deserialize, Serialize};
 
 use talk::crypto::primitives::multi::Signature as MultiSignature;
 
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) enum CommitResponse {
     Pong,
     MissingCommitProofs(Vec<Id>),
     WitnessShard(MultiSignature),
     MissingDependencies(Vec<Id>),
     CompletionShard(BatchCompletionShard),
+    Busy,
 }
+
+enveloped!(CommitResponse);
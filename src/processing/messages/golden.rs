@@ -0,0 +1,253 @@
+use bit_vec::BitVec;
+
+use crate::{
+    account::Entry,
+    commit::BatchCompletionShard,
+    crypto::{Certificate, Identify},
+    data::golden,
+    prepare::{
+        BatchCommitShard, BatchDefect, DefectReason, Prepare, ReductionStatement, WitnessStatement,
+    },
+    processing::{
+        messages::{
+            CommitRequest, CommitResponse, PrepareRequest, PrepareResponse, SignupRequest,
+            SignupResponse,
+        },
+        Namespace,
+    },
+    signup::{IdAllocation, IdAssignment, IdClaim, IdRequest},
+    view::test::InstallGenerator,
+};
+
+use std::collections::BTreeSet;
+
+use talk::crypto::primitives::hash;
+
+use zebra::vector::Vector;
+
+#[test]
+fn signup() {
+    golden::check(
+        "signup_request_id_requests",
+        &SignupRequest::IdRequests(vec![]),
+    );
+    golden::check("signup_request_id_claims", &SignupRequest::IdClaims(vec![]));
+
+    golden::check(
+        "signup_request_id_assignments",
+        &SignupRequest::IdAssignments(vec![]),
+    );
+
+    golden::check(
+        "signup_response_id_allocations",
+        &SignupResponse::IdAllocations(vec![]),
+    );
+
+    golden::check(
+        "signup_response_id_assignment_shards",
+        &SignupResponse::IdAssignmentShards(vec![]),
+    );
+
+    golden::check(
+        "signup_response_acknowledge_id_assignments",
+        &SignupResponse::AcknowledgeIdAssignments,
+    );
+
+    golden::check(
+        "signup_request_work_difficulty",
+        &SignupRequest::WorkDifficulty,
+    );
+
+    golden::check(
+        "signup_response_work_difficulty",
+        &SignupResponse::WorkDifficulty(8),
+    );
+}
+
+#[test]
+fn prepare() {
+    let mut excluded = BitVec::from_elem(12, false);
+    excluded.set(3, true);
+    excluded.set(7, true);
+
+    golden::check("prepare_request_ping", &PrepareRequest::Ping);
+    golden::check(
+        "prepare_request_assignments",
+        &PrepareRequest::Assignments(vec![]),
+    );
+    golden::check(
+        "prepare_request_exclusions",
+        &PrepareRequest::Exclusions(excluded),
+    );
+
+    golden::check("prepare_request_clock_ping", &PrepareRequest::ClockPing);
+
+    golden::check("prepare_response_pong", &PrepareResponse::Pong);
+    golden::check(
+        "prepare_response_clock_pong",
+        &PrepareResponse::ClockPong(1_650_000_000_000),
+    );
+
+    golden::check(
+        "prepare_response_unknown_ids",
+        &PrepareResponse::UnknownIds(vec![1, 2, 3, u64::MAX]),
+    );
+
+    golden::check("prepare_response_busy", &PrepareResponse::Busy);
+    golden::check(
+        "prepare_response_capabilities",
+        &PrepareResponse::Capabilities(true),
+    );
+
+    golden::check(
+        "prepare_response_malformed_batch",
+        &PrepareResponse::MalformedBatch(vec![
+            BatchDefect {
+                index: 2,
+                reason: DefectReason::DuplicateId,
+            },
+            BatchDefect {
+                index: 3,
+                reason: DefectReason::SignatureMissing,
+            },
+            BatchDefect {
+                index: 4,
+                reason: DefectReason::SignatureOutOfRange,
+            },
+        ]),
+    );
+}
+
+#[test]
+fn commit() {
+    golden::check("commit_request_ping", &CommitRequest::Ping);
+    golden::check(
+        "commit_request_witness_request",
+        &CommitRequest::WitnessRequest,
+    );
+    golden::check(
+        "commit_request_commit_proofs",
+        &CommitRequest::CommitProofs(vec![]),
+    );
+    golden::check(
+        "commit_request_dependencies",
+        &CommitRequest::Dependencies(vec![]),
+    );
+
+    golden::check("commit_response_pong", &CommitResponse::Pong);
+
+    golden::check(
+        "commit_response_missing_commit_proofs",
+        &CommitResponse::MissingCommitProofs(vec![1, 2, 3, u64::MAX]),
+    );
+
+    golden::check(
+        "commit_response_missing_dependencies",
+        &CommitResponse::MissingDependencies(vec![1, 2, 3, u64::MAX]),
+    );
+
+    golden::check("commit_response_busy", &CommitResponse::Busy);
+}
+
+#[test]
+fn signed() {
+    let generator = InstallGenerator::from_keychains(golden::keychains());
+    let view = generator.view(3);
+    let namespace = Namespace::default();
+
+    // The last `KeyChain` (not a member of `view`) signs on behalf of the client
+
+    let client = &generator.keychains[3];
+    let replica = &generator.keychains[0];
+
+    let request = IdRequest::new(client, &view, replica.keycard().identity(), 0);
+    let allocation = IdAllocation::new(replica, &namespace, &request, 1);
+    let claim = IdClaim::new(request.clone(), allocation.clone());
+    let shard = IdAssignment::certify(replica, &namespace, &claim);
+
+    golden::check(
+        "signup_request_id_requests_signed",
+        &SignupRequest::IdRequests(vec![request]),
+    );
+
+    golden::check(
+        "signup_response_id_allocations_signed",
+        &SignupResponse::IdAllocations(vec![allocation]),
+    );
+
+    golden::check(
+        "signup_request_id_claims_signed",
+        &SignupRequest::IdClaims(vec![claim.clone()]),
+    );
+
+    golden::check(
+        "signup_response_id_assignment_shards_signed",
+        &SignupResponse::IdAssignmentShards(vec![Ok(shard), Err(claim)]),
+    );
+
+    let prepare = Prepare::new(Entry { id: 1, height: 1 }, hash::hash(&0u64).unwrap());
+    let signature = client.sign(&prepare).unwrap();
+
+    let prepares = Vector::new(vec![prepare]).unwrap();
+    let reduction = client
+        .multisign(&ReductionStatement::new(prepares.root()))
+        .unwrap();
+
+    golden::check(
+        "prepare_request_batch",
+        &PrepareRequest::Batch(prepares.clone()),
+    );
+
+    golden::check(
+        "prepare_request_signatures",
+        &PrepareRequest::Signatures(reduction, vec![Some(signature), None]),
+    );
+
+    let statement = WitnessStatement::new(&namespace, prepares.root());
+
+    golden::check(
+        "prepare_response_witness_shard",
+        &PrepareResponse::WitnessShard(replica.multisign(&statement).unwrap()),
+    );
+
+    let components = generator
+        .keychains
+        .iter()
+        .take(view.plurality())
+        .map(|keychain| {
+            (
+                keychain.keycard().identity(),
+                keychain.multisign(&statement).unwrap(),
+            )
+        });
+
+    let witness = Certificate::aggregate_plurality(&view, components);
+
+    golden::check(
+        "prepare_request_witness",
+        &PrepareRequest::Witness(BitVec::from_elem(1, false), witness),
+    );
+
+    golden::check(
+        "prepare_response_commit_shard",
+        &PrepareResponse::CommitShard(BatchCommitShard::new(
+            replica,
+            &namespace,
+            view.identifier(),
+            prepares.root(),
+            BTreeSet::new(),
+            vec![],
+        )),
+    );
+
+    golden::check(
+        "commit_response_completion_shard",
+        &CommitResponse::CompletionShard(BatchCompletionShard::new(
+            replica,
+            &namespace,
+            view.identifier(),
+            prepares.root(),
+            vec![1],
+        )),
+    );
+}
@@ -4,6 +4,12 @@ mod prepare_request;
 mod prepare_response;
 mod signup_request;
 mod signup_response;
+mod stream_update;
+mod sync_request;
+mod sync_response;
+
+#[cfg(test)]
+mod golden;
 
 #[allow(unused_imports)]
 pub(crate) use commit_request::CommitRequest;
@@ -15,3 +21,6 @@ pub(crate) use prepare_request::PrepareRequest;
 pub(crate) use prepare_response::PrepareResponse;
 pub(crate) use signup_request::SignupRequest;
 pub(crate) use signup_response::SignupResponse;
+pub(crate) use stream_update::StreamUpdate;
+pub(crate) use sync_request::SyncRequest;
+pub(crate) use sync_response::SyncResponse;
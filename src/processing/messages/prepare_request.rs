@@ -1,21 +1,46 @@
+use bit_vec::BitVec;
+
 use crate::{
     crypto::Certificate,
-    prepare::{BatchCommit, Prepare},
+    data::enveloped,
+    data::Compressed,
+    prepare::{BatchCommit, Delegated, Equivocation, Prepare},
     signup::IdAssignment,
+    telemetry::TraceContext,
 };
 
 use serde::{Deserialize, Serialize};
 
-use talk::crypto::primitives::{multi::Signature as MultiSignature, sign::Signature};
+use std::collections::BTreeMap;
+
+use talk::crypto::primitives::{hash::Hash, multi::Signature as MultiSignature, sign::Signature};
 
 use zebra::vector::Vector;
 
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) enum PrepareRequest {
     Ping,
     Batch(Vector<Prepare>),
     Signatures(MultiSignature, Vec<Option<Signature>>),
     Assignments(Vec<IdAssignment>),
-    Witness(Certificate),
+    Exclusions(BitVec),
+    Witness(BitVec, Certificate),
     Commit(BatchCommit),
+    Reconcile(Hash, Vec<Equivocation>),
+    ClockPing,
+    CompressedBatch(Compressed<Vector<Prepare>>),
+    // Like `Signatures`, for batches including `Prepare`s signed by delegate keys
+    DelegatedSignatures(
+        MultiSignature,
+        Vec<Option<Signature>>,
+        BTreeMap<usize, Delegated>,
+    ),
+    // Optional session preamble, carrying the trace of the request that follows
+    Trace(TraceContext),
+    // Like `Assignments`, omitting the `IdAssignment`s unknown to the broker
+    // (the replica looks them up from its peers)
+    PartialAssignments(Vec<Option<IdAssignment>>),
 }
+
+enveloped!(PrepareRequest);
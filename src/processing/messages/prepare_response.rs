@@ -1,13 +1,28 @@
-use crate::{account::Id, prepare::BatchCommitShard};
+use bit_vec::BitVec;
+
+use crate::{
+    account::Id,
+    data::enveloped,
+    prepare::{BatchCommitShard, BatchDefect},
+};
 
 use serde::{Deserialize, Serialize};
 
 use talk::crypto::primitives::multi::Signature as MultiSignature;
 
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) enum PrepareResponse {
     Pong,
     UnknownIds(Vec<Id>),
     WitnessShard(MultiSignature),
+    PartialWitnessShard(BitVec, MultiSignature),
     CommitShard(BatchCommitShard),
+    ClockPong(u64),
+    MalformedBatch(Vec<BatchDefect>),
+    Busy,
+    // Follows `ClockPong`: whether the replica accepts `PrepareRequest::CompressedBatch`
+    Capabilities(bool),
 }
+
+enveloped!(PrepareResponse);
@@ -1,14 +1,25 @@
-use crate::signup::{IdAssignment, IdClaim, IdRequest};
+use crate::{
+    account::Id,
+    data::enveloped,
+    signup::{IdAssignment, IdClaim, IdRequest},
+};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) enum SignupRequest {
     IdRequests(Vec<IdRequest>),
     IdClaims(Vec<IdClaim>),
     IdAssignments(Vec<IdAssignment>),
+    // Issued by replicas to their peers, for `IdAssignment`s they are missing
+    IdLookups(Vec<Id>),
+    // Pre-flight, issued by brokers on behalf of clients about to compute `Work`
+    WorkDifficulty,
 }
 
+enveloped!(SignupRequest);
+
 impl SignupRequest {
     pub fn unwrap_id_requests(self) -> Vec<IdRequest> {
         match self {
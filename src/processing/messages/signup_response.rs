@@ -1,12 +1,22 @@
-use crate::signup::{IdAllocation, IdClaim};
+use crate::{
+    data::enveloped,
+    signup::{IdAllocation, IdAssignment, IdClaim},
+};
 
 use serde::{Deserialize, Serialize};
 
 use talk::crypto::primitives::multi::Signature as MultiSignature;
 
 #[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(crate) enum SignupResponse {
     IdAllocations(Vec<IdAllocation>),
     IdAssignmentShards(Vec<Result<MultiSignature, IdClaim>>),
     AcknowledgeIdAssignments,
+    IdLookups(Vec<Option<IdAssignment>>),
+    // Work difficulty currently required of `IdRequest`s. Also sent in place of
+    // `IdAllocations` if any `IdRequest` carries `Work` of lesser difficulty
+    WorkDifficulty(u64),
 }
+
+enveloped!(SignupResponse);
@@ -0,0 +1,15 @@
+use crate::data::enveloped;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub(crate) enum StreamUpdate {
+    // A serialized `Database` record (see `Database::subscribe`): the
+    // first `Record` of a stream holds the `Image` to start from
+    Record(Vec<u8>),
+    // Sent in place of `Record`s while the `Database` is idle
+    KeepAlive,
+}
+
+enveloped!(StreamUpdate);
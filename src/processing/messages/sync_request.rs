@@ -0,0 +1,16 @@
+use crate::data::enveloped;
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::primitives::hash::Hash;
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub(crate) enum SyncRequest {
+    // Prompts the replica to sign the `Snapshot` it took upon the last install it knows of
+    Snapshot,
+    // Requests a chunk of a `Snapshot` retained by the replica
+    Chunk { root: Hash, index: u64 },
+}
+
+enveloped!(SyncRequest);
@@ -0,0 +1,23 @@
+use crate::{data::enveloped, database::SnapshotChunk};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::primitives::{hash::Hash, multi::Signature as MultiSignature};
+
+use zebra::vector::Proof;
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub(crate) enum SyncResponse {
+    // `signature` signs the `SnapshotStatement` for `root` and `chunks`
+    Snapshot {
+        root: Hash,
+        chunks: u64,
+        signature: MultiSignature,
+    },
+    Chunk(SnapshotChunk, Proof),
+    // The requested `Snapshot` is no longer retained (or was never taken)
+    UnknownSnapshot,
+}
+
+enveloped!(SyncResponse);
@@ -1,12 +1,24 @@
+mod failure_injection;
+mod namespace;
 mod processor;
+mod processor_pool;
+mod timeout;
 
 #[cfg(test)]
 mod test;
 
+pub(crate) mod delegate;
 pub(crate) mod messages;
 pub(crate) mod processor_settings;
+pub(crate) mod sync;
 
+pub(crate) use failure_injection::FailureInjection;
+pub use namespace::Namespace;
 pub(crate) use processor::Processor;
 
+#[allow(unused_imports)]
+pub(crate) use processor_pool::{ProcessorPool, ProcessorPoolError};
+pub(crate) use timeout::Timeout;
+
 #[allow(unused_imports)]
 pub(crate) use processor_settings::ProcessorSettings;
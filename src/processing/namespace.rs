@@ -0,0 +1,118 @@
+use crate::{crypto::Identify, view::View};
+
+use serde::{Deserialize, Serialize};
+
+// A `Namespace` identifies one of the logical instances hosted by a replica
+// deployment. Instances in different `Namespace`s share views and networking,
+// but serve (and are served by brokers) on distinct contexts, each backed by
+// its own `Database`.
+//
+// Every statement signed by replicas (witnesses, commits, completions, `Id`
+// allocations and assignments, snapshots) covers its `Namespace`, so that
+// certificates issued in one `Namespace` are void in all others. Statements
+// signed by clients are bound to a `Namespace` through the (namespaced)
+// `IdAssignment` of their signer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Namespace(Option<String>);
+
+impl Namespace {
+    pub fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Namespace(Some(name.into()))
+    }
+
+    pub(crate) fn context(&self, view: &View, role: &str) -> String {
+        match &self.0 {
+            // The default `Namespace` preserves non-namespaced contexts
+            None => format!("{:?}::processor::{}", view.identifier(), role),
+            Some(name) => format!("{:?}::{}::processor::{}", view.identifier(), name, role),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Entry,
+        crypto::Certificate,
+        discovery::{ClientSettings, Embedded},
+        prepare::{Prepare, WitnessStatement, WitnessedBatch},
+        signup::test::assignment,
+        view::test::InstallGenerator,
+    };
+
+    use bit_vec::BitVec;
+
+    use std::collections::BTreeSet;
+
+    use talk::crypto::{primitives::hash, KeyChain};
+
+    use zebra::vector::Vector;
+
+    fn batch(generator: &InstallGenerator, view: &View, namespace: &Namespace) -> WitnessedBatch {
+        let prepares = Vector::new(vec![Prepare::new(
+            Entry { id: 1, height: 1 },
+            hash::hash(&0u64).unwrap(),
+        )])
+        .unwrap();
+
+        let statement = WitnessStatement::partial(namespace, prepares.root(), BTreeSet::new());
+
+        let components = generator
+            .keychains
+            .iter()
+            .take(view.plurality())
+            .map(|keychain| {
+                (
+                    keychain.keycard().identity(),
+                    keychain.multisign(&statement).unwrap(),
+                )
+            });
+
+        let witness = Certificate::aggregate_plurality(view, components);
+
+        WitnessedBatch::new(view.identifier(), prepares, BitVec::new(), witness)
+    }
+
+    #[tokio::test]
+    async fn foreign_certificates() {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let client = |namespace: &Namespace| {
+            embedded.client(ClientSettings {
+                namespace: namespace.clone(),
+                ..Default::default()
+            })
+        };
+
+        let a = Namespace::new("a");
+        let b = Namespace::new("b");
+
+        let discovery_a = client(&a);
+        let discovery_b = client(&b);
+        let discovery_default = client(&Namespace::default());
+
+        // Certificates issued in `a` are valid in `a` only
+
+        let assignment = assignment(&generator.keychains, &view, &a, &KeyChain::random(), 1);
+
+        assignment.validate(&discovery_a).unwrap();
+        assert!(assignment.validate(&discovery_b).is_err());
+        assert!(assignment.validate(&discovery_default).is_err());
+
+        let batch = batch(&generator, &view, &a);
+
+        batch.validate(&discovery_a).unwrap();
+        assert!(batch.validate(&discovery_b).is_err());
+        assert!(batch.validate(&discovery_default).is_err());
+    }
+}
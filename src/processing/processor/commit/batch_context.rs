@@ -0,0 +1,49 @@
+use crate::{
+    commit::{Payload, WitnessStatement},
+    crypto::Identify,
+    processing::Namespace,
+    view::View,
+};
+
+use talk::crypto::primitives::hash::Hash;
+
+use zebra::vector::Vector;
+
+// Values derived from a batch (and the `View` it is processed in), computed once
+// per session and shared by all steps (hashing `View`'s identifier, in particular,
+// is not free, and would otherwise be repeated by each step)
+pub(in crate::processing::processor::commit) struct BatchContext {
+    namespace: Namespace,
+    view: Hash,
+    root: Hash,
+    witness_statement: WitnessStatement,
+}
+
+impl BatchContext {
+    pub fn new(namespace: &Namespace, view: &View, payloads: &Vector<Payload>) -> Self {
+        let root = payloads.root();
+
+        BatchContext {
+            namespace: namespace.clone(),
+            view: view.identifier(),
+            root,
+            witness_statement: WitnessStatement::new(namespace, root),
+        }
+    }
+
+    pub fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+
+    pub fn view(&self) -> Hash {
+        self.view
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    pub fn witness_statement(&self) -> &WitnessStatement {
+        &self.witness_statement
+    }
+}
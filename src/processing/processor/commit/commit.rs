@@ -1,17 +1,21 @@
 use crate::{
+    benchmark::Metrics,
+    data::MemoryGauge,
     database::Database,
     discovery::Client,
+    handles::Lifecycle,
     processing::{
-        messages::CommitRequest,
+        messages::{CommitRequest, CommitResponse},
         processor::commit::{errors::ServeCommitError, handlers},
-        Processor,
+        Processor, Timeout,
     },
+    telemetry::{Registry, Span},
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use talk::{
     crypto::KeyChain,
@@ -26,22 +30,64 @@ impl Processor {
         view: View,
         database: Arc<Voidable<Database>>,
         listener: L,
+        memory_gauge: MemoryGauge,
+        metrics: Metrics,
+        registry: Registry,
+        receive_timeout: Timeout,
+        lifecycle: Lifecycle,
     ) where
         L: Listener,
     {
         let mut listener = SessionListener::new(listener);
+
+        let latency = registry.histogram(
+            "carbon_commit_session_seconds",
+            "Latency of successful commit sessions",
+        );
+
         let fuse = Fuse::new();
 
         loop {
             let (_, session) = listener.accept().await;
 
+            // While draining, new sessions are dropped unserved
+            let engagement = match lifecycle.engage() {
+                Some(engagement) => engagement,
+                None => continue,
+            };
+
             let keychain = keychain.clone();
             let discovery = discovery.clone();
             let view = view.clone();
             let database = database.clone();
+            let memory_gauge = memory_gauge.clone();
+            let metrics = metrics.clone();
+            let latency = latency.clone();
+            let receive_timeout = receive_timeout.clone();
 
             fuse.spawn(async move {
-                let _ = Processor::serve_commit(keychain, discovery, view, database, session).await;
+                let _engagement = engagement;
+
+                let start = Instant::now();
+
+                let result = Processor::serve_commit(
+                    keychain,
+                    discovery,
+                    view,
+                    database,
+                    session,
+                    memory_gauge,
+                    receive_timeout,
+                )
+                .await;
+
+                match result {
+                    Ok(()) => {
+                        metrics.success("commit", start.elapsed());
+                        latency.observe(start.elapsed());
+                    }
+                    Err(_) => metrics.failure("commit", "error"),
+                }
             });
         }
     }
@@ -52,12 +98,44 @@ impl Processor {
         view: View,
         database: Arc<Voidable<Database>>,
         mut session: Session,
+        memory_gauge: MemoryGauge,
+        receive_timeout: Timeout,
     ) -> Result<(), Top<ServeCommitError>> {
-        let request = session
-            .receive::<CommitRequest>()
+        let request = receive_timeout
+            .run(session.receive::<CommitRequest>())
             .await
+            .pot(ServeCommitError::ReceiveTimeout, here!())?
             .pot(ServeCommitError::ConnectionError, here!())?;
 
+        // A traced session is preceded by the `TraceContext` of the brokerage:
+        // the rest of the session is traced by a child span
+
+        let (_span, request) = match request {
+            CommitRequest::Trace(trace) => {
+                let span = Span::child("processor.commit", trace);
+
+                let request = receive_timeout
+                    .run(session.receive::<CommitRequest>())
+                    .await
+                    .pot(ServeCommitError::ReceiveTimeout, here!())?
+                    .pot(ServeCommitError::ConnectionError, here!())?;
+
+                (Some(span), request)
+            }
+            request => (None, request),
+        };
+
+        // While shedding, new batches are rejected: other requests are served as usual
+
+        if matches!(request, CommitRequest::Batch(_)) && memory_gauge.is_shedding() {
+            session
+                .send(&CommitResponse::Busy)
+                .await
+                .pot(ServeCommitError::ConnectionError, here!())?;
+
+            return ServeCommitError::Busy.fail().spot(here!());
+        }
+
         match request {
             CommitRequest::Ping => handlers::ping(session).await,
             CommitRequest::Batch(payloads) => {
@@ -67,6 +145,7 @@ impl Processor {
                     &view,
                     database.as_ref(),
                     session,
+                    &receive_timeout,
                     payloads,
                 )
                 .await
@@ -4,12 +4,20 @@ use doomstack::Doom;
 pub(in crate::processing::processor::commit) enum ServeCommitError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Unexpected request"))]
     UnexpectedRequest,
     #[doom(description("Malformed batch"))]
     MalformedBatch,
+    #[doom(description("Operation exceeds its `KeyDelegation`'s limits"))]
+    DelegationExceeded,
+    #[doom(description("Withdraw to a closed account"))]
+    ClosedBeneficiary,
     #[doom(description("Database void"))]
     DatabaseVoid,
+    #[doom(description("Failed to persist database update"))]
+    StorageFailed,
     #[doom(description("Invalid batch"))]
     InvalidBatch,
     #[doom(description("Malformed commit proofs"))]
@@ -26,4 +34,6 @@ pub(in crate::processing::processor::commit) enum ServeCommitError {
     BatchInapplicable,
     #[doom(description("`BatchCompletion` invalid"))]
     BatchCompletionInvalid,
+    #[doom(description("Busy: memory watermark exceeded"))]
+    Busy,
 }
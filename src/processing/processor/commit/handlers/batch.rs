@@ -4,7 +4,8 @@ use crate::{
     discovery::Client,
     processing::{
         messages::CommitResponse,
-        processor::commit::{errors::ServeCommitError, steps},
+        processor::commit::{errors::ServeCommitError, steps, BatchContext},
+        Timeout,
     },
     view::View,
 };
@@ -21,22 +22,35 @@ pub(in crate::processing::processor::commit) async fn batch(
     view: &View,
     database: &Voidable<Database>,
     mut session: Session,
+    receive_timeout: &Timeout,
     payloads: Vector<Payload>,
 ) -> Result<(), Top<ServeCommitError>> {
+    let context = BatchContext::new(discovery.namespace(), view, &payloads);
+
     // Obtain a `WitnessedBatch`
 
-    let batch =
-        steps::witnessed_batch(keychain, discovery, view, database, &mut session, payloads).await?;
+    let batch = steps::witnessed_batch(
+        keychain,
+        discovery,
+        &context,
+        database,
+        &mut session,
+        receive_timeout,
+        payloads,
+    )
+    .await?;
 
     // Retrieve the `Operation` (if any) on which each element of `payloads` depends. If any
     // `Operation` cannot be retrieved directly from a completed `WitnessedBatch` in `database`,
     // query `session` for the necessary `Completion`s.
 
-    let dependencies = steps::fetch_dependencies(discovery, database, &mut session, &batch).await?;
+    let dependencies =
+        steps::fetch_dependencies(discovery, database, &mut session, receive_timeout, &batch)
+            .await?;
 
     // Apply `batch` to `database` to obtain a `BatchCompletionShard`
 
-    let shard = steps::apply_batch(keychain, view, database, batch, dependencies).await?;
+    let shard = steps::apply_batch(keychain, &context, database, batch, dependencies).await?;
 
     // Send `shard` and end `session`
 
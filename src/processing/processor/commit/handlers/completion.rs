@@ -21,15 +21,11 @@ pub(in crate::processing::processor::commit) async fn completion(
 
     // Store `completion` in `completion.root()`'s `BatchHolder`, if still available in `database`
 
-    {
-        let mut database = database
-            .lock()
-            .pot(ServeCommitError::DatabaseVoid, here!())?;
-
-        if let Some(holder) = database.commit.batches.get_mut(&completion.root()) {
-            holder.attach(completion);
-        }
-    }
+    database
+        .lock()
+        .pot(ServeCommitError::DatabaseVoid, here!())?
+        .attach_batch_completion(completion)
+        .pot(ServeCommitError::StorageFailed, here!())?;
 
     session.end();
 
@@ -1,4 +1,7 @@
+mod batch_context;
 mod commit;
 mod errors;
 mod handlers;
 mod steps;
+
+use batch_context::BatchContext;
@@ -1,173 +1,58 @@
-use buckets::{Buckets, Split};
-
 use crate::{
-    account::{Account, Entry, Id, Operation},
-    commit::{BatchCompletionShard, Payload, WitnessedBatch},
-    crypto::Identify,
-    database::{
-        commit::{BatchHolder, PayloadHandle},
-        Database,
-    },
-    processing::processor::commit::errors::ServeCommitError,
-    view::View,
+    account::Operation,
+    commit::{BatchCompletionShard, WitnessedBatch},
+    database::Database,
+    processing::processor::commit::{errors::ServeCommitError, BatchContext},
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::collections::HashMap;
-
 use talk::{crypto::KeyChain, sync::voidable::Voidable};
 
-use zebra::database::TableTransaction;
-
 pub(in crate::processing::processor::commit) async fn apply_batch(
     keychain: &KeyChain,
-    view: &View,
+    context: &BatchContext,
     database: &Voidable<Database>,
     batch: WitnessedBatch,
-    dependencies: Vec<Option<Operation>>,
+    dependencies: Vec<Vec<Operation>>,
 ) -> Result<BatchCompletionShard, Top<ServeCommitError>> {
-    let root = batch.root();
-
-    // Check if `batch` can be applied to `database` (i.e.,
-    // every `Entry` in `batch.payloads()` is applicable
-    // to the relevant element of `database.accounts`)
-
-    let entries = batch
-        .payloads()
-        .iter()
-        .map(Payload::entry)
-        .collect::<Split<_>>();
-
-    let inapplicable_ids = {
-        let mut database = database
-            .lock()
-            .pot(ServeCommitError::DatabaseVoid, here!())?;
-
-        buckets::apply_sparse(&mut database.accounts, entries, |accounts, entry| {
-            // Fetch `entry.id`'s `Account` (if no operation was previously
-            // processed from `entry.id`, initialize an empty `Account`)
-
-            let account = accounts
-                .entry(entry.id)
-                .or_insert_with(|| Account::new(entry.id, &Default::default())); // TODO: Add settings
-
-            // If `entry.height` is not applicable to `account`, return `entry.id`
-
-            if account.applicable(entry.height) {
-                None
-            } else {
-                Some(entry.id)
-            }
-        })
+    let root = context.root();
+
+    // Dispatch `batch` to `database` (the whole batch must be applicable in order
+    // to be processed), then apply it once `database` is released: batches touching
+    // disjoint accounts are applied concurrently (see `Database::dispatch_commit_batch`)
+
+    let accounts = database
+        .lock()
+        .pot(ServeCommitError::DatabaseVoid, here!())?
+        .accounts
+        .clone();
+
+    let application = database
+        .lock()
+        .pot(ServeCommitError::DatabaseVoid, here!())?
+        .dispatch_commit_batch(&accounts, batch, dependencies)
+        .pot(ServeCommitError::StorageFailed, here!())?;
+
+    let exceptions = match application {
+        Some(application) => application.join(),
+        None => return ServeCommitError::BatchInapplicable.fail().spot(here!()),
     };
 
-    // The whole batch must be applicable in order to be processed
-    if !inapplicable_ids.is_empty() {
-        return ServeCommitError::BatchInapplicable.fail().spot(here!());
-    }
-
-    // Zip together in a `Split` an enumeration of `payloads` and `dependencies`
-
-    let applications = Split::with_key(
-        batch
-            .payloads()
-            .iter()
-            .cloned()
-            .zip(dependencies.iter().cloned())
-            .enumerate(),
-        |(_, (payload, _))| payload.id(),
-    );
-
-    let flush = {
-        let mut database = database
-            .lock()
-            .pot(ServeCommitError::DatabaseVoid, here!())?;
-
-        // Apply each `(_, (payload, dependency))` in `applications` to `database.accounts`,
-        // then store `payload` in `database.commit.payloads` as a `PayloadHandle`
-
-        fn fields(
-            database: &mut Database,
-        ) -> (
-            &mut Buckets<HashMap<Id, Account>>,
-            &mut Buckets<HashMap<Entry, PayloadHandle>>,
-        ) {
-            (&mut database.accounts, &mut database.commit.payloads)
-        }
-
-        let (accounts, payloads) = fields(&mut database);
-
-        let flush = buckets::apply_attached(
-            (accounts, payloads),
-            &root,
-            applications,
-            |(accounts, payloads), root, (index, (payload, dependency))| {
-                // Apply `(payload, dependency)` to `accounts`
-
-                // All missing accounts where created when checking applicability,
-                // so the following `unwrap` is guaranteed to succeed
-                let account = accounts.get_mut(&payload.id()).unwrap();
-
-                let exception = if account.apply(
-                    &payload,
-                    dependency.as_ref(),
-                    &Default::default(), // TODO: Add settings
-                ) {
-                    None
-                } else {
-                    Some(payload.id())
-                };
-
-                let id = payload.id();
-                let summary = account.summarize();
-
-                // Store (a reference to) `payload` in `payloads`
-
-                payloads.insert(
-                    payload.entry(),
-                    PayloadHandle {
-                        batch: *root,
-                        index,
-                    },
-                );
-
-                ((id, summary), exception)
-            },
-        );
-
-        // Store `batch` in `database.commit.batches`
-
-        database
-            .commit
-            .batches
-            .insert(root, BatchHolder::new(batch));
-
-        flush
-    }
-    .join();
-
-    let mut transaction = TableTransaction::new();
-
-    let exceptions = flush
-        .into_iter()
-        .filter_map(|((id, summary), exception)| {
-            transaction.set(id, summary).unwrap();
-            exception
-        })
-        .collect::<Vec<_>>();
-
-    {
-        let mut database = database
-            .lock()
-            .pot(ServeCommitError::DatabaseVoid, here!())?;
-
-        database.imminent.execute(transaction);
-    }
+    database
+        .lock()
+        .pot(ServeCommitError::DatabaseVoid, here!())?
+        .settle();
 
     // Sign and return a `BatchCompletionShard` with the appropriate `exceptions`
 
-    let shard = BatchCompletionShard::new(keychain, view.identifier(), root, exceptions);
+    let shard = BatchCompletionShard::new(
+        keychain,
+        context.namespace(),
+        context.view(),
+        root,
+        exceptions,
+    );
 
     Ok(shard)
 }
@@ -1,7 +1,7 @@
 use buckets::{Buckets, Split};
 
 use crate::{
-    account::{Entry, Operation},
+    account::{Entry, Id, Operation},
     commit::{Payload, WitnessedBatch},
     database::{
         commit::{BatchHolder, PayloadHandle},
@@ -11,6 +11,7 @@ use crate::{
     processing::{
         messages::{CommitRequest, CommitResponse},
         processor::commit::errors::ServeCommitError,
+        Timeout,
     },
 };
 
@@ -18,7 +19,7 @@ use doomstack::{here, Doom, ResultExt, Top};
 
 use rayon::prelude::*;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use talk::{crypto::primitives::hash::Hash, net::Session, sync::voidable::Voidable};
 
@@ -26,17 +27,21 @@ pub(in crate::processing::processor::commit) async fn fetch_dependencies(
     discovery: &Client,
     database: &Voidable<Database>,
     session: &mut Session,
+    receive_timeout: &Timeout,
     batch: &WitnessedBatch,
-) -> Result<Vec<Option<Operation>>, Top<ServeCommitError>> {
+) -> Result<Vec<Vec<Operation>>, Top<ServeCommitError>> {
     // Collect all completed `Operation`s in `database` on which
     // the `Payloads` of `batch` depend
 
     let database_operations = {
-        // Collect `Id` and dependency `Entry` of all `Payload`s in `batch` whose dependency is `Some`.
-        let queries = batch.payloads().iter().filter_map(|payload| {
+        // Collect `Id` and dependency `Entry` of all dependencies of all `Payload`s in `batch`
+        let queries = batch.payloads().iter().flat_map(|payload| {
+            let id = payload.id();
+
             payload
-                .dependency()
-                .map(|dependency| (payload.id(), dependency))
+                .dependencies()
+                .into_iter()
+                .map(move |dependency| (id, dependency))
         });
 
         // `Split` `queries` according to the `Id` of each dependency (`database`
@@ -100,51 +105,97 @@ pub(in crate::processing::processor::commit) async fn fetch_dependencies(
     }
     .join();
 
-    // Extract `Option` dependencies from `batch.payloads()`
-
-    let dependencies = batch.payloads().iter().map(Payload::dependency);
-
-    // Extract `Id`s and corresponding `Entry` dependencies missing from `database`
+    // Collect the `Id`s of all `Payload`s in `batch` for which at least one dependency is
+    // missing from `database` (`database_operations` lists the dependencies of each `Payload`
+    // contiguously, so that `dedup` suffices to remove duplicates)
 
-    let missing = database_operations
+    let mut missing = database_operations
         .iter()
-        .filter_map(|operation| operation.as_ref().err().cloned())
+        .filter_map(|operation| operation.as_ref().err().map(|(id, _)| *id))
         .collect::<Vec<_>>();
 
+    missing.dedup();
+
+    let session_operations = if missing.is_empty() {
+        Vec::new()
+    } else {
+        query_dependencies(discovery, session, receive_timeout, batch, missing.clone()).await?
+    };
+
+    // Satisfy the dependencies of each `Payload` in `batch`, either from `database_operations`
+    // or (if any was missing from `database`) from `session_operations`. The following
+    // `unwrap`s cannot fail:
+    //  - `database_operations` lists all the dependencies of all `Payload`s in `batch`
+    //  - `session_operations` lists all the dependencies of all `Payload`s in `missing`
+    //  - All elements of `database_operations` for `Payload`s not in `missing` are `Ok`
+
+    let missing = missing.into_iter().collect::<HashSet<_>>();
+
     let mut database_operations = database_operations.into_iter();
+    let mut session_operations = session_operations.into_iter();
 
-    if missing.is_empty() {
-        // No query to `session` is necessary: `unwrap` `database_operations` to match
-        // each `Some` element of `dependencies`
-        let operations = dependencies
-            .map(|dependency| {
-                dependency.map(|_| {
-                    // The following `unwrap`s cannot fail:
-                    // - There are as many `database_operations` as there are `Some` `dependencies`
-                    // - All elements of `database_operations` are guaranteed to be `Ok`
-                    database_operations.next().unwrap().unwrap()
-                })
-            })
-            .collect::<Vec<_>>();
-
-        return Ok(operations);
-    }
+    let operations = batch
+        .payloads()
+        .iter()
+        .map(|payload| {
+            let dependencies = payload.dependencies().len();
+            let database_operations = database_operations.by_ref().take(dependencies);
+
+            if missing.contains(&payload.id()) {
+                // Drain `database_operations` nonetheless, to keep it aligned
+                database_operations.for_each(drop);
+
+                session_operations
+                    .by_ref()
+                    .take(dependencies)
+                    .collect::<Vec<_>>()
+            } else {
+                database_operations
+                    .map(|operation| operation.unwrap())
+                    .collect::<Vec<_>>()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(operations)
+}
 
-    // Send the `MissingDependencies` vector of `Id`s of payloads whose dependency is `Some`,
-    // but could not be satisfied in `database`
+// Queries `session` for `Completion`s of all dependencies of the `Payload`s in `batch`
+// whose `Id` is in `missing`, returning their `Operation`s
+async fn query_dependencies(
+    discovery: &Client,
+    session: &mut Session,
+    receive_timeout: &Timeout,
+    batch: &WitnessedBatch,
+    missing: Vec<Id>,
+) -> Result<Vec<Operation>, Top<ServeCommitError>> {
+    // Dependencies that `session` is expected to provide, in order
+
+    let expected = {
+        let missing = missing.iter().copied().collect::<HashSet<_>>();
+
+        batch
+            .payloads()
+            .iter()
+            .filter(|payload| missing.contains(&payload.id()))
+            .flat_map(Payload::dependencies)
+            .collect::<Vec<_>>()
+    };
 
-    let missing_ids = missing.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    // Send the `MissingDependencies` vector of `Id`s of payloads for which
+    // at least one dependency could not be satisfied in `database`
 
     session
-        .send(&CommitResponse::MissingDependencies(missing_ids))
+        .send(&CommitResponse::MissingDependencies(missing))
         .await
         .pot(ServeCommitError::ConnectionError, here!())?;
 
     // Receive `Dependencies` (any other request is unexpected)
 
-    let request = session
-        .receive::<CommitRequest>()
+    let request = receive_timeout
+        .run(session.receive::<CommitRequest>())
         .await
+        .pot(ServeCommitError::ReceiveTimeout, here!())?
         .pot(ServeCommitError::ConnectionError, here!())?;
 
     let completions = match request {
@@ -154,18 +205,18 @@ pub(in crate::processing::processor::commit) async fn fetch_dependencies(
         }
     };
 
-    // Each element of `completions` must match  a corresponding element of `missing`
+    // Each element of `completions` must match a corresponding element of `expected`
 
-    if completions.len() != missing.len() {
+    if completions.len() != expected.len() {
         return ServeCommitError::MalformedDependencies.fail().spot(here!());
     }
 
-    // Validate each element of `completions` against the corresponding element of `missing`
+    // Validate each element of `completions` against the corresponding element of `expected`
 
-    missing
+    expected
         .par_iter()
         .zip(completions.par_iter())
-        .map(|((_, dependency), completion)| {
+        .map(|(dependency, completion)| {
             // `completion` must be relevant to `dependency` and valid
             if completion.entry() != *dependency {
                 ServeCommitError::MismatchedDependency.fail().spot(here!())
@@ -177,31 +228,9 @@ pub(in crate::processing::processor::commit) async fn fetch_dependencies(
         })
         .collect::<Result<_, _>>()?;
 
-    // Use `completions` to fill the gaps in `database_operations`
-    // and satisfy every element of `dependencies`
-
-    let mut session_operations = completions
+    let operations = completions
         .into_iter()
-        .map(|completion| completion.operation().clone());
-
-    let operations = dependencies
-        .map(|dependency| {
-            dependency.map(|_| {
-                // The next element of `database_operations` is `Some`
-                // if and only if the ignored argument `_` of this closure
-                // could be found in `database`.
-                // If the next element of `database_operations` is `None`,
-                // then `_` was satisfied by querying `session`, and the
-                // relevant operation can be extracted from the next element
-                // of `completions`.
-                match database_operations.next().unwrap() {
-                    Ok(operation) => operation,
-                    // Noting that `session_operations` is as long as
-                    // `missing`, the following cannot fail
-                    Err(_) => session_operations.next().unwrap(),
-                }
-            })
-        })
+        .map(|completion| completion.operation().clone())
         .collect::<Vec<_>>();
 
     Ok(operations)
@@ -3,6 +3,7 @@ use crate::{
     processing::{
         messages::{CommitRequest, CommitResponse},
         processor::commit::errors::ServeCommitError,
+        Timeout,
     },
 };
 
@@ -12,6 +13,7 @@ use talk::{crypto::primitives::multi::Signature as MultiSignature, net::Session}
 
 pub(in crate::processing::processor::commit) async fn trade_witnesses(
     session: &mut Session,
+    receive_timeout: &Timeout,
     shard: MultiSignature,
 ) -> Result<Certificate, Top<ServeCommitError>> {
     // Send witness `shard`
@@ -24,9 +26,10 @@ pub(in crate::processing::processor::commit) async fn trade_witnesses(
     // Receive witness certificate (which aggregates a plurality of witness
     // shards produced by other members of the replica's view)
 
-    let request = session
-        .receive::<CommitRequest>()
+    let request = receive_timeout
+        .run(session.receive::<CommitRequest>())
         .await
+        .pot(ServeCommitError::ReceiveTimeout, here!())?
         .pot(ServeCommitError::ConnectionError, here!())?;
 
     let witness = match request {
@@ -2,16 +2,17 @@ use buckets::{Buckets, Split};
 
 use crate::{
     account::Id,
-    commit::{Payload, WitnessStatement},
+    commit::Payload,
     database::{
         prepare::{BatchHolder, PrepareHandle, State},
         Database,
     },
     discovery::Client,
-    prepare::Prepare,
+    prepare::{BatchCommitCache, Prepare},
     processing::{
         messages::{CommitRequest, CommitResponse},
-        processor::commit::errors::ServeCommitError,
+        processor::commit::{errors::ServeCommitError, BatchContext},
+        Timeout,
     },
 };
 
@@ -35,8 +36,10 @@ use zebra::vector::Vector;
 pub(in crate::processing::processor::commit) async fn validate_batch(
     keychain: &KeyChain,
     discovery: &Client,
+    context: &BatchContext,
     database: &Voidable<Database>,
     session: &mut Session,
+    receive_timeout: &Timeout,
     payloads: &Vector<Payload>,
 ) -> Result<MultiSignature, Top<ServeCommitError>> {
     // Verify that `paylods` is strictly increasing by `Id`
@@ -50,6 +53,12 @@ pub(in crate::processing::processor::commit) async fn validate_batch(
         return ServeCommitError::MalformedBatch.fail().spot(here!());
     }
 
+    // Verify that each operation signed by a delegate key is within its delegation's limits
+
+    if !payloads.items().par_iter().all(Payload::authorized) {
+        return ServeCommitError::DelegationExceeded.fail().spot(here!());
+    }
+
     // Obtain the `Prepare` relevant to each element of `payloads`
 
     let prepares = payloads
@@ -65,6 +74,19 @@ pub(in crate::processing::processor::commit) async fn validate_batch(
             .lock()
             .pot(ServeCommitError::DatabaseVoid, here!())?;
 
+        // Refuse to credit accounts whose closure was already committed (remark: a
+        // withdraw racing its beneficiary's closure can still be committed, as
+        // closures are only known locally once applied)
+        database.settle();
+
+        if payloads
+            .items()
+            .iter()
+            .any(|payload| database.commit.credits_closed(payload.operation()))
+        {
+            return ServeCommitError::ClosedBeneficiary.fail().spot(here!());
+        }
+
         fn fields(
             database: &mut Database,
         ) -> (
@@ -149,9 +171,10 @@ pub(in crate::processing::processor::commit) async fn validate_batch(
 
         // Receive `CommitProofs` (any other request is unexpected)
 
-        let request = session
-            .receive::<CommitRequest>()
+        let request = receive_timeout
+            .run(session.receive::<CommitRequest>())
             .await
+            .pot(ServeCommitError::ReceiveTimeout, here!())?
             .pot(ServeCommitError::ConnectionError, here!())?;
 
         let proofs = match request {
@@ -164,13 +187,16 @@ pub(in crate::processing::processor::commit) async fn validate_batch(
             return ServeCommitError::MalformedCommitProofs.fail().spot(here!());
         }
 
-        // Each element of `unproven_prepares` must be valid against its corresponding element of `proofs`
+        // Each element of `unproven_prepares` must be valid against its corresponding element of `proofs`.
+        // Many `proofs` typically share the same `BatchCommit`, which is verified only once
+        let batch_commit_cache = BatchCommitCache::new(proofs.len());
+
         unproven_prepares
             .into_par_iter()
             .zip(proofs.into_par_iter())
             .map(|(prepare, proof)| {
                 proof
-                    .validate(discovery, &prepare)
+                    .validate_cached(discovery, &prepare, &batch_commit_cache)
                     .pot(ServeCommitError::InvalidCommitProof, here!())
             })
             .collect::<Result<(), Top<ServeCommitError>>>()?;
@@ -178,8 +204,7 @@ pub(in crate::processing::processor::commit) async fn validate_batch(
 
     // All `payloads` are eligible to be committed: sign and return a witness shard
 
-    let witness_statement = WitnessStatement::new(payloads.root());
-    let witness_shard = keychain.multisign(&witness_statement).unwrap();
+    let witness_shard = keychain.multisign(context.witness_statement()).unwrap();
 
     Ok(witness_shard)
 }
@@ -1,13 +1,12 @@
 use crate::{
     commit::{Payload, WitnessedBatch},
-    crypto::Identify,
     database::Database,
     discovery::Client,
     processing::{
         messages::CommitRequest,
-        processor::commit::{errors::ServeCommitError, steps},
+        processor::commit::{errors::ServeCommitError, steps, BatchContext},
+        Timeout,
     },
-    view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
@@ -19,9 +18,10 @@ use zebra::vector::Vector;
 pub(in crate::processing::processor::commit) async fn witnessed_batch(
     keychain: &KeyChain,
     discovery: &Client,
-    view: &View,
+    context: &BatchContext,
     database: &Voidable<Database>,
     session: &mut Session,
+    receive_timeout: &Timeout,
     payloads: Vector<Payload>,
 ) -> Result<WitnessedBatch, Top<ServeCommitError>> {
     // Receive either:
@@ -29,9 +29,10 @@ pub(in crate::processing::processor::commit) async fn witnessed_batch(
     //  - A request to validate the batch and produce a witness
     //    shard, which will be traded for a witness
 
-    let request = session
-        .receive::<CommitRequest>()
+    let request = receive_timeout
+        .run(session.receive::<CommitRequest>())
         .await
+        .pot(ServeCommitError::ReceiveTimeout, here!())?
         .pot(ServeCommitError::ConnectionError, here!())?;
 
     let witness = match request {
@@ -42,12 +43,20 @@ pub(in crate::processing::processor::commit) async fn witnessed_batch(
         }
         CommitRequest::WitnessRequest => {
             // Validate the batch to obtain a witness shard
-            let witness_shard =
-                steps::validate_batch(keychain, discovery, database, session, &payloads).await?;
+            let witness_shard = steps::validate_batch(
+                keychain,
+                discovery,
+                context,
+                database,
+                session,
+                receive_timeout,
+                &payloads,
+            )
+            .await?;
 
             // Trade `witness_shard` for a full witness (which aggregates the witness shards
             // of a plurality of replicas in `view`)
-            let witness = steps::trade_witnesses(session, witness_shard).await?;
+            let witness = steps::trade_witnesses(session, receive_timeout, witness_shard).await?;
 
             Ok(witness)
         }
@@ -56,7 +65,7 @@ pub(in crate::processing::processor::commit) async fn witnessed_batch(
 
     // Assemble `payloads` and `witness` in a `WitnessedBatch` to validate and return
 
-    let batch = WitnessedBatch::new(view.identifier(), payloads, witness);
+    let batch = WitnessedBatch::new(context.view(), payloads, witness);
 
     batch
         .validate(discovery)
@@ -0,0 +1,54 @@
+use crate::{
+    database::Database,
+    processing::{processor_settings::Compaction, Processor},
+};
+
+use std::sync::Arc;
+
+use talk::sync::voidable::Voidable;
+
+use tokio::time;
+
+impl Processor {
+    // Returns as soon as `database` can no longer be locked (see `probe_database`)
+    pub(in crate::processing::processor) async fn compact_database(
+        database: Arc<Voidable<Database>>,
+        settings: Compaction,
+    ) {
+        loop {
+            time::sleep(settings.interval).await;
+
+            let (prepare_pruned, commit_pruned, compacted) = match database.lock() {
+                Ok(mut database) => {
+                    let prepare_pruned = database.prune_prepare_batches(settings.batch_retention);
+                    let commit_pruned = database.prune_commit_batches(settings.commit_retention);
+
+                    // Compaction follows pruning, so that pruned batches are not retained
+                    let compacted = match database.log_size() {
+                        Some(size) if size > settings.log_threshold => {
+                            Some(database.compact().is_ok())
+                        }
+                        _ => None,
+                    };
+
+                    (prepare_pruned, commit_pruned, compacted)
+                }
+                Err(_) => return,
+            };
+
+            if prepare_pruned > 0 {
+                log::debug!("Pruned {} prepare batches", prepare_pruned);
+            }
+
+            if commit_pruned > 0 {
+                log::debug!("Pruned {} commit batches", commit_pruned);
+            }
+
+            match compacted {
+                Some(true) => log::debug!("Compacted the database log"),
+                Some(false) => log::warn!("Failed to compact the database log"),
+                None => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{database::Database, processing::Processor};
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
+
+use talk::sync::voidable::Voidable;
+
+use tokio::time;
+
+impl Processor {
+    // Returns as soon as `database` can no longer be locked
+    pub(in crate::processing::processor) async fn probe_database(
+        database: Arc<Voidable<Database>>,
+        interval: Duration,
+    ) {
+        loop {
+            time::sleep(interval).await;
+
+            // A task that panicked while holding `database`'s lock leaves the lock
+            // poisoned: locking then either fails or panics, both of which mean
+            // that `database` is lost
+            let available =
+                panic::catch_unwind(AssertUnwindSafe(|| database.lock().is_ok())).unwrap_or(false);
+
+            if !available {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{data::MemoryGauge, database::Database, processing::Processor};
+
+use std::{sync::Arc, time::Duration};
+
+use talk::sync::voidable::Voidable;
+
+use tokio::time;
+
+impl Processor {
+    // Returns as soon as `database` can no longer be locked (see `probe_database`)
+    pub(in crate::processing::processor) async fn probe_memory(
+        database: Arc<Voidable<Database>>,
+        memory_gauge: MemoryGauge,
+        interval: Duration,
+    ) {
+        loop {
+            time::sleep(interval).await;
+
+            let footprint = match database.lock() {
+                Ok(database) => database.footprint(),
+                Err(_) => return,
+            };
+
+            memory_gauge.set(footprint);
+        }
+    }
+}
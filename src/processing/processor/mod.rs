@@ -1,19 +1,43 @@
 use crate::{
-    crypto::Identify, database::Database, discovery::Client, processing::ProcessorSettings,
+    account::Id,
+    benchmark::Metrics,
+    crypto::Identify,
+    data::MemoryGauge,
+    database::{
+        commit::{HistoryError, HistoryPage, HistoryQuery},
+        Database, StorageError,
+    },
+    discovery::Client,
+    handles::{DrainError, Lifecycle},
+    processing::{ProcessorSettings, Timeout},
+    signup::AssignmentVerifier,
+    telemetry::{Badge, Exporter, Registry},
     view::View,
 };
 
-use std::sync::Arc;
+use doomstack::{here, ResultExt, Top};
+
+use std::{sync::Arc, time::Duration};
 
 use talk::{
     crypto::KeyChain,
     link::context::{ConnectDispatcher, ListenDispatcher},
-    net::{Connector, Listener},
+    net::{Connector, Listener, SessionConnector},
     sync::{fuse::Fuse, voidable::Voidable},
 };
 
+use tokio::time;
+
+// `Client` offers no notification for the discovery of a specific `View`
+const DISCOVERY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub(crate) struct Processor {
     database: Arc<Voidable<Database>>,
+    receive_timeout: Timeout,
+    memory_gauge: MemoryGauge,
+    metrics: Metrics,
+    registry: Registry,
+    lifecycle: Lifecycle,
     _fuse: Fuse,
 }
 
@@ -31,35 +55,198 @@ impl Processor {
         C: Connector,
         L: Listener,
     {
+        let connect_dispatcher = ConnectDispatcher::new(connector);
+        let listen_dispatcher =
+            ListenDispatcher::new(listener, settings.listen_dispatcher_settings.clone());
+
+        Processor::with_dispatcher(
+            keychain,
+            discovery,
+            view,
+            database,
+            &connect_dispatcher,
+            &listen_dispatcher,
+            settings,
+        )
+    }
+
+    // Opens the `Database` described by `settings.persistence` (recovering the
+    // state left behind by a previous run, if persistent), then starts serving it
+    pub fn open<C, L>(
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        view: View,
+        connector: C,
+        listener: L,
+        settings: ProcessorSettings,
+    ) -> Result<Self, Top<StorageError>>
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let account_settings = settings.persistence.account_settings.clone();
+
+        let database = match settings.persistence.directory.as_ref() {
+            Some(directory) => Database::open(directory, account_settings)?,
+            None => Database::with_account_settings(account_settings),
+        };
+
+        Ok(Processor::new(
+            keychain, discovery, view, database, connector, listener, settings,
+        ))
+    }
+
+    // Multiple `Processor`s (each in its own `Namespace`, as set by its discovery
+    // `Client`) can share the same `connect_dispatcher` and `listen_dispatcher`
+    pub fn with_dispatcher(
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        view: View,
+        database: Database,
+        connect_dispatcher: &ConnectDispatcher,
+        listen_dispatcher: &ListenDispatcher,
+        settings: ProcessorSettings,
+    ) -> Self {
         let database = Arc::new(Voidable::new(database));
+        let receive_timeout = Timeout::new(settings.timeouts.receive)
+            .with_failure_injection(settings.failure_injection.clone());
+        let memory_gauge = MemoryGauge::new(settings.memory.clone());
+        let metrics = Metrics::new(settings.metrics.clone());
+        let registry = Registry::new();
+        let verifier =
+            AssignmentVerifier::new(discovery.clone(), settings.assignment_verifier.clone());
+        let lifecycle = Lifecycle::new().with_badge(Badge::replica(keychain.keycard().identity()));
 
-        let _connect_dispatcher = ConnectDispatcher::new(connector);
-        let listen_dispatcher =
-            ListenDispatcher::new(listener, settings.listen_dispatcher_settings);
+        // `namespace` both selects the contexts of the `Processor` and scopes
+        // the statements it signs (see `Namespace`)
+        let namespace = discovery.namespace().clone();
+
+        // Peers are looked up on their signup context
+        let peers = Arc::new(Peers::new(
+            view.clone(),
+            keychain.keycard().identity(),
+            SessionConnector::new(connect_dispatcher.register(namespace.context(&view, "signup"))),
+            settings.timeouts.lookup,
+        ));
 
         let fuse = Fuse::new();
 
+        // The `Processor` is ready once `discovery` knows `view`: until then,
+        // requests (queued by the listeners below) are not served, as their
+        // certificates might refer to `view`
+
         {
-            let keychain = keychain.clone();
             let discovery = discovery.clone();
+            let view = view.identifier();
+
+            fuse.spawn(lifecycle.gate(async move {
+                while discovery.view(&view).is_none() {
+                    time::sleep(DISCOVERY_POLL_INTERVAL).await;
+                }
+            }));
+        }
+
+        // Serving tasks fail one request at a time once `database` is lost
+        // (voided, or poisoned by a panicking task): `database` is probed
+        // so that its loss is reported, rather than silently stalling service
+
+        {
+            let database = database.clone();
+            let probe_interval = settings.timeouts.database_probe;
+            let watchdog = lifecycle.clone();
+
+            fuse.spawn(lifecycle.guard("database", async move {
+                Processor::probe_database(database, probe_interval).await;
+
+                if !watchdog.is_shutting_down() {
+                    log::error!("Database lost: processor unavailable");
+                    watchdog.set_unavailable();
+                }
+            }));
+        }
+
+        // The footprint of `database` is estimated periodically: once it exceeds
+        // its high watermark, new batches are rejected until it is relieved
+
+        {
+            let database = database.clone();
+            let memory_gauge = memory_gauge.clone();
+            let probe_interval = settings.memory.probe_interval;
+
+            fuse.spawn(async move {
+                Processor::probe_memory(database, memory_gauge, probe_interval).await;
+            });
+        }
+
+        {
+            let metrics = metrics.clone();
+            fuse.spawn(metrics.run());
+        }
+
+        {
+            let memory_gauge = memory_gauge.clone();
+
+            registry.gauge(
+                "carbon_database_bytes",
+                "Estimated memory footprint of the database",
+                move || memory_gauge.usage() as f64,
+            );
+        }
+
+        if let Some(address) = settings.exporter.address {
+            let registry = registry.clone();
+
+            fuse.spawn(lifecycle.guard("exporter", async move {
+                if Exporter::run(registry, address).await.is_err() {
+                    log::error!("Failed to start metrics exporter on {}", address);
+                }
+            }));
+        }
+
+        // Unreferenced prepare batches are retained only for
+        // `settings.compaction.batch_retention` (see `Database::prune_prepare_batches`),
+        // commit batches for `settings.compaction.commit_retention`
+        // (see `Database::prune_commit_batches`)
+
+        {
+            let database = database.clone();
+            let compaction_settings = settings.compaction.clone();
+
+            fuse.spawn(async move {
+                Processor::compact_database(database, compaction_settings).await;
+            });
+        }
+
+        {
+            let keychain = keychain.clone();
             let view = view.clone();
             let database = database.clone();
+            let verifier = verifier.clone();
+            let namespace = namespace.clone();
 
-            let signup_context = format!("{:?}::processor::signup", view.identifier());
+            let signup_context = namespace.context(&view, "signup");
             let signup_listener = listen_dispatcher.register(signup_context);
+            let receive_timeout = receive_timeout.clone();
             let signup_settings = settings.signup;
 
-            fuse.spawn(async move {
+            let gate = lifecycle.clone();
+
+            fuse.spawn(lifecycle.guard("signup", async move {
+                gate.await_ready().await;
+
                 Processor::run_signup(
                     keychain,
-                    discovery,
+                    namespace,
                     view,
                     database,
+                    verifier,
                     signup_listener,
                     signup_settings,
+                    receive_timeout,
+                    gate,
                 )
                 .await;
-            });
+            }));
         }
 
         {
@@ -68,12 +255,36 @@ impl Processor {
             let view = view.clone();
             let database = database.clone();
 
-            let prepare_context = format!("{:?}::processor::prepare", view.identifier());
+            let prepare_context = namespace.context(&view, "prepare");
             let prepare_listener = listen_dispatcher.register(prepare_context);
+            let receive_timeout = receive_timeout.clone();
+            let prepare_settings = settings.prepare;
+            let memory_gauge = memory_gauge.clone();
+            let metrics = metrics.clone();
+            let registry = registry.clone();
 
-            fuse.spawn(async move {
-                Processor::run_prepare(keychain, discovery, view, database, prepare_listener).await;
-            });
+            let gate = lifecycle.clone();
+
+            fuse.spawn(lifecycle.guard("prepare", async move {
+                gate.await_ready().await;
+
+                Processor::run_prepare(
+                    keychain,
+                    discovery,
+                    view,
+                    database,
+                    peers,
+                    verifier,
+                    prepare_listener,
+                    prepare_settings,
+                    memory_gauge,
+                    metrics,
+                    registry,
+                    receive_timeout,
+                    gate,
+                )
+                .await;
+            }));
         }
 
         {
@@ -82,25 +293,158 @@ impl Processor {
             let view = view.clone();
             let database = database.clone();
 
-            let commit_context = format!("{:?}::processor::commit", view.identifier());
+            let commit_context = namespace.context(&view, "commit");
             let commit_listener = listen_dispatcher.register(commit_context);
+            let memory_gauge = memory_gauge.clone();
+            let metrics = metrics.clone();
+            let registry = registry.clone();
+            let receive_timeout = receive_timeout.clone();
 
-            fuse.spawn(async move {
-                Processor::run_commit(keychain, discovery, view, database, commit_listener).await;
-            });
+            let gate = lifecycle.clone();
+
+            fuse.spawn(lifecycle.guard("commit", async move {
+                gate.await_ready().await;
+
+                Processor::run_commit(
+                    keychain,
+                    discovery,
+                    view,
+                    database,
+                    commit_listener,
+                    memory_gauge,
+                    metrics,
+                    registry,
+                    receive_timeout,
+                    gate,
+                )
+                .await;
+            }));
+        }
+
+        {
+            let keychain = keychain.clone();
+            let discovery = discovery.clone();
+            let view = view.clone();
+            let database = database.clone();
+
+            let sync_context = namespace.context(&view, "sync");
+            let sync_listener = listen_dispatcher.register(sync_context);
+            let sync_settings = settings.state_sync;
+            let receive_timeout = receive_timeout.clone();
+
+            let gate = lifecycle.clone();
+
+            fuse.spawn(lifecycle.guard("sync", async move {
+                gate.await_ready().await;
+
+                Processor::run_sync(
+                    keychain,
+                    discovery,
+                    view,
+                    database,
+                    sync_listener,
+                    sync_settings,
+                    receive_timeout,
+                )
+                .await;
+            }));
+        }
+
+        // Read-only delegates mirror `database` from the stream of its records,
+        // offloading queries (see `Delegate`)
+
+        {
+            let database = database.clone();
+
+            let stream_context = namespace.context(&view, "stream");
+            let stream_listener = listen_dispatcher.register(stream_context);
+            let delegation = settings.delegation;
+
+            let gate = lifecycle.clone();
+
+            fuse.spawn(lifecycle.guard("stream", async move {
+                gate.await_ready().await;
+                Processor::run_stream(database, stream_listener, delegation).await;
+            }));
         }
 
         Processor {
             database,
+            receive_timeout,
+            memory_gauge,
+            metrics,
+            registry,
+            lifecycle,
             _fuse: fuse,
         }
     }
 
+    // Number of serve-path `receive`s that timed out
+    pub fn expired_timeouts(&self) -> u64 {
+        self.receive_timeout.expired()
+    }
+
+    // Whether the `Processor` is rejecting new batches, its `Database`
+    // having exceeded its memory watermarks
+    pub fn memory_gauge(&self) -> &MemoryGauge {
+        &self.memory_gauge
+    }
+
+    // Outcomes of the `Processor`'s prepare and commit sessions
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Instruments of the `Processor`, as served by its `Exporter` (if any)
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    // Stops serving new sessions, and waits (for at most `timeout`) for in-flight
+    // sessions to complete: a `shutdown` that follows cuts no session mid-protocol
+    pub async fn drain(&self, timeout: Duration) -> Result<(), Top<DrainError>> {
+        self.lifecycle.drain(timeout).await
+    }
+
+    // Answers `query` from the `Processor`'s `Database` (see `Commit::history`)
+    pub fn history(&self, query: &HistoryQuery) -> Result<HistoryPage, Top<HistoryError>> {
+        self.database
+            .lock()
+            .pot(HistoryError::DatabaseVoid, here!())?
+            .commit
+            .history(query)
+    }
+
+    // Balance of each element of `ids` (see `Database::balances`),
+    // or `None` if the `Processor` was shut down
+    pub fn balances(&self, ids: Vec<Id>) -> Option<Vec<Option<u64>>> {
+        let mut database = self.database.lock().ok()?;
+        Some(database.balances(ids))
+    }
+
     pub fn shutdown(self) -> Database {
+        self.lifecycle.shut_down();
+
+        if self.metrics.report().is_err() {
+            log::warn!("Failed to write metrics report");
+        }
+
         self.database.void()
     }
 }
 
 mod commit;
+mod compaction;
+mod database_probe;
+mod memory_probe;
+mod peers;
 mod prepare;
 mod signup;
+mod stream;
+mod sync;
+
+use peers::Peers;
@@ -0,0 +1,133 @@
+use crate::{
+    account::Id,
+    processing::messages::{SignupRequest, SignupResponse},
+    signup::{AssignmentVerifier, IdAssignment},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use std::time::Duration;
+
+use talk::{crypto::Identity, net::SessionConnector};
+
+use tokio::time;
+
+// `Peers` look up, from the other members of the local replica's `View`,
+// the `IdAssignment`s that the local replica is missing (e.g., because a
+// broker with a partial cache could not supply them)
+pub(in crate::processing) struct Peers {
+    view: View,
+    identity: Identity,
+    connector: SessionConnector,
+    lookup_timeout: Duration,
+}
+
+#[derive(Doom)]
+enum LookupError {
+    #[doom(description("Connection failed"))]
+    ConnectionFailed,
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Lookup timed out"))]
+    Timeout,
+    #[doom(description("Unexpected response"))]
+    UnexpectedResponse,
+}
+
+impl Peers {
+    pub fn new(
+        view: View,
+        identity: Identity,
+        connector: SessionConnector,
+        lookup_timeout: Duration,
+    ) -> Self {
+        Peers {
+            view,
+            identity,
+            connector,
+            lookup_timeout,
+        }
+    }
+
+    // Looks up `ids` from all peers concurrently, until a valid `IdAssignment`
+    // is found for each element of `ids` (or all peers have responded)
+    pub async fn fetch_assignments(
+        &self,
+        verifier: &AssignmentVerifier,
+        ids: &[Id],
+    ) -> Vec<Option<IdAssignment>> {
+        let mut assignments = vec![None; ids.len()];
+
+        let mut lookups = self
+            .view
+            .members()
+            .keys()
+            .copied()
+            .filter(|peer| *peer != self.identity)
+            .map(|peer| self.lookup(peer, ids.to_vec()))
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some(lookup) = lookups.next().await {
+            let found = match lookup {
+                Ok(found) if found.len() == ids.len() => found,
+                _ => continue,
+            };
+
+            // Peers might be Byzantine: each `IdAssignment` is validated
+            // against the `Id` it was looked up for
+            for ((slot, id), assignment) in assignments.iter_mut().zip(ids).zip(found) {
+                if slot.is_some() {
+                    continue;
+                }
+
+                if let Some(assignment) = assignment {
+                    if assignment.id() == *id && verifier.verify(&assignment).is_ok() {
+                        *slot = Some(assignment);
+                    }
+                }
+            }
+
+            if assignments.iter().all(Option::is_some) {
+                break;
+            }
+        }
+
+        assignments
+    }
+
+    async fn lookup(
+        &self,
+        peer: Identity,
+        ids: Vec<Id>,
+    ) -> Result<Vec<Option<IdAssignment>>, Top<LookupError>> {
+        time::timeout(self.lookup_timeout, async {
+            let mut session = self
+                .connector
+                .connect(peer)
+                .await
+                .pot(LookupError::ConnectionFailed, here!())?;
+
+            session
+                .send(&SignupRequest::IdLookups(ids))
+                .await
+                .pot(LookupError::ConnectionError, here!())?;
+
+            let response = session
+                .receive::<SignupResponse>()
+                .await
+                .pot(LookupError::ConnectionError, here!())?;
+
+            session.end();
+
+            match response {
+                SignupResponse::IdLookups(assignments) => Ok(assignments),
+                _ => LookupError::UnexpectedResponse.fail().spot(here!()),
+            }
+        })
+        .await
+        .pot(LookupError::Timeout, here!())?
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{
+    crypto::Identify,
+    prepare::{Prepare, ReductionStatement},
+    view::View,
+};
+
+use talk::crypto::primitives::hash::Hash;
+
+use zebra::vector::Vector;
+
+// Values derived from a batch (and the `View` it is processed in), computed once
+// per session and shared by all phases (hashing `View`'s identifier, in particular,
+// is not free, and would otherwise be repeated by each phase and step)
+pub(in crate::processing::processor::prepare) struct BatchContext {
+    view: Hash,
+    root: Hash,
+    reduction_statement: ReductionStatement,
+}
+
+impl BatchContext {
+    pub fn new(view: &View, prepares: &Vector<Prepare>) -> Self {
+        let root = prepares.root();
+
+        BatchContext {
+            view: view.identifier(),
+            root,
+            reduction_statement: ReductionStatement::new(root),
+        }
+    }
+
+    pub fn view(&self) -> Hash {
+        self.view
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    pub fn reduction_statement(&self) -> &ReductionStatement {
+        &self.reduction_statement
+    }
+}
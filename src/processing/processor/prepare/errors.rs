@@ -4,24 +4,50 @@ use doomstack::Doom;
 pub(in crate::processing::processor::prepare) enum ServePrepareError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Unexpected request"))]
     UnexpectedRequest,
     #[doom(description("Malformed batch"))]
     MalformedBatch,
+    #[doom(description("Compressed batches are not accepted"))]
+    CompressionRefused,
+    #[doom(description("Failed to decompress batch"))]
+    DecompressionFailed,
     #[doom(description("Database void"))]
     DatabaseVoid,
+    #[doom(description("Failed to persist database update"))]
+    StorageFailed,
     #[doom(description("Malformed id assignments"))]
     MalformedIdAssignments,
     #[doom(description("Mismatched id assignment"))]
     MismatchedIdAssignment,
     #[doom(description("Invalid id assignment"))]
     InvalidIdAssignment,
+    #[doom(description("Id assignment unknown to broker and peers"))]
+    UnresolvedIdAssignment,
     #[doom(description("Invalid batch"))]
     InvalidBatch,
+    #[doom(description("Malformed exclusions"))]
+    MalformedExclusions,
+    #[doom(description("Insufficient exclusions"))]
+    InsufficientExclusions,
     #[doom(description("Invalid witness"))]
     InvalidWitness,
     #[doom(description("Foreign commit"))]
     ForeignCommit,
     #[doom(description("Invalid commit"))]
     InvalidCommit,
+    #[doom(description("Unknown batch"))]
+    UnknownBatch,
+    #[doom(description("Foreign exception"))]
+    ForeignException,
+    #[doom(description("Invalid equivocation"))]
+    InvalidEquivocation,
+    #[doom(description("Spill failed"))]
+    SpillFailed,
+    #[doom(description("Spill corrupted"))]
+    SpillCorrupted,
+    #[doom(description("Busy: memory watermark exceeded"))]
+    Busy,
 }
@@ -1,11 +1,21 @@
 use crate::{
-    database::Database,
+    database::{Database, WriteBatch},
     discovery::Client,
     prepare::Prepare,
     processing::{
         messages::PrepareResponse,
-        processor::prepare::{errors::ServePrepareError, steps},
+        processor::prepare::{
+            errors::ServePrepareError,
+            phases::{Context, Phase, ReceiveBatch},
+            spill::Spill,
+            BatchContext,
+        },
+        processor::Peers,
+        processor_settings::Prepare as PrepareSettings,
+        Timeout,
     },
+    signup::AssignmentVerifier,
+    telemetry::Registry,
     view::View,
 };
 
@@ -13,6 +23,8 @@ use doomstack::{here, ResultExt, Top};
 
 use talk::{crypto::KeyChain, net::Session, sync::voidable::Voidable};
 
+use tokio::sync::OwnedSemaphorePermit;
+
 use zebra::vector::Vector;
 
 pub(in crate::processing::processor::prepare) async fn batch(
@@ -20,17 +32,46 @@ pub(in crate::processing::processor::prepare) async fn batch(
     discovery: &Client,
     view: &View,
     database: &Voidable<Database>,
+    peers: &Peers,
+    verifier: &AssignmentVerifier,
     mut session: Session,
+    receive_timeout: &Timeout,
     prepares: Vector<Prepare>,
+    slot: Option<OwnedSemaphorePermit>,
+    settings: &PrepareSettings,
+    registry: &Registry,
 ) -> Result<(), Top<ServePrepareError>> {
-    // Obtain a `WitnessedBatch`
+    let batch = BatchContext::new(view, &prepares);
+
+    // If `prepares` is oversized, spill it to disk while the broker
+    // gathers signatures (or a witness) for it: once `prepares` is
+    // no longer held in memory, `slot` can be released
+
+    let prepares = Spill::new(settings, prepares).await?;
+    drop(slot);
 
-    let batch =
-        steps::witnessed_batch(keychain, discovery, view, database, &mut session, prepares).await?;
+    // Run all phases to obtain a `BatchCommitShard`
 
-    // Apply `batch` to `database` to obtain a `BatchCommitShard`
+    let shard = {
+        let mut updates = WriteBatch::new();
 
-    let shard = steps::apply_batch(keychain, view, database, batch).await?;
+        let mut context = Context {
+            keychain,
+            discovery,
+            batch: &batch,
+            database,
+            updates: &mut updates,
+            peers,
+            verifier,
+            session: &mut session,
+            receive_timeout,
+            registry,
+        };
+
+        Phase::ReceiveBatch(ReceiveBatch::new(prepares))
+            .run(&mut context)
+            .await?
+    };
 
     // Send `shard` and end `session`
 
@@ -41,5 +82,9 @@ pub(in crate::processing::processor::prepare) async fn batch(
 
     session.end();
 
+    registry
+        .counter("carbon_prepare_batches_total", "Prepare batches served")
+        .increment();
+
     Ok(())
 }
@@ -0,0 +1,29 @@
+use crate::{
+    data,
+    processing::{messages::PrepareResponse, processor::prepare::errors::ServePrepareError},
+};
+
+use doomstack::{here, ResultExt, Top};
+
+use talk::net::Session;
+
+// `ClockPong` is followed by the local `Capabilities` (brokers unaware
+// of `Capabilities` end the session after `ClockPong`)
+pub(in crate::processing::processor::prepare) async fn clock_ping(
+    mut session: Session,
+    accept_compression: bool,
+) -> Result<(), Top<ServePrepareError>> {
+    session
+        .send(&PrepareResponse::ClockPong(data::timestamp()))
+        .await
+        .pot(ServePrepareError::ConnectionError, here!())?;
+
+    session
+        .send(&PrepareResponse::Capabilities(accept_compression))
+        .await
+        .pot(ServePrepareError::ConnectionError, here!())?;
+
+    session.end();
+
+    Ok(())
+}
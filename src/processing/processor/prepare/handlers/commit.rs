@@ -21,15 +21,11 @@ pub(in crate::processing::processor::prepare) async fn commit(
 
     // Store `commit` in `commit.root()`'s `BatchHolder`, if still available in `database`
 
-    {
-        let mut database = database
-            .lock()
-            .pot(ServePrepareError::DatabaseVoid, here!())?;
-
-        if let Some(holder) = database.prepare.batches.get_mut(&commit.root()) {
-            holder.attach(commit);
-        }
-    }
+    database
+        .lock()
+        .pot(ServePrepareError::DatabaseVoid, here!())?
+        .attach_batch_commit(commit)
+        .pot(ServePrepareError::StorageFailed, here!())?;
 
     session.end();
 
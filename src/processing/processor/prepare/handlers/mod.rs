@@ -1,7 +1,11 @@
 mod batch;
+mod clock_ping;
 mod commit;
 mod ping;
+mod reconcile;
 
 pub(in crate::processing::processor::prepare) use batch::batch;
+pub(in crate::processing::processor::prepare) use clock_ping::clock_ping;
 pub(in crate::processing::processor::prepare) use commit::commit;
 pub(in crate::processing::processor::prepare) use ping::ping;
+pub(in crate::processing::processor::prepare) use reconcile::reconcile;
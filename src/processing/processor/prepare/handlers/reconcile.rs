@@ -0,0 +1,135 @@
+use buckets::Split;
+
+use crate::{
+    crypto::Identify,
+    database::{prepare::State, Database},
+    discovery::Client,
+    prepare::{BatchCommitShard, Equivocation},
+    processing::{messages::PrepareResponse, processor::prepare::errors::ServePrepareError},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::collections::HashMap;
+
+use talk::{
+    crypto::{primitives::hash::Hash, KeyChain},
+    net::Session,
+    sync::voidable::Voidable,
+};
+
+pub(in crate::processing::processor::prepare) async fn reconcile(
+    keychain: &KeyChain,
+    discovery: &Client,
+    view: &View,
+    database: &Voidable<Database>,
+    mut session: Session,
+    root: Hash,
+    equivocations: Vec<Equivocation>,
+) -> Result<(), Top<ServePrepareError>> {
+    // Validate `equivocations` (before locking `database`, as validation is expensive)
+
+    for equivocation in equivocations.iter() {
+        equivocation
+            .validate(discovery)
+            .pot(ServePrepareError::InvalidEquivocation, here!())?;
+    }
+
+    let equivocations = equivocations
+        .into_iter()
+        .map(|equivocation| (equivocation.id(), equivocation))
+        .collect::<HashMap<_, _>>();
+
+    let shard = {
+        let mut database = database
+            .lock()
+            .pot(ServePrepareError::DatabaseVoid, here!())?;
+
+        // Only batches that were previously applied (and not yet garbage collected) can be reconciled
+
+        let batch = database
+            .prepare
+            .batches
+            .get(&root)
+            .ok_or(ServePrepareError::UnknownBatch.into_top())
+            .spot(here!())?
+            .batch();
+
+        // Each element of `equivocations` must concern an element of `batch` that
+        // was not excluded from `batch`'s witness (`batch.prepares()` is sorted by `Id`)
+
+        for id in equivocations.keys() {
+            let index = batch
+                .prepares()
+                .binary_search_by_key(id, |prepare| prepare.id())
+                .map_err(|_| ServePrepareError::ForeignException.into_top())
+                .spot(here!())?;
+
+            if batch.excludes(index) {
+                return ServePrepareError::ForeignException.fail().spot(here!());
+            }
+        }
+
+        let split = Split::with_key(
+            batch
+                .prepares()
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !batch.excludes(*index))
+                .map(|(_, prepare)| prepare.id()),
+            |id| *id,
+        );
+
+        let exclusions = batch.exclusions();
+
+        // `equivocations` are recorded before being adopted
+
+        database
+            .record_equivocations(equivocations.values())
+            .pot(ServePrepareError::StorageFailed, here!())?;
+
+        let prepare = &mut database.prepare;
+
+        // Adopt `equivocations`, then collect all exceptions relevant to `batch`
+        // (including those observed locally since `batch` was applied)
+        let exceptions = buckets::apply_sparse_attached(
+            (&mut prepare.states, &mut prepare.stale),
+            &equivocations,
+            split,
+            |(states, stale), equivocations, id| {
+                if let Some(State::Equivocated(equivocation)) = states.get(&id) {
+                    // `State::Equivocated` is absorbing and must not be updated
+                    return Some(equivocation.clone());
+                }
+
+                let equivocation = equivocations.get(&id)?.clone();
+
+                states.insert(id, State::Equivocated(equivocation.clone()));
+                stale.insert(id);
+
+                Some(equivocation)
+            },
+        );
+
+        BatchCommitShard::new(
+            keychain,
+            discovery.namespace(),
+            view.identifier(),
+            root,
+            exclusions,
+            exceptions,
+        )
+    };
+
+    // Send `shard` and end `session`
+
+    session
+        .send(&PrepareResponse::CommitShard(shard))
+        .await
+        .pot(ServePrepareError::ConnectionError, here!())?;
+
+    session.end();
+
+    Ok(())
+}
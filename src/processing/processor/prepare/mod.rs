@@ -1,4 +1,9 @@
+mod batch_context;
 mod errors;
 mod handlers;
+mod phases;
 mod prepare;
+mod spill;
 mod steps;
+
+use batch_context::BatchContext;
@@ -0,0 +1,135 @@
+use crate::{
+    prepare::WitnessedBatch,
+    processing::processor::prepare::{
+        errors::ServePrepareError,
+        phases::{Context, Phase},
+        steps,
+    },
+};
+
+use doomstack::{here, ResultExt, Top};
+
+use std::mem;
+
+pub(in crate::processing::processor::prepare) struct Commit {
+    batch: WitnessedBatch,
+}
+
+impl Commit {
+    pub fn new(batch: WitnessedBatch) -> Self {
+        Commit { batch }
+    }
+
+    pub async fn run(self, context: &mut Context<'_>) -> Result<Phase, Top<ServePrepareError>> {
+        // Validate `batch` (this checks the correctness of the witness
+        // acquired in previous phases)
+
+        self.batch
+            .validate(context.discovery)
+            .pot(ServePrepareError::InvalidBatch, here!())?;
+
+        // Apply `batch` to `database` to obtain a `BatchCommitShard`
+
+        let shard = steps::apply_batch(
+            context.keychain,
+            context.discovery.namespace(),
+            context.batch.view(),
+            context.database,
+            mem::take(context.updates),
+            self.batch,
+        )
+        .await?;
+
+        Ok(Phase::Done(shard))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        crypto::Identify,
+        processing::processor::prepare::{
+            phases::test::{self, Harness},
+            BatchContext,
+        },
+    };
+
+    use bit_vec::BitVec;
+
+    use std::collections::BTreeSet;
+
+    // Each element of `entries` is an `Id` and the value its `Prepare` commits to
+    fn witnessed_batch(
+        harness: &Harness,
+        entries: &[(u64, u64)],
+    ) -> (BatchContext, WitnessedBatch) {
+        let prepares = test::prepares(entries);
+        let context = BatchContext::new(&harness.view, &prepares);
+
+        let witness = harness.witness(context.root(), BTreeSet::new());
+        let batch =
+            WitnessedBatch::new(harness.view.identifier(), prepares, BitVec::new(), witness);
+
+        (context, batch)
+    }
+
+    #[tokio::test]
+    async fn commit() {
+        let (mut harness, _broker) = Harness::new().await;
+
+        let (batch, witnessed) = witnessed_batch(&harness, &[(1, 1), (2, 2), (3, 3)]);
+
+        let mut context = harness.context(&batch);
+        let shard = match Phase::Commit(Commit::new(witnessed))
+            .step(&mut context)
+            .await
+        {
+            Ok(Phase::Done(shard)) => shard,
+            _ => panic!("expected `Phase::Done`"),
+        };
+
+        assert!(shard.exceptions().is_empty());
+
+        // `Prepare`s conflicting with those already committed are reported as exceptions
+        let (batch, witnessed) = witnessed_batch(&harness, &[(2, 4), (4, 4)]);
+
+        let mut context = harness.context(&batch);
+        let shard = match Phase::Commit(Commit::new(witnessed))
+            .step(&mut context)
+            .await
+        {
+            Ok(Phase::Done(shard)) => shard,
+            _ => panic!("expected `Phase::Done`"),
+        };
+
+        assert_eq!(shard.exceptions(), BTreeSet::from([2]));
+    }
+
+    #[tokio::test]
+    async fn invalid_witness() {
+        let (mut harness, _broker) = Harness::new().await;
+
+        let prepares = test::prepares(&[(1, 1), (2, 2), (3, 3)]);
+        let batch = BatchContext::new(&harness.view, &prepares);
+
+        // `witness` excludes no `Prepare`, and does not hold for a batch excluding `Prepare` 0
+        let witness = harness.witness(batch.root(), BTreeSet::new());
+
+        let witnessed = WitnessedBatch::new(
+            harness.view.identifier(),
+            prepares,
+            vec![true, false, false].into_iter().collect(),
+            witness,
+        );
+
+        let mut context = harness.context(&batch);
+        let phase = Phase::Commit(Commit::new(witnessed))
+            .step(&mut context)
+            .await;
+
+        assert!(phase.is_err());
+        assert!(harness.database.lock().unwrap().prepare.batches.is_empty());
+    }
+}
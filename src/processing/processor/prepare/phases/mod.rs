@@ -0,0 +1,91 @@
+use crate::{
+    database::{Database, WriteBatch},
+    discovery::Client,
+    prepare::BatchCommitShard,
+    processing::{
+        processor::{
+            prepare::{errors::ServePrepareError, BatchContext},
+            Peers,
+        },
+        Timeout,
+    },
+    signup::AssignmentVerifier,
+    telemetry::Registry,
+};
+
+use doomstack::Top;
+
+use talk::{crypto::KeyChain, net::Session, sync::voidable::Voidable};
+
+mod commit;
+mod receive_batch;
+mod resolve_unknowns;
+mod verify_signatures;
+mod witness;
+
+#[cfg(test)]
+mod test;
+
+pub(in crate::processing::processor::prepare) use commit::Commit;
+pub(in crate::processing::processor::prepare) use receive_batch::ReceiveBatch;
+pub(in crate::processing::processor::prepare) use resolve_unknowns::ResolveUnknowns;
+pub(in crate::processing::processor::prepare) use verify_signatures::VerifySignatures;
+pub(in crate::processing::processor::prepare) use witness::Witness;
+
+// The batch path of `serve_prepare`, as a typed state machine. Each phase
+// consumes its predecessor to produce the next `Phase`: a phase can only be
+// entered with the data its predecessors produced, and the path is over once
+// a `BatchCommitShard` is obtained. Phases follow one of two sequences:
+//  - ReceiveBatch -> Commit, if the broker directly provides a witness.
+//  - ReceiveBatch -> ResolveUnknowns -> VerifySignatures -> Witness -> Commit,
+//    if the broker provides signatures, to be witnessed by the local replica.
+pub(in crate::processing::processor::prepare) enum Phase {
+    ReceiveBatch(ReceiveBatch),
+    ResolveUnknowns(ResolveUnknowns),
+    VerifySignatures(VerifySignatures),
+    Witness(Witness),
+    Commit(Commit),
+    Done(BatchCommitShard),
+}
+
+// Resources shared by all phases
+pub(in crate::processing::processor::prepare) struct Context<'a> {
+    pub keychain: &'a KeyChain,
+    pub discovery: &'a Client,
+    pub batch: &'a BatchContext,
+    pub database: &'a Voidable<Database>,
+    // Updates to `database`, applied all at once by the `Commit` phase
+    pub updates: &'a mut WriteBatch,
+    pub peers: &'a Peers,
+    pub verifier: &'a AssignmentVerifier,
+    pub session: &'a mut Session,
+    pub receive_timeout: &'a Timeout,
+    pub registry: &'a Registry,
+}
+
+impl Phase {
+    pub async fn step(self, context: &mut Context<'_>) -> Result<Phase, Top<ServePrepareError>> {
+        match self {
+            Phase::ReceiveBatch(phase) => phase.run(context).await,
+            Phase::ResolveUnknowns(phase) => phase.run(context).await,
+            Phase::VerifySignatures(phase) => phase.run(context).await,
+            Phase::Witness(phase) => phase.run(context).await,
+            Phase::Commit(phase) => phase.run(context).await,
+            Phase::Done(shard) => Ok(Phase::Done(shard)),
+        }
+    }
+
+    // Steps through all phases, until a `BatchCommitShard` is obtained
+    // (every `receive` is bounded by `context.receive_timeout`)
+    pub async fn run(
+        mut self,
+        context: &mut Context<'_>,
+    ) -> Result<BatchCommitShard, Top<ServePrepareError>> {
+        loop {
+            self = match self {
+                Phase::Done(shard) => return Ok(shard),
+                phase => phase.step(context).await?,
+            };
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    prepare::{Prepare, SignedBatch, WitnessedBatch},
+    processing::{
+        messages::PrepareRequest,
+        processor::prepare::{
+            errors::ServePrepareError,
+            phases::{Commit, Context, Phase, ResolveUnknowns},
+            spill::Spill,
+        },
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use talk::crypto::primitives::hash::Hash;
+
+use zebra::vector::Vector;
+
+pub(in crate::processing::processor::prepare) struct ReceiveBatch {
+    prepares: Spill,
+}
+
+impl ReceiveBatch {
+    pub fn new(prepares: Spill) -> Self {
+        ReceiveBatch { prepares }
+    }
+
+    pub async fn run(self, context: &mut Context<'_>) -> Result<Phase, Top<ServePrepareError>> {
+        // Receive either:
+        // - A witness, required to directly assemble a `WitnessedBatch`
+        // - A collection of signatures required to assemble a `SignedBatch`,
+        //   which will be validated to generate a witness shard
+
+        let request = context
+            .receive_timeout
+            .run(context.session.receive::<PrepareRequest>())
+            .await
+            .pot(ServePrepareError::ReceiveTimeout, here!())?
+            .pot(ServePrepareError::ConnectionError, here!())?;
+
+        // Reload `prepares`, if spilled (this checks `prepares`'s root against
+        // the one originally received, guarding against on-disk corruption)
+
+        let prepares = self.prepares.load().await?;
+
+        ReceiveBatch::classify(context.batch.view(), prepares, request)
+    }
+
+    fn classify(
+        view: Hash,
+        prepares: Vector<Prepare>,
+        request: PrepareRequest,
+    ) -> Result<Phase, Top<ServePrepareError>> {
+        match request {
+            PrepareRequest::Witness(excluded, witness) => {
+                // A witness is sufficient to assemble a `WitnessedBatch`
+                // (a plurality of other replicas verified the batch)
+                let batch = WitnessedBatch::new(view, prepares, excluded, witness);
+                Ok(Phase::Commit(Commit::new(batch)))
+            }
+            PrepareRequest::Signatures(reduction_signature, individual_signatures) => {
+                // Use signatures to obtain a `SignedBatch`
+                let batch = SignedBatch::new(prepares, reduction_signature, individual_signatures);
+                Ok(Phase::ResolveUnknowns(ResolveUnknowns::new(batch)))
+            }
+            PrepareRequest::DelegatedSignatures(
+                reduction_signature,
+                individual_signatures,
+                delegations,
+            ) => {
+                // Some `Prepare`s are signed by delegate keys (see `ResolveUnknowns`)
+                let batch = SignedBatch::new(prepares, reduction_signature, individual_signatures)
+                    .with_delegations(delegations);
+
+                Ok(Phase::ResolveUnknowns(ResolveUnknowns::new(batch)))
+            }
+            _ => ServePrepareError::UnexpectedRequest.fail().spot(here!()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{account::Entry, prepare::ReductionStatement};
+
+    use talk::crypto::{primitives::hash, KeyChain};
+
+    fn prepares() -> Vector<Prepare> {
+        let prepares = (0..4)
+            .map(|id| Prepare::new(Entry { id, height: 1 }, hash::hash(&id).unwrap()))
+            .collect::<Vec<_>>();
+
+        Vector::new(prepares).unwrap()
+    }
+
+    #[test]
+    fn signatures() {
+        let prepares = prepares();
+
+        let reduction_signature = KeyChain::random()
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        let request = PrepareRequest::Signatures(reduction_signature, vec![None; 4]);
+        let phase = ReceiveBatch::classify(hash::hash(&0u32).unwrap(), prepares, request);
+
+        assert!(matches!(phase, Ok(Phase::ResolveUnknowns(_))));
+    }
+
+    #[test]
+    fn unexpected() {
+        let phase =
+            ReceiveBatch::classify(hash::hash(&0u32).unwrap(), prepares(), PrepareRequest::Ping);
+
+        assert!(phase.is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::{
+    prepare::SignedBatch,
+    processing::{
+        messages::PrepareResponse,
+        processor::prepare::{
+            errors::ServePrepareError,
+            phases::{Context, Phase, VerifySignatures},
+            steps,
+        },
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use rayon::prelude::*;
+
+use talk::crypto::KeyCard;
+
+use tokio::task;
+
+pub(in crate::processing::processor::prepare) struct ResolveUnknowns {
+    batch: SignedBatch,
+}
+
+impl ResolveUnknowns {
+    pub fn new(batch: SignedBatch) -> Self {
+        ResolveUnknowns { batch }
+    }
+
+    pub async fn run(self, context: &mut Context<'_>) -> Result<Phase, Top<ServePrepareError>> {
+        // A malformed batch is rejected as a whole: its defects are reported
+        // to the broker, which can evict the offending elements
+
+        if let Err(error) = ResolveUnknowns::check_order(&self.batch) {
+            let _ = context
+                .session
+                .send(&PrepareResponse::MalformedBatch(self.batch.defects()))
+                .await;
+
+            return Err(error);
+        }
+
+        // Retrieve the `KeyCard` relevant to each of the elements of `batch.prepares()`.
+        // If any `KeyCard` is missing from `database`, query `session` for the necessary
+        // `IdAssignment`s (stage in `context.updates` all newly discovered `IdAssignments`).
+        // `IdAssignment`s that `session` cannot provide are looked up from `context.peers`.
+
+        let keycards = steps::fetch_keycards(
+            context.database,
+            context.updates,
+            context.peers,
+            context.verifier,
+            context.session,
+            context.receive_timeout,
+            &self.batch,
+        )
+        .await?;
+
+        // Delegations are verified on the crypto pool, off the asynchronous runtime
+        // (see `VerifySignatures`)
+
+        let batch = self.batch;
+
+        let (batch, keycards) = task::spawn_blocking(move || {
+            let keycards = ResolveUnknowns::resolve_delegates(&batch, keycards);
+            (batch, keycards)
+        })
+        .await
+        .unwrap();
+
+        Ok(Phase::VerifySignatures(VerifySignatures::new(
+            batch, keycards,
+        )))
+    }
+
+    // Replaces with its delegate's `KeyCard` the (master) `KeyCard` of each `Prepare`
+    // validly signed by a delegate key. An invalid `Delegated` is ignored: the
+    // signatures of its `Prepare` are then checked against the master `KeyCard`.
+    fn resolve_delegates(batch: &SignedBatch, mut keycards: Vec<KeyCard>) -> Vec<KeyCard> {
+        let delegates = batch
+            .delegations()
+            .par_iter()
+            .filter_map(|(index, delegated)| {
+                delegated
+                    .validate(&keycards[*index], &batch.prepares()[*index])
+                    .ok()
+                    .map(|_| (*index, delegated.delegate().clone()))
+            })
+            .collect::<Vec<_>>();
+
+        for (index, delegate) in delegates {
+            keycards[index] = delegate;
+        }
+
+        keycards
+    }
+
+    // Verify that `batch.prepares()` is strictly increasing by `Id`
+    // (this ensures searchability and non-duplication of `Id`s), that
+    // each element of `batch` has exactly one individual signature slot,
+    // and that each of `batch.delegations()` refers to an element of `batch`
+    fn check_order(batch: &SignedBatch) -> Result<(), Top<ServePrepareError>> {
+        if batch.defects().is_empty() {
+            Ok(())
+        } else {
+            ServePrepareError::MalformedBatch.fail().spot(here!())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::{Entry, KeyDelegation},
+        prepare::{Delegated, Prepare, ReductionStatement},
+    };
+
+    use std::collections::BTreeMap;
+
+    use talk::crypto::{primitives::hash, KeyChain};
+
+    use zebra::vector::Vector;
+
+    fn batch(ids: &[u64]) -> SignedBatch {
+        signed(ids, ids.len())
+    }
+
+    // Builds a batch of `ids`, with `signatures` individual signature slots
+    fn signed(ids: &[u64], signatures: usize) -> SignedBatch {
+        let prepares = ids
+            .iter()
+            .map(|id| Prepare::new(Entry { id: *id, height: 1 }, hash::hash(id).unwrap()))
+            .collect::<Vec<_>>();
+
+        let prepares = Vector::new(prepares).unwrap();
+
+        let reduction_signature = KeyChain::random()
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        SignedBatch::new(prepares, reduction_signature, vec![None; signatures])
+    }
+
+    #[test]
+    fn order() {
+        assert!(ResolveUnknowns::check_order(&batch(&[1, 2, 5, 8])).is_ok());
+        assert!(ResolveUnknowns::check_order(&batch(&[1, 5, 2, 8])).is_err());
+
+        // Duplicate `Id`s are malformed as well
+        assert!(ResolveUnknowns::check_order(&batch(&[1, 2, 2, 8])).is_err());
+
+        // So are batches whose individual signatures do not match their elements
+        assert!(ResolveUnknowns::check_order(&signed(&[1, 2, 5, 8], 3)).is_err());
+        assert!(ResolveUnknowns::check_order(&signed(&[1, 2, 5, 8], 5)).is_err());
+    }
+
+    #[test]
+    fn delegates() {
+        let masters = (0..3).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let delegate = KeyChain::random();
+
+        let operation = hash::hash(&42u32).unwrap();
+
+        let delegated = (0..3)
+            .map(|id| {
+                let delegation = KeyDelegation::new(&masters[id as usize], id, &delegate, 100, 10);
+                Delegated::new(delegation, operation)
+            })
+            .collect::<Vec<_>>();
+
+        // `Prepare` 0 binds its delegation, `Prepare` 1 does not, and
+        // `Prepare` 2 is beyond its delegation's expiry
+        let prepares = vec![
+            Prepare::new(Entry { id: 0, height: 1 }, delegated[0].commitment()),
+            Prepare::new(Entry { id: 1, height: 1 }, operation),
+            Prepare::new(Entry { id: 2, height: 11 }, delegated[2].commitment()),
+        ];
+
+        let prepares = Vector::new(prepares).unwrap();
+
+        let reduction_signature = delegate
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        let delegations = delegated
+            .into_iter()
+            .enumerate()
+            .collect::<BTreeMap<_, _>>();
+
+        let batch = SignedBatch::new(prepares, reduction_signature, vec![None; 3])
+            .with_delegations(delegations);
+
+        assert!(ResolveUnknowns::check_order(&batch).is_ok());
+
+        let keycards = masters.iter().map(KeyChain::keycard).collect::<Vec<_>>();
+        let keycards = ResolveUnknowns::resolve_delegates(&batch, keycards);
+
+        assert_eq!(keycards[0], delegate.keycard());
+        assert_eq!(keycards[1], masters[1].keycard());
+        assert_eq!(keycards[2], masters[2].keycard());
+    }
+}
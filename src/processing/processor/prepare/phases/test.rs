@@ -0,0 +1,160 @@
+use crate::{
+    account::Entry,
+    crypto::Certificate,
+    database::{Database, WriteBatch},
+    discovery::{Client, Embedded},
+    prepare::{Prepare, WitnessStatement},
+    processing::{
+        processor::{
+            prepare::{phases::Context, BatchContext},
+            Peers,
+        },
+        Timeout,
+    },
+    signup::AssignmentVerifier,
+    telemetry::Registry,
+    view::{test::InstallGenerator, View},
+};
+
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use talk::{
+    crypto::{
+        primitives::hash::{self, Hash},
+        KeyChain,
+    },
+    net::{
+        test::{System, TestConnector},
+        Session, SessionConnector, SessionListener,
+    },
+    sync::voidable::Voidable,
+};
+
+use zebra::vector::Vector;
+
+// Replica-side resources of a batch session, allowing phases to be run
+// in isolation (the broker side of the session is returned by `Harness::new`)
+pub(in crate::processing::processor::prepare) struct Harness {
+    pub generator: InstallGenerator,
+    pub view: View,
+    pub database: Voidable<Database>,
+    pub receive_timeout: Timeout,
+    pub discovery: Client,
+    updates: WriteBatch,
+    peers: Peers,
+    verifier: AssignmentVerifier,
+    session: Session,
+    registry: Registry,
+    _embedded: Embedded,
+    _connectors: Vec<TestConnector>,
+    _listener: SessionListener,
+}
+
+impl Harness {
+    pub async fn new() -> (Harness, Session) {
+        let generator = InstallGenerator::new(4);
+        let view = generator.view(4);
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = embedded.client(Default::default());
+
+        let replica = generator.keychains[0].clone();
+
+        let System {
+            mut connectors,
+            mut listeners,
+            ..
+        } = System::setup_with_keychains(vec![KeyChain::random(), replica.clone()]).await;
+
+        let connector = SessionConnector::new(connectors.remove(0));
+        let mut listener = SessionListener::new(listeners.remove(1));
+
+        let (broker, (_, session)) = tokio::join!(
+            connector.connect(replica.keycard().identity()),
+            listener.accept()
+        );
+
+        let broker = broker.unwrap();
+
+        let peers = Peers::new(
+            view.clone(),
+            replica.keycard().identity(),
+            SessionConnector::new(connectors.remove(0)),
+            Duration::from_secs(1),
+        );
+
+        let verifier = AssignmentVerifier::new(
+            Arc::new(embedded.client(Default::default())),
+            Default::default(),
+        );
+
+        let harness = Harness {
+            generator,
+            view,
+            database: Voidable::new(Database::new()),
+            receive_timeout: Timeout::new(Duration::from_millis(100)),
+            discovery,
+            updates: WriteBatch::new(),
+            peers,
+            verifier,
+            session,
+            registry: Registry::new(),
+            _embedded: embedded,
+            _connectors: connectors,
+            _listener: listener,
+        };
+
+        (harness, broker)
+    }
+
+    pub fn context<'a>(&'a mut self, batch: &'a BatchContext) -> Context<'a> {
+        Context {
+            keychain: &self.generator.keychains[0],
+            discovery: &self.discovery,
+            batch,
+            database: &self.database,
+            updates: &mut self.updates,
+            peers: &self.peers,
+            verifier: &self.verifier,
+            session: &mut self.session,
+            receive_timeout: &self.receive_timeout,
+            registry: &self.registry,
+        }
+    }
+
+    // Exclusions are identified by `Id`, as in `steps::witness_shard`
+    pub fn witness(&self, root: Hash, exclusions: BTreeSet<u64>) -> Certificate {
+        let statement = WitnessStatement::partial(self.discovery.namespace(), root, exclusions);
+
+        let components = self.generator.keychains[..self.view.plurality()]
+            .iter()
+            .map(|keychain| {
+                (
+                    keychain.keycard().identity(),
+                    keychain.multisign(&statement).unwrap(),
+                )
+            });
+
+        Certificate::aggregate_plurality(&self.view, components)
+    }
+}
+
+// Each element of `entries` is an `Id` and the value its `Prepare` commits to
+pub(in crate::processing::processor::prepare) fn prepares(
+    entries: &[(u64, u64)],
+) -> Vector<Prepare> {
+    let prepares = entries
+        .iter()
+        .map(|(id, commitment)| {
+            Prepare::new(
+                Entry { id: *id, height: 1 },
+                hash::hash(commitment).unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Vector::new(prepares).unwrap()
+}
@@ -0,0 +1,213 @@
+use bit_vec::BitVec;
+
+use crate::{
+    prepare::{ReductionStatement, SignedBatch},
+    processing::processor::prepare::{
+        errors::ServePrepareError,
+        phases::{Context, Phase, Witness},
+        steps,
+    },
+};
+
+use doomstack::{here, ResultExt, Top};
+
+use rayon::prelude::*;
+
+use talk::crypto::KeyCard;
+
+use tokio::task;
+
+pub(in crate::processing::processor::prepare) struct VerifySignatures {
+    batch: SignedBatch,
+    keycards: Vec<KeyCard>,
+}
+
+impl VerifySignatures {
+    pub fn new(batch: SignedBatch, keycards: Vec<KeyCard>) -> Self {
+        VerifySignatures { batch, keycards }
+    }
+
+    pub async fn run(self, context: &mut Context<'_>) -> Result<Phase, Top<ServePrepareError>> {
+        let VerifySignatures { batch, keycards } = self;
+
+        // Individual signatures, plus the reduction signature
+        let verifications = batch
+            .individual_signatures()
+            .iter()
+            .filter(|signature| signature.is_some())
+            .count()
+            + 1;
+
+        context
+            .registry
+            .counter(
+                "carbon_signature_verifications_total",
+                "Signatures verified while witnessing batches",
+            )
+            .add(verifications as u64);
+
+        // Verification is CPU-bound: it runs on the crypto pool (see `runtime::Runtimes`),
+        // and is awaited from a blocking task, off the asynchronous runtime
+
+        let reduction_statement = context.batch.reduction_statement().clone();
+
+        let (batch, flags) = task::spawn_blocking(move || {
+            VerifySignatures::verify(&batch, keycards.as_slice(), &reduction_statement)
+                .map(|flags| (batch, flags))
+        })
+        .await
+        .unwrap()?;
+
+        // Prepares issued by closed accounts (beyond their closing height) are also flagged:
+        // no further `Prepare` is accepted from a closed account
+
+        let closed = {
+            let mut database = context
+                .database
+                .lock()
+                .pot(ServePrepareError::DatabaseVoid, here!())?;
+
+            database.settle();
+
+            batch
+                .prepares()
+                .iter()
+                .map(|prepare| {
+                    database
+                        .commit
+                        .closures
+                        .get(&prepare.id())
+                        .map_or(false, |height| prepare.height() > *height)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let flags = flags
+            .into_iter()
+            .zip(closed)
+            .map(|(flag, closed)| flag || closed)
+            .collect::<Vec<_>>();
+
+        // All elements of `batch` that were not flagged are valid: generate a witness
+        // shard for `batch` (partial, if any element of `batch` was flagged)
+
+        let flagged = if flags.iter().any(|flag| *flag) {
+            flags.into_iter().collect::<BitVec>()
+        } else {
+            BitVec::new()
+        };
+
+        let shard = steps::witness_shard(
+            context.keychain,
+            context.discovery.namespace(),
+            context.batch.root(),
+            batch.prepares(),
+            &flagged,
+        )?;
+
+        Ok(Phase::Witness(Witness::new(batch, flagged, shard)))
+    }
+
+    // Flags the `Prepare`s of `batch` whose individual signature is invalid,
+    // failing if `batch`'s reduction signature is invalid
+    fn verify(
+        batch: &SignedBatch,
+        keycards: &[KeyCard],
+        reduction_statement: &ReductionStatement,
+    ) -> Result<Vec<bool>, Top<ServePrepareError>> {
+        // Check all individual signatures in `batch` and `batch`'s reduction signature
+        // concurrently: reduction signers are known upfront, as they are exactly the
+        // issuers of the `Prepare`s that carry no individual signature
+
+        // `steps` zips together corresponding `KeyCard`s, `Prepare`s and individual
+        // `Signature`'s from `keycards` and `batch`
+        let steps = keycards.par_iter().zip(
+            batch
+                .prepares()
+                .par_iter()
+                .zip(batch.individual_signatures()),
+        );
+
+        let reduction_signers = keycards
+            .iter()
+            .zip(batch.individual_signatures())
+            .filter(|(_, individual_signature)| individual_signature.is_none())
+            .map(|(keycard, _)| keycard);
+
+        let (flags, reduction) = rayon::join(
+            || {
+                // Map and collect each element of `steps` into a flag (set if the
+                // element's individual signature is invalid). Rather than invalidating
+                // the whole batch, an invalid individual signature only flags the
+                // corresponding `Prepare` for exclusion
+                steps
+                    .map(|(keycard, (prepare, individual_signature))| {
+                        individual_signature.as_ref().map_or(false, |signature| {
+                            signature.verify(&keycard, prepare).is_err()
+                        })
+                    })
+                    .collect::<Vec<bool>>()
+            },
+            || {
+                // The reduction signature is a single aggregate over all
+                // `reduction_signers`, verified in one go
+                batch
+                    .reduction_signature()
+                    .verify(reduction_signers, reduction_statement)
+            },
+        );
+
+        reduction.pot(ServePrepareError::InvalidBatch, here!())?;
+
+        Ok(flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::processing::processor::prepare::phases::test;
+
+    use talk::crypto::{primitives::multi::Signature as MultiSignature, KeyChain};
+
+    #[test]
+    fn verify() {
+        let keychains = (0..4).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        let keycards = keychains.iter().map(KeyChain::keycard).collect::<Vec<_>>();
+
+        let prepares = test::prepares(&[(0, 0), (1, 1), (2, 2), (3, 3)]);
+        let statement = ReductionStatement::new(prepares.root());
+
+        // `Prepare`s 0 and 1 are individually signed (1 by the wrong
+        // issuer), `Prepare`s 2 and 3 are covered by the reduction signature
+        let individual_signatures = vec![
+            Some(keychains[0].sign(&prepares.items()[0]).unwrap()),
+            Some(keychains[0].sign(&prepares.items()[1]).unwrap()),
+            None,
+            None,
+        ];
+
+        let reduction_signature = MultiSignature::aggregate(
+            keychains[2..]
+                .iter()
+                .map(|keychain| keychain.multisign(&statement).unwrap()),
+        )
+        .unwrap();
+
+        let batch = SignedBatch::new(
+            prepares.clone(),
+            reduction_signature,
+            individual_signatures.clone(),
+        );
+
+        let flags = VerifySignatures::verify(&batch, keycards.as_slice(), &statement).unwrap();
+        assert_eq!(flags, vec![false, true, false, false]);
+
+        // A reduction signature missing one of its signers invalidates the whole batch
+        let reduction_signature = keychains[2].multisign(&statement).unwrap();
+        let batch = SignedBatch::new(prepares, reduction_signature, individual_signatures);
+
+        assert!(VerifySignatures::verify(&batch, keycards.as_slice(), &statement).is_err());
+    }
+}
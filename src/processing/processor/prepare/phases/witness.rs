@@ -0,0 +1,186 @@
+use bit_vec::BitVec;
+
+use crate::{
+    prepare::SignedBatch,
+    processing::processor::prepare::{
+        errors::ServePrepareError,
+        phases::{Commit, Context, Phase},
+        steps,
+    },
+};
+
+use doomstack::Top;
+
+use talk::crypto::primitives::multi::Signature as MultiSignature;
+
+pub(in crate::processing::processor::prepare) struct Witness {
+    batch: SignedBatch,
+    flagged: BitVec,
+    shard: MultiSignature,
+}
+
+impl Witness {
+    pub fn new(batch: SignedBatch, flagged: BitVec, shard: MultiSignature) -> Self {
+        Witness {
+            batch,
+            flagged,
+            shard,
+        }
+    }
+
+    pub async fn run(self, context: &mut Context<'_>) -> Result<Phase, Top<ServePrepareError>> {
+        let Witness {
+            batch,
+            flagged,
+            shard,
+        } = self;
+
+        // Trade `shard` for a full witness (which aggregates the witness shards
+        // of a plurality of replicas in `view`, excluding at least `flagged`)
+        let (excluded, witness) = steps::trade_witnesses(
+            context.keychain,
+            context.discovery.namespace(),
+            context.session,
+            context.receive_timeout,
+            context.batch.root(),
+            batch.prepares(),
+            flagged,
+            shard,
+        )
+        .await?;
+
+        // Use `witness` to promote `batch` to `WitnessedBatch`
+        let batch = batch.into_witnessed(context.batch.view(), excluded, witness);
+
+        Ok(Phase::Commit(Commit::new(batch)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        prepare::ReductionStatement,
+        processing::{
+            messages::{PrepareRequest, PrepareResponse},
+            processor::prepare::{
+                phases::test::{self, Harness},
+                BatchContext,
+            },
+        },
+    };
+
+    use std::collections::BTreeSet;
+
+    use talk::crypto::KeyChain;
+
+    // A `Witness` phase whose `Prepare` 1 (with `Id` 2) is flagged by the local replica
+    fn setup(harness: &Harness) -> (BatchContext, Witness) {
+        let prepares = test::prepares(&[(1, 1), (2, 2), (3, 3)]);
+        let context = BatchContext::new(&harness.view, &prepares);
+
+        let reduction_signature = KeyChain::random()
+            .multisign(&ReductionStatement::new(prepares.root()))
+            .unwrap();
+
+        let batch = SignedBatch::new(prepares, reduction_signature, vec![None; 3]);
+        let flagged = vec![false, true, false].into_iter().collect::<BitVec>();
+
+        let shard = steps::witness_shard(
+            &harness.generator.keychains[0],
+            harness.discovery.namespace(),
+            context.root(),
+            batch.prepares(),
+            &flagged,
+        )
+        .unwrap();
+
+        (context, Witness::new(batch, flagged, shard))
+    }
+
+    #[tokio::test]
+    async fn witness() {
+        let (mut harness, mut broker) = Harness::new().await;
+        let (batch, witness) = setup(&harness);
+
+        let certificate = harness.witness(batch.root(), BTreeSet::from([2]));
+
+        let broker = tokio::spawn(async move {
+            let flagged = match broker.receive::<PrepareResponse>().await.unwrap() {
+                PrepareResponse::PartialWitnessShard(flagged, _) => flagged,
+                _ => panic!("expected `PrepareResponse::PartialWitnessShard`"),
+            };
+
+            // Exclusions covering the flagged `Prepare` are witnessed
+            broker
+                .send(&PrepareRequest::Exclusions(flagged.clone()))
+                .await
+                .unwrap();
+
+            match broker.receive::<PrepareResponse>().await.unwrap() {
+                PrepareResponse::PartialWitnessShard(excluded, _) => assert_eq!(excluded, flagged),
+                _ => panic!("expected `PrepareResponse::PartialWitnessShard`"),
+            }
+
+            broker
+                .send(&PrepareRequest::Witness(flagged, certificate))
+                .await
+                .unwrap();
+
+            broker
+        });
+
+        let mut context = harness.context(&batch);
+        let phase = Phase::Witness(witness).step(&mut context).await;
+
+        assert!(matches!(phase, Ok(Phase::Commit(_))));
+
+        broker.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn insufficient_exclusions() {
+        let (mut harness, mut broker) = Harness::new().await;
+        let (batch, witness) = setup(&harness);
+
+        let broker = tokio::spawn(async move {
+            broker.receive::<PrepareResponse>().await.unwrap();
+
+            // Exclusions fail to cover the flagged `Prepare`
+            broker
+                .send(&PrepareRequest::Exclusions(BitVec::from_elem(3, false)))
+                .await
+                .unwrap();
+
+            broker
+        });
+
+        let mut context = harness.context(&batch);
+        let phase = Phase::Witness(witness).step(&mut context).await;
+
+        assert!(phase.is_err());
+
+        broker.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let (mut harness, mut broker) = Harness::new().await;
+        let (batch, witness) = setup(&harness);
+
+        // The broker receives the witness shard, but never responds
+        let broker = tokio::spawn(async move {
+            broker.receive::<PrepareResponse>().await.unwrap();
+            broker
+        });
+
+        let mut context = harness.context(&batch);
+        let phase = Phase::Witness(witness).step(&mut context).await;
+
+        assert!(phase.is_err());
+        assert_eq!(harness.receive_timeout.expired(), 1);
+
+        broker.await.unwrap();
+    }
+}
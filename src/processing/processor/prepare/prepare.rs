@@ -1,17 +1,26 @@
 use crate::{
+    benchmark::Metrics,
+    data::MemoryGauge,
     database::Database,
     discovery::Client,
+    handles::Lifecycle,
     processing::{
-        messages::PrepareRequest,
-        processor::prepare::{errors::ServePrepareError, handlers},
-        Processor,
+        messages::{PrepareRequest, PrepareResponse},
+        processor::{
+            prepare::{errors::ServePrepareError, handlers},
+            Peers,
+        },
+        processor_settings::Prepare,
+        Processor, Timeout,
     },
+    signup::AssignmentVerifier,
+    telemetry::{Registry, Span},
     view::View,
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use talk::{
     crypto::KeyChain,
@@ -19,30 +28,90 @@ use talk::{
     sync::{fuse::Fuse, voidable::Voidable},
 };
 
+use tokio::{sync::Semaphore, task};
+
 impl Processor {
     pub(in crate::processing) async fn run_prepare<L>(
         keychain: KeyChain,
         discovery: Arc<Client>,
         view: View,
         database: Arc<Voidable<Database>>,
+        peers: Arc<Peers>,
+        verifier: AssignmentVerifier,
         listener: L,
+        settings: Prepare,
+        memory_gauge: MemoryGauge,
+        metrics: Metrics,
+        registry: Registry,
+        receive_timeout: Timeout,
+        lifecycle: Lifecycle,
     ) where
         L: Listener,
     {
         let mut listener = SessionListener::new(listener);
+
+        let latency = registry.histogram(
+            "carbon_prepare_session_seconds",
+            "Latency of successful prepare sessions",
+        );
+
+        let receiving = settings
+            .max_receiving
+            .map(|max_receiving| Arc::new(Semaphore::new(max_receiving)));
+
         let fuse = Fuse::new();
 
         loop {
             let (_, session) = listener.accept().await;
 
+            // While draining, new sessions are dropped unserved
+            let engagement = match lifecycle.engage() {
+                Some(engagement) => engagement,
+                None => continue,
+            };
+
             let keychain = keychain.clone();
             let discovery = discovery.clone();
             let view = view.clone();
             let database = database.clone();
+            let peers = peers.clone();
+            let verifier = verifier.clone();
+            let settings = settings.clone();
+            let receiving = receiving.clone();
+            let memory_gauge = memory_gauge.clone();
+            let metrics = metrics.clone();
+            let registry = registry.clone();
+            let latency = latency.clone();
+            let receive_timeout = receive_timeout.clone();
 
             fuse.spawn(async move {
-                let _ =
-                    Processor::serve_prepare(keychain, discovery, view, database, session).await;
+                let _engagement = engagement;
+
+                let start = Instant::now();
+
+                let result = Processor::serve_prepare(
+                    keychain,
+                    discovery,
+                    view,
+                    database,
+                    peers,
+                    verifier,
+                    session,
+                    settings,
+                    receiving,
+                    memory_gauge,
+                    registry,
+                    receive_timeout,
+                )
+                .await;
+
+                match result {
+                    Ok(()) => {
+                        metrics.success("prepare", start.elapsed());
+                        latency.observe(start.elapsed());
+                    }
+                    Err(_) => metrics.failure("prepare", "error"),
+                }
             });
         }
     }
@@ -52,29 +121,128 @@ impl Processor {
         discovery: Arc<Client>,
         view: View,
         database: Arc<Voidable<Database>>,
+        peers: Arc<Peers>,
+        verifier: AssignmentVerifier,
         mut session: Session,
+        settings: Prepare,
+        receiving: Option<Arc<Semaphore>>,
+        memory_gauge: MemoryGauge,
+        registry: Registry,
+        receive_timeout: Timeout,
     ) -> Result<(), Top<ServePrepareError>> {
-        let request = session
-            .receive::<PrepareRequest>()
+        // A slot is secured before receiving: a batch holds its slot until
+        // it is spilled (or found small enough to be kept in memory)
+        let slot = match receiving {
+            // `receiving` is never closed
+            Some(receiving) => Some(receiving.acquire_owned().await.unwrap()),
+            None => None,
+        };
+
+        let request = receive_timeout
+            .run(session.receive::<PrepareRequest>())
             .await
+            .pot(ServePrepareError::ReceiveTimeout, here!())?
             .pot(ServePrepareError::ConnectionError, here!())?;
 
+        // A traced session is preceded by the `TraceContext` of the brokerage:
+        // the rest of the session is traced by a child span
+
+        let (_span, request) = match request {
+            PrepareRequest::Trace(trace) => {
+                let span = Span::child("processor.prepare", trace);
+
+                let request = receive_timeout
+                    .run(session.receive::<PrepareRequest>())
+                    .await
+                    .pot(ServePrepareError::ReceiveTimeout, here!())?
+                    .pot(ServePrepareError::ConnectionError, here!())?;
+
+                (Some(span), request)
+            }
+            request => (None, request),
+        };
+
+        // While shedding, new batches (compressed or not) are rejected before
+        // being processed any further: other requests are served as usual
+
+        if matches!(
+            request,
+            PrepareRequest::Batch(_) | PrepareRequest::CompressedBatch(_)
+        ) && memory_gauge.is_shedding()
+        {
+            session
+                .send(&PrepareResponse::Busy)
+                .await
+                .pot(ServePrepareError::ConnectionError, here!())?;
+
+            return ServePrepareError::Busy.fail().spot(here!());
+        }
+
+        // Compressed batches are handled as their decompressed counterparts
+
+        let request = match request {
+            PrepareRequest::CompressedBatch(prepares) => {
+                if !settings.accept_compression {
+                    return ServePrepareError::CompressionRefused.fail().spot(here!());
+                }
+
+                // Decompression is CPU-bound: it runs off the asynchronous runtime
+                let max_decompressed_size = settings.max_decompressed_size;
+
+                let prepares =
+                    task::spawn_blocking(move || prepares.decompress(max_decompressed_size))
+                        .await
+                        .pot(ServePrepareError::DecompressionFailed, here!())?
+                        .pot(ServePrepareError::DecompressionFailed, here!())?;
+
+                PrepareRequest::Batch(prepares)
+            }
+            request => request,
+        };
+
+        let slot = if matches!(request, PrepareRequest::Batch(_)) {
+            slot
+        } else {
+            None
+        };
+
         match request {
             PrepareRequest::Ping => handlers::ping(session).await,
+            PrepareRequest::ClockPing => {
+                handlers::clock_ping(session, settings.accept_compression).await
+            }
             PrepareRequest::Batch(prepares) => {
                 handlers::batch(
                     &keychain,
                     discovery.as_ref(),
                     &view,
                     database.as_ref(),
+                    peers.as_ref(),
+                    &verifier,
                     session,
+                    &receive_timeout,
                     prepares,
+                    slot,
+                    &settings,
+                    &registry,
                 )
                 .await
             }
             PrepareRequest::Commit(commit) => {
                 handlers::commit(discovery.as_ref(), database.as_ref(), session, commit).await
             }
+            PrepareRequest::Reconcile(root, equivocations) => {
+                handlers::reconcile(
+                    &keychain,
+                    discovery.as_ref(),
+                    &view,
+                    database.as_ref(),
+                    session,
+                    root,
+                    equivocations,
+                )
+                .await
+            }
             _ => ServePrepareError::UnexpectedRequest.fail().spot(here!()),
         }
     }
@@ -0,0 +1,183 @@
+use crate::{
+    prepare::Prepare,
+    processing::{
+        processor::prepare::errors::ServePrepareError,
+        processor_settings::Prepare as PrepareSettings,
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use talk::crypto::primitives::hash::Hash;
+
+use tokio::task;
+
+use zebra::vector::Vector;
+
+// A batch body that is either held in memory or, if larger than
+// `PrepareSettings::spill_threshold`, temporarily stored on disk
+pub(in crate::processing::processor::prepare) enum Spill {
+    Resident(Vector<Prepare>),
+    Spilled(SpillFile),
+}
+
+pub(in crate::processing::processor::prepare) struct SpillFile {
+    path: PathBuf,
+    root: Hash,
+}
+
+impl Spill {
+    pub async fn new(
+        settings: &PrepareSettings,
+        prepares: Vector<Prepare>,
+    ) -> Result<Self, Top<ServePrepareError>> {
+        match settings.spill_threshold {
+            Some(threshold) if prepares.items().len() > threshold => {
+                let path = settings
+                    .spill_directory
+                    .join(format!("batch-{:016x}.spill", rand::random::<u64>()));
+
+                let root = prepares.root();
+
+                let spill = SpillFile { path, root };
+                let path = spill.path.clone();
+
+                // Serialization is CPU- and IO-bound: run it on a blocking thread
+                task::spawn_blocking(move || -> Result<(), Top<ServePrepareError>> {
+                    let file = File::create(&path).pot(ServePrepareError::SpillFailed, here!())?;
+
+                    bincode::serialize_into(BufWriter::new(file), prepares.items())
+                        .pot(ServePrepareError::SpillFailed, here!())
+                })
+                .await
+                .pot(ServePrepareError::SpillFailed, here!())??;
+
+                Ok(Spill::Spilled(spill))
+            }
+            _ => Ok(Spill::Resident(prepares)),
+        }
+    }
+
+    pub async fn load(self) -> Result<Vector<Prepare>, Top<ServePrepareError>> {
+        match self {
+            Spill::Resident(prepares) => Ok(prepares),
+            Spill::Spilled(spill) => {
+                let path = spill.path.clone();
+
+                let prepares = task::spawn_blocking(
+                    move || -> Result<Vector<Prepare>, Top<ServePrepareError>> {
+                        let file =
+                            File::open(&path).pot(ServePrepareError::SpillFailed, here!())?;
+
+                        let prepares: Vec<Prepare> =
+                            bincode::deserialize_from(BufReader::new(file))
+                                .pot(ServePrepareError::SpillCorrupted, here!())?;
+
+                        // Rebuilding `prepares` recomputes its Merkle root
+                        Vector::new(prepares).pot(ServePrepareError::SpillCorrupted, here!())
+                    },
+                )
+                .await
+                .pot(ServePrepareError::SpillFailed, here!())??;
+
+                if prepares.root() != spill.root {
+                    return ServePrepareError::SpillCorrupted.fail().spot(here!());
+                }
+
+                Ok(prepares)
+            }
+        }
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        // Best-effort cleanup: a stale spill file is harmless
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::account::Entry;
+
+    use std::env;
+
+    use talk::crypto::primitives::hash;
+
+    fn prepares(count: u64) -> Vector<Prepare> {
+        let prepares = (0..count)
+            .map(|id| Prepare::new(Entry { id, height: 1 }, hash::hash(&id).unwrap()))
+            .collect::<Vec<_>>();
+
+        Vector::new(prepares).unwrap()
+    }
+
+    fn settings(spill_threshold: usize) -> PrepareSettings {
+        PrepareSettings {
+            spill_threshold: Some(spill_threshold),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn resident() {
+        let prepares = prepares(4);
+        let root = prepares.root();
+
+        let spill = Spill::new(&settings(4), prepares).await.unwrap();
+        assert!(matches!(spill, Spill::Resident(_)));
+
+        assert_eq!(spill.load().await.unwrap().root(), root);
+    }
+
+    #[tokio::test]
+    async fn spilled() {
+        let prepares = prepares(8);
+        let root = prepares.root();
+
+        let spill = Spill::new(&settings(4), prepares).await.unwrap();
+
+        let path = match &spill {
+            Spill::Spilled(spill) => spill.path.clone(),
+            Spill::Resident(_) => panic!("Batch was not spilled"),
+        };
+
+        assert!(path.exists());
+        assert_eq!(spill.load().await.unwrap().root(), root);
+
+        // Spill files are removed once loaded
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn corrupted() {
+        let spill = Spill::new(&settings(4), prepares(8)).await.unwrap();
+
+        if let Spill::Spilled(spill) = &spill {
+            let forged = bincode::serialize(prepares(6).items()).unwrap();
+            fs::write(&spill.path, forged).unwrap();
+        }
+
+        assert!(spill.load().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unwritable() {
+        let settings = PrepareSettings {
+            spill_threshold: Some(4),
+            spill_directory: env::temp_dir().join("carbon-missing-spill-directory"),
+            ..Default::default()
+        };
+
+        assert!(Spill::new(&settings, prepares(8)).await.is_err());
+    }
+}
@@ -1,25 +1,28 @@
 use buckets::Split;
 
 use crate::{
-    database::Database,
-    discovery::Client,
+    account::Id,
+    database::{Database, WriteBatch},
     prepare::{Prepare, SignedBatch},
     processing::{
         messages::{PrepareRequest, PrepareResponse},
-        processor::prepare::errors::ServePrepareError,
+        processor::{prepare::errors::ServePrepareError, Peers},
+        Timeout,
     },
+    signup::{AssignmentVerifier, IdAssignment},
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use rayon::prelude::*;
-
 use talk::{crypto::KeyCard, net::Session, sync::voidable::Voidable};
 
 pub(in crate::processing::processor::prepare) async fn fetch_keycards(
-    discovery: &Client,
     database: &Voidable<Database>,
+    updates: &mut WriteBatch,
+    peers: &Peers,
+    verifier: &AssignmentVerifier,
     session: &mut Session,
+    receive_timeout: &Timeout,
     batch: &SignedBatch,
 ) -> Result<Vec<KeyCard>, Top<ServePrepareError>> {
     // For each element of `batch.prepares()`, retrieve from `database`,
@@ -77,13 +80,17 @@ pub(in crate::processing::processor::prepare) async fn fetch_keycards(
 
     // Receive requested `IdAssignments`
 
-    let request = session
-        .receive::<PrepareRequest>()
+    let request = receive_timeout
+        .run(session.receive::<PrepareRequest>())
         .await
+        .pot(ServePrepareError::ReceiveTimeout, here!())?
         .pot(ServePrepareError::ConnectionError, here!())?;
 
     let assignments = match request {
         PrepareRequest::Assignments(id_assignments) => id_assignments,
+        PrepareRequest::PartialAssignments(id_assignments) => {
+            complete_assignments(peers, verifier, &unknown_ids, id_assignments).await?
+        }
         _ => {
             return ServePrepareError::UnexpectedRequest.fail().spot(here!());
         }
@@ -99,47 +106,35 @@ pub(in crate::processing::processor::prepare) async fn fetch_keycards(
             .spot(here!());
     }
 
-    // Check that each element `assignments` is valid and relevant to the
-    // corresponding element of `unknown_ids`
-    unknown_ids
-        .par_iter()
-        .zip(assignments.par_iter())
-        .map(|(id, assignment)| {
-            if assignment.id() != *id {
-                ServePrepareError::MismatchedIdAssignment
-                    .fail()
-                    .spot(here!())
-            } else {
-                assignment
-                    .validate(discovery)
-                    .pot(ServePrepareError::InvalidIdAssignment, here!())
-            }
-        })
-        .collect::<Result<_, _>>()?;
+    // Check that each element `assignments` is relevant to the
+    // corresponding element of `unknown_ids`, then that it is valid
+    // (`IdAssignment`s looked up from `peers` are already cached by `verifier`)
+    if unknown_ids
+        .iter()
+        .zip(assignments.iter())
+        .any(|(id, assignment)| assignment.id() != *id)
+    {
+        return ServePrepareError::MismatchedIdAssignment
+            .fail()
+            .spot(here!());
+    }
 
-    // Store `assignments` in `database`, retain only the `KeyCard`s
-    // necessary to fill the gaps in `database_keycards`
+    verifier
+        .verify_all(assignments.as_slice())
+        .await
+        .pot(ServePrepareError::InvalidIdAssignment, here!())?;
 
-    let assignments = assignments.into_iter().collect::<Split<_>>();
+    // Retain only the `KeyCard`s necessary to fill the gaps in `database_keycards`,
+    // then stage `assignments` in `updates` (to be stored in `database` along with
+    // the batch, should it be committed)
 
-    let mut missing_keycards = {
-        let mut database = database
-            .lock()
-            .pot(ServePrepareError::DatabaseVoid, here!())?;
+    let mut missing_keycards = assignments
+        .iter()
+        .map(|assignment| assignment.keycard().clone())
+        .collect::<Vec<_>>()
+        .into_iter(); // Elements will be extracted in order from `missing_keycards`
 
-        // The following collects all `KeyCard`s in a `Vec` to avoid
-        // lingering references to `database` (which needs to be
-        // unlocked in a timely fashion)
-        database
-            .assignments
-            .apply(assignments, |assignments, assignment| {
-                let keycard = assignment.keycard().clone();
-                assignments.insert(assignment.id(), assignment);
-                keycard
-            })
-    }
-    .join()
-    .into_iter(); // Elements will be extracted in order from `missing_keycards`
+    updates.insert_assignments(assignments);
 
     // Use `missing_keycards` to fill the gaps in `database_keycards`
 
@@ -158,3 +153,46 @@ pub(in crate::processing::processor::prepare) async fn fetch_keycards(
 
     Ok(keycards)
 }
+
+// Fills the gaps in `partial` (the `IdAssignment`s the broker could provide) by looking up
+// the missing `IdAssignment`s from `peers`. The batch is rejected only if some `IdAssignment`
+// is still missing after all peers have been queried.
+async fn complete_assignments(
+    peers: &Peers,
+    verifier: &AssignmentVerifier,
+    unknown_ids: &[Id],
+    partial: Vec<Option<IdAssignment>>,
+) -> Result<Vec<IdAssignment>, Top<ServePrepareError>> {
+    if partial.len() != unknown_ids.len() {
+        return ServePrepareError::MalformedIdAssignments
+            .fail()
+            .spot(here!());
+    }
+
+    let missing_ids = unknown_ids
+        .iter()
+        .zip(partial.iter())
+        .filter(|(_, assignment)| assignment.is_none())
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+
+    let mut fetched = if missing_ids.is_empty() {
+        Vec::new()
+    } else {
+        peers.fetch_assignments(verifier, &missing_ids).await
+    }
+    .into_iter();
+
+    // Because `fetched.len() == missing_ids.len()`, `fetched.next()` is `Some` for each gap
+    partial
+        .into_iter()
+        .map(|assignment| match assignment {
+            Some(assignment) => Ok(assignment),
+            None => fetched
+                .next()
+                .unwrap()
+                .ok_or(ServePrepareError::UnresolvedIdAssignment.into_top())
+                .spot(here!()),
+        })
+        .collect()
+}
@@ -1,11 +1,9 @@
 mod apply_batch;
 mod fetch_keycards;
 mod trade_witnesses;
-mod validate_signed;
-mod witnessed_batch;
+mod witness_shard;
 
 pub(in crate::processing::processor::prepare) use apply_batch::apply_batch;
 pub(in crate::processing::processor::prepare) use fetch_keycards::fetch_keycards;
 pub(in crate::processing::processor::prepare) use trade_witnesses::trade_witnesses;
-pub(in crate::processing::processor::prepare) use validate_signed::validate_signed;
-pub(in crate::processing::processor::prepare) use witnessed_batch::witnessed_batch;
+pub(in crate::processing::processor::prepare) use witness_shard::witness_shard;
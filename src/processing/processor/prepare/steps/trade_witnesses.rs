@@ -1,43 +1,91 @@
+use bit_vec::BitVec;
+
 use crate::{
     crypto::Certificate,
+    prepare::Prepare,
     processing::{
         messages::{PrepareRequest, PrepareResponse},
-        processor::prepare::errors::ServePrepareError,
+        processor::prepare::{errors::ServePrepareError, steps},
+        Namespace, Timeout,
     },
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use talk::{crypto::primitives::multi::Signature as MultiSignature, net::Session};
+use std::iter;
+
+use talk::{
+    crypto::{
+        primitives::{hash::Hash, multi::Signature as MultiSignature},
+        KeyChain,
+    },
+    net::Session,
+};
 
 pub(in crate::processing::processor::prepare) async fn trade_witnesses(
+    keychain: &KeyChain,
+    namespace: &Namespace,
     session: &mut Session,
+    receive_timeout: &Timeout,
+    root: Hash,
+    prepares: &[Prepare],
+    flagged: BitVec,
     shard: MultiSignature,
-) -> Result<Certificate, Top<ServePrepareError>> {
-    // Send witness `shard`
+) -> Result<(BitVec, Certificate), Top<ServePrepareError>> {
+    // Send witness `shard` (along with the elements it excludes, if any)
+
+    let response = if flagged.is_empty() {
+        PrepareResponse::WitnessShard(shard)
+    } else {
+        PrepareResponse::PartialWitnessShard(flagged.clone(), shard)
+    };
 
     session
-        .send(&PrepareResponse::WitnessShard(shard))
+        .send(&response)
         .await
         .pot(ServePrepareError::ConnectionError, here!())?;
 
-    // Receive witness certificate (which aggregates a plurality of witness
-    // shards produced by other members of the replica's view)
+    loop {
+        // Receive either:
+        // - A set of exclusions, for which a (partial) witness shard must be produced
+        // - A witness certificate (which aggregates a plurality of witness shards
+        //   produced by other members of the replica's view)
 
-    let request = session
-        .receive::<PrepareRequest>()
-        .await
-        .pot(ServePrepareError::ConnectionError, here!())?;
+        let request = receive_timeout
+            .run(session.receive::<PrepareRequest>())
+            .await
+            .pot(ServePrepareError::ReceiveTimeout, here!())?
+            .pot(ServePrepareError::ConnectionError, here!())?;
 
-    let witness = match request {
-        PrepareRequest::Witness(witness) => witness,
-        _ => {
-            return ServePrepareError::UnexpectedRequest.fail().spot(here!());
-        }
-    };
+        match request {
+            PrepareRequest::Exclusions(excluded) => {
+                // A partial witness shard can be produced only if `excluded`
+                // covers all the elements flagged during validation
+                if flagged
+                    .iter()
+                    .zip(excluded.iter().chain(iter::repeat(false)))
+                    .any(|(flagged, excluded)| flagged && !excluded)
+                {
+                    return ServePrepareError::InsufficientExclusions
+                        .fail()
+                        .spot(here!());
+                }
 
-    // The verification of `witness` is delegated to the caller `witnessed_batch(..)`,
-    // which validates the `WitnessedBatch` it builds from `witness`
+                let shard = steps::witness_shard(keychain, namespace, root, prepares, &excluded)?;
 
-    Ok(witness)
+                session
+                    .send(&PrepareResponse::PartialWitnessShard(excluded, shard))
+                    .await
+                    .pot(ServePrepareError::ConnectionError, here!())?;
+            }
+
+            // The verification of `witness` is delegated to the caller `witnessed_batch(..)`,
+            // which validates the `WitnessedBatch` it builds from `witness`
+            PrepareRequest::Witness(excluded, witness) => return Ok((excluded, witness)),
+
+            _ => {
+                return ServePrepareError::UnexpectedRequest.fail().spot(here!());
+            }
+        }
+    }
 }
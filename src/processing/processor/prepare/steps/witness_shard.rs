@@ -0,0 +1,39 @@
+use bit_vec::BitVec;
+
+use crate::{
+    prepare::{Prepare, WitnessStatement},
+    processing::{processor::prepare::errors::ServePrepareError, Namespace},
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use talk::crypto::{
+    primitives::{hash::Hash, multi::Signature as MultiSignature},
+    KeyChain,
+};
+
+pub(in crate::processing::processor::prepare) fn witness_shard(
+    keychain: &KeyChain,
+    namespace: &Namespace,
+    root: Hash,
+    prepares: &[Prepare],
+    excluded: &BitVec,
+) -> Result<MultiSignature, Top<ServePrepareError>> {
+    // An empty `excluded` represents a full witness, otherwise `excluded`
+    // must flag each element of `prepares`
+    if !excluded.is_empty() && excluded.len() != prepares.len() {
+        return ServePrepareError::MalformedExclusions.fail().spot(here!());
+    }
+
+    // Witness statements identify excluded `Prepare`s by `Id`
+    let exclusions = prepares
+        .iter()
+        .zip(excluded.iter())
+        .filter_map(|(prepare, excluded)| if excluded { Some(prepare.id()) } else { None })
+        .collect();
+
+    let statement = WitnessStatement::partial(namespace, root, exclusions);
+    let shard = keychain.multisign(&statement).unwrap();
+
+    Ok(shard)
+}
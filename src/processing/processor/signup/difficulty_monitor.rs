@@ -0,0 +1,140 @@
+use crate::processing::processor_settings::Signup;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// A `DifficultyMonitor` tracks the rate at which a replica receives `IdRequest`s,
+// and raises the work difficulty it requires of them (see `Signup::difficulty_steps`)
+// when the rate grows, making Sybil signups more expensive under pressure. The
+// current difficulty is advertised to brokers (`SignupRequest::WorkDifficulty`),
+// so that clients can compute `Work` of the appropriate difficulty.
+// All clones of a `DifficultyMonitor` share the same state.
+#[derive(Clone)]
+pub(in crate::processing::processor::signup) struct DifficultyMonitor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    base: u64,
+    steps: Vec<(usize, u64)>,
+    window: Duration,
+    // Number of `IdRequest`s received at each `Instant` within `window`
+    arrivals: Mutex<VecDeque<(Instant, usize)>>,
+}
+
+impl DifficultyMonitor {
+    pub fn new(settings: &Signup) -> Self {
+        let steps = settings
+            .difficulty_steps
+            .iter()
+            .map(|step| (step.threshold, step.raise))
+            .collect();
+
+        DifficultyMonitor {
+            inner: Arc::new(Inner {
+                base: settings.signup_settings.work_difficulty,
+                steps,
+                window: settings.rate_window,
+                arrivals: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    pub fn record(&self, requests: usize) {
+        let now = Instant::now();
+
+        let mut arrivals = self.inner.arrivals.lock().unwrap();
+        arrivals.push_back((now, requests));
+        self.expire(&mut arrivals, now);
+    }
+
+    // Work difficulty currently required of `IdRequest`s
+    pub fn difficulty(&self) -> u64 {
+        let mut arrivals = self.inner.arrivals.lock().unwrap();
+        self.expire(&mut arrivals, Instant::now());
+
+        let rate = arrivals.iter().map(|(_, requests)| requests).sum::<usize>();
+
+        let raise = self
+            .inner
+            .steps
+            .iter()
+            .filter(|(threshold, _)| rate > *threshold)
+            .map(|(_, raise)| *raise)
+            .max()
+            .unwrap_or(0);
+
+        self.inner.base + raise
+    }
+
+    fn expire(&self, arrivals: &mut VecDeque<(Instant, usize)>, now: Instant) {
+        while let Some((arrival, _)) = arrivals.front() {
+            if now.duration_since(*arrival) <= self.inner.window {
+                break;
+            }
+
+            arrivals.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::processing::processor_settings::DifficultyStep;
+
+    use std::thread;
+
+    #[test]
+    fn steps() {
+        let settings = Signup {
+            difficulty_steps: vec![
+                DifficultyStep {
+                    threshold: 10,
+                    raise: 2,
+                },
+                DifficultyStep {
+                    threshold: 100,
+                    raise: 4,
+                },
+            ],
+            rate_window: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        let base = settings.signup_settings.work_difficulty;
+        let monitor = DifficultyMonitor::new(&settings);
+
+        assert_eq!(monitor.difficulty(), base);
+
+        monitor.record(10);
+        assert_eq!(monitor.difficulty(), base);
+
+        monitor.record(1);
+        assert_eq!(monitor.difficulty(), base + 2);
+
+        monitor.record(90);
+        assert_eq!(monitor.difficulty(), base + 4);
+
+        // Difficulty falls back once the burst leaves the window
+
+        thread::sleep(Duration::from_millis(150));
+        assert_eq!(monitor.difficulty(), base);
+    }
+
+    #[test]
+    fn disabled() {
+        let settings = Signup::default();
+        let monitor = DifficultyMonitor::new(&settings);
+
+        monitor.record(usize::MAX / 2);
+        assert_eq!(
+            monitor.difficulty(),
+            settings.signup_settings.work_difficulty
+        );
+    }
+}
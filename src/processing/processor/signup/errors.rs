@@ -4,12 +4,18 @@ use doomstack::Doom;
 pub(in crate::processing::processor::signup) enum ServeSignupError {
     #[doom(description("Connection error"))]
     ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
     #[doom(description("Database void"))]
     DatabaseVoid,
+    #[doom(description("Failed to persist database update"))]
+    StorageFailed,
     #[doom(description("Invalid request"))]
     InvalidRequest,
     #[doom(description("Foreign view"))]
     ForeignView,
     #[doom(description("Foreign allocator"))]
     ForeignAllocator,
+    #[doom(description("Biased allocator"))]
+    BiasedAllocator,
 }
@@ -1,20 +1,15 @@
-use buckets::Split;
-
 use crate::{
     database::Database,
-    discovery::Client,
     processing::{messages::SignupResponse, processor::signup::errors::ServeSignupError},
-    signup::IdAssignment,
+    signup::{AssignmentVerifier, IdAssignment},
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
 
-use rayon::prelude::*;
-
 use talk::sync::voidable::Voidable;
 
-pub(in crate::processing::processor::signup) fn id_assignments(
-    discovery: &Client,
+pub(in crate::processing::processor::signup) async fn id_assignments(
+    verifier: &AssignmentVerifier,
     database: &Voidable<Database>,
     assignments: Vec<IdAssignment>,
 ) -> Result<SignupResponse, Top<ServeSignupError>> {
@@ -29,30 +24,18 @@ pub(in crate::processing::processor::signup) fn id_assignments(
 
     // Validate `assignments` (in parallel)
 
-    assignments
-        .par_iter()
-        .map(|assignment| {
-            assignment
-                .validate(discovery)
-                .pot(ServeSignupError::InvalidRequest, here!())
-        })
-        .collect::<Result<(), Top<ServeSignupError>>>()?;
+    verifier
+        .verify_all(assignments.as_slice())
+        .await
+        .pot(ServeSignupError::InvalidRequest, here!())?;
 
     // Process `assignments`
 
-    let assignments = assignments.into_iter().collect::<Split<_>>();
-
-    {
-        let mut database = database
-            .lock()
-            .pot(ServeSignupError::DatabaseVoid, here!())?;
-
-        database
-            .assignments
-            .apply(assignments, |assignments, assignment| {
-                assignments.insert(assignment.id(), assignment)
-            });
-    }
+    database
+        .lock()
+        .pot(ServeSignupError::DatabaseVoid, here!())?
+        .insert_assignments(assignments)
+        .pot(ServeSignupError::StorageFailed, here!())?;
 
     Ok(SignupResponse::AcknowledgeIdAssignments)
 }
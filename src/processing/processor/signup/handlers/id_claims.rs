@@ -3,7 +3,7 @@ use crate::{
     database::Database,
     processing::{
         messages::SignupResponse, processor::signup::errors::ServeSignupError,
-        processor_settings::Signup,
+        processor_settings::Signup, Namespace,
     },
     signup::{IdAssignment, IdClaim},
     view::View,
@@ -19,6 +19,7 @@ use zebra::database::CollectionTransaction;
 
 pub(in crate::processing::processor::signup) fn id_claims(
     keychain: &KeyChain,
+    namespace: &Namespace,
     view: &View,
     database: &Voidable<Database>,
     claims: Vec<IdClaim>,
@@ -43,9 +44,17 @@ pub(in crate::processing::processor::signup) fn id_claims(
             }
 
             claim
-                .validate(settings.signup_settings.work_difficulty)
+                .validate(namespace, settings.signup_settings.work_difficulty)
                 .pot(ServeSignupError::InvalidRequest, here!())?;
 
+            // Under beacon allocation, only the beacon allocator can allocate an id
+            if settings.signup_settings.beacon_allocation {
+                claim
+                    .request()
+                    .validate_allocator()
+                    .pot(ServeSignupError::BiasedAllocator, here!())?;
+            }
+
             Ok(())
         })
         .collect::<Result<(), Top<ServeSignupError>>>()?;
@@ -73,7 +82,7 @@ pub(in crate::processing::processor::signup) fn id_claims(
                     // `claim.id()` will be inserted twice in `database.signup.claimed`
                     // (which is harmless) and the `IdAssignment` will be repeated
                     let _ = transaction.insert(claim.id());
-                    Ok(IdAssignment::certify(&keychain, &claim))
+                    Ok(IdAssignment::certify(&keychain, namespace, &claim))
                 } else {
                     // `claim.id()` was previously claimed by another client: return
                     // the relevant `IdClaim` as proof of conflict
@@ -0,0 +1,34 @@
+use buckets::Split;
+
+use crate::{
+    account::Id,
+    database::Database,
+    processing::{messages::SignupResponse, processor::signup::errors::ServeSignupError},
+};
+
+use doomstack::{here, ResultExt, Top};
+
+use talk::sync::voidable::Voidable;
+
+pub(in crate::processing::processor::signup) fn id_lookups(
+    database: &Voidable<Database>,
+    ids: Vec<Id>,
+) -> Result<SignupResponse, Top<ServeSignupError>> {
+    // Stored `IdAssignment`s were validated upon insertion (the
+    // requestor is expected to validate them nonetheless)
+
+    let ids = Split::with_key(ids, |id| *id);
+
+    let assignments = {
+        let mut database = database
+            .lock()
+            .pot(ServeSignupError::DatabaseVoid, here!())?;
+
+        database
+            .assignments
+            .apply(ids, |assignments, id| assignments.get(&id).cloned())
+    }
+    .join();
+
+    Ok(SignupResponse::IdLookups(assignments))
+}
@@ -2,8 +2,10 @@ use crate::{
     crypto::Identify,
     database::Database,
     processing::{
-        messages::SignupResponse, processor::signup::errors::ServeSignupError,
+        messages::SignupResponse,
+        processor::signup::{errors::ServeSignupError, DifficultyMonitor},
         processor_settings::Signup,
+        Namespace,
     },
     signup::{IdAllocation, IdRequest},
     view::View,
@@ -24,8 +26,10 @@ use talk::{
 
 pub(in crate::processing::processor::signup) fn id_requests(
     keychain: &KeyChain,
+    namespace: &Namespace,
     view: &View,
     database: &Voidable<Database>,
+    monitor: &DifficultyMonitor,
     requests: Vec<IdRequest>,
     settings: &Signup,
 ) -> Result<SignupResponse, Top<ServeSignupError>> {
@@ -38,11 +42,16 @@ pub(in crate::processing::processor::signup) fn id_requests(
         return ServeSignupError::InvalidRequest.fail().spot(here!());
     }
 
+    // All `requests` count towards the signup rate, including those rejected below
+
+    let difficulty = monitor.difficulty();
+    monitor.record(requests.len());
+
     // Validate `requests` (in parallel)
 
     let identity = keychain.keycard().identity();
 
-    requests
+    let sufficient = requests
         .par_iter()
         .map(|request| {
             if request.view() != view.identifier() {
@@ -57,9 +66,21 @@ pub(in crate::processing::processor::signup) fn id_requests(
                 .validate(settings.signup_settings.work_difficulty)
                 .pot(ServeSignupError::InvalidRequest, here!())?;
 
-            Ok(())
+            if settings.signup_settings.beacon_allocation {
+                request
+                    .validate_allocator()
+                    .pot(ServeSignupError::BiasedAllocator, here!())?;
+            }
+
+            Ok(request.meets_difficulty(difficulty))
         })
-        .collect::<Result<(), Top<ServeSignupError>>>()?;
+        .collect::<Result<Vec<bool>, Top<ServeSignupError>>>()?;
+
+    // `requests` are valid under the base difficulty, but not all meet the raised
+    // difficulty: advertise `difficulty` rather than failing (see `SignupResponse`)
+    if sufficient.into_iter().any(|sufficient| !sufficient) {
+        return Ok(SignupResponse::WorkDifficulty(difficulty));
+    }
 
     // Process `requests` into `allocations`
 
@@ -73,7 +94,15 @@ pub(in crate::processing::processor::signup) fn id_requests(
         requests
             .into_iter()
             .map(|request| {
-                allocate_id(&keychain, identity, &view, &mut database, request, settings)
+                allocate_id(
+                    &keychain,
+                    namespace,
+                    identity,
+                    &view,
+                    &mut database,
+                    request,
+                    settings,
+                )
             })
             .collect::<Vec<_>>()
     };
@@ -83,6 +112,7 @@ pub(in crate::processing::processor::signup) fn id_requests(
 
 fn allocate_id(
     keychain: &KeyChain,
+    namespace: &Namespace,
     identity: Identity,
     view: &View,
     database: &mut Database,
@@ -95,7 +125,7 @@ fn allocate_id(
         .get(&request.client().identity())
     {
         // `request` was previously served, repeat previous `IdAllocation`
-        return IdAllocation::new(&keychain, &request, *id);
+        return IdAllocation::new(&keychain, namespace, &request, *id);
     }
 
     let full_range = view.allocation_range(identity);
@@ -151,5 +181,5 @@ fn allocate_id(
         .allocations
         .insert(request.client().identity(), id);
 
-    IdAllocation::new(&keychain, &request, id)
+    IdAllocation::new(&keychain, namespace, &request, id)
 }
@@ -1,7 +1,9 @@
 mod id_assignments;
 mod id_claims;
+mod id_lookups;
 mod id_requests;
 
 pub(in crate::processing::processor::signup) use id_assignments::id_assignments;
 pub(in crate::processing::processor::signup) use id_claims::id_claims;
+pub(in crate::processing::processor::signup) use id_lookups::id_lookups;
 pub(in crate::processing::processor::signup) use id_requests::id_requests;
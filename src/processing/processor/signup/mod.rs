@@ -1,3 +1,6 @@
+mod difficulty_monitor;
 mod errors;
 mod handlers;
 mod signup;
+
+use difficulty_monitor::DifficultyMonitor;
@@ -1,12 +1,13 @@
 use crate::{
     database::Database,
-    discovery::Client,
+    handles::Lifecycle,
     processing::{
-        messages::SignupRequest,
-        processor::signup::{errors::ServeSignupError, handlers},
+        messages::{SignupRequest, SignupResponse},
+        processor::signup::{errors::ServeSignupError, handlers, DifficultyMonitor},
         processor_settings::Signup,
-        Processor,
+        Namespace, Processor, Timeout,
     },
+    signup::AssignmentVerifier,
     view::View,
 };
 
@@ -23,59 +24,104 @@ use talk::{
 impl Processor {
     pub(in crate::processing) async fn run_signup<L>(
         keychain: KeyChain,
-        discovery: Arc<Client>,
+        namespace: Namespace,
         view: View,
         database: Arc<Voidable<Database>>,
+        verifier: AssignmentVerifier,
         listener: L,
         settings: Signup,
+        receive_timeout: Timeout,
+        lifecycle: Lifecycle,
     ) where
         L: Listener,
     {
         let mut listener = SessionListener::new(listener);
+        let monitor = DifficultyMonitor::new(&settings);
         let fuse = Fuse::new();
 
         loop {
             let (_, session) = listener.accept().await;
 
+            // While draining, new sessions are dropped unserved
+            let engagement = match lifecycle.engage() {
+                Some(engagement) => engagement,
+                None => continue,
+            };
+
             let keychain = keychain.clone();
-            let discovery = discovery.clone();
+            let namespace = namespace.clone();
             let view = view.clone();
             let database = database.clone();
+            let verifier = verifier.clone();
+            let monitor = monitor.clone();
             let settings = settings.clone();
+            let receive_timeout = receive_timeout.clone();
 
             fuse.spawn(async move {
-                let _ =
-                    Processor::serve_signup(keychain, discovery, view, database, session, settings)
-                        .await;
+                let _engagement = engagement;
+
+                let _ = Processor::serve_signup(
+                    keychain,
+                    namespace,
+                    view,
+                    database,
+                    verifier,
+                    monitor,
+                    session,
+                    settings,
+                    receive_timeout,
+                )
+                .await;
             });
         }
     }
 
     async fn serve_signup(
         keychain: KeyChain,
-        discovery: Arc<Client>,
+        namespace: Namespace,
         view: View,
         database: Arc<Voidable<Database>>,
+        verifier: AssignmentVerifier,
+        monitor: DifficultyMonitor,
         mut session: Session,
         settings: Signup,
+        receive_timeout: Timeout,
     ) -> Result<(), Top<ServeSignupError>> {
-        let request = session
-            .receive::<SignupRequest>()
+        let request = receive_timeout
+            .run(session.receive::<SignupRequest>())
             .await
+            .pot(ServeSignupError::ReceiveTimeout, here!())?
             .pot(ServeSignupError::ConnectionError, here!())?;
 
         let response = {
             match request {
-                SignupRequest::IdRequests(requests) => {
-                    handlers::id_requests(&keychain, &view, database.as_ref(), requests, &settings)?
-                }
+                SignupRequest::IdRequests(requests) => handlers::id_requests(
+                    &keychain,
+                    &namespace,
+                    &view,
+                    database.as_ref(),
+                    &monitor,
+                    requests,
+                    &settings,
+                )?,
+
+                SignupRequest::IdClaims(claims) => handlers::id_claims(
+                    &keychain,
+                    &namespace,
+                    &view,
+                    database.as_ref(),
+                    claims,
+                    &settings,
+                )?,
 
-                SignupRequest::IdClaims(claims) => {
-                    handlers::id_claims(&keychain, &view, database.as_ref(), claims, &settings)?
+                SignupRequest::IdAssignments(assignments) => {
+                    handlers::id_assignments(&verifier, database.as_ref(), assignments).await?
                 }
 
-                SignupRequest::IdAssignments(assignments) => {
-                    handlers::id_assignments(discovery.as_ref(), database.as_ref(), assignments)?
+                SignupRequest::IdLookups(ids) => handlers::id_lookups(database.as_ref(), ids)?,
+
+                SignupRequest::WorkDifficulty => {
+                    SignupResponse::WorkDifficulty(monitor.difficulty())
                 }
             }
         };
@@ -123,7 +169,9 @@ mod tests {
         assert_eq!(allocations.len(), 1);
 
         let allocation = allocations.remove(0);
-        allocation.validate(&request).unwrap();
+        allocation
+            .validate(&Namespace::default(), &request)
+            .unwrap();
         assert!(allocation.id() <= u32::MAX as u64);
     }
 
@@ -135,6 +183,7 @@ mod tests {
             discovery_client,
             brokers,
             processors,
+            ..
         } = System::setup(4, 1).await;
 
         let allocator = processors[0].0.keycard().identity();
@@ -153,4 +202,40 @@ mod tests {
         let assignment = assignments.remove(0).unwrap();
         assignment.validate(&discovery_client).unwrap();
     }
+
+    #[tokio::test]
+    async fn id_lookups() {
+        let System {
+            view,
+            brokers,
+            processors,
+            ..
+        } = System::setup(4, 1).await;
+
+        let allocator = processors[0].0.keycard().identity();
+        let holder = processors[1].0.keycard().identity();
+
+        let client = KeyChain::random();
+        let request = IdRequest::new(
+            &client,
+            &view,
+            allocator,
+            SignupSettings::default().work_difficulty,
+        );
+
+        let assignment = brokers[0].signup(vec![request]).await.remove(0).unwrap();
+
+        let id = assignment.id();
+
+        brokers[0].id_assignments(holder, vec![assignment]).await;
+
+        // Only `holder` was provided with `assignment`
+
+        let found = brokers[0].id_lookups(holder, vec![id, id + 1]).await;
+        assert_eq!(found[0].as_ref().unwrap().id(), id);
+        assert!(found[1].is_none());
+
+        let found = brokers[0].id_lookups(allocator, vec![id]).await;
+        assert!(found[0].is_none());
+    }
 }
@@ -0,0 +1,13 @@
+use doomstack::Doom;
+
+#[derive(Doom)]
+pub(in crate::processing::processor::stream) enum ServeStreamError {
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Database void"))]
+    DatabaseVoid,
+    #[doom(description("Failed to serialize `Image`"))]
+    ImageFailed,
+    #[doom(description("Delegate lagging"))]
+    DelegateLagging,
+}
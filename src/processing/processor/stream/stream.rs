@@ -0,0 +1,86 @@
+use crate::{
+    database::Database,
+    processing::{
+        messages::StreamUpdate, processor::stream::errors::ServeStreamError,
+        processor_settings::Delegation, Processor,
+    },
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::sync::Arc;
+
+use talk::{
+    net::{Listener, Session, SessionListener},
+    sync::{fuse::Fuse, voidable::Voidable},
+};
+
+use tokio::{sync::broadcast::error::RecvError, time};
+
+impl Processor {
+    pub(in crate::processing) async fn run_stream<L>(
+        database: Arc<Voidable<Database>>,
+        listener: L,
+        settings: Delegation,
+    ) where
+        L: Listener,
+    {
+        let mut listener = SessionListener::new(listener);
+        let fuse = Fuse::new();
+
+        loop {
+            let (delegate, session) = listener.accept().await;
+
+            // Sessions are authenticated by the `KeyChain` of their initiator:
+            // only the delegates designated in `settings` are streamed to
+            if !settings.delegates.contains(&delegate) {
+                continue;
+            }
+
+            let database = database.clone();
+            let settings = settings.clone();
+
+            fuse.spawn(async move {
+                let _ = Processor::serve_stream(database, session, settings).await;
+            });
+        }
+    }
+
+    // Streams `database`'s records to a delegate, starting from an `Image` of `database`
+    // (see `Database::subscribe`). The stream ends (and the delegate resynchronizes)
+    // as soon as the delegate falls more than `settings.stream_capacity` records behind.
+    async fn serve_stream(
+        database: Arc<Voidable<Database>>,
+        mut session: Session,
+        settings: Delegation,
+    ) -> Result<(), Top<ServeStreamError>> {
+        let (image, mut records) = database
+            .lock()
+            .pot(ServeStreamError::DatabaseVoid, here!())?
+            .subscribe(settings.stream_capacity)
+            .pot(ServeStreamError::ImageFailed, here!())?;
+
+        session
+            .send(&StreamUpdate::Record(image))
+            .await
+            .pot(ServeStreamError::ConnectionError, here!())?;
+
+        loop {
+            let update = match time::timeout(settings.keep_alive, records.recv()).await {
+                Ok(Ok(record)) => StreamUpdate::Record(record.as_ref().clone()),
+                Ok(Err(RecvError::Lagged(_))) => {
+                    return ServeStreamError::DelegateLagging.fail().spot(here!());
+                }
+                Ok(Err(RecvError::Closed)) => {
+                    return ServeStreamError::DatabaseVoid.fail().spot(here!());
+                }
+                Err(_) => StreamUpdate::KeepAlive,
+            };
+
+            session
+                .send(&update)
+                .await
+                .pot(ServeStreamError::ConnectionError, here!())?;
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use doomstack::Doom;
+
+#[derive(Doom)]
+pub(in crate::processing::processor::sync) enum ServeSyncError {
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Receive timeout"))]
+    ReceiveTimeout,
+    #[doom(description("Database void"))]
+    DatabaseVoid,
+}
@@ -0,0 +1,2 @@
+mod errors;
+mod sync;
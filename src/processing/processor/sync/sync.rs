@@ -0,0 +1,208 @@
+use crate::{
+    crypto::Identify,
+    database::{Database, Snapshot},
+    discovery::Client,
+    processing::{
+        messages::{SyncRequest, SyncResponse},
+        processor::sync::errors::ServeSyncError,
+        processor_settings::StateSync,
+        sync::SnapshotStatement,
+        Processor, Timeout,
+    },
+    view::View,
+};
+
+use doomstack::{here, ResultExt, Top};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use talk::{
+    crypto::{primitives::hash::Hash, KeyChain},
+    net::{Listener, Session, SessionListener},
+    sync::{fuse::Fuse, voidable::Voidable},
+};
+
+use tokio::sync::watch::{self, Receiver, Sender};
+
+type Snapshots = Arc<Mutex<HashMap<Hash, Arc<Snapshot>>>>;
+
+// Height of the last install snapshotted, along with its `Snapshot`
+type Latest = Option<(usize, Arc<Snapshot>)>;
+
+impl Processor {
+    pub(in crate::processing) async fn run_sync<L>(
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        view: View,
+        database: Arc<Voidable<Database>>,
+        listener: L,
+        settings: StateSync,
+        receive_timeout: Timeout,
+    ) where
+        L: Listener,
+    {
+        let mut listener = SessionListener::new(listener);
+        let fuse = Fuse::new();
+
+        // Replicas snapshot `database` at agreed points, namely upon the install of `view`
+        // and of every later view. Chunks are served from the `Snapshot`s retained (by
+        // root), so that fetches in progress are not disrupted by later installs.
+
+        let snapshots = Arc::new(Mutex::new(HashMap::new()));
+        let (latest_inlet, latest_outlet) = watch::channel(None);
+
+        {
+            let discovery = discovery.clone();
+            let view = view.clone();
+            let database = database.clone();
+            let snapshots = snapshots.clone();
+            let settings = settings.clone();
+
+            fuse.spawn(async move {
+                Processor::take_snapshots(
+                    discovery,
+                    view,
+                    database,
+                    snapshots,
+                    latest_inlet,
+                    settings,
+                )
+                .await;
+            });
+        }
+
+        loop {
+            let (_, session) = listener.accept().await;
+
+            let keychain = keychain.clone();
+            let discovery = discovery.clone();
+            let view = view.clone();
+            let snapshots = snapshots.clone();
+            let latest = latest_outlet.clone();
+            let receive_timeout = receive_timeout.clone();
+
+            fuse.spawn(async move {
+                let _ = Processor::serve_sync(
+                    keychain,
+                    discovery,
+                    view,
+                    snapshots,
+                    latest,
+                    session,
+                    receive_timeout,
+                )
+                .await;
+            });
+        }
+    }
+
+    async fn take_snapshots(
+        discovery: Arc<Client>,
+        view: View,
+        database: Arc<Voidable<Database>>,
+        snapshots: Snapshots,
+        latest: Sender<Latest>,
+        settings: StateSync,
+    ) {
+        let mut height = view.height();
+        let mut retained = VecDeque::new();
+
+        loop {
+            let snapshot = match database.lock() {
+                Ok(mut database) => Arc::new(database.snapshot(settings.chunk_size)),
+                Err(_) => return,
+            };
+
+            let root = snapshot.root();
+
+            {
+                let mut snapshots = snapshots.lock().unwrap();
+
+                snapshots.insert(root, snapshot.clone());
+
+                // Consecutive installs might leave `database` unchanged
+                retained.retain(|retained| *retained != root);
+                retained.push_back(root);
+
+                while retained.len() > settings.snapshot_retention.max(1) {
+                    let evicted = retained.pop_front().unwrap();
+                    snapshots.remove(&evicted);
+                }
+            }
+
+            let _ = latest.send(Some((height, snapshot)));
+
+            height = discovery.beyond(height).await.destination().height();
+        }
+    }
+
+    async fn serve_sync(
+        keychain: KeyChain,
+        discovery: Arc<Client>,
+        view: View,
+        snapshots: Snapshots,
+        mut latest: Receiver<Latest>,
+        mut session: Session,
+        receive_timeout: Timeout,
+    ) -> Result<(), Top<ServeSyncError>> {
+        let request = receive_timeout
+            .run(session.receive::<SyncRequest>())
+            .await
+            .pot(ServeSyncError::ReceiveTimeout, here!())?
+            .pot(ServeSyncError::ConnectionError, here!())?;
+
+        let response = match request {
+            SyncRequest::Snapshot => {
+                // The last install known to `discovery` might be yet to be snapshotted
+                let top = discovery.top();
+
+                let snapshot = loop {
+                    let current = latest.borrow().clone();
+
+                    match current {
+                        Some((height, snapshot)) if height >= top => break snapshot,
+                        _ => latest
+                            .changed()
+                            .await
+                            .pot(ServeSyncError::DatabaseVoid, here!())?,
+                    }
+                };
+
+                let root = snapshot.root();
+                let chunks = snapshot.len() as u64;
+
+                let statement =
+                    SnapshotStatement::new(discovery.namespace(), view.identifier(), root, chunks);
+                let signature = keychain.multisign(&statement).unwrap();
+
+                SyncResponse::Snapshot {
+                    root,
+                    chunks,
+                    signature,
+                }
+            }
+
+            SyncRequest::Chunk { root, index } => {
+                // `snapshot` is cloned to release `snapshots` before building the response
+                let snapshot = snapshots.lock().unwrap().get(&root).cloned();
+
+                snapshot
+                    .and_then(|snapshot| snapshot.chunk(index as usize))
+                    .map(|(chunk, proof)| SyncResponse::Chunk(chunk, proof))
+                    .unwrap_or(SyncResponse::UnknownSnapshot)
+            }
+        };
+
+        session
+            .send(&response)
+            .await
+            .pot(ServeSyncError::ConnectionError, here!())?;
+
+        session.end();
+
+        Ok(())
+    }
+}
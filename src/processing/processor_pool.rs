@@ -0,0 +1,198 @@
+use crate::{
+    database::Database,
+    handles::{Failure, Health},
+    processing::Processor,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::collections::HashMap;
+
+use tokio::sync::broadcast::Receiver;
+
+// A `ProcessorPool` hosts the `Processor`s of several independent view families
+// in the same process. Families share nothing but the process: each `Processor`
+// runs with its own keychain, discovery client, `View`, `Database` and networking,
+// and is started and stopped independently of all other families.
+pub(crate) struct ProcessorPool {
+    families: HashMap<String, Processor>,
+}
+
+#[derive(Doom)]
+pub(crate) enum ProcessorPoolError {
+    #[doom(description("Family `{}` is already running", family))]
+    FamilyRunning { family: String },
+    #[doom(description("Family `{}` is not running", family))]
+    FamilyUnknown { family: String },
+}
+
+impl ProcessorPool {
+    pub fn new() -> Self {
+        ProcessorPool {
+            families: HashMap::new(),
+        }
+    }
+
+    pub fn start<S>(
+        &mut self,
+        family: S,
+        processor: Processor,
+    ) -> Result<(), Top<ProcessorPoolError>>
+    where
+        S: Into<String>,
+    {
+        let family = family.into();
+
+        if self.families.contains_key(&family) {
+            // Dropping `processor` would report its tasks as failed
+            processor.shutdown();
+            return ProcessorPoolError::FamilyRunning { family }
+                .fail()
+                .spot(here!());
+        }
+
+        self.families.insert(family, processor);
+        Ok(())
+    }
+
+    // Stops `family`, returning its `Database` (e.g., to restart `family` in a later `View`)
+    pub fn stop(&mut self, family: &str) -> Result<Database, Top<ProcessorPoolError>> {
+        let processor = self
+            .families
+            .remove(family)
+            .ok_or(
+                ProcessorPoolError::FamilyUnknown {
+                    family: family.to_string(),
+                }
+                .into_top(),
+            )
+            .spot(here!())?;
+
+        Ok(processor.shutdown())
+    }
+
+    pub fn processor(&self, family: &str) -> Option<&Processor> {
+        self.families.get(family)
+    }
+
+    pub fn families(&self) -> impl Iterator<Item = &str> {
+        self.families.keys().map(String::as_str)
+    }
+
+    pub fn is_ready(&self, family: &str) -> bool {
+        self.families
+            .get(family)
+            .map_or(false, |processor| processor.lifecycle().is_ready())
+    }
+
+    pub fn health(&self, family: &str) -> Option<Health> {
+        self.families
+            .get(family)
+            .map(|processor| processor.lifecycle().health())
+    }
+
+    // Notifies the failures of `family`'s serving tasks
+    pub fn failures(&self, family: &str) -> Option<Receiver<Failure>> {
+        self.families
+            .get(family)
+            .map(|processor| processor.lifecycle().failures())
+    }
+
+    pub fn shutdown(self) -> HashMap<String, Database> {
+        self.families
+            .into_iter()
+            .map(|(family, processor)| (family, processor.shutdown()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        discovery::{ClientSettings, Embedded, Mode},
+        processing::{processor_settings::Persistence, ProcessorSettings},
+        view::test::InstallGenerator,
+    };
+
+    use std::{env, fs, sync::Arc, time::Duration};
+
+    use talk::net::test::System as NetSystem;
+
+    #[tokio::test]
+    async fn families() {
+        let mut pool = ProcessorPool::new();
+        let mut servers = Vec::new();
+
+        let directory =
+            env::temp_dir().join(format!("processor-pool-{:016x}", rand::random::<u64>()));
+
+        for family in ["alpha", "beta"] {
+            let generator = InstallGenerator::new(4);
+            let view = generator.view(4);
+
+            let server = Embedded::new(view.clone(), Default::default())
+                .await
+                .unwrap();
+
+            let discovery = Arc::new(server.client(ClientSettings {
+                mode: Mode::Full,
+                ..Default::default()
+            }));
+
+            let NetSystem {
+                mut connectors,
+                mut listeners,
+                ..
+            } = NetSystem::setup_with_keychains(generator.keychains.iter().cloned()).await;
+
+            // Each family persists its `Database` in its own directory
+            let settings = ProcessorSettings {
+                persistence: Persistence {
+                    directory: Some(directory.join(family)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            let processor = Processor::open(
+                generator.keychains[0].clone(),
+                discovery,
+                view,
+                connectors.remove(0),
+                listeners.remove(0),
+                settings,
+            )
+            .unwrap();
+
+            pool.start(family, processor).unwrap();
+            servers.push(server);
+        }
+
+        for family in ["alpha", "beta"] {
+            pool.processor(family)
+                .unwrap()
+                .lifecycle()
+                .await_ready_within(Duration::from_secs(10))
+                .await
+                .unwrap();
+        }
+
+        // Stopping a family does not affect the others
+
+        let mut failures = pool.failures("beta").unwrap();
+
+        let database = pool.stop("alpha").unwrap();
+        assert!(database.log_size().is_some());
+        assert!(pool.stop("alpha").is_err());
+
+        assert!(!pool.is_ready("alpha"));
+        assert!(pool.is_ready("beta"));
+        assert!(failures.try_recv().is_err());
+
+        assert_eq!(pool.families().collect::<Vec<_>>(), vec!["beta"]);
+
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -1,17 +1,50 @@
-use crate::signup::SignupSettings;
+use crate::{
+    account::AccountSettings,
+    benchmark::MetricsSettings,
+    data::MemorySettings,
+    processing::FailureInjection,
+    signup::{AssignmentVerifierSettings, SignupSettings},
+    telemetry::ExporterSettings,
+};
 
-use talk::link::context::ListenDispatcherSettings;
+use std::{env, path::PathBuf, time::Duration};
+
+use talk::{crypto::Identity, link::context::ListenDispatcherSettings};
 
 #[derive(Debug, Clone, Default)]
 pub(crate) struct ProcessorSettings {
     pub listen_dispatcher_settings: ListenDispatcherSettings,
     pub signup: Signup,
+    pub prepare: Prepare,
+    pub timeouts: Timeouts,
+    pub failure_injection: FailureInjection,
+    // Watermarks on the (estimated) footprint of the `Database`: while shedding,
+    // new batches are rejected with `PrepareResponse::Busy` / `CommitResponse::Busy`
+    pub memory: MemorySettings,
+    // Shared by the prepare and signup paths (see `AssignmentVerifier`)
+    pub assignment_verifier: AssignmentVerifierSettings,
+    pub state_sync: StateSync,
+    // Read-only delegates streaming the `Database` (see `Delegate`)
+    pub delegation: Delegation,
+    // Where (if anywhere) the `Database` is persisted (see `Processor::open`)
+    pub persistence: Persistence,
+    pub compaction: Compaction,
+    // Serve sessions are accounted for in the `Processor`'s `Metrics`
+    pub metrics: MetricsSettings,
+    // Serves the `Processor`'s `Registry` to Prometheus
+    pub exporter: ExporterSettings,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct Signup {
     pub signup_settings: SignupSettings,
     pub priority_attempts: usize,
+    // Steps by which the work difficulty required of `IdRequest`s is raised
+    // above `signup_settings.work_difficulty` as the signup rate grows (empty
+    // disables adaptive difficulty, see `DifficultyMonitor`)
+    pub difficulty_steps: Vec<DifficultyStep>,
+    // Window over which the signup rate is measured
+    pub rate_window: Duration,
 }
 
 impl Default for Signup {
@@ -19,6 +52,143 @@ impl Default for Signup {
         Signup {
             signup_settings: SignupSettings::default(),
             priority_attempts: 32,
+            difficulty_steps: Vec::new(),
+            rate_window: Duration::from_secs(10),
+        }
+    }
+}
+
+// Once more than `threshold` `IdRequest`s are received within `rate_window`,
+// `IdRequest`s must carry `Work` of difficulty at least `work_difficulty + raise`
+#[derive(Debug, Clone)]
+pub(crate) struct DifficultyStep {
+    pub threshold: usize,
+    pub raise: u64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Prepare {
+    // Batches with more than `spill_threshold` elements are spilled
+    // to `spill_directory` while awaiting signatures or witnesses
+    // (`None` disables spilling altogether)
+    pub spill_threshold: Option<usize>,
+    pub spill_directory: PathBuf,
+    // At most `max_receiving` sessions receive (and, if oversized, spill)
+    // their batch at once: together with `spill_threshold`, this bounds
+    // the number of oversized batches held in memory at any time
+    // (`None` lifts the bound)
+    pub max_receiving: Option<usize>,
+    // Whether compressed batches are accepted (advertised to brokers
+    // upon `PrepareRequest::ClockPing`, see `PrepareResponse::Capabilities`)
+    pub accept_compression: bool,
+    // Compressed batches are rejected if they decompress
+    // to more than `max_decompressed_size` bytes
+    pub max_decompressed_size: usize,
+}
+
+impl Default for Prepare {
+    fn default() -> Self {
+        Prepare {
+            spill_threshold: None,
+            spill_directory: env::temp_dir(),
+            max_receiving: None,
+            accept_compression: true,
+            max_decompressed_size: 256 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct StateSync {
+    // Number of `Id`s covered by each chunk of a `Snapshot`
+    pub chunk_size: usize,
+    // Number of `Snapshot`s (one per install) retained to serve chunks: older
+    // `Snapshot`s are dropped, along with the fetches still in progress
+    pub snapshot_retention: usize,
+}
+
+impl Default for StateSync {
+    fn default() -> Self {
+        StateSync {
+            chunk_size: 4096,
+            snapshot_retention: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Delegation {
+    // Delegates allowed to stream the `Database`: sessions
+    // from any other `Identity` are dropped
+    pub delegates: Vec<Identity>,
+    // Records buffered for each delegate: a delegate that falls further behind
+    // is disconnected (and resynchronizes from a new `Image`, see `Delegate`)
+    pub stream_capacity: usize,
+    // Interval between two `StreamUpdate::KeepAlive`s on an idle stream
+    pub keep_alive: Duration,
+}
+
+impl Default for Delegation {
+    fn default() -> Self {
+        Delegation {
+            delegates: Vec::new(),
+            stream_capacity: 4096,
+            keep_alive: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Persistence {
+    // Directory holding the `Database`'s log, replayed upon opening
+    // (`None` keeps the `Database` in memory only)
+    pub directory: Option<PathBuf>,
+    // Must be the same across restarts (see `Database::with_storage`)
+    pub account_settings: AccountSettings,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Compaction {
+    // Interval between two compactions of the `Database`
+    pub interval: Duration,
+    // Prepare batches no `State` references any longer are dropped once applied
+    // for `batch_retention` (they are needed to serve reconciliations)
+    pub batch_retention: Duration,
+    // Commit batches are dropped (along with the history entries they
+    // serve, see `Commit::history`) once applied for `commit_retention`
+    pub commit_retention: Duration,
+    // The log of a persistent `Database` is compacted (see `Database::compact`)
+    // once it grows beyond `log_threshold` bytes
+    pub log_threshold: u64,
+}
+
+impl Default for Compaction {
+    fn default() -> Self {
+        Compaction {
+            interval: Duration::from_secs(10),
+            batch_retention: Duration::from_secs(300),
+            commit_retention: Duration::from_secs(3600),
+            log_threshold: 256 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Timeouts {
+    // Bounds every `receive` on serve paths
+    pub receive: Duration,
+    // Bounds each lookup of missing `IdAssignment`s from a peer replica
+    pub lookup: Duration,
+    // Interval between two probes of the `Database`'s availability
+    pub database_probe: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            receive: Duration::from_secs(60),
+            lookup: Duration::from_secs(5),
+            database_probe: Duration::from_secs(1),
         }
     }
 }
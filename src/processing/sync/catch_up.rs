@@ -0,0 +1,296 @@
+use crate::{
+    crypto::Certificate,
+    database::{Database, SnapshotChunk},
+    processing::{
+        messages::{SyncRequest, SyncResponse},
+        sync::{CatchUpSettings, SnapshotCertificate, SnapshotStatement},
+    },
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use futures::stream::{self, FuturesUnordered, StreamExt, TryStreamExt};
+
+use std::collections::HashMap;
+
+use talk::{
+    crypto::{
+        primitives::{hash::Hash, multi::Signature as MultiSignature},
+        Identity,
+    },
+    net::SessionConnector,
+};
+
+use tokio::time;
+
+// A `CatchUp` obtains the state of `view`'s replicas, to be installed by a replica
+// joining a later `View`. First, every member of `view` is asked for the `Snapshot` it
+// took upon the last install it knows of: once a plurality of members signed the same
+// root, the resulting `SnapshotCertificate` is verified, and each chunk is fetched from
+// the signers and verified against the certified root. `connector` must be registered
+// on the "sync" context of `view`'s replicas (see `Namespace::context`). A `CatchUp`
+// is expected to run once a `View` beyond `view` is installed (otherwise, replicas
+// might not have snapshotted the same install).
+pub(crate) struct CatchUp {
+    view: View,
+    connector: SessionConnector,
+    settings: CatchUpSettings,
+}
+
+#[derive(Doom)]
+pub(crate) enum CatchUpError {
+    #[doom(description("No plurality of replicas agreed on a `Snapshot`"))]
+    SnapshotUnagreed,
+    #[doom(description("`SnapshotCertificate` invalid"))]
+    CertificateInvalid,
+    #[doom(description("Chunk {} unavailable", index))]
+    ChunkUnavailable { index: u64 },
+    #[doom(description("Chunks out of order"))]
+    ChunksMisordered,
+}
+
+#[derive(Doom)]
+enum RequestError {
+    #[doom(description("Connection failed"))]
+    ConnectionFailed,
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Request timed out"))]
+    Timeout,
+    #[doom(description("Unexpected response"))]
+    UnexpectedResponse,
+    #[doom(description("Inclusion `Proof` invalid"))]
+    InclusionInvalid,
+}
+
+impl CatchUp {
+    pub fn new(view: View, connector: SessionConnector, settings: CatchUpSettings) -> Self {
+        CatchUp {
+            view,
+            connector,
+            settings,
+        }
+    }
+
+    pub async fn run(&self) -> Result<Database, Top<CatchUpError>> {
+        let (certificate, signers) = self.certify().await?;
+
+        certificate
+            .verify(&self.view, &self.settings.namespace)
+            .pot(CatchUpError::CertificateInvalid, here!())?;
+
+        // Chunks are fetched concurrently, but collected in order
+
+        let chunks = stream::iter(0..certificate.chunks())
+            .map(|index| self.fetch_chunk(&certificate, signers.as_slice(), index))
+            .buffered(self.settings.parallel_fetches.max(1))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        // Inclusion `Proof`s do not bind chunks to their index (see `SnapshotChunk::ordered`)
+        if !SnapshotChunk::ordered(chunks.as_slice()) {
+            return CatchUpError::ChunksMisordered.fail().spot(here!());
+        }
+
+        Ok(Database::from_snapshot(
+            chunks,
+            self.settings.account_settings.clone(),
+        ))
+    }
+
+    // Collects `Snapshot` signatures from all members of `self.view`, until a plurality
+    // of them agrees on a root. Returns the resulting `SnapshotCertificate`, along with
+    // the signers (which are known to hold the certified `Snapshot`).
+    async fn certify(&self) -> Result<(SnapshotCertificate, Vec<Identity>), Top<CatchUpError>> {
+        let mut requests = self
+            .view
+            .members()
+            .keys()
+            .copied()
+            .map(|replica| async move { (replica, self.request_snapshot(replica).await) })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut agreements = HashMap::new();
+
+        while let Some((replica, response)) = requests.next().await {
+            let (root, chunks, signature) = match response {
+                Ok(response) => response,
+                Err(_) => continue,
+            };
+
+            // Replicas might be Byzantine: each signature is verified individually,
+            // so that invalid signatures cannot spoil the aggregated `Certificate`
+
+            let statement = SnapshotStatement::new(
+                &self.settings.namespace,
+                self.view.identifier(),
+                root,
+                chunks,
+            );
+            let keycard = self.view.members().get(&replica).unwrap();
+
+            if signature.verify([keycard], &statement).is_err() {
+                continue;
+            }
+
+            let components = agreements
+                .entry((root, chunks))
+                .or_insert_with(Vec::<(Identity, MultiSignature)>::new);
+
+            components.push((replica, signature));
+
+            let power = self.view.power(components.iter().map(|(signer, _)| signer));
+
+            if power >= self.view.weighted_plurality() {
+                let signers = components.iter().map(|(signer, _)| *signer).collect();
+                let certificate = Certificate::aggregate_plurality(&self.view, components.clone());
+
+                return Ok((SnapshotCertificate::new(root, chunks, certificate), signers));
+            }
+        }
+
+        CatchUpError::SnapshotUnagreed.fail().spot(here!())
+    }
+
+    // Fetches chunk `index` from `signers` (one at a time, starting from
+    // a different signer for each chunk to spread the load)
+    async fn fetch_chunk(
+        &self,
+        certificate: &SnapshotCertificate,
+        signers: &[Identity],
+        index: u64,
+    ) -> Result<SnapshotChunk, Top<CatchUpError>> {
+        let offset = (index as usize) % signers.len();
+
+        for replica in signers.iter().cycle().skip(offset).take(signers.len()) {
+            if let Ok(chunk) = self.request_chunk(*replica, certificate, index).await {
+                return Ok(chunk);
+            }
+        }
+
+        CatchUpError::ChunkUnavailable { index }
+            .fail()
+            .spot(here!())
+    }
+
+    async fn request_snapshot(
+        &self,
+        replica: Identity,
+    ) -> Result<(Hash, u64, MultiSignature), Top<RequestError>> {
+        match self.request(replica, SyncRequest::Snapshot).await? {
+            SyncResponse::Snapshot {
+                root,
+                chunks,
+                signature,
+            } => Ok((root, chunks, signature)),
+            _ => RequestError::UnexpectedResponse.fail().spot(here!()),
+        }
+    }
+
+    async fn request_chunk(
+        &self,
+        replica: Identity,
+        certificate: &SnapshotCertificate,
+        index: u64,
+    ) -> Result<SnapshotChunk, Top<RequestError>> {
+        let request = SyncRequest::Chunk {
+            root: certificate.root(),
+            index,
+        };
+
+        match self.request(replica, request).await? {
+            SyncResponse::Chunk(chunk, proof) => {
+                proof
+                    .verify(certificate.root(), &chunk)
+                    .pot(RequestError::InclusionInvalid, here!())?;
+
+                Ok(chunk)
+            }
+            _ => RequestError::UnexpectedResponse.fail().spot(here!()),
+        }
+    }
+
+    async fn request(
+        &self,
+        replica: Identity,
+        request: SyncRequest,
+    ) -> Result<SyncResponse, Top<RequestError>> {
+        time::timeout(self.settings.request_timeout, async {
+            let mut session = self
+                .connector
+                .connect(replica)
+                .await
+                .pot(RequestError::ConnectionFailed, here!())?;
+
+            session
+                .send(&request)
+                .await
+                .pot(RequestError::ConnectionError, here!())?;
+
+            let response = session
+                .receive::<SyncResponse>()
+                .await
+                .pot(RequestError::ConnectionError, here!())?;
+
+            session.end();
+
+            Ok(response)
+        })
+        .await
+        .pot(RequestError::Timeout, here!())?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        crypto::Identify,
+        processing::test::System,
+        signup::{IdRequest, SignupSettings},
+    };
+
+    use talk::crypto::KeyChain;
+
+    #[tokio::test]
+    async fn catch_up() {
+        let System {
+            view,
+            install_generator,
+            discovery_client,
+            mut brokers,
+            processors,
+            ..
+        } = System::setup(4, 1).await;
+
+        let allocator = processors[0].0.keycard().identity();
+
+        let client = KeyChain::random();
+        let request = IdRequest::new(
+            &client,
+            &view,
+            allocator,
+            SignupSettings::default().work_difficulty,
+        );
+
+        let assignment = brokers[0].signup(vec![request]).await.remove(0).unwrap();
+        let id = assignment.id();
+
+        for (keychain, _) in processors.iter() {
+            brokers[0]
+                .id_assignments(keychain.keycard().identity(), vec![assignment.clone()])
+                .await;
+        }
+
+        // Replicas snapshot their state upon the install of a later view
+
+        discovery_client
+            .publish(install_generator.install(4, 5, []))
+            .await;
+
+        discovery_client.beyond(4).await;
+
+        let database = brokers.remove(0).catch_up().await.unwrap();
+        assert!(database.assigned.contains(&id));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{account::AccountSettings, processing::Namespace};
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub(crate) struct CatchUpSettings {
+    // Must match the `Namespace` of the replicas caught up from
+    pub namespace: Namespace,
+    // Bounds each request to a replica (for a snapshot root, or a chunk)
+    pub request_timeout: Duration,
+    // Number of chunks fetched concurrently
+    pub parallel_fetches: usize,
+    // Settings of the caught-up `Database` (see `Database::with_account_settings`)
+    pub account_settings: AccountSettings,
+}
+
+impl Default for CatchUpSettings {
+    fn default() -> Self {
+        CatchUpSettings {
+            namespace: Namespace::default(),
+            request_timeout: Duration::from_secs(10),
+            parallel_fetches: 8,
+            account_settings: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+mod catch_up;
+mod catch_up_settings;
+mod snapshot_certificate;
+mod snapshot_statement;
+
+#[allow(unused_imports)]
+pub(crate) use catch_up::{CatchUp, CatchUpError};
+pub(crate) use catch_up_settings::CatchUpSettings;
+#[allow(unused_imports)]
+pub(crate) use snapshot_certificate::{SnapshotCertificate, SnapshotCertificateError};
+pub(crate) use snapshot_statement::SnapshotStatement;
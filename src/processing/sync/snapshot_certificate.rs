@@ -0,0 +1,55 @@
+use crate::{
+    crypto::{Certificate, Identify},
+    processing::{sync::SnapshotStatement, Namespace},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use talk::crypto::primitives::hash::Hash;
+
+// A `SnapshotCertificate` attests that a plurality of `view`'s members (hence
+// at least one correct member) took a `Snapshot` with the given root
+#[derive(Debug, Clone)]
+pub(crate) struct SnapshotCertificate {
+    root: Hash,
+    chunks: u64,
+    certificate: Certificate,
+}
+
+#[derive(Doom)]
+pub(crate) enum SnapshotCertificateError {
+    #[doom(description("Certificate invalid"))]
+    CertificateInvalid,
+}
+
+impl SnapshotCertificate {
+    pub fn new(root: Hash, chunks: u64, certificate: Certificate) -> Self {
+        SnapshotCertificate {
+            root,
+            chunks,
+            certificate,
+        }
+    }
+
+    pub fn root(&self) -> Hash {
+        self.root
+    }
+
+    pub fn chunks(&self) -> u64 {
+        self.chunks
+    }
+
+    pub fn verify(
+        &self,
+        view: &View,
+        namespace: &Namespace,
+    ) -> Result<(), Top<SnapshotCertificateError>> {
+        let statement =
+            SnapshotStatement::new(namespace, view.identifier(), self.root, self.chunks);
+
+        self.certificate
+            .verify_plurality(view, &statement)
+            .pot(SnapshotCertificateError::CertificateInvalid, here!())
+    }
+}
@@ -0,0 +1,29 @@
+use crate::{crypto::Header, processing::Namespace};
+
+use serde::Serialize;
+
+use talk::crypto::{primitives::hash::Hash, Statement};
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SnapshotStatement {
+    namespace: Namespace,
+    view: Hash,
+    root: Hash,
+    chunks: u64,
+}
+
+impl SnapshotStatement {
+    pub fn new(namespace: &Namespace, view: Hash, root: Hash, chunks: u64) -> Self {
+        SnapshotStatement {
+            namespace: namespace.clone(),
+            view,
+            root,
+            chunks,
+        }
+    }
+}
+
+impl Statement for SnapshotStatement {
+    type Header = Header;
+    const HEADER: Header = Header::Snapshot;
+}
@@ -1,8 +1,8 @@
 use crate::{
     database::Database,
     discovery::{self, Client, Mode, Server},
-    processing::{test::TestBroker, Processor},
-    view::View,
+    processing::{processor_settings::Delegation, test::TestBroker, Processor, ProcessorSettings},
+    view::{test::InstallGenerator, View},
 };
 
 use std::sync::Arc;
@@ -11,6 +11,8 @@ use talk::{crypto::KeyChain, net::test::System as NetSystem};
 
 pub(crate) struct System {
     pub view: View,
+    // Generates `view`, along with a later view (with one more member) to install
+    pub install_generator: InstallGenerator,
     pub discovery_server: Server,
     pub discovery_client: Arc<Client>,
     pub processors: Vec<(KeyChain, Processor)>,
@@ -19,18 +21,35 @@ pub(crate) struct System {
 
 impl System {
     pub async fn setup(processors: usize, brokers: usize) -> Self {
+        System::setup_with_delegates(processors, brokers, 0).await
+    }
+
+    // The first `delegates` elements of `brokers` are designated as delegates
+    // by every processor (see `TestBroker::delegate`)
+    pub async fn setup_with_delegates(processors: usize, brokers: usize, delegates: usize) -> Self {
         let (install_generator, discovery_server, _, mut discovery_clients, _) =
-            discovery::test::setup(processors, processors, Mode::Full).await;
+            discovery::test::setup(processors + 1, processors, Mode::Full).await;
 
         let discovery_client = Arc::new(discovery_clients.next().unwrap());
         let view = install_generator.view(processors);
 
-        let mut processor_keychains = install_generator.keychains.clone();
+        let mut processor_keychains = install_generator.keychains[..processors].to_vec();
         processor_keychains.sort_by_key(|keychain| keychain.keycard().identity());
 
         let mut broker_keychains = (0..brokers).map(|_| KeyChain::random()).collect::<Vec<_>>();
         broker_keychains.sort_by_key(|keychain| keychain.keycard().identity());
 
+        let settings = ProcessorSettings {
+            delegation: Delegation {
+                delegates: broker_keychains[..delegates]
+                    .iter()
+                    .map(|keychain| keychain.keycard().identity())
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
         let NetSystem {
             mut connectors,
             mut listeners,
@@ -55,7 +74,7 @@ impl System {
                         Database::new(),
                         connectors.remove(0),
                         listeners.remove(0),
-                        Default::default(),
+                        settings.clone(),
                     ),
                 )
             })
@@ -68,6 +87,7 @@ impl System {
 
         System {
             view,
+            install_generator,
             discovery_server,
             discovery_client,
             brokers,
@@ -1,12 +1,21 @@
 use crate::{
+    account::Id,
     crypto::Identify,
-    processing::messages::{SignupRequest, SignupResponse},
+    database::Database,
+    processing::{
+        delegate::Delegate,
+        messages::{SignupRequest, SignupResponse},
+        sync::{CatchUp, CatchUpError},
+        Namespace,
+    },
     signup::{
         IdAllocation, IdAssignment, IdAssignmentAggregator, IdClaim, IdRequest, SignupSettings,
     },
     view::View,
 };
 
+use doomstack::Top;
+
 use futures::stream::{FuturesUnordered, StreamExt};
 
 use talk::{
@@ -19,6 +28,8 @@ pub(crate) struct TestBroker {
     keychain: KeyChain,
     view: View,
     signup_connector: SessionConnector,
+    sync_connector: SessionConnector,
+    stream_connector: SessionConnector,
 }
 
 impl TestBroker {
@@ -28,10 +39,18 @@ impl TestBroker {
         let signup_context = format!("{:?}::processor::signup", view.identifier());
         let signup_connector = SessionConnector::new(dispatcher.register(signup_context));
 
+        let sync_context = format!("{:?}::processor::sync", view.identifier());
+        let sync_connector = SessionConnector::new(dispatcher.register(sync_context));
+
+        let stream_context = format!("{:?}::processor::stream", view.identifier());
+        let stream_connector = SessionConnector::new(dispatcher.register(stream_context));
+
         Self {
             keychain,
             view,
             signup_connector,
+            sync_connector,
+            stream_connector,
         }
     }
 
@@ -91,7 +110,10 @@ impl TestBroker {
 
         for claim in claims.iter() {
             claim
-                .validate(SignupSettings::default().work_difficulty)
+                .validate(
+                    &Namespace::default(),
+                    SignupSettings::default().work_difficulty,
+                )
                 .unwrap();
         }
 
@@ -111,6 +133,37 @@ impl TestBroker {
         }
     }
 
+    pub async fn id_assignments(&self, replica: Identity, assignments: Vec<IdAssignment>) {
+        let mut session = self.signup_connector.connect(replica).await.unwrap();
+
+        session
+            .send(&SignupRequest::IdAssignments(assignments))
+            .await
+            .unwrap();
+
+        let response = session.receive().await.unwrap();
+        session.end();
+
+        match response {
+            SignupResponse::AcknowledgeIdAssignments => {}
+            _ => panic!("unexpected response"),
+        }
+    }
+
+    pub async fn id_lookups(&self, replica: Identity, ids: Vec<Id>) -> Vec<Option<IdAssignment>> {
+        let mut session = self.signup_connector.connect(replica).await.unwrap();
+
+        session.send(&SignupRequest::IdLookups(ids)).await.unwrap();
+
+        let response = session.receive().await.unwrap();
+        session.end();
+
+        match response {
+            SignupResponse::IdLookups(assignments) => assignments,
+            _ => panic!("unexpected response"),
+        }
+    }
+
     pub async fn signup(&self, requests: Vec<IdRequest>) -> Vec<Option<IdAssignment>> {
         let allocations = self.id_requests(requests.clone()).await;
 
@@ -118,7 +171,9 @@ impl TestBroker {
             .into_iter()
             .zip(allocations)
             .map(|(request, allocation)| {
-                allocation.validate(&request).unwrap();
+                allocation
+                    .validate(&Namespace::default(), &request)
+                    .unwrap();
                 IdClaim::new(request, allocation)
             })
             .collect::<Vec<_>>();
@@ -147,6 +202,7 @@ impl TestBroker {
             .map(|claim| {
                 Some(IdAssignmentAggregator::new(
                     self.view.clone(),
+                    &Namespace::default(),
                     claim.id(),
                     claim.client(),
                 ))
@@ -179,7 +235,10 @@ impl TestBroker {
                         let client = aggregator.as_ref().unwrap().keycard();
 
                         collision
-                            .validate(SignupSettings::default().work_difficulty)
+                            .validate(
+                                &Namespace::default(),
+                                SignupSettings::default().work_difficulty,
+                            )
                             .unwrap();
 
                         assert_eq!(collision.id(), id);
@@ -196,4 +255,16 @@ impl TestBroker {
             .map(|aggregator| aggregator.map(|aggregator| aggregator.finalize()))
             .collect::<Vec<_>>()
     }
+
+    // Catches up with the state of `self.view`'s replicas, as a joining replica would
+    pub async fn catch_up(self) -> Result<Database, Top<CatchUpError>> {
+        CatchUp::new(self.view, self.sync_connector, Default::default())
+            .run()
+            .await
+    }
+
+    // Mirrors the `Database` of `replica`, which must designate `self` as a delegate
+    pub fn delegate(self, replica: Identity) -> Delegate {
+        Delegate::new(replica, self.stream_connector, Default::default())
+    }
 }
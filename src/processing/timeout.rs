@@ -0,0 +1,73 @@
+use crate::processing::FailureInjection;
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::time::{self, error::Elapsed};
+
+// A `Timeout` bounds protocol `await`s on serve paths (so that a stalled
+// peer cannot tie up a task indefinitely), counting how many times it expired.
+// All clones of a `Timeout` share the same count.
+#[derive(Debug, Clone)]
+pub(crate) struct Timeout {
+    duration: Duration,
+    expired: Arc<AtomicU64>,
+    failure_injection: FailureInjection,
+}
+
+impl Timeout {
+    pub fn new(duration: Duration) -> Self {
+        Timeout {
+            duration,
+            expired: Arc::new(AtomicU64::new(0)),
+            failure_injection: FailureInjection::default(),
+        }
+    }
+
+    // Subjects every message received under `self` to `failure_injection` (see `run`)
+    pub fn with_failure_injection(self, failure_injection: FailureInjection) -> Self {
+        Timeout {
+            failure_injection,
+            ..self
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn expired(&self) -> u64 {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    // The output of `future` (a message received) is delayed, and possibly dropped,
+    // by `self`'s `FailureInjection`, independently for each message. A dropped
+    // message is never delivered: `self` expires instead.
+    pub async fn run<F>(&self, future: F) -> Result<F::Output, Elapsed>
+    where
+        F: Future,
+    {
+        let result = time::timeout(self.duration, async {
+            let output = future.await;
+
+            if !self.failure_injection.inject().await {
+                std::future::pending::<()>().await;
+            }
+
+            output
+        })
+        .await;
+
+        if result.is_err() {
+            self.expired.fetch_add(1, Ordering::Relaxed);
+        }
+
+        result
+    }
+}
@@ -0,0 +1,5 @@
+mod runtime_settings;
+mod runtimes;
+
+pub use runtime_settings::{RoleSettings, RuntimeSettings};
+pub use runtimes::{Runtimes, RuntimesError};
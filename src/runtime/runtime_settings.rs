@@ -0,0 +1,17 @@
+// Settings for the threads dedicated to a role (network I/O, crypto or
+// database maintenance)
+#[derive(Debug, Clone, Default)]
+pub struct RoleSettings {
+    // Number of worker threads (if `None`, one per available core)
+    pub threads: Option<usize>,
+    // Cores to which worker threads are pinned, in round-robin
+    // fashion (if empty, worker threads are not pinned)
+    pub cores: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeSettings {
+    pub network: RoleSettings,
+    pub crypto: RoleSettings,
+    pub maintenance: RoleSettings,
+}
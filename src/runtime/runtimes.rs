@@ -0,0 +1,168 @@
+use crate::runtime::{RoleSettings, RuntimeSettings};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use rayon::ThreadPoolBuilder;
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+// `Runtimes` lets executables separate the threads running each role, so that
+// CPU-heavy work cannot starve network I/O (and vice versa):
+//  - Tasks spawned within `network()` (e.g., by starting a `Processor` or a broker
+//    from a task running on `network()`) run on the `network` runtime.
+//  - `maintenance()` is meant for the executable's own background work (e.g.,
+//    periodic snapshots or metrics reporting).
+//  - Crypto runs on `rayon`'s global pool, which all `par_iter`s use. The global
+//    pool is process-wide: it is configured only by `configure_crypto`.
+// `Runtimes` must be created (and dropped) outside of any asynchronous context.
+pub struct Runtimes {
+    network: Runtime,
+    maintenance: Runtime,
+}
+
+#[derive(Doom)]
+pub enum RuntimesError {
+    #[doom(description("Failed to build runtime"))]
+    RuntimeFailed,
+    #[doom(description("Failed to build crypto pool"))]
+    CryptoPoolFailed,
+}
+
+// Assigns cores to the threads of a role, in round-robin fashion
+#[derive(Clone)]
+struct Pinner {
+    cores: Arc<Vec<usize>>,
+    next: Arc<AtomicUsize>,
+}
+
+impl Runtimes {
+    pub fn new(settings: RuntimeSettings) -> Result<Self, Top<RuntimesError>> {
+        let network = Runtimes::runtime("carbon-network", &settings.network)
+            .pot(RuntimesError::RuntimeFailed, here!())?;
+
+        let maintenance = Runtimes::runtime("carbon-maintenance", &settings.maintenance)
+            .pot(RuntimesError::RuntimeFailed, here!())?;
+
+        Ok(Runtimes {
+            network,
+            maintenance,
+        })
+    }
+
+    // Configures `rayon`'s global pool according to `settings.crypto`. This affects
+    // the whole process, and succeeds at most once per process: it fails if the global
+    // pool was already configured, or already used (e.g., by a `par_iter`).
+    pub fn configure_crypto(settings: &RuntimeSettings) -> Result<(), Top<RuntimesError>> {
+        let pinner = Pinner::new(&settings.crypto);
+
+        let mut crypto = ThreadPoolBuilder::new()
+            .thread_name(|index| format!("carbon-crypto-{}", index))
+            .start_handler(move |_| pinner.pin());
+
+        if let Some(threads) = settings.crypto.threads {
+            crypto = crypto.num_threads(threads);
+        }
+
+        crypto
+            .build_global()
+            .pot(RuntimesError::CryptoPoolFailed, here!())
+    }
+
+    pub fn network(&self) -> &Handle {
+        self.network.handle()
+    }
+
+    pub fn maintenance(&self) -> &Handle {
+        self.maintenance.handle()
+    }
+
+    fn runtime(name: &str, settings: &RoleSettings) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all().thread_name(name);
+
+        if let Some(threads) = settings.threads {
+            builder.worker_threads(threads);
+        }
+
+        // Remark: blocking threads are pinned as well
+        let pinner = Pinner::new(settings);
+        builder.on_thread_start(move || pinner.pin());
+
+        builder.build()
+    }
+}
+
+impl Pinner {
+    fn new(settings: &RoleSettings) -> Self {
+        Pinner {
+            cores: Arc::new(settings.cores.clone()),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn pin(&self) {
+        if self.cores.is_empty() {
+            return;
+        }
+
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.cores.len();
+
+        // Pinning is best-effort: a core unavailable to the process is ignored
+        core_affinity::set_for_current(core_affinity::CoreId {
+            id: self.cores[index],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn pinned_runtime() {
+        let runtime = Runtimes::runtime(
+            "carbon-test",
+            &RoleSettings {
+                threads: Some(2),
+                cores: vec![0],
+            },
+        )
+        .unwrap();
+
+        let name = runtime.block_on(async {
+            tokio::spawn(async { thread::current().name().map(String::from) })
+                .await
+                .unwrap()
+        });
+
+        assert_eq!(name.as_deref(), Some("carbon-test"));
+    }
+
+    #[test]
+    fn independent_runtimes() {
+        // Building `Runtimes` has no process-wide effect: any number can coexist
+        let first = Runtimes::new(Default::default()).unwrap();
+        let second = Runtimes::new(Default::default()).unwrap();
+
+        let name = first.network().block_on(async {
+            tokio::spawn(async { thread::current().name().map(String::from) })
+                .await
+                .unwrap()
+        });
+
+        assert_eq!(name.as_deref(), Some("carbon-network"));
+
+        drop(first);
+        drop(second);
+    }
+}
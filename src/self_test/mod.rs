@@ -0,0 +1,7 @@
+mod self_test;
+mod self_test_report;
+mod self_test_settings;
+
+pub use self_test::SelfTest;
+pub use self_test_report::{SelfTestReport, SelfTestStage, StageReport};
+pub use self_test_settings::SelfTestSettings;
@@ -0,0 +1,499 @@
+use crate::{
+    account::{Entry, Operation},
+    brokers::{
+        commit::{
+            Broker as CommitBroker, BrokerFailure as CommitBrokerFailure, Request as CommitRequest,
+        },
+        prepare::{
+            Broker as PrepareBroker, BrokerFailure as PrepareBrokerFailure, Inclusion,
+            Request as PrepareRequest,
+        },
+        signup::{
+            Broker as SignupBroker, BrokerFailure as SignupBrokerFailure,
+            Request as SignupBrokerRequest,
+        },
+    },
+    commit::{Commit, CommitProof, Completion, CompletionProof, Payload},
+    database::Database,
+    discovery::{Client, ClientSettings, Embedded, Mode},
+    prepare::BatchCommit,
+    processing::{Namespace, Processor},
+    self_test::{SelfTestReport, SelfTestSettings, SelfTestStage, StageReport},
+    signup::{IdAssignment, IdRequest, SignupSettings},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use futures::future;
+
+use std::{
+    fmt::Debug,
+    future::Future,
+    iter,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Instant,
+};
+
+use talk::{
+    crypto::{Identity, KeyChain},
+    net::{test::System as NetSystem, PlainConnection},
+};
+
+use tokio::{net::TcpStream, time};
+
+use zebra::vector::Proof;
+
+// `SelfTest` runs signup, prepare and commit for a handful of accounts against
+// an in-process `View` (replicas, discovery and one broker per kind), validating
+// every `Completion`. It gives operators a sanity check that a build and its
+// environment (crypto, local networking) can run the protocol end to end,
+// before joining a real deployment.
+pub struct SelfTest;
+
+struct Deployment {
+    view: View,
+    discovery: Arc<Client>,
+    processors: Vec<Processor>,
+    signup_broker: SignupBroker,
+    prepare_broker: PrepareBroker,
+    commit_broker: CommitBroker,
+    _discovery_server: Embedded,
+}
+
+#[derive(Doom)]
+enum SelfTestError {
+    #[doom(description("Failed to start discovery server"))]
+    DiscoveryFailed,
+    #[doom(description("Failed to start broker"))]
+    BrokerFailed,
+    #[doom(description("Failed to connect to broker"))]
+    ConnectFailed,
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Brokerage failed: {}", failure))]
+    BrokerageFailed { failure: String },
+    #[doom(description("`Inclusion` invalid"))]
+    InclusionInvalid,
+    #[doom(description("`Completion` invalid"))]
+    CompletionInvalid,
+    #[doom(description("Stage timed out"))]
+    Timeout,
+}
+
+impl SelfTest {
+    pub async fn run(settings: SelfTestSettings) -> SelfTestReport {
+        let mut report = SelfTestReport::default();
+
+        let start = Instant::now();
+
+        let setup =
+            match time::timeout(settings.stage_timeout, SelfTest::setup(settings.replicas)).await {
+                Ok(setup) => setup,
+                Err(_) => SelfTestError::Timeout.fail().spot(here!()),
+            };
+
+        let deployment =
+            match SelfTest::record(&mut report, SelfTestStage::Setup, start, vec![setup]) {
+                Some(mut deployments) => deployments.remove(0),
+                None => return report,
+            };
+
+        SelfTest::exercise(&deployment, &settings, &mut report).await;
+
+        for processor in deployment.processors {
+            processor.shutdown();
+        }
+
+        report
+    }
+
+    async fn setup(replicas: usize) -> Result<Deployment, Top<SelfTestError>> {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(replicas)
+            .collect::<Vec<_>>();
+
+        let view = View::genesis(keychains.iter().map(KeyChain::keycard));
+
+        let discovery_server = Embedded::new(view.clone(), Default::default())
+            .await
+            .pot(SelfTestError::DiscoveryFailed, here!())?;
+
+        let discovery = Arc::new(discovery_server.client(ClientSettings {
+            mode: Mode::Full,
+            ..Default::default()
+        }));
+
+        // One additional keychain for each broker
+        let NetSystem {
+            mut connectors,
+            mut listeners,
+            ..
+        } = NetSystem::setup_with_keychains(
+            keychains
+                .iter()
+                .cloned()
+                .chain(iter::repeat_with(KeyChain::random).take(3)),
+        )
+        .await;
+
+        let mut broker_connectors = connectors.split_off(replicas);
+
+        // Brokers are started first: a broker failing to start leaves
+        // no replica behind (dropping a `Processor` reports `Failure`s)
+
+        let signup_broker = SignupBroker::new(
+            view.clone(),
+            (Ipv4Addr::LOCALHOST, 0),
+            broker_connectors.remove(0),
+            Default::default(),
+        )
+        .await
+        .pot(SelfTestError::BrokerFailed, here!())?;
+
+        let prepare_broker = PrepareBroker::new(
+            discovery.clone(),
+            view.clone(),
+            (Ipv4Addr::LOCALHOST, 0),
+            broker_connectors.remove(0),
+            Default::default(),
+        )
+        .await
+        .pot(SelfTestError::BrokerFailed, here!())?;
+
+        let commit_broker = CommitBroker::new(
+            discovery.clone(),
+            view.clone(),
+            (Ipv4Addr::LOCALHOST, 0),
+            broker_connectors.remove(0),
+            Default::default(),
+        )
+        .await
+        .pot(SelfTestError::BrokerFailed, here!())?;
+
+        let processors = keychains
+            .into_iter()
+            .map(|keychain| {
+                Processor::new(
+                    keychain,
+                    discovery.clone(),
+                    view.clone(),
+                    Database::new(),
+                    connectors.remove(0),
+                    listeners.remove(0),
+                    Default::default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let deployment = Deployment {
+            view,
+            discovery,
+            processors,
+            signup_broker,
+            prepare_broker,
+            commit_broker,
+            _discovery_server: discovery_server,
+        };
+
+        // Readiness is bounded by the timeout on the whole stage
+        for lifecycle in deployment
+            .processors
+            .iter()
+            .map(Processor::lifecycle)
+            .chain([
+                deployment.signup_broker.lifecycle(),
+                deployment.prepare_broker.lifecycle(),
+                deployment.commit_broker.lifecycle(),
+            ])
+        {
+            lifecycle.await_ready().await;
+        }
+
+        Ok(deployment)
+    }
+
+    async fn exercise(
+        deployment: &Deployment,
+        settings: &SelfTestSettings,
+        report: &mut SelfTestReport,
+    ) {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(settings.accounts)
+            .collect::<Vec<_>>();
+
+        // Signup (allocators are assigned to accounts in round-robin fashion)
+
+        let start = Instant::now();
+
+        let attempts = keychains
+            .iter()
+            .zip(deployment.view.members().keys().copied().cycle())
+            .map(|(keychain, allocator)| deployment.signup(keychain, allocator))
+            .collect();
+
+        let outcomes = SelfTest::stage(settings, attempts).await;
+
+        let assignments = match SelfTest::record(report, SelfTestStage::Signup, start, outcomes) {
+            Some(assignments) => assignments,
+            None => return,
+        };
+
+        // Prepare
+
+        let start = Instant::now();
+
+        let attempts = keychains
+            .iter()
+            .zip(assignments)
+            .map(|(keychain, assignment)| deployment.prepare(keychain, assignment))
+            .collect();
+
+        let outcomes = SelfTest::stage(settings, attempts).await;
+
+        let commits = match SelfTest::record(report, SelfTestStage::Prepare, start, outcomes) {
+            Some(commits) => commits,
+            None => return,
+        };
+
+        // Commit
+
+        let start = Instant::now();
+
+        let attempts = commits
+            .into_iter()
+            .map(|(commit, payload)| deployment.commit(commit, payload))
+            .collect();
+
+        let outcomes = SelfTest::stage(settings, attempts).await;
+
+        SelfTest::record(report, SelfTestStage::Commit, start, outcomes);
+    }
+
+    async fn stage<A, T>(
+        settings: &SelfTestSettings,
+        attempts: Vec<A>,
+    ) -> Vec<Result<T, Top<SelfTestError>>>
+    where
+        A: Future<Output = Result<T, Top<SelfTestError>>>,
+    {
+        let attempted = attempts.len();
+
+        match time::timeout(settings.stage_timeout, future::join_all(attempts)).await {
+            Ok(outcomes) => outcomes,
+            Err(_) => iter::repeat_with(|| SelfTestError::Timeout.fail().spot(here!()))
+                .take(attempted)
+                .collect(),
+        }
+    }
+
+    // Appends a `StageReport` for `outcomes` to `report`, returning
+    // all outcomes if (and only if) all attempts succeeded
+    fn record<T>(
+        report: &mut SelfTestReport,
+        stage: SelfTestStage,
+        start: Instant,
+        outcomes: Vec<Result<T, Top<SelfTestError>>>,
+    ) -> Option<Vec<T>> {
+        let attempted = outcomes.len();
+
+        let mut values = Vec::with_capacity(attempted);
+        let mut failure = None;
+
+        for outcome in outcomes {
+            match outcome {
+                Ok(value) => values.push(value),
+                Err(error) => {
+                    failure.get_or_insert_with(|| format!("{:?}", error));
+                }
+            }
+        }
+
+        let succeeded = values.len();
+
+        report.stages.push(StageReport {
+            stage,
+            elapsed: start.elapsed(),
+            attempted,
+            succeeded,
+            failure,
+        });
+
+        if succeeded == attempted {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    async fn connect(address: SocketAddr) -> Result<PlainConnection, Top<SelfTestError>> {
+        let stream = TcpStream::connect(address)
+            .await
+            .pot(SelfTestError::ConnectFailed, here!())?;
+
+        Ok(stream.into())
+    }
+
+    fn brokerage_failed<F>(failure: F) -> Top<SelfTestError>
+    where
+        F: Debug,
+    {
+        SelfTestError::BrokerageFailed {
+            failure: format!("{:?}", failure),
+        }
+        .into_top()
+    }
+}
+
+impl Deployment {
+    async fn signup(
+        &self,
+        keychain: &KeyChain,
+        allocator: Identity,
+    ) -> Result<IdAssignment, Top<SelfTestError>> {
+        let request = IdRequest::new(
+            keychain,
+            &self.view,
+            allocator,
+            SignupSettings::default().work_difficulty,
+        );
+
+        let mut connection = SelfTest::connect(self.signup_broker.address()).await?;
+
+        connection
+            .send(&SignupBrokerRequest::IdRequest {
+                namespace: Namespace::default(),
+                request,
+            })
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?;
+
+        connection
+            .receive::<Result<IdAssignment, SignupBrokerFailure>>()
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?
+            .map_err(SelfTest::brokerage_failed)
+            .spot(here!())
+    }
+
+    async fn prepare(
+        &self,
+        keychain: &KeyChain,
+        assignment: IdAssignment,
+    ) -> Result<(Commit, Payload), Top<SelfTestError>> {
+        let payload = Payload::new(
+            Entry {
+                id: assignment.id(),
+                height: 1,
+            },
+            Operation::withdraw(assignment.id(), 0, 0),
+        );
+
+        let prepare = payload.prepare();
+
+        let request =
+            PrepareRequest::new(keychain, assignment, prepare.height(), prepare.commitment());
+
+        let mut connection = SelfTest::connect(self.prepare_broker.address()).await?;
+
+        connection
+            .send(&request)
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?;
+
+        let inclusion = connection
+            .receive::<Result<Inclusion, PrepareBrokerFailure>>()
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?
+            .map_err(SelfTest::brokerage_failed)
+            .spot(here!())?;
+
+        let reduction_shard = inclusion
+            .certify_reduction(keychain, request.prepare())
+            .pot(SelfTestError::InclusionInvalid, here!())?;
+
+        connection
+            .send(&reduction_shard)
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?;
+
+        // The broker sends back the `Proof` of inclusion of `request`'s `Prepare` along
+        // with `BatchCommit`: should the batch have been retried, it differs from `inclusion`'s
+
+        let (batch_commit, proof) = connection
+            .receive::<Result<(BatchCommit, Proof), PrepareBrokerFailure>>()
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?
+            .map_err(SelfTest::brokerage_failed)
+            .spot(here!())?;
+
+        proof
+            .verify(batch_commit.root(), request.prepare())
+            .pot(SelfTestError::InclusionInvalid, here!())?;
+
+        let commit_proof = CommitProof::new(batch_commit, proof);
+        let commit = Commit::new(commit_proof, payload.clone());
+
+        Ok((commit, payload))
+    }
+
+    async fn commit(
+        &self,
+        commit: Commit,
+        payload: Payload,
+    ) -> Result<Completion, Top<SelfTestError>> {
+        let request = CommitRequest::new(commit, Vec::new());
+
+        let mut connection = SelfTest::connect(self.commit_broker.address()).await?;
+
+        connection
+            .send(&request)
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?;
+
+        let completion_proof = connection
+            .receive::<Result<CompletionProof, CommitBrokerFailure>>()
+            .await
+            .pot(SelfTestError::ConnectionError, here!())?
+            .map_err(SelfTest::brokerage_failed)
+            .spot(here!())?;
+
+        let completion = Completion::new(completion_proof, payload);
+
+        completion
+            .validate(&self.discovery)
+            .pot(SelfTestError::CompletionInvalid, here!())?;
+
+        Ok(completion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn run() {
+        let settings = SelfTestSettings::default();
+        let report = SelfTest::run(settings.clone()).await;
+
+        assert!(report.is_ok(), "{}", report);
+
+        let stages = report
+            .stages
+            .iter()
+            .map(|stage| (stage.stage, stage.attempted, stage.succeeded))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            stages,
+            vec![
+                (SelfTestStage::Setup, 1, 1),
+                (SelfTestStage::Signup, settings.accounts, settings.accounts),
+                (SelfTestStage::Prepare, settings.accounts, settings.accounts),
+                (SelfTestStage::Commit, settings.accounts, settings.accounts),
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestStage {
+    Setup,
+    Signup,
+    Prepare,
+    Commit,
+}
+
+#[derive(Debug, Clone)]
+pub struct StageReport {
+    pub stage: SelfTestStage,
+    pub elapsed: Duration,
+    pub attempted: usize,
+    pub succeeded: usize,
+    // Description of the first failure observed in the stage, if any
+    pub failure: Option<String>,
+}
+
+// A `SelfTestReport` lists the stages run by `SelfTest::run`, in order. A
+// stage runs only if all accounts succeeded in all previous stages.
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub stages: Vec<StageReport>,
+}
+
+impl SelfTestReport {
+    pub fn is_ok(&self) -> bool {
+        self.stages.len() == 4 && self.stages.iter().all(StageReport::is_ok)
+    }
+}
+
+impl StageReport {
+    pub fn is_ok(&self) -> bool {
+        self.failure.is_none() && self.succeeded == self.attempted
+    }
+}
+
+impl fmt::Display for SelfTestStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self {
+            SelfTestStage::Setup => "setup",
+            SelfTestStage::Signup => "signup",
+            SelfTestStage::Prepare => "prepare",
+            SelfTestStage::Commit => "commit",
+        };
+
+        write!(f, "{}", stage)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stage in self.stages.iter() {
+            write!(
+                f,
+                "{:<8} {:>4}/{:<4} {:>8.3}s",
+                stage.stage.to_string(),
+                stage.succeeded,
+                stage.attempted,
+                stage.elapsed.as_secs_f64()
+            )?;
+
+            match &stage.failure {
+                Some(failure) => writeln!(f, "  FAILED: {}", failure)?,
+                None => writeln!(f, "  ok")?,
+            }
+        }
+
+        write!(f, "{}", if self.is_ok() { "PASSED" } else { "FAILED" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let report = SelfTestReport {
+            stages: vec![
+                StageReport {
+                    stage: SelfTestStage::Setup,
+                    elapsed: Duration::from_millis(1500),
+                    attempted: 1,
+                    succeeded: 1,
+                    failure: None,
+                },
+                StageReport {
+                    stage: SelfTestStage::Signup,
+                    elapsed: Duration::from_millis(250),
+                    attempted: 4,
+                    succeeded: 3,
+                    failure: Some("Timed out".to_string()),
+                },
+            ],
+        };
+
+        assert!(!report.is_ok());
+
+        let display = report.to_string();
+        let lines = display.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("setup"));
+        assert!(lines[0].ends_with("ok"));
+        assert!(lines[1].ends_with("FAILED: Timed out"));
+        assert_eq!(lines[2], "FAILED");
+    }
+}
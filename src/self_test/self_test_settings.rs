@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct SelfTestSettings {
+    // Replicas of the in-process `View` (at least 4, for Byzantine resilience)
+    pub replicas: usize,
+    // Accounts taken through signup, prepare and commit
+    pub accounts: usize,
+    // Maximum duration of each stage
+    pub stage_timeout: Duration,
+}
+
+impl Default for SelfTestSettings {
+    fn default() -> Self {
+        SelfTestSettings {
+            replicas: 4,
+            accounts: 4,
+            stage_timeout: Duration::from_secs(30),
+        }
+    }
+}
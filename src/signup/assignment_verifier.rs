@@ -0,0 +1,240 @@
+use crate::{
+    discovery::Client,
+    signup::{AssignmentVerifierSettings, IdAssignment, IdAssignmentError},
+};
+
+use doomstack::Top;
+
+use rayon::prelude::*;
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use talk::crypto::primitives::hash::{self, Hash};
+
+use tokio::sync::oneshot;
+
+// An `AssignmentVerifier` validates `IdAssignment`s on behalf of all the serving
+// tasks of a component (e.g., a replica's prepare and signup paths, or a prepare
+// broker's frontend). Valid `IdAssignment`s are cached by digest, so that an
+// `IdAssignment` relayed by several brokers, peers or requests has its certificate
+// verified only once. Bulk verifications are deduplicated, and offloaded to the
+// rayon pool so as not to stall the calling task's runtime.
+// All clones of an `AssignmentVerifier` share the same cache.
+#[derive(Clone)]
+pub(crate) struct AssignmentVerifier {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    discovery: Arc<Client>,
+    settings: AssignmentVerifierSettings,
+    cache: Mutex<Cache>,
+    verifications: AtomicU64,
+}
+
+struct Cache {
+    verified: HashSet<Hash>,
+    order: VecDeque<Hash>,
+}
+
+impl AssignmentVerifier {
+    pub fn new(discovery: Arc<Client>, settings: AssignmentVerifierSettings) -> Self {
+        AssignmentVerifier {
+            inner: Arc::new(Inner {
+                discovery,
+                settings,
+                cache: Mutex::new(Cache {
+                    verified: HashSet::new(),
+                    order: VecDeque::new(),
+                }),
+                verifications: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    // Number of certificates actually verified (i.e., cache misses)
+    pub fn verifications(&self) -> u64 {
+        self.inner.verifications.load(Ordering::Relaxed)
+    }
+
+    // Verifies `assignment` on the calling thread (e.g., when already
+    // running on the rayon pool)
+    pub fn verify(&self, assignment: &IdAssignment) -> Result<(), Top<IdAssignmentError>> {
+        let digest = hash::hash(assignment).unwrap();
+
+        if self.contains(digest) {
+            return Ok(());
+        }
+
+        self.verify_uncached(digest, assignment)
+    }
+
+    // Verifies all elements of `assignments`, failing if any is invalid
+    pub async fn verify_all(
+        &self,
+        assignments: &[IdAssignment],
+    ) -> Result<(), Top<IdAssignmentError>> {
+        let digests = assignments
+            .iter()
+            .map(|assignment| hash::hash(assignment).unwrap())
+            .collect::<Vec<_>>();
+
+        // Retain only the first occurrence of each uncached `IdAssignment`
+
+        let pending = {
+            let cache = self.inner.cache.lock().unwrap();
+            let mut seen = HashSet::new();
+
+            digests
+                .into_iter()
+                .zip(assignments)
+                .filter(|(digest, _)| !cache.verified.contains(digest) && seen.insert(*digest))
+                .map(|(digest, assignment)| (digest, assignment.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        if pending.len() <= self.inner.settings.offload_threshold {
+            return pending
+                .iter()
+                .map(|(digest, assignment)| self.verify_uncached(*digest, assignment))
+                .collect();
+        }
+
+        let (result_inlet, result_outlet) = oneshot::channel();
+        let verifier = self.clone();
+
+        rayon::spawn(move || {
+            let result = pending
+                .par_iter()
+                .map(|(digest, assignment)| verifier.verify_uncached(*digest, assignment))
+                .collect::<Result<(), _>>();
+
+            let _ = result_inlet.send(result);
+        });
+
+        // `result_inlet` is dropped without sending only if verification panicked
+        result_outlet.await.unwrap()
+    }
+
+    fn verify_uncached(
+        &self,
+        digest: Hash,
+        assignment: &IdAssignment,
+    ) -> Result<(), Top<IdAssignmentError>> {
+        self.inner.verifications.fetch_add(1, Ordering::Relaxed);
+
+        // Invalid `IdAssignment`s are never cached
+        assignment.validate(self.inner.discovery.as_ref())?;
+        self.insert(digest);
+
+        Ok(())
+    }
+
+    fn contains(&self, digest: Hash) -> bool {
+        self.inner.cache.lock().unwrap().verified.contains(&digest)
+    }
+
+    fn insert(&self, digest: Hash) {
+        let capacity = self.inner.settings.cache_capacity;
+
+        if capacity == 0 {
+            return;
+        }
+
+        let mut cache = self.inner.cache.lock().unwrap();
+
+        if cache.verified.insert(digest) {
+            if cache.order.len() >= capacity {
+                if let Some(oldest) = cache.order.pop_front() {
+                    cache.verified.remove(&oldest);
+                }
+            }
+
+            cache.order.push_back(digest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        account::Id,
+        discovery::Embedded,
+        processing::Namespace,
+        signup::test,
+        view::{test::InstallGenerator, View},
+    };
+
+    use talk::crypto::KeyChain;
+
+    fn assignment(generator: &InstallGenerator, view: &View, id: Id) -> IdAssignment {
+        test::assignment(
+            &generator.keychains,
+            view,
+            &Namespace::default(),
+            &KeyChain::random(),
+            id,
+        )
+    }
+
+    #[tokio::test]
+    async fn verify() {
+        let generator = InstallGenerator::new(5);
+        let view = generator.view(4);
+
+        let embedded = Embedded::new(view.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let discovery = Arc::new(embedded.client(Default::default()));
+
+        let verifier = AssignmentVerifier::new(
+            discovery,
+            AssignmentVerifierSettings {
+                cache_capacity: 1,
+                offload_threshold: 0,
+            },
+        );
+
+        let first = assignment(&generator, &view, 0);
+        let second = assignment(&generator, &view, 1);
+
+        // Repeated `IdAssignment`s are verified once, cached ones not at all
+
+        verifier
+            .verify_all(&[first.clone(), first.clone()])
+            .await
+            .unwrap();
+
+        assert_eq!(verifier.verifications(), 1);
+
+        verifier.verify(&first).unwrap();
+        assert_eq!(verifier.verifications(), 1);
+
+        // `second` evicts `first`
+
+        verifier.verify(&second).unwrap();
+        verifier.verify(&first).unwrap();
+
+        assert_eq!(verifier.verifications(), 3);
+
+        // `IdAssignment`s from a `View` unknown to discovery are invalid
+
+        let unknown = assignment(&generator, &generator.view(5), 2);
+
+        assert!(verifier
+            .verify_all(&[second, unknown.clone()])
+            .await
+            .is_err());
+
+        assert!(verifier.verify(&unknown).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+#[derive(Debug, Clone)]
+pub(crate) struct AssignmentVerifierSettings {
+    // Number of valid `IdAssignment`s remembered (once full, the oldest
+    // `IdAssignment` is forgotten first)
+    pub cache_capacity: usize,
+    // Bulk verifications of more than `offload_threshold` uncached `IdAssignment`s
+    // are carried out on the rayon pool, smaller ones by the calling task
+    pub offload_threshold: usize,
+}
+
+impl Default for AssignmentVerifierSettings {
+    fn default() -> Self {
+        AssignmentVerifierSettings {
+            cache_capacity: 65536,
+            offload_threshold: 1,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{account::Id, crypto::Header, signup::IdRequest, view::View};
+use crate::{account::Id, crypto::Header, processing::Namespace, signup::IdRequest, view::View};
 
 use doomstack::{here, Doom, ResultExt, Top};
 
@@ -17,6 +17,7 @@ pub(crate) struct IdAllocation {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Allocation {
+    namespace: Namespace,
     view: Hash,
     id: Id,
     client: Identity,
@@ -31,11 +32,14 @@ pub(crate) enum IdAllocationError {
 }
 
 impl IdAllocation {
-    pub fn new(keychain: &KeyChain, request: &IdRequest, id: Id) -> Self {
-        let view = request.view();
-        let client = request.client().identity();
+    pub fn new(keychain: &KeyChain, namespace: &Namespace, request: &IdRequest, id: Id) -> Self {
+        let allocation = Allocation {
+            namespace: namespace.clone(),
+            view: request.view(),
+            id,
+            client: request.client().identity(),
+        };
 
-        let allocation = Allocation { view, id, client };
         let signature = keychain.sign(&allocation).unwrap();
 
         IdAllocation { id, signature }
@@ -46,11 +50,16 @@ impl IdAllocation {
     }
 
     // In order to avoid panics, `request` must have been validated beforehand
-    pub fn validate(&self, request: &IdRequest) -> Result<(), Top<IdAllocationError>> {
+    pub fn validate(
+        &self,
+        namespace: &Namespace,
+        request: &IdRequest,
+    ) -> Result<(), Top<IdAllocationError>> {
         let view = View::get(request.view()).unwrap();
         let keycard = view.members().get(&request.allocator()).unwrap();
 
         let allocation = Allocation {
+            namespace: namespace.clone(),
             view: request.view(),
             id: self.id,
             client: request.client().identity(),
@@ -105,8 +114,15 @@ mod tests {
             SignupSettings::default().work_difficulty,
         );
 
-        let allocation = IdAllocation::new(&allocator, &request, 0);
-        allocation.validate(&request).unwrap();
+        let allocation = IdAllocation::new(&allocator, &Namespace::default(), &request, 0);
+        allocation
+            .validate(&Namespace::default(), &request)
+            .unwrap();
+
+        // Allocations are void outside of their `Namespace`
+        assert!(allocation
+            .validate(&Namespace::new("foreign"), &request)
+            .is_err());
     }
 
     #[test]
@@ -132,7 +148,9 @@ mod tests {
             SignupSettings::default().work_difficulty,
         );
 
-        let allocation = IdAllocation::new(&allocator, &request, 0);
-        assert!(allocation.validate(&request).is_err());
+        let allocation = IdAllocation::new(&allocator, &Namespace::default(), &request, 0);
+        assert!(allocation
+            .validate(&Namespace::default(), &request)
+            .is_err());
     }
 }
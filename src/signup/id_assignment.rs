@@ -4,6 +4,7 @@ use crate::{
     account::Id,
     crypto::{Aggregator, Certificate, Header, Identify},
     discovery::Client,
+    processing::Namespace,
     signup::IdClaim,
     view::View,
 };
@@ -33,7 +34,14 @@ struct Assignment {
     keycard: KeyCard,
 }
 
-pub(crate) struct IdAssignmentAggregator(Aggregator<Assignment>);
+// Signed by replicas to certify `assignment` in `namespace`
+#[derive(Clone, Debug, Serialize)]
+struct Statement {
+    namespace: Namespace,
+    assignment: Assignment,
+}
+
+pub(crate) struct IdAssignmentAggregator(Aggregator<Statement>);
 
 #[derive(Doom)]
 pub(crate) enum IdAssignmentError {
@@ -44,13 +52,16 @@ pub(crate) enum IdAssignmentError {
 }
 
 impl IdAssignment {
-    pub fn certify(keychain: &KeyChain, claim: &IdClaim) -> MultiSignature {
-        keychain
-            .multisign(&Assignment {
+    pub fn certify(keychain: &KeyChain, namespace: &Namespace, claim: &IdClaim) -> MultiSignature {
+        let statement = Statement {
+            namespace: namespace.clone(),
+            assignment: Assignment {
                 id: claim.id(),
                 keycard: claim.client(),
-            })
-            .unwrap()
+            },
+        };
+
+        keychain.multisign(&statement).unwrap()
     }
 
     pub fn id(&self) -> Id {
@@ -67,8 +78,13 @@ impl IdAssignment {
             .ok_or(IdAssignmentError::ViewUnknown.into_top())
             .spot(here!())?;
 
+        let statement = Statement {
+            namespace: discovery.namespace().clone(),
+            assignment: self.assignment.clone(),
+        };
+
         self.certificate
-            .verify_quorum(&view, &self.assignment)
+            .verify_quorum(&view, &statement)
             .pot(IdAssignmentError::CertificateInvalid, here!())?;
 
         Ok(())
@@ -76,8 +92,12 @@ impl IdAssignment {
 }
 
 impl IdAssignmentAggregator {
-    pub fn new(view: View, id: Id, keycard: KeyCard) -> Self {
-        let statement = Assignment { id, keycard };
+    pub fn new(view: View, namespace: &Namespace, id: Id, keycard: KeyCard) -> Self {
+        let statement = Statement {
+            namespace: namespace.clone(),
+            assignment: Assignment { id, keycard },
+        };
+
         let aggregator = Aggregator::new(view, statement);
 
         IdAssignmentAggregator(aggregator)
@@ -92,11 +112,11 @@ impl IdAssignmentAggregator {
     }
 
     pub fn id(&self) -> Id {
-        self.0.statement().id
+        self.0.statement().assignment.id
     }
 
     pub fn keycard(&self) -> KeyCard {
-        self.0.statement().keycard.clone()
+        self.0.statement().assignment.keycard.clone()
     }
 
     pub fn multiplicity(&self) -> usize {
@@ -105,11 +125,11 @@ impl IdAssignmentAggregator {
 
     pub fn finalize(self) -> IdAssignment {
         let view = self.0.view().identifier();
-        let (assignment, certificate) = self.0.finalize_quorum();
+        let (statement, certificate) = self.0.finalize_quorum();
 
         IdAssignment {
             view,
-            assignment,
+            assignment: statement.assignment,
             certificate,
         }
     }
@@ -123,7 +143,7 @@ impl Splittable for IdAssignment {
     }
 }
 
-impl CryptoStatement for Assignment {
+impl CryptoStatement for Statement {
     type Header = Header;
     const HEADER: Header = Header::IdAssignment;
 }
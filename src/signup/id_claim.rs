@@ -1,5 +1,6 @@
 use crate::{
     account::Id,
+    processing::Namespace,
     signup::{IdAllocation, IdRequest},
 };
 
@@ -31,6 +32,10 @@ impl IdClaim {
         }
     }
 
+    pub fn request(&self) -> &IdRequest {
+        &self.request
+    }
+
     pub fn view(&self) -> Hash {
         self.request.view()
     }
@@ -47,13 +52,17 @@ impl IdClaim {
         self.request.client()
     }
 
-    pub fn validate(&self, work_difficulty: u64) -> Result<(), Top<IdClaimError>> {
+    pub fn validate(
+        &self,
+        namespace: &Namespace,
+        work_difficulty: u64,
+    ) -> Result<(), Top<IdClaimError>> {
         self.request
             .validate(work_difficulty)
             .pot(IdClaimError::IdRequestInvalid, here!())?;
 
         self.allocation
-            .validate(&self.request)
+            .validate(namespace, &self.request)
             .pot(IdClaimError::IdAllocationInvalid, here!())?;
 
         Ok(())
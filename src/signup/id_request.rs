@@ -1,5 +1,6 @@
 use crate::{
     crypto::{Header, Identify, Rogue},
+    signup::IdRequestGeneration,
     view::View,
 };
 
@@ -8,14 +9,18 @@ use doomstack::{here, Doom, ResultExt, Top};
 use serde::{Deserialize, Serialize};
 
 use talk::crypto::{
-    primitives::{hash::Hash, work::Work},
+    primitives::{
+        hash::{self, Hash},
+        work::Work,
+    },
     Identity, KeyCard, KeyChain, Statement,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct IdRequest {
     request: Request,
-    work: Work,
+    // One `Work` per chunk (see `IdRequest::WORK_CHUNKS`)
+    work: Vec<Work>,
     rogue: Rogue,
 }
 
@@ -36,34 +41,84 @@ pub(crate) enum RequestIdError {
     WorkInvalid,
     #[doom(description("Rogue-safety proof invalid"))]
     RogueInvalid,
+    #[doom(description("Allocator is not the beacon allocator of the client"))]
+    BiasedAllocator,
 }
 
 impl IdRequest {
+    // The proof of work of an `IdRequest` is split into `WORK_CHUNKS` `Work`s (each
+    // over the request and the index of its chunk), each `WORK_CHUNK_BITS` easier
+    // than `work_difficulty`: their expected cost adds up to that of a single `Work`
+    // of difficulty `work_difficulty`, but they are computed (and can be interrupted)
+    // one at a time (see `IdRequestGeneration`).
+    pub const WORK_CHUNKS: u64 = 16;
+    const WORK_CHUNK_BITS: u64 = 4;
+
     pub fn new(
         keychain: &KeyChain,
         view: &View,
         allocator: Identity,
         work_difficulty: u64,
     ) -> Self {
-        let view = view.identifier();
-        let client = keychain.keycard();
-
-        let request = Request {
-            view,
-            allocator,
-            client,
-        };
+        let request = Request::new(keychain, view, allocator);
 
-        let work = Work::new(work_difficulty, &request).unwrap();
-        let rogue = Rogue::new(keychain);
+        let work = (0..IdRequest::WORK_CHUNKS)
+            .map(|index| request.work(work_difficulty, index))
+            .collect();
 
         IdRequest {
             request,
             work,
-            rogue,
+            rogue: Rogue::new(keychain),
         }
     }
 
+    // Like `new`, but the proof of work is computed on a blocking thread, one chunk
+    // at a time, without stalling the async caller (see `IdRequestGeneration`).
+    pub fn generate(
+        keychain: &KeyChain,
+        view: &View,
+        allocator: Identity,
+        work_difficulty: u64,
+    ) -> IdRequestGeneration {
+        let request = Request::new(keychain, view, allocator);
+        let rogue = Rogue::new(keychain);
+
+        let chunk = {
+            let request = request.clone();
+            move |index| request.work(work_difficulty, index)
+        };
+
+        IdRequestGeneration::new(
+            work_difficulty,
+            IdRequest::WORK_CHUNKS,
+            chunk,
+            move |work| IdRequest {
+                request,
+                work,
+                rogue,
+            },
+        )
+    }
+
+    // Derives the allocator of `client`'s `IdRequest`s in `view` from the beacon of
+    // `view` (the hash of its identifier, which no client controls) and `client`.
+    // Allocators are uniformly distributed across clients, and a client can bias
+    // its allocator only by generating (and proving work for) a new `KeyCard`.
+    pub fn beacon_allocator(view: &View, client: &KeyCard) -> Identity {
+        let beacon = hash::hash(&view.identifier()).unwrap();
+        let seed = hash::hash(&(beacon, client)).unwrap();
+
+        let seed = bincode::serialize(&seed).unwrap();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&seed[seed.len() - 8..]);
+
+        let index = (u64::from_le_bytes(bytes) % (view.members().len() as u64)) as usize;
+
+        // `index < view.members().len()`, so `nth(index)` is `Some`
+        view.members().keys().copied().nth(index).unwrap()
+    }
+
     pub fn view(&self) -> Hash {
         self.request.view
     }
@@ -85,9 +140,9 @@ impl IdRequest {
             return RequestIdError::ForeignAllocator.fail().spot(here!());
         }
 
-        self.work
-            .verify(work_difficulty, &self.request)
-            .pot(RequestIdError::WorkInvalid, here!())?;
+        if !self.meets_difficulty(work_difficulty) {
+            return RequestIdError::WorkInvalid.fail().spot(here!());
+        }
 
         self.rogue
             .validate(&self.request.client)
@@ -95,6 +150,47 @@ impl IdRequest {
 
         Ok(())
     }
+
+    // Checks `self`'s `Work` only (`self` must be otherwise valid, see `validate`)
+    pub fn meets_difficulty(&self, work_difficulty: u64) -> bool {
+        let difficulty = work_difficulty.saturating_sub(IdRequest::WORK_CHUNK_BITS);
+
+        self.work.len() as u64 == IdRequest::WORK_CHUNKS
+            && self.work.iter().enumerate().all(|(index, work)| {
+                work.verify(difficulty, &(&self.request, index as u64))
+                    .is_ok()
+            })
+    }
+
+    // Checks that `self` is addressed to its client's beacon allocator
+    // (only relevant under `SignupSettings::beacon_allocation`)
+    pub fn validate_allocator(&self) -> Result<(), Top<RequestIdError>> {
+        let view = View::get(self.request.view)
+            .ok_or(RequestIdError::UnknownView.into_top())
+            .spot(here!())?;
+
+        if self.request.allocator != IdRequest::beacon_allocator(&view, &self.request.client) {
+            return RequestIdError::BiasedAllocator.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+}
+
+impl Request {
+    fn new(keychain: &KeyChain, view: &View, allocator: Identity) -> Self {
+        Request {
+            view: view.identifier(),
+            allocator,
+            client: keychain.keycard(),
+        }
+    }
+
+    // `Work` for the chunk at `index` (see `IdRequest::WORK_CHUNKS`)
+    fn work(&self, work_difficulty: u64, index: u64) -> Work {
+        let difficulty = work_difficulty.saturating_sub(IdRequest::WORK_CHUNK_BITS);
+        Work::new(difficulty, &(self, index)).unwrap()
+    }
 }
 
 impl Statement for Request {
@@ -127,4 +223,131 @@ mod tests {
             .validate(SignupSettings::default().work_difficulty)
             .unwrap();
     }
+
+    #[tokio::test]
+    async fn generate() {
+        let install_generator = InstallGenerator::new(4);
+
+        let view = install_generator.view(4);
+        let allocator = install_generator.keycards[0].identity();
+
+        let client = KeyChain::random();
+
+        let generation = IdRequest::generate(
+            &client,
+            &view,
+            allocator,
+            SignupSettings::default().work_difficulty,
+        );
+
+        let request = generation.wait().await.unwrap();
+
+        request
+            .validate(SignupSettings::default().work_difficulty)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        let install_generator = InstallGenerator::new(4);
+
+        let view = install_generator.view(4);
+        let allocator = install_generator.keycards[0].identity();
+
+        let client = KeyChain::random();
+
+        let generation = IdRequest::generate(
+            &client,
+            &view,
+            allocator,
+            SignupSettings::default().work_difficulty,
+        );
+
+        generation.monitor().cancel();
+
+        assert!(generation.wait().await.is_err());
+    }
+
+    #[test]
+    fn chunks() {
+        let install_generator = InstallGenerator::new(4);
+
+        let view = install_generator.view(4);
+        let allocator = install_generator.keycards[0].identity();
+
+        let client = KeyChain::random();
+
+        // Each chunk is hard enough for a `Work` to hardly ever verify for another index
+        let difficulty = 16;
+        let request = IdRequest::new(&client, &view, allocator, difficulty);
+
+        assert_eq!(request.work.len() as u64, IdRequest::WORK_CHUNKS);
+        assert!(request.meets_difficulty(difficulty));
+
+        // Every chunk must be provided
+        let mut truncated = request.clone();
+        truncated.work.pop();
+
+        assert!(!truncated.meets_difficulty(difficulty));
+
+        // Chunks are bound to their index
+        let mut swapped = request.clone();
+        swapped.work.swap(0, 1);
+
+        assert!(!swapped.meets_difficulty(difficulty));
+    }
+
+    #[test]
+    fn beacon_allocation() {
+        let install_generator = InstallGenerator::new(4);
+        let view = install_generator.view(4);
+
+        let clients = (0..64).map(|_| KeyChain::random()).collect::<Vec<_>>();
+
+        let allocators = clients
+            .iter()
+            .map(|client| IdRequest::beacon_allocator(&view, &client.keycard()))
+            .collect::<Vec<_>>();
+
+        // Beacon allocators are deterministic members of `view`, and vary across clients
+
+        for (client, allocator) in clients.iter().zip(allocators.iter()) {
+            assert!(view.members().contains_key(allocator));
+            assert_eq!(
+                IdRequest::beacon_allocator(&view, &client.keycard()),
+                *allocator
+            );
+        }
+
+        assert!(allocators
+            .iter()
+            .any(|allocator| *allocator != allocators[0]));
+
+        // Requests addressed to any other allocator are biased
+
+        let biased = view
+            .members()
+            .keys()
+            .copied()
+            .find(|allocator| *allocator != allocators[0])
+            .unwrap();
+
+        let request = IdRequest::new(
+            &clients[0],
+            &view,
+            allocators[0],
+            SignupSettings::default().work_difficulty,
+        );
+
+        request.validate_allocator().unwrap();
+
+        let request = IdRequest::new(
+            &clients[0],
+            &view,
+            biased,
+            SignupSettings::default().work_difficulty,
+        );
+
+        assert!(request.validate_allocator().is_err());
+    }
 }
@@ -0,0 +1,251 @@
+use crate::signup::IdRequest;
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use talk::crypto::primitives::work::Work;
+
+use tokio::task::{self, JoinError, JoinHandle};
+
+// `IdRequestGeneration` is a handle to an `IdRequest` whose proof of work
+// is being computed on a blocking thread, one chunk at a time (see
+// `IdRequest::WORK_CHUNKS`). Progress can be polled, and generation cancelled,
+// through any number of `GenerationMonitor`s. Cancellation takes effect
+// between chunks: the blocking thread is released as soon as the chunk in
+// progress (if any) is complete.
+pub(crate) struct IdRequestGeneration {
+    state: Arc<State>,
+    handle: JoinHandle<Option<IdRequest>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct GenerationMonitor {
+    state: Arc<State>,
+}
+
+// `chunks_done` out of `chunks` chunks of work were computed after `elapsed`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GenerationProgress {
+    pub work_difficulty: u64,
+    pub chunks_done: u64,
+    pub chunks: u64,
+    pub elapsed: Duration,
+}
+
+struct State {
+    work_difficulty: u64,
+    chunks: u64,
+    start: Instant,
+    chunks_done: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+#[derive(Doom)]
+pub(crate) enum IdRequestGenerationError {
+    #[doom(description("Generation cancelled"))]
+    GenerationCancelled,
+    #[doom(description("Generation failed: {:?}", source))]
+    #[doom(wrap(generation_failed))]
+    GenerationFailed { source: JoinError },
+}
+
+impl IdRequestGeneration {
+    // Computes `chunk(0)` to `chunk(chunks - 1)` in order, then assembles
+    // the resulting `Work`s into an `IdRequest` by `assemble`
+    pub(in crate::signup) fn new<C, A>(
+        work_difficulty: u64,
+        chunks: u64,
+        mut chunk: C,
+        assemble: A,
+    ) -> Self
+    where
+        C: 'static + Send + FnMut(u64) -> Work,
+        A: 'static + Send + FnOnce(Vec<Work>) -> IdRequest,
+    {
+        let state = Arc::new(State {
+            work_difficulty,
+            chunks,
+            start: Instant::now(),
+            chunks_done: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        });
+
+        let handle = {
+            let state = state.clone();
+
+            task::spawn_blocking(move || {
+                let mut work = Vec::with_capacity(chunks as usize);
+
+                for index in 0..chunks {
+                    if state.cancelled.load(Ordering::Acquire) {
+                        return None;
+                    }
+
+                    work.push(chunk(index));
+                    state.chunks_done.fetch_add(1, Ordering::Release);
+                }
+
+                Some(assemble(work))
+            })
+        };
+
+        IdRequestGeneration { state, handle }
+    }
+
+    pub fn monitor(&self) -> GenerationMonitor {
+        GenerationMonitor {
+            state: self.state.clone(),
+        }
+    }
+
+    pub fn progress(&self) -> GenerationProgress {
+        self.state.progress()
+    }
+
+    // Guarantees that `wait` fails with `GenerationCancelled`. No further
+    // chunk is computed once the chunk in progress (if any) is complete.
+    pub fn cancel(&self) {
+        self.state.cancel();
+    }
+
+    pub async fn wait(self) -> Result<IdRequest, Top<IdRequestGenerationError>> {
+        let request = self
+            .handle
+            .await
+            .map_err(IdRequestGenerationError::generation_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        // If `cancel` was invoked while the last chunk was being computed,
+        // `request` is discarded anyway
+        if self.state.cancelled.load(Ordering::Acquire) {
+            return IdRequestGenerationError::GenerationCancelled
+                .fail()
+                .spot(here!());
+        }
+
+        // `request` is `None` only if generation was cancelled (see above)
+        Ok(request.unwrap())
+    }
+}
+
+impl GenerationMonitor {
+    pub fn progress(&self) -> GenerationProgress {
+        self.state.progress()
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancel();
+    }
+}
+
+impl GenerationProgress {
+    pub fn complete(&self) -> bool {
+        self.chunks_done == self.chunks
+    }
+}
+
+impl State {
+    fn progress(&self) -> GenerationProgress {
+        GenerationProgress {
+            work_difficulty: self.work_difficulty,
+            chunks_done: self.chunks_done.load(Ordering::Acquire),
+            chunks: self.chunks,
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::view::test::InstallGenerator;
+
+    use std::sync::mpsc;
+
+    use talk::crypto::KeyChain;
+
+    #[tokio::test]
+    async fn cancel_mid_run() {
+        let install_generator = InstallGenerator::new(4);
+
+        let view = install_generator.view(4);
+        let allocator = install_generator.keycards[0].identity();
+
+        let client = KeyChain::random();
+
+        // The chunk at `index` is announced on `started`, then computed
+        // only once `index` is sent on `permits`
+
+        let (started_inlet, started) = mpsc::channel::<u64>();
+        let (permits, permitted) = mpsc::channel::<u64>();
+
+        let chunk = move |index| {
+            started_inlet.send(index).unwrap();
+            assert_eq!(permitted.recv().unwrap(), index);
+
+            Work::new(0, &index).unwrap()
+        };
+
+        let generation = IdRequestGeneration::new(0, 4, chunk, move |_| {
+            IdRequest::new(&client, &view, allocator, 0)
+        });
+
+        let monitor = generation.monitor();
+
+        for index in 0..2 {
+            assert_eq!(started.recv().unwrap(), index);
+            permits.send(index).unwrap();
+        }
+
+        // The chunk at index 2 is in progress
+
+        assert_eq!(started.recv().unwrap(), 2);
+
+        let progress = monitor.progress();
+
+        assert_eq!(progress.chunks_done, 2);
+        assert_eq!(progress.chunks, 4);
+        assert!(!progress.complete());
+
+        // The chunk in progress is completed, but no further chunk is computed
+
+        monitor.cancel();
+        permits.send(2).unwrap();
+
+        assert!(generation.wait().await.is_err());
+
+        assert_eq!(monitor.progress().chunks_done, 3);
+        assert!(started.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn progress() {
+        let install_generator = InstallGenerator::new(4);
+
+        let view = install_generator.view(4);
+        let allocator = install_generator.keycards[0].identity();
+
+        let generation = IdRequest::generate(&KeyChain::random(), &view, allocator, 4);
+        let monitor = generation.monitor();
+
+        generation.wait().await.unwrap();
+
+        let progress = monitor.progress();
+
+        assert_eq!(progress.chunks, IdRequest::WORK_CHUNKS);
+        assert!(progress.complete());
+    }
+}
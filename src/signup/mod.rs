@@ -1,21 +1,35 @@
 mod allocation_range;
+mod assignment_verifier;
+mod assignment_verifier_settings;
 mod id_allocation;
 mod id_assignment;
 mod id_claim;
 mod id_request;
+mod id_request_generation;
 mod signup_settings;
 
+#[cfg(test)]
+pub(crate) mod test;
+
+pub(crate) use assignment_verifier::AssignmentVerifier;
+pub(crate) use assignment_verifier_settings::AssignmentVerifierSettings;
+
 #[allow(unused_imports)]
 pub(crate) use id_allocation::IdAllocation;
 
-#[allow(unused_imports)]
-pub(crate) use id_assignment::IdAssignment;
 #[allow(unused_imports)]
 pub(crate) use id_assignment::IdAssignmentAggregator;
+#[allow(unused_imports)]
+pub(crate) use id_assignment::{IdAssignment, IdAssignmentError};
 
 #[allow(unused_imports)]
 pub(crate) use id_claim::IdClaim;
 
 #[allow(unused_imports)]
 pub(crate) use id_request::IdRequest;
+
+#[allow(unused_imports)]
+pub(crate) use id_request_generation::{
+    GenerationMonitor, GenerationProgress, IdRequestGeneration, IdRequestGenerationError,
+};
 pub(crate) use signup_settings::SignupSettings;
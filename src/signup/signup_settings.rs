@@ -1,10 +1,16 @@
 #[derive(Debug, Clone)]
 pub(crate) struct SignupSettings {
     pub work_difficulty: u64,
+    // If `true`, clients do not choose the allocator of their `IdRequest`s: each
+    // `IdRequest` must be addressed to its beacon allocator (see `IdRequest::beacon_allocator`)
+    pub beacon_allocation: bool,
 }
 
 impl Default for SignupSettings {
     fn default() -> Self {
-        SignupSettings { work_difficulty: 8 }
+        SignupSettings {
+            work_difficulty: 8,
+            beacon_allocation: false,
+        }
     }
 }
@@ -0,0 +1,38 @@
+use crate::{
+    account::Id,
+    processing::Namespace,
+    signup::{IdAllocation, IdAssignment, IdAssignmentAggregator, IdClaim, IdRequest},
+    view::View,
+};
+
+use talk::crypto::KeyChain;
+
+// Assigns `id` to `client` in `namespace`: `id` is allocated by `replicas[0]`, and
+// the assignment is certified by the first quorum of `replicas` (which must be
+// members of `view`). The `IdRequest` carries no work.
+pub(crate) fn assignment(
+    replicas: &[KeyChain],
+    view: &View,
+    namespace: &Namespace,
+    client: &KeyChain,
+    id: Id,
+) -> IdAssignment {
+    let allocator = &replicas[0];
+
+    let request = IdRequest::new(client, view, allocator.keycard().identity(), 0);
+    let allocation = IdAllocation::new(allocator, namespace, &request, id);
+    let claim = IdClaim::new(request, allocation);
+
+    let mut aggregator = IdAssignmentAggregator::new(view.clone(), namespace, id, client.keycard());
+
+    for keychain in replicas.iter().take(view.quorum()) {
+        aggregator
+            .add(
+                &keychain.keycard(),
+                IdAssignment::certify(keychain, namespace, &claim),
+            )
+            .unwrap();
+    }
+
+    aggregator.finalize()
+}
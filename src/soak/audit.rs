@@ -0,0 +1,128 @@
+use crate::{account::Id, database::commit::HistoryQuery, processing::Processor, soak::Workload};
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tokio::time;
+
+// `Completion`s are certified by a quorum of replicas: the others
+// are given `SETTLE_TIMEOUT` to catch up before being audited
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// An `Audit` checks every replica against a `Workload` through the replicas'
+// query paths (`Processor::history`, `Processor::balances`), remembering the
+// number of `Entry`s each replica committed for each `Id` (its watermark)
+pub(in crate::soak) struct Audit {
+    watermarks: Vec<HashMap<Id, u64>>,
+}
+
+impl Audit {
+    pub fn new(replicas: usize) -> Self {
+        Audit {
+            watermarks: vec![HashMap::new(); replicas],
+        }
+    }
+
+    pub async fn settle(
+        &mut self,
+        processors: &[Processor],
+        workload: &Workload,
+    ) -> Result<(), String> {
+        let deadline = Instant::now() + SETTLE_TIMEOUT;
+
+        loop {
+            match self.check(processors, workload) {
+                Ok(()) => return Ok(()),
+                Err(violation) if Instant::now() >= deadline => return Err(violation),
+                Err(_) => time::sleep(Duration::from_millis(100)).await,
+            }
+        }
+    }
+
+    // Checks that every replica committed every `Entry` of the workload's accounts
+    // (no fewer than at the previous audit), agreeing with every other replica
+    // on their `Prepare`s, and holds the expected balances (which sum up to
+    // the funds minted, closed accounts included)
+    fn check(&mut self, processors: &[Processor], workload: &Workload) -> Result<(), String> {
+        let mut commitments = HashMap::new();
+
+        for (replica, processor) in processors.iter().enumerate() {
+            let watermarks = &mut self.watermarks[replica];
+
+            for member in workload.members() {
+                let (id, height) = (member.id, member.height);
+
+                let query = HistoryQuery::new(id, 1..(height + 1), height as usize);
+
+                let page = processor.history(&query).map_err(|error| {
+                    format!("Replica {}: history of {} failed: {:?}", replica, id, error)
+                })?;
+
+                let committed = page.entries.len() as u64;
+                let watermark = watermarks.entry(id).or_insert(0);
+
+                if committed < *watermark {
+                    return Err(format!(
+                        "Replica {}: watermark of {} decreased from {} to {}",
+                        replica, id, watermark, committed
+                    ));
+                }
+
+                *watermark = committed;
+
+                if committed < height {
+                    return Err(format!(
+                        "Replica {}: {} of {} `Entry`s of {} committed",
+                        replica, committed, height, id
+                    ));
+                }
+
+                for entry in page.entries {
+                    let commitment = entry.payload.prepare().commitment();
+
+                    if *commitments
+                        .entry(entry.payload.entry())
+                        .or_insert(commitment)
+                        != commitment
+                    {
+                        return Err(format!(
+                            "Replica {}: equivocation at {:?}",
+                            replica,
+                            entry.payload.entry()
+                        ));
+                    }
+                }
+            }
+
+            let balances = processor
+                .balances(workload.ids())
+                .ok_or_else(|| format!("Replica {}: shut down", replica))?;
+
+            let total = balances.iter().flatten().sum::<u64>();
+
+            if total != workload.minted() {
+                return Err(format!(
+                    "Replica {}: balances sum up to {} (conservation violated)",
+                    replica, total
+                ));
+            }
+
+            let expected = workload
+                .members()
+                .iter()
+                .map(|member| Some(member.balance))
+                .collect::<Vec<_>>();
+
+            if balances != expected {
+                return Err(format!(
+                    "Replica {}: balances {:?} differ from {:?}",
+                    replica, balances, expected
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+mod audit;
+mod report;
+mod soak;
+mod workload;
+
+use audit::Audit;
+use report::Report;
+use workload::{Deployment, Workload};
@@ -0,0 +1,53 @@
+use crate::{processing::Processor, soak::Workload};
+
+use std::{fs, path::PathBuf};
+
+// Diagnostics of a soak run, written to `path` upon violation
+pub(in crate::soak) struct Report {
+    pub path: PathBuf,
+    pub seed: u64,
+}
+
+impl Report {
+    // Writes the seed, round and violation, the operations of the round, and
+    // every replica's balances to `self.path`, then panics
+    pub fn fail(
+        &self,
+        round: u64,
+        processors: &[Processor],
+        workload: &Workload,
+        violation: String,
+    ) -> ! {
+        let ids = workload.ids();
+
+        let mut diagnostics = format!(
+            "Seed: {}\nRound: {}\nViolation: {}\nIds: {:?}\n",
+            self.seed, round, violation, ids
+        );
+
+        for operation in workload.log() {
+            diagnostics += &format!("Operation: {}\n", operation);
+        }
+
+        for member in workload.members() {
+            diagnostics += &format!(
+                "Expected {}: height {}, balance {}, closed {}\n",
+                member.id, member.height, member.balance, member.closed
+            );
+        }
+
+        for (replica, processor) in processors.iter().enumerate() {
+            diagnostics += &format!(
+                "Replica {} balances: {:?}\n",
+                replica,
+                processor.balances(ids.clone())
+            );
+        }
+
+        fs::write(&self.path, &diagnostics).unwrap();
+        panic!(
+            "Soak violation (report at {:?}):\n{}",
+            self.path, diagnostics
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    account::AccountSettings,
+    brokers::test::System,
+    soak::{Audit, Deployment, Report, Workload},
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use std::{
+    env,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+const INITIAL_CLIENTS: usize = 8;
+const INITIAL_BALANCE: u64 = 1000;
+
+// Runs a mixed workload (see `Workload::round`) on an in-process deployment for
+// `CARBON_SOAK_SECONDS` seconds. Every `CARBON_SOAK_CHECKPOINT` rounds, each
+// replica's history and balances are audited (see `Audit`). On violation, a
+// report is written to `CARBON_SOAK_REPORT`. The seed (`CARBON_SOAK_SEED`) fixes
+// the workload, but not the interleaving of the network.
+#[tokio::test]
+#[ignore]
+async fn soak() {
+    let duration = Duration::from_secs(variable("CARBON_SOAK_SECONDS", 3600));
+    let checkpoint = variable("CARBON_SOAK_CHECKPOINT", 10);
+    let seed = variable("CARBON_SOAK_SEED", rand::random());
+
+    let report = Report {
+        path: variable(
+            "CARBON_SOAK_REPORT",
+            env::temp_dir().join(format!("carbon-soak-{}.report", seed)),
+        ),
+        seed,
+    };
+
+    let System {
+        view,
+        discovery_server,
+        discovery_client: _discovery_client,
+        processors,
+        signup_brokers,
+        prepare_brokers,
+        commit_brokers,
+    } = System::setup_with_account_settings(
+        7,
+        1,
+        2,
+        2,
+        AccountSettings {
+            initial_balance: INITIAL_BALANCE,
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let processors = processors
+        .into_iter()
+        .map(|(_, processor)| processor)
+        .collect::<Vec<_>>();
+
+    let deployment = Deployment {
+        view,
+        discovery: discovery_server.address(),
+        signup: signup_brokers[0].address(),
+        prepare: prepare_brokers
+            .iter()
+            .map(|broker| broker.address())
+            .collect(),
+        commit: commit_brokers
+            .iter()
+            .map(|broker| broker.address())
+            .collect(),
+    };
+
+    let mut workload = Workload::new(StdRng::seed_from_u64(seed), INITIAL_BALANCE);
+    let mut audit = Audit::new(processors.len());
+
+    for _ in 0..INITIAL_CLIENTS {
+        if let Err(error) = workload.signup(&deployment).await {
+            report.fail(0, &processors, &workload, error);
+        }
+    }
+
+    let start = Instant::now();
+    let mut round = 0;
+
+    while start.elapsed() < duration {
+        if let Err(error) = workload.round(&deployment).await {
+            report.fail(round, &processors, &workload, error);
+        }
+
+        round += 1;
+
+        if round % checkpoint == 0 {
+            if let Err(violation) = audit.settle(&processors, &workload).await {
+                report.fail(round, &processors, &workload, violation);
+            }
+        }
+    }
+}
+
+fn variable<T>(name: &str, default: T) -> T
+where
+    T: FromStr,
+{
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
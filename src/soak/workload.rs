@@ -0,0 +1,277 @@
+use crate::{
+    account::{Entry, Id, Operation},
+    client::{BrokerAddresses, Client, Completion},
+    commit::Payload,
+    view::View,
+};
+
+use futures::future;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use std::net::SocketAddr;
+
+use talk::crypto::KeyChain;
+
+// Clients are signed up over time (up to `MAX_CLIENTS`): at each round, a new
+// client signs up with probability `SIGNUP_PROBABILITY`, and an open account
+// is closed with probability `CLOSE_PROBABILITY`
+const MAX_CLIENTS: usize = 32;
+const SIGNUP_PROBABILITY: f64 = 0.2;
+const CLOSE_PROBABILITY: f64 = 0.05;
+
+// Addresses of an in-process deployment, through which `Client`s operate
+pub(in crate::soak) struct Deployment {
+    pub view: View,
+    pub discovery: SocketAddr,
+    pub signup: SocketAddr,
+    pub prepare: Vec<SocketAddr>,
+    pub commit: Vec<SocketAddr>,
+}
+
+// A `Workload` drives signed-up `Client`s in rounds, tracking the state
+// each of their accounts is expected to reach
+pub(in crate::soak) struct Workload {
+    rng: StdRng,
+    initial_balance: u64,
+    members: Vec<Member>,
+    // Operations of the ongoing round, in order (see `Report`)
+    log: Vec<String>,
+}
+
+pub(in crate::soak) struct Member {
+    client: Client,
+    pub id: Id,
+    // Height of the last `Entry` committed by `client`
+    pub height: u64,
+    // Slot in which `id` is credited next (one per collected deposit)
+    pub slot: u64,
+    pub balance: u64,
+    pub closed: bool,
+}
+
+impl Deployment {
+    // Brokers are assigned to the `index`-th client round-robin
+    pub fn client(&self, index: usize) -> Client {
+        let brokers = BrokerAddresses {
+            signup: self.signup,
+            prepare: self.prepare[index % self.prepare.len()],
+            prepare_standby: None,
+            commit: self.commit[index % self.commit.len()],
+        };
+
+        Client::new(
+            KeyChain::random(),
+            self.view.clone(),
+            self.discovery,
+            brokers,
+            Default::default(),
+        )
+    }
+}
+
+impl Workload {
+    pub fn new(rng: StdRng, initial_balance: u64) -> Self {
+        Workload {
+            rng,
+            initial_balance,
+            members: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub fn members(&self) -> &[Member] {
+        self.members.as_slice()
+    }
+
+    pub fn ids(&self) -> Vec<Id> {
+        self.members.iter().map(|member| member.id).collect()
+    }
+
+    // Every account signed up is minted `initial_balance`
+    pub fn minted(&self) -> u64 {
+        self.initial_balance * (self.members.len() as u64)
+    }
+
+    pub fn log(&self) -> &[String] {
+        self.log.as_slice()
+    }
+
+    pub async fn signup(&mut self, deployment: &Deployment) -> Result<(), String> {
+        let mut client = deployment.client(self.members.len());
+
+        let id = client
+            .signup()
+            .await
+            .map_err(|error| format!("Signup failed: {:?}", error))?;
+
+        self.log.push(format!("Signup {}", id));
+
+        self.members.push(Member {
+            client,
+            id,
+            height: 0,
+            slot: 0,
+            balance: self.initial_balance,
+            closed: false,
+        });
+
+        Ok(())
+    }
+
+    // A round signs up a new client (see `SIGNUP_PROBABILITY`), transfers funds
+    // along a random ring of open accounts, then closes an open account (see
+    // `CLOSE_PROBABILITY`). No transfer is in flight across rounds: an account
+    // is never closed with funds yet to be deposited.
+    pub async fn round(&mut self, deployment: &Deployment) -> Result<(), String> {
+        self.log.clear();
+
+        if self.members.len() < MAX_CLIENTS && self.rng.gen_bool(SIGNUP_PROBABILITY) {
+            self.signup(deployment).await?;
+        }
+
+        self.transfer().await?;
+
+        // At least two accounts are kept open, so that transfers go on
+        let open = self.open();
+
+        if open.len() > 2 && self.rng.gen_bool(CLOSE_PROBABILITY) {
+            let index = open[self.rng.gen_range(0..open.len())];
+            self.close(index).await?;
+        }
+
+        Ok(())
+    }
+
+    fn open(&self) -> Vec<usize> {
+        (0..self.members.len())
+            .filter(|index| !self.members[*index].closed)
+            .collect()
+    }
+
+    // Each member of a random ring of open accounts withdraws a random amount to the
+    // next (in the next's current slot), then deposits the withdrawal it received
+    async fn transfer(&mut self) -> Result<(), String> {
+        let mut ring = self.open();
+
+        if ring.len() < 2 {
+            return Ok(());
+        }
+
+        ring.shuffle(&mut self.rng);
+
+        let len = self.rng.gen_range(2..=ring.len());
+        ring.truncate(len);
+
+        let mut amounts = Vec::with_capacity(ring.len());
+
+        for index in ring.iter() {
+            amounts.push(self.rng.gen_range(0..=self.members[*index].balance));
+        }
+
+        let members = &self.members;
+
+        let withdrawals = future::join_all(ring.iter().enumerate().map(|(position, index)| {
+            let member = &members[*index];
+            let beneficiary = &members[ring[(position + 1) % ring.len()]];
+
+            let payload = Payload::new(
+                Entry {
+                    id: member.id,
+                    height: member.height + 1,
+                },
+                Operation::withdraw(beneficiary.id, beneficiary.slot, amounts[position]),
+            );
+
+            member.submit(payload, Vec::new())
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+        for (position, index) in ring.iter().enumerate() {
+            let beneficiary = ring[(position + 1) % ring.len()];
+
+            self.log.push(format!(
+                "Transfer {} -> {}: {}",
+                self.members[*index].id, self.members[beneficiary].id, amounts[position]
+            ));
+
+            self.members[*index].height += 1;
+            self.members[*index].balance -= amounts[position];
+        }
+
+        let members = &self.members;
+
+        future::join_all(ring.iter().enumerate().map(|(position, index)| {
+            let member = &members[*index];
+            let withdrawal = withdrawals[(position + ring.len() - 1) % ring.len()].clone();
+
+            let payload = Payload::new(
+                Entry {
+                    id: member.id,
+                    height: member.height + 1,
+                },
+                Operation::deposit(withdrawal.entry(), None, true),
+            );
+
+            member.submit(payload, vec![withdrawal])
+        }))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+
+        for (position, index) in ring.iter().enumerate() {
+            let member = &mut self.members[*index];
+
+            member.height += 1;
+            member.slot += 1;
+            member.balance += amounts[(position + ring.len() - 1) % ring.len()];
+        }
+
+        Ok(())
+    }
+
+    // A closed account retains its balance, but applies no further operation
+    async fn close(&mut self, index: usize) -> Result<(), String> {
+        let member = &mut self.members[index];
+
+        let payload = Payload::new(
+            Entry {
+                id: member.id,
+                height: member.height + 1,
+            },
+            Operation::close(),
+        );
+
+        member.submit(payload, Vec::new()).await?;
+
+        member.height += 1;
+        member.closed = true;
+
+        self.log.push(format!("Close {}", member.id));
+
+        Ok(())
+    }
+}
+
+impl Member {
+    async fn submit(
+        &self,
+        payload: Payload,
+        dependencies: Vec<Completion>,
+    ) -> Result<Completion, String> {
+        let entry = payload.entry();
+
+        let prepared = self
+            .client
+            .prepare(payload)
+            .await
+            .map_err(|error| format!("Prepare of {:?} failed: {:?}", entry, error))?;
+
+        self.client
+            .commit(prepared, dependencies)
+            .await
+            .map_err(|error| format!("Commit of {:?} failed: {:?}", entry, error))
+    }
+}
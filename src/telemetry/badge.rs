@@ -0,0 +1,114 @@
+use std::{fmt, future::Future, net::SocketAddr};
+
+use talk::crypto::Identity;
+
+tokio::task_local! {
+    static BADGE: Option<Badge>;
+}
+
+// A `Badge` tags log lines with the role and identity of the local subsystem
+// that emitted them, so that the logs of several nodes (or of several subsystems
+// running in the same process) can be told apart. Replicas are identified by a
+// short prefix of their `Identity`, brokers by the address they listen on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Badge {
+    role: Role,
+    tag: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    Replica,
+    SignupBroker,
+    PrepareBroker,
+    CommitBroker,
+}
+
+impl Badge {
+    pub fn replica(identity: Identity) -> Self {
+        // Four bytes are enough to tell apart the members of a `View` (the tail
+        // of the serialized `Identity` is used, skipping any length prefix)
+        let bytes = bincode::serialize(&identity).unwrap();
+
+        let tag = bytes[bytes.len().saturating_sub(4)..]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Badge {
+            role: Role::Replica,
+            tag,
+        }
+    }
+
+    pub fn broker(role: Role, address: SocketAddr) -> Self {
+        Badge {
+            role,
+            tag: address.to_string(),
+        }
+    }
+
+    // The `Badge` of the current task (if any)
+    pub fn current() -> Option<Badge> {
+        BADGE.try_with(Clone::clone).ok().flatten()
+    }
+
+    // Runs `future` (and, through `Badge::inherit`, the tasks it spawns) under `badge`
+    pub async fn scope<F>(badge: Option<Badge>, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        BADGE.scope(badge, future).await
+    }
+
+    // Task-local values do not propagate to spawned tasks: `inherit` carries the
+    // current `Badge` (if any) into a `future` about to be spawned
+    pub fn inherit<F>(future: F) -> impl Future<Output = F::Output>
+    where
+        F: Future,
+    {
+        Badge::scope(Badge::current(), future)
+    }
+}
+
+impl fmt::Display for Badge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let role = match self.role {
+            Role::Replica => "replica",
+            Role::SignupBroker => "signup-broker",
+            Role::PrepareBroker => "prepare-broker",
+            Role::CommitBroker => "commit-broker",
+        };
+
+        write!(f, "[{} {}]", role, self.tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    #[tokio::test]
+    async fn scope() {
+        let badge = Badge::broker(Role::PrepareBroker, (Ipv4Addr::LOCALHOST, 9000).into());
+        assert_eq!(badge.to_string(), "[prepare-broker 127.0.0.1:9000]");
+
+        assert_eq!(Badge::current(), None);
+
+        let inner = Badge::scope(Some(badge.clone()), async {
+            let current = Badge::current();
+
+            // Spawned tasks inherit the `Badge` only through `Badge::inherit`
+            let inherited = tokio::spawn(Badge::inherit(async { Badge::current() }));
+            let orphan = tokio::spawn(async { Badge::current() });
+
+            (current, inherited.await.unwrap(), orphan.await.unwrap())
+        })
+        .await;
+
+        assert_eq!(inner, (Some(badge.clone()), Some(badge), None));
+        assert_eq!(Badge::current(), None);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::telemetry::Registry;
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{io, net::SocketAddr, time::Duration};
+
+use talk::sync::fuse::Fuse;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+// Requests are read up to the end of their headers: bodies are never expected
+const MAX_REQUEST_SIZE: usize = 8192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExporterSettings {
+    // If `Some`, the owner's `Registry` is served over HTTP at `address`
+    // (any path), for Prometheus to scrape
+    pub address: Option<SocketAddr>,
+}
+
+// An `Exporter` serves a `Registry` over plain HTTP, answering every request
+// with the `Registry`'s current rendering
+pub(crate) struct Exporter;
+
+#[derive(Doom)]
+pub(crate) enum ExporterError {
+    #[doom(description("Failed to bind exporter: {}", source))]
+    #[doom(wrap(bind_failed))]
+    BindFailed { source: io::Error },
+}
+
+#[derive(Doom)]
+enum ServeError {
+    #[doom(description("Connection error"))]
+    ConnectionError,
+    #[doom(description("Request timed out"))]
+    RequestTimeout,
+    #[doom(description("Request too large"))]
+    RequestTooLarge,
+}
+
+impl Exporter {
+    pub async fn run(registry: Registry, address: SocketAddr) -> Result<(), Top<ExporterError>> {
+        let listener = TcpListener::bind(address)
+            .await
+            .map_err(ExporterError::bind_failed)
+            .map_err(Doom::into_top)
+            .spot(here!())?;
+
+        let fuse = Fuse::new();
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            };
+
+            let registry = registry.clone();
+
+            fuse.spawn(async move {
+                let _ = Exporter::serve(registry, stream).await;
+            });
+        }
+    }
+
+    async fn serve(registry: Registry, mut stream: TcpStream) -> Result<(), Top<ServeError>> {
+        time::timeout(REQUEST_TIMEOUT, Exporter::receive(&mut stream))
+            .await
+            .pot(ServeError::RequestTimeout, here!())??;
+
+        let body = registry.render();
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+
+        stream
+            .write_all(response.as_bytes())
+            .await
+            .pot(ServeError::ConnectionError, here!())?;
+
+        stream
+            .shutdown()
+            .await
+            .pot(ServeError::ConnectionError, here!())
+    }
+
+    async fn receive(stream: &mut TcpStream) -> Result<(), Top<ServeError>> {
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream
+                .read(&mut buffer)
+                .await
+                .pot(ServeError::ConnectionError, here!())?;
+
+            if read == 0 {
+                return ServeError::ConnectionError.fail().spot(here!());
+            }
+
+            request.extend_from_slice(&buffer[..read]);
+
+            if request.len() > MAX_REQUEST_SIZE {
+                return ServeError::RequestTooLarge.fail().spot(here!());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    #[tokio::test]
+    async fn scrape() {
+        let registry = Registry::new();
+        registry
+            .counter("carbon_batches_total", "Batches served")
+            .add(42);
+
+        // Reserve a free port for the `Exporter`
+        let address = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let fuse = Fuse::new();
+        fuse.spawn(async move {
+            let _ = Exporter::run(registry, address).await;
+        });
+
+        let mut stream = loop {
+            match TcpStream::connect(address).await {
+                Ok(stream) => break stream,
+                Err(_) => time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("carbon_batches_total 42\n"));
+    }
+}
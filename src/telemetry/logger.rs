@@ -0,0 +1,50 @@
+use crate::telemetry::Badge;
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use std::io::{self, Write};
+
+static LOGGER: Logger = Logger;
+
+// Writes each log record to standard error, prefixed by the `Badge` of the
+// task that emitted it (if any)
+struct Logger;
+
+// Installs the `Badge`-prefixing logger: executables embedding Carbon should call
+// `init_logger` once at startup (before any `Processor` or broker is created).
+// Fails if another logger was already installed.
+pub fn init_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let badge = Badge::current()
+            .map(|badge| format!("{} ", badge))
+            .unwrap_or_default();
+
+        // Logging must never fail the caller
+        let _ = writeln!(
+            io::stderr(),
+            "{:<5} {}{}: {}",
+            record.level(),
+            badge,
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
@@ -0,0 +1,13 @@
+mod badge;
+mod exporter;
+mod logger;
+mod registry;
+mod span;
+mod trace_context;
+
+pub(crate) use badge::{Badge, Role};
+pub(crate) use exporter::{Exporter, ExporterError, ExporterSettings};
+pub use logger::init_logger;
+pub(crate) use registry::{Counter, Histogram, Registry};
+pub(crate) use span::Span;
+pub(crate) use trace_context::TraceContext;
@@ -0,0 +1,267 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+// Upper bounds (in seconds) of the buckets of every `Histogram`
+const BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10.,
+];
+
+// A `Registry` holds the instruments of a `Processor` or broker, by name, and
+// renders them in Prometheus' text exposition format (see `Exporter`).
+// Instruments are created on first use: `counter`, `gauge` and `histogram` return
+// the existing instrument if one is registered under the same name (a name
+// registered under a different kind of instrument is a bug, and panics).
+// All clones of a `Registry` share the same state.
+#[derive(Clone)]
+pub(crate) struct Registry {
+    families: Arc<Mutex<BTreeMap<&'static str, Family>>>,
+}
+
+struct Family {
+    help: &'static str,
+    instrument: Instrument,
+}
+
+#[derive(Clone)]
+enum Instrument {
+    Counter(Counter),
+    Gauge(Arc<dyn Fn() -> f64 + Send + Sync>),
+    Histogram(Histogram),
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Counter {
+    value: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Histogram {
+    state: Arc<Mutex<HistogramState>>,
+}
+
+#[derive(Debug)]
+struct HistogramState {
+    // Non-cumulative counts, one per element of `BUCKETS` (plus one for `+Inf`)
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry {
+            families: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn counter(&self, name: &'static str, help: &'static str) -> Counter {
+        let instrument = self.register(name, help, || Instrument::Counter(Counter::default()));
+
+        match instrument {
+            Instrument::Counter(counter) => counter,
+            _ => panic!("`{}` is not a counter", name),
+        }
+    }
+
+    // Registers a gauge whose value is read from `probe` at every rendering
+    // (a gauge already registered under `name` keeps its original `probe`)
+    pub fn gauge<P>(&self, name: &'static str, help: &'static str, probe: P)
+    where
+        P: 'static + Fn() -> f64 + Send + Sync,
+    {
+        let instrument = self.register(name, help, || Instrument::Gauge(Arc::new(probe)));
+
+        if !matches!(instrument, Instrument::Gauge(_)) {
+            panic!("`{}` is not a gauge", name);
+        }
+    }
+
+    pub fn histogram(&self, name: &'static str, help: &'static str) -> Histogram {
+        let instrument = self.register(name, help, || Instrument::Histogram(Histogram::new()));
+
+        match instrument {
+            Instrument::Histogram(histogram) => histogram,
+            _ => panic!("`{}` is not a histogram", name),
+        }
+    }
+
+    fn register<I>(&self, name: &'static str, help: &'static str, instrument: I) -> Instrument
+    where
+        I: FnOnce() -> Instrument,
+    {
+        self.families
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_insert_with(|| Family {
+                help,
+                instrument: instrument(),
+            })
+            .instrument
+            .clone()
+    }
+
+    pub fn render(&self) -> String {
+        // Gauges are probed outside of `families`' lock, as `probe`s might take locks of their own
+        let families = self
+            .families
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, family)| (*name, family.help, family.instrument.clone()))
+            .collect::<Vec<_>>();
+
+        let mut output = String::new();
+
+        for (name, help, instrument) in families {
+            let kind = match instrument {
+                Instrument::Counter(_) => "counter",
+                Instrument::Gauge(_) => "gauge",
+                Instrument::Histogram(_) => "histogram",
+            };
+
+            // Writing to a `String` never fails
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+
+            match instrument {
+                Instrument::Counter(counter) => {
+                    let _ = writeln!(output, "{} {}", name, counter.get());
+                }
+                Instrument::Gauge(probe) => {
+                    let _ = writeln!(output, "{} {}", name, probe());
+                }
+                Instrument::Histogram(histogram) => histogram.render(name, &mut output),
+            }
+        }
+
+        output
+    }
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let names = self
+            .families
+            .lock()
+            .unwrap()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+
+        f.debug_struct("Registry").field("names", &names).finish()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::new()
+    }
+}
+
+impl Counter {
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, amount: u64) {
+        self.value.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            state: Arc::new(Mutex::new(HistogramState {
+                counts: vec![0; BUCKETS.len() + 1],
+                sum: 0.,
+            })),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+
+        // `BUCKETS.len()` is the index of the `+Inf` bucket
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(BUCKETS.len());
+
+        let mut state = self.state.lock().unwrap();
+        state.counts[bucket] += 1;
+        state.sum += seconds;
+    }
+
+    fn render(&self, name: &str, output: &mut String) {
+        let state = self.state.lock().unwrap();
+        let mut cumulative = 0;
+
+        for (bucket, count) in state.counts.iter().enumerate() {
+            cumulative += count;
+
+            let bound = match BUCKETS.get(bucket) {
+                Some(bound) => bound.to_string(),
+                None => "+Inf".to_string(),
+            };
+
+            let _ = writeln!(output, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+
+        let _ = writeln!(output, "{}_sum {}", name, state.sum);
+        let _ = writeln!(output, "{}_count {}", name, cumulative);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let registry = Registry::new();
+
+        registry
+            .counter("carbon_batches_total", "Batches served")
+            .add(3);
+
+        registry
+            .counter("carbon_batches_total", "Batches served")
+            .increment();
+
+        registry.gauge("carbon_occupancy", "Sponge occupancy", || 7.);
+
+        let histogram = registry.histogram("carbon_session_seconds", "Session latency");
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(20));
+
+        let output = registry.render();
+
+        assert!(output.contains("# TYPE carbon_batches_total counter\ncarbon_batches_total 4\n"));
+        assert!(output.contains("# TYPE carbon_occupancy gauge\ncarbon_occupancy 7\n"));
+        assert!(output.contains("carbon_session_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(output.contains("carbon_session_seconds_bucket{le=\"0.025\"} 1\n"));
+        assert!(output.contains("carbon_session_seconds_bucket{le=\"10\"} 1\n"));
+        assert!(output.contains("carbon_session_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(output.contains("carbon_session_seconds_count 2\n"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched() {
+        let registry = Registry::new();
+
+        registry.counter("carbon_batches_total", "Batches served");
+        registry.histogram("carbon_batches_total", "Batches served");
+    }
+}
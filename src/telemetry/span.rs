@@ -0,0 +1,106 @@
+use crate::telemetry::TraceContext;
+
+#[cfg(feature = "telemetry")]
+use opentelemetry::{
+    global::{self, BoxedSpan},
+    trace::{
+        Link, Span as _, SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+        Tracer,
+    },
+    Context, KeyValue,
+};
+
+// A `Span` covers an operation of a trace (e.g., a broker assembling a batch,
+// or a replica serving a session), and ends when dropped. With the `telemetry`
+// feature, `Span`s are exported through the global OpenTelemetry tracer
+// (whose exporter is to be installed by the executable). Without the
+// `telemetry` feature, `Span`s only generate the `TraceContext`s to propagate.
+pub(crate) struct Span {
+    context: TraceContext,
+    #[cfg(feature = "telemetry")]
+    inner: BoxedSpan,
+}
+
+impl Span {
+    // Starts a new trace
+    pub fn root(name: &'static str) -> Self {
+        Span::start(name, TraceContext::root(), None, &[])
+    }
+
+    // Starts a span within `parent`'s trace (possibly started on another machine)
+    pub fn child(name: &'static str, parent: TraceContext) -> Self {
+        Span::start(name, parent.child(), Some(parent), &[])
+    }
+
+    // Starts a new trace, linked to the traces of `links` (e.g., a batch
+    // span, linked to the traces of the requests it aggregates)
+    pub fn linked(name: &'static str, links: &[TraceContext]) -> Self {
+        Span::start(name, TraceContext::root(), None, links)
+    }
+
+    pub fn context(&self) -> TraceContext {
+        self.context
+    }
+
+    #[allow(unused_variables)]
+    pub fn attribute(&mut self, key: &'static str, value: i64) {
+        #[cfg(feature = "telemetry")]
+        self.inner.set_attribute(KeyValue::new(key, value));
+    }
+
+    #[allow(unused_variables)]
+    pub fn event(&mut self, name: &'static str) {
+        #[cfg(feature = "telemetry")]
+        self.inner.add_event(name, Vec::new());
+    }
+
+    #[cfg(feature = "telemetry")]
+    fn start(
+        name: &'static str,
+        context: TraceContext,
+        parent: Option<TraceContext>,
+        links: &[TraceContext],
+    ) -> Self {
+        let tracer = global::tracer("carbon");
+
+        let otel_context = parent
+            .map(|parent| Context::new().with_remote_span_context(Span::span_context(&parent)))
+            .unwrap_or_else(Context::new);
+
+        let mut builder = tracer.span_builder(name);
+        builder.trace_id = Some(TraceId::from_u128(context.trace_id()));
+        builder.span_id = Some(SpanId::from_u64(context.span_id()));
+
+        let builder = builder.with_links(
+            links
+                .iter()
+                .map(|link| Link::new(Span::span_context(link), Vec::new()))
+                .collect(),
+        );
+
+        let inner = builder.start_with_context(&tracer, &otel_context);
+
+        Span { context, inner }
+    }
+
+    #[cfg(not(feature = "telemetry"))]
+    fn start(
+        _name: &'static str,
+        context: TraceContext,
+        _parent: Option<TraceContext>,
+        _links: &[TraceContext],
+    ) -> Self {
+        Span { context }
+    }
+
+    #[cfg(feature = "telemetry")]
+    fn span_context(context: &TraceContext) -> SpanContext {
+        SpanContext::new(
+            TraceId::from_u128(context.trace_id()),
+            SpanId::from_u64(context.span_id()),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        )
+    }
+}
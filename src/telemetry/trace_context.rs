@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+// A `TraceContext` identifies a span within a trace, and travels on the wire
+// so that spans on different machines can be correlated (ids follow the W3C
+// Trace Context format: 128-bit trace ids and 64-bit span ids, never zero)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+}
+
+impl TraceContext {
+    pub fn root() -> Self {
+        TraceContext {
+            trace_id: rand::random::<u128>().max(1),
+            span_id: rand::random::<u64>().max(1),
+        }
+    }
+
+    pub fn child(&self) -> Self {
+        TraceContext {
+            trace_id: self.trace_id,
+            span_id: rand::random::<u64>().max(1),
+        }
+    }
+
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child() {
+        let root = TraceContext::root();
+        let child = root.child();
+
+        assert_eq!(child.trace_id(), root.trace_id());
+        assert_ne!(child.span_id(), root.span_id());
+
+        assert_ne!(TraceContext::root().trace_id(), root.trace_id());
+    }
+}
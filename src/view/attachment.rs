@@ -0,0 +1,87 @@
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use std::hash::Hash as StdHash;
+
+const MAX_KEY_LENGTH: usize = 64;
+const MAX_VALUE_LENGTH: usize = 4096;
+
+// An `Attachment` sets the value of a key in a `View`'s metadata (e.g., a policy
+// parameter), and is installed like any other `Change`. Among all `Attachment`s
+// installed for the same key, the one with highest `version` is in effect (ties
+// between concurrent `Attachment`s are broken deterministically by `value`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, StdHash, Serialize, Deserialize)]
+pub struct Attachment {
+    key: String,
+    version: u64,
+    value: Vec<u8>,
+}
+
+#[derive(Doom)]
+pub enum AttachmentError {
+    #[doom(description("Empty key"))]
+    EmptyKey,
+    #[doom(description("Key too long"))]
+    KeyTooLong,
+    #[doom(description("Value too long"))]
+    ValueTooLong,
+    #[doom(description("Failed to serialize value"))]
+    SerializeFailed,
+    #[doom(description("Failed to deserialize value"))]
+    DeserializeFailed,
+}
+
+impl Attachment {
+    pub fn new<K, T>(key: K, version: u64, value: &T) -> Result<Self, Top<AttachmentError>>
+    where
+        K: Into<String>,
+        T: Serialize,
+    {
+        let attachment = Attachment {
+            key: key.into(),
+            version,
+            value: bincode::serialize(value).pot(AttachmentError::SerializeFailed, here!())?,
+        };
+
+        attachment.validate()?;
+
+        Ok(attachment)
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn value<T>(&self) -> Result<T, Top<AttachmentError>>
+    where
+        T: DeserializeOwned,
+    {
+        bincode::deserialize(self.value.as_slice()).pot(AttachmentError::DeserializeFailed, here!())
+    }
+
+    pub fn validate(&self) -> Result<(), Top<AttachmentError>> {
+        if self.key.is_empty() {
+            return AttachmentError::EmptyKey.fail().spot(here!());
+        }
+
+        if self.key.len() > MAX_KEY_LENGTH {
+            return AttachmentError::KeyTooLong.fail().spot(here!());
+        }
+
+        if self.value.len() > MAX_VALUE_LENGTH {
+            return AttachmentError::ValueTooLong.fail().spot(here!());
+        }
+
+        Ok(())
+    }
+
+    // `true` iff `self` is in effect over `other` (for the same key)
+    pub(in crate::view) fn supersedes(&self, other: &Attachment) -> bool {
+        (self.version, &self.value) > (other.version, &other.value)
+    }
+}
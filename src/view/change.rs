@@ -1,4 +1,4 @@
-use crate::crypto::Identify;
+use crate::{crypto::Identify, view::Attachment};
 
 use serde::{Deserialize, Serialize};
 
@@ -10,16 +10,19 @@ use talk::crypto::{
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, StdHash, Serialize, Deserialize)]
-pub(crate) enum Change {
+pub enum Change {
     Join(KeyCard),
     Leave(KeyCard), // TODO: Refactor to `Leave(Identity)`
+    Attach(Attachment),
 }
 
 impl Change {
-    pub fn keycard(&self) -> KeyCard {
+    // `None` if `self` does not pertain to a replica (i.e., `Change::Attach`)
+    pub fn keycard(&self) -> Option<KeyCard> {
         match self {
-            Change::Join(keycard) => keycard.clone(),
-            Change::Leave(keycard) => keycard.clone(),
+            Change::Join(keycard) => Some(keycard.clone()),
+            Change::Leave(keycard) => Some(keycard.clone()),
+            Change::Attach(_) => None,
         }
     }
 }
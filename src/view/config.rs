@@ -0,0 +1,157 @@
+use crate::view::View;
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use std::{collections::BTreeSet, fmt::Write, fs, path::Path};
+
+use talk::crypto::KeyCard;
+
+// Minimum number of members for Byzantine resilience (see `View::genesis`)
+const MIN_MEMBERS: usize = 4;
+
+#[derive(Doom)]
+pub enum ViewConfigError {
+    #[doom(description("Failed to read configuration file"))]
+    ReadFailed,
+    #[doom(description("Line {}: malformed hex encoding", line))]
+    MalformedHex { line: usize },
+    #[doom(description("Line {}: malformed `KeyCard`", line))]
+    MalformedKeyCard { line: usize },
+    #[doom(description("Line {}: duplicate member", line))]
+    DuplicateMember { line: usize },
+    #[doom(description("Too few members ({}) for Byzantine resilience", members))]
+    TooFewMembers { members: usize },
+}
+
+impl View {
+    // Loads a genesis `View` from a configuration file listing one hex-encoded
+    // (`bincode`-serialized) `KeyCard` per line. Blank lines and lines starting
+    // with `#` are ignored. Members are ordered by `Identity` by `View::genesis`,
+    // so the order of the file does not affect the resulting `View`.
+    pub fn from_config<P>(path: P) -> Result<View, Top<ViewConfigError>>
+    where
+        P: AsRef<Path>,
+    {
+        let config = fs::read_to_string(path).pot(ViewConfigError::ReadFailed, here!())?;
+        View::parse_config(config.as_str())
+    }
+
+    pub fn parse_config(config: &str) -> Result<View, Top<ViewConfigError>> {
+        let mut identities = BTreeSet::new();
+        let mut members = Vec::new();
+
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            let number = index + 1;
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let bytes = decode_hex(line)
+                .ok_or(ViewConfigError::MalformedHex { line: number }.into_top())
+                .spot(here!())?;
+
+            let keycard = bincode::deserialize::<KeyCard>(bytes.as_slice())
+                .pot(ViewConfigError::MalformedKeyCard { line: number }, here!())?;
+
+            if !identities.insert(keycard.identity()) {
+                return ViewConfigError::DuplicateMember { line: number }
+                    .fail()
+                    .spot(here!());
+            }
+
+            members.push(keycard);
+        }
+
+        if members.len() < MIN_MEMBERS {
+            return ViewConfigError::TooFewMembers {
+                members: members.len(),
+            }
+            .fail()
+            .spot(here!());
+        }
+
+        Ok(View::genesis(members))
+    }
+
+    // Renders `self`'s members in the format read by `View::from_config`
+    pub fn to_config(&self) -> String {
+        let mut config = String::new();
+
+        for keycard in self.members().values() {
+            for byte in bincode::serialize(keycard).unwrap() {
+                write!(config, "{:02x}", byte).unwrap();
+            }
+
+            config.push('\n');
+        }
+
+        config
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|offset| u8::from_str_radix(&hex[offset..offset + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::crypto::Identify;
+
+    use std::iter;
+
+    use talk::crypto::KeyChain;
+
+    fn config(members: usize) -> String {
+        let keycards = iter::repeat_with(|| KeyChain::random().keycard())
+            .take(members)
+            .collect::<Vec<_>>();
+
+        View::genesis(keycards).to_config()
+    }
+
+    #[test]
+    fn round_trip() {
+        let config = config(4);
+
+        let view = View::parse_config(config.as_str()).unwrap();
+        assert_eq!(view.to_config(), config);
+
+        // Comments, blank lines and member order are irrelevant
+
+        let mut lines = config.lines().collect::<Vec<_>>();
+        lines.reverse();
+
+        let shuffled = format!("# Genesis members\n\n{}\n", lines.join("\n"));
+        let shuffled = View::parse_config(shuffled.as_str()).unwrap();
+
+        assert_eq!(shuffled.identifier(), view.identifier());
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(View::parse_config("0g").is_err());
+        assert!(View::parse_config("abc").is_err());
+        assert!(View::parse_config("abcd").is_err());
+
+        let config = config(4);
+
+        let truncated = config.lines().skip(1).collect::<Vec<_>>().join("\n");
+        assert!(View::parse_config(truncated.as_str()).is_err());
+
+        let first = config.lines().next().unwrap();
+        let duplicated = format!("{}{}\n", config, first);
+
+        assert!(View::parse_config(duplicated.as_str()).is_err());
+    }
+}
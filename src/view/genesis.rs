@@ -0,0 +1,207 @@
+use crate::{
+    crypto::{Aggregator, Certificate, Header, Identify},
+    view::View,
+};
+
+use doomstack::{here, Doom, ResultExt, Top};
+
+use serde::{Deserialize, Serialize};
+
+use talk::crypto::{
+    primitives::{hash::Hash, multi::Signature as MultiSignature},
+    KeyCard, KeyChain, Statement as CryptoStatement,
+};
+
+// A `Genesis` document certifies, by unanimous signature of its members,
+// the genesis `View` of a system. Replicas and clients bootstrap from the same
+// `Genesis` document (which replaces any out-of-band agreement on `View::genesis`'s
+// members) and `validate` it before trusting the `View` it describes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Genesis {
+    members: Vec<KeyCard>,
+    certificate: Certificate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Statement {
+    view: Hash,
+}
+
+// `GenesisCeremony` coordinates the signing of a `Genesis` document: it is
+// initialized with the `KeyCard`s of all genesis members, then collects
+// one signature (see `Genesis::certify`) from each of them.
+pub(crate) struct GenesisCeremony {
+    members: Vec<KeyCard>,
+    aggregator: Aggregator<Statement>,
+}
+
+#[derive(Doom)]
+pub(crate) enum GenesisError {
+    #[doom(description("Certificate invalid"))]
+    CertificateInvalid,
+}
+
+#[derive(Doom)]
+pub(crate) enum GenesisCeremonyError {
+    #[doom(description("Foreign member"))]
+    ForeignMember,
+    #[doom(description("Signature invalid"))]
+    SignatureInvalid,
+    #[doom(description("Ceremony incomplete"))]
+    CeremonyIncomplete,
+}
+
+impl Genesis {
+    pub fn certify(keychain: &KeyChain, view: &View) -> MultiSignature {
+        let statement = Statement {
+            view: view.identifier(),
+        };
+
+        keychain
+            .multisign(&statement)
+            .expect("Panic at `Genesis::certify`: unexpected error from `keychain.multisign`")
+    }
+
+    pub fn members(&self) -> &[KeyCard] {
+        self.members.as_slice()
+    }
+
+    pub fn view(&self) -> View {
+        View::genesis(self.members.iter().cloned())
+    }
+
+    pub fn validate(&self) -> Result<View, Top<GenesisError>> {
+        let view = self.view();
+
+        let statement = Statement {
+            view: view.identifier(),
+        };
+
+        // A `Genesis` document must be signed by all members
+        self.certificate
+            .verify_threshold(&view, &statement, view.total_weight())
+            .pot(GenesisError::CertificateInvalid, here!())?;
+
+        Ok(view)
+    }
+}
+
+impl GenesisCeremony {
+    pub fn new<M>(members: M) -> Self
+    where
+        M: IntoIterator<Item = KeyCard>,
+    {
+        let members = members.into_iter().collect::<Vec<_>>();
+        let view = View::genesis(members.iter().cloned());
+
+        let statement = Statement {
+            view: view.identifier(),
+        };
+
+        let aggregator = Aggregator::new(view, statement);
+
+        GenesisCeremony {
+            members,
+            aggregator,
+        }
+    }
+
+    pub fn view(&self) -> &View {
+        self.aggregator.view()
+    }
+
+    pub fn add(
+        &mut self,
+        keycard: &KeyCard,
+        signature: MultiSignature,
+    ) -> Result<(), Top<GenesisCeremonyError>> {
+        if !self.view().members().contains_key(&keycard.identity()) {
+            return GenesisCeremonyError::ForeignMember.fail().spot(here!());
+        }
+
+        self.aggregator
+            .add(keycard, signature)
+            .pot(GenesisCeremonyError::SignatureInvalid, here!())
+    }
+
+    pub fn complete(&self) -> bool {
+        self.aggregator.multiplicity() == self.view().members().len()
+    }
+
+    pub fn finalize(self) -> Result<Genesis, Top<GenesisCeremonyError>> {
+        if !self.complete() {
+            return GenesisCeremonyError::CeremonyIncomplete
+                .fail()
+                .spot(here!());
+        }
+
+        let (_, certificate) = self.aggregator.finalize();
+
+        Ok(Genesis {
+            members: self.members,
+            certificate,
+        })
+    }
+}
+
+impl CryptoStatement for Statement {
+    type Header = Header;
+    const HEADER: Header = Header::Genesis;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::iter;
+
+    fn setup(size: usize) -> (Vec<KeyChain>, GenesisCeremony) {
+        let keychains = iter::repeat_with(KeyChain::random)
+            .take(size)
+            .collect::<Vec<_>>();
+
+        let ceremony = GenesisCeremony::new(keychains.iter().map(KeyChain::keycard));
+
+        (keychains, ceremony)
+    }
+
+    #[test]
+    fn complete() {
+        let (keychains, mut ceremony) = setup(8);
+
+        for keychain in keychains.iter() {
+            let signature = Genesis::certify(keychain, ceremony.view());
+            ceremony.add(&keychain.keycard(), signature).unwrap();
+        }
+
+        let view = ceremony.view().clone();
+        let genesis = ceremony.finalize().unwrap();
+
+        assert_eq!(genesis.validate().unwrap().identifier(), view.identifier());
+    }
+
+    #[test]
+    fn incomplete() {
+        let (keychains, mut ceremony) = setup(8);
+
+        for keychain in keychains.iter().skip(1) {
+            let signature = Genesis::certify(keychain, ceremony.view());
+            ceremony.add(&keychain.keycard(), signature).unwrap();
+        }
+
+        assert!(ceremony.finalize().is_err());
+    }
+
+    #[test]
+    fn foreign() {
+        let (keychains, mut ceremony) = setup(8);
+
+        let foreign = KeyChain::random();
+        let signature = Genesis::certify(&foreign, ceremony.view());
+
+        assert!(ceremony.add(&foreign.keycard(), signature).is_err());
+
+        let signature = Genesis::certify(&foreign, ceremony.view());
+        assert!(ceremony.add(&keychains[0].keycard(), signature).is_err());
+    }
+}
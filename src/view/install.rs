@@ -1,6 +1,6 @@
 use crate::{
     crypto::{Aggregator, Certificate, Header, Identify},
-    view::{Increment, Transition, View},
+    view::{Change, Increment, Transition, View},
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
@@ -17,7 +17,7 @@ use talk::crypto::{
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(remote = "Self")]
-pub(crate) struct Install {
+pub struct Install {
     statement: Statement,
     certificate: Certificate,
 }
@@ -36,6 +36,8 @@ pub(crate) enum InstallError {
     SourceUnknown,
     #[doom(description("Certificate invalid"))]
     CertificateInvalid,
+    #[doom(description("Attachment invalid"))]
+    AttachmentInvalid,
 }
 
 impl Install {
@@ -76,6 +78,16 @@ impl Install {
             .verify_plurality(&source, &self.statement)
             .pot(InstallError::CertificateInvalid, here!())?;
 
+        // Subsystems read policy from the metadata of installed `View`s:
+        // malformed `Attachment`s are rejected before ever being installed
+        for change in self.statement.increments.iter().flatten() {
+            if let Change::Attach(attachment) = change {
+                attachment
+                    .validate()
+                    .pot(InstallError::AttachmentInvalid, here!())?;
+            }
+        }
+
         #[cfg(debug_assertions)]
         {
             if self.statement.increments.len() == 0 {
@@ -112,6 +124,10 @@ impl InstallAggregator {
         self.0.multiplicity()
     }
 
+    pub fn power(&self) -> usize {
+        self.0.power()
+    }
+
     pub fn finalize(self) -> Install {
         let (statement, certificate) = self.0.finalize_plurality();
 
@@ -159,7 +175,7 @@ impl CryptoStatement for Statement {
     const HEADER: Header = Header::Install;
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-support"))]
 mod tests {
     use super::*;
 
@@ -1,4 +1,7 @@
+mod attachment;
 mod change;
+mod config;
+mod genesis;
 mod increment;
 mod install;
 mod store;
@@ -7,15 +10,22 @@ mod view;
 
 use store::{FAMILY, VIEWS};
 
-#[cfg(test)]
-pub(crate) mod test;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test;
 
-pub(crate) use change::Change;
-pub(crate) use increment::Increment;
-pub(crate) use install::Install;
+pub use attachment::Attachment;
+#[allow(unused_imports)]
+pub use attachment::AttachmentError;
+pub use change::Change;
+#[allow(unused_imports)]
+pub use config::ViewConfigError;
+#[allow(unused_imports)]
+pub(crate) use genesis::{Genesis, GenesisCeremony};
+pub use increment::Increment;
+pub use install::Install;
 #[allow(unused_imports)]
 pub(crate) use install::InstallAggregator;
-pub(crate) use transition::Transition;
-pub(crate) use view::View;
+pub use transition::Transition;
+pub use view::View;
 #[allow(unused_imports)]
-pub(crate) use view::ViewError;
+pub use view::ViewError;
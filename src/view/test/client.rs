@@ -3,20 +3,20 @@ use crate::{
     view::{Install, View},
 };
 
-pub(crate) struct Client {
+pub struct Client {
     current: View,
     last_installable: View,
 }
 
 impl Client {
-    pub(crate) fn new(current: View, last_installable: View) -> Self {
+    pub fn new(current: View, last_installable: View) -> Self {
         Self {
             current,          // The client's current view
             last_installable, // Only installable views *that the remote has knowledge about*
         }
     }
 
-    pub(crate) fn update(&mut self, installs: Vec<Install>) {
+    pub fn update(&mut self, installs: Vec<Install>) {
         let mut current = self.last_installable.clone();
 
         for install in installs {
@@ -37,11 +37,11 @@ impl Client {
         }
     }
 
-    pub(crate) fn current(&self) -> &View {
+    pub fn current(&self) -> &View {
         &self.current
     }
 
-    pub(crate) fn last_installable(&self) -> &View {
+    pub fn last_installable(&self) -> &View {
         &self.last_installable
     }
 }
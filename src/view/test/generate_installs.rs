@@ -1,6 +1,6 @@
 use rand::seq::IteratorRandom;
 
-pub(crate) fn generate_installs(
+pub fn generate_installs(
     genesis_height: usize,
     max_height: usize,
     unskippable_count: usize,
@@ -70,11 +70,7 @@ pub(crate) fn generate_installs(
     installs
 }
 
-pub(crate) fn last_installable<I>(
-    genesis_height: usize,
-    max_height: usize,
-    tailless: I,
-) -> Vec<usize>
+pub fn last_installable<I>(genesis_height: usize, max_height: usize, tailless: I) -> Vec<usize>
 where
     I: IntoIterator<Item = usize>,
 {
@@ -2,14 +2,19 @@ use crate::view::{Change, Increment, Install, InstallAggregator, View};
 
 use talk::crypto::{KeyCard, KeyChain};
 
-pub(crate) struct InstallGenerator {
+pub struct InstallGenerator {
     pub keychains: Vec<KeyChain>,
     pub keycards: Vec<KeyCard>,
 }
 
 impl InstallGenerator {
     pub fn new(views: usize) -> InstallGenerator {
-        let keychains = (0..views).map(|_| KeyChain::random()).collect::<Vec<_>>();
+        InstallGenerator::from_keychains((0..views).map(|_| KeyChain::random()).collect())
+    }
+
+    // Like `new`, with one view per `KeyChain` in `keychains` (e.g., to generate
+    // deterministic `Install`s, see `data::golden::keychains`)
+    pub fn from_keychains(keychains: Vec<KeyChain>) -> InstallGenerator {
         let keycards = keychains.iter().map(KeyChain::keycard).collect::<Vec<_>>();
 
         InstallGenerator {
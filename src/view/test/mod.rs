@@ -2,6 +2,6 @@ mod client;
 mod generate_installs;
 mod install_generator;
 
-pub(crate) use client::Client;
-pub(crate) use generate_installs::{generate_installs, last_installable};
-pub(crate) use install_generator::InstallGenerator;
+pub use client::Client;
+pub use generate_installs::{generate_installs, last_installable};
+pub use install_generator::InstallGenerator;
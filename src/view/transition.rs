@@ -3,7 +3,7 @@ use crate::view::{Increment, View};
 use talk::crypto::primitives::hash::Hash;
 
 #[derive(Clone)]
-pub(crate) struct Transition {
+pub struct Transition {
     source: View,
     destination: View,
     tail: Vec<View>,
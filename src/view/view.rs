@@ -1,6 +1,6 @@
 use crate::{
     crypto::Identify,
-    view::{Change, Increment, FAMILY, VIEWS},
+    view::{Attachment, Change, Increment, FAMILY, VIEWS},
 };
 
 use doomstack::{here, Doom, ResultExt, Top};
@@ -15,7 +15,7 @@ use talk::crypto::{primitives::hash::Hash, Identity, KeyCard};
 use zebra::database::{Collection, CollectionTransaction};
 
 #[derive(Clone)]
-pub(crate) struct View {
+pub struct View {
     data: Arc<Data>,
 }
 
@@ -23,19 +23,38 @@ struct Data {
     height: usize,
     changes: Collection<Change>,
     members: BTreeMap<Identity, KeyCard>,
+    // key -> `Attachment` in effect for key
+    metadata: BTreeMap<String, Attachment>,
+    // member -> voting weight of member (members not in `weights` weigh 1)
+    weights: BTreeMap<Identity, usize>,
+    total_weight: usize,
 }
 
 #[derive(Doom)]
-pub(crate) enum ViewError {
+pub enum ViewError {
     #[doom(description("Extension results in a member joining more than once"))]
     DoubleJoin,
     #[doom(description("Extension results in a member leaving before joining"))]
     UnmatchedLeave,
     #[doom(description("Extension results in a member leaving more than once"))]
     DoubleLeave,
+    #[doom(description("Extension results in an attachment being installed more than once"))]
+    DoubleAttach,
+    #[doom(description("Extension contains an invalid attachment"))]
+    AttachmentInvalid,
 }
 
 impl View {
+    // Key of the `Attachment` setting the members' voting weights. Its value
+    // is a `BTreeMap<Identity, u64>` of weights (each between 1 and `MAX_WEIGHT`),
+    // listing only members whose weight is other than 1.
+    pub const WEIGHTS: &'static str = "weights";
+
+    // Bounds on each member's weight and on the total weight of a `View`, so
+    // that no sum of weights can overflow (on any platform)
+    pub const MAX_WEIGHT: u64 = 1 << 16;
+    pub const MAX_TOTAL_WEIGHT: usize = 1 << 24;
+
     pub fn genesis<M>(members: M) -> Self
     where
         M: IntoIterator<Item = KeyCard>,
@@ -70,10 +89,15 @@ impl View {
 
         let identifier = changes.commit();
 
+        let total_weight = members.len();
+
         let data = Arc::new(Data {
             height,
             changes,
             members,
+            metadata: BTreeMap::new(),
+            weights: BTreeMap::new(),
+            total_weight,
         });
 
         let view = View { data };
@@ -107,6 +131,7 @@ impl View {
         let identifier = changes.commit();
 
         let mut members = self.data.members.clone();
+        let mut metadata = self.data.metadata.clone();
 
         for change in increment {
             match change {
@@ -116,13 +141,28 @@ impl View {
                 Change::Leave(replica) => {
                     members.remove(&replica.identity());
                 }
+                Change::Attach(attachment) => {
+                    // Attachments superseded by an attachment in effect have no effect
+                    let effective = metadata
+                        .get(attachment.key())
+                        .map_or(true, |current| attachment.supersedes(current));
+
+                    if effective {
+                        metadata.insert(attachment.key().to_string(), attachment);
+                    }
+                }
             }
         }
 
+        let (weights, total_weight) = View::decode_weights(&metadata, &members);
+
         let data = Arc::new(Data {
             height,
             changes,
             members,
+            metadata,
+            weights,
+            total_weight,
         });
 
         let view = View { data };
@@ -149,13 +189,81 @@ impl View {
         self.data.members.len() - (self.data.members.len() - 1) / 3
     }
 
+    // Voting weight of `member`, as set by the `View::WEIGHTS` attachment (by default, 1)
+    pub fn weight(&self, member: &Identity) -> usize {
+        self.data.weights.get(member).copied().unwrap_or(1)
+    }
+
+    pub fn total_weight(&self) -> usize {
+        self.data.total_weight
+    }
+
+    // Total weight of `members`
+    pub fn power<'m, M>(&self, members: M) -> usize
+    where
+        M: IntoIterator<Item = &'m Identity>,
+    {
+        // Weights are bounded (see `decode_weights`): `saturating_add` only guards
+        // against `members` listing the same member an unreasonable number of times
+        members.into_iter().fold(0usize, |power, member| {
+            power.saturating_add(self.weight(member))
+        })
+    }
+
+    // Length of the shortest prefix of `members` whose power reaches `threshold`
+    // (if none does, the length of `members`)
+    pub fn reach<'m, M>(&self, members: M, threshold: usize) -> usize
+    where
+        M: IntoIterator<Item = &'m Identity>,
+    {
+        let mut power = 0;
+        let mut length = 0;
+
+        for member in members {
+            if power >= threshold {
+                break;
+            }
+
+            power = power.saturating_add(self.weight(member));
+            length += 1;
+        }
+
+        length
+    }
+
+    // Weighted counterparts of `plurality` and `quorum`: the faulty members
+    // are assumed to hold less than a third of `total_weight`. If all members
+    // weigh 1, these coincide with `plurality` and `quorum`.
+
+    pub fn weighted_plurality(&self) -> usize {
+        (self.data.total_weight - 1) / 3 + 1
+    }
+
+    pub fn weighted_quorum(&self) -> usize {
+        self.data.total_weight - (self.data.total_weight - 1) / 3
+    }
+
     pub fn members(&self) -> &BTreeMap<Identity, KeyCard> {
         &self.data.members
     }
 
+    // Policy parameters agreed upon by `self`: key -> `Attachment` in effect
+    pub fn metadata(&self) -> &BTreeMap<String, Attachment> {
+        &self.data.metadata
+    }
+
+    pub fn attachment(&self, key: &str) -> Option<&Attachment> {
+        self.data.metadata.get(key)
+    }
+
     pub fn validate_extension(&self, change: &Change) -> Result<(), Top<ViewError>> {
-        let join = Change::Join(change.keycard());
-        let leave = Change::Leave(change.keycard());
+        let keycard = match change {
+            Change::Join(keycard) | Change::Leave(keycard) => keycard.clone(),
+            Change::Attach(attachment) => return self.validate_attachment(attachment),
+        };
+
+        let join = Change::Join(keycard.clone());
+        let leave = Change::Leave(keycard);
 
         let mut transaction = CollectionTransaction::new();
 
@@ -181,8 +289,96 @@ impl View {
                     Ok(())
                 }
             }
+            Change::Attach(_) => unreachable!(),
+        }
+    }
+
+    fn validate_attachment(&self, attachment: &Attachment) -> Result<(), Top<ViewError>> {
+        attachment
+            .validate()
+            .pot(ViewError::AttachmentInvalid, here!())?;
+
+        if attachment.key() == View::WEIGHTS {
+            let weights = attachment
+                .value::<BTreeMap<Identity, u64>>()
+                .pot(ViewError::AttachmentInvalid, here!())?;
+
+            if weights
+                .values()
+                .any(|weight| *weight == 0 || *weight > View::MAX_WEIGHT)
+            {
+                return ViewError::AttachmentInvalid.fail().spot(here!());
+            }
+
+            if View::weigh(&weights, self.data.members.keys()).is_none() {
+                return ViewError::AttachmentInvalid.fail().spot(here!());
+            }
+        }
+
+        let attach = Change::Attach(attachment.clone());
+
+        let mut transaction = CollectionTransaction::new();
+        let attach_query = transaction.contains(&attach).unwrap();
+
+        let response = self.data.changes.clone().execute(transaction);
+
+        if response.contains(&attach_query) {
+            ViewError::DoubleAttach.fail().spot(here!())
+        } else {
+            Ok(())
+        }
+    }
+
+    // Weights of `members` (as set by the `View::WEIGHTS` attachment in `metadata`),
+    // along with their total weight. The attachment is validated upon installation,
+    // but later joins can still push the total weight beyond `MAX_TOTAL_WEIGHT`: in
+    // that case (as for any out-of-bounds weight) every member weighs 1.
+    fn decode_weights(
+        metadata: &BTreeMap<String, Attachment>,
+        members: &BTreeMap<Identity, KeyCard>,
+    ) -> (BTreeMap<Identity, usize>, usize) {
+        let weights = metadata
+            .get(View::WEIGHTS)
+            .and_then(|attachment| attachment.value::<BTreeMap<Identity, u64>>().ok())
+            .unwrap_or_default();
+
+        let bounded = weights
+            .values()
+            .all(|weight| *weight >= 1 && *weight <= View::MAX_WEIGHT);
+
+        let total_weight = if bounded {
+            View::weigh(&weights, members.keys())
+        } else {
+            None
+        };
+
+        match total_weight {
+            Some(total_weight) => {
+                let weights = weights
+                    .into_iter()
+                    .map(|(member, weight)| (member, weight as usize))
+                    .collect();
+
+                (weights, total_weight)
+            }
+            None => (BTreeMap::new(), members.len()),
         }
     }
+
+    // Total weight of `members` under `weights` (each at most `MAX_WEIGHT`),
+    // or `None` if it exceeds `MAX_TOTAL_WEIGHT`
+    fn weigh<'m, M>(weights: &BTreeMap<Identity, u64>, members: M) -> Option<usize>
+    where
+        M: IntoIterator<Item = &'m Identity>,
+    {
+        members
+            .into_iter()
+            .try_fold(0usize, |total, member| {
+                let weight = weights.get(member).copied().unwrap_or(1);
+                total.checked_add(weight as usize)
+            })
+            .filter(|total| *total <= View::MAX_TOTAL_WEIGHT)
+    }
 }
 
 impl Identify for View {
@@ -299,6 +495,142 @@ mod tests {
         }
     }
 
+    #[test]
+    fn attachments() {
+        let view = View::genesis(random_keycards(4));
+        assert!(view.attachment("fee").is_none());
+
+        let first = Attachment::new("fee", 0, &10u64).unwrap();
+        let second = Attachment::new("fee", 1, &20u64).unwrap();
+
+        let view = view.extend(std::collections::BTreeSet::from([Change::Attach(
+            first.clone(),
+        )]));
+
+        assert_eq!(view.height(), 5);
+        assert_eq!(view.members().len(), 4);
+        assert_eq!(view.attachment("fee").unwrap().value::<u64>().unwrap(), 10);
+
+        assert!(view
+            .validate_extension(&Change::Attach(first.clone()))
+            .is_err());
+
+        let view = view.extend(std::collections::BTreeSet::from([Change::Attach(
+            second.clone(),
+        )]));
+
+        assert_eq!(view.attachment("fee").unwrap().value::<u64>().unwrap(), 20);
+
+        // Installing an older version has no effect on the attachment in effect
+
+        let stale = Attachment::new("fee", 0, &30u64).unwrap();
+
+        let view = view.extend(std::collections::BTreeSet::from([Change::Attach(stale)]));
+
+        assert_eq!(view.attachment("fee"), Some(&second));
+        assert_eq!(view.metadata().len(), 1);
+    }
+
+    #[test]
+    fn weights() {
+        let keycards = random_keycards(4);
+        let view = View::genesis(keycards.clone());
+
+        assert_eq!(view.total_weight(), 4);
+        assert_eq!(view.weighted_plurality(), view.plurality());
+        assert_eq!(view.weighted_quorum(), view.quorum());
+
+        let heavy = keycards[0].identity();
+        let weights = std::collections::BTreeMap::from([(heavy, 4u64)]);
+
+        let attachment = Attachment::new(View::WEIGHTS, 0, &weights).unwrap();
+
+        let view = view.extend(std::collections::BTreeSet::from([Change::Attach(
+            attachment,
+        )]));
+
+        assert_eq!(view.weight(&heavy), 4);
+        assert_eq!(view.weight(&keycards[1].identity()), 1);
+        assert_eq!(view.total_weight(), 7);
+        assert_eq!(view.weighted_plurality(), 3);
+        assert_eq!(view.weighted_quorum(), 5);
+
+        // Without `heavy`, no set of members reaches a weighted quorum
+        let light = keycards[1..]
+            .iter()
+            .map(KeyCard::identity)
+            .collect::<Vec<_>>();
+
+        assert_eq!(view.power(&light), 3);
+        assert!(view.power(&light) < view.weighted_quorum());
+
+        // Prefixes are measured in weight, not in members
+        assert_eq!(view.reach(&light, view.weighted_plurality()), 3);
+        assert_eq!(view.reach(&light, view.weighted_quorum()), 3);
+
+        let ranked = std::iter::once(heavy).chain(light).collect::<Vec<_>>();
+
+        assert_eq!(view.reach(&ranked, view.weighted_plurality()), 1);
+        assert_eq!(view.reach(&ranked, view.weighted_quorum()), 2);
+
+        // Weights must be strictly positive
+        let zero = std::collections::BTreeMap::from([(heavy, 0u64)]);
+        let attachment = Attachment::new(View::WEIGHTS, 1, &zero).unwrap();
+
+        assert!(view
+            .validate_extension(&Change::Attach(attachment))
+            .is_err());
+    }
+
+    #[test]
+    fn weight_bounds() {
+        let keycards = random_keycards(4);
+        let view = View::genesis(keycards.clone());
+
+        let heavy = keycards[0].identity();
+
+        // Weights beyond `MAX_WEIGHT` are rejected
+        let excessive = BTreeMap::from([(heavy, View::MAX_WEIGHT + 1)]);
+        let attachment = Attachment::new(View::WEIGHTS, 0, &excessive).unwrap();
+
+        assert!(view
+            .validate_extension(&Change::Attach(attachment.clone()))
+            .is_err());
+
+        // An out-of-bounds weight attached regardless (e.g., by a faulty
+        // installer) is ignored rather than overflowing the total weight
+
+        let overflowing = BTreeMap::from([(heavy, u64::MAX)]);
+        let overflowing = Attachment::new(View::WEIGHTS, 1, &overflowing).unwrap();
+
+        for attachment in [attachment, overflowing] {
+            let metadata = BTreeMap::from([(View::WEIGHTS.to_string(), attachment)]);
+            let (weights, total_weight) = View::decode_weights(&metadata, view.members());
+
+            assert!(weights.is_empty());
+            assert_eq!(total_weight, 4);
+        }
+
+        // Total weights are bounded by `MAX_TOTAL_WEIGHT`
+
+        let weights = keycards
+            .iter()
+            .map(|keycard| (keycard.identity(), View::MAX_WEIGHT))
+            .collect::<BTreeMap<_, _>>();
+
+        let attachment = Attachment::new(View::WEIGHTS, 0, &weights).unwrap();
+
+        assert!(view.validate_extension(&Change::Attach(attachment)).is_ok());
+
+        let limit = View::MAX_TOTAL_WEIGHT / (View::MAX_WEIGHT as usize);
+
+        let within = iter::repeat(&heavy).take(limit);
+        assert_eq!(View::weigh(&weights, within), Some(View::MAX_TOTAL_WEIGHT));
+
+        let beyond = iter::repeat(&heavy).take(limit + 1);
+        assert_eq!(View::weigh(&weights, beyond), None);
+    }
+
     #[test]
     fn identifier_associativity() {
         let keycards = random_keycards(32);
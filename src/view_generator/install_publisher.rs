@@ -0,0 +1,176 @@
+use crate::{
+    crypto::Identify,
+    discovery::Client as DiscoveryClient,
+    view::{Install, View},
+    view_generator::InstallPublisherSettings,
+};
+
+use std::{collections::HashMap, sync::Arc};
+
+use talk::{
+    crypto::primitives::hash::Hash,
+    net::{Connector, Listener},
+    sync::fuse::Fuse,
+    unicast::{Acknowledgement, PushSettings, Receiver, Sender},
+};
+
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time,
+};
+
+type InstallInlet = UnboundedSender<Install>;
+type InstallOutlet = UnboundedReceiver<Install>;
+
+// An `InstallPublisher` delivers `Install`s (produced by the local `ViewGenerator`,
+// or gossiped by other members of `view`) to the discovery server. Each `Install`
+// is gossiped to all members of `view` once, then published until the discovery
+// server's frame is observed to reach the `Install`'s destination (or for at most
+// `InstallPublisherSettings::max_attempts` attempts).
+pub(crate) struct InstallPublisher {
+    install_inlet: InstallInlet,
+    _fuse: Fuse,
+}
+
+impl InstallPublisher {
+    pub fn new<C, L>(
+        view: View,
+        discovery: Arc<DiscoveryClient>,
+        connector: C,
+        listener: L,
+        settings: InstallPublisherSettings,
+    ) -> Self
+    where
+        C: Connector,
+        L: Listener,
+    {
+        let sender = Sender::new(connector, settings.sender_settings.clone());
+        let receiver = Receiver::new(listener, settings.receiver_settings.clone());
+
+        let (install_inlet, install_outlet) = mpsc::unbounded_channel();
+
+        let fuse = Fuse::new();
+
+        fuse.spawn(async move {
+            InstallPublisher::run(view, discovery, sender, receiver, install_outlet, settings)
+                .await;
+        });
+
+        InstallPublisher {
+            install_inlet,
+            _fuse: fuse,
+        }
+    }
+
+    pub fn publish(&self, install: Install) {
+        // This cannot fail: the corresponding `install_outlet` is
+        // held by `run`, which returns only when `self._fuse` drops
+        let _ = self.install_inlet.send(install);
+    }
+
+    async fn run(
+        view: View,
+        discovery: Arc<DiscoveryClient>,
+        sender: Sender<Install>,
+        mut receiver: Receiver<Install>,
+        mut install_outlet: InstallOutlet,
+        settings: InstallPublisherSettings,
+    ) {
+        // Identifiers of the `Install`s published so far, along with their destination height
+        let mut published: HashMap<Hash, usize> = HashMap::new();
+
+        let fuse = Fuse::new();
+
+        loop {
+            // Remark: `Install`s are validated upon deserialization, so
+            // gossiped `Install`s can be trusted to be correctly certified
+            let install = tokio::select! {
+                Some(install) = install_outlet.recv() => install,
+                (source, install, acknowledger) = receiver.receive() => {
+                    acknowledger.strong();
+
+                    if !view.members().contains_key(&source) {
+                        continue;
+                    }
+
+                    install
+                }
+            };
+
+            let height = install.clone().into_transition().destination().height();
+            let top = discovery.top();
+
+            // `Install`s whose destination `discovery` already reached need no
+            // publication, and need not be remembered any longer
+
+            published.retain(|_, destination| *destination > top);
+
+            if height <= top {
+                continue;
+            }
+
+            // Each `Install` is gossiped and published only once
+
+            if published.insert(install.identifier(), height).is_some() {
+                continue;
+            }
+
+            for member in view.members().keys().copied() {
+                sender.spawn_push(
+                    member,
+                    install.clone(),
+                    PushSettings::compose(Acknowledgement::Weak, settings.push_settings.clone()),
+                    &fuse,
+                );
+            }
+
+            let discovery = discovery.clone();
+            let settings = settings.clone();
+
+            fuse.spawn(async move {
+                InstallPublisher::publish_until_confirmed(discovery.as_ref(), install, &settings)
+                    .await;
+            });
+        }
+    }
+
+    async fn publish_until_confirmed(
+        discovery: &DiscoveryClient,
+        install: Install,
+        settings: &InstallPublisherSettings,
+    ) {
+        let destination = install.clone().into_transition().destination().clone();
+        let height = destination.height();
+
+        // `install` needs no publication if `discovery` already installed its destination
+        if discovery
+            .view_at(height)
+            .map_or(false, |view| view.identifier() == destination.identifier())
+        {
+            return;
+        }
+
+        for _ in 0..settings.max_attempts {
+            // Each attempt publishes `install` (`DiscoveryClient::publish` retries until the
+            // server acknowledges) then waits for the server's frame to advance (by `install`,
+            // or by an `Install` superseding it), i.e., for `discovery` to observe a
+            // `Transition` reaching `height`
+            let attempt = async {
+                discovery.publish(install.clone()).await;
+                discovery.beyond(height - 1).await;
+            };
+
+            if time::timeout(settings.confirmation_timeout, attempt)
+                .await
+                .is_ok()
+            {
+                return;
+            }
+        }
+
+        log::warn!(
+            "Failed to confirm publication of install to height {}",
+            height
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+use talk::unicast::{PartialPushSettings, ReceiverSettings, SenderSettings};
+
+#[derive(Debug, Clone)]
+pub(crate) struct InstallPublisherSettings {
+    pub sender_settings: SenderSettings,
+    pub receiver_settings: ReceiverSettings,
+    pub push_settings: PartialPushSettings,
+
+    // How long to wait for the discovery server's frame to reach an
+    // `Install`'s destination (including the time to get the `Install`
+    // acknowledged by the server) before publishing it again
+    pub confirmation_timeout: Duration,
+
+    // How many times an `Install` is published before giving up (e.g., if
+    // the discovery server is unreachable)
+    pub max_attempts: usize,
+}
+
+impl Default for InstallPublisherSettings {
+    fn default() -> Self {
+        InstallPublisherSettings {
+            sender_settings: Default::default(),
+            receiver_settings: Default::default(),
+            push_settings: Default::default(),
+
+            confirmation_timeout: Duration::from_secs(10),
+            max_attempts: 6,
+        }
+    }
+}
@@ -1,9 +1,15 @@
-use crate::view_generator::messages::{SummarizationRequest, SummarizationResponse};
+use crate::{
+    data::enveloped,
+    view_generator::messages::{SummarizationRequest, SummarizationResponse},
+};
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub(in crate::view_generator) enum Message {
     SummarizationRequest(SummarizationRequest),
     SummarizationResponse(SummarizationResponse),
 }
+
+enveloped!(Message);
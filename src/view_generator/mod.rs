@@ -1,7 +1,10 @@
 mod install_precursor;
+mod install_publisher;
+mod install_publisher_settings;
 mod lattice_instance;
 mod message;
 mod messages;
+mod resolution_queue;
 mod sequence_lattice_brief;
 mod sequence_lattice_element;
 mod view_generator;
@@ -21,7 +24,16 @@ use view_lattice_brief::ViewLatticeBrief;
 use view_lattice_element::ViewLatticeElement;
 
 #[allow(unused_imports)]
-pub(crate) use view_generator::ViewGenerator;
+pub(crate) use install_publisher::InstallPublisher;
+
+#[allow(unused_imports)]
+pub(crate) use install_publisher_settings::InstallPublisherSettings;
+
+#[allow(unused_imports)]
+pub(crate) use resolution_queue::ResolutionQueue;
+
+#[allow(unused_imports)]
+pub(crate) use view_generator::{ViewGenerator, ViewGeneratorError};
 
 #[allow(unused_imports)]
 pub(crate) use view_generator_settings::ViewGeneratorSettings;
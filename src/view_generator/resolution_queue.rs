@@ -0,0 +1,107 @@
+use crate::{
+    churn::Churn,
+    crypto::Identify,
+    discovery::Client as DiscoveryClient,
+    view::{Change, View},
+};
+
+use std::collections::{BTreeMap, HashMap};
+
+use talk::crypto::primitives::hash::Hash;
+
+// A `ResolutionQueue` holds the `Churn` submitted while a view-generation instance
+// is running, until it can be proposed to the next instance. `Churn` is deduplicated
+// by `Change` and drained by priority: members leaving due to a fault (as voted by
+// a `Resolution`) are removed first, then resigning members, then joining replicas,
+// then metadata `Attachment`s.
+#[derive(Clone, Default)]
+pub(crate) struct ResolutionQueue {
+    pending: BTreeMap<(Priority, Hash), Churn>,
+    priorities: HashMap<Hash, Priority>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Priority {
+    Fault,
+    Resignation,
+    Join,
+    Attach,
+}
+
+impl ResolutionQueue {
+    pub fn new() -> Self {
+        ResolutionQueue::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    // Returns `false` if `churn` was already pending (by `Change`). If `churn`
+    // has higher priority than its pending duplicate, it replaces it.
+    pub fn push(&mut self, churn: Churn) -> bool {
+        let change = churn.change().identifier();
+        let priority = Priority::of(&churn);
+
+        match self.priorities.get(&change).copied() {
+            Some(pending) if pending <= priority => false,
+            Some(pending) => {
+                self.pending.remove(&(pending, change));
+                self.pending.insert((priority, change), churn);
+                self.priorities.insert(change, priority);
+
+                false
+            }
+            None => {
+                self.pending.insert((priority, change), churn);
+                self.priorities.insert(change, priority);
+
+                true
+            }
+        }
+    }
+
+    // Removes from `self` (by priority) up to `capacity` elements of `Churn` that
+    // can be applied to `view`. `Churn` that cannot be applied to `view` (e.g.,
+    // because a previous `View` already applied it) is discarded.
+    pub fn drain(
+        &mut self,
+        discovery: &DiscoveryClient,
+        view: &View,
+        capacity: Option<usize>,
+    ) -> Vec<Churn> {
+        let capacity = capacity.unwrap_or(usize::MAX);
+        let mut drained = Vec::new();
+
+        while drained.len() < capacity {
+            let key = match self.pending.keys().next() {
+                Some(key) => *key,
+                None => break,
+            };
+
+            let churn = self.pending.remove(&key).unwrap();
+            self.priorities.remove(&key.1);
+
+            if churn.validate(discovery, view).is_ok() {
+                drained.push(churn);
+            }
+        }
+
+        drained
+    }
+}
+
+impl Priority {
+    fn of(churn: &Churn) -> Self {
+        match (churn, churn.change()) {
+            (_, Change::Join(_)) => Priority::Join,
+            (Churn::Resolution(_), Change::Leave(_)) => Priority::Fault,
+            (Churn::Resignation(_), Change::Leave(_)) => Priority::Resignation,
+            (_, Change::Attach(_)) => Priority::Attach,
+        }
+    }
+}
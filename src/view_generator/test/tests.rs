@@ -3,10 +3,16 @@ use crate::{
     crypto::Identify,
     discovery::{Client, ClientSettings, Mode, Server},
     view::{test::InstallGenerator, View},
-    view_generator::ViewGenerator,
+    view_generator::{InstallPublisher, ResolutionQueue, ViewGenerator},
 };
 
-use std::{collections::BTreeSet, iter, iter::Iterator, net::Ipv4Addr, sync::Arc};
+use std::{
+    collections::BTreeSet,
+    iter,
+    iter::Iterator,
+    net::{Ipv4Addr, TcpListener as StdTcpListener},
+    sync::Arc,
+};
 
 use talk::net::test::System;
 
@@ -41,6 +47,246 @@ pub(crate) async fn setup_discovery(
     (server, clients)
 }
 
+#[tokio::test]
+async fn publish() {
+    const N: usize = 4;
+
+    let install_gen = InstallGenerator::new(N + 1);
+
+    let keychains = install_gen.keychains[0..N].to_vec();
+    let genesis = install_gen.view(N);
+    let (_server, clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let clients = clients.take(N).map(Arc::new).collect::<Vec<_>>();
+
+    let System {
+        connectors,
+        listeners,
+        ..
+    } = System::setup_with_keychains(keychains).await;
+
+    let publishers = clients
+        .iter()
+        .cloned()
+        .zip(connectors)
+        .zip(listeners)
+        .map(|((client, connector), listener)| {
+            InstallPublisher::new(
+                genesis.clone(),
+                client,
+                connector,
+                listener,
+                Default::default(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Publishing the same `Install` more than once is harmless
+    let install = install_gen.install(N, N + 1, []);
+
+    for publisher in publishers.iter() {
+        publisher.publish(install.clone());
+    }
+
+    for client in clients.iter() {
+        let transition = client.beyond(N).await;
+
+        assert_eq!(transition.destination().height(), N + 1);
+        assert!(client.install(&install.identifier()).is_some());
+    }
+}
+
+#[tokio::test]
+async fn disseminate() {
+    const N: usize = 4;
+
+    let install_gen = InstallGenerator::new(N + 1);
+
+    let keychains = install_gen.keychains[0..N].to_vec();
+    let genesis = install_gen.view(N);
+    let (_server, clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let clients = clients.take(N - 1).map(Arc::new).collect::<Vec<_>>();
+
+    // The first publisher cannot reach the discovery server: `install`
+    // reaches the server only if gossiped to the other publishers
+    let unreachable = {
+        let listener = StdTcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        listener.local_addr().unwrap()
+    };
+
+    let isolated = Arc::new(Client::new(
+        genesis.clone(),
+        unreachable,
+        ClientSettings {
+            mode: Mode::Full,
+            ..Default::default()
+        },
+    ));
+
+    let System {
+        connectors,
+        listeners,
+        ..
+    } = System::setup_with_keychains(keychains).await;
+
+    let publishers = iter::once(isolated)
+        .chain(clients.iter().cloned())
+        .zip(connectors)
+        .zip(listeners)
+        .map(|((client, connector), listener)| {
+            InstallPublisher::new(
+                genesis.clone(),
+                client,
+                connector,
+                listener,
+                Default::default(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let install = install_gen.install(N, N + 1, []);
+    publishers[0].publish(install.clone());
+
+    for client in clients.iter() {
+        let transition = client.beyond(N).await;
+
+        assert_eq!(transition.destination().height(), N + 1);
+        assert!(client.install(&install.identifier()).is_some());
+    }
+}
+
+#[tokio::test]
+async fn resolution_queue() {
+    let install_gen = InstallGenerator::new(10);
+
+    let keychains = install_gen.keychains.clone();
+    let view = install_gen.view(8);
+    let (_server, mut clients) = setup_discovery(view.clone(), Mode::Full).await;
+
+    let client = clients.next().unwrap();
+
+    let resign = |index: usize| Churn::Resignation(Resignation::new(&keychains[index]).into());
+
+    let mut queue = ResolutionQueue::new();
+
+    assert!(queue.push(resign(4)));
+    assert!(queue.push(resign(5)));
+    assert!(!queue.push(resign(5)));
+    assert!(queue.push(resign(6)));
+
+    // Replica 9 is not a member of `view`: its resignation is discarded upon draining
+    assert!(queue.push(resign(9)));
+
+    assert_eq!(queue.len(), 4);
+
+    // Resignations share the same priority: the order in which they are drained
+    // depends on the (random) identifiers of their `Change`s. Replica 9's resignation
+    // is either discarded by the first drain, or left pending for the second.
+
+    let first = queue.drain(&client, &view, Some(2));
+    assert_eq!(first.len(), 2);
+    assert!(queue.len() == 1 || queue.len() == 2);
+
+    let second = queue.drain(&client, &view, None);
+    assert_eq!(second.len(), 1);
+    assert!(queue.is_empty());
+
+    let drained = first
+        .iter()
+        .chain(second.iter())
+        .map(|churn| churn.change().identifier())
+        .collect::<BTreeSet<_>>();
+
+    let expected = [4, 5, 6]
+        .iter()
+        .map(|index| resign(*index).change().identifier())
+        .collect::<BTreeSet<_>>();
+
+    assert_eq!(drained, expected);
+}
+
+#[tokio::test]
+async fn propose_queued() {
+    const N: usize = 5;
+
+    let install_gen = InstallGenerator::new(N + 1);
+
+    let keychains = install_gen.keychains.clone();
+    let genesis = install_gen.view(N - 1);
+    let (_server, mut clients) = setup_discovery(genesis.clone(), Mode::Full).await;
+
+    let clients = (0..N)
+        .map(|_| Arc::new(clients.next().unwrap()))
+        .collect::<Vec<_>>();
+
+    // View-generation instances propose with respect to the `Install` of their view
+
+    let install = install_gen.install(N - 1, N, []);
+    clients[0].publish(install.clone()).await;
+
+    for client in clients.iter() {
+        client.beyond(N - 1).await;
+    }
+
+    let view = install_gen.view(N);
+
+    let System {
+        connectors,
+        listeners,
+        ..
+    } = System::setup_with_keychains(keychains[0..N].to_vec()).await;
+
+    let mut generators = keychains
+        .iter()
+        .cloned()
+        .zip(clients.iter().cloned())
+        .zip(connectors)
+        .zip(listeners)
+        .map(|(((keychain, client), connector), listener)| {
+            ViewGenerator::new(
+                view.clone(),
+                keychain,
+                client,
+                connector,
+                listener,
+                Default::default(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // Churn queued while no instance is running is proposed to the next instance
+
+    let resign = |index: usize| Churn::Resignation(Resignation::new(&keychains[index]).into());
+
+    let mut queue = ResolutionQueue::new();
+
+    assert!(queue.push(resign(4)));
+    assert!(!queue.push(resign(4)));
+
+    // Replica 5 is not a member of `view`: its resignation is discarded
+    assert!(queue.push(resign(5)));
+
+    generators[0].propose_queued(install.identifier(), &mut queue);
+    assert!(queue.is_empty());
+
+    let install = generators[0].decide().await;
+    let destination = install.into_transition().destination().clone();
+
+    assert_eq!(
+        destination
+            .members()
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>(),
+        keychains[0..N - 1]
+            .iter()
+            .map(|keychain| keychain.keycard().identity())
+            .collect::<BTreeSet<_>>()
+    );
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 32)]
 #[ignore]
 async fn stress_simple() {
@@ -86,6 +332,7 @@ async fn stress_simple() {
                     listeners.remove(0),
                     Default::default(),
                 )
+                .unwrap()
             })
             .collect::<Vec<_>>();
 
@@ -2,16 +2,18 @@ use crate::{
     churn::Churn,
     crypto::Identify,
     discovery::Client as DiscoveryClient,
-    lattice::{Decision, LatticeAgreement},
+    lattice::{Decision, LatticeAgreement, LatticeMultiplexer},
     view::{Increment, Install, InstallAggregator, View},
     view_generator::{
         messages::{SummarizationRequest, SummarizationResponse},
         view_lattice_brief::ViewLatticeBrief,
-        InstallPrecursor, LatticeInstance, Message, SequenceLatticeBrief, SequenceLatticeElement,
-        ViewGeneratorSettings, ViewLatticeElement,
+        InstallPrecursor, InstallPublisher, LatticeInstance, Message, ResolutionQueue,
+        SequenceLatticeBrief, SequenceLatticeElement, ViewGeneratorSettings, ViewLatticeElement,
     },
 };
 
+use doomstack::{here, Doom, ResultExt, Top};
+
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     future,
@@ -42,11 +44,20 @@ type DecisionInlet = OneshotSender<Install>;
 type DecisionOutlet = OneshotReceiver<Install>;
 
 pub(crate) struct ViewGenerator {
+    view: View,
+    discovery: Arc<DiscoveryClient>,
+    max_proposal_churn: Option<usize>,
     proposal_inlet: Option<ProposalInlet>,
     decision_outlet: DecisionOutlet,
     _fuse: Fuse,
 }
 
+#[derive(Doom)]
+pub(crate) enum ViewGeneratorError {
+    #[doom(description("Failed to start lattice agreement (invalid settings)"))]
+    LatticeFailed,
+}
+
 impl ViewGenerator {
     pub fn new<C, L>(
         view: View,
@@ -55,7 +66,7 @@ impl ViewGenerator {
         connector: C,
         listener: L,
         settings: ViewGeneratorSettings,
-    ) -> Self
+    ) -> Result<Self, Top<ViewGeneratorError>>
     where
         C: Connector,
         L: Listener,
@@ -64,43 +75,48 @@ impl ViewGenerator {
         let listen_dispatcher =
             ListenDispatcher::new(listener, settings.listen_dispatcher_settings);
 
-        // Setup view lattice
+        // Setup lattice transport (shared by all lattices)
+
+        let lattice_context = format!("{:?}::view_generator::lattice", view.identifier(),);
+
+        let lattice_connector = connect_dispatcher.register(lattice_context.clone());
+        let lattice_listener = listen_dispatcher.register(lattice_context);
 
-        let view_lattice_context =
-            format!("{:?}::view_generator::view_lattice", view.identifier(),);
+        let lattice_multiplexer = LatticeMultiplexer::new(
+            lattice_connector,
+            lattice_listener,
+            settings.lattice_sender_settings,
+            settings.lattice_receiver_settings,
+        );
 
-        let view_lattice_connector = connect_dispatcher.register(view_lattice_context.clone());
-        let view_lattice_listener = listen_dispatcher.register(view_lattice_context);
+        // Setup view lattice
 
-        let view_lattice = LatticeAgreement::<LatticeInstance, ViewLatticeElement>::new(
+        // `LatticeInstance::ViewLattice` is registered only once:
+        // this fails only if `settings.view_lattice_settings` is invalid
+        let view_lattice = LatticeAgreement::<LatticeInstance, ViewLatticeElement>::multiplexed(
             view.clone(),
             LatticeInstance::ViewLattice,
             keychain.clone(),
             discovery.clone(),
-            view_lattice_connector,
-            view_lattice_listener,
+            &lattice_multiplexer,
             settings.view_lattice_settings,
-        );
+        )
+        .pot(ViewGeneratorError::LatticeFailed, here!())?;
 
         // Setup sequence lattice
 
-        let sequence_lattice_context =
-            format!("{:?}::view_generator::sequence_lattice", view.identifier(),);
-
-        let sequence_lattice_connector =
-            connect_dispatcher.register(sequence_lattice_context.clone());
-
-        let sequence_lattice_listener = listen_dispatcher.register(sequence_lattice_context);
-
-        let sequence_lattice = LatticeAgreement::<LatticeInstance, SequenceLatticeElement>::new(
-            view.clone(),
-            LatticeInstance::SequenceLattice,
-            keychain.clone(),
-            discovery.clone(),
-            sequence_lattice_connector,
-            sequence_lattice_listener,
-            settings.sequence_lattice_settings,
-        );
+        // `LatticeInstance::SequenceLattice` is registered only once:
+        // this fails only if `settings.sequence_lattice_settings` is invalid
+        let sequence_lattice =
+            LatticeAgreement::<LatticeInstance, SequenceLatticeElement>::multiplexed(
+                view.clone(),
+                LatticeInstance::SequenceLattice,
+                keychain.clone(),
+                discovery.clone(),
+                &lattice_multiplexer,
+                settings.sequence_lattice_settings,
+            )
+            .pot(ViewGeneratorError::LatticeFailed, here!())?;
 
         // Setup channels and shared memory
 
@@ -127,7 +143,25 @@ impl ViewGenerator {
             settings.summarization_receiver_settings,
         );
 
+        // Setup install publication
+
+        let install_publisher_context =
+            format!("{:?}::view_generator::install_publisher", view.identifier(),);
+
+        let install_publisher_connector =
+            connect_dispatcher.register(install_publisher_context.clone());
+        let install_publisher_listener = listen_dispatcher.register(install_publisher_context);
+
+        let install_publisher = InstallPublisher::new(
+            view.clone(),
+            discovery.clone(),
+            install_publisher_connector,
+            install_publisher_listener,
+            settings.install_publisher_settings,
+        );
+
         let push_settings = settings.push_settings;
+        let max_proposal_churn = settings.max_proposal_churn;
 
         let fuse = Fuse::new();
 
@@ -157,25 +191,34 @@ impl ViewGenerator {
 
         // Spawn summarization task
 
-        fuse.spawn(async move {
-            ViewGenerator::serve(
-                view,
-                discovery,
-                keychain,
-                aggregator_slot,
-                summarization_sender,
-                summarization_receiver,
-                decision_inlet,
-                push_settings,
-            )
-            .await;
-        });
+        {
+            let view = view.clone();
+            let discovery = discovery.clone();
 
-        Self {
+            fuse.spawn(async move {
+                ViewGenerator::serve(
+                    view,
+                    discovery,
+                    keychain,
+                    aggregator_slot,
+                    summarization_sender,
+                    summarization_receiver,
+                    install_publisher,
+                    decision_inlet,
+                    push_settings,
+                )
+                .await;
+            });
+        }
+
+        Ok(Self {
+            view,
+            discovery,
+            max_proposal_churn,
             proposal_inlet: Some(proposal_inlet),
             decision_outlet,
             _fuse: fuse,
-        }
+        })
     }
 
     pub fn propose_churn<C>(&mut self, install: Hash, churn: C)
@@ -188,6 +231,15 @@ impl ViewGenerator {
         let _ = self.proposal_inlet.take().unwrap().send(proposal);
     }
 
+    // Proposes the `Churn` pending in `queue` (by priority, and up to
+    // `ViewGeneratorSettings::max_proposal_churn`), leaving the rest for
+    // the next view-generation instance
+    pub fn propose_queued(&mut self, install: Hash, queue: &mut ResolutionQueue) {
+        let churn = queue.drain(self.discovery.as_ref(), &self.view, self.max_proposal_churn);
+
+        self.propose_churn(install, churn);
+    }
+
     pub fn propose_tail(&mut self, install: Hash) {
         let proposal = ViewLatticeElement::Tail { install };
         let _ = self.proposal_inlet.take().unwrap().send(proposal);
@@ -280,6 +332,7 @@ impl ViewGenerator {
         future::pending::<()>().await;
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn serve(
         view: View,
         discovery: Arc<DiscoveryClient>,
@@ -287,6 +340,7 @@ impl ViewGenerator {
         aggregator_slot: Arc<Mutex<Option<InstallAggregator>>>,
         summarization_sender: Sender<Message>,
         mut summarization_receiver: Receiver<Message>,
+        install_publisher: InstallPublisher,
         decision_inlet: DecisionInlet,
         push_settings: PartialPushSettings,
     ) {
@@ -380,8 +434,13 @@ impl ViewGenerator {
                     aggregator = if let Some(mut aggregator) = aggregator.take() {
                         let _ = aggregator.add(keycard, confirm.signature);
 
-                        if aggregator.multiplicity() >= view.plurality() {
+                        if aggregator.power() >= view.weighted_plurality() {
                             let install = aggregator.finalize();
+
+                            // Disseminate `install` to the other members of `view`
+                            // and to the discovery server
+                            install_publisher.publish(install.clone());
+
                             let _ = decision_inlet.take().unwrap().send(install);
 
                             None
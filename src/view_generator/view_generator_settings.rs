@@ -1,4 +1,4 @@
-use crate::lattice::LatticeAgreementSettings;
+use crate::{lattice::LatticeAgreementSettings, view_generator::InstallPublisherSettings};
 
 use talk::{
     link::context::ListenDispatcherSettings,
@@ -8,9 +8,16 @@ use talk::{
 #[derive(Debug, Clone, Default)]
 pub(crate) struct ViewGeneratorSettings {
     pub listen_dispatcher_settings: ListenDispatcherSettings,
+    // Settings of the transport shared by the view and sequence lattices
+    pub lattice_sender_settings: SenderSettings,
+    pub lattice_receiver_settings: ReceiverSettings,
     pub view_lattice_settings: LatticeAgreementSettings,
     pub sequence_lattice_settings: LatticeAgreementSettings,
     pub summarization_sender_settings: SenderSettings,
     pub summarization_receiver_settings: ReceiverSettings,
     pub push_settings: PartialPushSettings,
+    pub install_publisher_settings: InstallPublisherSettings,
+    // Maximum number of elements of `Churn` drained from a `ResolutionQueue`
+    // into a single proposal (if `None`, the whole queue is proposed)
+    pub max_proposal_churn: Option<usize>,
 }